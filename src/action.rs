@@ -0,0 +1,63 @@
+use gdnative::prelude::{Dictionary, Unique, Variant, VariantType};
+
+/// The key that holds the type of an action that is a Dictionary.
+pub const TYPE_KEY: &str = "type";
+
+/// Returns the type of an action. An action can either be just its type, like
+/// a value from an enum, or a Dictionary with its type under the `type` key and
+/// any other data the action needs under other keys.
+///
+/// # Arguments
+///
+/// * `action` - The action to get the type of.
+pub fn action_type(action: &Variant) -> Variant {
+    match action.try_to_dictionary() {
+        Some(action) if action.contains(TYPE_KEY) => action.get(TYPE_KEY),
+        Some(_) => Variant::new(),
+        None => action.clone(),
+    }
+}
+
+/// Returns whether an action has the type with the name, which is how the
+/// actions handled natively by the store, like `navigate`, are recognized.
+///
+/// # Arguments
+///
+/// * `action` - The action to check.
+/// * `name` - The name of the type.
+pub fn is_type(action: &Variant, name: &str) -> bool {
+    let action_type = action_type(action);
+
+    action_type.get_type() == VariantType::GodotString && action_type.to_string() == name
+}
+
+/// Returns the value under a key of an action that is a Dictionary, or `None`
+/// if the action isn't a Dictionary or doesn't have the key.
+///
+/// # Arguments
+///
+/// * `action` - The action to get the value from.
+/// * `key` - The key of the value.
+pub fn field(action: &Variant, key: &str) -> Option<Variant> {
+    let action = action.try_to_dictionary()?;
+
+    if action.contains(key) {
+        Some(action.get(key))
+    } else {
+        None
+    }
+}
+
+/// Returns an action that is a Dictionary with the type with the name, which
+/// data can be added to. This is how the store builds the actions it
+/// dispatches itself.
+///
+/// # Arguments
+///
+/// * `name` - The name of the type.
+pub fn named(name: &str) -> Dictionary<Unique> {
+    let action = Dictionary::new();
+    action.insert(TYPE_KEY, name);
+
+    action
+}
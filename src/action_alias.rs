@@ -0,0 +1,87 @@
+use gdnative::api::FuncRef;
+use gdnative::prelude::{OwnedToVariant, Ref, Shared, Variant};
+
+use crate::action::{self, action_type};
+
+/// An old action type registered with `register_action_alias`, which actions
+/// are renamed from, and optionally restructured, before they are dispatched
+/// so old replays, saves, and peers keep working after an action changes.
+#[derive(Clone)]
+pub struct ActionAlias {
+    /// The old type.
+    old: Variant,
+    /// The type actions of the old type are renamed to.
+    new: Variant,
+    /// A function that is passed the renamed action and returns it
+    /// restructured, if the action's data changed too.
+    transform: Option<Ref<FuncRef, Shared>>,
+}
+
+impl ActionAlias {
+    /// Creates an alias, returning an error if the transform is neither
+    /// `null` nor a FuncRef.
+    ///
+    /// # Arguments
+    ///
+    /// * `old` - The old type.
+    /// * `new` - The type actions of the old type are renamed to.
+    /// * `transform` - Optional FuncRef that restructures the renamed action.
+    pub fn new(old: Variant, new: Variant, transform: &Variant) -> Result<Self, String> {
+        let transform = if transform.is_nil() {
+            None
+        } else {
+            Some(transform.try_to_object::<FuncRef>().ok_or_else(|| {
+                format!(
+                    "The transform has to be a FuncRef, got {}",
+                    transform.to_string()
+                )
+            })?)
+        };
+
+        Ok(ActionAlias {
+            old,
+            new,
+            transform,
+        })
+    }
+
+    /// Returns the old type.
+    pub fn old_type(&self) -> &Variant {
+        &self.old
+    }
+}
+
+/// Returns an action migrated through the aliases, following them from alias
+/// to alias so an action can be renamed more than once. Each alias is used at
+/// most once so aliases that rename types back and forth can't loop forever.
+///
+/// # Arguments
+///
+/// * `aliases` - The aliases.
+/// * `action` - The action to migrate.
+pub fn migrate(aliases: &[ActionAlias], action: Variant) -> Variant {
+    let mut action = action;
+    let mut used = vec![false; aliases.len()];
+
+    while let Some(index) = (0..aliases.len())
+        .find(|index| !used[*index] && aliases[*index].old == action_type(&action))
+    {
+        used[index] = true;
+        let alias = &aliases[index];
+
+        action = match action.try_to_dictionary() {
+            Some(fields) => {
+                let renamed = fields.duplicate();
+                renamed.insert(action::TYPE_KEY, &alias.new);
+                renamed.owned_to_variant()
+            }
+            None => alias.new.clone(),
+        };
+
+        if let Some(transform) = &alias.transform {
+            action = unsafe { transform.assume_safe() }.call_func(&[action]);
+        }
+    }
+
+    action
+}
@@ -0,0 +1,234 @@
+use gdnative::api::{AudioServer, AudioStreamPlayer, AudioStreamPlayer2D, AudioStreamPlayer3D};
+use gdnative::prelude::{Dictionary, Node, Ref, Shared, Variant, VariantType};
+
+use crate::action::action_type;
+use crate::path;
+
+/// The key of the sounds played when actions are dispatched in the map given
+/// to `bind_audio`.
+const ACTIONS_KEY: &str = "actions";
+
+/// The key of the sounds played when values in the state change in the map
+/// given to `bind_audio`.
+const CHANGES_KEY: &str = "changes";
+
+/// The key of the buses whose volume follows a value in the state in the map
+/// given to `bind_audio`.
+const BUSES_KEY: &str = "buses";
+
+/// The volume a bus is set to when the value that controls it is 0, which is
+/// as quiet as the editor's volume slider goes.
+const SILENT_VOLUME_DB: f64 = -80.0;
+
+/// The sounds played when the value at a path changes.
+#[derive(Clone)]
+enum Change {
+    /// A sound played whenever the value changes.
+    Any(Ref<Node, Shared>),
+    /// The sounds played when the value changes to each of the values.
+    To(Vec<(Variant, Ref<Node, Shared>)>),
+}
+
+/// The sounds and buses bound to the store with `bind_audio`.
+#[derive(Clone)]
+pub struct AudioBinding {
+    /// The sounds played when actions of each type are dispatched.
+    actions: Vec<(Variant, Ref<Node, Shared>)>,
+    /// The sounds played when the values at each path change.
+    changes: Vec<(String, Change)>,
+    /// The names of the buses and the paths of the values between 0 and 1
+    /// their volume follows.
+    buses: Vec<(String, String)>,
+}
+
+impl AudioBinding {
+    /// Creates a binding from the map given to `bind_audio`, returning an
+    /// error describing what is wrong with it if it isn't valid.
+    ///
+    /// # Arguments
+    ///
+    /// * `map` - The map of actions, changes, and buses.
+    pub fn new(map: &Dictionary) -> Result<Self, String> {
+        let mut actions = vec![];
+        for (action, player) in dictionary_under(map, ACTIONS_KEY)?.iter() {
+            actions.push((action, player_of(&player)?));
+        }
+
+        let mut changes = vec![];
+        for (path, change) in dictionary_under(map, CHANGES_KEY)?.iter() {
+            let change = match change.try_to_dictionary() {
+                Some(values) => {
+                    let mut to = vec![];
+                    for (value, player) in values.iter() {
+                        to.push((value, player_of(&player)?));
+                    }
+
+                    Change::To(to)
+                }
+                None => Change::Any(player_of(&change)?),
+            };
+            changes.push((path.to_string(), change));
+        }
+
+        let mut buses = vec![];
+        for (bus, path) in dictionary_under(map, BUSES_KEY)?.iter() {
+            if AudioServer::godot_singleton().get_bus_index(bus.to_string()) < 0 {
+                return Err(format!("There is no audio bus named {}", bus.to_string()));
+            }
+            buses.push((bus.to_string(), path.to_string()));
+        }
+
+        Ok(AudioBinding {
+            actions,
+            changes,
+            buses,
+        })
+    }
+
+    /// Plays the sounds for an action and for the values it changed, and
+    /// updates the volume of the buses whose value changed.
+    ///
+    /// # Arguments
+    ///
+    /// * `action` - The action that was dispatched.
+    /// * `old_state` - The state before the action.
+    /// * `new_state` - The state after the action.
+    pub fn apply(&self, action: &Variant, old_state: &Dictionary, new_state: &Dictionary) {
+        let action_type = action_type(action);
+        for (_, player) in self.actions.iter().filter(|(a, _)| *a == action_type) {
+            play(player);
+        }
+
+        for (path, change) in &self.changes {
+            let value = match changed_value(old_state, new_state, path) {
+                Some(value) => value,
+                None => continue,
+            };
+
+            match change {
+                Change::Any(player) => play(player),
+                Change::To(to) => to
+                    .iter()
+                    .filter(|(expected, _)| *expected == value)
+                    .for_each(|(_, player)| play(player)),
+            }
+        }
+
+        for (bus, path) in &self.buses {
+            if let Some(volume) = changed_value(old_state, new_state, path) {
+                set_bus_volume(bus, &volume);
+            }
+        }
+    }
+
+    /// Sets the volume of every bus to the value it follows in the state.
+    ///
+    /// # Arguments
+    ///
+    /// * `state` - The current state.
+    pub fn sync_buses(&self, state: &Dictionary) {
+        for (bus, path) in &self.buses {
+            if let Some(volume) = path::get(state, path) {
+                set_bus_volume(bus, &volume);
+            }
+        }
+    }
+}
+
+/// Returns the Dictionary under a key of the map given to `bind_audio`, which
+/// is empty if the key isn't in the map.
+///
+/// # Arguments
+///
+/// * `map` - The map.
+/// * `key` - The key.
+fn dictionary_under(map: &Dictionary, key: &str) -> Result<Dictionary, String> {
+    if !map.contains(key) {
+        return Ok(Dictionary::new_shared());
+    }
+
+    map.get(key)
+        .try_to_dictionary()
+        .ok_or_else(|| format!("The {} of the audio map have to be a Dictionary", key))
+}
+
+/// Returns the audio player in a value of the map given to `bind_audio`.
+///
+/// # Arguments
+///
+/// * `value` - The value, which should be an AudioStreamPlayer, an
+/// AudioStreamPlayer2D, or an AudioStreamPlayer3D.
+fn player_of(value: &Variant) -> Result<Ref<Node, Shared>, String> {
+    let is_player = |node: &Ref<Node, Shared>| {
+        let node = unsafe { node.assume_safe() };
+
+        node.cast::<AudioStreamPlayer>().is_some()
+            || node.cast::<AudioStreamPlayer2D>().is_some()
+            || node.cast::<AudioStreamPlayer3D>().is_some()
+    };
+
+    value
+        .try_to_object::<Node>()
+        .filter(is_player)
+        .ok_or_else(|| format!("{} isn't an audio player", value.to_string()))
+}
+
+/// Returns the value at a path in the new state if it's different from the one
+/// in the old state.
+///
+/// # Arguments
+///
+/// * `old_state` - The state before the action.
+/// * `new_state` - The state after the action.
+/// * `path` - The path to the value.
+fn changed_value(old_state: &Dictionary, new_state: &Dictionary, path: &str) -> Option<Variant> {
+    let value = path::get(new_state, path)?;
+    if path::get(old_state, path).as_ref() == Some(&value) {
+        return None;
+    }
+
+    Some(value)
+}
+
+/// Plays an audio player from the start.
+///
+/// # Arguments
+///
+/// * `player` - The audio player.
+fn play(player: &Ref<Node, Shared>) {
+    // The player might have been freed since it was bound.
+    let node = match unsafe { player.assume_safe_if_sane() } {
+        Some(node) => node,
+        None => return,
+    };
+
+    if let Some(player) = node.cast::<AudioStreamPlayer>() {
+        player.play(0.0);
+    } else if let Some(player) = node.cast::<AudioStreamPlayer2D>() {
+        player.play(0.0);
+    } else if let Some(player) = node.cast::<AudioStreamPlayer3D>() {
+        player.play(0.0);
+    }
+}
+
+/// Sets the volume of a bus from a value between 0 and 1.
+///
+/// # Arguments
+///
+/// * `bus` - The name of the bus.
+/// * `volume` - The volume, where 0 is silent and 1 is the bus's full volume.
+fn set_bus_volume(bus: &str, volume: &Variant) {
+    if !matches!(volume.get_type(), VariantType::I64 | VariantType::F64) {
+        return;
+    }
+
+    let volume = volume.to_f64();
+    let volume_db = if volume > 0.0 {
+        (20.0 * volume.log10()).max(SILENT_VOLUME_DB)
+    } else {
+        SILENT_VOLUME_DB
+    };
+
+    let audio_server = AudioServer::godot_singleton();
+    audio_server.set_bus_volume_db(audio_server.get_bus_index(bus), volume_db);
+}
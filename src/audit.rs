@@ -0,0 +1,88 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use gdnative::prelude::{Dictionary, Unique, Variant, VariantArray};
+
+use crate::path;
+
+/// Keeps track of which paths of the state are read and which paths are
+/// written by each action while the access audit is enabled.
+#[derive(Default)]
+pub struct AccessAudit {
+    /// The number of times each path was read.
+    reads: BTreeMap<String, u64>,
+    /// The paths that were changed by each type of action.
+    writes: Vec<(Variant, BTreeSet<String>)>,
+}
+
+impl AccessAudit {
+    /// Records that a path of the state was read.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path that was read.
+    pub fn record_read(&mut self, path: &str) {
+        *self.reads.entry(path.to_string()).or_insert(0) += 1;
+    }
+
+    /// Records the paths of the state that were changed by an action.
+    ///
+    /// # Arguments
+    ///
+    /// * `action_type` - The type of the action that changed the paths.
+    /// * `paths` - The paths that were changed.
+    pub fn record_writes(&mut self, action_type: &Variant, paths: Vec<String>) {
+        match self.writes.iter_mut().find(|(t, _)| t == action_type) {
+            Some((_, written)) => written.extend(paths),
+            None => self
+                .writes
+                .push((action_type.clone(), paths.into_iter().collect())),
+        }
+    }
+
+    /// Returns whether a path, or a path that contains it, has been read or
+    /// written.
+    ///
+    /// # Arguments
+    ///
+    /// * `leaf` - The path to check.
+    fn is_accessed(&self, leaf: &str) -> bool {
+        self.reads
+            .keys()
+            .chain(self.writes.iter().flat_map(|(_, paths)| paths))
+            .any(|accessed| path::is_within(leaf, accessed))
+    }
+
+    /// Returns a report of the accesses recorded so far, containing:
+    ///
+    /// * `reads` - A Dictionary of the paths that were read and how many times.
+    /// * `writes` - A Dictionary of action types and the paths each one changed.
+    /// * `unused` - The paths in the current state that were never read or written.
+    ///
+    /// # Arguments
+    ///
+    /// * `state` - The current state, used to find the unused paths.
+    pub fn report(&self, state: &Dictionary) -> Dictionary<Unique> {
+        let reads = Dictionary::new();
+        for (path, count) in &self.reads {
+            reads.insert(path, *count);
+        }
+
+        let writes = Dictionary::new();
+        for (action_type, paths) in &self.writes {
+            let paths = paths.iter().collect::<VariantArray<Unique>>();
+            writes.insert(action_type, paths);
+        }
+
+        let unused = path::leaves(state)
+            .into_iter()
+            .filter(|leaf| !self.is_accessed(leaf))
+            .collect::<VariantArray<Unique>>();
+
+        let report = Dictionary::new();
+        report.insert("reads", reads);
+        report.insert("writes", writes);
+        report.insert("unused", unused);
+
+        report
+    }
+}
@@ -0,0 +1,107 @@
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+
+use gdnative::prelude::{Dictionary, OwnedToVariant, Unique, Variant, VariantArray, VariantType};
+
+use crate::file::Encryption;
+use crate::persistence;
+
+/// A save being written to a file on a worker thread by `save_to_file`.
+pub struct BackgroundSave {
+    /// The path of the file.
+    path: String,
+    /// Where the worker thread sends whether the save could be written.
+    result: Receiver<Result<(), String>>,
+}
+
+impl BackgroundSave {
+    /// Starts writing a save on a worker thread. The metadata and the state
+    /// have to be snapshots made with `snapshot` so nothing else can change
+    /// them while they're being written.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the file.
+    /// * `metadata` - The metadata of the save.
+    /// * `state` - The state to save.
+    /// * `backups` - The number of backups of the save to keep.
+    /// * `encryption` - What the save is encrypted with, if it is.
+    pub fn start(
+        path: String,
+        metadata: Dictionary<Unique>,
+        state: Dictionary<Unique>,
+        backups: usize,
+        encryption: Option<Encryption>,
+    ) -> Self {
+        let (sender, result) = mpsc::channel();
+
+        let file_path = path.clone();
+        thread::spawn(move || {
+            let written = persistence::write(
+                &file_path,
+                &metadata.into_shared(),
+                &state.into_shared(),
+                backups,
+                encryption.as_ref(),
+            );
+
+            // The store could have been freed while the save was written, in
+            // which case nobody is waiting for the result.
+            let _ = sender.send(written);
+        });
+
+        BackgroundSave { path, result }
+    }
+
+    /// Returns the path of the file.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Returns whether the save could be written if it's done, or `None` if
+    /// it's still being written.
+    pub fn poll(&self) -> Option<Result<(), String>> {
+        match self.result.try_recv() {
+            Ok(written) => Some(written),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => {
+                Some(Err("The save thread stopped before it finished".to_string()))
+            }
+        }
+    }
+}
+
+/// Returns a copy of a Dictionary where every Dictionary and Array inside of
+/// it is copied as well, so the copy doesn't share anything with the
+/// original and can be handed to another thread.
+///
+/// # Arguments
+///
+/// * `dictionary` - The Dictionary to copy.
+pub fn snapshot(dictionary: &Dictionary) -> Dictionary<Unique> {
+    let copy = Dictionary::new();
+    for (key, value) in dictionary.iter() {
+        copy.insert(copy_value(&key), copy_value(&value));
+    }
+
+    copy
+}
+
+/// Returns a copy of a value, copying the Dictionaries and Arrays inside of
+/// it.
+///
+/// # Arguments
+///
+/// * `value` - The value to copy.
+pub fn copy_value(value: &Variant) -> Variant {
+    match value.get_type() {
+        VariantType::Dictionary => snapshot(&value.to_dictionary()).owned_to_variant(),
+        VariantType::VariantArray => value
+            .to_array()
+            .iter()
+            .map(|item| copy_value(&item))
+            .collect::<VariantArray<Unique>>()
+            .owned_to_variant(),
+        _ => value.clone(),
+    }
+}
@@ -0,0 +1,95 @@
+use std::collections::BTreeMap;
+
+use gdnative::prelude::{Dictionary, Unique, VariantArray};
+
+/// How long the calls to a subscriber took.
+#[derive(Default)]
+struct CallTimes {
+    /// The number of times the subscriber was called.
+    calls: u64,
+    /// How long the calls took altogether, in microseconds.
+    total_usec: i64,
+    /// How long the slowest call took, in microseconds.
+    max_usec: i64,
+}
+
+/// How long the calls to every subscriber took, so frame spikes caused by
+/// heavy subscribers can be traced back to them, along with the budget a
+/// single call to a subscriber is warned about going over.
+#[derive(Default)]
+pub struct CallBudget {
+    /// How long the calls to each subscriber took, by the identity of the
+    /// subscriber.
+    times: BTreeMap<String, CallTimes>,
+    /// How long a single call to a subscriber can take before it's warned
+    /// about, in microseconds, or `None` to not warn.
+    budget_usec: Option<i64>,
+}
+
+impl CallBudget {
+    /// Sets how long a single call to a subscriber can take before it's
+    /// warned about.
+    ///
+    /// # Arguments
+    ///
+    /// * `budget_usec` - The budget, in microseconds, or `None` to not warn.
+    pub fn set_budget(&mut self, budget_usec: Option<i64>) {
+        self.budget_usec = budget_usec;
+    }
+
+    /// Adds how long a call to a subscriber took, and returns the budget it
+    /// went over, in microseconds, if it did.
+    ///
+    /// # Arguments
+    ///
+    /// * `identity` - The identity of the subscriber.
+    /// * `usec` - How long the call took, in microseconds.
+    pub fn record(&mut self, identity: &str, usec: i64) -> Option<i64> {
+        let times = self.times.entry(identity.to_string()).or_default();
+        times.calls += 1;
+        times.total_usec += usec;
+        times.max_usec = times.max_usec.max(usec);
+
+        self.budget_usec.filter(|budget| usec > *budget)
+    }
+
+    /// Returns the subscribers whose calls took at least a number of
+    /// microseconds altogether, slowest first, each as a Dictionary with the
+    /// identity of the subscriber under `subscriber`, the number of calls
+    /// under `calls`, and how long they took altogether, on average, and at
+    /// most under `total_ms`, `avg_ms`, and `max_ms`.
+    ///
+    /// # Arguments
+    ///
+    /// * `threshold_usec` - How long the calls have to have taken, in
+    /// microseconds.
+    pub fn slow(&self, threshold_usec: i64) -> VariantArray<Unique> {
+        let mut slow = self
+            .times
+            .iter()
+            .filter(|(_, times)| times.total_usec >= threshold_usec)
+            .collect::<Vec<_>>();
+        slow.sort_by(|(_, a), (_, b)| b.total_usec.cmp(&a.total_usec));
+
+        let subscribers = VariantArray::new();
+        for (identity, times) in slow {
+            let subscriber = Dictionary::new();
+            subscriber.insert("subscriber", identity.as_str());
+            subscriber.insert("calls", times.calls as i64);
+            subscriber.insert("total_ms", times.total_usec as f64 / 1000.0);
+            subscriber.insert(
+                "avg_ms",
+                times.total_usec as f64 / times.calls.max(1) as f64 / 1000.0,
+            );
+            subscriber.insert("max_ms", times.max_usec as f64 / 1000.0);
+            subscribers.push(subscriber.into_shared());
+        }
+
+        subscribers
+    }
+
+    /// Forgets how long the calls took, keeping the budget.
+    pub fn reset(&mut self) {
+        self.times.clear();
+    }
+}
@@ -0,0 +1,177 @@
+use gdnative::prelude::{Dictionary, OwnedToVariant, Unique, Variant};
+
+use crate::action::action_type;
+use crate::path;
+
+/// The key of the slice of the state that has the cheats that were used.
+pub const SLICE: &str = "cheats";
+
+/// The key of the number of times each cheat was used in the slice, by name.
+const USED_KEY: &str = "used";
+
+/// A cheat, like god mode or unlocking every level, which dispatches an
+/// action when it's used.
+struct Cheat {
+    /// The name of the cheat.
+    name: String,
+    /// The action the cheat dispatches.
+    action: Variant,
+    /// The permission level needed to use the cheat.
+    level: i64,
+}
+
+/// The cheats registered with `register_cheat` and the permission level that
+/// decides which of them can be used, so QA builds can expose cheats through
+/// the store while release builds reject them.
+#[derive(Default)]
+pub struct Cheats {
+    /// The cheats, in the order they were registered.
+    cheats: Vec<Cheat>,
+    /// The permission level, set with `set_cheat_permission`. Cheats that
+    /// need a higher level than this can't be used.
+    permission: i64,
+}
+
+impl Cheats {
+    /// Registers a cheat, replacing the one that had the name before.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the cheat.
+    /// * `action` - The action the cheat dispatches.
+    /// * `level` - The permission level needed to use the cheat.
+    pub fn register(&mut self, name: &str, action: Variant, level: i64) {
+        self.cheats.retain(|cheat| cheat.name != name);
+        self.cheats.push(Cheat {
+            name: name.to_string(),
+            action,
+            level,
+        });
+    }
+
+    /// Sets the permission level.
+    ///
+    /// # Arguments
+    ///
+    /// * `permission` - The permission level.
+    pub fn set_permission(&mut self, permission: i64) {
+        self.permission = permission;
+    }
+
+    /// Returns the action a cheat dispatches, or an error if there's no cheat
+    /// with the name or it needs a higher permission level.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the cheat.
+    pub fn action(&self, name: &str) -> Result<Variant, String> {
+        let cheat = self
+            .cheats
+            .iter()
+            .find(|cheat| cheat.name == name)
+            .ok_or_else(|| format!("There's no cheat named {}", name))?;
+        if cheat.level > self.permission {
+            return Err(format!(
+                "The cheat {} needs permission level {}, but the level is {}",
+                name, cheat.level, self.permission
+            ));
+        }
+
+        // The action is copied so changing the one that was dispatched
+        // doesn't change the cheat.
+        Ok(match cheat.action.try_to_dictionary() {
+            Some(action) => action.duplicate().owned_to_variant(),
+            None => cheat.action.clone(),
+        })
+    }
+
+    /// Returns the name of a cheat that dispatches actions of the same type
+    /// as an action and needs a higher permission level, or `None` if the
+    /// action can be dispatched.
+    ///
+    /// # Arguments
+    ///
+    /// * `action` - The action being dispatched.
+    pub fn forbidding(&self, action: &Variant) -> Option<&str> {
+        if self.cheats.is_empty() {
+            return None;
+        }
+
+        let dispatched = action_type(action);
+        self.cheats
+            .iter()
+            .find(|cheat| cheat.level > self.permission && action_type(&cheat.action) == dispatched)
+            .map(|cheat| cheat.name.as_str())
+    }
+
+    /// Returns the names of the cheats that dispatch actions of the same type
+    /// as an action.
+    ///
+    /// # Arguments
+    ///
+    /// * `action` - The action that was dispatched.
+    pub fn used_by(&self, action: &Variant) -> Vec<&str> {
+        if self.cheats.is_empty() {
+            return vec![];
+        }
+
+        let dispatched = action_type(action);
+        self.cheats
+            .iter()
+            .filter(|cheat| action_type(&cheat.action) == dispatched)
+            .map(|cheat| cheat.name.as_str())
+            .collect()
+    }
+
+    /// Returns the names of the cheats that can be used with the permission
+    /// level, along with the level each of them needs.
+    pub fn available(&self) -> Vec<(&str, i64)> {
+        self.cheats
+            .iter()
+            .filter(|cheat| cheat.level <= self.permission)
+            .map(|cheat| (cheat.name.as_str(), cheat.level))
+            .collect()
+    }
+
+    /// Returns the types of the actions the cheats dispatch, in the order
+    /// the cheats were registered.
+    pub fn action_types(&self) -> Vec<Variant> {
+        self.cheats
+            .iter()
+            .map(|cheat| action_type(&cheat.action))
+            .collect()
+    }
+
+    /// Drops every cheat.
+    pub fn clear(&mut self) {
+        self.cheats.clear();
+    }
+}
+
+/// Returns the slice after cheats were used, with the number of times each
+/// cheat was used under `used`, by name, which games can check to turn off
+/// achievements or leaderboards. Returns `None` if no cheat was used.
+///
+/// # Arguments
+///
+/// * `current` - The slice before the action.
+/// * `used` - The names of the cheats that were used.
+pub fn reduce(current: &Dictionary, used: &[&str]) -> Option<Dictionary<Unique>> {
+    if used.is_empty() {
+        return None;
+    }
+
+    let counts = path::get(current, USED_KEY)
+        .and_then(|counts| counts.try_to_dictionary())
+        .unwrap_or_default()
+        .duplicate();
+    for name in used {
+        let count = path::get(&counts, name).map_or(0, |count| count.to_i64());
+        counts.insert(*name, count + 1);
+    }
+
+    let next = current.duplicate();
+    next.insert(USED_KEY, counts);
+
+    Some(next)
+}
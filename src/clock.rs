@@ -0,0 +1,26 @@
+use gdnative::api::{FuncRef, OS};
+use gdnative::prelude::{Ref, ThreadLocal};
+
+/// Where the store gets the current time from for anything that depends on
+/// time passing.
+pub enum Clock {
+    /// The real time since the engine started.
+    Real,
+    /// A time that only moves forward when it is advanced, starting from the
+    /// time it was created at.
+    Manual(f64),
+    /// A time returned by calling a function, for example one provided by a
+    /// test framework.
+    Custom(Ref<FuncRef, ThreadLocal>),
+}
+
+impl Default for Clock {
+    fn default() -> Self {
+        Clock::Real
+    }
+}
+
+/// Returns the real time since the engine started, in seconds.
+pub fn real_now() -> f64 {
+    OS::godot_singleton().get_ticks_usec() as f64 / 1_000_000.0
+}
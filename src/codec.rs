@@ -0,0 +1,302 @@
+use std::collections::BTreeMap;
+
+use gdnative::api::{FuncRef, Marshalls};
+use gdnative::prelude::{
+    ByteArray, Dictionary, OwnedToVariant, Ref, ThreadLocal, Variant, VariantArray, VariantType,
+};
+use rmpv::Value;
+
+use crate::{file, math_type, snapshot};
+
+/// The name of the JSON codec.
+const JSON: &str = "json";
+
+/// The name of the binary codec.
+const BINARY: &str = "binary";
+
+/// The name of the MessagePack codec.
+const MESSAGE_PACK: &str = "msgpack";
+
+/// How values are turned into bytes and back, for saving the state to a file
+/// or sending it somewhere else.
+#[derive(Clone)]
+pub enum Codec {
+    /// JSON, which can be read and edited by hand but turns every number
+    /// into a float and values like a `NodePath` into strings. `Vector2`s,
+    /// `Vector3`s, and `Color`s are written as Dictionaries of their
+    /// components and read back as what they were.
+    Json,
+    /// The binary encoding of Variants that `var2bytes` uses, which keeps the
+    /// type of every value, like a `Vector2`, a `Color`, or a `PoolIntArray`.
+    Binary,
+    /// MessagePack, which is smaller than JSON and keeps integers, floats,
+    /// and `PoolByteArray`s apart, and writes `Vector2`s, `Vector3`s, and
+    /// `Color`s like JSON does, but turns values like a `NodePath` into
+    /// strings.
+    MessagePack,
+    /// Functions that are passed a value and return a `PoolByteArray`, and
+    /// the other way around, registered with `register_codec`.
+    Custom(Ref<FuncRef, ThreadLocal>, Ref<FuncRef, ThreadLocal>),
+}
+
+impl Codec {
+    /// Turns a value into bytes.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The value.
+    pub fn encode(&self, value: &Variant) -> Result<ByteArray, String> {
+        match self {
+            Codec::Json => Ok(ByteArray::from_vec(
+                snapshot::to_stable_text(value).into_bytes(),
+            )),
+            Codec::Binary => {
+                let marshalls = Marshalls::godot_singleton();
+                Ok(marshalls.base64_to_raw(marshalls.variant_to_base64(value.clone(), false)))
+            }
+            Codec::MessagePack => {
+                let mut bytes = vec![];
+                rmpv::encode::write_value(&mut bytes, &to_message_pack(value))
+                    .map_err(|error| format!("Unable to encode as MessagePack: {}", error))?;
+                Ok(ByteArray::from_vec(bytes))
+            }
+            Codec::Custom(encode, _) => encode
+                .call_func(&[value.clone()])
+                .try_to_byte_array()
+                .ok_or_else(|| "The encode function has to return a PoolByteArray".to_string()),
+        }
+    }
+
+    /// Turns bytes made by `encode` back into a value.
+    ///
+    /// # Arguments
+    ///
+    /// * `bytes` - The bytes.
+    pub fn decode(&self, bytes: ByteArray) -> Result<Variant, String> {
+        match self {
+            Codec::Json => {
+                let text = String::from_utf8(bytes.read().to_vec())
+                    .map_err(|_| "The JSON isn't valid UTF-8".to_string())?;
+                file::parse_json(&text)
+            }
+            Codec::Binary => {
+                let marshalls = Marshalls::godot_singleton();
+                let value = marshalls.base64_to_variant(marshalls.raw_to_base64(bytes), false);
+                Ok(value)
+            }
+            Codec::MessagePack => {
+                let bytes = bytes.read().to_vec();
+                let value = rmpv::decode::read_value(&mut bytes.as_slice())
+                    .map_err(|error| format!("Unable to decode the MessagePack: {}", error))?;
+                from_message_pack(&value).map(|value| math_type::revive(&value))
+            }
+            Codec::Custom(_, decode) => Ok(decode.call_func(&[Variant::from_byte_array(&bytes)])),
+        }
+    }
+}
+
+/// The built-in codecs and the custom ones registered with `register_codec`,
+/// by name.
+#[derive(Default)]
+pub struct Codecs {
+    /// The custom codecs, by name.
+    custom: BTreeMap<String, Codec>,
+}
+
+impl Codecs {
+    /// Registers a custom codec, replacing the one that had the name before.
+    /// Returns an error if the name is empty or belongs to a built-in codec.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the codec.
+    /// * `codec` - The codec.
+    pub fn register(&mut self, name: &str, codec: Codec) -> Result<(), String> {
+        if name.is_empty() || [JSON, BINARY, MESSAGE_PACK].contains(&name) {
+            return Err(format!(
+                "The name of a codec can't be empty or the name of a built-in codec, got {}",
+                name
+            ));
+        }
+
+        self.custom.insert(name.to_string(), codec);
+        Ok(())
+    }
+
+    /// Returns the names of every codec, with the built-in ones first.
+    pub fn names(&self) -> Vec<String> {
+        [JSON, BINARY, MESSAGE_PACK]
+            .iter()
+            .map(|name| name.to_string())
+            .chain(self.custom.keys().cloned())
+            .collect()
+    }
+
+    /// Returns the codec with a name, or the codec that goes with the
+    /// extension of a file if the name is empty, which is JSON for `.json`
+    /// files, MessagePack for `.msgpack` files, and binary for anything else.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the codec.
+    /// * `path` - The path to the file, or an empty string if the value isn't
+    /// going to a file.
+    pub fn get(&self, name: &str, path: &str) -> Result<Codec, String> {
+        let path = path.to_lowercase();
+        match name {
+            "" if path.ends_with(".json") => Ok(Codec::Json),
+            "" if path.ends_with(".msgpack") => Ok(Codec::MessagePack),
+            "" | BINARY => Ok(Codec::Binary),
+            JSON => Ok(Codec::Json),
+            MESSAGE_PACK => Ok(Codec::MessagePack),
+            other => self.custom.get(other).cloned().ok_or_else(|| {
+                format!(
+                    "The codec has to be json, binary, msgpack, or one registered with register_codec, got {}",
+                    other
+                )
+            }),
+        }
+    }
+
+    /// Drops the custom codecs.
+    pub fn clear(&mut self) {
+        self.custom.clear();
+    }
+}
+
+/// Returns the MessagePack value for a value. `Vector2`s, `Vector3`s, and
+/// `Color`s are written as a map of their type and components, and other
+/// values that MessagePack has no representation for, like a `NodePath`, are
+/// written as a string made up of their type and value, like in JSON.
+///
+/// # Arguments
+///
+/// * `value` - The value.
+fn to_message_pack(value: &Variant) -> Value {
+    match value.get_type() {
+        VariantType::Nil => Value::Nil,
+        VariantType::Bool => Value::from(value.to_bool()),
+        VariantType::I64 => Value::from(value.to_i64()),
+        VariantType::F64 => Value::from(value.to_f64()),
+        VariantType::GodotString => Value::from(value.to_string()),
+        VariantType::ByteArray => Value::from(value.to_byte_array().read().to_vec()),
+        VariantType::Dictionary => Value::Map(
+            value
+                .to_dictionary()
+                .iter()
+                .map(|(key, value)| (to_message_pack(&key), to_message_pack(&value)))
+                .collect(),
+        ),
+        VariantType::VariantArray => Value::Array(
+            value
+                .to_array()
+                .iter()
+                .map(|value| to_message_pack(&value))
+                .collect(),
+        ),
+        other => match math_type::to_tagged(value) {
+            Some(tagged) => to_message_pack(&tagged.owned_to_variant()),
+            None => Value::from(format!("{:?}{}", other, value.to_string())),
+        },
+    }
+}
+
+/// Returns the value for a MessagePack value.
+///
+/// # Arguments
+///
+/// * `value` - The MessagePack value.
+fn from_message_pack(value: &Value) -> Result<Variant, String> {
+    match value {
+        Value::Nil => Ok(Variant::new()),
+        Value::Boolean(value) => Ok(Variant::from_bool(*value)),
+        Value::Integer(value) => value
+            .as_i64()
+            .map(Variant::from_i64)
+            .ok_or_else(|| format!("The MessagePack integer {} is too large", value)),
+        Value::F32(value) => Ok(Variant::from_f64(*value as f64)),
+        Value::F64(value) => Ok(Variant::from_f64(*value)),
+        Value::String(value) => value
+            .as_str()
+            .map(Variant::from_str)
+            .ok_or_else(|| "The MessagePack string isn't valid UTF-8".to_string()),
+        Value::Binary(bytes) => Ok(Variant::from_byte_array(&ByteArray::from_slice(bytes))),
+        Value::Array(values) => {
+            let array = VariantArray::new();
+            for value in values {
+                array.push(from_message_pack(value)?);
+            }
+            Ok(array.owned_to_variant())
+        }
+        Value::Map(entries) => {
+            let dictionary = Dictionary::new();
+            for (key, value) in entries {
+                dictionary.insert(from_message_pack(key)?, from_message_pack(value)?);
+            }
+            Ok(dictionary.owned_to_variant())
+        }
+        Value::Ext(kind, _) => Err(format!(
+            "The MessagePack extension type {} isn't supported",
+            kind
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Codec, Codecs};
+
+    #[test]
+    fn get_picks_the_codec_by_name_or_by_extension() {
+        let codecs = Codecs::default();
+
+        assert!(matches!(
+            codecs.get("", "user://save.json"),
+            Ok(Codec::Json)
+        ));
+        assert!(matches!(
+            codecs.get("", "user://SAVE.MSGPACK"),
+            Ok(Codec::MessagePack)
+        ));
+        assert!(matches!(
+            codecs.get("", "user://save.dat"),
+            Ok(Codec::Binary)
+        ));
+        assert!(matches!(codecs.get("", ""), Ok(Codec::Binary)));
+        assert!(matches!(
+            codecs.get("json", "user://save.dat"),
+            Ok(Codec::Json)
+        ));
+        assert!(matches!(
+            codecs.get("binary", "user://save.json"),
+            Ok(Codec::Binary)
+        ));
+        assert!(codecs.get("yaml", "user://save.yaml").is_err());
+    }
+
+    #[test]
+    fn register_refuses_the_names_of_built_in_codecs() {
+        let mut codecs = Codecs::default();
+
+        assert!(codecs.register("", Codec::Json).is_err());
+        assert!(codecs.register("json", Codec::Binary).is_err());
+        assert!(codecs.register("msgpack", Codec::Json).is_err());
+        assert!(codecs.register("compact", Codec::Binary).is_ok());
+        assert!(matches!(codecs.get("compact", ""), Ok(Codec::Binary)));
+    }
+
+    #[test]
+    fn names_lists_the_built_in_codecs_first() {
+        let mut codecs = Codecs::default();
+        codecs.register("zipped", Codec::Binary).unwrap();
+        codecs.register("compact", Codec::Binary).unwrap();
+
+        assert_eq!(
+            codecs.names(),
+            vec!["json", "binary", "msgpack", "compact", "zipped"]
+        );
+
+        codecs.clear();
+        assert_eq!(codecs.names(), vec!["json", "binary", "msgpack"]);
+    }
+}
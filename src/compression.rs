@@ -0,0 +1,91 @@
+use gdnative::api::Marshalls;
+use gdnative::prelude::{ByteArray, Variant};
+use miniz_oxide::deflate::compress_to_vec;
+use miniz_oxide::inflate::decompress_to_vec;
+
+/// How hard values are compressed, from 0 for not at all to 10 for as small
+/// as possible. 6 is what zlib uses by default and keeps compressing a
+/// snapshot on every dispatch fast enough.
+const LEVEL: u8 = 6;
+
+/// A value kept as compressed bytes, for snapshots of the state that are kept
+/// around in case they're needed but are rarely read. The value is rebuilt
+/// from the bytes every time it's read, so reading it gives a new copy.
+pub struct Compressed {
+    /// The value encoded the way `var2bytes` encodes it, and then compressed.
+    bytes: Vec<u8>,
+}
+
+impl Compressed {
+    /// Compresses a value.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The value.
+    pub fn new(value: &Variant) -> Self {
+        let marshalls = Marshalls::godot_singleton();
+        let encoded = marshalls.base64_to_raw(marshalls.variant_to_base64(value.clone(), false));
+
+        Compressed {
+            bytes: compress(&encoded.read()),
+        }
+    }
+
+    /// Returns the value, rebuilt from the compressed bytes.
+    pub fn value(&self) -> Variant {
+        let marshalls = Marshalls::godot_singleton();
+        let encoded = ByteArray::from_vec(decompress(&self.bytes));
+
+        marshalls.base64_to_variant(marshalls.raw_to_base64(encoded), false)
+    }
+
+    /// Returns the number of bytes the compressed value takes up.
+    pub fn size(&self) -> usize {
+        self.bytes.len()
+    }
+}
+
+/// Returns bytes compressed with deflate.
+///
+/// # Arguments
+///
+/// * `bytes` - The bytes to compress.
+fn compress(bytes: &[u8]) -> Vec<u8> {
+    compress_to_vec(bytes, LEVEL)
+}
+
+/// Returns bytes compressed by `compress` as they were before. Only bytes
+/// made by `compress` are passed in, so this doesn't fail, but if it ever
+/// does no bytes are returned so the value reads as `null`.
+///
+/// # Arguments
+///
+/// * `bytes` - The compressed bytes.
+fn decompress(bytes: &[u8]) -> Vec<u8> {
+    decompress_to_vec(bytes).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{compress, decompress};
+
+    #[test]
+    fn decompress_gives_back_the_bytes_that_were_compressed() {
+        let bytes = (0..=255).cycle().take(1000).collect::<Vec<u8>>();
+
+        assert_eq!(decompress(&compress(&bytes)), bytes);
+        assert_eq!(decompress(&compress(&[])), Vec::<u8>::new());
+    }
+
+    #[test]
+    fn repeated_bytes_take_up_less_room() {
+        let bytes = b"\"hp\": 100, ".repeat(100);
+
+        assert!(compress(&bytes).len() < bytes.len() / 10);
+    }
+
+    #[test]
+    fn bytes_that_werent_compressed_decompress_to_nothing() {
+        assert!(decompress(b"not deflate").is_empty());
+    }
+}
@@ -0,0 +1,138 @@
+use gdnative::prelude::{Dictionary, OwnedToVariant, Unique, Variant, VariantArray, VariantType};
+
+use crate::file;
+
+/// The name the store's command is registered under in a console.
+pub const COMMAND: &str = "store";
+
+/// The number of actions `history` lists when it isn't given a count.
+const DEFAULT_HISTORY_COUNT: usize = 10;
+
+/// The text shown by the `help` command.
+pub const HELP: &str = "\
+state                    Shows the whole state
+state get <path>         Shows the value at a path, like player/hp
+state set <path> <json>  Sets the value at a path
+dispatch <json>          Dispatches an action, like {\"type\": \"heal\", \"amount\": 10}
+history [count]          Lists the last actions that reached the reducer
+undo                     Undoes the last action
+cheat <name>             Uses a cheat, like god_mode
+cheats                   Lists the cheats that can be used
+help                     Shows this list";
+
+/// A command typed into the console.
+pub enum Command {
+    /// Shows the value at a path, or the whole state if there isn't one.
+    Get(Option<String>),
+    /// Sets the value at a path.
+    Set(String, Variant),
+    /// Dispatches an action.
+    Dispatch(Variant),
+    /// Lists the last actions in the event log.
+    History(usize),
+    /// Rebuilds the state without the last action in the event log.
+    Undo,
+    /// Uses a cheat.
+    Cheat(String),
+    /// Lists the cheats that can be used.
+    Cheats,
+    /// Shows the commands.
+    Help,
+}
+
+/// Parses a line typed into the console, returning an error describing what
+/// is wrong with it if it isn't a command. Whole numbers in JSON values are
+/// read as integers so they match enum values.
+///
+/// # Arguments
+///
+/// * `line` - The line, without the name of the command it was typed after.
+pub fn parse(line: &str) -> Result<Command, String> {
+    let line = line.trim();
+    let (name, rest) = split_word(line);
+
+    match name {
+        "state" => {
+            let (sub_command, rest) = split_word(rest);
+            match sub_command {
+                "" => Ok(Command::Get(None)),
+                "get" if !rest.is_empty() => Ok(Command::Get(Some(rest.to_string()))),
+                "set" => {
+                    let (path, value) = split_word(rest);
+                    if path.is_empty() || value.is_empty() {
+                        return Err("Usage: state set <path> <json>".to_string());
+                    }
+
+                    Ok(Command::Set(path.to_string(), parse_value(value)?))
+                }
+                _ => Err("Usage: state [get <path> | set <path> <json>]".to_string()),
+            }
+        }
+        "dispatch" if !rest.is_empty() => Ok(Command::Dispatch(parse_value(rest)?)),
+        "dispatch" => Err("Usage: dispatch <json>".to_string()),
+        "history" if rest.is_empty() => Ok(Command::History(DEFAULT_HISTORY_COUNT)),
+        "history" => rest
+            .parse::<usize>()
+            .map(Command::History)
+            .map_err(|_| format!("The count has to be a whole number, got {}", rest)),
+        "undo" => Ok(Command::Undo),
+        "cheat" if !rest.is_empty() => Ok(Command::Cheat(rest.to_string())),
+        "cheat" => Err("Usage: cheat <name>".to_string()),
+        "cheats" => Ok(Command::Cheats),
+        "help" | "" => Ok(Command::Help),
+        _ => Err(format!(
+            "Unknown command {}, type help to see the commands",
+            name
+        )),
+    }
+}
+
+/// Splits the first word off of a line.
+///
+/// # Arguments
+///
+/// * `line` - The line.
+fn split_word(line: &str) -> (&str, &str) {
+    match line.find(char::is_whitespace) {
+        Some(end) => (&line[..end], line[end..].trim_start()),
+        None => (line, ""),
+    }
+}
+
+/// Parses a JSON value typed into the console.
+///
+/// # Arguments
+///
+/// * `text` - The JSON text.
+fn parse_value(text: &str) -> Result<Variant, String> {
+    file::parse_json(text)
+        .map(|value| whole_numbers_as_integers(&value))
+        .map_err(|message| format!("Unable to parse {} as JSON: {}", text, message))
+}
+
+/// Returns a copy of a value parsed from JSON with every whole number turned
+/// into an integer, since JSON reads every number as a float.
+///
+/// # Arguments
+///
+/// * `value` - The value.
+pub fn whole_numbers_as_integers(value: &Variant) -> Variant {
+    match value.get_type() {
+        VariantType::F64 if value.to_f64().fract() == 0.0 => Variant::from_i64(value.to_i64()),
+        VariantType::Dictionary => {
+            let converted = Dictionary::new();
+            for (key, value) in value.to_dictionary().iter() {
+                converted.insert(key, whole_numbers_as_integers(&value));
+            }
+
+            converted.owned_to_variant()
+        }
+        VariantType::VariantArray => value
+            .to_array()
+            .iter()
+            .map(|value| whole_numbers_as_integers(&value))
+            .collect::<VariantArray<Unique>>()
+            .owned_to_variant(),
+        _ => value.clone(),
+    }
+}
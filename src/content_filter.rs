@@ -0,0 +1,134 @@
+use gdnative::api::FuncRef;
+use gdnative::prelude::{Dictionary, Ref, Shared, Unique, Variant, VariantType};
+
+use crate::action::{self, action_type};
+use crate::path;
+
+/// The type of the action dispatched instead of an action that had blocked
+/// content in one of its fields.
+pub const CONTENT_BLOCKED: &str = "CONTENT_BLOCKED";
+
+/// The key of the blocked action in a `CONTENT_BLOCKED` action.
+const ACTION_KEY: &str = "action";
+
+/// The key of the path of the blocked field in a `CONTENT_BLOCKED` action.
+const FIELD_KEY: &str = "field";
+
+/// How the text in a field is checked.
+#[derive(Clone)]
+enum Rule {
+    /// The words that are blocked, in lowercase.
+    BlockList(Vec<String>),
+    /// A function that is passed the text, the path of the field, and the
+    /// action and returns whether the text is blocked.
+    Callback(Ref<FuncRef, Shared>),
+}
+
+/// A filter added with `add_content_filter`, which checks the text in fields
+/// of actions of a type before they reach the middleware and the reducer.
+#[derive(Clone)]
+pub struct ContentFilter {
+    /// The type of the actions the filter checks.
+    action: Variant,
+    /// The paths of the fields the filter checks, like `player/name`.
+    fields: Vec<String>,
+    /// How the text in the fields is checked.
+    rule: Rule,
+}
+
+impl ContentFilter {
+    /// Creates a filter from a rule that is either an Array of blocked words or
+    /// a FuncRef, returning an error describing what is wrong with it if it's
+    /// neither.
+    ///
+    /// # Arguments
+    ///
+    /// * `action` - The type of the actions the filter checks.
+    /// * `fields` - The paths of the fields the filter checks.
+    /// * `rule` - The blocked words or the function that checks the text.
+    pub fn new(action: Variant, fields: Vec<String>, rule: &Variant) -> Result<Self, String> {
+        let rule = match rule.get_type() {
+            VariantType::VariantArray => Rule::BlockList(
+                rule.to_array()
+                    .iter()
+                    .map(|word| word.to_string().to_lowercase())
+                    .collect(),
+            ),
+            _ => rule
+                .try_to_object::<FuncRef>()
+                .map(Rule::Callback)
+                .ok_or_else(|| {
+                    format!(
+                        "The rule has to be an Array of blocked words or a FuncRef, got {}",
+                        rule.to_string()
+                    )
+                })?,
+        };
+
+        Ok(ContentFilter {
+            action,
+            fields,
+            rule,
+        })
+    }
+
+    /// Returns the path of the first field of the action with blocked text, or
+    /// `None` if the filter doesn't check actions of its type or nothing in it
+    /// is blocked. Fields that don't exist or aren't strings are skipped.
+    ///
+    /// # Arguments
+    ///
+    /// * `action` - The action that was dispatched.
+    pub fn blocked_field(&self, action: &Variant) -> Option<String> {
+        if action_type(action) != self.action {
+            return None;
+        }
+        let fields = action.try_to_dictionary()?;
+
+        self.fields
+            .iter()
+            .find(|field| match path::get(&fields, field) {
+                Some(text) if text.get_type() == VariantType::GodotString => {
+                    self.is_blocked(&text, field, action)
+                }
+                _ => false,
+            })
+            .cloned()
+    }
+
+    /// Returns whether text is blocked by the rule. A block list matches whole
+    /// words, ignoring case.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The text in the field.
+    /// * `field` - The path of the field.
+    /// * `action` - The action the field is in.
+    fn is_blocked(&self, text: &Variant, field: &str, action: &Variant) -> bool {
+        match &self.rule {
+            Rule::BlockList(words) => text
+                .to_string()
+                .to_lowercase()
+                .split(|c: char| !c.is_alphanumeric())
+                .any(|word| words.iter().any(|blocked| blocked == word)),
+            Rule::Callback(callback) => unsafe { callback.assume_safe() }
+                .call_func(&[text.clone(), Variant::from_str(field), action.clone()])
+                .to_bool(),
+        }
+    }
+}
+
+/// Returns the action dispatched instead of an action that had blocked
+/// content.
+///
+/// # Arguments
+///
+/// * `blocked` - The action that was blocked.
+/// * `field` - The path of the field with the blocked content.
+pub fn content_blocked(blocked: &Variant, field: &str) -> Dictionary<Unique> {
+    let action = action::named(CONTENT_BLOCKED);
+    action.insert(ACTION_KEY, blocked);
+    action.insert(FIELD_KEY, field);
+
+    action
+}
@@ -0,0 +1,244 @@
+use gdnative::prelude::{Dictionary, OwnedToVariant, Unique, VariantType};
+
+use crate::path;
+
+/// The key of the id in a content pack.
+const ID_KEY: &str = "id";
+
+/// The key of the slices in a content pack.
+const SLICES_KEY: &str = "slices";
+
+/// A content pack, like a DLC, that adds reference data like items, levels,
+/// or translations to the read-only slices of the state.
+pub struct ContentPack {
+    /// The id of the pack.
+    id: String,
+    /// The data the pack adds to each slice, by slice key.
+    slices: Vec<(String, Dictionary)>,
+    /// Whether the pack can replace values that are already in the slices.
+    overrides: bool,
+}
+
+impl ContentPack {
+    /// Creates a content pack from its definition, which has its id under
+    /// `id` and the data it adds under `slices`, as a Dictionary for each
+    /// slice it adds to.
+    ///
+    /// # Arguments
+    ///
+    /// * `definition` - The definition of the pack.
+    /// * `overrides` - Whether the pack can replace values that are already
+    /// in the slices.
+    pub fn new(definition: &Dictionary, overrides: bool) -> Result<Self, String> {
+        let id = definition.get(ID_KEY);
+        if id.get_type() != VariantType::GodotString || id.to_string().is_empty() {
+            return Err(format!(
+                "A content pack needs an id under {}, got {}",
+                ID_KEY,
+                id.to_string()
+            ));
+        }
+        let id = id.to_string();
+
+        let slices = definition
+            .get(SLICES_KEY)
+            .try_to_dictionary()
+            .ok_or_else(|| {
+                format!(
+                    "The content pack {} needs a Dictionary of slices under {}",
+                    id, SLICES_KEY
+                )
+            })?;
+        let slices = slices
+            .iter()
+            .map(|(slice, data)| {
+                let slice = slice.to_string();
+                match data.try_to_dictionary() {
+                    Some(data) => Ok((slice, data)),
+                    None => Err(format!(
+                        "The slice {} of the content pack {} has to be a Dictionary, got {}",
+                        slice,
+                        id,
+                        data.to_string()
+                    )),
+                }
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+
+        Ok(ContentPack {
+            id,
+            slices,
+            overrides,
+        })
+    }
+
+    /// Returns the id of the pack.
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Returns the keys of the slices the pack adds to.
+    pub fn slice_keys(&self) -> impl Iterator<Item = &str> {
+        self.slices.iter().map(|(slice, _)| slice.as_str())
+    }
+}
+
+/// The content packs loaded into the store, in the order they were loaded,
+/// along with what their slices had in them before any pack was loaded, so
+/// the slices can be put back together when a pack is removed.
+#[derive(Default)]
+pub struct ContentPacks {
+    /// What each slice a pack adds to had in it before the first pack.
+    base: Vec<(String, Dictionary)>,
+    /// The packs, in the order they were loaded.
+    packs: Vec<ContentPack>,
+}
+
+impl ContentPacks {
+    /// Returns the ids of the packs, in the order they were loaded.
+    pub fn ids(&self) -> Vec<String> {
+        self.packs.iter().map(|pack| pack.id.clone()).collect()
+    }
+
+    /// Returns whether a pack is loaded.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The id of the pack.
+    pub fn contains(&self, id: &str) -> bool {
+        self.packs.iter().any(|pack| pack.id == id)
+    }
+
+    /// Loads a pack after the ones that are already loaded and returns the
+    /// slices it adds to with its data merged into them. Returns an error
+    /// with the paths of the values it collides with if it has values that
+    /// are already in the slices and it can't replace them, in which case it
+    /// isn't loaded.
+    ///
+    /// # Arguments
+    ///
+    /// * `pack` - The pack.
+    /// * `state` - The state, which the slices are read from.
+    pub fn load(
+        &mut self,
+        pack: ContentPack,
+        state: &Dictionary,
+    ) -> Result<Vec<(String, Dictionary)>, String> {
+        let mut collisions = vec![];
+        let slices = pack
+            .slices
+            .iter()
+            .map(|(slice, data)| {
+                let current = state.get(slice).try_to_dictionary().unwrap_or_default();
+                let merged = merge(&current, data, slice, pack.overrides, &mut collisions);
+
+                (slice.clone(), merged.into_shared())
+            })
+            .collect::<Vec<_>>();
+
+        if !collisions.is_empty() {
+            return Err(format!(
+                "The content pack {} has values that are already loaded at {}",
+                pack.id,
+                collisions.join(", ")
+            ));
+        }
+
+        for (slice, _) in &pack.slices {
+            if self.base.iter().all(|(base, _)| base != slice) {
+                let current = state.get(slice).try_to_dictionary().unwrap_or_default();
+                self.base.push((slice.clone(), current));
+            }
+        }
+        self.packs.push(pack);
+
+        Ok(slices)
+    }
+
+    /// Removes a pack and returns the slices it added to, put back together
+    /// from what they had in them before the first pack and the packs that
+    /// are still loaded, in the order they were loaded. Returns `None` if the
+    /// pack isn't loaded.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The id of the pack.
+    pub fn unload(&mut self, id: &str) -> Option<Vec<(String, Dictionary)>> {
+        let index = self.packs.iter().position(|pack| pack.id == id)?;
+        let removed = self.packs.remove(index);
+
+        let slices = removed
+            .slices
+            .iter()
+            .map(|(slice, _)| {
+                let base = self
+                    .base
+                    .iter()
+                    .find(|(base, _)| base == slice)
+                    .map(|(_, data)| data.new_ref())
+                    .unwrap_or_default();
+
+                // The packs that are left were loaded on top of each other
+                // without colliding, so they can't collide now.
+                let mut collisions = vec![];
+                let data = self
+                    .packs
+                    .iter()
+                    .flat_map(|pack| {
+                        pack.slices
+                            .iter()
+                            .filter(move |(key, _)| key == slice)
+                            .map(move |(_, data)| (data, pack.overrides))
+                    })
+                    .fold(base, |data, (pack_data, overrides)| {
+                        merge(&data, pack_data, slice, overrides, &mut collisions).into_shared()
+                    });
+
+                (slice.clone(), data)
+            })
+            .collect();
+
+        Some(slices)
+    }
+}
+
+/// Returns a copy of a Dictionary with the data of a pack merged into it.
+/// Dictionaries in both are merged however deeply they are nested, and any
+/// other value of the pack that is already in the Dictionary is a collision,
+/// which the pack replaces if it can and is added to the collisions if not.
+///
+/// # Arguments
+///
+/// * `current` - The Dictionary.
+/// * `data` - The data of the pack.
+/// * `prefix` - The path to the Dictionary.
+/// * `overrides` - Whether the pack can replace values.
+/// * `collisions` - The paths of the collisions the pack can't replace.
+fn merge(
+    current: &Dictionary,
+    data: &Dictionary,
+    prefix: &str,
+    overrides: bool,
+    collisions: &mut Vec<String>,
+) -> Dictionary<Unique> {
+    let merged = current.duplicate();
+    for (key, value) in data.iter() {
+        if !current.contains(&key) {
+            merged.insert(key, value);
+            continue;
+        }
+
+        let key_path = path::join(prefix, &key.to_string());
+        let existing = current.get(&key);
+        match (existing.try_to_dictionary(), value.try_to_dictionary()) {
+            (Some(existing), Some(value)) => {
+                let value = merge(&existing, &value, &key_path, overrides, collisions);
+                merged.insert(key, value.owned_to_variant());
+            }
+            _ if overrides => merged.insert(key, value),
+            _ => collisions.push(key_path),
+        }
+    }
+
+    merged
+}
@@ -0,0 +1,209 @@
+use gdnative::prelude::{Dictionary, Unique, Variant};
+
+use crate::{action, path};
+
+/// The key of the slice of the state that holds every cooldown, by name.
+pub const SLICE: &str = "cooldowns";
+
+/// The type of the action that starts a cooldown.
+const START_COOLDOWN: &str = "start_cooldown";
+
+/// The type of the action dispatched every frame while a cooldown is running.
+const COOLDOWN_TICK: &str = "cooldown_tick";
+
+/// The key of the name of the cooldown in a `start_cooldown` action.
+const COOLDOWN_KEY: &str = "cooldown";
+
+/// The key of how long the cooldown lasts, both in a cooldown and in a
+/// `start_cooldown` action.
+const DURATION_KEY: &str = "duration";
+
+/// The key of how much of the cooldown is left in a cooldown.
+const REMAINING_KEY: &str = "remaining";
+
+/// The key of the seconds that passed in a `cooldown_tick` action.
+const DELTA_KEY: &str = "delta";
+
+/// How long a cooldown lasts and how much of it is left, in seconds.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Cooldown {
+    /// How long the cooldown lasts.
+    duration: f64,
+    /// How much of the cooldown is left.
+    remaining: f64,
+}
+
+impl Cooldown {
+    /// Creates a cooldown that was just started, where a negative duration
+    /// is the same as 0.
+    ///
+    /// # Arguments
+    ///
+    /// * `duration` - How long the cooldown lasts, in seconds.
+    fn started(duration: f64) -> Self {
+        let duration = duration.max(0.0);
+
+        Cooldown {
+            duration,
+            remaining: duration,
+        }
+    }
+
+    /// Reads a cooldown from the slice.
+    ///
+    /// # Arguments
+    ///
+    /// * `entry` - The cooldown in the slice.
+    fn from_dictionary(entry: &Dictionary) -> Self {
+        Cooldown {
+            duration: path::get(entry, DURATION_KEY).unwrap_or_default().to_f64(),
+            remaining: path::get(entry, REMAINING_KEY).unwrap_or_default().to_f64(),
+        }
+    }
+
+    /// Returns whether the cooldown is still running.
+    fn is_running(&self) -> bool {
+        self.remaining > 0.0
+    }
+
+    /// Returns the cooldown after some time passed, which stops at 0.
+    ///
+    /// # Arguments
+    ///
+    /// * `delta` - The seconds that passed.
+    fn ticked(self, delta: f64) -> Self {
+        Cooldown {
+            remaining: (self.remaining - delta).max(0.0),
+            ..self
+        }
+    }
+
+    /// Returns the cooldown as it's kept in the slice, with how long it lasts
+    /// under `duration` and how much of it is left under `remaining`.
+    fn to_dictionary(self) -> Dictionary<Unique> {
+        let cooldown = Dictionary::new();
+        cooldown.insert(DURATION_KEY, self.duration);
+        cooldown.insert(REMAINING_KEY, self.remaining);
+
+        cooldown
+    }
+}
+
+/// Returns the action that starts a cooldown.
+///
+/// # Arguments
+///
+/// * `name` - The name of the cooldown.
+/// * `duration` - How long the cooldown lasts, in seconds.
+pub fn start(name: &str, duration: f64) -> Dictionary<Unique> {
+    let action = action::named(START_COOLDOWN);
+    action.insert(COOLDOWN_KEY, name);
+    action.insert(DURATION_KEY, duration);
+
+    action
+}
+
+/// Returns the action that counts down every running cooldown.
+///
+/// # Arguments
+///
+/// * `delta` - The seconds that passed since the last tick.
+pub fn tick(delta: f64) -> Dictionary<Unique> {
+    let action = action::named(COOLDOWN_TICK);
+    action.insert(DELTA_KEY, delta);
+
+    action
+}
+
+/// Returns the slice after an action, or `None` if the action isn't a
+/// cooldown action.
+///
+/// # Arguments
+///
+/// * `current` - The slice before the action.
+/// * `action` - The action that was dispatched.
+pub fn reduce(current: &Dictionary, action: &Variant) -> Option<Dictionary<Unique>> {
+    if action::is_type(action, START_COOLDOWN) {
+        let name = action::field(action, COOLDOWN_KEY)?.to_string();
+        let duration = action::field(action, DURATION_KEY)?.to_f64();
+
+        let next = current.duplicate();
+        next.insert(name, Cooldown::started(duration).to_dictionary());
+
+        return Some(next);
+    }
+
+    if action::is_type(action, COOLDOWN_TICK) {
+        let delta = action::field(action, DELTA_KEY)?.to_f64();
+
+        let next = current.duplicate();
+        for (name, entry) in current.iter() {
+            let entry = match entry.try_to_dictionary() {
+                Some(entry) => entry,
+                None => continue,
+            };
+
+            let cooldown = Cooldown::from_dictionary(&entry);
+            if cooldown.is_running() {
+                next.insert(name, cooldown.ticked(delta).to_dictionary());
+            }
+        }
+
+        return Some(next);
+    }
+
+    None
+}
+
+/// Returns whether any cooldown in the slice is still running.
+///
+/// # Arguments
+///
+/// * `slice` - The slice.
+pub fn any_running(slice: &Dictionary) -> bool {
+    slice.iter().any(|(_, entry)| {
+        entry
+            .try_to_dictionary()
+            .is_some_and(|entry| Cooldown::from_dictionary(&entry).is_running())
+    })
+}
+
+/// Returns how many seconds are left on a cooldown, which is 0 for a cooldown
+/// that was never started.
+///
+/// # Arguments
+///
+/// * `slice` - The slice.
+/// * `name` - The name of the cooldown.
+pub fn time_left(slice: &Dictionary, name: &str) -> f64 {
+    path::get(slice, name)
+        .and_then(|entry| entry.try_to_dictionary())
+        .map_or(0.0, |entry| Cooldown::from_dictionary(&entry).remaining)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Cooldown;
+
+    #[test]
+    fn a_started_cooldown_has_all_of_its_duration_left() {
+        let cooldown = Cooldown::started(1.5);
+
+        assert_eq!(cooldown.remaining, 1.5);
+        assert!(cooldown.is_running());
+        assert!(!Cooldown::started(-1.0).is_running());
+        assert_eq!(Cooldown::started(-1.0).duration, 0.0);
+    }
+
+    #[test]
+    fn ticking_counts_down_and_stops_at_zero() {
+        let cooldown = Cooldown::started(1.0).ticked(0.25);
+        assert_eq!(cooldown.remaining, 0.75);
+        assert_eq!(cooldown.duration, 1.0);
+
+        let cooldown = cooldown.ticked(2.0);
+        assert_eq!(cooldown.remaining, 0.0);
+        assert!(!cooldown.is_running());
+        assert_eq!(cooldown.ticked(1.0), cooldown);
+    }
+}
@@ -0,0 +1,213 @@
+use gdnative::prelude::{Variant, VariantArray};
+
+use crate::action::action_type;
+use crate::{path, snapshot};
+
+/// How actions of a type are deduplicated.
+struct Rule {
+    /// The type of the actions.
+    action_type: Variant,
+    /// How long after an action the same action is dropped, in seconds.
+    window: f64,
+    /// The paths of the values in the action that decide whether two actions
+    /// are the same, or none to compare the whole action.
+    payload_keys: Vec<String>,
+}
+
+/// The rules set with `set_dedupe` and the actions that were let through
+/// recently, so the same action dispatched a few times in a row, like the
+/// pickup events of overlapping `Area2D`s, is only dispatched once.
+#[derive(Default)]
+pub struct Dedupe {
+    /// The rules, one for each action type.
+    rules: Vec<Rule>,
+    /// The actions that were let through that are still within the window of
+    /// their rule, as their type and what they were compared by.
+    seen: Seen<(Variant, String)>,
+}
+
+impl Dedupe {
+    /// Sets how actions of a type are deduplicated, replacing the rule the
+    /// type had before, or stops deduplicating them if the window is 0.
+    ///
+    /// # Arguments
+    ///
+    /// * `action_type` - The type of the actions.
+    /// * `window_ms` - How long after an action the same action is dropped,
+    /// in milliseconds.
+    /// * `payload_keys` - The paths of the values in the action that decide
+    /// whether two actions are the same, or none to compare the whole action.
+    pub fn set(&mut self, action_type: Variant, window_ms: f64, payload_keys: &VariantArray) {
+        self.rules.retain(|rule| rule.action_type != action_type);
+        self.seen.forget(|(seen_type, _)| *seen_type == action_type);
+        if window_ms > 0.0 {
+            self.rules.push(Rule {
+                action_type,
+                window: window_ms / 1000.0,
+                payload_keys: payload_keys.iter().map(|key| key.to_string()).collect(),
+            });
+        }
+    }
+
+    /// Returns whether an action is the same as one that was let through
+    /// within the window of its rule, and otherwise remembers it so the same
+    /// action is dropped until the window has passed.
+    ///
+    /// # Arguments
+    ///
+    /// * `action` - The action being dispatched.
+    /// * `now` - The current time, in seconds.
+    /// * `dry_run` - Whether the action is dispatched by a dry run, like
+    /// `preview` or `run_scenario`, which is never dropped or remembered.
+    pub fn is_duplicate(&mut self, action: &Variant, now: f64, dry_run: bool) -> bool {
+        if self.rules.is_empty() {
+            return false;
+        }
+
+        let action_type = action_type(action);
+        let rule = match self
+            .rules
+            .iter()
+            .find(|rule| rule.action_type == action_type)
+        {
+            Some(rule) => rule,
+            None => return false,
+        };
+        let key = compared(action, &rule.payload_keys);
+        let window = rule.window;
+
+        self.seen
+            .is_duplicate((action_type, key), now, window, dry_run)
+    }
+
+    /// Returns the types of the actions that are deduplicated.
+    pub fn action_types(&self) -> impl Iterator<Item = &Variant> {
+        self.rules.iter().map(|rule| &rule.action_type)
+    }
+
+    /// Drops every rule.
+    pub fn clear(&mut self) {
+        self.rules.clear();
+        self.seen = Seen::default();
+    }
+}
+
+/// The keys of the actions that were let through, with the time the window
+/// of each one ends at, in seconds.
+struct Seen<K> {
+    /// The keys and the times their windows end at.
+    keys: Vec<(K, f64)>,
+}
+
+impl<K> Default for Seen<K> {
+    fn default() -> Self {
+        Seen { keys: vec![] }
+    }
+}
+
+impl<K: PartialEq> Seen<K> {
+    /// Returns whether a key was let through within its window, and otherwise
+    /// remembers it until the window has passed. Dry runs neither check nor
+    /// remember the key, so a preview can't drop the real dispatch that comes
+    /// after it, and actions replayed from the history aren't dropped for
+    /// being the same as the ones they were recorded from.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The key of the action.
+    /// * `now` - The current time, in seconds.
+    /// * `window` - How long the key is remembered for, in seconds.
+    /// * `dry_run` - Whether the action is dispatched by a dry run.
+    fn is_duplicate(&mut self, key: K, now: f64, window: f64, dry_run: bool) -> bool {
+        if dry_run {
+            return false;
+        }
+
+        self.keys.retain(|(_, ends)| *ends > now);
+        let duplicate = self.keys.iter().any(|(seen, _)| *seen == key);
+        if !duplicate {
+            self.keys.push((key, now + window));
+        }
+
+        duplicate
+    }
+
+    /// Forgets the keys that match a predicate.
+    ///
+    /// # Arguments
+    ///
+    /// * `matches` - Returns whether a key is forgotten.
+    fn forget(&mut self, matches: impl Fn(&K) -> bool) {
+        self.keys.retain(|(key, _)| !matches(key));
+    }
+}
+
+/// Returns what an action is compared by, which is the values at the payload
+/// keys or the whole action.
+///
+/// # Arguments
+///
+/// * `action` - The action.
+/// * `payload_keys` - The paths of the values to compare.
+fn compared(action: &Variant, payload_keys: &[String]) -> String {
+    let dictionary = match action.try_to_dictionary() {
+        Some(dictionary) if !payload_keys.is_empty() => dictionary,
+        _ => return snapshot::to_stable_text(action),
+    };
+
+    payload_keys
+        .iter()
+        .map(|key| {
+            path::get(&dictionary, key)
+                .map(|value| snapshot::to_stable_text(&value))
+                .unwrap_or_default()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Seen;
+
+    #[test]
+    fn drops_the_same_key_within_its_window() {
+        let mut seen = Seen::default();
+
+        assert!(!seen.is_duplicate("pickup", 0.0, 1.0, false));
+        assert!(seen.is_duplicate("pickup", 0.5, 1.0, false));
+        assert!(!seen.is_duplicate("drop", 0.5, 1.0, false));
+        assert!(!seen.is_duplicate("pickup", 1.0, 1.0, false));
+    }
+
+    #[test]
+    fn dispatch_after_a_dry_run_goes_through() {
+        let mut seen = Seen::default();
+
+        // Like `preview` followed by the dispatch it previewed.
+        assert!(!seen.is_duplicate("pickup", 0.0, 1.0, true));
+        assert!(!seen.is_duplicate("pickup", 0.1, 1.0, false));
+        assert!(seen.is_duplicate("pickup", 0.2, 1.0, false));
+    }
+
+    #[test]
+    fn dry_run_after_a_dispatch_goes_through() {
+        let mut seen = Seen::default();
+
+        // Like `replay_from` running the action it was recorded from again.
+        assert!(!seen.is_duplicate("pickup", 0.0, 1.0, false));
+        assert!(!seen.is_duplicate("pickup", 0.1, 1.0, true));
+        assert!(seen.is_duplicate("pickup", 0.2, 1.0, false));
+    }
+
+    #[test]
+    fn forget_drops_the_matching_keys() {
+        let mut seen = Seen::default();
+        seen.is_duplicate("pickup", 0.0, 1.0, false);
+        seen.is_duplicate("drop", 0.0, 1.0, false);
+        seen.forget(|key| *key == "pickup");
+
+        assert!(!seen.is_duplicate("pickup", 0.1, 1.0, false));
+        assert!(seen.is_duplicate("drop", 0.1, 1.0, false));
+    }
+}
@@ -0,0 +1,426 @@
+use gdnative::api::networked_multiplayer_peer::ConnectionStatus;
+use gdnative::api::{WebSocketClient, WebSocketPeer, OS};
+use gdnative::prelude::{
+    ByteArray, Dictionary, OwnedToVariant, Ref, Shared, StringArray, Unique, Variant,
+};
+
+use crate::action::field;
+use crate::codec::Codec;
+use crate::console;
+
+/// The URL of a Redux DevTools remote server started with its default
+/// options, like `remotedev-server --port 8000`.
+const DEFAULT_URL: &str = "ws://localhost:8000/socketcluster/";
+
+/// The name the store shows up as in the DevTools UI if it isn't given one.
+const DEFAULT_NAME: &str = "Godot";
+
+/// The call id of the handshake that starts the connection.
+const HANDSHAKE_CID: i64 = 1;
+
+/// The call id of the login, which answers with the channel the messages from
+/// the DevTools UI are sent on.
+const LOGIN_CID: i64 = 2;
+
+/// The call id of the subscription to the channel of the DevTools UI.
+const SUBSCRIBE_CID: i64 = 3;
+
+/// The ping the server sends to check that the connection is still alive,
+/// along with the pong that answers it.
+const PINGS: &[(&str, &str)] = &[("#1", "#2"), ("", "")];
+
+/// How far along the connection to the server is.
+#[derive(PartialEq)]
+enum Phase {
+    /// The WebSocket is still connecting.
+    Connecting,
+    /// The handshake was sent and the server hasn't answered it yet.
+    Handshaking,
+    /// The login was sent and the server hasn't answered it yet.
+    LoggingIn,
+    /// The store is subscribed to the channel of the DevTools UI.
+    Ready,
+}
+
+/// What the DevTools UI asked the store to do.
+pub enum Command {
+    /// Send the whole state, since the UI was opened or wants to start
+    /// over from it.
+    SendState,
+    /// Dispatch an action typed into the UI.
+    Dispatch(Variant),
+    /// Replace the state with one the UI travelled back or forward to.
+    Jump(Dictionary),
+}
+
+/// A connection to a Redux DevTools remote server, like `remotedev-server`,
+/// which speaks the SocketCluster protocol over a WebSocket. The store sends
+/// every action with the state after it so they show up in the DevTools UI,
+/// and the UI sends back the actions typed into it and the states it travels
+/// to. Every message is JSON, encoded with the `json` codec.
+pub struct DevTools {
+    /// The WebSocket connected to the server.
+    client: Ref<WebSocketClient, Unique>,
+    /// The name the store shows up as in the DevTools UI.
+    name: String,
+    /// How far along the connection is.
+    phase: Phase,
+    /// The id the server gave the connection in the handshake.
+    socket_id: Variant,
+    /// The channel the DevTools UI sends messages on.
+    channel: String,
+    /// Whether the DevTools UI is recording the actions, which it stops
+    /// doing when it's closed or paused.
+    monitoring: bool,
+}
+
+impl DevTools {
+    /// Starts connecting to a server. The connection is made while `poll` is
+    /// called.
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - The URL of the server, or an empty string for the one
+    /// started with the default options.
+    /// * `name` - The name the store shows up as in the DevTools UI, or an
+    /// empty string for `Godot`.
+    pub fn connect(url: &str, name: &str) -> Result<Self, String> {
+        let url = Some(url)
+            .filter(|url| !url.is_empty())
+            .unwrap_or(DEFAULT_URL);
+        let name = Some(name)
+            .filter(|name| !name.is_empty())
+            .unwrap_or(DEFAULT_NAME);
+        let client = WebSocketClient::new();
+        client
+            .connect_to_url(url, StringArray::new(), false, StringArray::new())
+            .map_err(|error| format!("Unable to connect to {}: {:?}", url, error))?;
+
+        Ok(DevTools {
+            client,
+            name: name.to_string(),
+            phase: Phase::Connecting,
+            socket_id: Variant::new(),
+            channel: String::new(),
+            monitoring: false,
+        })
+    }
+
+    /// Returns whether the DevTools UI is recording the actions, so the state
+    /// only has to be encoded when somebody is looking at it.
+    pub fn is_monitoring(&self) -> bool {
+        self.phase == Phase::Ready && self.monitoring
+    }
+
+    /// Sends and receives the messages that arrived since it was last called
+    /// and returns what the DevTools UI asked the store to do. Returns an
+    /// error if the connection was closed or the server refused it.
+    pub fn poll(&mut self) -> Result<Vec<Command>, String> {
+        self.client.poll();
+        if self.client.get_connection_status() == ConnectionStatus::DISCONNECTED {
+            return Err("The connection to the server was closed".to_string());
+        }
+
+        let peer = match self.peer() {
+            Some(peer) => peer,
+            None => return Ok(vec![]),
+        };
+        let peer = unsafe { peer.assume_safe() };
+        if self.phase == Phase::Connecting {
+            peer.set_write_mode(WebSocketPeer::WRITE_MODE_TEXT);
+            let data = Dictionary::new();
+            data.insert("authToken", Variant::new());
+            self.emit("#handshake", data.owned_to_variant(), Some(HANDSHAKE_CID));
+            self.phase = Phase::Handshaking;
+        }
+
+        let mut commands = vec![];
+        while peer.get_available_packet_count() > 0 {
+            // A message that isn't valid UTF-8 is ignored like any other
+            // message that can't be read.
+            if let Ok(text) = String::from_utf8(peer.get_packet().read().to_vec()) {
+                commands.extend(self.receive(&text)?);
+            }
+        }
+
+        Ok(commands)
+    }
+
+    /// Sends the whole state to the DevTools UI, which starts its list of
+    /// actions over from it.
+    ///
+    /// # Arguments
+    ///
+    /// * `state` - The state.
+    pub fn send_state(&self, state: &Dictionary) {
+        if self.phase != Phase::Ready {
+            return;
+        }
+
+        let message = self.message("INIT");
+        message.insert("payload", encode(&Variant::from_dictionary(state)));
+        self.emit("log", message.owned_to_variant(), None);
+    }
+
+    /// Sends an action to the DevTools UI with the state after it, if the UI
+    /// is recording the actions.
+    ///
+    /// # Arguments
+    ///
+    /// * `action` - The action that was dispatched.
+    /// * `state` - The state after the action.
+    pub fn send_action(&self, action: &Variant, state: &Dictionary) {
+        if !self.is_monitoring() {
+            return;
+        }
+
+        // Redux actions are objects with a type, so an action that is just
+        // its type is sent as one.
+        let action = match action.try_to_dictionary() {
+            Some(action) => action,
+            None => {
+                let wrapped = Dictionary::new();
+                wrapped.insert("type", action);
+                wrapped.into_shared()
+            }
+        };
+        let performed = Dictionary::new();
+        performed.insert("type", "PERFORM_ACTION");
+        performed.insert("action", action);
+        performed.insert("timestamp", OS::godot_singleton().get_system_time_msecs());
+
+        let message = self.message("ACTION");
+        message.insert("action", encode(&performed.owned_to_variant()));
+        message.insert("payload", encode(&Variant::from_dictionary(state)));
+        self.emit("log", message.owned_to_variant(), None);
+    }
+
+    /// Closes the connection.
+    pub fn close(&self) {
+        self.client.disconnect_from_host(1000, "");
+    }
+
+    /// Returns the peer of the connection to the server once it's connected.
+    fn peer(&self) -> Option<Ref<WebSocketPeer, Shared>> {
+        if self.client.get_connection_status() != ConnectionStatus::CONNECTED {
+            return None;
+        }
+
+        self.client.get_peer(1)
+    }
+
+    /// Handles a message from the server and returns what the DevTools UI
+    /// asked the store to do in it, if anything. Messages that aren't what the
+    /// protocol says they should be are ignored, since they come from outside
+    /// of the game.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The message.
+    fn receive(&mut self, text: &str) -> Result<Vec<Command>, String> {
+        if let Some((_, pong)) = PINGS.iter().find(|(ping, _)| *ping == text) {
+            self.send_text(pong);
+            return Ok(vec![]);
+        }
+
+        let message = match decode_value(text) {
+            Some(message) => message,
+            None => return Ok(vec![]),
+        };
+
+        if let Some(rid) = field(&message, "rid") {
+            if let Some(error) = field(&message, "error").filter(|error| !error.is_nil()) {
+                return Err(format!(
+                    "The server refused the connection: {}",
+                    error.to_string()
+                ));
+            }
+            let data = field(&message, "data").unwrap_or_else(Variant::new);
+            return Ok(self.answered(rid.to_i64(), &data));
+        }
+
+        let (event, data) = match (field(&message, "event"), field(&message, "data")) {
+            (Some(event), Some(data)) => (event.to_string(), data),
+            _ => return Ok(vec![]),
+        };
+        let from_monitor = if event == "#publish" {
+            field(&data, "channel")
+                .filter(|channel| channel.to_string() == self.channel)
+                .and_then(|_| field(&data, "data"))
+        } else if !self.channel.is_empty() && event == self.channel {
+            Some(data)
+        } else {
+            None
+        };
+
+        Ok(from_monitor.map_or(vec![], |data| self.command(&data)))
+    }
+
+    /// Moves the connection along once the server answers the handshake or
+    /// the login.
+    ///
+    /// # Arguments
+    ///
+    /// * `cid` - The call id of what the server answered.
+    /// * `data` - The answer.
+    fn answered(&mut self, cid: i64, data: &Variant) -> Vec<Command> {
+        match cid {
+            HANDSHAKE_CID if self.phase == Phase::Handshaking => {
+                self.socket_id = field(data, "id").unwrap_or_else(Variant::new);
+                self.emit("login", Variant::from_str("master"), Some(LOGIN_CID));
+                self.phase = Phase::LoggingIn;
+                vec![]
+            }
+            LOGIN_CID if self.phase == Phase::LoggingIn => {
+                self.channel = data.to_string();
+                let subscription = Dictionary::new();
+                subscription.insert("channel", &self.channel);
+                self.emit(
+                    "#subscribe",
+                    subscription.owned_to_variant(),
+                    Some(SUBSCRIBE_CID),
+                );
+                self.phase = Phase::Ready;
+                self.monitoring = true;
+                vec![Command::SendState]
+            }
+            _ => vec![],
+        }
+    }
+
+    /// Returns what a message from the DevTools UI asks the store to do.
+    /// Messages the store doesn't support, like toggling single actions,
+    /// are ignored, and so are the ones that are missing what they need.
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - The message.
+    fn command(&mut self, message: &Variant) -> Vec<Command> {
+        let message_type = field(message, "type").map(|message_type| message_type.to_string());
+        match message_type.as_deref() {
+            Some("START") => {
+                self.monitoring = true;
+                vec![Command::SendState]
+            }
+            Some("STOP") => {
+                self.monitoring = false;
+                vec![]
+            }
+            Some("UPDATE") => vec![Command::SendState],
+            Some("ACTION") => match field(message, "action") {
+                Some(action) => {
+                    // An action that isn't JSON, like `INCREMENT`, is taken
+                    // to be just its type.
+                    let text = action.to_string();
+                    let action = decode_value(&text).unwrap_or_else(|| Variant::from_str(&text));
+                    vec![Command::Dispatch(action)]
+                }
+                None => vec![],
+            },
+            Some("DISPATCH") => {
+                let payload = field(message, "payload").unwrap_or_else(Variant::new);
+                let payload_type =
+                    field(&payload, "type").map(|payload_type| payload_type.to_string());
+                match payload_type.as_deref() {
+                    Some("JUMP_TO_STATE") | Some("JUMP_TO_ACTION") | Some("ROLLBACK") => {
+                        field(message, "state")
+                            .and_then(|state| decode(&state.to_string()))
+                            .map_or(vec![], |state| vec![Command::Jump(state)])
+                    }
+                    Some("COMMIT") => vec![Command::SendState],
+                    Some("PAUSE_RECORDING") => {
+                        self.monitoring =
+                            !field(&payload, "status").is_some_and(|status| status.to_bool());
+                        vec![]
+                    }
+                    _ => vec![],
+                }
+            }
+            _ => vec![],
+        }
+    }
+
+    /// Returns a message for the DevTools UI of a type, with what every
+    /// message needs to be told apart from the ones of other stores.
+    ///
+    /// # Arguments
+    ///
+    /// * `message_type` - The type of the message.
+    fn message(&self, message_type: &str) -> Dictionary<Unique> {
+        let message = Dictionary::new();
+        message.insert("type", message_type);
+        message.insert("id", &self.socket_id);
+        message.insert("name", &self.name);
+        message.insert("instanceId", &self.name);
+
+        message
+    }
+
+    /// Sends an event to the server.
+    ///
+    /// # Arguments
+    ///
+    /// * `event` - The name of the event.
+    /// * `data` - The data of the event.
+    /// * `cid` - The call id, if the server has to answer the event.
+    fn emit(&self, event: &str, data: Variant, cid: Option<i64>) {
+        let message = Dictionary::new();
+        message.insert("event", event);
+        message.insert("data", data);
+        if let Some(cid) = cid {
+            message.insert("cid", cid);
+        }
+
+        self.send_text(&encode(&message.owned_to_variant()));
+    }
+
+    /// Sends text to the server.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The text.
+    fn send_text(&self, text: &str) {
+        if let Some(peer) = self.peer() {
+            let peer = unsafe { peer.assume_safe() };
+
+            // A message that can't be sent is dropped, since `poll` notices
+            // when the connection is closed.
+            let _ = peer.put_packet(ByteArray::from_slice(text.as_bytes()));
+        }
+    }
+}
+
+/// Returns a value as JSON text.
+///
+/// # Arguments
+///
+/// * `value` - The value.
+fn encode(value: &Variant) -> String {
+    Codec::Json
+        .encode(value)
+        .ok()
+        .and_then(|bytes| String::from_utf8(bytes.read().to_vec()).ok())
+        .unwrap_or_default()
+}
+
+/// Returns the value in JSON text with its whole numbers as integers, so
+/// they match enum values, or `None` if it isn't JSON.
+///
+/// # Arguments
+///
+/// * `text` - The JSON text.
+fn decode_value(text: &str) -> Option<Variant> {
+    Codec::Json
+        .decode(ByteArray::from_slice(text.as_bytes()))
+        .ok()
+        .map(|value| console::whole_numbers_as_integers(&value))
+}
+
+/// Returns the Dictionary in JSON text, or `None` if it isn't a JSON object.
+///
+/// # Arguments
+///
+/// * `text` - The JSON text.
+fn decode(text: &str) -> Option<Dictionary> {
+    decode_value(text).and_then(|value| value.try_to_dictionary())
+}
@@ -0,0 +1,454 @@
+use std::collections::BTreeMap;
+
+use gdnative::prelude::{Dictionary, Unique, Variant, VariantArray};
+
+use crate::{action, path};
+
+/// The key of the slice of the state that holds the current dialogue.
+pub const SLICE: &str = "dialogue";
+
+/// The type of the action that starts a dialogue.
+const START_DIALOGUE: &str = "start_dialogue";
+
+/// The type of the action that picks a choice, or moves on from a line
+/// without choices.
+const CHOOSE_DIALOGUE_OPTION: &str = "choose_dialogue_option";
+
+/// The type of the action that skips ahead to the next line with choices.
+const SKIP_DIALOGUE: &str = "skip_dialogue";
+
+/// The type of the action that goes back to earlier lines.
+const REWIND_DIALOGUE: &str = "rewind_dialogue";
+
+/// The key of the name of the dialogue, both in the slice and in a
+/// `start_dialogue` action.
+const DIALOGUE_KEY: &str = "dialogue";
+
+/// The key of the current node in the slice.
+const NODE_KEY: &str = "node";
+
+/// The key of the nodes that were visited before the current one in the
+/// slice, oldest first.
+const HISTORY_KEY: &str = "history";
+
+/// The key of the id of the picked choice in a `choose_dialogue_option`
+/// action.
+const OPTION_KEY: &str = "option";
+
+/// The key of the number of lines to go back in a `rewind_dialogue` action.
+const STEPS_KEY: &str = "steps";
+
+/// The key of the node the dialogue starts at, in a dialogue graph.
+const START_KEY: &str = "start";
+
+/// The key of the nodes, by id, in a dialogue graph.
+const NODES_KEY: &str = "nodes";
+
+/// The key of the speaker of a node.
+const SPEAKER_KEY: &str = "speaker";
+
+/// The key of the line of a node, and of the text of a choice.
+const TEXT_KEY: &str = "text";
+
+/// The key of the node that comes next, both in a node and in a choice.
+const NEXT_KEY: &str = "next";
+
+/// The key of the choices of a node.
+const CHOICES_KEY: &str = "choices";
+
+/// The key of the id of a choice.
+const ID_KEY: &str = "id";
+
+/// A choice the player can pick on a line.
+#[derive(Clone)]
+struct Choice {
+    /// The id of the choice, passed to `dispatch_choice`.
+    id: String,
+    /// The text of the choice.
+    text: String,
+    /// The node the choice leads to, or `None` if it ends the dialogue.
+    next: Option<String>,
+}
+
+/// A line of a dialogue.
+#[derive(Clone)]
+struct DialogueNode {
+    /// The name of whoever says the line.
+    speaker: String,
+    /// The line.
+    text: String,
+    /// The node that comes after the line when it has no choices, or `None` if
+    /// the dialogue ends after it.
+    next: Option<String>,
+    /// The choices the player can pick on the line.
+    choices: Vec<Choice>,
+}
+
+/// A dialogue graph loaded with `load_dialogue`.
+#[derive(Clone)]
+pub struct Dialogue {
+    /// The node the dialogue starts at.
+    start: String,
+    /// The nodes of the dialogue, by id.
+    nodes: BTreeMap<String, DialogueNode>,
+}
+
+impl Dialogue {
+    /// Creates a dialogue from its graph, returning an error describing the
+    /// first node that isn't valid or refers to a node that doesn't exist.
+    ///
+    /// # Arguments
+    ///
+    /// * `graph` - The dialogue graph, with the id of the first node under
+    /// `start` and the nodes by id under `nodes`.
+    pub fn new(graph: &Dictionary) -> Result<Self, String> {
+        let start = path::get(graph, START_KEY)
+            .ok_or("The dialogue is missing its start node")?
+            .to_string();
+        let nodes = path::get(graph, NODES_KEY)
+            .and_then(|nodes| nodes.try_to_dictionary())
+            .ok_or("The dialogue is missing its nodes")?
+            .iter()
+            .map(|(id, node)| {
+                let id = id.to_string();
+                parse_node(&id, &node).map(|node| (id, node))
+            })
+            .collect::<Result<BTreeMap<_, _>, _>>()?;
+
+        let dialogue = Dialogue { start, nodes };
+        dialogue.check_node(&dialogue.start, "The start node")?;
+        for (id, node) in &dialogue.nodes {
+            if let Some(next) = &node.next {
+                dialogue.check_node(next, &format!("The next node of {}", id))?;
+            }
+            for choice in &node.choices {
+                if let Some(next) = &choice.next {
+                    dialogue.check_node(
+                        next,
+                        &format!("The next node of the choice {} of {}", choice.id, id),
+                    )?;
+                }
+            }
+        }
+
+        Ok(dialogue)
+    }
+
+    /// Returns an error if the dialogue doesn't have a node.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The id of the node.
+    /// * `description` - What refers to the node, used in the error.
+    fn check_node(&self, id: &str, description: &str) -> Result<(), String> {
+        if self.nodes.contains_key(id) {
+            Ok(())
+        } else {
+            Err(format!("{} {} isn't in the dialogue", description, id))
+        }
+    }
+
+    /// Returns the node after a choice, or after a line without choices when
+    /// the option is empty. `None` is returned when the dialogue ends.
+    ///
+    /// # Arguments
+    ///
+    /// * `node` - The id of the current node.
+    /// * `option` - The id of the picked choice, or an empty String.
+    fn next(&self, node: &str, option: &str) -> Result<Option<String>, String> {
+        let current = &self.nodes[node];
+
+        if current.choices.is_empty() {
+            return Ok(current.next.clone());
+        }
+
+        current
+            .choices
+            .iter()
+            .find(|choice| choice.id == option)
+            .map(|choice| choice.next.clone())
+            .ok_or_else(|| format!("The line {} doesn't have the choice {}", node, option))
+    }
+}
+
+/// Returns the slice before any dialogue has been started.
+pub fn initial_slice() -> Dictionary<Unique> {
+    slice("", None, VariantArray::new())
+}
+
+/// Returns the action that starts a dialogue.
+///
+/// # Arguments
+///
+/// * `name` - The name of the dialogue.
+pub fn start(name: &str) -> Dictionary<Unique> {
+    let action = action::named(START_DIALOGUE);
+    action.insert(DIALOGUE_KEY, name);
+
+    action
+}
+
+/// Returns the action that picks a choice, or moves on from a line without
+/// choices when the option is empty.
+///
+/// # Arguments
+///
+/// * `option` - The id of the choice.
+pub fn choose(option: &str) -> Dictionary<Unique> {
+    let action = action::named(CHOOSE_DIALOGUE_OPTION);
+    action.insert(OPTION_KEY, option);
+
+    action
+}
+
+/// Returns the action that skips ahead to the next line with choices.
+pub fn skip() -> Dictionary<Unique> {
+    action::named(SKIP_DIALOGUE)
+}
+
+/// Returns the action that goes back to earlier lines.
+///
+/// # Arguments
+///
+/// * `steps` - The number of lines to go back.
+pub fn rewind(steps: i64) -> Dictionary<Unique> {
+    let action = action::named(REWIND_DIALOGUE);
+    action.insert(STEPS_KEY, steps);
+
+    action
+}
+
+/// Returns the slice after an action, or `None` if the action isn't a
+/// dialogue action. An error is returned for an action that can't be carried
+/// out, like picking a choice the current line doesn't have.
+///
+/// # Arguments
+///
+/// * `dialogues` - The loaded dialogues, by name.
+/// * `current` - The slice before the action.
+/// * `action` - The action that was dispatched.
+pub fn reduce(
+    dialogues: &BTreeMap<String, Dialogue>,
+    current: &Dictionary,
+    action: &Variant,
+) -> Result<Option<Dictionary<Unique>>, String> {
+    if action::is_type(action, START_DIALOGUE) {
+        let name = action::field(action, DIALOGUE_KEY)
+            .unwrap_or_default()
+            .to_string();
+        let dialogue = dialogues
+            .get(&name)
+            .ok_or_else(|| format!("The dialogue {} hasn't been loaded", name))?;
+
+        return Ok(Some(slice(
+            &name,
+            Some(&dialogue.start),
+            VariantArray::new(),
+        )));
+    }
+
+    let is_dialogue_action = [CHOOSE_DIALOGUE_OPTION, SKIP_DIALOGUE, REWIND_DIALOGUE]
+        .iter()
+        .any(|name| action::is_type(action, name));
+    if !is_dialogue_action {
+        return Ok(None);
+    }
+
+    let name = string_value(current, DIALOGUE_KEY);
+    let dialogue = dialogues
+        .get(&name)
+        .ok_or("There is no dialogue to change since none was started")?;
+    let history = path::get(current, HISTORY_KEY)
+        .and_then(|history| history.try_to_array())
+        .map(|history| history.duplicate())
+        .unwrap_or_default();
+
+    if action::is_type(action, REWIND_DIALOGUE) {
+        let steps = action::field(action, STEPS_KEY).map_or(1, |steps| steps.to_i64());
+        let mut node = current_node(current);
+        for _ in 0..steps.max(0) {
+            match history.pop().try_to_string() {
+                Some(previous) => node = Some(previous),
+                None => break,
+            }
+        }
+
+        return Ok(Some(slice(&name, node.as_deref(), history)));
+    }
+
+    let node = current_node(current).ok_or("The dialogue has already ended")?;
+    let node = if action::is_type(action, CHOOSE_DIALOGUE_OPTION) {
+        let option = action::field(action, OPTION_KEY)
+            .unwrap_or_default()
+            .to_string();
+        history.push(&node);
+        dialogue.next(&node, &option)?
+    } else {
+        // Skipping stops at the first line the player has to pick a choice on,
+        // keeping every line that was skipped in the history. Lines that lead
+        // back to each other without choices are only skipped through once.
+        let mut next = Some(node);
+        for _ in 0..dialogue.nodes.len() {
+            let id = match next {
+                Some(id) if dialogue.nodes[&id].choices.is_empty() => id,
+                _ => break,
+            };
+
+            history.push(&id);
+            next = dialogue.next(&id, "")?;
+        }
+
+        next
+    };
+
+    Ok(Some(slice(&name, node.as_deref(), history)))
+}
+
+/// Returns the speaker of the current line, or an empty String if there is
+/// no dialogue.
+///
+/// # Arguments
+///
+/// * `dialogues` - The loaded dialogues, by name.
+/// * `slice` - The slice.
+pub fn speaker(dialogues: &BTreeMap<String, Dialogue>, slice: &Dictionary) -> String {
+    node(dialogues, slice)
+        .map(|node| node.speaker)
+        .unwrap_or_default()
+}
+
+/// Returns the current line, or an empty String if there is no dialogue.
+///
+/// # Arguments
+///
+/// * `dialogues` - The loaded dialogues, by name.
+/// * `slice` - The slice.
+pub fn line(dialogues: &BTreeMap<String, Dialogue>, slice: &Dictionary) -> String {
+    node(dialogues, slice)
+        .map(|node| node.text)
+        .unwrap_or_default()
+}
+
+/// Returns the choices of the current line as Dictionaries with the id of
+/// the choice under `id` and its text under `text`.
+///
+/// # Arguments
+///
+/// * `dialogues` - The loaded dialogues, by name.
+/// * `slice` - The slice.
+pub fn choices(dialogues: &BTreeMap<String, Dialogue>, slice: &Dictionary) -> VariantArray<Unique> {
+    let choices = VariantArray::new();
+
+    for choice in node(dialogues, slice)
+        .map(|node| node.choices)
+        .unwrap_or_default()
+    {
+        let entry = Dictionary::new();
+        entry.insert(ID_KEY, choice.id);
+        entry.insert(TEXT_KEY, choice.text);
+        choices.push(entry);
+    }
+
+    choices
+}
+
+/// Returns the current node of the dialogue in the slice, if there is one.
+///
+/// # Arguments
+///
+/// * `dialogues` - The loaded dialogues, by name.
+/// * `slice` - The slice.
+fn node(dialogues: &BTreeMap<String, Dialogue>, slice: &Dictionary) -> Option<DialogueNode> {
+    let dialogue = dialogues.get(&string_value(slice, DIALOGUE_KEY))?;
+
+    dialogue.nodes.get(&current_node(slice)?).cloned()
+}
+
+/// Returns the id of the current node in the slice, or `None` if the
+/// dialogue has ended.
+///
+/// # Arguments
+///
+/// * `slice` - The slice.
+fn current_node(slice: &Dictionary) -> Option<String> {
+    path::get(slice, NODE_KEY).and_then(|node| node.try_to_string())
+}
+
+/// Parses a node of a dialogue graph.
+///
+/// # Arguments
+///
+/// * `id` - The id of the node.
+/// * `node` - The Dictionary of the node.
+fn parse_node(id: &str, node: &Variant) -> Result<DialogueNode, String> {
+    let node = node.try_to_dictionary().ok_or_else(|| {
+        format!(
+            "The dialogue node {} has to be a Dictionary, got {}",
+            id,
+            node.to_string()
+        )
+    })?;
+
+    let choices = path::get(&node, CHOICES_KEY)
+        .and_then(|choices| choices.try_to_array())
+        .unwrap_or_else(VariantArray::new_shared)
+        .iter()
+        .map(|choice| {
+            let choice = choice.try_to_dictionary().ok_or_else(|| {
+                format!(
+                    "The choices of the dialogue node {} have to be Dictionaries, got {}",
+                    id,
+                    choice.to_string()
+                )
+            })?;
+
+            Ok(Choice {
+                id: path::get(&choice, ID_KEY)
+                    .ok_or_else(|| {
+                        format!("A choice of the dialogue node {} is missing its id", id)
+                    })?
+                    .to_string(),
+                text: string_value(&choice, TEXT_KEY),
+                next: path::get(&choice, NEXT_KEY).and_then(|next| next.try_to_string()),
+            })
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+
+    Ok(DialogueNode {
+        speaker: string_value(&node, SPEAKER_KEY),
+        text: string_value(&node, TEXT_KEY),
+        next: path::get(&node, NEXT_KEY).and_then(|next| next.try_to_string()),
+        choices,
+    })
+}
+
+/// Creates the slice.
+///
+/// # Arguments
+///
+/// * `dialogue` - The name of the dialogue.
+/// * `node` - The id of the current node, or `None` if the dialogue ended.
+/// * `history` - The nodes that were visited before the current one.
+fn slice(dialogue: &str, node: Option<&str>, history: VariantArray<Unique>) -> Dictionary<Unique> {
+    let slice = Dictionary::new();
+    slice.insert(DIALOGUE_KEY, dialogue);
+    match node {
+        Some(node) => slice.insert(NODE_KEY, node),
+        None => slice.insert(NODE_KEY, Variant::new()),
+    }
+    slice.insert(HISTORY_KEY, history);
+
+    slice
+}
+
+/// Returns the String under a key of a Dictionary, or an empty String.
+///
+/// # Arguments
+///
+/// * `dictionary` - The Dictionary.
+/// * `key` - The key of the String.
+fn string_value(dictionary: &Dictionary, key: &str) -> String {
+    path::get(dictionary, key)
+        .map(|value| value.to_string())
+        .unwrap_or_default()
+}
@@ -0,0 +1,257 @@
+use gdnative::prelude::{Dictionary, OwnedToVariant, Unique, Variant, VariantArray};
+
+use crate::compression::Compressed;
+use crate::equality::Equality;
+use crate::{math_type, path};
+
+/// What happened to the value under a key of a Dictionary between two
+/// states.
+pub enum Change {
+    /// The key was added or its value was replaced with this one.
+    Set(Variant),
+    /// The key was removed.
+    Remove,
+    /// The value is a Dictionary in both states and some of its keys changed.
+    Nested(Delta),
+}
+
+/// The keys of a Dictionary that changed between two states and how they
+/// changed.
+pub type Delta = Vec<(Variant, Change)>;
+
+/// Returns the paths of every value that was added, removed, or changed
+/// between two states. Nested Dictionaries are compared key by key so only
+/// the deepest paths that changed are returned, and `Vector2`s, `Vector3`s,
+/// and `Color`s are compared component by component, so only
+/// `player/position/x` is returned when just `x` changed. Any other value
+/// (including Arrays) is compared as a whole.
+///
+/// # Arguments
+///
+/// * `old` - The state before the change.
+/// * `new` - The state after the change.
+pub fn changed_paths(old: &Dictionary, new: &Dictionary) -> Vec<String> {
+    let mut paths = vec![];
+    collect_changed_paths("", old, new, &mut paths);
+
+    paths
+}
+
+/// Returns the top-level keys whose value was added, removed, or changed
+/// between two states. Values that are the same Dictionary or Array are
+/// skipped without comparing what's inside of them.
+///
+/// # Arguments
+///
+/// * `old` - The state before the change.
+/// * `new` - The state after the change.
+pub fn changed_keys(old: &Dictionary, new: &Dictionary) -> Vec<String> {
+    let mut keys = vec![];
+    for (key, new_value) in new {
+        let changed = !old.contains(&key) || {
+            let old_value = old.get(&key);
+            !Equality::Reference.equals(&old_value, &new_value)
+                && !Equality::Deep.equals(&old_value, &new_value)
+        };
+        if changed {
+            keys.push(key.to_string());
+        }
+    }
+
+    for (key, _) in old {
+        if !new.contains(&key) {
+            keys.push(key.to_string());
+        }
+    }
+
+    keys
+}
+
+/// Adds the paths of the values that changed between two Dictionaries to the
+/// list of paths.
+///
+/// # Arguments
+///
+/// * `prefix` - The path to the Dictionaries being compared.
+/// * `old` - The Dictionary before the change.
+/// * `new` - The Dictionary after the change.
+/// * `paths` - The list to add the changed paths to.
+fn collect_changed_paths(
+    prefix: &str,
+    old: &Dictionary,
+    new: &Dictionary,
+    paths: &mut Vec<String>,
+) {
+    for (key, new_value) in new {
+        let key_path = path::join(prefix, &key.to_string());
+
+        if !old.contains(&key) {
+            paths.push(key_path);
+            continue;
+        }
+
+        let old_value = old.get(&key);
+        match (old_value.try_to_dictionary(), new_value.try_to_dictionary()) {
+            (Some(old_child), Some(new_child)) => {
+                collect_changed_paths(&key_path, &old_child, &new_child, paths)
+            }
+            _ => match (
+                math_type::components(&old_value),
+                math_type::components(&new_value),
+            ) {
+                (Some(old_components), Some(new_components))
+                    if old_value.get_type() == new_value.get_type() =>
+                {
+                    for ((name, old), (_, new)) in old_components.iter().zip(&new_components) {
+                        if old != new {
+                            paths.push(path::join(&key_path, name));
+                        }
+                    }
+                }
+                _ => {
+                    if old_value != new_value {
+                        paths.push(key_path);
+                    }
+                }
+            },
+        }
+    }
+
+    for (key, _) in old {
+        if !new.contains(&key) {
+            paths.push(path::join(prefix, &key.to_string()));
+        }
+    }
+}
+
+/// Returns the keys of a Dictionary that changed between two states. Values
+/// that are the same Dictionary or Array are skipped without comparing what's
+/// inside of them, and Dictionaries in both states are compared key by key so
+/// only what changed inside of them is kept.
+///
+/// # Arguments
+///
+/// * `old` - The Dictionary before the change.
+/// * `new` - The Dictionary after the change.
+pub fn delta(old: &Dictionary, new: &Dictionary) -> Delta {
+    let mut delta = vec![];
+    for (key, new_value) in new.iter() {
+        if !old.contains(&key) {
+            delta.push((key, Change::Set(new_value)));
+            continue;
+        }
+
+        let old_value = old.get(&key);
+        if Equality::Reference.equals(&old_value, &new_value) {
+            continue;
+        }
+
+        match (old_value.try_to_dictionary(), new_value.try_to_dictionary()) {
+            (Some(old_child), Some(new_child)) => {
+                let nested = self::delta(&old_child, &new_child);
+                if !nested.is_empty() {
+                    delta.push((key, Change::Nested(nested)));
+                }
+            }
+            _ if Equality::Deep.equals(&old_value, &new_value) => {}
+            _ => delta.push((key, Change::Set(new_value))),
+        }
+    }
+
+    for (key, _) in old.iter() {
+        if !new.contains(&key) {
+            delta.push((key, Change::Remove));
+        }
+    }
+
+    delta
+}
+
+/// Returns a copy of a Dictionary with the changes of a delta made to it.
+/// Only the Dictionaries that changed are copied.
+///
+/// # Arguments
+///
+/// * `dictionary` - The Dictionary before the change.
+/// * `delta` - The change.
+pub fn apply(dictionary: &Dictionary, delta: &[(Variant, Change)]) -> Dictionary<Unique> {
+    let copy = dictionary.duplicate();
+    for (key, change) in delta {
+        match change {
+            Change::Set(value) => copy.insert(key, value),
+            Change::Remove => copy.erase(key),
+            Change::Nested(nested) => {
+                let child = dictionary.get(key).try_to_dictionary().unwrap_or_default();
+                copy.insert(key, apply(&child, nested).owned_to_variant());
+            }
+        }
+    }
+
+    copy
+}
+
+/// Returns a delta compressed so it takes up as little room as it can. The
+/// delta is written as a Dictionary first, with an Array holding the new
+/// value of each key that was set, an empty Array for each key that was
+/// removed, and a Dictionary for each Dictionary that changed.
+///
+/// # Arguments
+///
+/// * `delta` - The delta.
+pub fn compress(delta: &[(Variant, Change)]) -> Compressed {
+    Compressed::new(&to_dictionary(delta).owned_to_variant())
+}
+
+/// Returns a delta compressed by `compress` as it was before.
+///
+/// # Arguments
+///
+/// * `compressed` - The compressed delta.
+pub fn decompress(compressed: &Compressed) -> Delta {
+    from_dictionary(&compressed.value().try_to_dictionary().unwrap_or_default())
+}
+
+/// Returns a delta written as a Dictionary the way `compress` describes.
+///
+/// # Arguments
+///
+/// * `delta` - The delta.
+fn to_dictionary(delta: &[(Variant, Change)]) -> Dictionary<Unique> {
+    let dictionary = Dictionary::new();
+    for (key, change) in delta {
+        match change {
+            Change::Set(value) => {
+                let set = VariantArray::new();
+                set.push(value);
+                dictionary.insert(key, set.owned_to_variant());
+            }
+            Change::Remove => dictionary.insert(key, VariantArray::new().owned_to_variant()),
+            Change::Nested(nested) => {
+                dictionary.insert(key, to_dictionary(nested).owned_to_variant())
+            }
+        }
+    }
+
+    dictionary
+}
+
+/// Returns a delta written as a Dictionary by `to_dictionary` as it was
+/// before.
+///
+/// # Arguments
+///
+/// * `dictionary` - The delta written as a Dictionary.
+fn from_dictionary(dictionary: &Dictionary) -> Delta {
+    dictionary
+        .iter()
+        .map(|(key, change)| {
+            let change = match (change.try_to_dictionary(), change.try_to_array()) {
+                (Some(nested), _) => Change::Nested(from_dictionary(&nested)),
+                (None, Some(set)) if !set.is_empty() => Change::Set(set.get(0)),
+                _ => Change::Remove,
+            };
+
+            (key, change)
+        })
+        .collect()
+}
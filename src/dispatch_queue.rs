@@ -0,0 +1,143 @@
+use gdnative::prelude::Variant;
+
+/// The lane an action queued with `dispatch_deferred` waits in. Lanes are
+/// dispatched in order, so critical actions jump ahead of cosmetic ones.
+#[derive(Clone, Copy)]
+pub enum Lane {
+    /// Dispatched first, and always dispatched even when the budget is used
+    /// up, like critical gameplay actions.
+    Immediate = 0,
+    /// Dispatched after the immediate actions, which is the default.
+    Normal = 1,
+    /// Dispatched last, like cosmetic and analytics actions.
+    Idle = 2,
+}
+
+impl Lane {
+    /// Returns the lane with a name, which is `immediate`, `normal`, or `idle`.
+    /// An empty name is the normal lane.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the lane.
+    pub fn from_name(name: &str) -> Result<Self, String> {
+        match name {
+            "immediate" => Ok(Lane::Immediate),
+            "normal" | "" => Ok(Lane::Normal),
+            "idle" => Ok(Lane::Idle),
+            _ => Err(format!(
+                "The lane has to be immediate, normal, or idle, got {}",
+                name
+            )),
+        }
+    }
+}
+
+/// The actions queued with `dispatch_deferred`, with the correlation id of the
+/// dispatch that queued each one, in their lanes.
+pub struct DispatchQueue<T = Variant> {
+    /// The queued actions of each lane, in the order they were queued.
+    lanes: [Vec<(T, Option<u64>)>; 3],
+}
+
+impl<T> Default for DispatchQueue<T> {
+    fn default() -> Self {
+        DispatchQueue {
+            lanes: [vec![], vec![], vec![]],
+        }
+    }
+}
+
+impl<T> DispatchQueue<T> {
+    /// Queues an action, returning whether the queue was empty before so a
+    /// flush has to be scheduled. Actions queued by a dry run, like `preview`
+    /// or `run_scenario`, are dropped instead, since the queue is flushed once
+    /// the dry run is over and they would change the live state.
+    ///
+    /// # Arguments
+    ///
+    /// * `action` - The action.
+    /// * `correlation` - The correlation id of the dispatch that queued it.
+    /// * `lane` - The lane to queue it in.
+    /// * `dry_run` - Whether the action is queued by a dry run.
+    pub fn push(&mut self, action: T, correlation: Option<u64>, lane: Lane, dry_run: bool) -> bool {
+        if dry_run {
+            return false;
+        }
+
+        let was_empty = self.is_empty();
+        self.lanes[lane as usize].push((action, correlation));
+
+        was_empty
+    }
+
+    /// Returns whether nothing is queued.
+    pub fn is_empty(&self) -> bool {
+        self.lanes.iter().all(Vec::is_empty)
+    }
+
+    /// Returns the number of queued actions.
+    pub fn len(&self) -> usize {
+        self.lanes.iter().map(Vec::len).sum()
+    }
+
+    /// Removes every queued action.
+    pub fn clear(&mut self) {
+        self.lanes.iter_mut().for_each(Vec::clear);
+    }
+
+    /// Takes the actions to dispatch now, which are every immediate action and
+    /// then the normal and idle actions up to the budget. The actions over the
+    /// budget are left in the queue.
+    ///
+    /// # Arguments
+    ///
+    /// * `budget` - The most normal and idle actions to take, or `None` to
+    /// take all of them.
+    pub fn take(&mut self, budget: Option<usize>) -> Vec<(T, Option<u64>)> {
+        let mut taken = std::mem::take(&mut self.lanes[Lane::Immediate as usize]);
+
+        let mut left = budget.unwrap_or(usize::MAX);
+        for lane in &mut self.lanes[Lane::Normal as usize..] {
+            let count = lane.len().min(left);
+            taken.extend(lane.drain(..count));
+            left -= count;
+        }
+
+        taken
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DispatchQueue, Lane};
+
+    #[test]
+    fn take_dispatches_the_lanes_in_order_within_the_budget() {
+        let mut queue = DispatchQueue::default();
+        assert!(queue.push("track", None, Lane::Idle, false));
+        assert!(!queue.push("score", Some(1), Lane::Normal, false));
+        assert!(!queue.push("damage", None, Lane::Immediate, false));
+
+        assert_eq!(
+            queue.take(Some(1)),
+            vec![("damage", None), ("score", Some(1))]
+        );
+        assert_eq!(queue.take(None), vec![("track", None)]);
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn actions_queued_by_a_dry_run_are_dropped() {
+        let mut queue = DispatchQueue::default();
+
+        // Like an effect of an action passed to `preview` deferring a
+        // dispatch, which must not reach the live state on the next frame.
+        assert!(!queue.push("score", None, Lane::Normal, true));
+        assert!(queue.is_empty());
+        assert!(queue.take(None).is_empty());
+
+        assert!(queue.push("score", None, Lane::Normal, false));
+        assert_eq!(queue.len(), 1);
+    }
+}
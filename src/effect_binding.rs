@@ -0,0 +1,59 @@
+use gdnative::prelude::{GodotString, Node, Ref, Shared, Variant};
+
+use crate::action::action_type;
+
+/// A method of a node bound to an action type with `bind_effect`, which is
+/// called with the action whenever one of the type is dispatched, for example
+/// to start particles or other visual effects.
+#[derive(Clone)]
+pub struct EffectBinding {
+    /// The type of the actions the method is called for.
+    action: Variant,
+    /// The node with the method.
+    node: Ref<Node, Shared>,
+    /// The name of the method.
+    method: GodotString,
+}
+
+impl EffectBinding {
+    /// Creates a binding.
+    ///
+    /// # Arguments
+    ///
+    /// * `action` - The type of the actions the method is called for.
+    /// * `node` - The node with the method.
+    /// * `method` - The name of the method.
+    pub fn new(action: Variant, node: Ref<Node, Shared>, method: GodotString) -> Self {
+        EffectBinding {
+            action,
+            node,
+            method,
+        }
+    }
+
+    /// Returns the type of the actions the method is called for.
+    pub fn action_type(&self) -> &Variant {
+        &self.action
+    }
+
+    /// Returns whether the method is called for an action.
+    ///
+    /// # Arguments
+    ///
+    /// * `action` - The action that was dispatched.
+    pub fn matches(&self, action: &Variant) -> bool {
+        action_type(action) == self.action
+    }
+
+    /// Calls the method with an action.
+    ///
+    /// # Arguments
+    ///
+    /// * `action` - The action that was dispatched.
+    pub fn call(&self, action: &Variant) {
+        // The node might have been freed since it was bound.
+        if let Some(node) = unsafe { self.node.assume_safe_if_sane() } {
+            unsafe { node.call(self.method.clone(), &[action.clone()]) };
+        }
+    }
+}
@@ -0,0 +1,154 @@
+use std::collections::BTreeMap;
+
+use gdnative::api::FuncRef;
+use gdnative::prelude::{Ref, Shared, Variant, VariantArray};
+
+use crate::action::action_type;
+
+/// The effects run one after the other for every action of a type, added
+/// with `on_action`.
+struct Pipeline {
+    /// The id of the pipeline, which can be passed to `remove_on_action`.
+    id: i64,
+    /// The type of the actions the effects are run for.
+    action_type: Variant,
+    /// The effects, in the order they're run.
+    effects: Vec<Ref<FuncRef, Shared>>,
+}
+
+/// A pipeline that is being run for an action.
+struct Run {
+    /// The action the effects are run for.
+    action: Variant,
+    /// The effects, in the order they're run.
+    effects: Vec<Ref<FuncRef, Shared>>,
+    /// The index of the next effect to run.
+    next: usize,
+}
+
+/// The pipelines added with `on_action` and the ones being run, which can
+/// take more than one frame when an effect yields.
+#[derive(Default)]
+pub struct Pipelines {
+    /// The pipelines, in the order they were added.
+    pipelines: Vec<Pipeline>,
+    /// The id the next pipeline gets.
+    next_id: i64,
+    /// The pipelines being run, by the id of the run.
+    runs: BTreeMap<i64, Run>,
+    /// The id the next run gets.
+    next_run: i64,
+}
+
+impl Pipelines {
+    /// Adds a pipeline and returns its id, or an error if one of the effects
+    /// isn't a FuncRef.
+    ///
+    /// # Arguments
+    ///
+    /// * `action_type` - The type of the actions the effects are run for.
+    /// * `effects` - The effects, in the order they're run.
+    pub fn add(&mut self, action_type: Variant, effects: &VariantArray) -> Result<i64, String> {
+        let effects = effects
+            .iter()
+            .enumerate()
+            .map(|(index, effect)| {
+                effect.try_to_object::<FuncRef>().ok_or_else(|| {
+                    format!(
+                        "The effect at {} has to be a FuncRef, got {}",
+                        index,
+                        effect.to_string()
+                    )
+                })
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+
+        self.next_id += 1;
+        self.pipelines.push(Pipeline {
+            id: self.next_id,
+            action_type,
+            effects,
+        });
+
+        Ok(self.next_id)
+    }
+
+    /// Removes a pipeline, and returns whether there was one with the id. The
+    /// runs it already started are finished.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The id of the pipeline.
+    pub fn remove(&mut self, id: i64) -> bool {
+        let count = self.pipelines.len();
+        self.pipelines.retain(|pipeline| pipeline.id != id);
+
+        self.pipelines.len() != count
+    }
+
+    /// Starts running the pipelines of the type of an action, and returns the
+    /// ids of the runs.
+    ///
+    /// # Arguments
+    ///
+    /// * `action` - The action that was dispatched.
+    pub fn start(&mut self, action: &Variant) -> Vec<i64> {
+        if self.pipelines.is_empty() {
+            return vec![];
+        }
+
+        let dispatched = action_type(action);
+        let mut started = vec![];
+        for pipeline in &self.pipelines {
+            if pipeline.action_type != dispatched || pipeline.effects.is_empty() {
+                continue;
+            }
+
+            self.next_run += 1;
+            self.runs.insert(
+                self.next_run,
+                Run {
+                    action: action.clone(),
+                    effects: pipeline.effects.clone(),
+                    next: 0,
+                },
+            );
+            started.push(self.next_run);
+        }
+
+        started
+    }
+
+    /// Returns the next effect of a run along with its index and the action
+    /// it's run for, and forgets the run once it has no effects left. Returns
+    /// `None` if the run is done.
+    ///
+    /// # Arguments
+    ///
+    /// * `run` - The id of the run.
+    pub fn next_effect(&mut self, run: i64) -> Option<(Ref<FuncRef, Shared>, usize, Variant)> {
+        let current = self.runs.get_mut(&run)?;
+        let index = current.next;
+        let effect = current.effects[index].clone();
+        let action = current.action.clone();
+
+        current.next += 1;
+        if current.next == current.effects.len() {
+            self.runs.remove(&run);
+        }
+
+        Some((effect, index, action))
+    }
+
+    /// Returns the types of the actions the pipelines are run for, in the
+    /// order the pipelines were added.
+    pub fn action_types(&self) -> impl Iterator<Item = &Variant> {
+        self.pipelines.iter().map(|pipeline| &pipeline.action_type)
+    }
+
+    /// Drops every pipeline and the runs that haven't finished.
+    pub fn clear(&mut self) {
+        self.pipelines.clear();
+        self.runs.clear();
+    }
+}
@@ -0,0 +1,157 @@
+use gdnative::api::FuncRef;
+use gdnative::prelude::{Dictionary, Ref, Shared, Variant, VariantArray, VariantType};
+
+/// How two values are compared to decide whether something changed.
+#[derive(Clone)]
+pub enum Equality {
+    /// Dictionaries and Arrays are equal only if they are the same Dictionary
+    /// or Array, and anything else is compared by value. This is the cheapest
+    /// comparison.
+    Reference,
+    /// Dictionaries and Arrays are equal if they have the same keys or length
+    /// and each of their values is equal by reference.
+    Shallow,
+    /// Dictionaries and Arrays are equal if all of their values are equal,
+    /// however deeply they are nested.
+    Deep,
+    /// A function that is passed the old and the new value and returns
+    /// whether they are equal.
+    Custom(Ref<FuncRef, Shared>),
+}
+
+impl Equality {
+    /// Returns the equality described by a value, which can be `null` for the
+    /// default of `reference`, the name of one of the built-in equalities, or
+    /// a FuncRef to compare with.
+    ///
+    /// # Arguments
+    ///
+    /// * `equality` - The value describing the equality.
+    pub fn from_variant(equality: &Variant) -> Result<Self, String> {
+        match equality.get_type() {
+            VariantType::Nil => Ok(Equality::Reference),
+            VariantType::GodotString => match equality.to_string().as_str() {
+                "reference" => Ok(Equality::Reference),
+                "shallow" => Ok(Equality::Shallow),
+                "deep" => Ok(Equality::Deep),
+                other => Err(format!(
+                    "The equality has to be reference, shallow, deep, or a FuncRef, got {}",
+                    other
+                )),
+            },
+            _ => equality
+                .try_to_object::<FuncRef>()
+                .map(Equality::Custom)
+                .ok_or_else(|| {
+                    format!(
+                        "The equality has to be reference, shallow, deep, or a FuncRef, got {}",
+                        equality.to_string()
+                    )
+                }),
+        }
+    }
+
+    /// Returns whether two values are equal.
+    ///
+    /// # Arguments
+    ///
+    /// * `old` - The old value.
+    /// * `new` - The new value.
+    pub fn equals(&self, old: &Variant, new: &Variant) -> bool {
+        match self {
+            Equality::Reference => same(old, new),
+            Equality::Shallow => match (old.get_type(), new.get_type()) {
+                (VariantType::Dictionary, VariantType::Dictionary) => {
+                    dictionaries_equal(&old.to_dictionary(), &new.to_dictionary(), same)
+                }
+                (VariantType::VariantArray, VariantType::VariantArray) => {
+                    arrays_equal(&old.to_array(), &new.to_array(), same)
+                }
+                _ => same(old, new),
+            },
+            Equality::Deep => deep_equal(old, new),
+            Equality::Custom(comparator) => unsafe { comparator.assume_safe() }
+                .call_func(&[old.clone(), new.clone()])
+                .to_bool(),
+        }
+    }
+}
+
+/// Returns whether two values are the same Dictionary or Array, or are equal
+/// if they're anything else.
+///
+/// # Arguments
+///
+/// * `old` - The old value.
+/// * `new` - The new value.
+fn same(old: &Variant, new: &Variant) -> bool {
+    // A Dictionary or Array is a pointer to its contents, so two of them are
+    // the same if they point to the same contents.
+    match (old.get_type(), new.get_type()) {
+        (VariantType::Dictionary, VariantType::Dictionary) => unsafe {
+            *(old.to_dictionary().sys() as *const usize)
+                == *(new.to_dictionary().sys() as *const usize)
+        },
+        (VariantType::VariantArray, VariantType::VariantArray) => unsafe {
+            *(old.to_array().sys() as *const usize) == *(new.to_array().sys() as *const usize)
+        },
+        _ => old == new,
+    }
+}
+
+/// Returns whether two values are equal, comparing the values inside of
+/// Dictionaries and Arrays however deeply they are nested.
+///
+/// # Arguments
+///
+/// * `old` - The old value.
+/// * `new` - The new value.
+fn deep_equal(old: &Variant, new: &Variant) -> bool {
+    match (old.get_type(), new.get_type()) {
+        (VariantType::Dictionary, VariantType::Dictionary) => {
+            dictionaries_equal(&old.to_dictionary(), &new.to_dictionary(), deep_equal)
+        }
+        (VariantType::VariantArray, VariantType::VariantArray) => {
+            arrays_equal(&old.to_array(), &new.to_array(), deep_equal)
+        }
+        _ => old == new,
+    }
+}
+
+/// Returns whether two Dictionaries have the same keys with values that are
+/// equal.
+///
+/// # Arguments
+///
+/// * `old` - The old Dictionary.
+/// * `new` - The new Dictionary.
+/// * `equal` - How the values are compared.
+fn dictionaries_equal(
+    old: &Dictionary,
+    new: &Dictionary,
+    equal: fn(&Variant, &Variant) -> bool,
+) -> bool {
+    old.len() == new.len()
+        && old
+            .iter()
+            .all(|(key, value)| new.contains(&key) && equal(&value, &new.get(&key)))
+}
+
+/// Returns whether two Arrays have the same length and values that are equal.
+///
+/// # Arguments
+///
+/// * `old` - The old Array.
+/// * `new` - The new Array.
+/// * `equal` - How the values are compared.
+fn arrays_equal(
+    old: &VariantArray,
+    new: &VariantArray,
+    equal: fn(&Variant, &Variant) -> bool,
+) -> bool {
+    old.len() == new.len()
+        && old
+            .iter()
+            .zip(new.iter())
+            .all(|(old, new)| equal(&old, &new))
+}
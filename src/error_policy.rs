@@ -0,0 +1,49 @@
+use gdnative::api::FuncRef;
+use gdnative::prelude::{Ref, Shared, Variant};
+
+/// What the store does when a stage of a dispatch fails, like a reducer that
+/// doesn't return a Dictionary or a state that doesn't match the schema, set
+/// with `set_error_policy`.
+#[derive(Clone)]
+pub enum ErrorPolicy {
+    /// The state is put back the way it was before the action and the store
+    /// carries on, which is what release builds usually want.
+    Revert,
+    /// The state is put back, the `fatal_error` signal is emitted, and every
+    /// dispatch after it is dropped until `resume_dispatching` is called, so
+    /// errors can't go unnoticed during development.
+    Halt,
+    /// The state is put back and a FuncRef is called with the stage, the
+    /// error, and the action, so the game can decide what to do.
+    Handler(Ref<FuncRef, Shared>),
+}
+
+impl ErrorPolicy {
+    /// Returns the policy with a name, or `None` if the name is empty, which
+    /// leaves failures to be handled by each stage like before.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the policy.
+    /// * `handler` - The FuncRef of the `handler` policy.
+    pub fn from_name(name: &str, handler: &Variant) -> Result<Option<Self>, String> {
+        match name {
+            "" => Ok(None),
+            "revert" => Ok(Some(ErrorPolicy::Revert)),
+            "halt" => Ok(Some(ErrorPolicy::Halt)),
+            "handler" => handler
+                .try_to_object::<FuncRef>()
+                .map(|handler| Some(ErrorPolicy::Handler(handler)))
+                .ok_or_else(|| {
+                    format!(
+                        "The handler policy needs a FuncRef, got {}",
+                        handler.to_string()
+                    )
+                }),
+            other => Err(format!(
+                "The error policy has to be revert, halt, or handler, got {}",
+                other
+            )),
+        }
+    }
+}
@@ -0,0 +1,263 @@
+use gdnative::api::Marshalls;
+use gdnative::prelude::{Dictionary, Unique, Variant, VariantArray};
+
+use crate::compression::Compressed;
+use crate::path;
+
+/// The key of the state the log starts from in a saved log.
+const INITIAL_STATE_KEY: &str = "initial_state";
+
+/// The key of the actions in a saved log.
+const EVENTS_KEY: &str = "events";
+
+/// The actions that reached the reducer while event sourcing is enabled, along
+/// with the state they were dispatched on top of, so the state can be rebuilt
+/// by running them through the reducer again.
+pub struct EventLog {
+    /// The state before the first action in the log, compressed since it's
+    /// only read when the state is rebuilt or the log is saved.
+    initial_state: Compressed,
+    /// The actions, in the order they reached the reducer.
+    events: Events<Variant>,
+}
+
+impl EventLog {
+    /// Creates an empty log that starts from a state.
+    ///
+    /// # Arguments
+    ///
+    /// * `initial_state` - The state before the first action.
+    pub fn new(initial_state: &Dictionary) -> Self {
+        EventLog {
+            initial_state: Compressed::new(&Variant::from_dictionary(initial_state)),
+            events: Events::default(),
+        }
+    }
+
+    /// Reads a log saved with `to_variant`, returning an error if the value
+    /// isn't one.
+    ///
+    /// # Arguments
+    ///
+    /// * `saved` - The saved log.
+    pub fn from_variant(saved: &Variant) -> Result<Self, String> {
+        let saved = saved
+            .try_to_dictionary()
+            .ok_or_else(|| format!("Expected an event log, got {}", saved.to_string()))?;
+
+        let initial_state = path::get(&saved, INITIAL_STATE_KEY)
+            .and_then(|state| state.try_to_dictionary())
+            .ok_or_else(|| "The event log is missing its initial state".to_string())?;
+        let events = path::get(&saved, EVENTS_KEY)
+            .and_then(|events| events.try_to_array())
+            .ok_or_else(|| "The event log is missing its events".to_string())?;
+
+        Ok(EventLog {
+            initial_state: Compressed::new(&Variant::from_dictionary(&initial_state)),
+            events: Events {
+                sizes: events.iter().map(|event| encoded_size(&event)).collect(),
+                events: events.iter().collect(),
+            },
+        })
+    }
+
+    /// Records an action that reached the reducer.
+    ///
+    /// # Arguments
+    ///
+    /// * `action` - The action.
+    pub fn record(&mut self, action: &Variant) {
+        self.events.push(action.clone(), encoded_size(action));
+    }
+
+    /// Removes the last action from the log and returns it, or `None` if the
+    /// log is empty.
+    pub fn pop(&mut self) -> Option<Variant> {
+        self.events.pop()
+    }
+
+    /// Returns the number of bytes the actions in the log take up when they're
+    /// encoded, which is roughly how much memory they use.
+    pub fn size(&self) -> usize {
+        self.events.size()
+    }
+
+    /// Removes the oldest actions from the log until the rest of them take up
+    /// at most a number of bytes, and returns the ones that were removed. The
+    /// state the log starts from has to be moved forward past them with
+    /// `set_initial_state`.
+    ///
+    /// # Arguments
+    ///
+    /// * `limit` - The number of bytes the actions can take up.
+    pub fn trim_to(&mut self, limit: usize) -> Vec<Variant> {
+        self.events.trim_to(limit)
+    }
+
+    /// Replaces the state before the first action in the log.
+    ///
+    /// # Arguments
+    ///
+    /// * `initial_state` - The new state before the first action.
+    pub fn set_initial_state(&mut self, initial_state: &Dictionary) {
+        self.initial_state = Compressed::new(&Variant::from_dictionary(initial_state));
+    }
+
+    /// Returns the state before the first action in the log, rebuilt from
+    /// its compressed copy.
+    pub fn initial_state(&self) -> Dictionary {
+        self.initial_state
+            .value()
+            .try_to_dictionary()
+            .unwrap_or_default()
+    }
+
+    /// Returns the actions in the log, in the order they reached the reducer.
+    pub fn events(&self) -> &[Variant] {
+        &self.events.events
+    }
+
+    /// Returns the log as a Dictionary with the initial state under
+    /// `initial_state` and the actions under `events`, which is how it's saved.
+    pub fn to_variant(&self) -> Dictionary<Unique> {
+        let saved = Dictionary::new();
+        saved.insert(INITIAL_STATE_KEY, self.initial_state());
+        saved.insert(
+            EVENTS_KEY,
+            self.events().iter().collect::<VariantArray<Unique>>(),
+        );
+
+        saved
+    }
+}
+
+/// Returns the number of bytes a value takes up when it's encoded.
+///
+/// # Arguments
+///
+/// * `value` - The value.
+pub fn encoded_size(value: &Variant) -> usize {
+    // Every 4 characters of base64 encode 3 bytes.
+    Marshalls::godot_singleton()
+        .variant_to_base64(value.clone(), false)
+        .len()
+        * 3
+        / 4
+}
+
+/// The events in a log, in the order they happened, along with how much
+/// room each of them takes up.
+struct Events<T> {
+    /// The events.
+    events: Vec<T>,
+    /// The number of bytes each event takes up, in the same order as the
+    /// events.
+    sizes: Vec<usize>,
+}
+
+impl<T> Default for Events<T> {
+    fn default() -> Self {
+        Events {
+            events: vec![],
+            sizes: vec![],
+        }
+    }
+}
+
+impl<T> Events<T> {
+    /// Adds an event after the others.
+    ///
+    /// # Arguments
+    ///
+    /// * `event` - The event.
+    /// * `size` - The number of bytes the event takes up.
+    fn push(&mut self, event: T, size: usize) {
+        self.events.push(event);
+        self.sizes.push(size);
+    }
+
+    /// Removes the last event and returns it, or `None` if there are none.
+    fn pop(&mut self) -> Option<T> {
+        self.sizes.pop();
+        self.events.pop()
+    }
+
+    /// Returns the number of bytes the events take up.
+    fn size(&self) -> usize {
+        self.sizes.iter().sum()
+    }
+
+    /// Removes the oldest events until the rest of them take up at most a
+    /// number of bytes, and returns the ones that were removed.
+    ///
+    /// # Arguments
+    ///
+    /// * `limit` - The number of bytes the events can take up.
+    fn trim_to(&mut self, limit: usize) -> Vec<T> {
+        let mut size = self.size();
+        let count = self
+            .sizes
+            .iter()
+            .take_while(|event_size| {
+                let over = size > limit;
+                size -= **event_size;
+                over
+            })
+            .count();
+
+        self.sizes.drain(..count);
+        self.events.drain(..count).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Events;
+
+    /// Creates events from names and sizes.
+    fn events(events: &[(&'static str, usize)]) -> Events<&'static str> {
+        let mut all = Events::default();
+        for (event, size) in events {
+            all.push(*event, *size);
+        }
+
+        all
+    }
+
+    #[test]
+    fn events_are_kept_in_the_order_they_happened() {
+        let events = events(&[("jump", 10), ("land", 20), ("jump", 10)]);
+
+        assert_eq!(events.events, vec!["jump", "land", "jump"]);
+        assert_eq!(events.size(), 40);
+    }
+
+    #[test]
+    fn pop_removes_the_latest_event() {
+        let mut events = events(&[("jump", 10), ("land", 20)]);
+
+        assert_eq!(events.pop(), Some("land"));
+        assert_eq!(events.size(), 10);
+        assert_eq!(events.pop(), Some("jump"));
+        assert_eq!(events.pop(), None);
+        assert_eq!(events.size(), 0);
+    }
+
+    #[test]
+    fn trim_to_removes_the_oldest_events_until_they_fit() {
+        let mut events = events(&[("jump", 10), ("land", 20), ("run", 5), ("stop", 5)]);
+
+        assert_eq!(events.trim_to(15), vec!["jump", "land"]);
+        assert_eq!(events.events, vec!["run", "stop"]);
+        assert_eq!(events.size(), 10);
+    }
+
+    #[test]
+    fn trim_to_keeps_events_that_already_fit() {
+        let mut events = events(&[("jump", 10), ("land", 20)]);
+
+        assert!(events.trim_to(30).is_empty());
+        assert_eq!(events.trim_to(0), vec!["jump", "land"]);
+        assert!(events.events.is_empty());
+    }
+}
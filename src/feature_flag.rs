@@ -0,0 +1,104 @@
+use gdnative::prelude::{Dictionary, Unique, Variant};
+
+use crate::{action, path};
+
+/// The key of the slice of the state that holds the feature flags.
+pub const SLICE: &str = "feature_flags";
+
+/// The type of the action that sets the default value of feature flags.
+const SET_FLAGS: &str = "set_flags";
+
+/// The type of the action that overrides feature flags, for example with
+/// flags loaded from a file or a server.
+const OVERRIDE_FLAGS: &str = "override_flags";
+
+/// The key of the flags in a `set_flags` or `override_flags` action.
+const FLAGS_KEY: &str = "flags";
+
+/// The key of the default values of the flags in the slice.
+const DEFAULTS_KEY: &str = "defaults";
+
+/// The key of the overridden values of the flags in the slice, which win over
+/// the defaults.
+const OVERRIDES_KEY: &str = "overrides";
+
+/// Returns the action that sets the default value of feature flags.
+///
+/// # Arguments
+///
+/// * `flags` - The flags and whether they are enabled.
+pub fn set_flags(flags: &Dictionary) -> Dictionary<Unique> {
+    let action = action::named(SET_FLAGS);
+    action.insert(FLAGS_KEY, flags);
+
+    action
+}
+
+/// Returns the action that overrides feature flags.
+///
+/// # Arguments
+///
+/// * `flags` - The flags and whether they are enabled.
+pub fn override_flags(flags: &Dictionary) -> Dictionary<Unique> {
+    let action = action::named(OVERRIDE_FLAGS);
+    action.insert(FLAGS_KEY, flags);
+
+    action
+}
+
+/// Returns the slice after an action, or `None` if the action isn't a feature
+/// flag action. The flags in the action are merged into the ones the slice
+/// already has.
+///
+/// # Arguments
+///
+/// * `current` - The slice before the action.
+/// * `action` - The action that was dispatched.
+pub fn reduce(current: &Dictionary, action: &Variant) -> Option<Dictionary<Unique>> {
+    let key = if action::is_type(action, SET_FLAGS) {
+        DEFAULTS_KEY
+    } else if action::is_type(action, OVERRIDE_FLAGS) {
+        OVERRIDES_KEY
+    } else {
+        return None;
+    };
+
+    let flags = action::field(action, FLAGS_KEY)?.try_to_dictionary()?;
+    let merged = flags_under(current, key).duplicate();
+    for (flag, enabled) in flags.iter() {
+        merged.insert(flag, enabled.to_bool());
+    }
+
+    let next = current.duplicate();
+    next.insert(key, merged);
+
+    Some(next)
+}
+
+/// Returns whether a feature flag is enabled, which is its override if it has
+/// one and otherwise its default. Flags that were never set are disabled.
+///
+/// # Arguments
+///
+/// * `slice` - The slice.
+/// * `flag` - The name of the flag.
+pub fn is_enabled(slice: &Dictionary, flag: &str) -> bool {
+    [OVERRIDES_KEY, DEFAULTS_KEY]
+        .iter()
+        .map(|key| flags_under(slice, key))
+        .find(|flags| flags.contains(flag))
+        .is_some_and(|flags| flags.get(flag).to_bool())
+}
+
+/// Returns the flags under a key of the slice, or an empty Dictionary if the
+/// slice doesn't have any.
+///
+/// # Arguments
+///
+/// * `slice` - The slice.
+/// * `key` - The key of the flags.
+fn flags_under(slice: &Dictionary, key: &str) -> Dictionary {
+    path::get(slice, key)
+        .and_then(|flags| flags.try_to_dictionary())
+        .unwrap_or_else(Dictionary::new_shared)
+}
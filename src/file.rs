@@ -0,0 +1,305 @@
+use gdnative::api::{Directory, File, Resource, ResourceLoader, JSON};
+use gdnative::prelude::{ByteArray, Dictionary, GodotError, Ref, Unique, Variant, VariantType};
+
+use crate::math_type;
+
+/// What a file is encrypted with, using Godot's encrypted files, which are
+/// encrypted with AES-256 and keep an MD5 of their contents so a file that
+/// was changed or corrupted can't be opened.
+#[derive(Clone)]
+pub enum Encryption {
+    /// A password the key is made from.
+    Password(String),
+    /// A 32 byte key.
+    Key(Vec<u8>),
+}
+
+/// Returns whether a file exists.
+///
+/// # Arguments
+///
+/// * `path` - The path to the file, for example `user://state.json`.
+pub fn exists(path: &str) -> bool {
+    File::new().file_exists(path)
+}
+
+/// Moves a file to another path, replacing the file at that path if there is
+/// one.
+///
+/// # Arguments
+///
+/// * `from` - The path to the file, for example `user://save.dat`.
+/// * `to` - The path to move the file to.
+pub fn rename(from: &str, to: &str) -> Result<(), String> {
+    Directory::new()
+        .rename(from, to)
+        .map_err(|err| format!("Unable to move {} to {}: {}", from, to, err))
+}
+
+/// Reads the whole contents of a text file.
+///
+/// # Arguments
+///
+/// * `path` - The path to the file, for example `user://state.json`.
+pub fn read_text(path: &str) -> Result<String, String> {
+    let file = File::new();
+    file.open(path, File::READ)
+        .map_err(|err| format!("Unable to open {} for reading: {}", path, err))?;
+
+    let text = file.get_as_text().to_string();
+    file.close();
+
+    Ok(text)
+}
+
+/// Writes text to a file, replacing anything that was in the file before.
+///
+/// # Arguments
+///
+/// * `path` - The path to the file, for example `user://state.json`.
+/// * `text` - The text to write to the file.
+pub fn write_text(path: &str, text: &str) -> Result<(), String> {
+    let file = File::new();
+    file.open(path, File::WRITE)
+        .map_err(|err| format!("Unable to open {} for writing: {}", path, err))?;
+
+    file.store_string(text);
+    file.close();
+
+    Ok(())
+}
+
+/// Writes a value to a file in Godot's binary format, which keeps the type of
+/// every value in it, replacing anything that was in the file before.
+///
+/// # Arguments
+///
+/// * `path` - The path to the file, for example `user://events.log`.
+/// * `value` - The value to write to the file.
+pub fn write_var(path: &str, value: &Variant) -> Result<(), String> {
+    let file = File::new();
+    file.open(path, File::WRITE)
+        .map_err(|err| format!("Unable to open {} for writing: {}", path, err))?;
+
+    file.store_var(value.clone(), false);
+    file.close();
+
+    Ok(())
+}
+
+/// Reads a value written to a file by `write_var`.
+///
+/// # Arguments
+///
+/// * `path` - The path to the file, for example `user://events.log`.
+pub fn read_var(path: &str) -> Result<Variant, String> {
+    let file = File::new();
+    file.open(path, File::READ)
+        .map_err(|err| format!("Unable to open {} for reading: {}", path, err))?;
+
+    let value = file.get_var(false);
+    file.close();
+
+    Ok(value)
+}
+
+/// Writes bytes to a file, replacing anything that was in the file before.
+///
+/// # Arguments
+///
+/// * `path` - The path to the file, for example `user://state.dat`.
+/// * `bytes` - The bytes to write to the file.
+/// * `encryption` - What the file is encrypted with, if it is.
+pub fn write_bytes(
+    path: &str,
+    bytes: ByteArray,
+    encryption: Option<&Encryption>,
+) -> Result<(), String> {
+    let file = open(path, File::WRITE, encryption)?;
+
+    file.store_buffer(bytes);
+    file.close();
+
+    Ok(())
+}
+
+/// Reads every byte of a file.
+///
+/// # Arguments
+///
+/// * `path` - The path to the file, for example `user://state.dat`.
+/// * `encryption` - What the file is encrypted with, if it is.
+pub fn read_bytes(path: &str, encryption: Option<&Encryption>) -> Result<ByteArray, String> {
+    let file = open(path, File::READ, encryption)?;
+
+    let bytes = file.get_buffer(file.get_len());
+    file.close();
+
+    Ok(bytes)
+}
+
+/// Writes values to a file one after another in Godot's binary format,
+/// replacing anything that was in the file before, so the first ones can be
+/// read back with `read_vars` without reading the rest.
+///
+/// # Arguments
+///
+/// * `path` - The path to the file, for example `user://save.dat`.
+/// * `values` - The values to write to the file, in order.
+/// * `encryption` - What the file is encrypted with, if it is.
+pub fn write_vars(
+    path: &str,
+    values: &[Variant],
+    encryption: Option<&Encryption>,
+) -> Result<(), String> {
+    let file = open(path, File::WRITE, encryption)?;
+
+    for value in values {
+        file.store_var(value.clone(), false);
+    }
+    file.close();
+
+    Ok(())
+}
+
+/// Reads the first values written to a file by `write_vars`, returning an
+/// error if the file has fewer values.
+///
+/// # Arguments
+///
+/// * `path` - The path to the file, for example `user://save.dat`.
+/// * `count` - The number of values to read.
+/// * `encryption` - What the file is encrypted with, if it is.
+pub fn read_vars(
+    path: &str,
+    count: usize,
+    encryption: Option<&Encryption>,
+) -> Result<Vec<Variant>, String> {
+    let file = open(path, File::READ, encryption)?;
+
+    let mut values = vec![];
+    while values.len() < count && file.get_position() < file.get_len() {
+        values.push(file.get_var(false));
+    }
+    file.close();
+
+    if values.len() < count {
+        return Err(format!("{} ends before all of its values", path));
+    }
+
+    Ok(values)
+}
+
+/// Opens a file for reading or writing, encrypted if there's an encryption.
+/// Returns an error if an encrypted file doesn't match the MD5 of its
+/// contents, which means it was changed, got corrupted, or was encrypted
+/// with something else.
+///
+/// # Arguments
+///
+/// * `path` - The path to the file, for example `user://save.dat`.
+/// * `mode` - Whether the file is read or written.
+/// * `encryption` - What the file is encrypted with, if it is.
+fn open(
+    path: &str,
+    mode: i64,
+    encryption: Option<&Encryption>,
+) -> Result<Ref<File, Unique>, String> {
+    let file = File::new();
+    let opened = match encryption {
+        None => file.open(path, mode),
+        Some(Encryption::Password(password)) => {
+            file.open_encrypted_with_pass(path, mode, password.as_str())
+        }
+        Some(Encryption::Key(key)) => file.open_encrypted(path, mode, ByteArray::from_slice(key)),
+    };
+
+    match opened {
+        Ok(()) => Ok(file),
+        Err(GodotError::FileCorrupt) | Err(GodotError::FileUnrecognized)
+            if encryption.is_some() =>
+        {
+            Err(format!(
+                "{} was changed, got corrupted, or isn't encrypted with the same key",
+                path
+            ))
+        }
+        Err(err) if mode == File::WRITE => {
+            Err(format!("Unable to open {} for writing: {}", path, err))
+        }
+        Err(err) => Err(format!("Unable to open {} for reading: {}", path, err)),
+    }
+}
+
+/// Reads a JSON file and returns the value in it.
+///
+/// # Arguments
+///
+/// * `path` - The path to the file, for example `user://stats.json`.
+pub fn read_json(path: &str) -> Result<Variant, String> {
+    let text = read_text(path)?;
+
+    parse_json(&text).map_err(|message| format!("Unable to parse {} as JSON: {}", path, message))
+}
+
+/// Parses JSON text and returns the value in it, with the Dictionaries that
+/// stand in for a `Vector2`, `Vector3`, or `Color` turned back into them.
+///
+/// # Arguments
+///
+/// * `text` - The JSON text.
+pub fn parse_json(text: &str) -> Result<Variant, String> {
+    let parsed = JSON::godot_singleton()
+        .parse(text)
+        .ok_or_else(|| "The JSON parser couldn't be created".to_string())?;
+    let parsed = unsafe { parsed.assume_safe() };
+
+    match parsed.error() {
+        Ok(()) => Ok(math_type::revive(&parsed.result())),
+        Err(_) => Err(format!(
+            "{} on line {}",
+            parsed.error_string(),
+            parsed.error_line()
+        )),
+    }
+}
+
+/// Reads a Dictionary of definitions, like quests or dialogue, from wherever
+/// they're kept. The source can be the Dictionary itself, the path of a JSON
+/// file with the Dictionary in it, or a Resource, or the path of one, with the
+/// Dictionary in one of its properties.
+///
+/// # Arguments
+///
+/// * `source` - The Dictionary, the path of a JSON file or Resource, or a
+/// Resource.
+/// * `property` - The property of the Resource that has the Dictionary.
+pub fn read_definitions(source: &Variant, property: &str) -> Result<Dictionary, String> {
+    let definitions = match source.get_type() {
+        VariantType::Dictionary => return Ok(source.to_dictionary()),
+        VariantType::GodotString if source.to_string().ends_with(".json") => {
+            read_json(&source.to_string())?
+        }
+        VariantType::GodotString => {
+            let path = source.to_string();
+            let resource = ResourceLoader::godot_singleton()
+                .load(&path, "", false)
+                .ok_or_else(|| format!("Unable to load the resource {}", path))?;
+
+            unsafe { resource.assume_safe() }.get(property)
+        }
+        VariantType::Object => match source.try_to_object::<Resource>() {
+            Some(resource) => unsafe { resource.assume_safe() }.get(property),
+            None => Variant::new(),
+        },
+        _ => Variant::new(),
+    };
+
+    definitions.try_to_dictionary().ok_or_else(|| {
+        format!(
+            "Expected a Dictionary, the path of a JSON file, or a Resource with a {} property, got {}",
+            property,
+            source.to_string()
+        )
+    })
+}
@@ -0,0 +1,432 @@
+use gdnative::api::{BaseButton, Control, FuncRef, LineEdit, Node};
+use gdnative::prelude::{
+    godot_error, Dictionary, Object, Ref, Shared, TRef, ThreadLocal, Unique, Variant, VariantArray,
+    VariantType,
+};
+
+use crate::{action, path};
+
+/// The key of the slice of the state that holds every form, by name.
+pub const SLICE: &str = "forms";
+
+/// The type of the action that sets the value of a field.
+const SET_FIELD: &str = "set_field";
+
+/// The type of the action that marks a field as touched.
+const TOUCH: &str = "touch";
+
+/// The type of the action that submits a form.
+const SUBMIT: &str = "submit";
+
+/// The type of the action that puts a form back to its initial values.
+const RESET: &str = "reset";
+
+/// The key of the name of the form in a form action.
+const FORM_KEY: &str = "form";
+
+/// The key of the name of the field in a form action.
+const FIELD_KEY: &str = "field";
+
+/// The key of the value in a `set_field` action and of the initial value in a
+/// field definition.
+const VALUE_KEY: &str = "value";
+
+/// The key of whether a field has to be filled in, in a field definition.
+const REQUIRED_KEY: &str = "required";
+
+/// The key of the validator of a field, in a field definition.
+const VALIDATOR_KEY: &str = "validator";
+
+/// The error used for a required field that isn't filled in.
+const REQUIRED_ERROR: &str = "This field is required";
+
+/// A field of a form.
+#[derive(Clone)]
+struct Field {
+    /// The name of the field.
+    name: String,
+    /// The value the field starts at and goes back to when the form is reset.
+    initial_value: Variant,
+    /// Whether the field has to be filled in.
+    required: bool,
+    /// The function that checks the value of the field, if there is one.
+    validator: Option<Ref<FuncRef, ThreadLocal>>,
+}
+
+/// A form created with `create_form`. The state of the form lives in the
+/// `forms` slice while the form itself only keeps what's needed to update it.
+#[derive(Clone)]
+pub struct Form {
+    /// The fields of the form, in the order they were defined.
+    fields: Vec<Field>,
+}
+
+impl Form {
+    /// Creates a form from its field definitions. Each field is either defined
+    /// by just its initial value or by a Dictionary with its initial value under
+    /// `value`, whether it has to be filled in under `required`, and a FuncRef
+    /// that checks its value under `validator`.
+    ///
+    /// # Arguments
+    ///
+    /// * `definitions` - The field definitions, by field name.
+    pub fn new(definitions: &Dictionary) -> Self {
+        let fields = definitions
+            .iter()
+            .map(|(name, definition)| {
+                let name = name.to_string();
+
+                match definition.try_to_dictionary() {
+                    Some(definition) => Field {
+                        name,
+                        initial_value: path::get(&definition, VALUE_KEY).unwrap_or_default(),
+                        required: path::get(&definition, REQUIRED_KEY)
+                            .unwrap_or_default()
+                            .to_bool(),
+                        validator: path::get(&definition, VALIDATOR_KEY)
+                            .unwrap_or_default()
+                            .try_to_object::<FuncRef>()
+                            .map(|validator| unsafe { validator.assume_thread_local() }),
+                    },
+                    None => Field {
+                        name,
+                        initial_value: definition,
+                        required: false,
+                        validator: None,
+                    },
+                }
+            })
+            .collect();
+
+        Form { fields }
+    }
+
+    /// Returns whether the form has a field with the name.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the field.
+    pub fn has_field(&self, name: &str) -> bool {
+        self.fields.iter().any(|field| field.name == name)
+    }
+
+    /// Returns the state of the form with every field at its initial value.
+    pub fn initial_slice(&self) -> Dictionary<Unique> {
+        let values = Dictionary::new();
+        for field in &self.fields {
+            values.insert(&field.name, &field.initial_value);
+        }
+        let values = values.into_shared();
+
+        self.slice(&values, &Dictionary::new_shared(), false)
+    }
+
+    /// Returns the state of the form after an action, or `None` if the action
+    /// isn't a form action the form can handle.
+    ///
+    /// # Arguments
+    ///
+    /// * `current` - The state of the form before the action.
+    /// * `action` - The action that was dispatched.
+    pub fn reduce(&self, current: &Dictionary, action: &Variant) -> Option<Dictionary<Unique>> {
+        let values = path::get(current, "values")
+            .unwrap_or_default()
+            .try_to_dictionary()
+            .unwrap_or_else(Dictionary::new_shared);
+        let touched = path::get(current, "touched")
+            .unwrap_or_default()
+            .try_to_dictionary()
+            .unwrap_or_else(Dictionary::new_shared);
+
+        if action::is_type(action, RESET) {
+            return Some(self.initial_slice());
+        }
+
+        if action::is_type(action, SUBMIT) {
+            let touched = Dictionary::new();
+            for field in &self.fields {
+                touched.insert(&field.name, true);
+            }
+
+            return Some(self.slice(&values, &touched.into_shared(), true));
+        }
+
+        let field = action::field(action, FIELD_KEY)?.to_string();
+        if !self.has_field(&field) {
+            return None;
+        }
+
+        if action::is_type(action, SET_FIELD) {
+            let value = action::field(action, VALUE_KEY).unwrap_or_default();
+            let values = values.duplicate();
+            values.insert(&field, value);
+
+            return Some(self.slice(&values.into_shared(), &touched, false));
+        }
+
+        if action::is_type(action, TOUCH) {
+            let touched = touched.duplicate();
+            touched.insert(&field, true);
+
+            return Some(self.slice(&values, &touched.into_shared(), false));
+        }
+
+        None
+    }
+
+    /// Returns the state of the form for its values, which is made up of:
+    ///
+    /// * `values` - The value of each field.
+    /// * `dirty` - Whether each field is different from its initial value.
+    /// * `touched` - Whether each field has been touched.
+    /// * `errors` - The reason each invalid field is invalid.
+    /// * `valid` - Whether every field is valid.
+    /// * `submitted` - Whether the form was submitted while it was valid.
+    ///
+    /// # Arguments
+    ///
+    /// * `values` - The value of each field.
+    /// * `touched` - Whether each field has been touched.
+    /// * `submitting` - Whether the form is being submitted.
+    fn slice(
+        &self,
+        values: &Dictionary,
+        touched: &Dictionary,
+        submitting: bool,
+    ) -> Dictionary<Unique> {
+        let dirty = Dictionary::new();
+        let all_touched = Dictionary::new();
+        for field in &self.fields {
+            dirty.insert(
+                &field.name,
+                path::get(values, &field.name).unwrap_or_default() != field.initial_value,
+            );
+            all_touched.insert(
+                &field.name,
+                path::get(touched, &field.name)
+                    .unwrap_or_default()
+                    .to_bool(),
+            );
+        }
+
+        let errors = self.validate(values);
+        let valid = errors.is_empty();
+
+        let slice = Dictionary::new();
+        slice.insert("values", values);
+        slice.insert("dirty", dirty);
+        slice.insert("touched", all_touched);
+        slice.insert("errors", errors);
+        slice.insert("valid", valid);
+        slice.insert("submitted", submitting && valid);
+
+        slice
+    }
+
+    /// Returns the reason each invalid field is invalid, by field name.
+    ///
+    /// # Arguments
+    ///
+    /// * `values` - The value of each field.
+    fn validate(&self, values: &Dictionary) -> Dictionary<Unique> {
+        let errors = Dictionary::new();
+
+        for field in &self.fields {
+            let value = path::get(values, &field.name).unwrap_or_default();
+
+            if field.required && is_blank(&value) {
+                errors.insert(&field.name, REQUIRED_ERROR);
+                continue;
+            }
+
+            if let Some(validator) = &field.validator {
+                let error = validator.call_func(&[value, Variant::from_dictionary(values)]);
+                if !error.is_nil() && !error.to_string().is_empty() {
+                    errors.insert(&field.name, error);
+                }
+            }
+        }
+
+        errors
+    }
+}
+
+/// A LineEdit or a button that was bound to a field of a form with
+/// `bind_form`.
+#[derive(Clone)]
+pub struct BoundField {
+    /// The name of the form.
+    form: String,
+    /// The name of the field.
+    field: String,
+    /// The LineEdit or button.
+    control: Ref<Control, Shared>,
+}
+
+impl BoundField {
+    /// Updates the control to show the value of its field, unless it already
+    /// does.
+    ///
+    /// # Arguments
+    ///
+    /// * `forms` - The `forms` slice of the state.
+    pub fn sync(&self, forms: &Dictionary) {
+        // The control might have been freed since it was bound.
+        let control = match unsafe { self.control.assume_safe_if_sane() } {
+            Some(control) => control,
+            None => return,
+        };
+
+        let value_path = path::join(&path::join(&self.form, "values"), &self.field);
+        let value = path::get(forms, &value_path).unwrap_or_default();
+
+        if let Some(line_edit) = control.cast::<LineEdit>() {
+            let text = if value.is_nil() {
+                String::new()
+            } else {
+                value.to_string()
+            };
+            if line_edit.text().to_string() != text {
+                line_edit.set_text(text);
+            }
+        } else if let Some(button) = control.cast::<BaseButton>() {
+            if button.is_pressed() != value.to_bool() {
+                button.set_pressed(value.to_bool());
+            }
+        }
+    }
+}
+
+/// Connects every LineEdit and button inside of the container that is named
+/// after a field of the form to the store, so editing them dispatches
+/// `set_field` and leaving them dispatches `touch`. Returns the controls that
+/// were bound.
+///
+/// # Arguments
+///
+/// * `store` - The store to connect the controls to.
+/// * `container` - The node that contains the controls.
+/// * `form_name` - The name of the form.
+/// * `form` - The form.
+pub fn bind(
+    store: TRef<Object>,
+    container: TRef<Node>,
+    form_name: &str,
+    form: &Form,
+) -> Vec<BoundField> {
+    let mut bound = vec![];
+
+    for child in container.get_children().iter() {
+        let child = match child.try_to_object::<Node>() {
+            Some(child) => unsafe { child.assume_safe() },
+            None => continue,
+        };
+
+        let field = child.name().to_string();
+        if form.has_field(&field) {
+            let changed_signal = if child.cast::<LineEdit>().is_some() {
+                Some("text_changed")
+            } else if child.cast::<BaseButton>().is_some() {
+                Some("toggled")
+            } else {
+                None
+            };
+
+            if let (Some(changed_signal), Some(control)) = (changed_signal, child.cast::<Control>())
+            {
+                connect(
+                    control,
+                    changed_signal,
+                    store,
+                    "on_form_field_changed",
+                    form_name,
+                    &field,
+                );
+                connect(
+                    control,
+                    "focus_exited",
+                    store,
+                    "on_form_field_touched",
+                    form_name,
+                    &field,
+                );
+
+                bound.push(BoundField {
+                    form: form_name.to_string(),
+                    field,
+                    control: control.claim(),
+                });
+            }
+        }
+
+        bound.extend(bind(store, child, form_name, form));
+    }
+
+    bound
+}
+
+/// Connects a signal of a control to a method of the store, passing the name
+/// of the form and field along as extra arguments.
+///
+/// # Arguments
+///
+/// * `control` - The control whose signal is connected.
+/// * `signal` - The name of the signal.
+/// * `store` - The store to connect the signal to.
+/// * `method` - The name of the method of the store.
+/// * `form` - The name of the form.
+/// * `field` - The name of the field.
+fn connect(
+    control: TRef<Control>,
+    signal: &str,
+    store: TRef<Object>,
+    method: &str,
+    form: &str,
+    field: &str,
+) {
+    let binds = VariantArray::new();
+    binds.push(form);
+    binds.push(field);
+
+    if let Err(error) = control.connect(signal, store, method, binds.into_shared(), 0) {
+        godot_error!(
+            "Couldn't bind the field {} of the form {}: {:?}",
+            field,
+            form,
+            error
+        );
+    }
+}
+
+/// Returns the name of the form an action is for, or `None` if the action
+/// isn't a form action.
+///
+/// # Arguments
+///
+/// * `action` - The action to get the name of the form from.
+pub fn target(action: &Variant) -> Option<String> {
+    let is_form_action = [SET_FIELD, TOUCH, SUBMIT, RESET]
+        .iter()
+        .any(|name| action::is_type(action, name));
+
+    if is_form_action {
+        action::field(action, FORM_KEY).map(|form| form.to_string())
+    } else {
+        None
+    }
+}
+
+/// Returns whether a field hasn't been filled in, which is when it's `null`,
+/// an empty String, or an unchecked CheckBox.
+///
+/// # Arguments
+///
+/// * `value` - The value of the field.
+fn is_blank(value: &Variant) -> bool {
+    match value.get_type() {
+        VariantType::Nil => true,
+        VariantType::Bool => !value.to_bool(),
+        VariantType::GodotString => value.to_string().trim().is_empty(),
+        _ => false,
+    }
+}
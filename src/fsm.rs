@@ -0,0 +1,314 @@
+use std::collections::BTreeMap;
+
+use gdnative::api::FuncRef;
+use gdnative::prelude::{Dictionary, Ref, ThreadLocal, Unique, Variant, VariantArray};
+
+use crate::{action, path};
+
+/// The key of the slice of the state that holds every state machine, by name.
+pub const SLICE: &str = "fsm";
+
+/// The type of the action that moves a state machine to another state.
+const FSM_TRANSITION: &str = "fsm_transition";
+
+/// The key of the name of the state machine in an `fsm_transition` action.
+const MACHINE_KEY: &str = "machine";
+
+/// The key of the state to move to in an `fsm_transition` action.
+const TO_KEY: &str = "to";
+
+/// The key of the current state of a state machine in the slice.
+const STATE_KEY: &str = "state";
+
+/// The key of the state a state machine was in before the current one in the
+/// slice.
+const PREVIOUS_KEY: &str = "previous";
+
+/// A state machine created with `create_fsm_slice`.
+#[derive(Clone)]
+pub struct Machine {
+    /// The states of the machine, starting with the initial state.
+    states: Vec<String>,
+    /// The states each state can move to, by state.
+    transitions: BTreeMap<String, Vec<String>>,
+    /// The functions that have to allow moving into a state, by state.
+    guards: BTreeMap<String, Vec<Ref<FuncRef, ThreadLocal>>>,
+    /// The functions that are run after moving into a state, by state.
+    on_enter: BTreeMap<String, Vec<Ref<FuncRef, ThreadLocal>>>,
+}
+
+impl Machine {
+    /// Creates a state machine, returning an error if it has no states or a
+    /// transition refers to a state it doesn't have.
+    ///
+    /// # Arguments
+    ///
+    /// * `states` - The states of the machine, starting with the initial
+    /// state.
+    /// * `transitions` - The states each state can move to, by state.
+    pub fn new(states: &VariantArray, transitions: &Dictionary) -> Result<Self, String> {
+        let states = states
+            .iter()
+            .map(|state| state.to_string())
+            .collect::<Vec<_>>();
+
+        let transitions = transitions
+            .iter()
+            .map(|(from, to)| {
+                let from = from.to_string();
+                let to = to
+                    .try_to_array()
+                    .ok_or_else(|| {
+                        format!(
+                            "The transitions from {} have to be an Array of states, got {}",
+                            from,
+                            to.to_string()
+                        )
+                    })?
+                    .iter()
+                    .map(|state| state.to_string())
+                    .collect::<Vec<_>>();
+
+                Ok((from, to))
+            })
+            .collect::<Result<BTreeMap<_, _>, String>>()?;
+
+        Self::from_states(states, transitions)
+    }
+
+    /// Creates a state machine from the names of its states, returning an
+    /// error if it has no states or a transition refers to a state it doesn't
+    /// have.
+    ///
+    /// # Arguments
+    ///
+    /// * `states` - The states of the machine, starting with the initial
+    /// state.
+    /// * `transitions` - The states each state can move to, by state.
+    fn from_states(
+        states: Vec<String>,
+        transitions: BTreeMap<String, Vec<String>>,
+    ) -> Result<Self, String> {
+        if states.is_empty() {
+            return Err("A state machine needs at least one state".to_string());
+        }
+
+        for (from, to) in &transitions {
+            if let Some(unknown) = std::iter::once(from)
+                .chain(to.iter())
+                .find(|state| !states.contains(state))
+            {
+                return Err(format!(
+                    "The transitions from {} refer to the state {} which isn't in the state machine",
+                    from, unknown
+                ));
+            }
+        }
+
+        Ok(Machine {
+            states,
+            transitions,
+            guards: BTreeMap::new(),
+            on_enter: BTreeMap::new(),
+        })
+    }
+
+    /// Returns the state the machine starts in.
+    pub fn initial_state(&self) -> &str {
+        &self.states[0]
+    }
+
+    /// Returns whether the machine has a state.
+    ///
+    /// # Arguments
+    ///
+    /// * `state` - The name of the state.
+    pub fn has_state(&self, state: &str) -> bool {
+        self.states.iter().any(|s| s == state)
+    }
+
+    /// Adds a function that has to allow moving into a state.
+    ///
+    /// # Arguments
+    ///
+    /// * `state` - The name of the state.
+    /// * `guard` - The function.
+    pub fn add_guard(&mut self, state: &str, guard: Ref<FuncRef, ThreadLocal>) {
+        self.guards
+            .entry(state.to_string())
+            .or_default()
+            .push(guard);
+    }
+
+    /// Adds a function that is run after moving into a state.
+    ///
+    /// # Arguments
+    ///
+    /// * `state` - The name of the state.
+    /// * `effect` - The function.
+    pub fn add_on_enter(&mut self, state: &str, effect: Ref<FuncRef, ThreadLocal>) {
+        self.on_enter
+            .entry(state.to_string())
+            .or_default()
+            .push(effect);
+    }
+
+    /// Returns the functions that have to allow moving into a state.
+    ///
+    /// # Arguments
+    ///
+    /// * `state` - The name of the state.
+    pub fn guards(&self, state: &str) -> &[Ref<FuncRef, ThreadLocal>] {
+        self.guards.get(state).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    /// Returns the functions that are run after moving into a state.
+    ///
+    /// # Arguments
+    ///
+    /// * `state` - The name of the state.
+    pub fn on_enter(&self, state: &str) -> &[Ref<FuncRef, ThreadLocal>] {
+        self.on_enter.get(state).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    /// Returns an error describing why the machine can't move from one state
+    /// to another, if it can't.
+    ///
+    /// # Arguments
+    ///
+    /// * `from` - The state the machine is in.
+    /// * `to` - The state to move to.
+    pub fn check_transition(&self, from: &str, to: &str) -> Result<(), String> {
+        if !self.has_state(to) {
+            return Err(format!("It doesn't have the state {}", to));
+        }
+
+        let allowed = self
+            .transitions
+            .get(from)
+            .is_some_and(|states| states.iter().any(|state| state == to));
+        if allowed {
+            Ok(())
+        } else {
+            Err(format!("It can't move from {} to {}", from, to))
+        }
+    }
+}
+
+/// Returns the action that moves a state machine to another state.
+///
+/// # Arguments
+///
+/// * `machine` - The name of the state machine.
+/// * `to` - The state to move to.
+pub fn transition(machine: &str, to: &str) -> Dictionary<Unique> {
+    let action = action::named(FSM_TRANSITION);
+    action.insert(MACHINE_KEY, machine);
+    action.insert(TO_KEY, to);
+
+    action
+}
+
+/// Returns the name of the state machine and the state to move to of an
+/// `fsm_transition` action, or `None` if the action isn't one.
+///
+/// # Arguments
+///
+/// * `action` - The action to get the transition from.
+pub fn target(action: &Variant) -> Option<(String, String)> {
+    if !action::is_type(action, FSM_TRANSITION) {
+        return None;
+    }
+
+    let machine = action::field(action, MACHINE_KEY)?.to_string();
+    let to = action::field(action, TO_KEY)?.to_string();
+
+    Some((machine, to))
+}
+
+/// Returns the state of a state machine in the slice, with its current state
+/// under `state` and the state it was in before under `previous`.
+///
+/// # Arguments
+///
+/// * `state` - The current state.
+/// * `previous` - The state before the current one, if there was one.
+pub fn machine_slice(state: &str, previous: Option<&str>) -> Dictionary<Unique> {
+    let slice = Dictionary::new();
+    slice.insert(STATE_KEY, state);
+    match previous {
+        Some(previous) => slice.insert(PREVIOUS_KEY, previous),
+        None => slice.insert(PREVIOUS_KEY, Variant::new()),
+    }
+
+    slice
+}
+
+/// Returns the current state of a state machine in the slice, or `None` if
+/// the slice doesn't have the state machine.
+///
+/// # Arguments
+///
+/// * `slice` - The slice.
+/// * `machine` - The name of the state machine.
+pub fn current_state(slice: &Dictionary, machine: &str) -> Option<String> {
+    path::get(slice, &path::join(machine, STATE_KEY)).map(|state| state.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::Machine;
+
+    /// Creates a state machine with the states of a door.
+    fn door() -> Machine {
+        let states = vec!["closed", "open", "locked"];
+        let transitions = vec![
+            ("closed", vec!["open", "locked"]),
+            ("open", vec!["closed"]),
+            ("locked", vec!["closed"]),
+        ];
+
+        machine(&states, &transitions).unwrap()
+    }
+
+    /// Creates a state machine from the names of its states and transitions.
+    fn machine(states: &[&str], transitions: &[(&str, Vec<&str>)]) -> Result<Machine, String> {
+        let states = states.iter().map(|state| state.to_string()).collect();
+        let transitions = transitions
+            .iter()
+            .map(|(from, to)| {
+                let to = to.iter().map(|state| state.to_string()).collect();
+                (from.to_string(), to)
+            })
+            .collect::<BTreeMap<_, _>>();
+
+        Machine::from_states(states, transitions)
+    }
+
+    #[test]
+    fn the_first_state_is_the_initial_state() {
+        assert_eq!(door().initial_state(), "closed");
+        assert!(door().has_state("locked"));
+        assert!(!door().has_state("broken"));
+    }
+
+    #[test]
+    fn a_machine_needs_states_and_known_transitions() {
+        assert!(machine(&[], &[]).is_err());
+        assert!(machine(&["closed"], &[("closed", vec!["open"])]).is_err());
+        assert!(machine(&["closed"], &[("open", vec!["closed"])]).is_err());
+    }
+
+    #[test]
+    fn only_listed_transitions_are_allowed() {
+        let door = door();
+
+        assert!(door.check_transition("closed", "open").is_ok());
+        assert!(door.check_transition("closed", "locked").is_ok());
+        assert!(door.check_transition("open", "locked").is_err());
+        assert!(door.check_transition("locked", "open").is_err());
+        assert!(door.check_transition("closed", "broken").is_err());
+    }
+}
@@ -0,0 +1,39 @@
+use gdnative::prelude::Variant;
+
+/// Shrinks a sequence of actions that makes a check fail down to a smaller
+/// sequence that still makes it fail, by repeatedly trying to leave out each
+/// action and keeping it out whenever the check still fails without it. The
+/// sequence that is returned can't be shrunk any further by removing a single
+/// action.
+///
+/// # Arguments
+///
+/// * `actions` - The sequence of actions that makes the check fail.
+/// * `fails` - Runs a sequence of actions and returns whether the check failed.
+pub fn shrink<F>(actions: Vec<Variant>, mut fails: F) -> Vec<Variant>
+where
+    F: FnMut(&[Variant]) -> bool,
+{
+    let mut actions = actions;
+
+    loop {
+        let mut shrunk = false;
+
+        let mut index = 0;
+        while index < actions.len() {
+            let mut candidate = actions.clone();
+            candidate.remove(index);
+
+            if fails(&candidate) {
+                actions = candidate;
+                shrunk = true;
+            } else {
+                index += 1;
+            }
+        }
+
+        if !shrunk {
+            return actions;
+        }
+    }
+}
@@ -1,35 +1,705 @@
-use gdnative::api::FuncRef;
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeMap, VecDeque};
+use std::path::Path;
+
+use gdnative::api::{Engine, FuncRef, HTTPClient, HTTPRequest, InputEvent, Node, SceneTree};
 use gdnative::prelude::{
-    core_types::GodotString, methods, Dictionary, NativeClass, Object, Ref, Shared, Unique, Variant,
+    core_types::GodotString, godot_error, godot_print, godot_warn, methods, ByteArray,
+    ClassBuilder, Dictionary, ExportInfo, GodotObject, Instance, NativeClass, NewRef, Object,
+    OwnedToVariant, PropertyUsage, Ref, Reference, Shared, Signal, SignalArgument, StringArray,
+    TRef, ThreadLocal, ToVariant, Unique, Variant, VariantArray, VariantType,
 };
 
+use crate::action::{self, action_type};
+use crate::action_alias::{self, ActionAlias};
+use crate::audio::AudioBinding;
+use crate::audit::AccessAudit;
+use crate::background_save::{self, BackgroundSave};
+use crate::call_budget::CallBudget;
+use crate::cheat::{self, Cheats};
+use crate::clock::{self, Clock};
+use crate::codec::{Codec, Codecs};
+use crate::console::{self, Command};
+use crate::content_filter::{self, ContentFilter};
+use crate::content_pack::{ContentPack, ContentPacks};
+use crate::cooldown;
+use crate::dedupe::Dedupe;
+use crate::devtools::{self, DevTools};
+use crate::dialogue::{self, Dialogue};
+use crate::dispatch_queue::{DispatchQueue, Lane};
+use crate::effect_binding::EffectBinding;
+use crate::effect_pipeline::Pipelines;
+use crate::equality::Equality;
+use crate::error_policy::ErrorPolicy;
+use crate::event_log::EventLog;
+use crate::feature_flag;
+use crate::file::Encryption;
+use crate::form::{self, BoundField, Form};
+use crate::fsm::{self, Machine};
+use crate::history::History;
+use crate::input_recording::{self, Recording, Replay};
+use crate::interpolation;
+use crate::inventory::{self, Inventory};
+use crate::logging::{Logger, Verbosity};
+use crate::merge::Merge;
+use crate::middleware::{Chain, Convention, Middleware, MiddlewareNext, Origin};
+use crate::migration::{self, Migrations};
+use crate::pause::{self, PauseModes};
+use crate::persistence::{self, SaveInfo};
+use crate::preload::{self, Preloader};
+use crate::protection::{self, Protection};
+use crate::query::{self, Query};
+use crate::quest::{self, Completion, Quests};
+use crate::random;
+use crate::router::{self, Router};
+use crate::schema::Schema;
+use crate::scrub::Scrubber;
+use crate::selector::{MemoizedSelector, PathSubscription, SelectorSubscription};
+use crate::session_analytics::SessionAnalytics;
+use crate::stats::{self, Stats};
+use crate::storage::Storage;
+use crate::subscriber::{self, Subscriber};
+use crate::telemetry::Telemetry;
+use crate::testing::{self, DispatchLog};
+use crate::threadsafe_dispatch::ThreadSafeDispatcher;
+use crate::tick::{self, Tick};
+use crate::undo::{self, UndoStack};
+use crate::view_model::ViewModel;
+use crate::{diff, file, fuzz, patch, path, snapshot, window};
+
+/// The default maximum number of dispatches that can be nested inside of each
+/// other before the store refuses to go any deeper.
+const DEFAULT_MAX_DISPATCH_DEPTH: usize = 32;
+
+/// The key of the correlation id of the dispatch in the context passed to
+/// middleware added with `add_context_middleware`.
+const CORRELATION_ID_KEY: &str = "correlation_id";
+
+/// The key of the instance id in the Dictionary returned by
+/// `get_reducer_info`.
+const INSTANCE_ID_KEY: &str = "instance_id";
+
+/// The key of the name of the reducer in the Dictionary returned by
+/// `get_reducer_info`.
+const METHOD_KEY: &str = "method";
+
+/// The key of the state in the Dictionary returned by `preview`.
+const PREVIEW_STATE_KEY: &str = "state";
+
+/// The key of the changed paths in the Dictionary returned by `preview`.
+const PREVIEW_DIFF_KEY: &str = "diff";
+
+/// The slices of the state that the store manages itself when they're used.
+const BUILT_IN_SLICES: &[&str] = &[
+    router::SLICE,
+    form::SLICE,
+    query::SLICE,
+    stats::SLICE,
+    inventory::SLICE,
+    quest::SLICE,
+    dialogue::SLICE,
+    fsm::SLICE,
+    cooldown::SLICE,
+    feature_flag::SLICE,
+    input_recording::SLICE,
+    preload::SLICE,
+    cheat::SLICE,
+    random::SLICE,
+];
+
+/// The property of a Resource loaded with `load_initial_state_from_resource`
+/// that has the data of the slice.
+const REFERENCE_DATA_PROPERTY: &str = "data";
+
+/// The number of actions that passed the reducer kept for `debug_dump`.
+const RECENT_ACTIONS: usize = 50;
+
+/// The store. Every exported method only borrows the store immutably so that
+/// reducers, middleware, and subscriptions can call back into it (for example
+/// to dispatch another action) while a dispatch is still being processed.
+/// Because of this, no `RefCell` can be kept borrowed across a call into
+/// gdscript.
 #[inherit(Object)]
+#[register_with(Self::register_signals)]
 #[derive(NativeClass)]
 pub struct GodotRedux {
+    /// The object the store is attached to, which emits the store's signals.
+    owner: Ref<Object, Shared>,
+    /// The instance id of the owner, which is used to check whether the owner
+    /// was freed before calling into it.
+    owner_id: i64,
     /// The initial state of the application.
-    state: Dictionary,
+    state: RefCell<Dictionary>,
     /// The reducer function.
-    reducer: Ref<FuncRef, Unique>,
+    reducer: RefCell<Ref<FuncRef, ThreadLocal>>,
+    /// The reducers registered with `combine_reducers` along with the key of
+    /// the slice each one reduces, which are run instead of the reducer if
+    /// there are any.
+    slice_reducers: RefCell<Vec<(String, Ref<FuncRef, Shared>)>>,
+    /// The reducers added with `inject_reducer` along with the key of the
+    /// slice each one reduces, which are run after the reducer.
+    injected_reducers: RefCell<Vec<(String, Ref<FuncRef, Shared>)>>,
+    /// The state the store was created with.
+    initial_state: RefCell<Dictionary>,
+    /// The instance id of the reducer's instance and the name of the reducer,
+    /// if the store was created.
+    reducer_info: RefCell<Option<(i64, String)>>,
     /// The middleware functions used to intercept actions and change them
-    /// before they reach the reducer.
-    middleware: Vec<Ref<FuncRef, Unique>>,
-    /// The callback functions to run when the state is changed.
-    subscriptions: Vec<Ref<FuncRef, Unique>>,
+    /// before they reach the reducer, with the id of each one, what it's
+    /// passed, whether it has side effects, and the instance and function it
+    /// calls, like `Node#1234.log_middleware`. The ids go up in the order the
+    /// functions were added, which is the order they run in.
+    middleware: RefCell<Chain<Middleware>>,
+    /// The id the next middleware function gets.
+    next_middleware_id: Cell<i64>,
+    /// The callback functions to run when the state is changed, with the id of
+    /// each one.
+    subscriptions: RefCell<Vec<(i64, Subscriber)>>,
+    /// The subscriptions that only run when the value they select changes,
+    /// with the id of each one.
+    selector_subscriptions: RefCell<Vec<(i64, SelectorSubscription)>>,
+    /// The subscriptions that only run when the value at their path changes,
+    /// with the id of each one.
+    path_subscriptions: RefCell<Vec<(i64, PathSubscription)>>,
+    /// The id the next subscription gets, which is shared by every kind of
+    /// subscription.
+    next_subscription_id: Cell<i64>,
+    /// The filters that check the text in actions before they are dispatched.
+    content_filters: RefCell<Vec<ContentFilter>>,
+    /// The schemas that the payloads of actions are checked against, by action
+    /// type.
+    action_schemas: RefCell<Vec<(Variant, Schema)>>,
+    /// The schema that the state is checked against when it's loaded, set
+    /// with `set_state_schema`.
+    state_schema: RefCell<Option<Schema>>,
+    /// Whether the state returned by the reducer is checked against the
+    /// state schema after every dispatch.
+    check_dispatched_state: Cell<bool>,
+    /// The action types gated behind feature flags with `gate_action`, with
+    /// the flag each one is gated behind.
+    flag_gates: RefCell<Vec<(Variant, String)>>,
+    /// The action types deduplicated with `set_dedupe` and the actions that
+    /// were let through recently.
+    dedupe: RefCell<Dedupe>,
+    /// The instance ids of the requests for flag overrides that haven't
+    /// completed yet.
+    flag_requests: RefCell<Vec<i64>>,
+    /// The old action types registered with `register_action_alias`.
+    action_aliases: RefCell<Vec<ActionAlias>>,
+    /// The sounds and buses bound with `bind_audio`.
+    audio: RefCell<Option<AudioBinding>>,
+    /// The methods bound to action types with `bind_effect`.
+    effect_bindings: RefCell<Vec<EffectBinding>>,
+    /// The bound methods to call once the outermost dispatch is done, along with
+    /// the action each one is called with.
+    pending_bound_effects: RefCell<Vec<(EffectBinding, Variant)>>,
+    /// The effects run one after the other for action types, added with
+    /// `on_action`, and the runs of them that haven't finished.
+    pipelines: RefCell<Pipelines>,
+    /// The runs of pipelines to start once the outermost dispatch is done.
+    pending_pipeline_runs: RefCell<Vec<i64>>,
+    /// What happens when a stage of a dispatch fails, set with
+    /// `set_error_policy`, or `None` to only print an error.
+    error_policy: RefCell<Option<ErrorPolicy>>,
+    /// Whether dispatching was halted by the `halt` error policy, in which
+    /// case actions are dropped until `resume_dispatching` is called.
+    dispatch_halted: Cell<bool>,
+    /// The types of the actions that are currently being dispatched, from the
+    /// outermost dispatch to the innermost one, each with the action as
+    /// stable text so the same action being dispatched again can be found.
+    dispatch_stack: RefCell<Vec<(Variant, String)>>,
+    /// The maximum number of dispatches that can be nested inside of each other.
+    max_dispatch_depth: Cell<usize>,
+    /// The number of `with_batch` calls that are running inside of each other.
+    batch_depth: Cell<usize>,
+    /// Whether the state changed during the running batch, so subscribers have
+    /// to be notified once it's done.
+    batch_changed: Cell<bool>,
+    /// The actions of each macro defined with `define_macro`, by name.
+    macros: RefCell<BTreeMap<String, Vec<Variant>>>,
+    /// The record of the state paths read and written, if auditing is enabled.
+    access_audit: RefCell<Option<AccessAudit>>,
+    /// The record of every dispatched action, if the test mode is enabled.
+    dispatch_log: RefCell<Option<DispatchLog>>,
+    /// Whether a scenario is being run with `run_scenario`, in which case the
+    /// subscriptions aren't run.
+    running_scenario: Cell<bool>,
+    /// Whether recorded input is being dispatched by an input replay.
+    replaying_input: Cell<bool>,
+    /// Where the store gets the current time from.
+    clock: RefCell<Clock>,
+    /// The actions waiting to be dispatched at the end of the frame, with the
+    /// correlation id of the dispatch that queued each one.
+    deferred_dispatches: RefCell<DispatchQueue>,
+    /// The most normal and idle deferred actions dispatched in a frame, if
+    /// there is a limit.
+    deferred_budget: Cell<Option<usize>>,
+    /// What other threads dispatch actions through, created the first time
+    /// it's asked for with `get_threadsafe_dispatcher`.
+    threadsafe_dispatcher: RefCell<Option<Instance<ThreadSafeDispatcher, Shared>>>,
+    /// The deferred actions that are waiting for the tree to be unpaused.
+    frozen_dispatches: RefCell<Vec<(Variant, Option<u64>)>>,
+    /// The correlation id of the dispatch in progress, which is shared by
+    /// every action dispatched because of it.
+    correlation: Cell<Option<u64>>,
+    /// The correlation id the next dispatch that isn't part of another one
+    /// gets.
+    next_correlation: Cell<u64>,
+    /// What keeps going while the tree is paused.
+    pause_modes: RefCell<PauseModes>,
+    /// The tree the store was attached to with `attach_to_tree`, if any.
+    tree: RefCell<Option<Ref<SceneTree, Shared>>>,
+    /// The router that changes scenes for navigation actions, if scene routing
+    /// is enabled.
+    router: RefCell<Option<Router>>,
+    /// The forms created with `create_form`, by name.
+    forms: RefCell<BTreeMap<String, Form>>,
+    /// The controls bound to the fields of forms with `bind_form`.
+    bound_fields: RefCell<Vec<BoundField>>,
+    /// Whether the bound controls are being updated to show the state.
+    syncing_bound_fields: Cell<bool>,
+    /// The queries registered with `register_query`, by name.
+    queries: RefCell<BTreeMap<String, Query>>,
+    /// The statistics tracked by the store, if they are enabled.
+    stats: RefCell<Option<Stats>>,
+    /// Whether the statistics changed during the dispatch in progress, so
+    /// they're saved once it's done instead of by the reducer.
+    stats_changed: Cell<bool>,
+    /// The signals to emit once the state has been updated, with their
+    /// arguments.
+    pending_signals: RefCell<Vec<(GodotString, Vec<Variant>)>>,
+    /// The action types that emit a signal once they pass the reducer, set
+    /// with `emit_signal_for_action`, with the name of the signal.
+    action_signals: RefCell<Vec<(Variant, GodotString)>>,
+    /// The inventory managed by the store, if it's enabled.
+    inventory: RefCell<Option<Inventory>>,
+    /// The quests loaded with `load_quests`, if any were loaded.
+    quests: RefCell<Option<Quests>>,
+    /// The dialogues loaded with `load_dialogue`, by name.
+    dialogues: RefCell<BTreeMap<String, Dialogue>>,
+    /// The state machines created with `create_fsm_slice`, by name.
+    machines: RefCell<BTreeMap<String, Machine>>,
+    /// The functions to run once the state has been updated, with the
+    /// arguments to pass after the state.
+    pending_effects: RefCell<Vec<(Ref<FuncRef, ThreadLocal>, Vec<Variant>)>>,
+    /// The time the running cooldowns were last counted down at, if any are
+    /// running.
+    last_cooldown_tick: Cell<Option<f64>>,
+    /// The fixed rate `TICK` actions are dispatched at, if it's enabled.
+    tick: RefCell<Option<Tick>>,
+    /// The resources being preloaded and the ones that were.
+    preloader: RefCell<Preloader>,
+    /// The cheats registered with `register_cheat` and the permission level
+    /// that decides which of them can be used.
+    cheats: RefCell<Cheats>,
+    /// The dispatches counted for the `stats_tick` signal and `get_stats`, if
+    /// telemetry is enabled.
+    telemetry: RefCell<Option<Telemetry>>,
+    /// How often each type of action was dispatched during the play session
+    /// and what was in them, if `set_session_analytics` enabled it.
+    session_analytics: RefCell<Option<SessionAnalytics>>,
+    /// How long the calls to each subscriber took, for `get_slow_subscribers`,
+    /// along with the budget set with `set_subscriber_budget`.
+    call_budget: RefCell<CallBudget>,
+    /// One in how many outermost dispatches is profiled by telemetry.
+    stats_sample_rate: Cell<u64>,
+    /// The paths of the state whose values are kept sealed, if any are
+    /// protected.
+    protection: RefCell<Option<Protection>>,
+    /// The actions that reached the reducer, if event sourcing is enabled.
+    event_log: RefCell<Option<EventLog>>,
+    /// The actions that were dispatched and the states they led to, if time
+    /// travel is enabled.
+    history: RefCell<Option<History>>,
+    /// The changes that can be undone and redone, if undo is enabled.
+    undo_stack: RefCell<Option<UndoStack>>,
+    /// The number of bytes the history can take up before its oldest entries
+    /// are trimmed, if it's limited.
+    history_memory_limit: Cell<Option<usize>>,
+    /// The projection reducers registered with `register_projection` along
+    /// with the state each one starts from, by name.
+    projections: RefCell<BTreeMap<String, (Ref<FuncRef, ThreadLocal>, Dictionary)>>,
+    /// The number of frames that have passed, counted by `attach_to_tree` and
+    /// `simulate_frames`.
+    frame: Cell<u64>,
+    /// The input recording, if input is being recorded.
+    input_recording: RefCell<Option<Recording>>,
+    /// The input replay, if input is being replayed.
+    input_replay: RefCell<Option<Replay>>,
+    /// The slices of the state that reducers can read but not modify.
+    readonly_slices: RefCell<Vec<String>>,
+    /// The content packs loaded with `load_content_pack`.
+    content_packs: RefCell<ContentPacks>,
+    /// The view models registered with `register_view_model`, by name.
+    view_models: RefCell<BTreeMap<String, ViewModel>>,
+    /// The selectors registered with `register_selector`, by name.
+    memoized_selectors: RefCell<BTreeMap<String, MemoizedSelector>>,
+    /// The top-level keys of the state that changed since the dirty slices
+    /// were last read, in the order they first changed.
+    dirty_slices: RefCell<Vec<String>>,
+    /// The state before the last dispatch, which values are blended from by
+    /// `get_interpolated`. Like the state, its protected values are sealed.
+    previous_state: RefCell<Dictionary>,
+    /// What is written into the metadata of every save.
+    save_info: RefCell<SaveInfo>,
+    /// The saves being written on worker threads.
+    background_saves: RefCell<Vec<BackgroundSave>>,
+    /// The number of backups kept of every save, set with `set_backup_count`.
+    backup_count: Cell<usize>,
+    /// What saves are encrypted with, set with `set_save_encryption`.
+    encryption: RefCell<Option<Encryption>>,
+    /// Where saves are kept on the platform the game is running on.
+    storage: RefCell<Storage>,
+    /// The id of the middleware added with `persist` along with the path and
+    /// the format it writes the state in.
+    persisted: RefCell<Option<(i64, GodotString, GodotString)>>,
+    /// The id of the middleware added with `enable_logging` along with the
+    /// logger it prints the actions with.
+    logger: RefCell<Option<(i64, Logger)>>,
+    /// The codecs registered with `register_codec`, along with the built-in
+    /// ones.
+    codecs: RefCell<Codecs>,
+    /// The migrations registered with `register_migration` that upgrade
+    /// states saved with older versions of the state.
+    migrations: RefCell<Migrations>,
+    /// The connection to a Redux DevTools server made with
+    /// `connect_devtools`, if there is one.
+    devtools: RefCell<Option<DevTools>>,
+    /// The last actions that passed the reducer, with the frame and the time
+    /// they were dispatched at, with the latest one last.
+    recent_actions: RefCell<VecDeque<(Variant, u64, f64)>>,
 }
 
 #[methods]
 impl GodotRedux {
     /// Initializes the struct with default values.
-    fn new(_owner: &Object) -> Self {
+    fn new(owner: &Object) -> Self {
         GodotRedux {
-            state: Dictionary::new_shared(),
-            reducer: FuncRef::new(),
-            middleware: vec![],
-            subscriptions: vec![],
+            owner: unsafe { owner.assume_shared() },
+            owner_id: owner.get_instance_id(),
+            state: RefCell::new(Dictionary::new_shared()),
+            reducer: RefCell::new(FuncRef::new().into_thread_local()),
+            slice_reducers: RefCell::new(vec![]),
+            injected_reducers: RefCell::new(vec![]),
+            initial_state: RefCell::new(Dictionary::new_shared()),
+            reducer_info: RefCell::new(None),
+            middleware: RefCell::new(Chain::default()),
+            next_middleware_id: Cell::new(1),
+            subscriptions: RefCell::new(vec![]),
+            selector_subscriptions: RefCell::new(vec![]),
+            path_subscriptions: RefCell::new(vec![]),
+            next_subscription_id: Cell::new(1),
+            content_filters: RefCell::new(vec![]),
+            action_schemas: RefCell::new(vec![]),
+            state_schema: RefCell::new(None),
+            check_dispatched_state: Cell::new(false),
+            flag_gates: RefCell::new(vec![]),
+            dedupe: RefCell::new(Dedupe::default()),
+            flag_requests: RefCell::new(vec![]),
+            action_aliases: RefCell::new(vec![]),
+            audio: RefCell::new(None),
+            effect_bindings: RefCell::new(vec![]),
+            pending_bound_effects: RefCell::new(vec![]),
+            pipelines: RefCell::new(Pipelines::default()),
+            pending_pipeline_runs: RefCell::new(vec![]),
+            error_policy: RefCell::new(None),
+            dispatch_halted: Cell::new(false),
+            dispatch_stack: RefCell::new(vec![]),
+            max_dispatch_depth: Cell::new(DEFAULT_MAX_DISPATCH_DEPTH),
+            batch_depth: Cell::new(0),
+            batch_changed: Cell::new(false),
+            macros: RefCell::new(BTreeMap::new()),
+            access_audit: RefCell::new(None),
+            dispatch_log: RefCell::new(None),
+            running_scenario: Cell::new(false),
+            replaying_input: Cell::new(false),
+            clock: RefCell::new(Clock::default()),
+            deferred_dispatches: RefCell::new(DispatchQueue::default()),
+            deferred_budget: Cell::new(None),
+            threadsafe_dispatcher: RefCell::new(None),
+            frozen_dispatches: RefCell::new(vec![]),
+            correlation: Cell::new(None),
+            next_correlation: Cell::new(1),
+            pause_modes: RefCell::new(PauseModes::default()),
+            tree: RefCell::new(None),
+            router: RefCell::new(None),
+            forms: RefCell::new(BTreeMap::new()),
+            bound_fields: RefCell::new(vec![]),
+            syncing_bound_fields: Cell::new(false),
+            queries: RefCell::new(BTreeMap::new()),
+            stats: RefCell::new(None),
+            stats_changed: Cell::new(false),
+            pending_signals: RefCell::new(vec![]),
+            action_signals: RefCell::new(vec![]),
+            inventory: RefCell::new(None),
+            quests: RefCell::new(None),
+            dialogues: RefCell::new(BTreeMap::new()),
+            machines: RefCell::new(BTreeMap::new()),
+            pending_effects: RefCell::new(vec![]),
+            last_cooldown_tick: Cell::new(None),
+            tick: RefCell::new(None),
+            preloader: RefCell::new(Preloader::default()),
+            cheats: RefCell::new(Cheats::default()),
+            telemetry: RefCell::new(None),
+            session_analytics: RefCell::new(None),
+            call_budget: RefCell::new(CallBudget::default()),
+            stats_sample_rate: Cell::new(1),
+            protection: RefCell::new(None),
+            event_log: RefCell::new(None),
+            history: RefCell::new(None),
+            undo_stack: RefCell::new(None),
+            history_memory_limit: Cell::new(None),
+            projections: RefCell::new(BTreeMap::new()),
+            frame: Cell::new(0),
+            input_recording: RefCell::new(None),
+            input_replay: RefCell::new(None),
+            readonly_slices: RefCell::new(vec![]),
+            content_packs: RefCell::new(ContentPacks::default()),
+            view_models: RefCell::new(BTreeMap::new()),
+            memoized_selectors: RefCell::new(BTreeMap::new()),
+            dirty_slices: RefCell::new(vec![]),
+            previous_state: RefCell::new(Dictionary::new_shared()),
+            save_info: RefCell::new(SaveInfo::default()),
+            backup_count: Cell::new(0),
+            encryption: RefCell::new(None),
+            storage: RefCell::new(Storage::default()),
+            background_saves: RefCell::new(vec![]),
+            persisted: RefCell::new(None),
+            logger: RefCell::new(None),
+            codecs: RefCell::new(Codecs::default()),
+            migrations: RefCell::new(Migrations::default()),
+            devtools: RefCell::new(None),
+            recent_actions: RefCell::new(VecDeque::new()),
+        }
+    }
+
+    /// Registers the signals emitted by the store.
+    ///
+    /// # Arguments
+    ///
+    /// * `builder` - The builder of the class.
+    fn register_signals(builder: &ClassBuilder<Self>) {
+        builder.add_signal(Signal {
+            name: "achievement_unlocked",
+            args: &[SignalArgument {
+                name: "name",
+                default: Variant::from_str(""),
+                export_info: ExportInfo::new(VariantType::GodotString),
+                usage: PropertyUsage::DEFAULT,
+            }],
+        });
+        builder.add_signal(Signal {
+            name: "input_replay_finished",
+            args: &[],
+        });
+        builder.add_signal(Signal {
+            name: "objective_completed",
+            args: &[
+                SignalArgument {
+                    name: "quest",
+                    default: Variant::from_str(""),
+                    export_info: ExportInfo::new(VariantType::GodotString),
+                    usage: PropertyUsage::DEFAULT,
+                },
+                SignalArgument {
+                    name: "objective",
+                    default: Variant::from_str(""),
+                    export_info: ExportInfo::new(VariantType::GodotString),
+                    usage: PropertyUsage::DEFAULT,
+                },
+            ],
+        });
+        for name in &["entered_state", "exited_state"] {
+            builder.add_signal(Signal {
+                name: *name,
+                args: &[
+                    SignalArgument {
+                        name: "machine",
+                        default: Variant::from_str(""),
+                        export_info: ExportInfo::new(VariantType::GodotString),
+                        usage: PropertyUsage::DEFAULT,
+                    },
+                    SignalArgument {
+                        name: "state",
+                        default: Variant::from_str(""),
+                        export_info: ExportInfo::new(VariantType::GodotString),
+                        usage: PropertyUsage::DEFAULT,
+                    },
+                ],
+            });
+        }
+        builder.add_signal(Signal {
+            name: "subscriber_error",
+            args: &[
+                SignalArgument {
+                    name: "subscriber",
+                    default: Variant::from_str(""),
+                    export_info: ExportInfo::new(VariantType::GodotString),
+                    usage: PropertyUsage::DEFAULT,
+                },
+                SignalArgument {
+                    name: "message",
+                    default: Variant::from_str(""),
+                    export_info: ExportInfo::new(VariantType::GodotString),
+                    usage: PropertyUsage::DEFAULT,
+                },
+            ],
+        });
+        builder.add_signal(Signal {
+            name: "fatal_error",
+            args: &[
+                SignalArgument {
+                    name: "stage",
+                    default: Variant::from_str(""),
+                    export_info: ExportInfo::new(VariantType::GodotString),
+                    usage: PropertyUsage::DEFAULT,
+                },
+                SignalArgument {
+                    name: "message",
+                    default: Variant::from_str(""),
+                    export_info: ExportInfo::new(VariantType::GodotString),
+                    usage: PropertyUsage::DEFAULT,
+                },
+                SignalArgument {
+                    name: "action",
+                    default: Variant::new(),
+                    export_info: ExportInfo::new(VariantType::Nil),
+                    usage: PropertyUsage::DEFAULT,
+                },
+            ],
+        });
+        builder.add_signal(Signal {
+            name: "store_initialized",
+            args: &[],
+        });
+        builder.add_signal(Signal {
+            name: "save_started",
+            args: &[SignalArgument {
+                name: "path",
+                default: Variant::from_str(""),
+                export_info: ExportInfo::new(VariantType::GodotString),
+                usage: PropertyUsage::DEFAULT,
+            }],
+        });
+        builder.add_signal(Signal {
+            name: "save_finished",
+            args: &[
+                SignalArgument {
+                    name: "success",
+                    default: Variant::from_bool(false),
+                    export_info: ExportInfo::new(VariantType::Bool),
+                    usage: PropertyUsage::DEFAULT,
+                },
+                SignalArgument {
+                    name: "path",
+                    default: Variant::from_str(""),
+                    export_info: ExportInfo::new(VariantType::GodotString),
+                    usage: PropertyUsage::DEFAULT,
+                },
+            ],
+        });
+        builder.add_signal(Signal {
+            name: "persistence_loaded",
+            args: &[SignalArgument {
+                name: "path",
+                default: Variant::from_str(""),
+                export_info: ExportInfo::new(VariantType::GodotString),
+                usage: PropertyUsage::DEFAULT,
+            }],
+        });
+        for name in &["reducer_replaced", "middleware_added"] {
+            builder.add_signal(Signal {
+                name: *name,
+                args: &[SignalArgument {
+                    name: "function",
+                    default: Variant::from_str(""),
+                    export_info: ExportInfo::new(VariantType::GodotString),
+                    usage: PropertyUsage::DEFAULT,
+                }],
+            });
         }
+        builder.add_signal(Signal {
+            name: "history_trimmed",
+            args: &[SignalArgument {
+                name: "count",
+                default: Variant::from_i64(0),
+                export_info: ExportInfo::new(VariantType::I64),
+                usage: PropertyUsage::DEFAULT,
+            }],
+        });
+        for name in &["content_loaded", "content_unloaded"] {
+            builder.add_signal(Signal {
+                name: *name,
+                args: &[SignalArgument {
+                    name: "pack_id",
+                    default: Variant::from_str(""),
+                    export_info: ExportInfo::new(VariantType::GodotString),
+                    usage: PropertyUsage::DEFAULT,
+                }],
+            });
+        }
+        builder.add_signal(Signal {
+            name: "stats_tick",
+            args: &[
+                SignalArgument {
+                    name: "dispatch_count",
+                    default: Variant::from_i64(0),
+                    export_info: ExportInfo::new(VariantType::I64),
+                    usage: PropertyUsage::DEFAULT,
+                },
+                SignalArgument {
+                    name: "queue_depth",
+                    default: Variant::from_i64(0),
+                    export_info: ExportInfo::new(VariantType::I64),
+                    usage: PropertyUsage::DEFAULT,
+                },
+                SignalArgument {
+                    name: "avg_dispatch_ms",
+                    default: Variant::from_f64(0.0),
+                    export_info: ExportInfo::new(VariantType::F64),
+                    usage: PropertyUsage::DEFAULT,
+                },
+            ],
+        });
+        builder.add_signal(Signal {
+            name: "action_dispatched",
+            args: &[SignalArgument {
+                name: "action",
+                default: Variant::new(),
+                export_info: ExportInfo::new(VariantType::Nil),
+                usage: PropertyUsage::DEFAULT,
+            }],
+        });
+        builder.add_signal(Signal {
+            name: "state_changed",
+            args: &[
+                SignalArgument {
+                    name: "new_state",
+                    default: Variant::from_dictionary(&Dictionary::new_shared()),
+                    export_info: ExportInfo::new(VariantType::Dictionary),
+                    usage: PropertyUsage::DEFAULT,
+                },
+                SignalArgument {
+                    name: "action",
+                    default: Variant::new(),
+                    export_info: ExportInfo::new(VariantType::Nil),
+                    usage: PropertyUsage::DEFAULT,
+                },
+            ],
+        });
+        builder.add_signal(Signal {
+            name: "quest_completed",
+            args: &[SignalArgument {
+                name: "quest",
+                default: Variant::from_str(""),
+                export_info: ExportInfo::new(VariantType::GodotString),
+                usage: PropertyUsage::DEFAULT,
+            }],
+        });
     }
 
-    /// Creates a new store
+    /// Creates a new store, emitting `store_initialized` the first time and
+    /// `reducer_replaced` with the new reducer after that.
     ///
     /// # Arguments
     ///
@@ -42,212 +712,9908 @@ impl GodotRedux {
     ///
     #[export]
     fn set_state_and_reducer(
-        &mut self,
-        _owner: &Object,
+        &self,
+        owner: &Object,
         initial_state: Dictionary,
         reducer_fn_instance: Ref<Object, Shared>,
         reducer_fn_name: GodotString,
     ) {
-        self.state = initial_state;
+        let (replaced, reducer) = self.set_reducer(reducer_fn_instance, reducer_fn_name);
 
-        self.reducer = FuncRef::new();
-        self.reducer.set_instance(reducer_fn_instance);
-        self.reducer.set_function(reducer_fn_name);
+        *self.initial_state.borrow_mut() = initial_state.duplicate().into_shared();
+        *self.state.borrow_mut() = self.sealed(&initial_state);
+        *self.previous_state.borrow_mut() = self.state.borrow().new_ref();
+        self.slice_reducers.borrow_mut().clear();
+        self.injected_reducers.borrow_mut().clear();
 
-        self.middleware = vec![];
-        self.subscriptions = vec![];
+        self.middleware.borrow_mut().clear();
+        self.subscriptions.borrow_mut().clear();
+        self.selector_subscriptions.borrow_mut().clear();
+        self.path_subscriptions.borrow_mut().clear();
+
+        if replaced {
+            owner.emit_signal("reducer_replaced", &[Variant::from_str(reducer)]);
+        } else {
+            owner.emit_signal("store_initialized", &[]);
+        }
     }
 
-    /// Returns the current state.
+    /// Replaces the reducer without changing the state, for example after the
+    /// script with the reducer was reloaded. The subscriptions, middleware,
+    /// and reducers added with `inject_reducer` are kept, while the reducers
+    /// combined with `combine_reducers` are removed so the new reducer is the
+    /// one that runs. Emits `reducer_replaced` with the new reducer.
+    ///
+    /// # Arguments
+    ///
+    /// * `reducer_fn_instance` - The instance on which the reducer exists.
+    /// * `reducer_fn_name` - The name of the reducer function.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _on_script_reloaded(game):
+    ///     store.replace_reducer(game, 'reducer')
+    /// ```
     #[export]
-    fn state(&self, _owner: &Object) -> Dictionary<Unique> {
-        self.state.duplicate()
+    fn replace_reducer(
+        &self,
+        owner: &Object,
+        reducer_fn_instance: Ref<Object, Shared>,
+        reducer_fn_name: GodotString,
+    ) {
+        if self.reducer_info.borrow().is_none() {
+            godot_error!(
+                "Unable to replace the reducer since the store wasn't created with set_state_and_reducer"
+            );
+            return;
+        }
+
+        let (_, reducer) = self.set_reducer(reducer_fn_instance, reducer_fn_name);
+        self.slice_reducers.borrow_mut().clear();
+
+        owner.emit_signal("reducer_replaced", &[Variant::from_str(reducer)]);
     }
 
-    /// Dispatches an action to update the state.
+    /// Adds a reducer for a single slice of the state on top of the reducer,
+    /// for game systems that are loaded after the store was created. The
+    /// reducer is passed just its slice and the action after the reducer ran,
+    /// and returns the new slice. The state is left as it is until the next
+    /// dispatch, so a slice that isn't in the state yet is passed as `null`
+    /// the first time. Injecting a reducer for a slice that already has one
+    /// replaces it.
     ///
     /// # Arguments
     ///
-    /// * `action` - The action to dispatch.
+    /// * `slice` - The key of the slice.
+    /// * `reducer_fn_instance` - The instance on which the reducer exists.
+    /// * `reducer_fn_name` - The name of the reducer function.
     ///
     /// # Example
     ///
     /// ```
-    /// const state = {
-    ///     "counter": 0,
-    /// }
+    /// func _on_fishing_loaded(fishing):
+    ///     store.inject_reducer("fishing", fishing, 'reducer')
+    /// ```
+    #[export]
+    fn inject_reducer(
+        &self,
+        _owner: &Object,
+        slice: GodotString,
+        reducer_fn_instance: Ref<Object, Shared>,
+        reducer_fn_name: GodotString,
+    ) {
+        let slice = slice.to_string();
+        let reducer = FuncRef::new();
+        reducer.set_instance(reducer_fn_instance);
+        reducer.set_function(reducer_fn_name);
+
+        let mut injected_reducers = self.injected_reducers.borrow_mut();
+        injected_reducers.retain(|(injected, _)| *injected != slice);
+        injected_reducers.push((slice, reducer.into_shared()));
+    }
+
+    /// Sets the reducer function and returns whether it replaced another one
+    /// along with a description of it, like `Node#1234.reducer`.
     ///
-    /// enum Action {
-    ///     INCREMENT,
-    ///     DECREMENT,
-    /// }
+    /// # Arguments
     ///
-    /// func reducer(state, action):
-    ///     match action:
-    ///         Action.INCREMENT:
-    ///             return {
-    ///                 "counter": state.counter + 1,
-    ///             }
-    ///         Action.DECREMENT:
-    ///             return {
-    ///                 "counter": state.counter - 1,
-    ///             }
+    /// * `reducer_fn_instance` - The instance on which the reducer exists.
+    /// * `reducer_fn_name` - The name of the reducer function.
+    fn set_reducer(
+        &self,
+        reducer_fn_instance: Ref<Object, Shared>,
+        reducer_fn_name: GodotString,
+    ) -> (bool, String) {
+        let (instance_id, reducer) = {
+            let instance = unsafe { reducer_fn_instance.assume_safe() };
+
+            (
+                instance.get_instance_id(),
+                subscriber::identity(&instance, &reducer_fn_name),
+            )
+        };
+        let replaced = self
+            .reducer_info
+            .replace(Some((instance_id, reducer_fn_name.to_string())))
+            .is_some();
+
+        *self.reducer.borrow_mut() = func_ref(reducer_fn_instance, reducer_fn_name);
+
+        (replaced, reducer)
+    }
+
+    /// Splits the reducer into one reducer for each slice of the state, like
+    /// `player`, `inventory`, and `settings`. Each reducer is passed just its
+    /// slice and the action and returns the new slice, which is put back into
+    /// the state, and the slices without a reducer are left as they are. The
+    /// slice reducers are run instead of the reducer passed to
+    /// `set_state_and_reducer`, and combining an empty Dictionary or replacing
+    /// the reducer with `replace_reducer` goes back to a single reducer.
+    ///
+    /// # Arguments
+    ///
+    /// * `slices` - The reducer of each slice, as a FuncRef, by the key of the
+    /// slice.
     ///
+    /// # Example
+    ///
+    /// ```
     /// func _ready():
-    ///     var store = Store.new(state, self, 'reducer')
-    ///     store.dispatch(Action.INCREMENT)
+    ///     store.combine_reducers({
+    ///         "player": funcref(self, "reduce_player"),
+    ///         "inventory": funcref(self, "reduce_inventory"),
+    ///     })
     /// ```
     #[export]
-    fn dispatch(&mut self, _owner: &Object, action: i64) {
-        if self.middleware.is_empty() {
-            self.dispatch_reducer(action);
-        } else {
-            self.dispatch_middleware(0, action);
+    fn combine_reducers(&self, _owner: &Object, slices: Dictionary) {
+        let slice_reducers = slices
+            .iter()
+            .map(|(key, reducer)| {
+                reducer
+                    .try_to_object::<FuncRef>()
+                    .map(|reducer| (key.to_string(), reducer))
+                    .ok_or_else(|| {
+                        format!(
+                            "The reducer of the slice {} has to be a FuncRef, got {}",
+                            key.to_string(),
+                            reducer.to_string()
+                        )
+                    })
+            })
+            .collect::<Result<Vec<_>, String>>();
+
+        match slice_reducers {
+            Ok(slice_reducers) => *self.slice_reducers.borrow_mut() = slice_reducers,
+            Err(message) => godot_error!("Unable to combine the reducers: {}", message),
         }
     }
 
-    /// Runs a single middleware function. If the middleware function returns an
-    /// action then it runs the next middleware function in the middlewares array with
-    /// the action returned by the previous one.
+    /// Loads data bundled with the game, like an item database or balance
+    /// tables, into a read-only slice of the state. Reducers can read the
+    /// slice but any change they make to it is dropped with an error. The
+    /// data in the slice replaces anything that was under it before.
     ///
     /// # Arguments
     ///
-    /// * `index` - The index of the middleware function to run from the array.
-    /// * `action` - The action to pass to the middleware function.
-    fn dispatch_middleware(&mut self, index: usize, action: i64) {
-        if index == self.middleware.len() {
-            self.dispatch_reducer(action);
+    /// * `path` - The path of a JSON file with the data, or of a Resource with
+    /// the data in its `data` property.
+    /// * `slice` - Optional key of the slice, which defaults to the name of
+    /// the file without its extension.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _ready():
+    ///     store.load_initial_state_from_resource("res://data/item_db.json")
+    ///
+    ///     print(store.get_value("item_db/sword/damage")) # 12
+    /// ```
+    #[export]
+    fn load_initial_state_from_resource(
+        &self,
+        owner: &Object,
+        path: GodotString,
+        #[opt] slice: GodotString,
+    ) {
+        let path = path.to_string();
+        let data = match file::read_definitions(&Variant::from_str(&path), REFERENCE_DATA_PROPERTY)
+        {
+            Ok(data) => data,
+            Err(message) => {
+                godot_error!(
+                    "Unable to load the initial state from {}: {}",
+                    path,
+                    message
+                );
+                return;
+            }
+        };
+
+        let slice = match slice.to_string() {
+            slice if !slice.is_empty() => slice,
+            _ => Path::new(&path)
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+        };
+        if slice.is_empty() {
+            godot_error!("Unable to name the slice for {}, pass it a slice", path);
             return;
         }
 
-        let args = &[
-            Variant::from_dictionary(&self.state),
-            Variant::from_i64(action),
-        ];
-        let next = self.middleware[index].call_func(args);
-        let next_to_int = next.try_to_i64();
+        let state = with_slice(&self.readable_state(), &slice, data);
+        *self.state.borrow_mut() = self.sealed(&state);
 
-        match next_to_int {
-            Some(x) => self.dispatch_middleware(index + 1, x),
-            _ => return,
-        }
+        self.mark_readonly(owner, GodotString::from_str(slice));
     }
 
-    /// Runs the reducer for the specified action and then call any attached subscriptions.
+    /// Marks a slice of the state as read-only reference data, like an item
+    /// database. Reducers can read the slice but any change they make to it is
+    /// dropped with an error.
     ///
     /// # Arguments
     ///
-    /// * `action` - The action to run the reducer for.
-    fn dispatch_reducer(&mut self, action: i64) {
-        let args = &[
-            Variant::from_dictionary(&self.state),
-            Variant::from_i64(action),
-        ];
-        let new_state = self.reducer.call_func(args);
-
-        self.state = new_state.to_dictionary();
+    /// * `slice` - The key of the slice.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _ready():
+    ///     store.set_state_and_reducer({ "item_db": items, "player": {} }, self, "reducer")
+    ///     store.mark_readonly("item_db")
+    /// ```
+    #[export]
+    fn mark_readonly(&self, _owner: &Object, slice: GodotString) {
+        let slice = slice.to_string();
 
-        self.dispatch_subscriptions();
+        let mut readonly_slices = self.readonly_slices.borrow_mut();
+        if !readonly_slices.contains(&slice) {
+            readonly_slices.push(slice);
+        }
     }
 
-    /// Runs the subscriptions for the store.
-    fn dispatch_subscriptions(&self) {
-        let args = &[Variant::from_dictionary(&self.state)];
+    /// Loads a content pack, like a DLC, that adds reference data like items,
+    /// levels, or translations to the read-only slices of the state. The pack
+    /// has its id under `id` and the data it adds under `slices`, with a
+    /// Dictionary for each slice. Its data is merged into the slices however
+    /// deeply they are nested, and packs are merged in the order they're
+    /// loaded. A pack with a value that is already in a slice, from the game
+    /// or from another pack, isn't loaded unless it's allowed to override
+    /// values, in which case its value replaces the other one. Slices that
+    /// aren't in the state yet are added and marked read-only, while slices
+    /// that the reducer changes can't be added to.
+    ///
+    /// The subscriptions are run with the new state and the `content_loaded`
+    /// signal is emitted with the id of the pack. Returns whether the pack
+    /// could be loaded.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of a JSON file with the pack, or of a Resource with
+    /// the pack in its `data` property.
+    /// * `allow_overrides` - Optional, whether the pack can replace values
+    /// that are already loaded.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # res://dlc/frost.json:
+    /// # {
+    /// #     "id": "frost",
+    /// #     "slices": {
+    /// #         "item_db": { "frost_sword": { "damage": 14 } },
+    /// #         "translations": { "en": { "frost_sword": "Frost Sword" } }
+    /// #     }
+    /// # }
+    ///
+    /// func _ready():
+    ///     store.connect("content_loaded", self, "_on_content_loaded")
+    ///     store.load_content_pack("res://dlc/frost.json")
+    ///
+    /// func _on_content_loaded(pack_id):
+    ///     print("Loaded %s" % pack_id)
+    /// ```
+    #[export]
+    fn load_content_pack(
+        &self,
+        owner: &Object,
+        path: GodotString,
+        #[opt] allow_overrides: bool,
+    ) -> bool {
+        let path = path.to_string();
+        let pack = file::read_definitions(&Variant::from_str(&path), REFERENCE_DATA_PROPERTY)
+            .and_then(|definition| ContentPack::new(&definition, allow_overrides));
+        let pack = match pack {
+            Ok(pack) => pack,
+            Err(message) => {
+                godot_error!("Unable to load the content pack {}: {}", path, message);
+                return false;
+            }
+        };
+
+        let id = pack.id().to_string();
+        if self.content_packs.borrow().contains(&id) {
+            godot_error!("The content pack {} is already loaded", id);
+            return false;
+        }
 
-        for subscription in &self.subscriptions {
-            subscription.call_func(args);
+        let state = self.readable_state();
+        let writable = pack.slice_keys().find(|slice| {
+            state.contains(*slice) && !self.readonly_slices.borrow().iter().any(|key| key == slice)
+        });
+        if let Some(slice) = writable {
+            godot_error!(
+                "Unable to load the content pack {} since the slice {} isn't read-only",
+                id,
+                slice
+            );
+            return false;
         }
+
+        let slices = match self.content_packs.borrow_mut().load(pack, &state) {
+            Ok(slices) => slices,
+            Err(message) => {
+                godot_error!("Unable to load the content pack {}: {}", path, message);
+                return false;
+            }
+        };
+        for (slice, _) in &slices {
+            self.mark_readonly(owner, GodotString::from_str(slice));
+        }
+        self.replace_content_slices(&slices);
+
+        owner.emit_signal("content_loaded", &[Variant::from_str(id)]);
+        true
     }
 
-    /// Subscribes to changes to the state. When a change to the state is made, the
-    /// callback function is run and passed the current state as an argument.
+    /// Removes a content pack loaded with `load_content_pack`, putting its
+    /// slices back together from the data the game has and the packs that
+    /// are still loaded. The subscriptions are run with the new state and the
+    /// `content_unloaded` signal is emitted with the id of the pack. Returns
+    /// whether the pack was loaded.
     ///
     /// # Arguments
     ///
-    /// * `callback_fn_instance` - The instance that contains the callback function.
-    /// * `param callback_fn_name` - The name of the callback function.
+    /// * `pack_id` - The id of the pack.
     ///
     /// # Example
     ///
     /// ```
-    /// const state = {
-    ///     "counter": 0,
-    /// }
-    ///
-    /// enum Action {
-    ///     INCREMENT,
-    ///     DECREMENT,
-    /// }
+    /// func _on_dlc_disabled():
+    ///     store.unload_content_pack("frost")
+    /// ```
+    #[export]
+    fn unload_content_pack(&self, owner: &Object, pack_id: GodotString) -> bool {
+        let pack_id = pack_id.to_string();
+        let slices = match self.content_packs.borrow_mut().unload(&pack_id) {
+            Some(slices) => slices,
+            None => {
+                godot_error!("The content pack {} isn't loaded", pack_id);
+                return false;
+            }
+        };
+        self.replace_content_slices(&slices);
+
+        owner.emit_signal("content_unloaded", &[Variant::from_str(pack_id)]);
+        true
+    }
+
+    /// Returns the ids of the content packs loaded with `load_content_pack`,
+    /// in the order they were loaded.
     ///
-    /// func reducer(state, action):
-    ///     match action:
-    ///         Action.INCREMENT:
-    ///             return {
-    ///                 "counter": state.counter + 1,
-    ///             }
-    ///         Action.DECREMENT:
-    ///             return {
-    ///                 "counter": state.counter - 1,
-    ///             }
+    /// # Example
     ///
+    /// ```
     /// func _ready():
-    ///     var store = Store.new()
-    ///     store.set_state_and_reducer(initial_state, self, 'reducer')
-    ///     store.subscribe(self, 'print_counter')
-    ///
-    /// func print_counter(state):
-    ///     print(state.counter)
+    ///     print(store.get_content_packs()) # ["frost", "swamp"]
     /// ```
     #[export]
-    fn subscribe(
-        &mut self,
-        _owner: &Object,
-        subscriber_fn_instance: Ref<Object, Shared>,
-        subscriber_fn_name: GodotString,
-    ) {
-        let subscribe_fn_ref = FuncRef::new();
-        subscribe_fn_ref.set_instance(subscriber_fn_instance);
-        subscribe_fn_ref.set_function(subscriber_fn_name);
+    fn get_content_packs(&self, _owner: &Object) -> VariantArray {
+        self.content_packs
+            .borrow()
+            .ids()
+            .iter()
+            .map(Variant::from_str)
+            .collect::<VariantArray<Unique>>()
+            .into_shared()
+    }
+
+    /// Replaces the read-only slices that content packs were loaded into or
+    /// removed from and runs the subscriptions with the new state.
+    ///
+    /// # Arguments
+    ///
+    /// * `slices` - The new data of the slices, by slice key.
+    fn replace_content_slices(&self, slices: &[(String, Dictionary)]) {
+        let old_state = self.readable_state();
+        let state = slices
+            .iter()
+            .fold(old_state.new_ref(), |state, (slice, data)| {
+                with_slice(&state, slice, data.new_ref())
+            });
 
-        self.subscriptions.push(subscribe_fn_ref);
+        *self.state.borrow_mut() = self.sealed(&state);
+        self.mark_dirty_slices(&old_state, &state);
+
+        self.sync_bound_fields();
+        self.dispatch_subscriptions();
     }
 
-    /// Adds a middleware function that can intercept a dispatch and modify the action
-    /// to be run before it reaches the reducer.
+    /// Returns the state returned by the reducer with the read-only slices put
+    /// back as they were before the action. A change made to one of them is
+    /// dropped with an error, while a slice the reducer left out is put back
+    /// quietly like the built-in slices are.
     ///
     /// # Arguments
     ///
-    /// * `middleware_fn_instance` - The instance that contains the middleware function.
-    /// * `middleware_fn_name` - The name of the middleware function.
+    /// * `action` - The action that was dispatched.
+    /// * `new_state` - The state returned by the reducer.
+    fn keep_readonly_slices(&self, action: &Variant, new_state: Dictionary) -> Dictionary {
+        if self.readonly_slices.borrow().is_empty() {
+            return new_state;
+        }
+
+        let state = self.readable_state();
+        let mut new_state = new_state;
+        for key in self.readonly_slices.borrow().iter() {
+            if !state.contains(key) {
+                continue;
+            }
+
+            let current = state.get(key);
+            if new_state.contains(key) {
+                // Checking the reference first skips comparing big slices that
+                // the reducer passed along untouched.
+                let next = new_state.get(key);
+                if Equality::Reference.equals(&current, &next)
+                    || Equality::Deep.equals(&current, &next)
+                {
+                    continue;
+                }
+
+                godot_error!(
+                    "The read-only slice {} was modified by {}, the change was dropped",
+                    key,
+                    action.to_string()
+                );
+            }
+
+            new_state = with_slice(&new_state, key, current);
+        }
+
+        new_state
+    }
+
+    /// Returns the current state.
+    #[export]
+    fn state(&self, _owner: &Object) -> Dictionary<Unique> {
+        self.detect_tampering();
+
+        self.readable_state().duplicate()
+    }
+
+    /// Returns a copy of the state the store was created with in
+    /// `set_state_and_reducer`.
     ///
     /// # Example
     ///
     /// ```
-    /// func reverse_middleware(state, action):
-    ///     match action {
-    ///         Action.INCREMENT:
-    ///             return Action.DECREMENT
-    ///         Action.DECREMENT:
-    ///             return Action.INCREMENT
+    /// func _on_new_game_pressed():
+    ///     var fresh = store.get_initial_state()
+    /// ```
+    #[export]
+    fn get_initial_state(&self, _owner: &Object) -> Dictionary<Unique> {
+        self.initial_state.borrow().duplicate()
+    }
+
+    /// Returns the reducer the store was created with as a Dictionary with the
+    /// instance id of its instance under `instance_id` and its name under
+    /// `method`, or an empty Dictionary if the store wasn't created yet.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func test_store_uses_game_reducer():
+    ///     var info = store.get_reducer_info()
+    ///     assert_eq(instance_from_id(info.instance_id), self)
+    ///     assert_eq(info.method, "reducer")
+    /// ```
+    #[export]
+    fn get_reducer_info(&self, _owner: &Object) -> Dictionary<Unique> {
+        let info = Dictionary::new();
+        if let Some((instance_id, method)) = self.reducer_info.borrow().as_ref() {
+            info.insert(INSTANCE_ID_KEY, *instance_id);
+            info.insert(METHOD_KEY, method);
+        }
+
+        info
+    }
+
+    /// Returns every slice of the state along with what manages it, which is
+    /// `built_in` for the slices the store manages itself, like `forms` or
+    /// `quests`, `readonly` for the slices marked with `mark_readonly`, and
+    /// `reducer` for the rest.
     ///
+    /// # Example
+    ///
+    /// ```
     /// func _ready():
-    ///     var store = Store.new(state, self, 'reducer')
-    ///     store.add_middleware(self, 'reverse_middleware')
+    ///     print(store.get_registered_slices()) # { "player": "reducer", "quests": "built_in" }
+    /// ```
+    #[export]
+    fn get_registered_slices(&self, _owner: &Object) -> Dictionary<Unique> {
+        let readonly_slices = self.readonly_slices.borrow();
+        let slices = Dictionary::new();
+
+        for key in self.state.borrow().keys().iter() {
+            let name = key.to_string();
+            let kind = if readonly_slices.contains(&name) {
+                "readonly"
+            } else if BUILT_IN_SLICES.contains(&name.as_str()) {
+                "built_in"
+            } else {
+                "reducer"
+            };
+            slices.insert(key, kind);
+        }
+
+        slices
+    }
+
+    /// Returns everything a debugging tool, like a dock in an EditorPlugin,
+    /// needs to show what the store is doing while the game runs, in a
+    /// Dictionary with:
     ///
-    ///     # This will actually run the `DECREMENT` action because of our middleware.
-    ///     store.dispatch(Action.INCREMENT)
+    /// * `state` - A copy of the state.
+    /// * `slices` - Every slice along with what manages it, like in
+    ///   `get_registered_slices`.
+    /// * `recent_actions` - The last 50 actions that passed the reducer, with
+    ///   the latest one last, as Dictionaries with the action under `action`,
+    ///   the frame it was dispatched on under `frame`, and the time it was
+    ///   dispatched at under `time`.
+    /// * `subscription_count` - The number of subscriptions.
+    /// * `middleware_count` - The number of middleware functions.
+    /// * `frame` - The current frame.
+    /// * `stats` - The stats returned by `get_stats`, which are empty if
+    ///   telemetry isn't enabled.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _on_refresh_timer_timeout():
+    ///     var dump = store.debug_dump()
+    ///     $StateTree.show_state(dump.state)
+    ///     $ActionLog.show_actions(dump.recent_actions)
     /// ```
     #[export]
-    fn add_middleware(
-        &mut self,
-        _owner: &Object,
-        middleware_fn_instance: Ref<Object, Shared>,
-        middleware_fn_name: GodotString,
-    ) {
-        let middleware_fn_ref = FuncRef::new();
-        middleware_fn_ref.set_instance(middleware_fn_instance);
-        middleware_fn_ref.set_function(middleware_fn_name);
+    fn debug_dump(&self, owner: &Object) -> Dictionary<Unique> {
+        let recent_actions = self
+            .recent_actions
+            .borrow()
+            .iter()
+            .map(|(action, frame, time)| {
+                let entry = Dictionary::new();
+                entry.insert("action", action);
+                entry.insert("frame", *frame as i64);
+                entry.insert("time", *time);
+                entry
+            })
+            .collect::<VariantArray<Unique>>();
+
+        let dump = Dictionary::new();
+        dump.insert("state", self.readable_state().duplicate());
+        dump.insert("slices", self.get_registered_slices(owner));
+        dump.insert("recent_actions", recent_actions);
+        dump.insert("subscription_count", self.get_subscription_count(owner));
+        dump.insert("middleware_count", self.middleware.borrow().len() as i64);
+        dump.insert("frame", self.frame.get() as i64);
+        dump.insert("stats", self.get_stats(owner));
+
+        dump
+    }
+
+    /// Returns a description of how the store is set up, for tools like an
+    /// editor dock or an external dashboard that build their UI from it
+    /// instead of hardcoding the game's slices and actions, in a Dictionary
+    /// with:
+    ///
+    /// * `versions` - The version of the plugin under `store`, and the ones set
+    ///   with `set_game_version` and `set_state_version` under `game` and
+    ///   `state`.
+    /// * `slices` - Every slice along with what manages it, like in
+    ///   `get_registered_slices`.
+    /// * `reducer` - The instance id and method of the reducer, like in
+    ///   `get_reducer_info`.
+    /// * `action_types` - Every action type the store was told about, with
+    ///   what it was registered with, out of `schema`, `gate`, `dedupe`,
+    ///   `signal`, `effect`, `pipeline`, `alias`, and `cheat`.
+    /// * `middleware` - The middleware, in the order it runs in, as
+    ///   Dictionaries with its id under `id`, the instance and function it
+    ///   calls under `name`, and whether it has side effects under
+    ///   `side_effects`.
+    /// * `subsystems` - Whether each of the store's optional systems is
+    ///   enabled, by name, like `undo`, `router`, or `telemetry`.
+    /// * `codecs` - The names of the codecs, like in `get_codecs`.
+    /// * `macros` - The names of the macros defined with `define_macro`.
+    /// * `subscription_count` - The number of subscriptions.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _ready():
+    ///     var description = store.describe()
+    ///     for action_type in description.action_types:
+    ///         $ActionPicker.add_item(str(action_type))
+    /// ```
+    #[export]
+    fn describe(&self, owner: &Object) -> Dictionary<Unique> {
+        let versions = Dictionary::new();
+        versions.insert("store", env!("CARGO_PKG_VERSION"));
+        versions.insert("game", &self.save_info.borrow().game_version);
+        versions.insert("state", self.save_info.borrow().state_version);
+
+        let mut registered: Vec<(Variant, Vec<&'static str>)> = vec![];
+        let mut register = |action_type: &Variant, feature: &'static str| match registered
+            .iter_mut()
+            .find(|(registered_type, _)| registered_type == action_type)
+        {
+            Some((_, features)) if features.contains(&feature) => {}
+            Some((_, features)) => features.push(feature),
+            None => registered.push((action_type.clone(), vec![feature])),
+        };
+        for (action_type, _) in self.action_schemas.borrow().iter() {
+            register(action_type, "schema");
+        }
+        for (action_type, _) in self.flag_gates.borrow().iter() {
+            register(action_type, "gate");
+        }
+        for action_type in self.dedupe.borrow().action_types() {
+            register(action_type, "dedupe");
+        }
+        for (action_type, _) in self.action_signals.borrow().iter() {
+            register(action_type, "signal");
+        }
+        for binding in self.effect_bindings.borrow().iter() {
+            register(binding.action_type(), "effect");
+        }
+        for action_type in self.pipelines.borrow().action_types() {
+            register(action_type, "pipeline");
+        }
+        for alias in self.action_aliases.borrow().iter() {
+            register(alias.old_type(), "alias");
+        }
+        for action_type in self.cheats.borrow().action_types() {
+            register(&action_type, "cheat");
+        }
+
+        let action_types = Dictionary::new();
+        for (action_type, features) in registered {
+            let features = features
+                .into_iter()
+                .map(Variant::from_str)
+                .collect::<VariantArray<Unique>>();
+            action_types.insert(action_type, features);
+        }
+
+        let middleware = self
+            .middleware
+            .borrow()
+            .iter()
+            .map(|(id, middleware)| {
+                let entry = Dictionary::new();
+                entry.insert("id", id);
+                entry.insert("name", &middleware.identity);
+                entry.insert("side_effects", middleware.side_effects);
+                entry
+            })
+            .collect::<VariantArray<Unique>>();
+
+        let subsystems = Dictionary::new();
+        subsystems.insert("undo", self.undo_stack.borrow().is_some());
+        subsystems.insert("history", self.history.borrow().is_some());
+        subsystems.insert("event_log", self.event_log.borrow().is_some());
+        subsystems.insert("router", self.router.borrow().is_some());
+        subsystems.insert("forms", !self.forms.borrow().is_empty());
+        subsystems.insert("queries", !self.queries.borrow().is_empty());
+        subsystems.insert("stats", self.stats.borrow().is_some());
+        subsystems.insert("inventory", self.inventory.borrow().is_some());
+        subsystems.insert("quests", self.quests.borrow().is_some());
+        subsystems.insert("dialogues", !self.dialogues.borrow().is_empty());
+        subsystems.insert("machines", !self.machines.borrow().is_empty());
+        subsystems.insert("tick", self.tick.borrow().is_some());
+        subsystems.insert("audio", self.audio.borrow().is_some());
+        subsystems.insert("telemetry", self.telemetry.borrow().is_some());
+        subsystems.insert(
+            "session_analytics",
+            self.session_analytics.borrow().is_some(),
+        );
+        subsystems.insert("logging", self.logger.borrow().is_some());
+        subsystems.insert("persistence", self.persisted.borrow().is_some());
+        subsystems.insert("encryption", self.encryption.borrow().is_some());
+        subsystems.insert("protection", self.protection.borrow().is_some());
+        subsystems.insert("state_schema", self.state_schema.borrow().is_some());
+        subsystems.insert("access_audit", self.access_audit.borrow().is_some());
+        subsystems.insert("input_recording", self.input_recording.borrow().is_some());
+        subsystems.insert("devtools", self.devtools.borrow().is_some());
+        subsystems.insert("error_policy", self.error_policy.borrow().is_some());
+        subsystems.insert("tree", self.tree.borrow().is_some());
+
+        let macros = self
+            .macros
+            .borrow()
+            .keys()
+            .map(Variant::from_str)
+            .collect::<VariantArray<Unique>>();
+
+        let description = Dictionary::new();
+        description.insert("versions", versions);
+        description.insert("slices", self.get_registered_slices(owner));
+        description.insert("reducer", self.get_reducer_info(owner));
+        description.insert("action_types", action_types);
+        description.insert("middleware", middleware);
+        description.insert("subsystems", subsystems);
+        description.insert("codecs", self.get_codecs(owner));
+        description.insert("macros", macros);
+        description.insert("subscription_count", self.get_subscription_count(owner));
+
+        description
+    }
+
+    /// Returns the last action that passed the reducer, or `null` if no
+    /// action has yet. The `action_dispatched` signal is emitted with every
+    /// action that passes the reducer, once the state has been updated, for
+    /// tools that follow the actions as they happen.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _on_store_action_dispatched(action):
+    ///     $ActionLog.add_item(str(action))
+    /// ```
+    #[export]
+    fn get_last_action(&self, _owner: &Object) -> Variant {
+        self.recent_actions
+            .borrow()
+            .back()
+            .map_or_else(Variant::new, |(action, _, _)| action.clone())
+    }
+
+    /// Returns the number of subscriptions, counting the ones added with
+    /// `subscribe`, `subscribe_selector`, and `subscribe_to_path`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _exit_tree():
+    ///     print("%d subscriptions are still registered" % store.get_subscription_count())
+    /// ```
+    #[export]
+    fn get_subscription_count(&self, _owner: &Object) -> i64 {
+        self.subscription_count() as i64
+    }
+
+    /// Unsubscribes the subscriptions whose instance was freed and returns
+    /// how many there were. This happens on its own before the subscriptions
+    /// are run, so it only needs to be called to let go of them right away,
+    /// for example after a scene full of subscribed nodes was freed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _on_level_unloaded():
+    ///     store.prune_subscriptions()
+    /// ```
+    #[export]
+    fn prune_subscriptions(&self, _owner: &Object) -> i64 {
+        self.drop_freed_subscriptions() as i64
+    }
+
+    /// Unsubscribes the subscriptions whose instance was freed and returns
+    /// how many there were.
+    fn drop_freed_subscriptions(&self) -> usize {
+        let count = self.subscription_count();
+
+        self.subscriptions
+            .borrow_mut()
+            .retain(|(_, subscriber)| subscriber.is_alive());
+        self.selector_subscriptions
+            .borrow_mut()
+            .retain(|(_, subscription)| subscription.is_alive());
+        self.path_subscriptions
+            .borrow_mut()
+            .retain(|(_, subscription)| subscription.is_alive());
+
+        count - self.subscription_count()
+    }
+
+    /// Returns the number of subscriptions of every kind.
+    fn subscription_count(&self) -> usize {
+        self.subscriptions.borrow().len()
+            + self.selector_subscriptions.borrow().len()
+            + self.path_subscriptions.borrow().len()
+    }
+
+    /// Keeps an action that passed the reducer for `debug_dump` and
+    /// `get_last_action`, dropping the oldest one once there are too many.
+    ///
+    /// # Arguments
+    ///
+    /// * `action` - The action that was dispatched.
+    fn record_recent_action(&self, action: &Variant) {
+        let time = self.now();
+        let mut recent_actions = self.recent_actions.borrow_mut();
+        recent_actions.push_back((action.clone(), self.frame.get(), time));
+        if recent_actions.len() > RECENT_ACTIONS {
+            recent_actions.pop_front();
+        }
+    }
+
+    /// Returns the value at a path in the state, where each key in the path is
+    /// separated by a `/`. Returns `null` if the path doesn't exist.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path to the value, for example `player/hp`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _ready():
+    ///     var store = Store.new()
+    ///     store.set_state_and_reducer({ "player": { "hp": 10 } }, self, 'reducer')
+    ///
+    ///     print(store.get_value("player/hp")) # 10
+    /// ```
+    #[export]
+    fn get_value(&self, _owner: &Object, path: GodotString) -> Variant {
+        self.read_path(&path.to_string())
+            .unwrap_or_else(Variant::new)
+    }
+
+    /// Returns whether a path exists in the state, even if the value at the
+    /// path is `null`.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path to check, for example `player/hp`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _ready():
+    ///     if store.has_path("player/pet"):
+    ///         $Pet.show()
+    /// ```
+    #[export]
+    fn has_path(&self, _owner: &Object, path: GodotString) -> bool {
+        self.read_path(&path.to_string()).is_some()
+    }
+
+    /// Returns the type of the value at a path in the state, which is one of
+    /// the `TYPE_*` constants also returned by `typeof`. Returns `TYPE_NIL` if
+    /// the path doesn't exist.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path to the value, for example `player/hp`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _ready():
+    ///     if store.get_type("player/inventory") == TYPE_ARRAY:
+    ///         show_inventory()
+    /// ```
+    #[export]
+    fn get_type(&self, _owner: &Object, path: GodotString) -> i64 {
+        self.read_path(&path.to_string())
+            .map_or(VariantType::Nil, |value| value.get_type()) as i64
+    }
+
+    /// Returns the keys of the Dictionary at a path in the state, or the
+    /// indices of the Array at the path. Returns an empty Array if the path
+    /// doesn't exist or has any other value. An empty path lists the keys of
+    /// the whole state.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path to the Dictionary or Array, for example `party`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _ready():
+    ///     for member in store.list_keys("party"):
+    ///         add_portrait(member)
+    /// ```
+    #[export]
+    fn list_keys(&self, _owner: &Object, path: GodotString) -> VariantArray<Unique> {
+        match self.read_path(&path.to_string()) {
+            Some(value) if value.get_type() == VariantType::Dictionary => {
+                value.to_dictionary().keys()
+            }
+            Some(value) if value.get_type() == VariantType::VariantArray => {
+                (0..value.to_array().len())
+                    .map(|index| Variant::from_i64(index as i64))
+                    .collect()
+            }
+            _ => VariantArray::new(),
+        }
+    }
+
+    /// Returns the top-level keys of the state that changed since the last
+    /// time this was called, so systems that poll once per frame can skip
+    /// their work when their slice didn't change. The list is cleared when
+    /// it's read unless `peek` is true, which lets several systems read the
+    /// same list before the last one clears it.
+    ///
+    /// # Arguments
+    ///
+    /// * `peek` - Optional, whether to leave the list as it is.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _process(delta):
+    ///     if "world" in store.get_dirty_slices():
+    ///         redraw_world(store.get_value("world"))
+    /// ```
+    #[export]
+    fn get_dirty_slices(&self, _owner: &Object, #[opt] peek: bool) -> VariantArray<Unique> {
+        let dirty_slices = if peek {
+            self.dirty_slices.borrow().clone()
+        } else {
+            self.dirty_slices.replace(vec![])
+        };
+
+        dirty_slices.iter().map(Variant::from_str).collect()
+    }
+
+    /// Returns a blend between the value at a path before the last dispatch
+    /// and its value now, so rendering code can smooth values that
+    /// only change on physics ticks without keeping its own history. Numbers,
+    /// `Vector2`s, `Vector3`s, and `Color`s are blended, along with the ones
+    /// inside of Dictionaries and Arrays, and any other value, or a value that
+    /// didn't exist before the last dispatch, is returned as it is. Returns
+    /// `null` if the path doesn't exist.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path to the value, for example `player/position`.
+    /// * `weight` - How far to blend from the previous value to the current
+    /// one, from 0 to 1, which is usually the physics interpolation fraction.
+    /// Weights outside of this are clamped.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _process(delta):
+    ///     var weight = Engine.get_physics_interpolation_fraction()
+    ///     $Player.position = store.get_interpolated("player/position", weight)
+    /// ```
+    #[export]
+    fn get_interpolated(&self, _owner: &Object, path: GodotString, weight: f64) -> Variant {
+        let path = path.to_string();
+        let current = match self.read_path(&path) {
+            Some(current) => current,
+            None => return Variant::new(),
+        };
+
+        let previous_state = self.previous_state.borrow().new_ref();
+        let previous_state = match self.protection.borrow().as_ref() {
+            Some(protection) => protection.unseal_state(&previous_state).0,
+            None => previous_state,
+        };
+
+        path::get(&previous_state, &path)
+            .and_then(|previous| interpolation::blend(&previous, &current, weight))
+            .unwrap_or(current)
+    }
+
+    /// Adds the top-level keys that changed between two states to the dirty
+    /// slices.
+    ///
+    /// # Arguments
+    ///
+    /// * `old_state` - The state before the change.
+    /// * `new_state` - The state after the change.
+    fn mark_dirty_slices(&self, old_state: &Dictionary, new_state: &Dictionary) {
+        let mut dirty_slices = self.dirty_slices.borrow_mut();
+        for key in diff::changed_keys(old_state, new_state) {
+            if !dirty_slices.contains(&key) {
+                dirty_slices.push(key);
+            }
+        }
+    }
+
+    /// Returns the value at a path in the state, or `None` if the path doesn't
+    /// exist, recording the read if the access audit is enabled.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path to the value.
+    fn read_path(&self, path: &str) -> Option<Variant> {
+        if let Some(audit) = self.access_audit.borrow_mut().as_mut() {
+            audit.record_read(path);
+        }
+
+        self.detect_tampering();
+
+        path::get(&self.readable_state(), path)
+    }
+
+    /// Registers a view model, which is a flat Dictionary made from values in
+    /// the state that UI code can bind to directly. Each field of the view
+    /// model is taken from a path of the state and can be formatted into a
+    /// String with the same placeholders as GDScript's `%` operator. A field
+    /// is only computed again when the value at its path changes.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the view model.
+    /// * `mapping` - The source of each field, by key, which is either a path
+    /// or a Dictionary with the path under `path` and a format under `format`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _ready():
+    ///     store.register_view_model("hud", {
+    ///         "hp": "player/hp",
+    ///         "gold_label": { "path": "player/gold", "format": "%d gold" },
+    ///     })
+    /// ```
+    #[export]
+    fn register_view_model(&self, _owner: &Object, name: GodotString, mapping: Dictionary) {
+        match ViewModel::new(&mapping) {
+            Ok(view_model) => {
+                self.view_models
+                    .borrow_mut()
+                    .insert(name.to_string(), view_model);
+            }
+            Err(message) => godot_error!("Unable to register the view model {}: {}", name, message),
+        }
+    }
+
+    /// Returns a view model registered with `register_view_model` for the
+    /// current state.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the view model.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _on_state_changed():
+    ///     var hud = store.view_model("hud")
+    ///     $GoldLabel.text = hud.gold_label
+    /// ```
+    #[export]
+    fn view_model(&self, _owner: &Object, name: GodotString) -> Dictionary<Unique> {
+        self.detect_tampering();
+
+        let state = self.readable_state();
+        let mut view_models = self.view_models.borrow_mut();
+        let view_model = match view_models.get_mut(&name.to_string()) {
+            Some(view_model) => view_model,
+            None => {
+                godot_error!("The view model {} isn't registered", name);
+                return Dictionary::new();
+            }
+        };
+
+        if let Some(audit) = self.access_audit.borrow_mut().as_mut() {
+            view_model.paths().for_each(|path| audit.record_read(path));
+        }
+
+        view_model.compute(&state)
+    }
+
+    /// Registers a selector that computes derived data from the state, like
+    /// the enemies the player can see, and remembers what it returned so
+    /// `select` only runs it again once the state changed. If input paths are
+    /// given, the selector is only run again once the value at one of them
+    /// changed. Registering a selector with a name that is already used
+    /// replaces the other one.
+    ///
+    /// The equality used to compare the inputs can be `reference`, which is
+    /// the cheapest and the default, `shallow`, `deep`, or a FuncRef, like in
+    /// `subscribe_selector`.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the selector.
+    /// * `selector_fn_instance` - The instance that contains the selector function.
+    /// * `selector_fn_name` - The name of the selector function, which is
+    /// passed the state.
+    /// * `inputs` - Optional paths of the state the selector depends on.
+    /// * `equality` - Optional name of the equality or a FuncRef to compare
+    /// the inputs with.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _ready():
+    ///     store.register_selector("visible_enemies", self, "compute_visible", ["enemies", "player/position"])
+    ///
+    /// func compute_visible(state):
+    ///     var visible = []
+    ///     for enemy in state.enemies:
+    ///         if enemy.position.distance_to(state.player.position) < SIGHT:
+    ///             visible.append(enemy)
+    ///     return visible
+    /// ```
+    #[export]
+    fn register_selector(
+        &self,
+        _owner: &Object,
+        name: GodotString,
+        selector_fn_instance: Ref<Object, Shared>,
+        selector_fn_name: GodotString,
+        #[opt] inputs: VariantArray,
+        #[opt] equality: Variant,
+    ) {
+        let equality = match Equality::from_variant(&equality) {
+            Ok(equality) => equality,
+            Err(message) => {
+                godot_error!("Unable to register the selector {}: {}", name, message);
+                return;
+            }
+        };
+
+        let selector = MemoizedSelector::new(
+            Subscriber::new(selector_fn_instance, selector_fn_name),
+            inputs.iter().map(|input| input.to_string()).collect(),
+            equality,
+        );
+        self.memoized_selectors
+            .borrow_mut()
+            .insert(name.to_string(), selector);
+    }
+
+    /// Returns what a selector registered with `register_selector` computes
+    /// for the current state. The selector is only run if the state, or one
+    /// of its inputs, changed since the last time, and otherwise the value it
+    /// returned last is returned again. Returns `null` if there is no selector
+    /// with the name or it couldn't be called.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the selector.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _process(delta):
+    ///     for enemy in store.select("visible_enemies"):
+    ///         draw_marker(enemy)
+    /// ```
+    #[export]
+    fn select(&self, _owner: &Object, name: GodotString) -> Variant {
+        self.detect_tampering();
+
+        // The inputs are read from the sealed state, which stays the same
+        // Dictionary until the state changes, and the selector is called
+        // after the selectors are let go of since it could select from
+        // another one.
+        let name = name.to_string();
+        let state = self.state.borrow().new_ref();
+        let (inputs, selector) = match self.memoized_selectors.borrow().get(&name) {
+            Some(memoized) => {
+                let inputs = memoized.inputs(&state);
+                if let Some(value) = memoized.cached(&inputs) {
+                    return value;
+                }
+
+                (inputs, memoized.selector().clone())
+            }
+            None => {
+                godot_error!("The selector {} isn't registered", name);
+                return Variant::new();
+            }
+        };
+
+        let value = match selector.call(&[Variant::from_dictionary(&self.readable_state())]) {
+            Ok(value) => value,
+            Err(message) => {
+                self.report_subscriber_error(&selector, &message);
+                return Variant::new();
+            }
+        };
+
+        if let Some(memoized) = self.memoized_selectors.borrow_mut().get_mut(&name) {
+            memoized.remember(inputs, value.clone());
+        }
+
+        value
+    }
+
+    /// Returns a page of an array in the state sorted by a value in each of
+    /// its items, like a page of a leaderboard or of a large inventory. The
+    /// array isn't copied or sorted as a whole, only the values it's sorted by
+    /// are, and only the items in the page are returned. Items with the same
+    /// value keep the order they have in the array, and items without the
+    /// value come last. Numbers are sorted by value, Strings alphabetically,
+    /// and any other value by its text. Returns an empty Array if the value at
+    /// the path isn't an Array.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path to the array, for example `leaderboard/entries`.
+    /// * `start` - The index of the first item of the page in the sorted items.
+    /// * `count` - The most items in the page.
+    /// * `sort_key` - The path to the value in each item to sort by, for example
+    /// `score`. Defaults to sorting by the items themselves.
+    /// * `descending` - Whether the items with the largest values come first.
+    /// Defaults to `false`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func show_page(page):
+    ///     var rows = store.select_window("leaderboard", page * 20, 20, "score", true)
+    ///     for row in rows:
+    ///         add_row(row.name, row.score)
+    /// ```
+    #[export]
+    fn select_window(
+        &self,
+        _owner: &Object,
+        path: GodotString,
+        start: i64,
+        count: i64,
+        #[opt] sort_key: GodotString,
+        #[opt] descending: bool,
+    ) -> VariantArray<Unique> {
+        if start < 0 || count < 0 {
+            godot_error!(
+                "The start and count of a window can't be negative, got {} and {}",
+                start,
+                count
+            );
+            return VariantArray::new();
+        }
+
+        let path = path.to_string();
+        match self.read_path(&path).and_then(|value| value.try_to_array()) {
+            Some(array) => window::select(
+                &array,
+                start as usize,
+                count as usize,
+                &sort_key.to_string(),
+                descending,
+            ),
+            None => {
+                godot_error!("The value at {} isn't an Array", path);
+                VariantArray::new()
+            }
+        }
+    }
+
+    /// Protects a path of the state, like a currency that players might try
+    /// to change with a memory editor. The value at the path is kept
+    /// obfuscated in the state along with a checksum and is only turned back
+    /// into the plain value when it's read, so the reducer, middleware, and
+    /// subscriptions see it as usual. If the value is changed without going
+    /// through the store it's dropped, so it reads as `null`, and a
+    /// `TAMPER_DETECTED` action is dispatched with the path under `path` so the
+    /// reducer can decide what to do about it. Values are checked before each
+    /// dispatch and when the state is read with `state` or `get_value`.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path to protect, for example `player/gold`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _ready():
+    ///     store.protect_path("player/gold")
+    ///
+    /// func reducer(state, action):
+    ///     if action is Dictionary and action.type == "TAMPER_DETECTED":
+    ///         return load_last_save()
+    /// ```
+    #[export]
+    fn protect_path(&self, _owner: &Object, path: GodotString) {
+        self.protection
+            .borrow_mut()
+            .get_or_insert_with(Protection::default)
+            .add_path(&path.to_string());
+
+        let state = self.sealed(&self.state.borrow());
+        *self.state.borrow_mut() = state;
+    }
+
+    /// Returns the state with the values at the protected paths turned back
+    /// into their plain values, which is the state everything outside of the
+    /// store gets to see.
+    fn readable_state(&self) -> Dictionary {
+        let state = self.state.borrow().new_ref();
+
+        match self.protection.borrow().as_ref() {
+            Some(protection) => protection.unseal_state(&state).0,
+            None => state,
+        }
+    }
+
+    /// Returns a state with the values at the protected paths sealed so it can
+    /// be kept as the state of the store.
+    ///
+    /// # Arguments
+    ///
+    /// * `state` - The state with plain values.
+    fn sealed(&self, state: &Dictionary) -> Dictionary {
+        match self.protection.borrow().as_ref() {
+            Some(protection) => protection.seal_state(state),
+            None => state.new_ref(),
+        }
+    }
+
+    /// Checks that none of the protected values were changed without going
+    /// through the store, dropping the ones that were and dispatching a
+    /// `TAMPER_DETECTED` action for each of them.
+    fn detect_tampering(&self) {
+        let (state, tampered) = match self.protection.borrow().as_ref() {
+            Some(protection) => protection.drop_tampered(&self.state.borrow()),
+            None => return,
+        };
+        if tampered.is_empty() {
+            return;
+        }
+
+        *self.state.borrow_mut() = state;
+        for path in tampered {
+            godot_warn!(
+                "The protected value at {} was changed without going through the store",
+                path
+            );
+            self.dispatch_action(protection::tamper_detected(&path).owned_to_variant());
+        }
+    }
+
+    /// Turns the access audit on or off. While it is on, the store records
+    /// every path read with `get_value` and every path changed by each action
+    /// so that unused state and reducers that change more than they should can
+    /// be found with `get_access_report`. Turning the audit on clears anything
+    /// recorded previously.
+    ///
+    /// # Arguments
+    ///
+    /// * `enabled` - Whether the audit should be on or off.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _ready():
+    ///     var store = Store.new()
+    ///     store.set_state_and_reducer(state, self, 'reducer')
+    ///     store.set_access_audit(true)
+    /// ```
+    #[export]
+    fn set_access_audit(&self, _owner: &Object, enabled: bool) {
+        *self.access_audit.borrow_mut() = if enabled {
+            Some(AccessAudit::default())
+        } else {
+            None
+        };
+    }
+
+    /// Returns what the access audit has recorded so far as a Dictionary with
+    /// the paths that were read and how many times under `reads`, the paths
+    /// changed by each action under `writes`, and the paths in the current
+    /// state that were never read or written under `unused`. Returns an empty
+    /// Dictionary if the audit is off.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _ready():
+    ///     var store = Store.new()
+    ///     store.set_state_and_reducer(state, self, 'reducer')
+    ///     store.set_access_audit(true)
+    ///
+    ///     store.dispatch(Action.INCREMENT)
+    ///     store.get_value("counter")
+    ///
+    ///     # { "reads": { "counter": 1 }, "writes": { 0: ["counter"] }, "unused": [] }
+    ///     print(store.get_access_report())
+    /// ```
+    #[export]
+    fn get_access_report(&self, _owner: &Object) -> Dictionary<Unique> {
+        match self.access_audit.borrow().as_ref() {
+            Some(audit) => audit.report(&self.state.borrow()),
+            None => Dictionary::new(),
+        }
+    }
+
+    /// Turns the test mode on or off. While it is on, every dispatched action,
+    /// including the ones dispatched by middleware, reducers, and
+    /// subscriptions, is recorded so tests can check what was dispatched with
+    /// `get_dispatched_actions`, `expect_action`, and
+    /// `assert_no_unexpected_dispatches`. Turning the test mode on clears any
+    /// actions recorded previously.
+    ///
+    /// # Arguments
+    ///
+    /// * `enabled` - Whether the test mode should be on or off.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func test_counter():
+    ///     var store = Store.new()
+    ///     store.set_state_and_reducer(state, self, 'reducer')
+    ///     store.set_test_mode(true)
+    /// ```
+    #[export]
+    fn set_test_mode(&self, _owner: &Object, enabled: bool) {
+        *self.dispatch_log.borrow_mut() = if enabled {
+            Some(DispatchLog::default())
+        } else {
+            None
+        };
+    }
+
+    /// Returns every action dispatched since the test mode was turned on, in
+    /// the order they were dispatched.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func test_counter():
+    ///     var store = Store.new()
+    ///     store.set_state_and_reducer(state, self, 'reducer')
+    ///     store.set_test_mode(true)
+    ///
+    ///     store.dispatch(Action.INCREMENT)
+    ///
+    ///     assert_eq(store.get_dispatched_actions(), [Action.INCREMENT])
+    /// ```
+    #[export]
+    fn get_dispatched_actions(&self, _owner: &Object) -> VariantArray<Unique> {
+        match self.dispatch_log.borrow().as_ref() {
+            Some(log) => log.actions(),
+            None => VariantArray::new(),
+        }
+    }
+
+    /// Checks that an action of the type was dispatched since the test mode was
+    /// turned on and marks it as expected. Each dispatched action can only be
+    /// expected once, so expecting the same action twice checks that it was
+    /// dispatched twice. Prints an error and returns `false` if no matching
+    /// action was dispatched.
+    ///
+    /// # Arguments
+    ///
+    /// * `action_type` - The type of the action that should have been dispatched.
+    /// * `payload_matcher` - Optionally, either a Dictionary of keys and values
+    /// the action has to contain or a FuncRef that is passed the action and
+    /// returns whether it matches.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func test_pickup():
+    ///     var store = Store.new()
+    ///     store.set_state_and_reducer(state, self, 'reducer')
+    ///     store.set_test_mode(true)
+    ///
+    ///     chest.open()
+    ///
+    ///     assert_true(store.expect_action(Action.ADD_ITEM, { "item": "sword" }))
+    ///     assert_true(store.expect_action(Action.ADD_GOLD, funcref(self, "is_over_100")))
+    /// ```
+    #[export]
+    fn expect_action(
+        &self,
+        _owner: &Object,
+        action_type: Variant,
+        #[opt] payload_matcher: Variant,
+    ) -> bool {
+        let unmatched = match self.dispatch_log.borrow().as_ref() {
+            Some(log) => log.unmatched(),
+            None => {
+                godot_error!("expect_action can only be used while the test mode is on");
+                return false;
+            }
+        };
+
+        // The log isn't borrowed while matching since the matcher can be a
+        // FuncRef that calls back into the store.
+        for (index, action) in unmatched {
+            if testing::matches(&action, &action_type, &payload_matcher) {
+                if let Some(log) = self.dispatch_log.borrow_mut().as_mut() {
+                    log.mark_matched(index);
+                }
+                return true;
+            }
+        }
+
+        godot_error!(
+            "Expected an action of type {} matching {} to be dispatched but none was",
+            action_type.to_string(),
+            payload_matcher.to_string()
+        );
+        false
+    }
+
+    /// Checks that every action dispatched since the test mode was turned on
+    /// has been matched by `expect_action`. Prints an error listing the
+    /// unexpected actions and returns `false` if any weren't.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func test_pickup():
+    ///     var store = Store.new()
+    ///     store.set_state_and_reducer(state, self, 'reducer')
+    ///     store.set_test_mode(true)
+    ///
+    ///     chest.open()
+    ///
+    ///     assert_true(store.expect_action(Action.ADD_ITEM))
+    ///     assert_true(store.assert_no_unexpected_dispatches())
+    /// ```
+    #[export]
+    fn assert_no_unexpected_dispatches(&self, _owner: &Object) -> bool {
+        let unexpected = match self.dispatch_log.borrow().as_ref() {
+            Some(log) => log.unmatched(),
+            None => {
+                godot_error!(
+                    "assert_no_unexpected_dispatches can only be used while the test mode is on"
+                );
+                return false;
+            }
+        };
+
+        if unexpected.is_empty() {
+            return true;
+        }
+
+        let unexpected = unexpected
+            .iter()
+            .map(|(_, action)| action.to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+        godot_error!("Unexpected actions were dispatched: {}", unexpected);
+        false
+    }
+
+    /// Runs a list of actions through the middleware and the reducer starting
+    /// from the initial state, one after another and without waiting on
+    /// anything, and returns the state after the last one. This makes it
+    /// possible to check the outcome of a sequence of actions
+    /// deterministically, for example in headless CI runs. The subscriptions
+    /// and the middleware with side effects aren't run during the scenario
+    /// and the state of the store is put back the way it was once the
+    /// scenario is done.
+    ///
+    /// # Arguments
+    ///
+    /// * `initial_state` - The state to start the scenario from.
+    /// * `actions` - The actions to dispatch, in order.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func test_counter():
+    ///     var store = Store.new()
+    ///     store.set_state_and_reducer(state, self, 'reducer')
+    ///
+    ///     var final_state = store.run_scenario({ "counter": 0 }, [
+    ///         Action.INCREMENT,
+    ///         Action.INCREMENT,
+    ///         Action.DECREMENT,
+    ///     ])
+    ///
+    ///     assert_eq(final_state.counter, 1)
+    /// ```
+    #[export]
+    fn run_scenario(
+        &self,
+        _owner: &Object,
+        initial_state: Dictionary,
+        actions: VariantArray,
+    ) -> Dictionary<Unique> {
+        let previous_state = self
+            .state
+            .replace(self.sealed(&initial_state.duplicate().into_shared()));
+        let was_running_scenario = self.running_scenario.replace(true);
+
+        for action in actions.iter() {
+            self.dispatch_action(action);
+        }
+
+        self.running_scenario.set(was_running_scenario);
+        let final_state = self.readable_state();
+        *self.state.borrow_mut() = previous_state;
+
+        final_state.duplicate()
+    }
+
+    /// Returns what dispatching an action would do without changing the
+    /// state. The action is run through the middleware and the reducer like
+    /// it would be in a scenario, so no signals are emitted and no
+    /// subscriptions or effects run. It isn't recorded in the dispatch log or
+    /// the telemetry either, and an error that would halt dispatching only
+    /// halts the preview. The result has the state the action would lead to
+    /// under `state` and every path it would change under `diff`, with the old
+    /// and new values of each path in an Array.
+    ///
+    /// # Arguments
+    ///
+    /// * `action` - The action to preview.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _on_upgrade_hovered():
+    ///     var preview = store.preview({ "type": Action.UPGRADE, "item": "sword" })
+    ///     for path in preview.diff:
+    ///         print("%s: %s -> %s" % [path, preview.diff[path][0], preview.diff[path][1]])
+    /// ```
+    #[export]
+    fn preview(&self, _owner: &Object, action: Variant) -> Dictionary<Unique> {
+        let previous_state = self.state.borrow().new_ref();
+        let old_state = self.readable_state();
+        let was_running_scenario = self.running_scenario.replace(true);
+        let was_halted = self.dispatch_halted.get();
+
+        self.dispatch_action(action);
+
+        self.running_scenario.set(was_running_scenario);
+        self.dispatch_halted.set(was_halted);
+        let new_state = self.readable_state();
+        *self.state.borrow_mut() = previous_state;
+
+        let diff = Dictionary::new();
+        for changed in diff::changed_paths(&old_state, &new_state) {
+            let values = VariantArray::new();
+            values.push(path::get(&old_state, &changed).unwrap_or_else(Variant::new));
+            values.push(path::get(&new_state, &changed).unwrap_or_else(Variant::new));
+            diff.insert(changed, values);
+        }
+
+        let preview = Dictionary::new();
+        preview.insert(PREVIEW_STATE_KEY, new_state.duplicate());
+        preview.insert(PREVIEW_DIFF_KEY, diff);
+
+        preview
+    }
+
+    /// Runs the invariant checker passed to `fuzz` against the current state and
+    /// returns the reason it failed, or `None` if the invariants hold. The
+    /// state has to match the state schema too, if there is one.
+    ///
+    /// # Arguments
+    ///
+    /// * `invariant_checker` - The function that checks the state.
+    /// * `action` - The action that was just dispatched.
+    fn check_invariants(
+        &self,
+        invariant_checker: &Ref<FuncRef, Shared>,
+        action: &Variant,
+    ) -> Option<String> {
+        let state = self.readable_state();
+        let errors = self.state_schema_errors(&state);
+        if !errors.is_empty() {
+            return Some(format!(
+                "the state doesn't match the schema: {}",
+                errors.join(", ")
+            ));
+        }
+
+        let args = &[Variant::from_dictionary(&state), action.clone()];
+        let result = unsafe { invariant_checker.assume_safe() }.call_func(args);
+
+        if result.try_to_bool() == Some(true) {
+            return None;
+        }
+
+        match result.try_to_string() {
+            Some(reason) => Some(reason),
+            None => Some("the invariant checker didn't return true".to_string()),
+        }
+    }
+
+    /// Dispatches a list of actions one after another starting from a state,
+    /// without running the subscriptions, and runs the invariant checker after
+    /// each one. Returns the reason the checker failed, or `None` if it never
+    /// failed. The state of the store is put back the way it was afterwards.
+    ///
+    /// # Arguments
+    ///
+    /// * `start_state` - The state to start from.
+    /// * `actions` - The actions to dispatch, in order.
+    /// * `invariant_checker` - The function that checks the state after each action.
+    fn check_actions(
+        &self,
+        start_state: &Dictionary,
+        actions: &[Variant],
+        invariant_checker: &Ref<FuncRef, Shared>,
+    ) -> Option<String> {
+        let previous_state = self.state.replace(start_state.duplicate().into_shared());
+        let was_running_scenario = self.running_scenario.replace(true);
+
+        let mut failure = None;
+        for action in actions {
+            self.dispatch_action(action.clone());
+
+            failure = self.check_invariants(invariant_checker, action);
+            if failure.is_some() {
+                break;
+            }
+        }
+
+        self.running_scenario.set(was_running_scenario);
+        *self.state.borrow_mut() = previous_state;
+
+        failure
+    }
+
+    /// Dispatches randomly generated actions and checks that the state still
+    /// satisfies a set of invariants after each one, which is a good way to
+    /// find edge cases in reducers. The actions are run starting from the
+    /// current state without running the subscriptions and the state is put
+    /// back the way it was afterwards, like with `run_scenario`.
+    ///
+    /// If the invariants fail, the sequence of actions that led to it is shrunk
+    /// to the smallest one that still makes them fail and an error describing
+    /// it is printed. A Dictionary is returned with:
+    ///
+    /// * `passed` - Whether the invariants held after every action.
+    /// * `iterations` - The number of actions that were dispatched.
+    /// * `failing_actions` - The smallest sequence of actions that make the invariants fail.
+    /// * `reason` - Why the invariants failed.
+    ///
+    /// # Arguments
+    ///
+    /// * `action_generator` - A FuncRef that is passed the current state and returns a random action.
+    /// * `iterations` - The number of actions to generate and dispatch.
+    /// * `invariant_checker` - A FuncRef that is passed the state and the last
+    /// action and returns `true` if the invariants hold or a String with the
+    /// reason they don't.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func random_action(state):
+    ///     return [Action.INCREMENT, Action.DECREMENT][randi() % 2]
+    ///
+    /// func counter_is_positive(state, action):
+    ///     if state.counter < 0:
+    ///         return "the counter went below 0"
+    ///     return true
+    ///
+    /// func test_counter_never_goes_negative():
+    ///     var store = Store.new()
+    ///     store.set_state_and_reducer(state, self, 'reducer')
+    ///
+    ///     var result = store.fuzz(funcref(self, 'random_action'), 1000, funcref(self, 'counter_is_positive'))
+    ///     assert_true(result.passed)
+    /// ```
+    #[export]
+    fn fuzz(
+        &self,
+        _owner: &Object,
+        action_generator: Ref<FuncRef, Shared>,
+        iterations: i64,
+        invariant_checker: Ref<FuncRef, Shared>,
+    ) -> Dictionary<Unique> {
+        let start_state = self.state.borrow().duplicate().into_shared();
+        let previous_state = self.state.replace(start_state.duplicate().into_shared());
+        let was_running_scenario = self.running_scenario.replace(true);
+
+        let mut actions = vec![];
+        let mut failure = None;
+        for _ in 0..iterations.max(0) {
+            let args = &[Variant::from_dictionary(&self.readable_state())];
+            let action = unsafe { action_generator.assume_safe() }.call_func(args);
+
+            self.dispatch_action(action.clone());
+            failure = self.check_invariants(&invariant_checker, &action);
+            actions.push(action);
+
+            if failure.is_some() {
+                break;
+            }
+        }
+
+        self.running_scenario.set(was_running_scenario);
+        *self.state.borrow_mut() = previous_state;
+
+        let result = Dictionary::new();
+        result.insert("iterations", actions.len() as i64);
+
+        match failure {
+            None => {
+                result.insert("passed", true);
+                result.insert("failing_actions", VariantArray::new());
+                result.insert("reason", "");
+            }
+            Some(_) => {
+                let minimal = fuzz::shrink(actions, |candidate| {
+                    self.check_actions(&start_state, candidate, &invariant_checker)
+                        .is_some()
+                });
+                let reason = self
+                    .check_actions(&start_state, &minimal, &invariant_checker)
+                    .unwrap_or_else(|| "the failure couldn't be reproduced".to_string());
+
+                let failing_actions = minimal.iter().collect::<VariantArray<Unique>>();
+                godot_error!(
+                    "The invariants failed because {} after the actions: {}",
+                    reason,
+                    minimal
+                        .iter()
+                        .map(|action| action.to_string())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                );
+
+                result.insert("passed", false);
+                result.insert("failing_actions", failing_actions);
+                result.insert("reason", reason);
+            }
+        }
+
+        result
+    }
+
+    /// Returns the current time of the store's clock in seconds.
+    fn now(&self) -> f64 {
+        let now_fn = match &*self.clock.borrow() {
+            Clock::Real => return clock::real_now(),
+            Clock::Manual(now) => return *now,
+            Clock::Custom(now_fn) => now_fn.clone(),
+        };
+
+        now_fn.call_func(&[]).to_f64()
+    }
+
+    /// Returns the current time of the store's clock in seconds. This is the
+    /// real time since the engine started unless a test clock has been set
+    /// with `set_test_clock` or `advance_time`.
+    #[export]
+    fn get_time(&self, _owner: &Object) -> f64 {
+        self.now()
+    }
+
+    /// Makes the store get the current time by calling a function instead of
+    /// using the real time, so anything in the store that depends on time
+    /// passing can be driven by a test.
+    ///
+    /// # Arguments
+    ///
+    /// * `now_fn_instance` - The instance that contains the function.
+    /// * `now_fn_name` - The name of the function, which returns the current time in seconds.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// var fake_time = 0.0
+    ///
+    /// func now():
+    ///     return fake_time
+    ///
+    /// func test_something_timed():
+    ///     var store = Store.new()
+    ///     store.set_state_and_reducer(state, self, 'reducer')
+    ///     store.set_test_clock(self, 'now')
+    /// ```
+    #[export]
+    fn set_test_clock(
+        &self,
+        _owner: &Object,
+        now_fn_instance: Ref<Object, Shared>,
+        now_fn_name: GodotString,
+    ) {
+        *self.clock.borrow_mut() = Clock::Custom(func_ref(now_fn_instance, now_fn_name));
+    }
+
+    /// Moves the store's clock forward. The first time this is called the
+    /// store stops using the real time and from then on time only passes when
+    /// it is advanced, which lets tests skip ahead instead of waiting.
+    ///
+    /// # Arguments
+    ///
+    /// * `seconds` - How far to move the clock forward, in seconds.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func test_something_timed():
+    ///     var store = Store.new()
+    ///     store.set_state_and_reducer(state, self, 'reducer')
+    ///
+    ///     store.advance_time(2.5)
+    /// ```
+    #[export]
+    fn advance_time(&self, _owner: &Object, seconds: f64) {
+        if seconds < 0.0 {
+            godot_error!(
+                "The clock can't be moved backwards, got {} seconds",
+                seconds
+            );
+            return;
+        }
+
+        let mut clock = self.clock.borrow_mut();
+        *clock = match &*clock {
+            Clock::Real => Clock::Manual(clock::real_now() + seconds),
+            Clock::Manual(now) => Clock::Manual(now + seconds),
+            Clock::Custom(_) => {
+                godot_error!("The clock set with set_test_clock can't be advanced by the store");
+                return;
+            }
+        };
+    }
+
+    /// Makes the store go back to using the real time after `set_test_clock`
+    /// or `advance_time` were used.
+    #[export]
+    fn use_real_clock(&self, _owner: &Object) {
+        *self.clock.borrow_mut() = Clock::Real;
+    }
+
+    /// Returns the current state serialized the same way every time, with the
+    /// keys of every Dictionary sorted, so it can be compared to a snapshot.
+    fn state_snapshot(&self) -> String {
+        snapshot::to_stable_text(&Variant::from_dictionary(&self.readable_state()))
+    }
+
+    /// Returns the current state as pretty printed JSON with sorted keys, with
+    /// sensitive values like tokens, emails, or random seeds removed or
+    /// masked, so it can be attached to public bug reports or sent along with
+    /// analytics. The rules are the paths to scrub, along with whether to
+    /// `remove` the values at them or `mask` them with `***`. A key of `*` in
+    /// a path matches any key of a Dictionary or index of an Array. Returns an
+    /// empty String if the rules aren't valid.
+    ///
+    /// # Arguments
+    ///
+    /// * `rules` - Whether to `remove` or `mask` the values, by path.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _on_report_bug_pressed():
+    ///     var state = store.export_state_scrubbed({
+    ///         "account/token": "remove",
+    ///         "account/email": "mask",
+    ///         "friends/*/name": "mask",
+    ///         "world/seed": "remove",
+    ///     })
+    ///     BugReporter.send($Description.text, state)
+    /// ```
+    #[export]
+    fn export_state_scrubbed(&self, _owner: &Object, rules: Dictionary) -> GodotString {
+        let scrubber = match Scrubber::new(&rules) {
+            Ok(scrubber) => scrubber,
+            Err(message) => {
+                godot_error!("Unable to scrub the state: {}", message);
+                return GodotString::new();
+            }
+        };
+
+        let state = scrubber.scrub(&self.readable_state());
+        GodotString::from_str(snapshot::to_stable_text(&Variant::from_dictionary(&state)))
+    }
+
+    /// Writes the current state to a file as pretty printed JSON with sorted
+    /// keys so it can be used as a golden file by `assert_state_matches_file`.
+    /// Returns whether the snapshot could be written.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the file to write the snapshot to.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func test_level_complete():
+    ///     var store = Store.new()
+    ///     store.set_state_and_reducer(state, self, 'reducer')
+    ///     store.dispatch(Action.COMPLETE_LEVEL)
+    ///
+    ///     store.write_state_snapshot("res://tests/snapshots/level_complete.json")
+    /// ```
+    #[export]
+    fn write_state_snapshot(&self, _owner: &Object, path: GodotString) -> bool {
+        match file::write_text(&path.to_string(), &self.state_snapshot()) {
+            Ok(()) => true,
+            Err(message) => {
+                godot_error!("{}", message);
+                false
+            }
+        }
+    }
+
+    /// Checks that the current state matches a snapshot written previously by
+    /// `write_state_snapshot`. Prints an error with a line by line diff between
+    /// the snapshot and the current state and returns `false` if it doesn't.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the snapshot file.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func test_level_complete():
+    ///     var store = Store.new()
+    ///     store.set_state_and_reducer(state, self, 'reducer')
+    ///     store.dispatch(Action.COMPLETE_LEVEL)
+    ///
+    ///     assert_true(store.assert_state_matches_file("res://tests/snapshots/level_complete.json"))
+    /// ```
+    #[export]
+    fn assert_state_matches_file(&self, _owner: &Object, path: GodotString) -> bool {
+        let path = path.to_string();
+
+        let expected = match file::read_text(&path) {
+            Ok(expected) => expected,
+            Err(message) => {
+                godot_error!(
+                    "{}, the snapshot can be created with write_state_snapshot",
+                    message
+                );
+                return false;
+            }
+        };
+        let actual = self.state_snapshot();
+
+        if expected.lines().eq(actual.lines()) {
+            return true;
+        }
+
+        godot_error!(
+            "The state doesn't match the snapshot in {}:\n{}",
+            path,
+            snapshot::line_diff(&expected, &actual)
+        );
+        false
+    }
+
+    /// Checks that the current state is equal to the expected state. If it
+    /// isn't, an error is printed with the path of every value that is
+    /// different along with the expected and actual values and `false` is
+    /// returned.
+    ///
+    /// # Arguments
+    ///
+    /// * `expected` - The state that is expected.
+    /// * `ignore_paths` - Optional paths that don't have to match, like timestamps.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func test_take_damage():
+    ///     var store = Store.new()
+    ///     store.set_state_and_reducer({ "player": { "hp": 100, "hit_at": 0 } }, self, 'reducer')
+    ///     store.dispatch({ "type": Action.TAKE_DAMAGE, "amount": 10 })
+    ///
+    ///     assert_true(store.assert_state_equals({ "player": { "hp": 90, "hit_at": 0 } }, ["player/hit_at"]))
+    /// ```
+    #[export]
+    fn assert_state_equals(
+        &self,
+        _owner: &Object,
+        expected: Dictionary,
+        #[opt] ignore_paths: VariantArray,
+    ) -> bool {
+        let ignore_paths = ignore_paths
+            .iter()
+            .map(|path| path.to_string())
+            .collect::<Vec<_>>();
+        let mismatches =
+            testing::state_mismatches(&expected, &self.readable_state(), &ignore_paths);
+
+        if mismatches.is_empty() {
+            return true;
+        }
+
+        godot_error!(
+            "The state doesn't match the expected state:\n{}",
+            mismatches.join("\n")
+        );
+        false
+    }
+
+    /// Checks that the value at a path in the state is equal to the expected
+    /// value. If it isn't, an error is printed with the path and the expected
+    /// and actual values and `false` is returned.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path to the value to check.
+    /// * `value` - The value that is expected.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func test_take_damage():
+    ///     var store = Store.new()
+    ///     store.set_state_and_reducer({ "player": { "hp": 100 } }, self, 'reducer')
+    ///     store.dispatch({ "type": Action.TAKE_DAMAGE, "amount": 10 })
+    ///
+    ///     assert_true(store.assert_path_equals("player/hp", 90))
+    /// ```
+    #[export]
+    fn assert_path_equals(&self, _owner: &Object, path: GodotString, value: Variant) -> bool {
+        let path = path.to_string();
+        let actual = path::get(&self.readable_state(), &path);
+
+        let message = match actual {
+            Some(actual) => match (value.try_to_dictionary(), actual.try_to_dictionary()) {
+                (Some(expected), Some(actual)) => {
+                    let mismatches = testing::state_mismatches(&expected, &actual, &[]);
+                    if mismatches.is_empty() {
+                        return true;
+                    }
+                    mismatches
+                        .iter()
+                        .map(|mismatch| path::join(&path, mismatch))
+                        .collect::<Vec<_>>()
+                        .join("\n")
+                }
+                _ if actual == value => return true,
+                _ => testing::describe_mismatch(&path, Some(&value), Some(&actual)),
+            },
+            None => testing::describe_mismatch(&path, Some(&value), None),
+        };
+
+        godot_error!("The value in the state doesn't match:\n{}", message);
+        false
+    }
+
+    /// Enables or disables event sourcing. While it's enabled every action that
+    /// reaches the reducer is kept in a log, starting from the state at the
+    /// time it was enabled, so the log can be saved with `save_event_log` and
+    /// the state rebuilt from it with `rebuild_from_events`, and projections
+    /// can be run over it with `get_projection`. Enabling it again starts a
+    /// new log. Actions dispatched while running a scenario aren't logged.
+    ///
+    /// # Arguments
+    ///
+    /// * `enabled` - Whether event sourcing should be enabled.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _ready():
+    ///     var store = Store.new()
+    ///     store.set_state_and_reducer(state, self, 'reducer')
+    ///     store.set_event_sourcing(true)
+    /// ```
+    #[export]
+    fn set_event_sourcing(&self, _owner: &Object, enabled: bool) {
+        *self.event_log.borrow_mut() = if enabled {
+            Some(EventLog::new(&self.readable_state()))
+        } else {
+            None
+        };
+    }
+
+    /// Saves the state to a file along with a small header of metadata, which
+    /// can be read with `read_save_metadata` without loading the state, for
+    /// example to show the saves in a load game menu. The metadata has the
+    /// version of the game and the state set with `set_game_version` and
+    /// `set_state_version`, the time the save was made at, the playtime read
+    /// from the path set with `set_playtime_path`, and any custom metadata.
+    /// The state is saved in Godot's binary format so every value keeps its
+    /// type. Read-only slices are left out since they come with the game, and
+    /// protected values stay obfuscated in the file.
+    ///
+    /// The `save_started` signal is emitted with the path when the save starts
+    /// and the `save_finished` signal with whether it succeeded and the path
+    /// when it's done. A save in the background is written by a worker thread
+    /// from a snapshot of the state, so a large state doesn't hold up the
+    /// frame, and finishes on a later frame. On platforms without threads,
+    /// like HTML5 exports without thread support, it's written right away
+    /// instead. Returns whether the state could be saved, or for a save in
+    /// the background whether it could be started.
+    /// The saves that were at the path before are kept as backups if
+    /// `set_backup_count` was called. A relative path is kept in the folder
+    /// for saves on the platform, like `user://`, and the folders in the path
+    /// are created if they don't exist.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the file, for example `user://saves/1.save`.
+    /// * `metadata` - Optional custom metadata, like the name of the level or
+    /// a screenshot path, which is saved under `custom`.
+    /// * `background` - Optional, whether to write the save on a worker
+    /// thread.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _on_save_pressed():
+    ///     store.save_to_file("user://saves/1.save", { "level": "Forest" })
+    ///
+    /// func _on_autosave_timeout():
+    ///     store.save_to_file("user://saves/auto.save", {}, true)
+    /// ```
+    #[export]
+    fn save_to_file(
+        &self,
+        owner: TRef<Object>,
+        path: GodotString,
+        #[opt] metadata: Dictionary,
+        #[opt] background: bool,
+    ) -> bool {
+        let path = match self.save_path(&path.to_string()) {
+            Ok(path) => path,
+            Err(message) => {
+                godot_error!("Unable to save the state to {}: {}", path, message);
+                return false;
+            }
+        };
+
+        let state = self.readable_state().duplicate();
+        for slice in self.readonly_slices.borrow().iter() {
+            state.erase(slice);
+        }
+        let state = state.into_shared();
+
+        let header = self
+            .save_info
+            .borrow()
+            .metadata(&state, &metadata)
+            .into_shared();
+        let state = match self.protection.borrow().as_ref() {
+            Some(protection) => protection.for_files().seal_state(&state),
+            None => state,
+        };
+
+        if background && self.storage.borrow().has_threads() {
+            let saving = self
+                .background_saves
+                .borrow()
+                .iter()
+                .any(|save| save.path() == path);
+            if saving {
+                godot_error!(
+                    "Unable to save the state to {} since it's still being saved",
+                    path
+                );
+                return false;
+            }
+
+            owner.emit_signal("save_started", &[Variant::from_str(&path)]);
+            self.background_saves
+                .borrow_mut()
+                .push(BackgroundSave::start(
+                    path,
+                    background_save::snapshot(&header),
+                    background_save::snapshot(&state),
+                    self.backup_count.get(),
+                    self.encryption.borrow().clone(),
+                ));
+            self.watch_background_saves(owner);
+
+            return true;
+        }
+
+        owner.emit_signal("save_started", &[Variant::from_str(&path)]);
+        let written = persistence::write(
+            &path,
+            &header,
+            &state,
+            self.backup_count.get(),
+            self.encryption.borrow().as_ref(),
+        );
+        self.finish_save(owner, &path, written)
+    }
+
+    /// Checks whether the saves being written in the background are done when
+    /// a frame starts, emitting `save_finished` for the ones that are.
+    #[export]
+    fn on_background_save_frame(&self, owner: TRef<Object>) {
+        let mut finished = vec![];
+        self.background_saves
+            .borrow_mut()
+            .retain(|save| match save.poll() {
+                Some(written) => {
+                    finished.push((save.path().to_string(), written));
+                    false
+                }
+                None => true,
+            });
+
+        for (path, written) in finished {
+            self.finish_save(owner, &path, written);
+        }
+
+        // The store stops checking once every save is done.
+        let done = self.background_saves.borrow().is_empty();
+        if let Some(tree) = main_scene_tree().filter(|_| done) {
+            let tree = unsafe { tree.assume_safe() };
+            if tree.is_connected("idle_frame", owner, "on_background_save_frame") {
+                tree.disconnect("idle_frame", owner, "on_background_save_frame");
+            }
+        }
+    }
+
+    /// Has the store check on the saves being written in the background at
+    /// the start of every frame until they're done.
+    ///
+    /// # Arguments
+    ///
+    /// * `owner` - The store, which is called when a frame starts.
+    fn watch_background_saves(&self, owner: TRef<Object>) {
+        let tree = match main_scene_tree() {
+            Some(tree) => unsafe { tree.assume_safe() },
+            None => {
+                godot_error!("Unable to wait for the saves to finish since there is no SceneTree");
+                return;
+            }
+        };
+
+        if tree.is_connected("idle_frame", owner, "on_background_save_frame") {
+            return;
+        }
+        let connected = tree.connect(
+            "idle_frame",
+            owner,
+            "on_background_save_frame",
+            VariantArray::new_shared(),
+            0,
+        );
+
+        if let Err(error) = connected {
+            godot_error!("Unable to wait for the saves to finish: {:?}", error);
+        }
+    }
+
+    /// Emits `save_finished` for a save, printing an error if it couldn't be
+    /// written, and returns whether it was written.
+    ///
+    /// # Arguments
+    ///
+    /// * `owner` - The store, which emits the signal.
+    /// * `path` - The path of the save.
+    /// * `written` - Whether the save could be written.
+    fn finish_save(&self, owner: TRef<Object>, path: &str, written: Result<(), String>) -> bool {
+        let success = match written {
+            Ok(()) => {
+                self.storage.borrow_mut().flush();
+                true
+            }
+            Err(message) => {
+                godot_error!("Unable to save the state to {}: {}", path, message);
+                false
+            }
+        };
+
+        owner.emit_signal(
+            "save_finished",
+            &[Variant::from_bool(success), Variant::from_str(path)],
+        );
+        success
+    }
+
+    /// Replaces the state with one saved with `save_to_file`. The read-only
+    /// slices the store has are kept since they aren't saved, and a protected
+    /// value that was changed in the file is dropped and a `TAMPER_DETECTED`
+    /// action is dispatched for it. If the save can't be read or doesn't match
+    /// its checksum, its backups are tried from the newest to the oldest. A
+    /// save made with an older version of the state is upgraded with the
+    /// migrations registered with `register_migration` first. The
+    /// subscriptions are run with the loaded state and the
+    /// `persistence_loaded` signal is emitted with the path of the file that
+    /// was loaded, which is a backup if the save couldn't be. Returns whether
+    /// the state could be loaded, which it can't be if the loaded state can't
+    /// be upgraded or doesn't match the schema set with `set_state_schema`.
+    ///
+    /// The saved state replaces the state the store has by default, but it
+    /// can be merged into it instead, for the whole state or for single
+    /// slices, like `import_state`.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the file, for example `user://saves/1.save`.
+    /// * `strategy` - Optional merge strategy for the whole state, which
+    /// defaults to `replace`.
+    /// * `slice_strategies` - Optional merge strategies of single slices, by
+    /// key.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _on_load_pressed():
+    ///     store.load_from_file("user://saves/1.save")
+    ///
+    /// func _on_load_settings_pressed():
+    ///     store.load_from_file("user://settings.save", "keep_existing", { "settings": "deep_merge" })
+    /// ```
+    #[export]
+    fn load_from_file(
+        &self,
+        owner: &Object,
+        path: GodotString,
+        #[opt] strategy: GodotString,
+        #[opt] slice_strategies: Dictionary,
+    ) -> bool {
+        let merge = match Merge::new(&strategy.to_string(), &slice_strategies) {
+            Ok(merge) => merge,
+            Err(message) => {
+                godot_error!("Unable to load the state from {}: {}", path, message);
+                return false;
+            }
+        };
+        let path = match self.storage.borrow().path(&path.to_string()) {
+            Ok(path) => path,
+            Err(message) => {
+                godot_error!("Unable to load the state from {}: {}", path, message);
+                return false;
+            }
+        };
+        let mut candidates = (1..=self.backup_count.get())
+            .map(|backup| persistence::backup_path(&path, backup))
+            .filter(|backup| file::exists(backup));
+        let mut candidate = path.clone();
+        let (path, version, saved) = loop {
+            match persistence::read_state(&candidate, self.encryption.borrow().as_ref()) {
+                Ok((version, saved)) => break (candidate, version, saved),
+                Err(message) => match candidates.next() {
+                    Some(backup) => {
+                        godot_warn!(
+                            "Unable to load the state from {}, trying {}: {}",
+                            candidate,
+                            backup,
+                            message
+                        );
+                        candidate = backup;
+                    }
+                    None => {
+                        godot_error!("Unable to load the state from {}: {}", candidate, message);
+                        return false;
+                    }
+                },
+            }
+        };
+
+        self.hydrate(owner, &path, version, &saved, &merge)
+    }
+
+    /// Makes a state read from a file the state, upgrading it with the
+    /// migrations if it was saved with an older version of the state and
+    /// dispatching a `TAMPER_DETECTED` action for every protected value that
+    /// was changed in the file, runs the subscriptions, and emits
+    /// `persistence_loaded`. Returns whether the state was loaded, which it
+    /// isn't if it can't be upgraded or doesn't match the state schema.
+    ///
+    /// # Arguments
+    ///
+    /// * `owner` - The store, which emits the signal.
+    /// * `path` - The path of the file.
+    /// * `version` - The version of the state read from the file.
+    /// * `saved` - The state read from the file, with its protected values
+    /// sealed.
+    /// * `merge` - How the state is combined with the one the store has.
+    fn hydrate(
+        &self,
+        owner: &Object,
+        path: &str,
+        version: i64,
+        saved: &Dictionary,
+        merge: &Merge,
+    ) -> bool {
+        let (loaded, tampered) = match self.protection.borrow().as_ref() {
+            Some(protection) => protection.for_files().unseal_state(saved),
+            None => (saved.new_ref(), vec![]),
+        };
+
+        // The migrations are copied out since they call into GDScript, which
+        // could register more of them.
+        let current_version = self.save_info.borrow().state_version;
+        let chain = self.migrations.borrow().chain(version, current_version);
+        let loaded = match chain.and_then(|chain| migration::run(loaded, &chain)) {
+            Ok(loaded) => loaded,
+            Err(message) => {
+                godot_error!("Unable to load the state from {}: {}", path, message);
+                return false;
+            }
+        };
+
+        if let Err(errors) = self.merge_state(&loaded, merge) {
+            godot_error!(
+                "Unable to load the state from {} since it doesn't match the schema:\n{}",
+                path,
+                errors.join("\n")
+            );
+            return false;
+        }
+
+        for protected in tampered {
+            godot_warn!(
+                "The protected value at {} was changed in {}",
+                protected,
+                path
+            );
+            self.dispatch_action(protection::tamper_detected(&protected).owned_to_variant());
+        }
+
+        self.sync_bound_fields();
+        self.dispatch_subscriptions();
+
+        owner.emit_signal("persistence_loaded", &[Variant::from_str(path)]);
+        true
+    }
+
+    /// Layers a state onto the state the store has, like a partial save or
+    /// the content of a DLC pack. The merge strategy decides how each slice
+    /// is combined with the one the store has:
+    ///
+    /// * `replace` - The imported slice replaces the one the store has, and
+    ///   slices that aren't imported are dropped. This is the default.
+    /// * `shallow_merge` - The keys of the imported slice replace the same
+    ///   keys of the one the store has, keeping the rest of them.
+    /// * `deep_merge` - Like `shallow_merge`, but Dictionaries in both slices
+    ///   are merged the same way however deeply they are nested.
+    /// * `keep_existing` - Only keys the store doesn't have yet are added,
+    ///   however deeply they are nested.
+    ///
+    /// The strategy for the whole state can be overridden for single slices.
+    /// Read-only slices are never changed. The subscriptions are run with the
+    /// new state. Returns whether the state could be imported, which it can't
+    /// be if the state it leads to doesn't match the schema set with
+    /// `set_state_schema`.
+    ///
+    /// # Arguments
+    ///
+    /// * `state` - The state to import.
+    /// * `strategy` - Optional merge strategy for the whole state.
+    /// * `slice_strategies` - Optional merge strategies of single slices, by
+    /// key.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _on_dlc_installed(pack):
+    ///     store.import_state(pack.state, "keep_existing", { "levels": "deep_merge" })
+    /// ```
+    #[export]
+    fn import_state(
+        &self,
+        _owner: &Object,
+        state: Dictionary,
+        #[opt] strategy: GodotString,
+        #[opt] slice_strategies: Dictionary,
+    ) -> bool {
+        let merge = match Merge::new(&strategy.to_string(), &slice_strategies) {
+            Ok(merge) => merge,
+            Err(message) => {
+                godot_error!("Unable to import the state: {}", message);
+                return false;
+            }
+        };
+
+        if let Err(errors) = self.merge_state(&state, &merge) {
+            godot_error!(
+                "Unable to import the state since it doesn't match the schema:\n{}",
+                errors.join("\n")
+            );
+            return false;
+        }
+
+        self.sync_bound_fields();
+        self.dispatch_subscriptions();
+        true
+    }
+
+    /// Combines a loaded or imported state with the state the store has and
+    /// makes it the state, keeping the read-only slices the store has.
+    /// Returns the errors of the fields that don't match the state schema,
+    /// leaving the state as it was, if the combined state doesn't match it.
+    ///
+    /// # Arguments
+    ///
+    /// * `incoming` - The state being loaded or imported.
+    /// * `merge` - How the states are combined.
+    fn merge_state(&self, incoming: &Dictionary, merge: &Merge) -> Result<(), Vec<String>> {
+        let old_state = self.readable_state();
+        let state = merge.apply(&old_state, incoming);
+        for slice in self.readonly_slices.borrow().iter() {
+            if old_state.contains(slice) {
+                state.insert(slice, old_state.get(slice));
+            } else {
+                state.erase(slice);
+            }
+        }
+        let state = state.into_shared();
+
+        let errors = self.state_schema_errors(&state);
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+
+        *self.state.borrow_mut() = self.sealed(&state);
+        *self.previous_state.borrow_mut() = self.state.borrow().new_ref();
+        self.mark_dirty_slices(&old_state, &state);
+        Ok(())
+    }
+
+    /// Returns the metadata of a save made with `save_to_file` without loading
+    /// its state, which has the `game_version`, `state_version`, `timestamp`,
+    /// `playtime`, and `custom` metadata of the save. Returns an empty
+    /// Dictionary if the save can't be read.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the file, for example `user://saves/1.save`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func show_saves():
+    ///     for slot in range(3):
+    ///         var metadata = store.read_save_metadata("user://saves/%d.save" % slot)
+    ///         if not metadata.empty():
+    ///             add_slot(metadata.custom.level, metadata.playtime, metadata.timestamp)
+    /// ```
+    #[export]
+    fn read_save_metadata(&self, _owner: &Object, path: GodotString) -> Dictionary {
+        let path = path.to_string();
+
+        let metadata =
+            self.storage.borrow().path(&path).and_then(|path| {
+                persistence::read_metadata(&path, self.encryption.borrow().as_ref())
+            });
+        metadata.unwrap_or_else(|message| {
+            godot_error!("Unable to read the metadata of {}: {}", path, message);
+            Dictionary::new_shared()
+        })
+    }
+
+    /// Sets the number of backups kept of every save made with
+    /// `save_to_file`. Before a save is written, the file that was at the path
+    /// is moved to `.bak1`, the one at `.bak1` to `.bak2`, and so on, so a
+    /// save that gets corrupted, for example by the game crashing while
+    /// writing it, can be recovered by `load_from_file`. It starts out at 0,
+    /// which keeps no backups.
+    ///
+    /// # Arguments
+    ///
+    /// * `count` - The number of backups to keep.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _ready():
+    ///     store.set_backup_count(3)
+    /// ```
+    #[export]
+    fn set_backup_count(&self, _owner: &Object, count: i64) {
+        if count < 0 {
+            godot_error!("The backup count has to be 0 or more, got {}", count);
+            return;
+        }
+
+        self.backup_count.set(count as usize);
+    }
+
+    /// Encrypts every save made after this is called, with `save_to_file`,
+    /// `save_state`, or `persist`, using Godot's encrypted files, which are
+    /// encrypted with AES-256 so the state can't be read or changed by
+    /// editing the file. The encrypted files also keep an MD5 of what's in
+    /// them, so a save that was changed or corrupted isn't loaded, and
+    /// `load_from_file` tries its backups instead. The encryption is either a
+    /// password the key is made from or a PoolByteArray with a 32 byte key,
+    /// and `null` or an empty password stops encrypting saves. Saves have to
+    /// be loaded with the encryption they were made with.
+    ///
+    /// # Arguments
+    ///
+    /// * `encryption` - The password or the key.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _ready():
+    ///     if not OS.is_debug_build():
+    ///         store.set_save_encryption("a password only the game knows")
+    /// ```
+    #[export]
+    fn set_save_encryption(&self, _owner: &Object, encryption: Variant) {
+        let encryption = match encryption.get_type() {
+            VariantType::Nil => None,
+            VariantType::GodotString if encryption.to_string().is_empty() => None,
+            VariantType::GodotString => Some(Encryption::Password(encryption.to_string())),
+            VariantType::ByteArray if encryption.to_byte_array().len() == 32 => {
+                Some(Encryption::Key(encryption.to_byte_array().read().to_vec()))
+            }
+            _ => {
+                godot_error!(
+                    "The save encryption has to be a password or a 32 byte PoolByteArray, got {}",
+                    encryption.to_string()
+                );
+                return;
+            }
+        };
+
+        *self.encryption.borrow_mut() = encryption;
+    }
+
+    /// Writes just the state to a file, without the metadata, checksum, and
+    /// backups of `save_to_file`, so a game that uses the state as its save
+    /// data only needs this and `load_state`. The state can be written with
+    /// any of the codecs:
+    ///
+    /// * `json` - JSON, which can be read and edited by hand but turns every
+    ///   number into a float and values like a `Vector2` into strings.
+    /// * `binary` - The binary encoding of Variants that `var2bytes` uses,
+    ///   which keeps the type of every value, like a `Vector2`, a `Color`, or
+    ///   a `PoolIntArray`, so the state is loaded exactly as it was saved.
+    /// * `msgpack` - MessagePack, which is smaller than JSON and keeps
+    ///   integers and floats apart, but turns values like a `Vector2` into
+    ///   strings like JSON does.
+    /// * Any codec registered with `register_codec`.
+    ///
+    /// Without a format, a path ending in `.json` is written as JSON, one
+    /// ending in `.msgpack` as MessagePack, and any other path as binary.
+    /// Read-only slices are left out since they come with the game, and
+    /// protected values stay obfuscated in the file. A relative path is kept
+    /// in the folder for saves on the platform, like `user://`, and the
+    /// folders in the path are created if they don't exist. The version set
+    /// with `set_state_version` is written along with the state so it can be
+    /// upgraded when it's loaded by a later version. Returns whether the
+    /// state could be saved.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the file, for example `user://state.json`.
+    /// * `format` - Optional name of the codec to write the state with.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _on_quit_pressed():
+    ///     store.save_state("user://state.dat", "binary")
+    ///     get_tree().quit()
+    /// ```
+    #[export]
+    fn save_state(&self, _owner: &Object, path: GodotString, #[opt] format: GodotString) -> bool {
+        let written = self.save_path(&path.to_string()).and_then(|path| {
+            let codec = self.codecs.borrow().get(&format.to_string(), &path)?;
+            let state = self.state_without_readonly_slices();
+            let state = match self.protection.borrow().as_ref() {
+                Some(protection) => protection.for_files().seal_state(&state),
+                None => state,
+            };
+
+            let version = self.save_info.borrow().state_version;
+            let encryption = self.encryption.borrow().clone();
+            persistence::write_plain(&path, &state, version, &codec, encryption.as_ref())
+        });
+
+        match written {
+            Ok(()) => {
+                self.storage.borrow_mut().flush();
+                true
+            }
+            Err(message) => {
+                godot_error!("Unable to save the state to {}: {}", path, message);
+                false
+            }
+        }
+    }
+
+    /// Replaces the state with one written with `save_state`, or by the
+    /// `persist` middleware, which has to be read in the format it was
+    /// written in. Like `load_from_file`, a state written with an older
+    /// version of the state is upgraded with the migrations, the read-only
+    /// slices the store has are kept, a protected value that was changed in
+    /// the file is dropped and a `TAMPER_DETECTED` action is dispatched for
+    /// it, the subscriptions are run with the loaded state, and the
+    /// `persistence_loaded` signal is emitted with the path. Returns whether
+    /// the state could be loaded, which it can't be if the loaded state can't
+    /// be upgraded or doesn't match the schema set with `set_state_schema`.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the file, for example `user://state.json`.
+    /// * `format` - Optional name of the codec the state was written with,
+    /// which goes by the extension of the path like in `save_state` if it's
+    /// left out.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _ready():
+    ///     if File.new().file_exists("user://state.dat"):
+    ///         store.load_state("user://state.dat", "binary")
+    /// ```
+    #[export]
+    fn load_state(&self, owner: &Object, path: GodotString, #[opt] format: GodotString) -> bool {
+        let stored = self.storage.borrow().path(&path.to_string());
+        let loaded = stored.and_then(|path| {
+            let codec = self.codecs.borrow().get(&format.to_string(), &path)?;
+            let encryption = self.encryption.borrow().clone();
+            Ok((
+                persistence::read_plain(&path, &codec, encryption.as_ref())?,
+                path,
+            ))
+        });
+        let ((version, saved), path) = match loaded {
+            Ok(loaded) => loaded,
+            Err(message) => {
+                godot_error!("Unable to load the state from {}: {}", path, message);
+                return false;
+            }
+        };
+
+        self.hydrate(owner, &path, version, &saved, &Merge::replace())
+    }
+
+    /// Adds middleware that writes the state to a file with `save_state`
+    /// after every action is dispatched, so the state is always saved without
+    /// having to save it by hand, and returns the id of the middleware, which
+    /// can be removed with `remove_middleware` to stop. The middleware is
+    /// marked as having side effects so nothing is written while running a
+    /// scenario, previewing an action, or replaying input. Calling this again
+    /// replaces the middleware added before, so the state is only written to
+    /// one file. Since the whole state is written every time, it's best
+    /// suited to small states like settings or progress. Returns -1 if there
+    /// is no codec with the name of the format.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the file, for example `user://state.json`.
+    /// * `format` - Optional name of the codec to write the state with, like
+    /// in `save_state`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _ready():
+    ///     if File.new().file_exists("user://settings.json"):
+    ///         store.load_state("user://settings.json")
+    ///     store.persist("user://settings.json")
+    /// ```
+    #[export]
+    fn persist(&self, owner: &Object, path: GodotString, #[opt] format: GodotString) -> i64 {
+        let codec = self
+            .codecs
+            .borrow()
+            .get(&format.to_string(), &path.to_string());
+        if let Err(message) = codec {
+            godot_error!("Unable to persist the state to {}: {}", path, message);
+            return -1;
+        }
+
+        let previous = self.persisted.borrow_mut().take();
+        if let Some((id, _, _)) = previous {
+            self.middleware.borrow_mut().remove(id);
+        }
+
+        let id = self.push_middleware(
+            owner,
+            self.owner.clone(),
+            GodotString::from_str("persist_middleware"),
+            Convention::Next,
+        );
+        self.set_middleware_side_effects(owner, id, true);
+        *self.persisted.borrow_mut() = Some((id, path, format));
+
+        id
+    }
+
+    /// The middleware added with `persist`, which passes the action on and
+    /// then writes the state it led to.
+    ///
+    /// # Arguments
+    ///
+    /// * `state` - The state, which isn't used.
+    /// * `action` - The action being dispatched.
+    /// * `next` - The function that continues the middleware chain.
+    #[export]
+    fn persist_middleware(
+        &self,
+        owner: &Object,
+        _state: Variant,
+        action: Variant,
+        next: Variant,
+    ) -> Variant {
+        let result = match next.try_to_object::<Reference>() {
+            Some(next) => unsafe { next.assume_safe().call("call_func", &[action]) },
+            None => Variant::new(),
+        };
+
+        let persisted = self
+            .persisted
+            .borrow()
+            .as_ref()
+            .map(|(_, path, format)| (path.clone(), format.clone()));
+        if let Some((path, format)) = persisted {
+            self.save_state(owner, path, format);
+        }
+
+        result
+    }
+
+    /// Adds middleware that prints every action that is dispatched to the
+    /// output, along with the state before and after it, so the game doesn't
+    /// need a logger of its own, and returns the id of the middleware. The
+    /// middleware is added after the middleware that is already there, so it
+    /// logs the actions as they reach the reducer. It's marked as having side
+    /// effects so nothing is printed while running a scenario, previewing an
+    /// action, or replaying input. Calling this again replaces the logger
+    /// added before, and a verbosity of `off` removes it. Returns -1 if the
+    /// logger was removed or the verbosity isn't valid.
+    ///
+    /// # Arguments
+    ///
+    /// * `verbosity` - How much is logged for each action, which is `types`
+    /// for only the type of the action, `actions` for the whole action,
+    /// `states` for the action along with the state before and after it, or
+    /// `off`. Defaults to `states`.
+    /// * `options` - Optional Dictionary with whether to print actions and
+    /// states as indented JSON with sorted keys under `pretty`, an Array of
+    /// the paths of the state to print instead of the whole state under
+    /// `paths`, and an Array of the types of the actions that aren't printed
+    /// under `ignored_types`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _ready():
+    ///     if OS.is_debug_build():
+    ///         store.enable_logging("states", {
+    ///             "pretty": true,
+    ///             "paths": ["player", "level/name"],
+    ///             "ignored_types": ["TICK"],
+    ///         })
+    /// ```
+    #[export]
+    fn enable_logging(
+        &self,
+        owner: &Object,
+        #[opt] verbosity: GodotString,
+        #[opt] options: Dictionary,
+    ) -> i64 {
+        let verbosity = match Verbosity::from_name(&verbosity.to_string()) {
+            Ok(verbosity) => verbosity,
+            Err(message) => {
+                godot_error!("Unable to enable logging: {}", message);
+                return -1;
+            }
+        };
+
+        let previous = self.logger.borrow_mut().take();
+        if let Some((id, _)) = previous {
+            self.middleware.borrow_mut().remove(id);
+        }
+        if verbosity == Verbosity::Off {
+            return -1;
+        }
+
+        let id = self.push_middleware(
+            owner,
+            self.owner.clone(),
+            GodotString::from_str("logging_middleware"),
+            Convention::Next,
+        );
+        self.set_middleware_side_effects(owner, id, true);
+        *self.logger.borrow_mut() = Some((id, Logger::new(verbosity, &options)));
+
+        id
+    }
+
+    /// The middleware added with `enable_logging`, which passes the action
+    /// on and then prints it along with the state before and after it.
+    ///
+    /// # Arguments
+    ///
+    /// * `state` - The state before the action.
+    /// * `action` - The action being dispatched.
+    /// * `next` - The function that continues the middleware chain.
+    #[export]
+    fn logging_middleware(
+        &self,
+        _owner: &Object,
+        state: Dictionary,
+        action: Variant,
+        next: Variant,
+    ) -> Variant {
+        let result = match next.try_to_object::<Reference>() {
+            Some(next) => unsafe { next.assume_safe().call("call_func", &[action.clone()]) },
+            None => Variant::new(),
+        };
+
+        let lines = match self.logger.borrow().as_ref() {
+            Some((_, logger)) => logger.lines(&action, &state, &self.readable_state()),
+            None => vec![],
+        };
+        for line in lines {
+            godot_print!("{}", line);
+        }
+
+        result
+    }
+
+    /// Registers a codec that turns values into bytes and back, like a
+    /// compressed or a custom binary format, which can then be used by name
+    /// anywhere a codec can, like `save_state`, `load_state`, `persist`,
+    /// `encode_value`, and `decode_value`. The encode function is passed a
+    /// value and returns a PoolByteArray, and the decode function is passed
+    /// the PoolByteArray and returns the value. Registering a codec with the
+    /// name of one that was registered before replaces it, but the built-in
+    /// `json`, `binary`, and `msgpack` codecs can't be replaced. Returns
+    /// whether the codec was registered.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the codec.
+    /// * `instance` - The instance on which the functions exist.
+    /// * `encode_fn_name` - The name of the function that encodes values.
+    /// * `decode_fn_name` - The name of the function that decodes values.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _ready():
+    ///     store.register_codec("compressed", self, "encode", "decode")
+    ///     store.persist("user://state.dat", "compressed")
+    ///
+    /// func encode(value):
+    ///     return var2bytes(value).compress(File.COMPRESSION_GZIP)
+    ///
+    /// func decode(bytes):
+    ///     return bytes2var(bytes.decompress_dynamic(-1, File.COMPRESSION_GZIP))
+    /// ```
+    #[export]
+    fn register_codec(
+        &self,
+        _owner: &Object,
+        name: GodotString,
+        instance: Ref<Object, Shared>,
+        encode_fn_name: GodotString,
+        decode_fn_name: GodotString,
+    ) -> bool {
+        let codec = Codec::Custom(
+            func_ref(instance.clone(), encode_fn_name),
+            func_ref(instance, decode_fn_name),
+        );
+
+        match self.codecs.borrow_mut().register(&name.to_string(), codec) {
+            Ok(()) => true,
+            Err(message) => {
+                godot_error!("Unable to register the codec {}: {}", name, message);
+                false
+            }
+        }
+    }
+
+    /// Returns the names of the codecs, with the built-in `json`, `binary`,
+    /// and `msgpack` codecs first and then the ones registered with
+    /// `register_codec`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _ready():
+    ///     print(store.get_codecs()) # ["json", "binary", "msgpack", "compressed"]
+    /// ```
+    #[export]
+    fn get_codecs(&self, _owner: &Object) -> VariantArray {
+        self.codecs
+            .borrow()
+            .names()
+            .iter()
+            .map(Variant::from_str)
+            .collect::<VariantArray<Unique>>()
+            .into_shared()
+    }
+
+    /// Turns a value into bytes with a codec, so anything sent over the
+    /// network or written somewhere else is encoded the same way as the
+    /// state that is saved. Returns an empty PoolByteArray if the value
+    /// couldn't be encoded.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The value, for example the state.
+    /// * `codec` - The name of the codec.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _on_peer_connected(id):
+    ///     var bytes = store.encode_value(store.get_state(), "msgpack")
+    ///     rpc_id(id, "receive_state", bytes)
+    /// ```
+    #[export]
+    fn encode_value(&self, _owner: &Object, value: Variant, codec: GodotString) -> ByteArray {
+        // The codec is taken out first since a custom one calls back into
+        // GDScript.
+        let found = self.codecs.borrow().get(&codec.to_string(), "");
+        let encoded = found.and_then(|found| found.encode(&value));
+
+        encoded.unwrap_or_else(|message| {
+            godot_error!("Unable to encode the value with {}: {}", codec, message);
+            ByteArray::new()
+        })
+    }
+
+    /// Turns bytes made by `encode_value` back into a value with the same
+    /// codec. Returns `null` if the bytes couldn't be decoded.
+    ///
+    /// # Arguments
+    ///
+    /// * `bytes` - The bytes.
+    /// * `codec` - The name of the codec.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// remote func receive_state(bytes):
+    ///     store.import_state(store.decode_value(bytes, "msgpack"))
+    /// ```
+    #[export]
+    fn decode_value(&self, _owner: &Object, bytes: ByteArray, codec: GodotString) -> Variant {
+        let found = self.codecs.borrow().get(&codec.to_string(), "");
+        let decoded = found.and_then(|found| found.decode(bytes));
+
+        decoded.unwrap_or_else(|message| {
+            godot_error!("Unable to decode the value with {}: {}", codec, message);
+            Variant::new()
+        })
+    }
+
+    /// Returns where a save at a path is kept, creating the folders in it if
+    /// they don't exist.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the save.
+    fn save_path(&self, path: &str) -> Result<String, String> {
+        let storage = self.storage.borrow();
+        let path = storage.path(path)?;
+        storage.prepare(&path)?;
+
+        Ok(path)
+    }
+
+    /// Sets the folder that saves given as a relative path, like
+    /// `slot_1.save`, are kept in. By default they're kept in `user://` on
+    /// desktop, mobile, and HTML5 exports, while other platforms, like
+    /// consoles, have their own place for saves that has to be set with this.
+    /// Paths like `user://saves/1.save` are always kept as they are. An empty
+    /// folder goes back to the default.
+    ///
+    /// # Arguments
+    ///
+    /// * `root` - The folder, for example `user://saves`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _ready():
+    ///     if OS.get_name() == "Switch":
+    ///         store.set_storage_root(SwitchSaveData.get_mount_path())
+    ///     store.save_to_file("slot_1.save")
+    /// ```
+    #[export]
+    fn set_storage_root(&self, _owner: &Object, root: GodotString) {
+        self.storage
+            .borrow_mut()
+            .set_root(Some(root.to_string()).filter(|root| !root.is_empty()));
+    }
+
+    /// Returns where a save at a path is kept on the platform the game is
+    /// running on, or an empty String if saves can't be kept there.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the save, for example `slot_1.save`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _ready():
+    ///     print(store.get_storage_path("slot_1.save")) # user://slot_1.save
+    /// ```
+    #[export]
+    fn get_storage_path(&self, _owner: &Object, path: GodotString) -> GodotString {
+        match self.storage.borrow().path(&path.to_string()) {
+            Ok(path) => GodotString::from_str(path),
+            Err(message) => {
+                godot_error!("{}", message);
+                GodotString::new()
+            }
+        }
+    }
+
+    /// Sets the version of the game written into the metadata of saves.
+    ///
+    /// # Arguments
+    ///
+    /// * `version` - The version of the game, like `1.2.0`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _ready():
+    ///     store.set_game_version(ProjectSettings.get_setting("application/config/version"))
+    /// ```
+    #[export]
+    fn set_game_version(&self, _owner: &Object, version: GodotString) {
+        self.save_info.borrow_mut().game_version = version.to_string();
+    }
+
+    /// Sets the version of the shape of the state written into saves, which
+    /// should go up whenever the state changes in a way that older saves
+    /// don't match. A save with an older version is upgraded with the
+    /// migrations registered with `register_migration` when it's loaded with
+    /// `load_from_file` or `load_state`. It starts out at 0.
+    ///
+    /// # Arguments
+    ///
+    /// * `version` - The version of the state.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _ready():
+    ///     store.set_state_version(3)
+    /// ```
+    #[export]
+    fn set_state_version(&self, _owner: &Object, version: i64) {
+        self.save_info.borrow_mut().state_version = version;
+    }
+
+    /// Registers a migration that upgrades a state saved with one version of
+    /// the state to the next version, so saves from older versions of the
+    /// game keep working after the shape of the state changes. When a save
+    /// is loaded with `load_from_file` or `load_state`, the migrations from
+    /// its version up to the one set with `set_state_version` are run one
+    /// after another before the state is loaded, each being passed the state
+    /// and returning the upgraded state. A save can't be loaded if one of
+    /// the migrations it needs is missing or it was saved with a newer
+    /// version. Registering a migration for a version again replaces it.
+    ///
+    /// # Arguments
+    ///
+    /// * `from_version` - The version the migration upgrades from, which it
+    /// upgrades to the version after it.
+    /// * `instance` - The instance that has the migration function.
+    /// * `fn_name` - The name of the migration function.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _ready():
+    ///     store.set_state_version(2)
+    ///     store.register_migration(0, self, "add_settings")
+    ///     store.register_migration(1, self, "rename_health")
+    ///
+    /// func rename_health(state):
+    ///     state.player.hp = state.player.health
+    ///     state.player.erase("health")
+    ///     return state
+    /// ```
+    #[export]
+    fn register_migration(
+        &self,
+        _owner: &Object,
+        from_version: i64,
+        instance: Ref<Object, Shared>,
+        fn_name: GodotString,
+    ) {
+        self.migrations
+            .borrow_mut()
+            .register(from_version, func_ref(instance, fn_name));
+    }
+
+    /// Sets the path of the playtime in the state, whose value is written into
+    /// the metadata of saves. An empty path leaves the playtime out.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the playtime, for example `stats/playtime`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _ready():
+    ///     store.set_playtime_path("stats/playtime")
+    /// ```
+    #[export]
+    fn set_playtime_path(&self, _owner: &Object, path: GodotString) {
+        self.save_info.borrow_mut().playtime_path =
+            Some(path.to_string()).filter(|path| !path.is_empty());
+    }
+
+    /// Sets the most memory the history can take up, so long play sessions on
+    /// devices with little memory don't run out of it. Once the actions kept
+    /// by event sourcing or time travel take up more than this, the oldest
+    /// ones are trimmed after the dispatch that went over and the
+    /// `history_trimmed` signal is emitted with the number of actions that
+    /// were trimmed. The state the event log and the time travel history
+    /// start from is moved forward past the trimmed actions, so the state can
+    /// still be rebuilt from them. The size of an action is the
+    /// number of bytes it takes up when it's encoded, which is close to how
+    /// much memory it uses.
+    ///
+    /// # Arguments
+    ///
+    /// * `bytes` - The most bytes the history can take up, or 0 for no limit,
+    /// which is the default.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _ready():
+    ///     store.set_event_sourcing(true)
+    ///     store.set_history_memory_limit(4 * 1024 * 1024)
+    /// ```
+    #[export]
+    fn set_history_memory_limit(&self, _owner: &Object, bytes: i64) {
+        if bytes < 0 {
+            godot_error!("The history memory limit can't be negative, got {}", bytes);
+            return;
+        }
+
+        self.history_memory_limit
+            .set(Some(bytes as usize).filter(|bytes| *bytes > 0));
+        self.trim_history();
+    }
+
+    /// Trims the oldest actions from the event log, the time travel history,
+    /// and the undo stack while they take up more than the history memory
+    /// limit and emits `history_trimmed` with the number of actions that were
+    /// trimmed.
+    fn trim_history(&self) {
+        let limit = match self.history_memory_limit.get() {
+            Some(limit) => limit,
+            None => return,
+        };
+
+        let trimmed = self.trim_event_log(limit)
+            + self
+                .history
+                .borrow_mut()
+                .as_mut()
+                .map_or(0, |history| history.trim_to(limit))
+            + self
+                .undo_stack
+                .borrow_mut()
+                .as_mut()
+                .map_or(0, |undo_stack| undo_stack.trim_to(limit));
+        if trimmed == 0 || self.owner_was_freed() {
+            return;
+        }
+
+        unsafe { self.owner.assume_safe() }
+            .emit_signal("history_trimmed", &[Variant::from_i64(trimmed as i64)]);
+    }
+
+    /// Trims the oldest actions from the event log while it takes up more than
+    /// a number of bytes, moving the state the log starts from forward by
+    /// running them through the reducer, and returns the number of actions
+    /// that were trimmed.
+    ///
+    /// # Arguments
+    ///
+    /// * `limit` - The number of bytes the event log can take up.
+    fn trim_event_log(&self, limit: usize) -> usize {
+        let (initial_state, trimmed) = match self.event_log.borrow_mut().as_mut() {
+            Some(log) => (log.initial_state(), log.trim_to(limit)),
+            None => return 0,
+        };
+        if trimmed.is_empty() {
+            return 0;
+        }
+
+        // The trimmed actions are run like they are in `replay_event_log`, on
+        // top of the state the log started from, and the state is put back
+        // the way it was afterwards.
+        let current_state = self.state.replace(self.sealed(&initial_state));
+        let was_running_scenario = self.running_scenario.replace(true);
+        for event in &trimmed {
+            self.dispatch_reducer(&self.migrated(event.clone()));
+        }
+        self.running_scenario.set(was_running_scenario);
+        let initial_state = self.readable_state();
+        *self.state.borrow_mut() = current_state;
+
+        if let Some(log) = self.event_log.borrow_mut().as_mut() {
+            log.set_initial_state(&initial_state);
+        }
+
+        trimmed.len()
+    }
+
+    /// Saves the event log to a file, keeping the type of every value in it.
+    /// Returns whether the log could be saved.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the file to save the log to.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _on_level_finished():
+    ///     store.save_event_log("user://runs/level_1.log")
+    /// ```
+    #[export]
+    fn save_event_log(&self, _owner: &Object, path: GodotString) -> bool {
+        let saved = match self.event_log.borrow().as_ref() {
+            Some(log) => log.to_variant(),
+            None => {
+                godot_error!("Unable to save the event log since event sourcing isn't enabled");
+                return false;
+            }
+        };
+
+        match file::write_var(&path.to_string(), &saved.owned_to_variant()) {
+            Ok(()) => true,
+            Err(message) => {
+                godot_error!("Unable to save the event log: {}", message);
+                false
+            }
+        }
+    }
+
+    /// Rebuilds the state from an event log saved with `save_event_log` by
+    /// running every action in it through the reducer again, starting from
+    /// the state the log starts from. The middleware is skipped since the log
+    /// holds the actions that reached the reducer, and nothing other than the
+    /// state is changed while the actions are replayed, the same as in
+    /// `run_scenario`. The subscriptions are run once the state has been
+    /// rebuilt and event sourcing carries on from the loaded log. Returns
+    /// whether the state could be rebuilt.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the saved log.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _ready():
+    ///     store.set_state_and_reducer(state, self, 'reducer')
+    ///     store.rebuild_from_events("user://runs/level_1.log")
+    /// ```
+    #[export]
+    fn rebuild_from_events(&self, _owner: &Object, path: GodotString) -> bool {
+        let path = path.to_string();
+        let log = match file::read_var(&path).and_then(|saved| EventLog::from_variant(&saved)) {
+            Ok(log) => log,
+            Err(message) => {
+                godot_error!("Unable to rebuild the state from {}: {}", path, message);
+                return false;
+            }
+        };
+
+        self.replay_event_log(log);
+        true
+    }
+
+    /// Rebuilds the state by running every action in an event log through the
+    /// reducer again and carries on event sourcing from the log.
+    ///
+    /// # Arguments
+    ///
+    /// * `log` - The event log.
+    fn replay_event_log(&self, log: EventLog) {
+        *self.state.borrow_mut() = self.sealed(&log.initial_state());
+
+        let was_running_scenario = self.running_scenario.replace(true);
+        for event in log.events() {
+            self.dispatch_reducer(&self.migrated(event.clone()));
+        }
+        self.running_scenario.set(was_running_scenario);
+
+        *self.event_log.borrow_mut() = Some(log);
+
+        self.sync_bound_fields();
+        self.dispatch_subscriptions();
+    }
+
+    /// Enables or disables time travel. While it's enabled every action that
+    /// reaches the reducer is kept in a history along with the state it led
+    /// to, starting from the state at the time it was enabled, so the state
+    /// can be moved back to any of them with `jump_to` while debugging. Only
+    /// what changed in the state is kept for each action, and the history is
+    /// trimmed with the rest of it by `set_history_memory_limit`. Enabling it
+    /// again starts a new history. Actions dispatched while running a
+    /// scenario aren't kept.
+    ///
+    /// # Arguments
+    ///
+    /// * `enabled` - Whether time travel should be enabled.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _ready():
+    ///     if OS.is_debug_build():
+    ///         store.set_time_travel(true)
+    /// ```
+    #[export]
+    fn set_time_travel(&self, _owner: &Object, enabled: bool) {
+        *self.history.borrow_mut() = if enabled {
+            Some(History::new(&self.readable_state()))
+        } else {
+            None
+        };
+    }
+
+    /// Returns the actions kept while time travel is enabled, in the order
+    /// they were dispatched, as Dictionaries with the action under `action`
+    /// and the state after it under `state`. Returns an empty Array if time
+    /// travel isn't enabled.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func show_history():
+    ///     for entry in store.get_history():
+    ///         $HistoryList.add_item(str(entry.action))
+    /// ```
+    #[export]
+    fn get_history(&self, _owner: &Object) -> VariantArray {
+        match self.history.borrow().as_ref() {
+            Some(history) => history.to_array().into_shared(),
+            None => VariantArray::new_shared(),
+        }
+    }
+
+    /// Moves the state back, or forward again, to the state after the action
+    /// at an index of `get_history` and runs the subscriptions. The history
+    /// is kept as it is until another action is dispatched, at which point the
+    /// actions after the index are dropped. Read-only slices keep the values
+    /// they have now. Returns whether the index is in the history.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - The index of the action.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _on_HistoryList_item_selected(index):
+    ///     store.jump_to(index)
+    /// ```
+    #[export]
+    fn jump_to(&self, _owner: &Object, index: i64) -> bool {
+        let jumped = match self.history.borrow_mut().as_mut() {
+            Some(history) => history.jump_to(index),
+            None => {
+                godot_error!("Jumping to an action needs time travel to be enabled");
+                return false;
+            }
+        };
+        let state = match jumped {
+            Ok(state) => state,
+            Err(message) => {
+                godot_error!("{}", message);
+                return false;
+            }
+        };
+
+        self.travel_to(&state);
+        self.sync_bound_fields();
+        self.dispatch_subscriptions();
+        true
+    }
+
+    /// Runs the actions from an index of `get_history` onward through the
+    /// reducer again, starting from the state before the action at the index,
+    /// and runs the subscriptions once they're done. This is useful to see
+    /// what the same actions do after changing the reducer, for example with
+    /// `replace_reducer`. The middleware is skipped since the history holds
+    /// the actions that reached the reducer, and nothing other than the state
+    /// is changed while the actions are replayed, the same as in
+    /// `run_scenario`. The history is recorded again from the index. Returns
+    /// whether the index is in the history.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - The index of the first action to replay.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _on_ReducerReloaded():
+    ///     store.replace_reducer(self, 'reducer')
+    ///     store.replay_from(0)
+    /// ```
+    #[export]
+    fn replay_from(&self, _owner: &Object, index: i64) -> bool {
+        let rewound = match self.history.borrow_mut().as_mut() {
+            Some(history) => history.rewind_to(index),
+            None => {
+                godot_error!("Replaying the history needs time travel to be enabled");
+                return false;
+            }
+        };
+        let (state, actions) = match rewound {
+            Ok(rewound) => rewound,
+            Err(message) => {
+                godot_error!("{}", message);
+                return false;
+            }
+        };
+
+        self.travel_to(&state);
+
+        let was_running_scenario = self.running_scenario.replace(true);
+        for action in actions {
+            let old_state = self.readable_state();
+            self.dispatch_reducer(&action);
+            if let Some(history) = self.history.borrow_mut().as_mut() {
+                history.record(&action, &old_state, &self.readable_state());
+            }
+        }
+        self.running_scenario.set(was_running_scenario);
+
+        self.sync_bound_fields();
+        self.dispatch_subscriptions();
+        true
+    }
+
+    /// Enables the undo stack, which keeps the changes made by every action
+    /// that reaches the reducer so they can be undone with `undo` and redone
+    /// with `redo`, like the moves in a puzzle or the edits in a level editor.
+    /// Only the keys each action changed are kept, so undoing an action puts
+    /// just those back. Actions of the excluded types, like camera moves,
+    /// aren't kept, and neither are actions that didn't change anything or
+    /// that were dispatched while running a scenario. Dispatching an action
+    /// after undoing forgets the changes that could be redone. The stack is
+    /// trimmed along with the rest of the history by
+    /// `set_history_memory_limit`. Enabling it again starts a new stack.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_depth` - The most changes that are kept, or 0 to disable the
+    /// undo stack.
+    /// * `excluded_types` - Optional action types that aren't kept.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _ready():
+    ///     store.enable_undo(100, [Action.MOVE_CAMERA, Action.ZOOM])
+    /// ```
+    #[export]
+    fn enable_undo(&self, _owner: &Object, max_depth: i64, #[opt] excluded_types: VariantArray) {
+        if max_depth < 0 {
+            godot_error!("The undo depth can't be negative, got {}", max_depth);
+            return;
+        }
+
+        *self.undo_stack.borrow_mut() = if max_depth > 0 {
+            Some(UndoStack::new(max_depth as usize, &excluded_types))
+        } else {
+            None
+        };
+    }
+
+    /// Undoes the latest change in the undo stack and runs the subscriptions.
+    /// Returns whether there was a change to undo.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _unhandled_input(event):
+    ///     if event.is_action_pressed("undo"):
+    ///         store.undo()
+    /// ```
+    #[export]
+    fn undo(&self, _owner: &Object) -> bool {
+        self.undo_step().is_some()
+    }
+
+    /// Redoes the latest change that was undone with `undo` and runs the
+    /// subscriptions. Returns whether there was a change to redo.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _unhandled_input(event):
+    ///     if event.is_action_pressed("redo"):
+    ///         store.redo()
+    /// ```
+    #[export]
+    fn redo(&self, _owner: &Object) -> bool {
+        self.redo_step().is_some()
+    }
+
+    /// Returns whether there's a change in the undo stack that can be undone.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _on_state_changed(state):
+    ///     $UndoButton.disabled = not store.can_undo()
+    /// ```
+    #[export]
+    fn can_undo(&self, _owner: &Object) -> bool {
+        self.undo_stack
+            .borrow()
+            .as_ref()
+            .is_some_and(|undo_stack| undo_stack.undo_count() > 0)
+    }
+
+    /// Returns whether there's a change that was undone and can be redone.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _on_state_changed(state):
+    ///     $RedoButton.disabled = not store.can_redo()
+    /// ```
+    #[export]
+    fn can_redo(&self, _owner: &Object) -> bool {
+        self.undo_stack
+            .borrow()
+            .as_ref()
+            .is_some_and(|undo_stack| undo_stack.redo_count() > 0)
+    }
+
+    /// Undoes the latest change in the undo stack and returns the action that
+    /// made it, or `None` if there's nothing to undo.
+    fn undo_step(&self) -> Option<Variant> {
+        let state = self.readable_state();
+        let undone = self.undo_stack.borrow_mut().as_mut()?.undo(&state);
+
+        undone.map(|(state, undone_action)| {
+            self.change_to(&state, &action::named(undo::UNDO).owned_to_variant());
+            undone_action
+        })
+    }
+
+    /// Redoes the latest change that was undone and returns the action that
+    /// made it, or `None` if there's nothing to redo.
+    fn redo_step(&self) -> Option<Variant> {
+        let state = self.readable_state();
+        let redone = self.undo_stack.borrow_mut().as_mut()?.redo(&state);
+
+        redone.map(|(state, redone_action)| {
+            self.change_to(&state, &action::named(undo::REDO).owned_to_variant());
+            redone_action
+        })
+    }
+
+    /// Replaces the state with one from the undo stack, recording the change
+    /// in the time travel history so it stays in step with the state, and
+    /// runs the subscriptions.
+    ///
+    /// # Arguments
+    ///
+    /// * `state` - The state from the undo stack.
+    /// * `marker` - The action the change is recorded as in the history.
+    fn change_to(&self, state: &Dictionary, marker: &Variant) {
+        let old_state = self.readable_state();
+        self.travel_to(state);
+
+        if let Some(history) = self.history.borrow_mut().as_mut() {
+            history.record(marker, &old_state, &self.readable_state());
+        }
+
+        self.sync_bound_fields();
+        self.dispatch_subscriptions();
+    }
+
+    /// Replaces the state with an earlier or later one, like one from the time
+    /// travel history or the undo stack, keeping the values the read-only
+    /// slices have now, and marks the slices that changed as dirty.
+    ///
+    /// # Arguments
+    ///
+    /// * `state` - The earlier or later state.
+    fn travel_to(&self, state: &Dictionary) {
+        let old_state = self.readable_state();
+        let state = state.duplicate();
+        for slice in self.readonly_slices.borrow().iter() {
+            if old_state.contains(slice) {
+                state.insert(slice, old_state.get(slice));
+            } else {
+                state.erase(slice);
+            }
+        }
+        let state = state.into_shared();
+
+        let previous = self.state.replace(self.sealed(&state));
+        *self.previous_state.borrow_mut() = previous;
+        self.mark_dirty_slices(&old_state, &state);
+    }
+
+    /// Registers a projection, which is a reducer that builds an alternate
+    /// view of the application out of the event log instead of the state, like
+    /// statistics that weren't tracked when the actions were dispatched. The
+    /// projection is run over the whole log every time it's read with
+    /// `get_projection`, so it can be registered or changed at any point.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the projection.
+    /// * `projection_fn_instance` - The instance that contains the projection
+    /// function.
+    /// * `projection_fn_name` - The name of the projection function, which is
+    /// passed the projection so far and an action and returns the projection
+    /// after the action.
+    /// * `initial_state` - The projection before the first action, which is
+    /// an empty Dictionary by default.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func count_jumps(projection, action):
+    ///     if action.type == "jump":
+    ///         return { "jumps": projection.jumps + 1 }
+    ///     return projection
+    ///
+    /// func _ready():
+    ///     store.register_projection("jumps", self, "count_jumps", { "jumps": 0 })
+    /// ```
+    #[export]
+    fn register_projection(
+        &self,
+        _owner: &Object,
+        name: GodotString,
+        projection_fn_instance: Ref<Object, Shared>,
+        projection_fn_name: GodotString,
+        #[opt] initial_state: Variant,
+    ) {
+        let initial_state = initial_state
+            .try_to_dictionary()
+            .unwrap_or_else(Dictionary::new_shared);
+
+        self.projections.borrow_mut().insert(
+            name.to_string(),
+            (
+                func_ref(projection_fn_instance, projection_fn_name),
+                initial_state,
+            ),
+        );
+    }
+
+    /// Runs a projection registered with `register_projection` over every
+    /// action in the event log and returns the result.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the projection.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _on_run_finished():
+    ///     print("Jumped %d times" % store.get_projection("jumps").jumps)
+    /// ```
+    #[export]
+    fn get_projection(&self, _owner: &Object, name: GodotString) -> Dictionary<Unique> {
+        let (projection, initial_state) = match self.projections.borrow().get(&name.to_string()) {
+            Some((projection, initial_state)) => (projection.clone(), initial_state.duplicate()),
+            None => {
+                godot_error!("The projection {} isn't registered", name);
+                return Dictionary::new();
+            }
+        };
+
+        // The events are copied so the projection can dispatch without
+        // invalidating the log being iterated over.
+        let events = match self.event_log.borrow().as_ref() {
+            Some(log) => log.events().to_vec(),
+            None => {
+                godot_error!(
+                    "Unable to run the projection {} since event sourcing isn't enabled",
+                    name
+                );
+                return Dictionary::new();
+            }
+        };
+
+        let mut projected = initial_state.into_shared();
+        for event in events {
+            let event = self.migrated(event);
+            projected = projection
+                .call_func(&[Variant::from_dictionary(&projected), event])
+                .to_dictionary();
+        }
+
+        projected.duplicate()
+    }
+
+    /// Registers the store's commands with an in-game debug console so it can
+    /// drive the store without any glue code. The console needs an
+    /// `add_command(name, instance, method)` method, like most consoles do,
+    /// and the commands are added under `store`, so typing `store help` lists
+    /// them. The console is passed the text typed after `store` and gets the
+    /// output back, which is the same as calling `run_console_command`.
+    ///
+    /// # Arguments
+    ///
+    /// * `console_node` - The console.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _ready():
+    ///     store.register_console_commands($DebugConsole)
+    /// ```
+    #[export]
+    fn register_console_commands(&self, _owner: &Object, console_node: Ref<Object, Shared>) {
+        let console_node = unsafe { console_node.assume_safe() };
+        if !console_node.has_method("add_command") {
+            godot_error!("Unable to register the console commands since the console doesn't have an add_command method");
+            return;
+        }
+
+        unsafe {
+            console_node.call(
+                "add_command",
+                &[
+                    Variant::from_str(console::COMMAND),
+                    self.owner.to_variant(),
+                    Variant::from_str("run_console_command"),
+                ],
+            );
+        }
+    }
+
+    /// Connects the store to a Redux DevTools remote server, like the one
+    /// started with `remotedev-server --port 8000`, so every action shows up
+    /// in the DevTools UI along with the state after it. The UI can travel
+    /// back and forth between the states, which replaces the state of the
+    /// store with the one it travelled to, and actions typed into it are
+    /// dispatched to the store. The read-only slices are left out of what is
+    /// sent since they come with the game, and the protected values are sent
+    /// as they are, so it's only meant for debugging. The connection is kept
+    /// going at the start of every frame, so the store has to be in a
+    /// SceneTree. Connecting again replaces the connection made before.
+    /// Returns whether the connection could be started.
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - Optional URL of the server, which defaults to
+    /// `ws://localhost:8000/socketcluster/`.
+    /// * `name` - Optional name the store shows up as in the DevTools UI,
+    /// which defaults to `Godot`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _ready():
+    ///     if OS.is_debug_build():
+    ///         store.connect_devtools("ws://localhost:8000/socketcluster/", "Platformer")
+    /// ```
+    #[export]
+    fn connect_devtools(
+        &self,
+        owner: TRef<Object>,
+        #[opt] url: GodotString,
+        #[opt] name: GodotString,
+    ) -> bool {
+        let tree = match main_scene_tree() {
+            Some(tree) => unsafe { tree.assume_safe() },
+            None => {
+                godot_error!("Unable to connect to the DevTools since there is no SceneTree");
+                return false;
+            }
+        };
+
+        let connection = match DevTools::connect(&url.to_string(), &name.to_string()) {
+            Ok(connection) => connection,
+            Err(message) => {
+                godot_error!("Unable to connect to the DevTools: {}", message);
+                return false;
+            }
+        };
+        if let Some(previous) = self.devtools.replace(Some(connection)) {
+            previous.close();
+        }
+
+        if !tree.is_connected("idle_frame", owner, "on_devtools_frame") {
+            let connected = tree.connect(
+                "idle_frame",
+                owner,
+                "on_devtools_frame",
+                VariantArray::new_shared(),
+                0,
+            );
+            if let Err(error) = connected {
+                godot_error!("Unable to connect to the DevTools: {:?}", error);
+                *self.devtools.borrow_mut() = None;
+                return false;
+            }
+        }
+
+        true
+    }
+
+    /// Closes the connection to the Redux DevTools server made with
+    /// `connect_devtools`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _exit_tree():
+    ///     store.disconnect_devtools()
+    /// ```
+    #[export]
+    fn disconnect_devtools(&self, owner: TRef<Object>) {
+        if let Some(devtools) = self.devtools.replace(None) {
+            devtools.close();
+        }
+
+        if let Some(tree) = main_scene_tree() {
+            let tree = unsafe { tree.assume_safe() };
+            if tree.is_connected("idle_frame", owner, "on_devtools_frame") {
+                tree.disconnect("idle_frame", owner, "on_devtools_frame");
+            }
+        }
+    }
+
+    /// Sends and receives the messages of the DevTools connection when a
+    /// frame starts, and does what the DevTools UI asked for in them.
+    #[export]
+    fn on_devtools_frame(&self, owner: TRef<Object>) {
+        let polled = match self.devtools.borrow_mut().as_mut() {
+            Some(devtools) => devtools.poll(),
+            None => Ok(vec![]),
+        };
+        let commands = match polled {
+            Ok(commands) => commands,
+            Err(message) => {
+                godot_warn!("Disconnected from the DevTools: {}", message);
+                self.disconnect_devtools(owner);
+                return;
+            }
+        };
+
+        for command in commands {
+            match command {
+                devtools::Command::SendState => {
+                    let state = self.state_without_readonly_slices();
+                    if let Some(devtools) = self.devtools.borrow().as_ref() {
+                        devtools.send_state(&state);
+                    }
+                }
+                devtools::Command::Dispatch(action) => {
+                    self.dispatch_action(action);
+                }
+                devtools::Command::Jump(state) => {
+                    if let Err(errors) = self.merge_state(&state, &Merge::replace()) {
+                        godot_error!(
+                            "Unable to jump to the state from the DevTools since it doesn't match the schema:\n{}",
+                            errors.join("\n")
+                        );
+                        continue;
+                    }
+                    self.sync_bound_fields();
+                    self.dispatch_subscriptions();
+                }
+            }
+            if self.owner_was_freed() {
+                return;
+            }
+        }
+    }
+
+    /// Sends an action that passed the reducer to the DevTools with the state
+    /// after it, if the DevTools are connected and recording.
+    ///
+    /// # Arguments
+    ///
+    /// * `action` - The action that was dispatched.
+    fn send_to_devtools(&self, action: &Variant) {
+        let monitoring = self
+            .devtools
+            .borrow()
+            .as_ref()
+            .is_some_and(|devtools| devtools.is_monitoring());
+        if !monitoring {
+            return;
+        }
+
+        let state = self.state_without_readonly_slices();
+        if let Some(devtools) = self.devtools.borrow().as_ref() {
+            devtools.send_action(action, &state);
+        }
+    }
+
+    /// Returns a copy of the state without the read-only slices, with its
+    /// protected values readable, for sending or saving it somewhere else.
+    fn state_without_readonly_slices(&self) -> Dictionary {
+        let state = self.readable_state().duplicate();
+        for slice in self.readonly_slices.borrow().iter() {
+            state.erase(slice);
+        }
+
+        state.into_shared()
+    }
+
+    /// Runs a debug console command and returns its output, which is an error
+    /// message if the command couldn't be run. The commands are:
+    ///
+    /// * `state` - Shows the whole state.
+    /// * `state get <path>` - Shows the value at a path, like `player/hp`.
+    /// * `state set <path> <json>` - Sets the value at a path with a `__patch`
+    ///   action.
+    /// * `dispatch <json>` - Dispatches an action.
+    /// * `history [count]` - Lists the last actions in the event log.
+    /// * `undo` - Undoes the last action with the undo stack, or rebuilds the
+    ///   state from the event log without it.
+    /// * `cheat <name>` - Uses a cheat registered with `register_cheat`.
+    /// * `cheats` - Lists the cheats that can be used.
+    /// * `help` - Lists the commands.
+    ///
+    /// `history` needs event sourcing to be enabled with `set_event_sourcing`,
+    /// and so does `undo` unless the undo stack is enabled with `enable_undo`.
+    /// Whole numbers in JSON are read as integers so they match enum values.
+    ///
+    /// # Arguments
+    ///
+    /// * `line` - The command.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _on_console_text_entered(text):
+    ///     $Output.text += store.run_console_command(text) + "\n"
+    /// ```
+    #[export]
+    fn run_console_command(&self, _owner: &Object, line: GodotString) -> GodotString {
+        let output = console::parse(&line.to_string()).and_then(|command| match command {
+            Command::Get(None) => Ok(self.state_snapshot()),
+            Command::Get(Some(path)) => path::get(&self.readable_state(), &path)
+                .map(|value| snapshot::to_stable_text(&value))
+                .ok_or_else(|| format!("{} doesn't exist", path)),
+            Command::Set(path, value) => {
+                self.dispatch_action(patch::set(&path, &value).owned_to_variant());
+                Ok(format!("Set {}", path))
+            }
+            Command::Dispatch(action) => {
+                let description = action.to_string();
+                self.dispatch_action(action);
+                Ok(format!("Dispatched {}", description))
+            }
+            Command::History(count) => match self.event_log.borrow().as_ref() {
+                Some(log) => {
+                    let events = log.events();
+                    let start = events.len().saturating_sub(count);
+                    Ok(events[start..]
+                        .iter()
+                        .enumerate()
+                        .map(|(index, event)| format!("{}: {}", start + index, event.to_string()))
+                        .collect::<Vec<_>>()
+                        .join("\n"))
+                }
+                None => Err("The history needs event sourcing to be enabled".to_string()),
+            },
+            Command::Undo => {
+                let undone = if self.undo_stack.borrow().is_some() {
+                    self.undo_step()
+                        .ok_or_else(|| "There's nothing to undo".to_string())
+                } else {
+                    self.undo_last_event()
+                };
+                undone.map(|undone| format!("Undid {}", undone.to_string()))
+            }
+            Command::Cheat(name) => {
+                let action = self.cheats.borrow().action(&name);
+                action.map(|action| {
+                    self.dispatch_action(action);
+                    format!("Used {}", name)
+                })
+            }
+            Command::Cheats => Ok(self
+                .cheats
+                .borrow()
+                .available()
+                .iter()
+                .map(|(name, level)| format!("{} (level {})", name, level))
+                .collect::<Vec<_>>()
+                .join("\n")),
+            Command::Help => Ok(console::HELP.to_string()),
+        });
+
+        match output {
+            Ok(output) => GodotString::from_str(output),
+            Err(message) => GodotString::from_str(message),
+        }
+    }
+
+    /// Rebuilds the state from the event log without its last action and
+    /// returns the action, or an error if there's nothing to undo.
+    fn undo_last_event(&self) -> Result<Variant, String> {
+        let mut log = self
+            .event_log
+            .borrow_mut()
+            .take()
+            .ok_or_else(|| "Undoing needs event sourcing to be enabled".to_string())?;
+
+        match log.pop() {
+            Some(undone) => {
+                self.replay_event_log(log);
+                Ok(undone)
+            }
+            None => {
+                *self.event_log.borrow_mut() = Some(log);
+                Err("There's nothing to undo".to_string())
+            }
+        }
+    }
+
+    /// Dispatches an action to update the state. The action can either be
+    /// just its type, like a value from an enum, or a Dictionary with its type
+    /// under the `type` key and any data the reducer needs under other keys.
+    /// An action with the `__patch` type changes the state with the JSON Patch
+    /// operations under `ops` without going through the reducer. Returns what
+    /// the first middleware returned, which is the action itself if there's no
+    /// middleware, or `null` if the action was dropped.
+    ///
+    /// # Arguments
+    ///
+    /// * `action` - The action to dispatch.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// const state = {
+    ///     "counter": 0,
+    /// }
+    ///
+    /// enum Action {
+    ///     INCREMENT,
+    ///     DECREMENT,
+    /// }
+    ///
+    /// func reducer(state, action):
+    ///     match action:
+    ///         Action.INCREMENT:
+    ///             return {
+    ///                 "counter": state.counter + 1,
+    ///             }
+    ///         Action.DECREMENT:
+    ///             return {
+    ///                 "counter": state.counter - 1,
+    ///             }
+    ///
+    /// func _ready():
+    ///     var store = Store.new(state, self, 'reducer')
+    ///     store.dispatch(Action.INCREMENT)
+    ///     store.dispatch({ "type": Action.INCREMENT, "amount": 5 })
+    /// ```
+    #[export]
+    fn dispatch(&self, _owner: &Object, action: Variant) -> Variant {
+        self.dispatch_action(action)
+    }
+
+    /// Calls a function and batches every dispatch made inside of it, so the
+    /// actions are applied one after the other but subscribers are only
+    /// notified once, with the state after the last one. This is useful in
+    /// callbacks like physics collisions that dispatch many actions at once.
+    /// Batches can be nested, in which case subscribers are notified when the
+    /// outermost one is done.
+    ///
+    /// # Arguments
+    ///
+    /// * `instance` - The instance on which the function exists.
+    /// * `fn_name` - The name of the function, which is called without any
+    /// arguments.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _on_body_entered(body):
+    ///     store.with_batch(self, "_apply_hit")
+    ///
+    /// func _apply_hit():
+    ///     store.dispatch({ "type": Action.DAMAGE, "amount": 10 })
+    ///     store.dispatch({ "type": Action.KNOCKBACK, "force": 5 })
+    ///     store.dispatch({ "type": Action.ADD_COMBO })
+    /// ```
+    #[export]
+    fn with_batch(
+        &self,
+        _owner: &Object,
+        instance: Ref<Object, Shared>,
+        fn_name: GodotString,
+    ) -> Variant {
+        let batch = func_ref(instance, fn_name);
+
+        self.batched(|| batch.call_func(&[]))
+    }
+
+    /// Runs a function with every dispatch made inside of it batched, so
+    /// subscribers are only notified once the outermost batch is done.
+    ///
+    /// # Arguments
+    ///
+    /// * `batch` - The function to run.
+    fn batched<R>(&self, batch: impl FnOnce() -> R) -> R {
+        self.batch_depth.set(self.batch_depth.get() + 1);
+        let result = batch();
+        self.batch_depth.set(self.batch_depth.get() - 1);
+
+        if self.batch_depth.get() == 0 && self.batch_changed.replace(false) {
+            self.dispatch_subscriptions();
+        }
+
+        result
+    }
+
+    /// Defines a macro, which is a higher-level action made up of other
+    /// actions, so designers can compose new verbs without new reducer code.
+    /// Defining a macro again replaces it.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the macro.
+    /// * `actions` - The actions the macro dispatches, in order.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _ready():
+    ///     store.define_macro("respawn", [
+    ///         { "type": Action.HEAL_FULL },
+    ///         { "type": Action.MOVE_TO_CHECKPOINT },
+    ///         Action.RESET_COMBO,
+    ///     ])
+    /// ```
+    #[export]
+    fn define_macro(&self, _owner: &Object, name: GodotString, actions: VariantArray) {
+        self.macros
+            .borrow_mut()
+            .insert(name.to_string(), actions.iter().collect());
+    }
+
+    /// Dispatches the actions of a macro defined with `define_macro` as a
+    /// batch, so subscribers are only notified once after the last one. The
+    /// payload is merged into every action, turning actions that are just
+    /// their type into Dictionaries, without replacing their type or any value
+    /// they already have.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the macro.
+    /// * `payload` - Optional data to add to every action.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _on_player_died():
+    ///     store.dispatch_macro("respawn", { "checkpoint": "cave_entrance" })
+    /// ```
+    #[export]
+    fn dispatch_macro(&self, _owner: &Object, name: GodotString, #[opt] payload: Dictionary) {
+        let actions = match self.macros.borrow().get(&name.to_string()) {
+            Some(actions) => actions.clone(),
+            None => {
+                godot_error!("The macro {} isn't defined", name);
+                return;
+            }
+        };
+
+        self.batched(|| {
+            for action in actions {
+                self.dispatch_action(with_payload(&action, &payload));
+            }
+        });
+    }
+
+    /// Queues an action to be dispatched at the end of the current frame
+    /// instead of right away, so many nodes dispatching from
+    /// `_physics_process` don't change the state in the middle of the frame.
+    /// The queued actions are dispatched one after the other as a batch, so
+    /// subscribers are only notified once after the last one. Actions are
+    /// queued in lanes that are dispatched in order: `immediate`, then
+    /// `normal`, which is the default, and then `idle`. Actions in the same
+    /// lane are dispatched in the order they were queued. Actions queued
+    /// during a dry run, like `preview` or `run_scenario`, are dropped so they
+    /// don't change the live state on the next frame.
+    ///
+    /// # Arguments
+    ///
+    /// * `action` - The action to dispatch.
+    /// * `lane` - Optional lane to queue the action in.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _on_enemy_died():
+    ///     store.dispatch_deferred({ "type": Action.ADD_SCORE, "amount": 100 })
+    ///     store.dispatch_deferred({ "type": Action.TRACK, "event": "enemy_died" }, "idle")
+    /// ```
+    #[export]
+    fn dispatch_deferred(&self, owner: &Object, action: Variant, #[opt] lane: GodotString) {
+        let lane = match Lane::from_name(&lane.to_string()) {
+            Ok(lane) => lane,
+            Err(message) => {
+                godot_error!("{}", message);
+                return;
+            }
+        };
+
+        let was_empty = self.deferred_dispatches.borrow_mut().push(
+            action,
+            self.correlation.get(),
+            lane,
+            self.running_scenario.get(),
+        );
+
+        if was_empty {
+            unsafe { owner.call_deferred("flush_deferred_dispatches", &[]) };
+        }
+    }
+
+    /// Dispatches every action that was queued with `dispatch_deferred` as a
+    /// batch, notifying subscribers once after the last one. This is called
+    /// automatically at the end of the frame so it only needs to be called to
+    /// dispatch the queued actions early. Actions queued while the
+    /// queue is being flushed are left for the next flush, as are the normal
+    /// and idle actions over the budget set with `set_deferred_budget`, and
+    /// actions whose pause mode is `stop` are held until the tree is unpaused.
+    #[export]
+    fn flush_deferred_dispatches(&self, owner: &Object) {
+        // The queue is taken before dispatching so that anything queued by the
+        // reducer, middleware, or subscriptions schedules a new flush.
+        let (actions, over_budget) = {
+            let mut deferred_dispatches = self.deferred_dispatches.borrow_mut();
+            let actions = deferred_dispatches.take(self.deferred_budget.get());
+
+            (actions, !deferred_dispatches.is_empty())
+        };
+        if over_budget {
+            unsafe { owner.call_deferred("flush_deferred_dispatches", &[]) };
+        }
+
+        let paused = self.is_paused();
+
+        self.batched(|| {
+            for (action, correlation) in actions {
+                if self.owner_was_freed() {
+                    return;
+                }
+
+                let frozen = paused && self.pause_modes.borrow().stops_action(&action);
+                if frozen {
+                    self.frozen_dispatches
+                        .borrow_mut()
+                        .push((action, correlation));
+                } else {
+                    self.dispatch_in_correlation(action, correlation);
+                }
+            }
+        });
+    }
+
+    /// Sets the most normal and idle actions queued with `dispatch_deferred`
+    /// that are dispatched in a frame, so a burst of cosmetic or analytics
+    /// actions doesn't blow the frame budget. The actions over the budget are
+    /// dispatched on the next frames, and immediate actions are always
+    /// dispatched.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_actions` - The most actions to dispatch in a frame, or 0 for no
+    /// limit, which is the default.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _ready():
+    ///     store.set_deferred_budget(16)
+    /// ```
+    #[export]
+    fn set_deferred_budget(&self, _owner: &Object, max_actions: i64) {
+        if max_actions < 0 {
+            godot_error!("The deferred budget can't be negative, got {}", max_actions);
+            return;
+        }
+
+        self.deferred_budget
+            .set(Some(max_actions as usize).filter(|max_actions| *max_actions > 0));
+    }
+
+    /// Returns what threads other than the main thread dispatch actions
+    /// through, since the store can only be called from the main thread. The
+    /// dispatcher has to be gotten on the main thread and handed to the
+    /// thread, which can then call its `dispatch_threadsafe` with an action
+    /// from anywhere. The action is copied and queued behind a lock, and once
+    /// the main thread gets to it, it's queued like an action dispatched with
+    /// `dispatch_deferred` in the normal lane and dispatched at the end of the
+    /// frame. Actions from the same thread are dispatched in the order they
+    /// were queued.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _on_target_picked(target):
+    ///     thread.start(self, "_find_path", [store.get_threadsafe_dispatcher(), target])
+    ///
+    /// func _find_path(args):
+    ///     var path = find_path_to(args[1])
+    ///     args[0].dispatch_threadsafe({ "type": Action.PATH_FOUND, "path": path })
+    /// ```
+    #[export]
+    fn get_threadsafe_dispatcher(&self, _owner: &Object) -> Instance<ThreadSafeDispatcher, Shared> {
+        self.threadsafe_dispatcher
+            .borrow_mut()
+            .get_or_insert_with(|| {
+                Instance::emplace(ThreadSafeDispatcher::create(self.owner_id)).into_shared()
+            })
+            .clone()
+    }
+
+    /// Queues the actions dispatched from other threads with the
+    /// dispatcher returned by `get_threadsafe_dispatcher` to be dispatched at
+    /// the end of the frame. This is called on the main thread automatically
+    /// once an action is dispatched from another thread.
+    #[export]
+    fn flush_threadsafe_dispatches(&self, owner: &Object) {
+        let dispatcher = self.threadsafe_dispatcher.borrow().clone();
+        let actions = match dispatcher {
+            Some(dispatcher) => unsafe { dispatcher.assume_safe() }
+                .map(|dispatcher, _| dispatcher.take())
+                .unwrap_or_default(),
+            None => return,
+        };
+
+        for action in actions {
+            self.dispatch_deferred(owner, action, GodotString::new());
+        }
+    }
+
+    /// Sets whether the deferred actions of a type are held while the tree the
+    /// store is attached to is paused. The mode can either be `stop`, which
+    /// holds them until the tree is unpaused like gameplay actions, or
+    /// `process`, which keeps dispatching them like UI and menu actions and is
+    /// the default.
+    ///
+    /// # Arguments
+    ///
+    /// * `action_type` - The type of the actions.
+    /// * `mode` - The pause mode, either `stop` or `process`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _ready():
+    ///     store.attach_to_tree(get_tree())
+    ///     store.set_action_pause_mode(Action.ADD_SCORE, 'stop')
+    ///     store.set_action_pause_mode(Action.OPEN_MENU, 'process')
+    /// ```
+    #[export]
+    fn set_action_pause_mode(&self, _owner: &Object, action_type: Variant, mode: GodotString) {
+        match pause::Mode::from_name(&mode.to_string()) {
+            Ok(mode) => self
+                .pause_modes
+                .borrow_mut()
+                .set_action_mode(action_type, mode),
+            Err(message) => godot_error!("{}", message),
+        }
+    }
+
+    /// Sets whether the timed updates of a slice the store manages, like the
+    /// countdown of the `cooldowns` slice, are frozen while the tree the store
+    /// is attached to is paused. The mode can either be `stop` or `process`,
+    /// which is the default.
+    ///
+    /// # Arguments
+    ///
+    /// * `slice` - The key of the slice.
+    /// * `mode` - The pause mode, either `stop` or `process`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _ready():
+    ///     store.attach_to_tree(get_tree())
+    ///     store.set_slice_pause_mode('cooldowns', 'stop')
+    /// ```
+    #[export]
+    fn set_slice_pause_mode(&self, _owner: &Object, slice: GodotString, mode: GodotString) {
+        match pause::Mode::from_name(&mode.to_string()) {
+            Ok(mode) => self
+                .pause_modes
+                .borrow_mut()
+                .set_slice_mode(&slice.to_string(), mode),
+            Err(message) => godot_error!("{}", message),
+        }
+    }
+
+    /// Returns whether the tree the store is attached to is paused.
+    fn is_paused(&self) -> bool {
+        match self.tree.borrow().as_ref() {
+            Some(tree) => unsafe { tree.assume_safe() }.is_paused(),
+            None => false,
+        }
+    }
+
+    /// Returns whether the timed updates of a slice are frozen right now.
+    ///
+    /// # Arguments
+    ///
+    /// * `slice` - The key of the slice.
+    fn is_slice_frozen(&self, slice: &str) -> bool {
+        self.is_paused() && self.pause_modes.borrow().stops_slice(slice)
+    }
+
+    /// Dispatches the deferred actions that were held while the tree was
+    /// paused, once it isn't anymore.
+    fn thaw_dispatches(&self) {
+        if self.frozen_dispatches.borrow().is_empty() || self.is_paused() {
+            return;
+        }
+
+        let actions = self.frozen_dispatches.replace(vec![]);
+        for (action, correlation) in actions {
+            self.dispatch_in_correlation(action, correlation);
+        }
+    }
+
+    /// Simulates a number of frames passing so code that relies on deferred
+    /// dispatches or on the frame count can be tested without waiting on the
+    /// engine. Each frame advances the frame count, dispatches any replayed
+    /// input that is due, and then dispatches the actions that were queued
+    /// before it, so an action queued while handling another one is dispatched
+    /// on the next frame just like it would be in the game.
+    ///
+    /// # Arguments
+    ///
+    /// * `frames` - The number of frames to simulate.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func test_score_is_added_after_enemy_dies():
+    ///     enemy.die()
+    ///     store.simulate_frames(1)
+    ///
+    ///     assert_eq(store.get_value("score"), 100)
+    /// ```
+    #[export]
+    fn simulate_frames(&self, owner: &Object, frames: i64) {
+        for _ in 0..frames.max(0) {
+            self.advance_frame();
+            if self.owner_was_freed() {
+                return;
+            }
+            self.flush_deferred_dispatches(owner);
+        }
+    }
+
+    /// Counts the frames of the tree so `get_frame`, input recording, and input
+    /// replays follow the game. Frames are counted on the tree's
+    /// `physics_frame` signal so they run at a fixed rate. The store also checks
+    /// whether this tree is paused for the pause modes.
+    ///
+    /// # Arguments
+    ///
+    /// * `tree` - The SceneTree whose frames should be counted.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _ready():
+    ///     store.set_state_and_reducer(state, self, 'reducer')
+    ///     store.attach_to_tree(get_tree())
+    /// ```
+    #[export]
+    fn attach_to_tree(&self, owner: TRef<Object>, tree: Ref<SceneTree, Shared>) {
+        *self.tree.borrow_mut() = Some(tree.clone());
+
+        let tree = unsafe { tree.assume_safe() };
+        let connected = tree.connect(
+            "physics_frame",
+            owner,
+            "on_physics_frame",
+            VariantArray::new_shared(),
+            0,
+        );
+
+        if let Err(error) = connected {
+            godot_error!("Couldn't attach the store to the tree: {:?}", error);
+        }
+    }
+
+    /// Advances the frame count when the tree the store is attached to starts
+    /// a physics frame.
+    #[export]
+    fn on_physics_frame(&self, _owner: &Object) {
+        self.advance_frame();
+    }
+
+    /// Enables or disables the `stats_tick` signal, which is emitted once a
+    /// second with the number of actions that were dispatched in that second,
+    /// the number of actions waiting in the `dispatch_deferred` queue, and how
+    /// long an outermost dispatch took on average in milliseconds, including
+    /// the dispatches it caused. This lets live dashboards and systems that
+    /// adapt the quality of the game react when the store gets busy. The
+    /// signal is only emitted while the store is attached to the tree with
+    /// `attach_to_tree`, and the second is measured with the store's clock.
+    /// Telemetry also keeps the stats returned by `get_stats`.
+    ///
+    /// # Arguments
+    ///
+    /// * `enabled` - Whether the signal should be emitted.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _ready():
+    ///     store.set_telemetry(true)
+    ///     store.connect("stats_tick", self, "_on_stats_tick")
+    ///
+    /// func _on_stats_tick(dispatch_count, queue_depth, avg_dispatch_ms):
+    ///     $Dashboard.text = "%d/s, %d queued, %.2f ms" % [dispatch_count, queue_depth, avg_dispatch_ms]
+    /// ```
+    #[export]
+    fn set_telemetry(&self, _owner: &Object, enabled: bool) {
+        *self.telemetry.borrow_mut() = if enabled {
+            Some(Telemetry::new(self.now(), self.stats_sample_rate.get()))
+        } else {
+            None
+        };
+    }
+
+    /// Returns the stats kept by telemetry since it was enabled with
+    /// `set_telemetry` or `reset_stats` was called, or an empty Dictionary if
+    /// telemetry isn't enabled. These are about the store itself and not the
+    /// statistics of the game kept in the `stats` slice by `enable_stats`.
+    /// The stats have:
+    ///
+    /// * `dispatches` - The number of actions that were dispatched.
+    /// * `profiled` - The number of outermost dispatches that were profiled.
+    /// * `sample_rate` - One in how many outermost dispatches is profiled.
+    /// * `avg_dispatch_ms` - How long a profiled dispatch took on average, in
+    ///   milliseconds.
+    /// * `max_dispatch_ms` - How long the slowest profiled dispatch took.
+    /// * `slices` - A breakdown of each slice by key, with the number of
+    ///   times its reducer was run under `reductions`, how long its reducer
+    ///   took altogether and on average in milliseconds under `reducer_ms` and
+    ///   `avg_reducer_ms`, and the number of profiled dispatches that changed
+    ///   it under `changes`. Only slices with reducers of their own, from
+    ///   `combine_reducers` or `inject_reducer`, have their reducers timed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _on_debug_overlay_toggled():
+    ///     var stats = store.get_stats()
+    ///     for slice in stats.slices:
+    ///         print("%s: %.3f ms" % [slice, stats.slices[slice].avg_reducer_ms])
+    /// ```
+    #[export]
+    fn get_stats(&self, _owner: &Object) -> Dictionary {
+        match self.telemetry.borrow().as_ref() {
+            Some(telemetry) => telemetry.to_dictionary().into_shared(),
+            None => Dictionary::new_shared(),
+        }
+    }
+
+    /// Forgets the stats kept by telemetry and how long the calls to the
+    /// subscribers took, and starts counting again, for example to profile a
+    /// single level or a single fight. The sample rate and the subscriber
+    /// budget are kept.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _on_boss_fight_started():
+    ///     store.reset_stats()
+    /// ```
+    #[export]
+    fn reset_stats(&self, _owner: &Object) {
+        let now = self.now();
+        if let Some(telemetry) = self.telemetry.borrow_mut().as_mut() {
+            telemetry.reset(now);
+        }
+        self.call_budget.borrow_mut().reset();
+    }
+
+    /// Returns the subscribers whose calls took at least a number of
+    /// milliseconds altogether since the store was created or `reset_stats`
+    /// was called, slowest first, so frame spikes caused by heavy subscribers
+    /// can be traced back to them. Subscriptions added with `subscribe`,
+    /// `subscribe_selector`, and `subscribe_to_path` are timed, along with
+    /// the selectors of `subscribe_selector`. Each subscriber is a Dictionary
+    /// with:
+    ///
+    /// * `subscriber` - The class and id of the instance and the name of the
+    ///   function, like `Node#1234.on_state_changed`.
+    /// * `calls` - The number of times it was called.
+    /// * `total_ms` - How long the calls took altogether, in milliseconds.
+    /// * `avg_ms` - How long a call took on average.
+    /// * `max_ms` - How long the slowest call took.
+    ///
+    /// # Arguments
+    ///
+    /// * `threshold_ms` - How long the calls to a subscriber have to have
+    /// taken altogether for it to be returned, in milliseconds.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _on_debug_overlay_toggled():
+    ///     for subscriber in store.get_slow_subscribers(5.0):
+    ///         print("%s: %.3f ms" % [subscriber.subscriber, subscriber.total_ms])
+    /// ```
+    #[export]
+    fn get_slow_subscribers(&self, _owner: &Object, threshold_ms: f64) -> VariantArray {
+        self.call_budget
+            .borrow()
+            .slow((threshold_ms * 1000.0) as i64)
+            .into_shared()
+    }
+
+    /// Sets how long a single call to a subscriber can take during a
+    /// dispatch before a warning naming the subscriber is printed, so a
+    /// listener that causes a frame spike is caught when it happens.
+    ///
+    /// # Arguments
+    ///
+    /// * `budget_ms` - How long a call can take, in milliseconds, or 0 to stop
+    /// warning.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _ready():
+    ///     if OS.is_debug_build():
+    ///         store.set_subscriber_budget(2.0)
+    /// ```
+    #[export]
+    fn set_subscriber_budget(&self, _owner: &Object, budget_ms: f64) {
+        let budget_usec = Some((budget_ms * 1000.0) as i64).filter(|budget| *budget > 0);
+        self.call_budget.borrow_mut().set_budget(budget_usec);
+    }
+
+    /// Enables or disables recording how often each type of action is
+    /// dispatched during the play session and what's in them, so designers
+    /// can see how players actually play, like how often they heal or which
+    /// weapons they pick, without an analytics service. Enabling it starts a
+    /// new session, and disabling it drops what was recorded. Only actions
+    /// that pass the reducer are recorded, and the time is measured with the
+    /// store's clock.
+    ///
+    /// # Arguments
+    ///
+    /// * `enabled` - Whether actions should be recorded.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _ready():
+    ///     if OS.has_feature("playtest"):
+    ///         store.set_session_analytics(true)
+    /// ```
+    #[export]
+    fn set_session_analytics(&self, _owner: &Object, enabled: bool) {
+        *self.session_analytics.borrow_mut() = if enabled {
+            Some(SessionAnalytics::new(self.now()))
+        } else {
+            None
+        };
+    }
+
+    /// Returns what was recorded since `set_session_analytics` enabled it, or
+    /// an empty Dictionary if it isn't enabled, in a Dictionary with:
+    ///
+    /// * `duration` - How long the session has lasted, in seconds.
+    /// * `action_count` - The number of actions that were recorded.
+    /// * `actions` - The stats of each action type, by type, which are:
+    ///   * `count` - The number of actions.
+    ///   * `per_minute` - How many were dispatched a minute on average.
+    ///   * `first_at` and `last_at` - The times since the start of the
+    ///     session the first and the last one were dispatched at, in seconds.
+    ///   * `payload` - The stats of each field of the actions, by key, with
+    ///     the `count`, `sum`, `min`, `max`, and `average` of the numbers, and
+    ///     the number of times each text or bool was seen under `values`, up
+    ///     to 20 different ones with the rest counted under `other`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _on_level_finished():
+    ///     var heals = store.get_session_analytics().actions.get(Action.HEAL)
+    ///     if heals:
+    ///         print("Healed %d times, %.1f hp on average" % [heals.count, heals.payload.amount.average])
+    /// ```
+    #[export]
+    fn get_session_analytics(&self, _owner: &Object) -> Dictionary<Unique> {
+        let now = self.now();
+        self.session_analytics
+            .borrow()
+            .as_ref()
+            .map_or_else(Dictionary::new, |analytics| analytics.to_dictionary(now))
+    }
+
+    /// Writes what `get_session_analytics` returns to a file as pretty
+    /// printed JSON with sorted keys, so playtest sessions can be collected
+    /// and compared. Returns whether the file could be written.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path to the file, for example
+    /// `user://analytics/session.json`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _exit_tree():
+    ///     store.export_session_analytics("user://analytics/%d.json" % OS.get_unix_time())
+    /// ```
+    #[export]
+    fn export_session_analytics(&self, owner: &Object, path: GodotString) -> bool {
+        if self.session_analytics.borrow().is_none() {
+            godot_error!("Unable to export the session analytics since they aren't enabled");
+            return false;
+        }
+
+        let analytics = self.get_session_analytics(owner);
+        let text = snapshot::to_stable_text(&analytics.owned_to_variant());
+        match file::write_text(&path.to_string(), &text) {
+            Ok(()) => true,
+            Err(message) => {
+                godot_error!("Unable to export the session analytics: {}", message);
+                false
+            }
+        }
+    }
+
+    /// Records an action that passed the reducer in the session analytics,
+    /// if they're enabled.
+    ///
+    /// # Arguments
+    ///
+    /// * `action` - The action that was dispatched.
+    fn record_session_action(&self, action: &Variant) {
+        let now = self.now();
+        if let Some(analytics) = self.session_analytics.borrow_mut().as_mut() {
+            analytics.record(action, now);
+        }
+    }
+
+    /// Sets one in how many outermost dispatches is profiled by telemetry,
+    /// which times the dispatch and the reducers of the slices. Every dispatch
+    /// is still counted, but profiling fewer of them keeps the overhead down
+    /// in shipped debug builds. It starts out at 1, which profiles every
+    /// dispatch.
+    ///
+    /// # Arguments
+    ///
+    /// * `rate` - The rate, for example 10 to profile one in every ten
+    /// dispatches.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _ready():
+    ///     store.set_telemetry(true)
+    ///     store.set_stats_sample_rate(10)
+    /// ```
+    #[export]
+    fn set_stats_sample_rate(&self, _owner: &Object, rate: i64) {
+        if rate < 1 {
+            godot_error!("The sample rate has to be 1 or more, got {}", rate);
+            return;
+        }
+
+        self.stats_sample_rate.set(rate as u64);
+        if let Some(telemetry) = self.telemetry.borrow_mut().as_mut() {
+            telemetry.set_sample_rate(rate as u64);
+        }
+    }
+
+    /// Emits `stats_tick` if telemetry is enabled and a second has passed
+    /// since it was last emitted.
+    fn report_telemetry(&self) {
+        if self.telemetry.borrow().is_none() {
+            return;
+        }
+
+        let now = self.now();
+        let report = self
+            .telemetry
+            .borrow_mut()
+            .as_mut()
+            .and_then(|telemetry| telemetry.report(now));
+        let (dispatch_count, avg_dispatch_ms) = match report {
+            Some(report) => report,
+            None => return,
+        };
+        let queue_depth = self.deferred_dispatches.borrow().len();
+
+        if self.owner_was_freed() {
+            return;
+        }
+        unsafe { self.owner.assume_safe() }.emit_signal(
+            "stats_tick",
+            &[
+                Variant::from_i64(dispatch_count as i64),
+                Variant::from_i64(queue_depth as i64),
+                Variant::from_f64(avg_dispatch_ms),
+            ],
+        );
+    }
+
+    /// Returns the number of frames that have passed since the store was
+    /// attached to the tree, including simulated frames.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func test_frames_are_counted():
+    ///     store.simulate_frames(3)
+    ///
+    ///     assert_eq(store.get_frame(), 3)
+    /// ```
+    #[export]
+    fn get_frame(&self, _owner: &Object) -> i64 {
+        self.frame.get() as i64
+    }
+
+    /// Advances the frame count, dispatches the deferred actions that were held
+    /// while the tree was paused and the replayed input that is due by the new
+    /// frame, counts down the running cooldowns, loads some of the resources
+    /// being preloaded, dispatches the ticks that are due, and emits
+    /// `stats_tick` if a second has passed since it was last emitted.
+    fn advance_frame(&self) {
+        let frame = self.frame.get() + 1;
+        self.frame.set(frame);
+
+        self.thaw_dispatches();
+        self.replay_due_input(frame);
+        self.tick_cooldowns();
+        self.poll_preloads();
+        self.dispatch_due_ticks();
+        self.report_telemetry();
+    }
+
+    /// Dispatches the replayed input that is due by a frame.
+    ///
+    /// # Arguments
+    ///
+    /// * `frame` - The current frame.
+    fn replay_due_input(&self, frame: u64) {
+        let due = match self.input_replay.borrow_mut().as_mut() {
+            Some(replay) => replay.due(frame),
+            None => return,
+        };
+        let was_replaying = self.replaying_input.replace(true);
+        for action in due {
+            self.dispatch_action(action);
+        }
+        self.replaying_input.set(was_replaying);
+
+        // The replay is checked again since the replayed actions might have
+        // stopped it or started another one.
+        let finished = self
+            .input_replay
+            .borrow()
+            .as_ref()
+            .is_some_and(|replay| replay.is_finished());
+        if finished {
+            self.emit_after_update("input_replay_finished", vec![]);
+            self.end_input_replay();
+        }
+    }
+
+    /// Dispatches an action as part of the dispatch in progress, or with a new
+    /// correlation id if there isn't one.
+    ///
+    /// # Arguments
+    ///
+    /// * `action` - The action to dispatch.
+    fn dispatch_action(&self, action: Variant) -> Variant {
+        match self.correlation.get() {
+            Some(_) => self.run_dispatch(action),
+            None => {
+                let correlation = self.next_correlation.get();
+                self.next_correlation.set(correlation + 1);
+
+                self.dispatch_in_correlation(action, Some(correlation))
+            }
+        }
+    }
+
+    /// Dispatches an action with a correlation id, like a deferred action with
+    /// the id of the dispatch that queued it, or with a new id if it's `None`.
+    ///
+    /// # Arguments
+    ///
+    /// * `action` - The action to dispatch.
+    /// * `correlation` - The correlation id.
+    fn dispatch_in_correlation(&self, action: Variant, correlation: Option<u64>) -> Variant {
+        let previous = self.correlation.replace(correlation);
+        let result = self.dispatch_action(action);
+        self.correlation.set(previous);
+
+        result
+    }
+
+    /// Returns the correlation id of the dispatch in progress, or `null` if
+    /// nothing is being dispatched. Every action dispatched because of another
+    /// one, by middleware, effects, subscriptions, or with `dispatch_deferred`,
+    /// shares its correlation id, so logs can show which action caused which.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func log_middleware(state, action):
+    ///     print("[%s] %s" % [store.get_current_correlation(), action])
+    ///     return action
+    /// ```
+    #[export]
+    fn get_current_correlation(&self, _owner: &Object) -> Variant {
+        match self.correlation.get() {
+            Some(correlation) => Variant::from_u64(correlation),
+            None => Variant::new(),
+        }
+    }
+
+    /// Runs an action through the middleware and the reducer and then runs the
+    /// subscriptions. Returns what the first middleware returned, the action if
+    /// there's no middleware, or `null` if the action was dropped.
+    ///
+    /// # Arguments
+    ///
+    /// * `action` - The action to dispatch.
+    fn run_dispatch(&self, action: Variant) -> Variant {
+        if self.owner_was_freed() {
+            return Variant::new();
+        }
+
+        // Actions are dropped while dispatching is halted, since the
+        // `fatal_error` signal already reported why.
+        if self.dispatch_halted.get() {
+            return Variant::new();
+        }
+
+        // Dry runs leave the tamper check, the dispatch log and the telemetry
+        // alone, since the action never reaches the live state.
+        let dry_run = self.running_scenario.get();
+        if !dry_run {
+            self.detect_tampering();
+        }
+        let action = self.migrated(action);
+
+        if let Err(message) = self.enter_dispatch(&action) {
+            godot_error!("{}", message);
+            self.pipeline_failed("dispatch", &action, &message);
+            return Variant::new();
+        }
+
+        if !dry_run {
+            if let Some(log) = self.dispatch_log.borrow_mut().as_mut() {
+                log.record(&action);
+            }
+        }
+
+        // Only outermost dispatches are profiled, along with everything they
+        // dispatch.
+        let outermost = self.dispatch_stack.borrow().len() == 1;
+        let started = match self.telemetry.borrow_mut().as_mut() {
+            Some(telemetry) if !dry_run => {
+                telemetry.count_dispatch();
+                if outermost && telemetry.start_dispatch() {
+                    Some(clock::real_now())
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        };
+
+        let result = if self.is_duplicate(&action) {
+            // Actions that are the same as one dispatched within their dedupe
+            // window are collapsed into that one.
+            Variant::new()
+        } else if self.is_gated(&action) {
+            // Actions gated behind a disabled flag are dropped.
+            Variant::new()
+        } else if let Some(cheat) = self.forbidden_cheat(&action) {
+            godot_warn!(
+                "The action {} was rejected since the cheat {} needs a higher permission level",
+                action.to_string(),
+                cheat
+            );
+            Variant::new()
+        } else if let Err(message) = self.validate_payload(&action) {
+            godot_error!(
+                "The action {} was rejected: {}",
+                action.to_string(),
+                message
+            );
+            Variant::new()
+        } else if let Some(field) = self.blocked_field(&action) {
+            self.dispatch_action(
+                content_filter::content_blocked(&action, &field).owned_to_variant(),
+            );
+            Variant::new()
+        } else if self.middleware.borrow().is_empty() {
+            self.dispatch_reducer(&action);
+            action
+        } else {
+            let context = Dictionary::new();
+            if let Some(correlation) = self.correlation.get() {
+                context.insert(CORRELATION_ID_KEY, correlation);
+            }
+
+            self.dispatch_middleware(0, action, context.into_shared())
+        };
+
+        self.leave_dispatch();
+
+        if let Some(started) = started {
+            if let Some(telemetry) = self.telemetry.borrow_mut().as_mut() {
+                let usec = (clock::real_now() - started) * 1_000_000.0;
+                telemetry.time_dispatch(usec as i64);
+            }
+        }
+
+        result
+    }
+
+    /// Finishes the dispatch in progress, trimming the history, saving the
+    /// statistics, and calling the bound effects if it was the outermost one.
+    fn leave_dispatch(&self) {
+        self.dispatch_stack.borrow_mut().pop();
+
+        let outermost = self.dispatch_stack.borrow().is_empty();
+        if outermost {
+            self.trim_history();
+            self.save_stats();
+            self.call_bound_effects();
+            self.run_pending_pipelines();
+        }
+    }
+
+    /// Sets the maximum number of dispatches that can be nested inside of each
+    /// other, for example when a middleware dispatches an action that has a
+    /// middleware that dispatches another action. Dispatches that would go past
+    /// this depth are dropped and an error is printed. An action that is
+    /// dispatched again with the same data while it's still being dispatched
+    /// is dropped as a cycle right away, while actions of the same type with
+    /// different data can nest up to this depth.
+    ///
+    /// # Arguments
+    ///
+    /// * `depth` - The maximum dispatch depth, which has to be at least 1.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _ready():
+    ///     var store = Store.new()
+    ///     store.set_state_and_reducer(state, self, 'reducer')
+    ///     store.set_max_dispatch_depth(8)
+    /// ```
+    #[export]
+    fn set_max_dispatch_depth(&self, _owner: &Object, depth: i64) {
+        if depth < 1 {
+            godot_error!("The max dispatch depth has to be at least 1, got {}", depth);
+            return;
+        }
+
+        self.max_dispatch_depth.set(depth as usize);
+    }
+
+    /// Records that an action is being dispatched, unless dispatching it
+    /// would create a cycle with a dispatch that is still in progress or go
+    /// past the max dispatch depth, in which case an error describing the
+    /// chain of dispatches is returned instead. A cycle is the same action,
+    /// with the same type and the same data, being dispatched again while it's
+    /// still being dispatched, since it would keep dispatching itself the same
+    /// way. Actions of the same type with different data, like a countdown
+    /// that dispatches itself with one less, are only limited by the depth.
+    ///
+    /// # Arguments
+    ///
+    /// * `action` - The action that is about to be dispatched.
+    fn enter_dispatch(&self, action: &Variant) -> Result<(), String> {
+        let mut stack = self.dispatch_stack.borrow_mut();
+        let action_type = action_type(action);
+        let key = snapshot::to_stable_text(action);
+
+        let describe_chain = |stack: &[(Variant, String)]| {
+            stack
+                .iter()
+                .map(|(a, _)| a)
+                .chain(std::iter::once(&action_type))
+                .map(|a| a.to_string())
+                .collect::<Vec<_>>()
+                .join(" -> ")
+        };
+
+        if stack.iter().any(|(dispatched_type, dispatched)| {
+            *dispatched_type == action_type && *dispatched == key
+        }) {
+            return Err(format!(
+                "Circular dispatch detected, action {} was dispatched with the same data while it was still being dispatched: {}",
+                action_type.to_string(),
+                describe_chain(stack.as_slice())
+            ));
+        }
+
+        if stack.len() >= self.max_dispatch_depth.get() {
+            return Err(format!(
+                "Max dispatch depth of {} exceeded: {}",
+                self.max_dispatch_depth.get(),
+                describe_chain(stack.as_slice())
+            ));
+        }
+
+        stack.push((action_type, key));
+        Ok(())
+    }
+
+    /// Registers an alias for an action type that was renamed, so actions of
+    /// the old type are renamed to the new type before they are dispatched,
+    /// which keeps old input replays, event logs, saves with pending actions,
+    /// and peers running an older version working. If the data of the action
+    /// changed too, a transform can be given that is passed the renamed action
+    /// and returns it restructured. Aliases are followed one after another, so
+    /// a type can be renamed more than once.
+    ///
+    /// # Arguments
+    ///
+    /// * `old_type` - The old action type.
+    /// * `new_type` - The action type it was renamed to.
+    /// * `transform` - Optional FuncRef that restructures the renamed action.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _ready():
+    ///     store.register_action_alias("heal", "restore_health")
+    ///     store.register_action_alias("damage", "take_damage", funcref(self, "migrate_damage"))
+    ///
+    /// func migrate_damage(action):
+    ///     return { "type": action.type, "amount": action.hp, "source": null }
+    /// ```
+    #[export]
+    fn register_action_alias(
+        &self,
+        _owner: &Object,
+        old_type: Variant,
+        new_type: Variant,
+        #[opt] transform: Variant,
+    ) {
+        match ActionAlias::new(old_type, new_type, &transform) {
+            Ok(alias) => self.action_aliases.borrow_mut().push(alias),
+            Err(message) => godot_error!("Unable to register the action alias: {}", message),
+        }
+    }
+
+    /// Returns an action migrated through the aliases registered with
+    /// `register_action_alias`.
+    ///
+    /// # Arguments
+    ///
+    /// * `action` - The action to migrate.
+    fn migrated(&self, action: Variant) -> Variant {
+        if self.action_aliases.borrow().is_empty() {
+            return action;
+        }
+
+        // The aliases are copied since a transform could register another one.
+        let aliases = self.action_aliases.borrow().clone();
+
+        action_alias::migrate(&aliases, action)
+    }
+
+    /// Returns the path of the first field of an action with text blocked by a
+    /// content filter, or `None` if nothing in it is blocked.
+    ///
+    /// # Arguments
+    ///
+    /// * `action` - The action that was dispatched.
+    fn blocked_field(&self, action: &Variant) -> Option<String> {
+        // The filters are copied since a filter's callback could add another one.
+        let filters = self.content_filters.borrow().clone();
+
+        filters
+            .iter()
+            .find_map(|filter| filter.blocked_field(action))
+    }
+
+    /// Adds a content filter, which checks the text in fields of actions of a
+    /// type before they reach the middleware and the reducer, like chat messages
+    /// or player names. An action with blocked text is dropped and a
+    /// `CONTENT_BLOCKED` action is dispatched instead, with the blocked action
+    /// under `action` and the path of the field under `field`.
+    ///
+    /// The rule can either be an Array of blocked words, which match whole words
+    /// ignoring case, or a FuncRef that is passed the text, the path of the
+    /// field, and the action and returns whether the text is blocked.
+    ///
+    /// # Arguments
+    ///
+    /// * `action_type` - The type of the actions to check.
+    /// * `fields` - The paths of the fields to check, like `player/name`.
+    /// * `rule` - The blocked words or the function that checks the text.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _ready():
+    ///     store.add_content_filter(Action.SEND_MESSAGE, ['message'], ['darn', 'heck'])
+    ///     store.add_content_filter(Action.SET_NAME, ['player/name'], funcref(self, 'is_name_blocked'))
+    ///
+    /// func is_name_blocked(text, field, action):
+    ///     return text.length() > 16
+    /// ```
+    #[export]
+    fn add_content_filter(
+        &self,
+        _owner: &Object,
+        action_type: Variant,
+        fields: VariantArray,
+        rule: Variant,
+    ) {
+        let fields = fields.iter().map(|field| field.to_string()).collect();
+        match ContentFilter::new(action_type, fields, &rule) {
+            Ok(filter) => self.content_filters.borrow_mut().push(filter),
+            Err(message) => godot_error!("Unable to add the content filter: {}", message),
+        }
+    }
+
+    /// Sets the schema that the payload of actions of a type is checked
+    /// against. An action whose payload doesn't match the schema is rejected
+    /// with an error naming the bad field before any middleware runs. Each
+    /// field of the schema is defined by its type, like `int` or `TYPE_INT`,
+    /// or by a Dictionary with its type under `type`, whether it can be left
+    /// out under `optional`, the smallest and largest values of a number
+    /// under `min` and `max`, and the values it can have under `values`.
+    /// Setting the schema of a type again replaces it.
+    ///
+    /// # Arguments
+    ///
+    /// * `action_type` - The type of the actions to check.
+    /// * `schema` - The definition of each field, by name.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _ready():
+    ///     store.set_action_schema(Action.DAMAGE, {
+    ///         "amount": { "type": "int", "min": 0 },
+    ///         "element": { "type": "String", "values": ["fire", "ice"], "optional": true },
+    ///     })
+    /// ```
+    #[export]
+    fn set_action_schema(&self, _owner: &Object, action_type: Variant, schema: Dictionary) {
+        let schema = match Schema::new(&schema) {
+            Ok(schema) => schema,
+            Err(message) => {
+                godot_error!(
+                    "Unable to set the schema of {}: {}",
+                    action_type.to_string(),
+                    message
+                );
+                return;
+            }
+        };
+
+        let mut action_schemas = self.action_schemas.borrow_mut();
+        action_schemas.retain(|(schema_type, _)| *schema_type != action_type);
+        action_schemas.push((action_type, schema));
+    }
+
+    /// Checks the payload of an action against the schema of its type, if it
+    /// has one, returning an error naming the bad field if it doesn't match.
+    ///
+    /// # Arguments
+    ///
+    /// * `action` - The action that was dispatched.
+    fn validate_payload(&self, action: &Variant) -> Result<(), String> {
+        let action_type = action_type(action);
+        let action_schemas = self.action_schemas.borrow();
+        let schema = match action_schemas
+            .iter()
+            .find(|(schema_type, _)| *schema_type == action_type)
+        {
+            Some((_, schema)) => schema,
+            None => return Ok(()),
+        };
+
+        // An action that is just its type has no payload, so it only matches
+        // a schema whose fields are all optional.
+        let payload = action
+            .try_to_dictionary()
+            .unwrap_or_else(Dictionary::new_shared);
+        schema.validate(&payload)
+    }
+
+    /// Sets the schema that the state is checked against, with the keys it
+    /// has to have and the type of each of them, so a save or a reducer that
+    /// leaves out a key or gives it the wrong type is caught right away
+    /// instead of crashing something later. The schema is defined like the
+    /// one passed to `set_action_schema`, and the name of a field can be a
+    /// path to check a nested value, like `player/hp`. A state loaded with
+    /// `load_state` or `load_from_file`, or imported with `import_state`,
+    /// that doesn't match the schema isn't loaded, and an error listing
+    /// every field that is missing or has the wrong type is printed. `fuzz`
+    /// also fails as soon as the state doesn't match the schema. Passing an
+    /// empty schema removes it.
+    ///
+    /// The state returned by the reducer can also be checked after every
+    /// dispatch, which prints the same error but keeps the state. Since this
+    /// checks the whole state every time, it's best turned on in debug builds
+    /// only.
+    ///
+    /// # Arguments
+    ///
+    /// * `schema` - The definition of each field, by name or path.
+    /// * `check_dispatches` - Optional, whether the state is checked after
+    /// every dispatch.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _ready():
+    ///     store.set_state_schema({
+    ///         "player/hp": { "type": "int", "min": 0 },
+    ///         "player/name": "String",
+    ///         "settings": "Dictionary",
+    ///     }, OS.is_debug_build())
+    /// ```
+    #[export]
+    fn set_state_schema(&self, _owner: &Object, schema: Dictionary, #[opt] check_dispatches: bool) {
+        if schema.is_empty() {
+            *self.state_schema.borrow_mut() = None;
+            self.check_dispatched_state.set(false);
+            return;
+        }
+
+        match Schema::new(&schema) {
+            Ok(schema) => {
+                *self.state_schema.borrow_mut() = Some(schema);
+                self.check_dispatched_state.set(check_dispatches);
+            }
+            Err(message) => godot_error!("Unable to set the state schema: {}", message),
+        }
+    }
+
+    /// Checks the state against the schema set with `set_state_schema` and
+    /// returns an error for every field that is missing or doesn't match it,
+    /// which is empty if the state matches the schema or there's no schema.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func test_new_game_matches_the_schema():
+    ///     store.dispatch({ "type": Action.NEW_GAME })
+    ///     assert_eq(store.validate_state().size(), 0)
+    /// ```
+    #[export]
+    fn validate_state(&self, _owner: &Object) -> VariantArray {
+        self.state_schema_errors(&self.readable_state())
+            .iter()
+            .map(Variant::from_str)
+            .collect::<VariantArray<Unique>>()
+            .into_shared()
+    }
+
+    /// Returns an error for every field of a state that is missing or
+    /// doesn't match the state schema, which is empty if there's no schema.
+    ///
+    /// # Arguments
+    ///
+    /// * `state` - The state to check, with its protected values readable.
+    fn state_schema_errors(&self, state: &Dictionary) -> Vec<String> {
+        match self.state_schema.borrow().as_ref() {
+            Some(schema) => schema.errors(state),
+            None => vec![],
+        }
+    }
+
+    /// Runs a single middleware function. If the middleware function returns an
+    /// action then it runs the next middleware function in the middlewares array with
+    /// the action returned by the previous one, including any data that action has.
+    /// The next function is looked up by id each time, so middleware that is
+    /// removed while the action is on its way to the reducer is skipped.
+    ///
+    /// # Arguments
+    ///
+    /// * `after` - The id of the middleware function that ran last, or 0 to
+    /// run the first one.
+    /// * `action` - The action to pass to the middleware function.
+    /// * `context` - The context of the dispatch, which is passed to middleware
+    /// added with `add_context_middleware`.
+    fn dispatch_middleware(&self, after: i64, action: Variant, context: Dictionary) -> Variant {
+        // Middleware with side effects, like sending analytics or playing
+        // sounds, is skipped when the action isn't really happening.
+        let middleware = self
+            .middleware
+            .borrow()
+            .next_to_run(after, self.is_dry_run(), |middleware| {
+                middleware.side_effects
+            })
+            .map(|(id, middleware)| (id, middleware.function.clone(), middleware.convention));
+        let (id, middleware, convention) = match middleware {
+            Some(middleware) => middleware,
+            None => {
+                self.dispatch_reducer(&action);
+                return action;
+            }
+        };
+
+        let mut args = vec![Variant::from_dictionary(&self.readable_state()), action];
+        match convention {
+            Convention::Plain => {}
+            Convention::Context => args.push(Variant::from_dictionary(&context)),
+            Convention::Store => args.push(self.owner.to_variant()),
+            Convention::Next => {
+                let next =
+                    MiddlewareNext::create(self.owner.clone(), id, context, self.dispatch_origin());
+                args.push(Instance::emplace(next).into_shared().to_variant());
+
+                // The middleware decides whether the chain goes on by calling
+                // next, and what it returns is passed back as it is.
+                return middleware.call_func(&args);
+            }
+        }
+        let next = middleware.call_func(&args);
+
+        // A middleware function that yields returns the state of the function
+        // instead of an action, which stops the action like returning `null`.
+        if !next.is_nil() && !is_yielded(&next) {
+            self.dispatch_middleware(id, next, context)
+        } else {
+            Variant::new()
+        }
+    }
+
+    /// Returns whether actions aren't really happening, which is the case
+    /// while running a scenario, previewing an action, replaying the time
+    /// travel history or an event log, and dispatching the input of an input
+    /// replay.
+    fn is_dry_run(&self) -> bool {
+        self.running_scenario.get() || self.replaying_input.get()
+    }
+
+    /// Continues the middleware chain with the middleware after the one with
+    /// an id, which is what the `next` function passed to middleware added
+    /// with `add_next_middleware` calls. If the middleware yielded and calls
+    /// `next` once its dispatch is done, the rest of the chain is run as a
+    /// dispatch of its own with the same correlation id. A `next` that is
+    /// called while another dispatch is in progress, or while dispatching is
+    /// halted, is refused. It has to be exported
+    /// for `MiddlewareNext` to call it, so it's named like a private method
+    /// to keep it out of the way of scripts.
+    ///
+    /// # Arguments
+    ///
+    /// * `after` - The id of the middleware that called `next`.
+    /// * `action` - The action to pass on.
+    /// * `context` - The context of the dispatch.
+    /// * `correlation` - The correlation id of the dispatch `next` was created
+    /// in, or `null` if it didn't have one.
+    /// * `depth` - How many dispatches were nested when `next` was created.
+    #[export]
+    fn _continue_middleware(
+        &self,
+        _owner: &Object,
+        after: i64,
+        action: Variant,
+        context: Dictionary,
+        correlation: Variant,
+        depth: u64,
+    ) -> Variant {
+        if self.dispatch_halted.get() {
+            return Variant::new();
+        }
+
+        let origin = Origin {
+            correlation: correlation.try_to_u64(),
+            depth: depth as usize,
+        };
+        if origin.is_stale(self.dispatch_origin()) {
+            godot_error!(
+                "next was called from another dispatch, so {} wasn't passed on",
+                action.to_string()
+            );
+            return Variant::new();
+        }
+        if !self.dispatch_stack.borrow().is_empty() {
+            return self.dispatch_middleware(after, action, context);
+        }
+
+        if self.owner_was_freed() {
+            return Variant::new();
+        }
+        if let Err(message) = self.enter_dispatch(&action) {
+            godot_error!("{}", message);
+            return Variant::new();
+        }
+
+        let previous = self.correlation.replace(origin.correlation);
+        let result = self.dispatch_middleware(after, action, context);
+        self.correlation.set(previous);
+        self.leave_dispatch();
+
+        result
+    }
+
+    /// Returns the dispatch in progress, for telling whether a `next` function
+    /// continues it.
+    fn dispatch_origin(&self) -> Origin {
+        Origin {
+            correlation: self.correlation.get(),
+            depth: self.dispatch_stack.borrow().len(),
+        }
+    }
+
+    /// Runs the reducer for the specified action and then call any attached subscriptions.
+    ///
+    /// # Arguments
+    ///
+    /// * `action` - The action to run the reducer for.
+    fn dispatch_reducer(&self, action: &Variant) {
+        // Patch actions are applied by the store instead of the reducer, and
+        // a patch that can't be applied is dropped without changing anything.
+        let patched = patch::apply(&self.readable_state(), action);
+        let new_state = match patched {
+            Ok(Some(patched)) => patched,
+            Ok(None) => {
+                // The slice reducers are copied since one of them could
+                // combine or inject other reducers.
+                let slice_reducers = self.slice_reducers.borrow().clone();
+                let injected_reducers = self.injected_reducers.borrow().clone();
+                let profiling = self
+                    .telemetry
+                    .borrow()
+                    .as_ref()
+                    .is_some_and(|telemetry| telemetry.is_profiling());
+                let mut timings = vec![];
+
+                let reduced = if slice_reducers.is_empty() {
+                    let args = &[
+                        Variant::from_dictionary(&self.readable_state()),
+                        action.clone(),
+                    ];
+                    let reducer = self.reducer.borrow().clone();
+                    let reduced = reducer.call_func(args);
+                    match reduced.try_to_dictionary() {
+                        Some(reduced) => reduced,
+                        None => {
+                            let message = format!(
+                                "The reducer returned {} instead of a Dictionary",
+                                reduced.to_string()
+                            );
+                            godot_error!("{}", message);
+                            if self.pipeline_failed("reducer", action, &message) {
+                                return;
+                            }
+                            reduced.to_dictionary()
+                        }
+                    }
+                } else {
+                    reduce_slices(
+                        &self.readable_state(),
+                        action,
+                        &slice_reducers,
+                        profiling.then_some(&mut timings),
+                    )
+                };
+                let reduced = if injected_reducers.is_empty() {
+                    reduced
+                } else {
+                    reduce_slices(
+                        &reduced,
+                        action,
+                        &injected_reducers,
+                        profiling.then_some(&mut timings),
+                    )
+                };
+
+                if let Some(telemetry) = self.telemetry.borrow_mut().as_mut() {
+                    for (slice, usec) in timings {
+                        telemetry.time_slice(&slice, usec);
+                    }
+                }
+                reduced
+            }
+            Err(message) => {
+                godot_error!(
+                    "Unable to apply the patch {}: {}",
+                    action.to_string(),
+                    message
+                );
+                self.pipeline_failed("patch", action, &message);
+                return;
+            }
+        };
+
+        if !self.running_scenario.get() {
+            if let Some(log) = self.event_log.borrow_mut().as_mut() {
+                log.record(action);
+            }
+        }
+
+        let new_state = self.keep_readonly_slices(action, new_state);
+        let new_state = self.reduce_built_in_slices(action, new_state);
+
+        if self.check_dispatched_state.get() {
+            let errors = self.state_schema_errors(&new_state);
+            if !errors.is_empty() {
+                godot_error!(
+                    "The state after {} doesn't match the schema:\n{}",
+                    action.to_string(),
+                    errors.join("\n")
+                );
+                if self.pipeline_failed("schema", action, &errors.join("\n")) {
+                    return;
+                }
+            }
+        }
+
+        if let Some(audit) = self.access_audit.borrow_mut().as_mut() {
+            audit.record_writes(
+                &action_type(action),
+                diff::changed_paths(&self.readable_state(), &new_state),
+            );
+        }
+
+        let old_state = self.readable_state();
+        let sealed = self.sealed(&new_state);
+        let previous = self.state.replace(sealed);
+
+        if !self.running_scenario.get() {
+            *self.previous_state.borrow_mut() = previous;
+            self.mark_dirty_slices(&old_state, &new_state);
+
+            if let Some(telemetry) = self.telemetry.borrow_mut().as_mut() {
+                if telemetry.is_profiling() {
+                    telemetry.count_changes(diff::changed_keys(&old_state, &new_state));
+                }
+            }
+
+            if let Some(history) = self.history.borrow_mut().as_mut() {
+                history.record(action, &old_state, &new_state);
+            }
+            if let Some(undo_stack) = self.undo_stack.borrow_mut().as_mut() {
+                if undo_stack.keeps(&action_type(action)) {
+                    undo_stack.record(action, &old_state, &new_state);
+                }
+            }
+        }
+
+        self.play_bound_audio(action, &old_state, &new_state);
+        self.sync_bound_fields();
+        if !self.running_scenario.get() {
+            self.send_to_devtools(action);
+            self.record_recent_action(action);
+            self.record_session_action(action);
+        }
+        if self.state_changed_is_connected()
+            && !diff::changed_keys(&old_state, &new_state).is_empty()
+        {
+            self.emit_after_update(
+                "state_changed",
+                vec![Variant::from_dictionary(&new_state), action.clone()],
+            );
+        }
+        self.emit_after_update("action_dispatched", vec![action.clone()]);
+        self.queue_action_signals(action);
+        self.emit_pending_signals();
+        self.run_pending_effects();
+        self.queue_bound_effects(action);
+        self.queue_pipelines(action);
+        self.dispatch_subscriptions();
+    }
+
+    /// Updates the slices of the state that the store manages itself, like the
+    /// router slice, once the reducer has returned the new state. The reducer
+    /// doesn't have to keep these slices around since they are put back here.
+    ///
+    /// # Arguments
+    ///
+    /// * `action` - The action that was dispatched.
+    /// * `new_state` - The state returned by the reducer.
+    fn reduce_built_in_slices(&self, action: &Variant, new_state: Dictionary) -> Dictionary {
+        let new_state = self.reduce_router(action, new_state);
+        let new_state = self.reduce_forms(action, new_state);
+        let new_state = self.reduce_queries(action, new_state);
+        let new_state = self.reduce_stats(action, new_state);
+        let new_state = self.reduce_inventory(action, new_state);
+        let new_state = self.reduce_quests(action, new_state);
+        let new_state = self.reduce_dialogue(action, new_state);
+        let new_state = self.reduce_fsm(action, new_state);
+        let new_state = self.reduce_cooldowns(action, new_state);
+        let new_state = self.reduce_feature_flags(action, new_state);
+        let new_state = self.reduce_input_recording(action, new_state);
+
+        let new_state = self.reduce_preload(action, new_state);
+
+        let new_state = self.reduce_cheats(action, new_state);
+
+        self.reduce_random(action, new_state)
+    }
+
+    /// Updates the `router` slice and changes scenes for navigation actions if
+    /// scene routing is enabled.
+    ///
+    /// # Arguments
+    ///
+    /// * `action` - The action that was dispatched.
+    /// * `new_state` - The state returned by the reducer.
+    fn reduce_router(&self, action: &Variant, new_state: Dictionary) -> Dictionary {
+        if self.router.borrow().is_none() {
+            return new_state;
+        }
+
+        let current = slice(&self.readable_state(), router::SLICE);
+        let next = match router::reduce(&current, action) {
+            Ok(Some(next)) => next,
+            Ok(None) if new_state.contains(router::SLICE) => return new_state,
+            Ok(None) => return with_slice(&new_state, router::SLICE, current),
+            Err(message) => {
+                godot_error!("{}", message);
+                return with_slice(&new_state, router::SLICE, current);
+            }
+        };
+
+        // Scenes aren't changed while running a scenario so that navigation
+        // can be tested without leaving the scene running the tests.
+        if !self.running_scenario.get() {
+            let scene = router::scene(&next);
+            let changed = self
+                .router
+                .borrow()
+                .as_ref()
+                .map(|r| r.change_scene(&scene));
+            if let Some(Err(message)) = changed {
+                godot_error!("{}", message);
+                return with_slice(&new_state, router::SLICE, current);
+            }
+        }
+
+        with_slice(&new_state, router::SLICE, next)
+    }
+
+    /// Enables scene routing, which adds a `router` slice to the state with the
+    /// path of the current scene and the scenes that were navigated away from.
+    /// Once enabled, dispatching a `navigate` action with the path of a scene
+    /// under `scene` changes to that scene and dispatching a `navigate_back`
+    /// action goes back to the previous one. Both actions are also passed to
+    /// the reducer like any other action.
+    ///
+    /// # Arguments
+    ///
+    /// * `tree` - The SceneTree whose scene should be changed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _ready():
+    ///     store.set_state_and_reducer(state, self, 'reducer')
+    ///     store.enable_scene_routing(get_tree())
+    ///
+    /// func _on_next_level_pressed():
+    ///     store.dispatch({ "type": "navigate", "scene": "res://levels/2.tscn" })
+    ///
+    /// func _on_back_pressed():
+    ///     store.dispatch({ "type": "navigate_back" })
+    /// ```
+    #[export]
+    fn enable_scene_routing(&self, _owner: &Object, tree: Ref<SceneTree, Shared>) {
+        let router = Router::new(tree);
+
+        let state = with_slice(
+            &self.readable_state(),
+            router::SLICE,
+            router.initial_slice(),
+        );
+        *self.state.borrow_mut() = self.sealed(&state);
+        *self.router.borrow_mut() = Some(router);
+    }
+
+    /// Updates the `forms` slice for form actions.
+    ///
+    /// # Arguments
+    ///
+    /// * `action` - The action that was dispatched.
+    /// * `new_state` - The state returned by the reducer.
+    fn reduce_forms(&self, action: &Variant, new_state: Dictionary) -> Dictionary {
+        if self.forms.borrow().is_empty() {
+            return new_state;
+        }
+
+        let current = slice(&self.readable_state(), form::SLICE);
+
+        // The form is copied since its validators are run while reducing and
+        // they might call back into the store.
+        let target = form::target(action)
+            .and_then(|name| Some((self.forms.borrow().get(&name)?.clone(), name)));
+        let next = target.and_then(|(form, name)| {
+            let next = form.reduce(&slice(&current, &name), action)?;
+            Some(with_slice(&current, &name, next))
+        });
+
+        keep_slice(new_state, form::SLICE, next, current)
+    }
+
+    /// Creates a form, which adds a slice for it under `forms` in the state
+    /// that has the value of each field, whether each field is dirty or has been
+    /// touched, the reason each invalid field is invalid, and whether the form
+    /// is valid and was submitted. The form is updated by dispatching
+    /// `set_field`, `touch`, `submit`, and `reset` actions with its name under
+    /// `form`.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the form.
+    /// * `fields` - The initial value of each field, or a Dictionary with the
+    /// initial value under `value`, whether the field has to be filled in under
+    /// `required`, and a FuncRef that returns an error for an invalid value
+    /// under `validator`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _ready():
+    ///     store.create_form("login", {
+    ///         "username": { "value": "", "required": true },
+    ///         "password": { "value": "", "validator": funcref(self, "validate_password") },
+    ///         "remember_me": false,
+    ///     })
+    ///
+    /// func validate_password(value, values):
+    ///     return "" if value.length() >= 8 else "The password needs at least 8 characters"
+    /// ```
+    #[export]
+    fn create_form(&self, _owner: &Object, name: GodotString, fields: Dictionary) {
+        let name = name.to_string();
+        let form = Form::new(&fields);
+        let initial_slice = form.initial_slice();
+
+        self.forms.borrow_mut().insert(name.clone(), form);
+
+        let state = self.readable_state();
+        let forms = with_slice(&slice(&state, form::SLICE), &name, initial_slice);
+        let state = with_slice(&state, form::SLICE, forms);
+        *self.state.borrow_mut() = self.sealed(&state);
+    }
+
+    /// Binds every LineEdit, CheckBox, and other button inside of a container
+    /// that is named after a field of a form to that field. Editing a control
+    /// dispatches `set_field`, leaving it dispatches `touch`, and the controls
+    /// are updated whenever the values of the form change, for example when the
+    /// form is reset.
+    ///
+    /// # Arguments
+    ///
+    /// * `container` - The node that contains the controls.
+    /// * `form_name` - Optional name of the form, which defaults to the name of the container.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _ready():
+    ///     store.create_form("login", { "username": "", "password": "", "remember_me": false })
+    ///     store.bind_form($Login)
+    /// ```
+    #[export]
+    fn bind_form(
+        &self,
+        owner: TRef<Object>,
+        container: Ref<Node, Shared>,
+        #[opt] form_name: GodotString,
+    ) {
+        let container = unsafe { container.assume_safe() };
+        let form_name = if form_name.is_empty() {
+            container.name().to_string()
+        } else {
+            form_name.to_string()
+        };
+
+        let form = match self.forms.borrow().get(&form_name) {
+            Some(form) => form.clone(),
+            None => {
+                godot_error!("There is no form named {} to bind", form_name);
+                return;
+            }
+        };
+
+        let bound = form::bind(owner, container, &form_name, &form);
+        self.bound_fields.borrow_mut().extend(bound);
+        self.sync_bound_fields();
+    }
+
+    /// Dispatches `set_field` when a control bound with `bind_form` is edited.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The new value of the control.
+    /// * `form` - The name of the form.
+    /// * `field` - The name of the field.
+    #[export]
+    fn on_form_field_changed(
+        &self,
+        _owner: &Object,
+        value: Variant,
+        form: GodotString,
+        field: GodotString,
+    ) {
+        if self.syncing_bound_fields.get() {
+            return;
+        }
+
+        let action = Dictionary::new();
+        action.insert("type", "set_field");
+        action.insert("form", form);
+        action.insert("field", field);
+        action.insert("value", value);
+
+        self.dispatch_action(action.owned_to_variant());
+    }
+
+    /// Dispatches `touch` when a control bound with `bind_form` loses focus.
+    ///
+    /// # Arguments
+    ///
+    /// * `form` - The name of the form.
+    /// * `field` - The name of the field.
+    #[export]
+    fn on_form_field_touched(&self, _owner: &Object, form: GodotString, field: GodotString) {
+        let action = Dictionary::new();
+        action.insert("type", "touch");
+        action.insert("form", form);
+        action.insert("field", field);
+
+        self.dispatch_action(action.owned_to_variant());
+    }
+
+    /// Updates the controls bound with `bind_form` to show the values in the
+    /// state.
+    fn sync_bound_fields(&self) {
+        if self.running_scenario.get() || self.bound_fields.borrow().is_empty() {
+            return;
+        }
+
+        let forms = slice(&self.readable_state(), form::SLICE);
+        let bound_fields = self.bound_fields.borrow().clone();
+
+        // Updating a button emits its signal, which would otherwise dispatch
+        // `set_field` again.
+        self.syncing_bound_fields.set(true);
+        for bound_field in &bound_fields {
+            bound_field.sync(&forms);
+        }
+        self.syncing_bound_fields.set(false);
+    }
+
+    /// Binds sounds and audio buses to the store so they follow the actions and
+    /// the state. The map can have any of these keys:
+    ///
+    /// * `actions` - A Dictionary of action types to the audio players that are
+    ///   played when an action of the type is dispatched.
+    /// * `changes` - A Dictionary of paths to the audio players that are played
+    ///   when the value at the path changes, or to a Dictionary of values to the
+    ///   audio players that are played when it changes to each of those values.
+    /// * `buses` - A Dictionary of the names of audio buses to the paths of
+    ///   values between 0 and 1 that their volume follows.
+    ///
+    /// Binding audio again replaces what was bound before.
+    ///
+    /// # Arguments
+    ///
+    /// * `map` - The map of actions, changes, and buses.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _ready():
+    ///     store.bind_audio({
+    ///         "actions": { Action.JUMP: $JumpSound },
+    ///         "changes": { "player/state": { "dead": $DeathSound } },
+    ///         "buses": { "Music": "settings/music_volume" },
+    ///     })
+    /// ```
+    #[export]
+    fn bind_audio(&self, _owner: &Object, map: Dictionary) {
+        let audio = match AudioBinding::new(&map) {
+            Ok(audio) => audio,
+            Err(message) => {
+                godot_error!("Unable to bind the audio: {}", message);
+                return;
+            }
+        };
+
+        audio.sync_buses(&self.readable_state());
+        *self.audio.borrow_mut() = Some(audio);
+    }
+
+    /// Plays the sounds bound with `bind_audio` for an action and updates the
+    /// volume of the bound buses.
+    ///
+    /// # Arguments
+    ///
+    /// * `action` - The action that was dispatched.
+    /// * `old_state` - The state before the action.
+    /// * `new_state` - The state after the action.
+    fn play_bound_audio(&self, action: &Variant, old_state: &Dictionary, new_state: &Dictionary) {
+        if self.running_scenario.get() {
+            return;
+        }
+
+        if let Some(audio) = self.audio.borrow().as_ref() {
+            audio.apply(action, old_state, new_state);
+        }
+    }
+
+    /// Binds a method of a node to an action type, so that the method is called
+    /// with the action whenever one of the type is dispatched. The calls are
+    /// batched until the outermost dispatch is done, so actions dispatched by
+    /// middleware or subscriptions are all handled first, which makes it easy
+    /// to hook particles and other visual effects to gameplay actions from a
+    /// single table of bindings.
+    ///
+    /// # Arguments
+    ///
+    /// * `action_type` - The type of the actions to call the method for.
+    /// * `node` - The node with the method.
+    /// * `method` - The name of the method, which is passed the action.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _ready():
+    ///     store.bind_effect(Action.ENEMY_KILLED, $Explosion, 'play_at')
+    ///     store.bind_effect(Action.ENEMY_KILLED, $Camera, 'shake')
+    ///     store.bind_effect(Action.HEAL, $HealParticles, 'play_at')
+    /// ```
+    #[export]
+    fn bind_effect(
+        &self,
+        _owner: &Object,
+        action_type: Variant,
+        node: Ref<Node, Shared>,
+        method: GodotString,
+    ) {
+        self.effect_bindings
+            .borrow_mut()
+            .push(EffectBinding::new(action_type, node, method));
+    }
+
+    /// Queues a call to each method bound with `bind_effect` to the type of an
+    /// action. Nothing is queued while running a scenario.
+    ///
+    /// # Arguments
+    ///
+    /// * `action` - The action that was dispatched.
+    fn queue_bound_effects(&self, action: &Variant) {
+        if self.running_scenario.get() {
+            return;
+        }
+
+        let matching = self
+            .effect_bindings
+            .borrow()
+            .iter()
+            .filter(|binding| binding.matches(action))
+            .map(|binding| (binding.clone(), action.clone()))
+            .collect::<Vec<_>>();
+        self.pending_bound_effects.borrow_mut().extend(matching);
+    }
+
+    /// Calls the methods queued by `queue_bound_effects`.
+    fn call_bound_effects(&self) {
+        let pending_bound_effects = self.pending_bound_effects.replace(vec![]);
+
+        for (binding, action) in pending_bound_effects {
+            if self.owner_was_freed() {
+                return;
+            }
+            binding.call(&action);
+        }
+    }
+
+    /// Adds a pipeline of effects that are run one after the other whenever
+    /// an action of a type passes the reducer, as a lighter alternative to
+    /// sagas for follow-up work like saving, playing a cutscene, and then
+    /// unlocking the next level. Each effect is a FuncRef that is passed the
+    /// state and the action, and is only run once the one before it is done,
+    /// so an effect that yields, for example on a timer or a request, holds
+    /// up the rest of the pipeline until it returns. An effect that can't be
+    /// called, for example because its instance was freed, prints an error
+    /// and is skipped, and the effects after it still run. The pipelines start
+    /// once the outermost dispatch is done, in the order they were added, and
+    /// don't run while running a scenario. Returns the id of the pipeline,
+    /// which can be passed to `remove_on_action`, or -1 if one of the effects
+    /// isn't a FuncRef.
+    ///
+    /// # Arguments
+    ///
+    /// * `action_type` - The type of the actions to run the effects for.
+    /// * `effects` - The FuncRefs of the effects, in the order they're run.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _ready():
+    ///     store.on_action(Action.LEVEL_COMPLETED, [
+    ///         funcref(self, "save_progress"),
+    ///         funcref(self, "play_outro"),
+    ///         funcref(self, "unlock_next_level"),
+    ///     ])
+    ///
+    /// func play_outro(state, action):
+    ///     $Outro.play()
+    ///     yield($Outro, "animation_finished")
+    /// ```
+    #[export]
+    fn on_action(&self, _owner: &Object, action_type: Variant, effects: VariantArray) -> i64 {
+        match self.pipelines.borrow_mut().add(action_type, &effects) {
+            Ok(id) => id,
+            Err(message) => {
+                godot_error!("Unable to add the effects: {}", message);
+                -1
+            }
+        }
+    }
+
+    /// Removes a pipeline of effects added with `on_action`, and returns
+    /// whether there was one with the id. Pipelines that already started
+    /// running for an action are finished.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The id of the pipeline.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _exit_tree():
+    ///     store.remove_on_action(outro_pipeline)
+    /// ```
+    #[export]
+    fn remove_on_action(&self, _owner: &Object, id: i64) -> bool {
+        self.pipelines.borrow_mut().remove(id)
+    }
+
+    /// Starts a run of each pipeline added with `on_action` for the type of an
+    /// action, to be run once the outermost dispatch is done. Nothing is
+    /// started while running a scenario.
+    ///
+    /// # Arguments
+    ///
+    /// * `action` - The action that was dispatched.
+    fn queue_pipelines(&self, action: &Variant) {
+        if self.running_scenario.get() {
+            return;
+        }
+
+        let runs = self.pipelines.borrow_mut().start(action);
+        self.pending_pipeline_runs.borrow_mut().extend(runs);
+    }
+
+    /// Runs the pipelines queued by `queue_pipelines`.
+    fn run_pending_pipelines(&self) {
+        let pending_pipeline_runs = self.pending_pipeline_runs.replace(vec![]);
+
+        for run in pending_pipeline_runs {
+            self.run_pipeline(run);
+        }
+    }
+
+    /// Runs the effects of a pipeline that are left, one after the other,
+    /// until one of them yields, in which case the rest are run once it
+    /// returns.
+    ///
+    /// # Arguments
+    ///
+    /// * `run` - The id of the run.
+    fn run_pipeline(&self, run: i64) {
+        loop {
+            if self.owner_was_freed() {
+                return;
+            }
+
+            let next = self.pipelines.borrow_mut().next_effect(run);
+            let (effect, index, action) = match next {
+                Some(next) => next,
+                None => return,
+            };
+
+            let effect = unsafe { effect.assume_safe() };
+            if !effect.is_valid() {
+                let message = format!(
+                    "Unable to run the effect at {} for {}: The instance doesn't have the function",
+                    index,
+                    action.to_string()
+                );
+                godot_error!("{}", message);
+                self.pipeline_failed("effect", &action, &message);
+                continue;
+            }
+
+            let result =
+                effect.call_func(&[Variant::from_dictionary(&self.readable_state()), action]);
+            // An effect that yields returns the state of the function, which
+            // emits `completed` once the function returns.
+            let function_state = match result.try_to_object::<Object>() {
+                Some(function_state) if is_yielded(&result) => function_state,
+                _ => continue,
+            };
+
+            let binds = VariantArray::new();
+            binds.push(run);
+            let connected = unsafe { function_state.assume_safe() }.connect(
+                "completed",
+                unsafe { self.owner.assume_safe() },
+                "on_pipeline_effect_completed",
+                binds.into_shared(),
+                0,
+            );
+            match connected {
+                Ok(()) => return,
+                Err(error) => godot_error!("Couldn't wait on the effect at {}: {:?}", index, error),
+            }
+        }
+    }
+
+    /// Called when an effect of a pipeline that yielded returns, which runs
+    /// the rest of the pipeline.
+    ///
+    /// # Arguments
+    ///
+    /// * `result` - What the effect returned, which isn't used.
+    /// * `run` - The id of the run.
+    #[export]
+    fn on_pipeline_effect_completed(&self, _owner: &Object, _result: Variant, run: i64) {
+        self.run_pipeline(run);
+    }
+
+    /// Updates the `queries` slice for query actions.
+    ///
+    /// # Arguments
+    ///
+    /// * `action` - The action that was dispatched.
+    /// * `new_state` - The state returned by the reducer.
+    fn reduce_queries(&self, action: &Variant, new_state: Dictionary) -> Dictionary {
+        if self.queries.borrow().is_empty() {
+            return new_state;
+        }
+
+        let current = slice(&self.readable_state(), query::SLICE);
+        let next = query::reduce(&current, action);
+
+        keep_slice(new_state, query::SLICE, next, current)
+    }
+
+    /// Registers a query, which caches the data returned by a fetch function in
+    /// the `queries` slice of the state along with whether it's loading and the
+    /// error it failed with. The data is fetched with `fetch_query`.
+    ///
+    /// The fetch function is passed the arguments given to `fetch_query` and
+    /// can either return the data right away, return the state of a function
+    /// that yields and returns the data once it's done, or return `null` and
+    /// call `resolve_query` or `reject_query` later.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the query.
+    /// * `fetch_fn_instance` - The instance on which the fetch function exists.
+    /// * `fetch_fn_name` - The name of the fetch function.
+    /// * `ttl` - How long fetched data is used for before it's fetched again, in seconds.
+    /// * `tags` - Optional tags that invalidate the query when passed to `invalidate_tags`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _ready():
+    ///     store.register_query("profile", self, "fetch_profile", 60.0, ["player"])
+    ///
+    /// func fetch_profile(args):
+    ///     $HTTPRequest.request("https://example.com/profile/%s" % args.id)
+    ///     var response = yield($HTTPRequest, "request_completed")
+    ///     return parse_json(response[3].get_string_from_utf8())
+    /// ```
+    #[export]
+    fn register_query(
+        &self,
+        _owner: &Object,
+        name: GodotString,
+        fetch_fn_instance: Ref<Object, Shared>,
+        fetch_fn_name: GodotString,
+        ttl: f64,
+        #[opt] tags: VariantArray,
+    ) {
+        let name = name.to_string();
+        let query = Query {
+            fetch: func_ref(fetch_fn_instance, fetch_fn_name),
+            ttl,
+            tags: tags.iter().map(|tag| tag.to_string()).collect(),
+            last_args: Variant::new(),
+            request: 0,
+        };
+        self.queries.borrow_mut().insert(name.clone(), query);
+
+        let state = self.readable_state();
+        let queries = slice(&state, query::SLICE);
+        if query::entry(&queries, &name).is_none() {
+            let queries = with_slice(&queries, &name, query::empty_entry());
+            let state = with_slice(&state, query::SLICE, queries);
+            *self.state.borrow_mut() = self.sealed(&state);
+        }
+    }
+
+    /// Fetches the data of a query unless the cached data is still fresh or
+    /// the query is already fetching, and returns the cached data. The state
+    /// of the query, including the fetched data once it arrives, is kept under
+    /// `queries` in the state.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the query.
+    /// * `args` - Optional arguments to pass to the fetch function.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _on_profile_opened():
+    ///     store.fetch_query("profile", { "id": player_id })
+    ///
+    /// func _on_state_changed(state):
+    ///     $Spinner.visible = state.queries.profile.loading
+    /// ```
+    #[export]
+    fn fetch_query(&self, owner: TRef<Object>, name: GodotString, #[opt] args: Variant) -> Variant {
+        let name = name.to_string();
+        let query = match self.queries.borrow().get(&name) {
+            Some(query) => query.clone(),
+            None => {
+                godot_error!("There is no query named {} to fetch", name);
+                return Variant::new();
+            }
+        };
+
+        let entry = query::entry(&slice(&self.readable_state(), query::SLICE), &name)
+            .unwrap_or_else(|| query::empty_entry().into_shared());
+        if query::is_loading(&entry) || query::is_fresh(&entry, query.ttl, self.now()) {
+            return query::data(&entry);
+        }
+
+        self.start_fetch(owner, &name, args);
+
+        query::data(&entry)
+    }
+
+    /// Dispatches `query_started` and runs the fetch function of a query.
+    ///
+    /// # Arguments
+    ///
+    /// * `owner` - The store, which the completion of a yielding fetch function is connected to.
+    /// * `name` - The name of the query.
+    /// * `args` - The arguments to pass to the fetch function.
+    fn start_fetch(&self, owner: TRef<Object>, name: &str, args: Variant) {
+        let (fetch, request) = match self.queries.borrow_mut().get_mut(name) {
+            Some(query) => {
+                query.request += 1;
+                query.last_args = args.clone();
+                (query.fetch.clone(), query.request)
+            }
+            None => return,
+        };
+
+        self.dispatch_action(query::started(name).owned_to_variant());
+
+        let result = fetch.call_func(&[args]);
+        if result.is_nil() {
+            return;
+        }
+
+        // A fetch function that yields returns the state of the function, which
+        // emits `completed` with the data once the function returns.
+        match result.try_to_object::<Object>() {
+            Some(state) if unsafe { state.assume_safe() }.has_signal("completed") => {
+                let binds = VariantArray::new();
+                binds.push(name);
+                binds.push(request);
+
+                let connected = unsafe { state.assume_safe() }.connect(
+                    "completed",
+                    owner,
+                    "on_query_completed",
+                    binds.into_shared(),
+                    0,
+                );
+                if let Err(error) = connected {
+                    godot_error!("Couldn't wait on the query {}: {:?}", name, error);
+                }
+            }
+            _ => self.finish_fetch(name, request, Ok(result)),
+        }
+    }
+
+    /// Dispatches `query_succeeded` or `query_failed` for a fetch, unless a
+    /// newer fetch of the query was started since or the query isn't fetching
+    /// anymore.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the query.
+    /// * `request` - The number of the fetch.
+    /// * `result` - The fetched data or the error the fetch failed with.
+    fn finish_fetch(&self, name: &str, request: u64, result: Result<Variant, Variant>) {
+        let is_latest = self
+            .queries
+            .borrow()
+            .get(name)
+            .is_some_and(|query| query.request == request);
+        let is_loading = query::entry(&slice(&self.readable_state(), query::SLICE), name)
+            .is_some_and(|entry| query::is_loading(&entry));
+
+        if !is_latest || !is_loading {
+            return;
+        }
+
+        let action = match result {
+            Ok(data) => query::succeeded(name, data, self.now()),
+            Err(error) => query::failed(name, error),
+        };
+        self.dispatch_action(action.owned_to_variant());
+    }
+
+    /// Called when a fetch function that yielded returns.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - The data returned by the fetch function.
+    /// * `name` - The name of the query.
+    /// * `request` - The number of the fetch.
+    #[export]
+    fn on_query_completed(&self, _owner: &Object, data: Variant, name: GodotString, request: i64) {
+        self.finish_fetch(&name.to_string(), request as u64, Ok(data));
+    }
+
+    /// Sets the data of a query that is fetching, for fetch functions that
+    /// return `null` and finish fetching later, for example from a signal.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the query.
+    /// * `data` - The fetched data.
+    #[export]
+    fn resolve_query(&self, _owner: &Object, name: GodotString, data: Variant) {
+        let name = name.to_string();
+        let request = self.queries.borrow().get(&name).map(|query| query.request);
+
+        if let Some(request) = request {
+            self.finish_fetch(&name, request, Ok(data));
+        }
+    }
+
+    /// Sets the error of a query that is fetching, for fetch functions that
+    /// return `null` and finish fetching later, for example from a signal.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the query.
+    /// * `error` - The error the fetch failed with.
+    #[export]
+    fn reject_query(&self, _owner: &Object, name: GodotString, error: Variant) {
+        let name = name.to_string();
+        let request = self.queries.borrow().get(&name).map(|query| query.request);
+
+        if let Some(request) = request {
+            self.finish_fetch(&name, request, Err(error));
+        }
+    }
+
+    /// Marks every query with any of the tags as stale and fetches the ones
+    /// that were fetched before again, for example after an action changed the
+    /// data on the server.
+    ///
+    /// # Arguments
+    ///
+    /// * `tags` - The tags of the queries to invalidate.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _on_profile_saved():
+    ///     store.invalidate_tags(["player"])
+    /// ```
+    #[export]
+    fn invalidate_tags(&self, owner: TRef<Object>, tags: VariantArray) {
+        let tags = tags.iter().map(|tag| tag.to_string()).collect::<Vec<_>>();
+        let invalidated = self
+            .queries
+            .borrow()
+            .iter()
+            .filter(|(_, query)| query.has_any_tag(&tags))
+            .map(|(name, query)| (name.clone(), query.last_args.clone()))
+            .collect::<Vec<_>>();
+
+        if invalidated.is_empty() {
+            return;
+        }
+
+        let names = invalidated
+            .iter()
+            .map(|(name, _)| name.clone())
+            .collect::<Vec<_>>();
+        self.dispatch_action(query::invalidated(&names).owned_to_variant());
+
+        for (name, args) in invalidated {
+            let queries = slice(&self.readable_state(), query::SLICE);
+            let was_fetched =
+                query::entry(&queries, &name).is_some_and(|entry| query::was_fetched(&entry));
+
+            if was_fetched {
+                self.start_fetch(owner, &name, args);
+            }
+        }
+    }
+
+    /// Updates the `stats` slice for actions that match a stats rule, queues
+    /// `achievement_unlocked` for the achievements that were unlocked, and
+    /// saves the statistics if they are saved.
+    ///
+    /// # Arguments
+    ///
+    /// * `action` - The action that was dispatched.
+    /// * `new_state` - The state returned by the reducer.
+    fn reduce_stats(&self, action: &Variant, new_state: Dictionary) -> Dictionary {
+        let stats = match self.stats.borrow().as_ref() {
+            Some(stats) => stats.clone(),
+            None => return new_state,
+        };
+
+        let current = slice(&self.readable_state(), stats::SLICE);
+        let next = stats.reduce(&current, action).map(|(next, unlocked)| {
+            for name in unlocked {
+                self.emit_after_update("achievement_unlocked", vec![Variant::from_str(name)]);
+            }
+
+            // Statistics that change while running a scenario aren't saved.
+            if !self.running_scenario.get() {
+                self.stats_changed.set(true);
+            }
+
+            next.into_shared()
+        });
+
+        keep_slice(new_state, stats::SLICE, next, current)
+    }
+
+    /// Saves the statistics to the file passed to `enable_stats` if they
+    /// changed during the dispatch that just finished, making sure the file
+    /// is kept like a save of the state.
+    fn save_stats(&self) {
+        if !self.stats_changed.replace(false) {
+            return;
+        }
+
+        let save_path = match self.stats.borrow().as_ref() {
+            Some(stats) => stats.save_path.clone(),
+            None => return,
+        };
+        if let Some(save_path) = save_path {
+            let stats = slice(&self.readable_state(), stats::SLICE);
+            let text = snapshot::to_stable_text(&Variant::from_dictionary(&stats));
+            match file::write_text(&save_path, &text) {
+                Ok(()) => self.storage.borrow_mut().flush(),
+                Err(message) => godot_error!("Unable to save the stats: {}", message),
+            }
+        }
+    }
+
+    /// Enables statistics, which adds a `stats` slice to the state with the
+    /// value of every statistic under `values` and the names of the unlocked
+    /// achievements under `unlocked`. Statistics are changed by rules whenever
+    /// an action of their type is dispatched and the `achievement_unlocked`
+    /// signal is emitted once a statistic reaches the threshold of an
+    /// achievement.
+    ///
+    /// # Arguments
+    ///
+    /// * `rules` - The rules that change the statistics.
+    /// * `achievements` - The achievements that can be unlocked.
+    /// * `save_path` - Optional file to save the statistics to after they
+    /// change and to load them from when they are enabled.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _ready():
+    ///     store.enable_stats(
+    ///         [{ "action": Action.ENEMY_KILLED, "stat": "kills", "op": "increment" }],
+    ///         [{ "name": "slayer", "stat": "kills", "threshold": 100 }],
+    ///         "user://stats.json"
+    ///     )
+    ///     store.connect("achievement_unlocked", self, "_on_achievement_unlocked")
+    /// ```
+    #[export]
+    fn enable_stats(
+        &self,
+        _owner: &Object,
+        rules: VariantArray,
+        achievements: VariantArray,
+        #[opt] save_path: GodotString,
+    ) {
+        let save_path = Some(save_path.to_string()).filter(|path| !path.is_empty());
+        let stats = match Stats::new(&rules, &achievements, save_path.clone()) {
+            Ok(stats) => stats,
+            Err(message) => {
+                godot_error!("{}", message);
+                return;
+            }
+        };
+
+        let state = self.readable_state();
+        let saved = save_path
+            .filter(|path| file::exists(path))
+            .and_then(
+                |path| match file::read_json(&path).map(|saved| saved.try_to_dictionary()) {
+                    Ok(Some(saved)) => Some(saved),
+                    Ok(None) => {
+                        godot_error!("The saved stats in {} aren't a Dictionary", path);
+                        None
+                    }
+                    Err(message) => {
+                        godot_error!("{}", message);
+                        None
+                    }
+                },
+            );
+        let initial_slice = match saved {
+            Some(saved) => saved,
+            None if state.contains(stats::SLICE) => slice(&state, stats::SLICE),
+            None => stats.initial_slice().into_shared(),
+        };
+
+        let state = with_slice(&state, stats::SLICE, initial_slice);
+        *self.state.borrow_mut() = self.sealed(&state);
+        *self.stats.borrow_mut() = Some(stats);
+    }
+
+    /// Updates the `inventory` slice for inventory actions. Actions that can't
+    /// be carried out print an error and leave the inventory as it was.
+    ///
+    /// # Arguments
+    ///
+    /// * `action` - The action that was dispatched.
+    /// * `new_state` - The state returned by the reducer.
+    fn reduce_inventory(&self, action: &Variant, new_state: Dictionary) -> Dictionary {
+        let inventory = match self.inventory.borrow().as_ref() {
+            Some(inventory) => inventory.clone(),
+            None => return new_state,
+        };
+
+        let current = slice(&self.state.borrow(), inventory::SLICE);
+        let next = match inventory.reduce(&current, action) {
+            Ok(next) => next,
+            Err(message) => {
+                godot_error!("{}", message);
+                None
+            }
+        };
+
+        keep_slice(new_state, inventory::SLICE, next, current)
+    }
+
+    /// Enables the inventory, which adds an `inventory` slice to the state
+    /// with its slots under `slots`. An empty slot is `null` and a slot with
+    /// items is a Dictionary with the name of the item under `item` and the
+    /// number of items under `count`. The inventory is changed with the
+    /// `add_item`, `remove_item`, `move_item`, `swap_items`, and `split_stack`
+    /// actions, which are also passed to the reducer like any other action.
+    /// An `inventory` slice already in the state, for example from a save, is
+    /// kept.
+    ///
+    /// # Arguments
+    ///
+    /// * `capacity` - The number of slots.
+    /// * `items` - The items that can be put in the inventory, by name, each
+    /// with the most items that fit in one slot under `max_stack` and its tags
+    /// under `tags`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _ready():
+    ///     store.enable_inventory(20, {
+    ///         "potion": { "max_stack": 10, "tags": ["consumable"] },
+    ///         "sword": { "tags": ["weapon"] },
+    ///     })
+    ///     store.dispatch({ "type": "add_item", "item": "potion", "count": 3 })
+    /// ```
+    #[export]
+    fn enable_inventory(&self, _owner: &Object, capacity: i64, items: Dictionary) {
+        let inventory = match Inventory::new(capacity, &items) {
+            Ok(inventory) => inventory,
+            Err(message) => {
+                godot_error!("{}", message);
+                return;
+            }
+        };
+
+        if !self.state.borrow().contains(inventory::SLICE) {
+            let state = with_slice(
+                &self.state.borrow(),
+                inventory::SLICE,
+                inventory.initial_slice(),
+            );
+            *self.state.borrow_mut() = state;
+        }
+        *self.inventory.borrow_mut() = Some(inventory);
+    }
+
+    /// Returns every stack in the inventory of an item with the tag, each as a
+    /// Dictionary with the slot under `slot`, the name of the item under
+    /// `item`, and the number of items under `count`.
+    ///
+    /// # Arguments
+    ///
+    /// * `tag` - The tag to look for.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _on_quick_heal_pressed():
+    ///     var potions = store.get_items_by_tag("consumable")
+    ///     if not potions.empty():
+    ///         store.dispatch({ "type": "remove_item", "item": potions[0].item })
+    /// ```
+    #[export]
+    fn get_items_by_tag(&self, _owner: &Object, tag: GodotString) -> VariantArray<Unique> {
+        match self.inventory.borrow().as_ref() {
+            Some(inventory) => inventory.items_by_tag(
+                &slice(&self.state.borrow(), inventory::SLICE),
+                &tag.to_string(),
+            ),
+            None => VariantArray::new(),
+        }
+    }
+
+    /// Returns the total number of an item across every slot of the inventory.
+    ///
+    /// # Arguments
+    ///
+    /// * `item` - The name of the item.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _on_shop_opened():
+    ///     $Gold.text = str(store.get_item_count("gold"))
+    /// ```
+    #[export]
+    fn get_item_count(&self, _owner: &Object, item: GodotString) -> i64 {
+        inventory::item_count(
+            &slice(&self.state.borrow(), inventory::SLICE),
+            &item.to_string(),
+        )
+    }
+
+    /// Updates the `quests` slice for quest actions and queues
+    /// `objective_completed` and `quest_completed` for what they completed.
+    /// Actions that can't be carried out print an error and leave the quests
+    /// as they were.
+    ///
+    /// # Arguments
+    ///
+    /// * `action` - The action that was dispatched.
+    /// * `new_state` - The state returned by the reducer.
+    fn reduce_quests(&self, action: &Variant, new_state: Dictionary) -> Dictionary {
+        let quests = match self.quests.borrow().as_ref() {
+            Some(quests) => quests.clone(),
+            None => return new_state,
+        };
+
+        let current = slice(&self.state.borrow(), quest::SLICE);
+        let next = match quests.reduce(&current, action) {
+            Ok(next) => next.map(|(next, completions)| {
+                for completion in completions {
+                    match completion {
+                        Completion::Objective(quest, objective) => self.emit_after_update(
+                            "objective_completed",
+                            vec![Variant::from_str(quest), Variant::from_str(objective)],
+                        ),
+                        Completion::Quest(quest) => self
+                            .emit_after_update("quest_completed", vec![Variant::from_str(quest)]),
+                    }
+                }
+
+                next
+            }),
+            Err(message) => {
+                godot_error!("{}", message);
+                None
+            }
+        };
+
+        keep_slice(new_state, quest::SLICE, next, current)
+    }
+
+    /// Loads quest definitions, which adds a `quests` slice to the state with
+    /// the progress of every quest by name. The progress of a quest has its
+    /// status under `status`, which is `inactive`, `active`, `completed`, or
+    /// `failed`, and the progress of each objective under `objectives`.
+    /// Quests are changed with the `start_quest`, `advance_quest`,
+    /// `complete_quest`, and `fail_quest` actions, which are also passed to the
+    /// reducer like any other action. Progress already in the state, for
+    /// example from a save, is kept.
+    ///
+    /// # Arguments
+    ///
+    /// * `definitions` - The quest definitions by name, the path of a JSON file
+    /// with them, or a Resource, or the path of one, with them in its `quests`
+    /// property.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _ready():
+    ///     store.load_quests("res://quests.json")
+    ///     store.connect("quest_completed", self, "_on_quest_completed")
+    ///     store.dispatch({ "type": "start_quest", "quest": "rescue_cat" })
+    /// ```
+    #[export]
+    fn load_quests(&self, _owner: &Object, definitions: Variant) {
+        let quests = match file::read_definitions(&definitions, quest::SLICE)
+            .and_then(|definitions| Quests::new(&definitions))
+        {
+            Ok(quests) => quests,
+            Err(message) => {
+                godot_error!("Unable to load the quests: {}", message);
+                return;
+            }
+        };
+
+        let initial_slice = quests.initial_slice(&slice(&self.state.borrow(), quest::SLICE));
+        let state = with_slice(&self.state.borrow(), quest::SLICE, initial_slice);
+        *self.state.borrow_mut() = state;
+        *self.quests.borrow_mut() = Some(quests);
+    }
+
+    /// Returns whether a quest can be started, which is when it hasn't been
+    /// started yet and all of its prerequisites have been completed.
+    ///
+    /// # Arguments
+    ///
+    /// * `quest` - The name of the quest.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _on_mayor_talked_to():
+    ///     $QuestMarker.visible = store.can_start_quest("rescue_cat")
+    /// ```
+    #[export]
+    fn can_start_quest(&self, _owner: &Object, quest: GodotString) -> bool {
+        match self.quests.borrow().as_ref() {
+            Some(quests) => quests.can_start(
+                &slice(&self.state.borrow(), quest::SLICE),
+                &quest.to_string(),
+            ),
+            None => false,
+        }
+    }
+
+    /// Updates the `dialogue` slice for dialogue actions. Actions that can't be
+    /// carried out print an error and leave the dialogue as it was.
+    ///
+    /// # Arguments
+    ///
+    /// * `action` - The action that was dispatched.
+    /// * `new_state` - The state returned by the reducer.
+    fn reduce_dialogue(&self, action: &Variant, new_state: Dictionary) -> Dictionary {
+        if self.dialogues.borrow().is_empty() {
+            return new_state;
+        }
+
+        let current = slice(&self.state.borrow(), dialogue::SLICE);
+        let next = match dialogue::reduce(&self.dialogues.borrow(), &current, action) {
+            Ok(next) => next,
+            Err(message) => {
+                godot_error!("{}", message);
+                None
+            }
+        };
+
+        keep_slice(new_state, dialogue::SLICE, next, current)
+    }
+
+    /// Loads a dialogue graph, which adds a `dialogue` slice to the state with
+    /// the name of the current dialogue under `dialogue`, the id of the
+    /// current node under `node`, and the nodes that were visited before it
+    /// under `history`. Node is `null` once the dialogue has ended.
+    ///
+    /// The graph has the id of the first node under `start` and the nodes by
+    /// id under `nodes`. Each node has whoever says the line under `speaker`,
+    /// the line under `text`, and either the node that comes next under `next`
+    /// or the choices the player can pick under `choices`, each with its id
+    /// under `id`, its text under `text`, and the node it leads to under
+    /// `next`. Leaving out `next` ends the dialogue.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the dialogue.
+    /// * `graph` - The dialogue graph, the path of a JSON file with it, or a
+    /// Resource, or the path of one, with it in its `dialogue` property.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _ready():
+    ///     store.load_dialogue("shopkeeper", "res://dialogue/shopkeeper.json")
+    /// ```
+    #[export]
+    fn load_dialogue(&self, _owner: &Object, name: GodotString, graph: Variant) {
+        let dialogue = match file::read_definitions(&graph, dialogue::SLICE)
+            .and_then(|graph| Dialogue::new(&graph))
+        {
+            Ok(dialogue) => dialogue,
+            Err(message) => {
+                godot_error!("Unable to load the dialogue {}: {}", name, message);
+                return;
+            }
+        };
+
+        if !self.state.borrow().contains(dialogue::SLICE) {
+            let state = with_slice(
+                &self.state.borrow(),
+                dialogue::SLICE,
+                dialogue::initial_slice(),
+            );
+            *self.state.borrow_mut() = state;
+        }
+        self.dialogues
+            .borrow_mut()
+            .insert(name.to_string(), dialogue);
+    }
+
+    /// Starts a dialogue from its first node by dispatching a `start_dialogue`
+    /// action.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the dialogue.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _on_shopkeeper_interacted():
+    ///     store.start_dialogue("shopkeeper")
+    /// ```
+    #[export]
+    fn start_dialogue(&self, _owner: &Object, name: GodotString) {
+        self.dispatch_action(dialogue::start(&name.to_string()).owned_to_variant());
+    }
+
+    /// Advances the dialogue by dispatching a `choose_dialogue_option` action,
+    /// which moves to the node the choice leads to. On a line without choices
+    /// the option is left out to move on to the next line.
+    ///
+    /// # Arguments
+    ///
+    /// * `option_id` - The id of the picked choice, left out on a line without
+    /// choices.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _on_choice_pressed(choice):
+    ///     store.dispatch_choice(choice.id)
+    ///
+    /// func _on_continue_pressed():
+    ///     store.dispatch_choice()
+    /// ```
+    #[export]
+    fn dispatch_choice(&self, _owner: &Object, #[opt] option_id: GodotString) {
+        self.dispatch_action(dialogue::choose(&option_id.to_string()).owned_to_variant());
+    }
+
+    /// Skips ahead to the next line the player has to pick a choice on, or to
+    /// the end of the dialogue, by dispatching a `skip_dialogue` action. The
+    /// skipped lines are kept in the history.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _on_skip_pressed():
+    ///     store.skip_dialogue()
+    /// ```
+    #[export]
+    fn skip_dialogue(&self, _owner: &Object) {
+        self.dispatch_action(dialogue::skip().owned_to_variant());
+    }
+
+    /// Goes back to earlier lines of the dialogue by dispatching a
+    /// `rewind_dialogue` action.
+    ///
+    /// # Arguments
+    ///
+    /// * `steps` - The number of lines to go back.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _on_back_pressed():
+    ///     store.rewind_dialogue(1)
+    /// ```
+    #[export]
+    fn rewind_dialogue(&self, _owner: &Object, steps: i64) {
+        self.dispatch_action(dialogue::rewind(steps).owned_to_variant());
+    }
+
+    /// Returns the current line of the dialogue, or an empty String if no
+    /// dialogue is running.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _on_state_changed(state):
+    ///     $Line.text = store.get_dialogue_line()
+    /// ```
+    #[export]
+    fn get_dialogue_line(&self, _owner: &Object) -> GodotString {
+        let slice = slice(&self.state.borrow(), dialogue::SLICE);
+
+        dialogue::line(&self.dialogues.borrow(), &slice).into()
+    }
+
+    /// Returns whoever says the current line of the dialogue, or an empty
+    /// String if no dialogue is running.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _on_state_changed(state):
+    ///     $Speaker.text = store.get_dialogue_speaker()
+    /// ```
+    #[export]
+    fn get_dialogue_speaker(&self, _owner: &Object) -> GodotString {
+        let slice = slice(&self.state.borrow(), dialogue::SLICE);
+
+        dialogue::speaker(&self.dialogues.borrow(), &slice).into()
+    }
+
+    /// Returns the choices of the current line of the dialogue as
+    /// Dictionaries with the id of the choice under `id` and its text under
+    /// `text`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _on_state_changed(state):
+    ///     for choice in store.get_dialogue_choices():
+    ///         add_choice_button(choice.id, choice.text)
+    /// ```
+    #[export]
+    fn get_dialogue_choices(&self, _owner: &Object) -> VariantArray<Unique> {
+        let slice = slice(&self.state.borrow(), dialogue::SLICE);
+
+        dialogue::choices(&self.dialogues.borrow(), &slice)
+    }
+
+    /// Moves a state machine to another state for `fsm_transition` actions.
+    /// Transitions the machine doesn't allow, or that a guard rejects, print a
+    /// warning and leave the machine in its current state. Otherwise
+    /// `exited_state` and `entered_state` are queued along with the on-enter
+    /// effects of the new state.
+    ///
+    /// # Arguments
+    ///
+    /// * `action` - The action that was dispatched.
+    /// * `new_state` - The state returned by the reducer.
+    fn reduce_fsm(&self, action: &Variant, new_state: Dictionary) -> Dictionary {
+        if self.machines.borrow().is_empty() {
+            return new_state;
+        }
+
+        let current = slice(&self.state.borrow(), fsm::SLICE);
+        let (name, to) = match fsm::target(action) {
+            Some(target) => target,
+            None => return keep_slice(new_state, fsm::SLICE, None::<Dictionary>, current),
+        };
+
+        // The machine is cloned so the guards can call back into the store.
+        let machine = match self.machines.borrow().get(&name) {
+            Some(machine) => machine.clone(),
+            None => {
+                godot_warn!(
+                    "Unable to transition the state machine {} since it doesn't exist",
+                    name
+                );
+                return keep_slice(new_state, fsm::SLICE, None::<Dictionary>, current);
+            }
+        };
+        let from = fsm::current_state(&current, &name)
+            .unwrap_or_else(|| machine.initial_state().to_string());
+
+        if let Err(message) = machine.check_transition(&from, &to) {
+            godot_warn!(
+                "Rejected a transition of the state machine {}: {}",
+                name,
+                message
+            );
+            return keep_slice(new_state, fsm::SLICE, None::<Dictionary>, current);
+        }
+
+        let state = Variant::from_dictionary(&self.readable_state());
+        for guard in machine.guards(&to) {
+            let args = &[
+                state.clone(),
+                Variant::from_str(&from),
+                Variant::from_str(&to),
+            ];
+            if !guard.call_func(args).to_bool() {
+                godot_warn!(
+                    "Rejected a transition of the state machine {}: A guard didn't allow moving from {} to {}",
+                    name,
+                    from,
+                    to
+                );
+                return keep_slice(new_state, fsm::SLICE, None::<Dictionary>, current);
+            }
+        }
+
+        let next = current.duplicate();
+        next.insert(&name, fsm::machine_slice(&to, Some(from.as_str())));
+
+        self.emit_after_update(
+            "exited_state",
+            vec![Variant::from_str(&name), Variant::from_str(&from)],
+        );
+        self.emit_after_update(
+            "entered_state",
+            vec![Variant::from_str(&name), Variant::from_str(&to)],
+        );
+        for effect in machine.on_enter(&to) {
+            self.pending_effects.borrow_mut().push((
+                effect.clone(),
+                vec![Variant::from_str(&from), Variant::from_str(&to)],
+            ));
+        }
+
+        keep_slice(new_state, fsm::SLICE, Some(next), current)
+    }
+
+    /// Creates a state machine, which is kept in the `fsm` slice of the state
+    /// by name with its current state under `state` and the state it was in
+    /// before under `previous`. The machine is moved to another state by
+    /// dispatching an `fsm_transition` action, which `transition_fsm` does, and
+    /// transitions that aren't allowed are rejected with a warning. The
+    /// `exited_state` and `entered_state` signals are emitted with the name of
+    /// the machine and the state after each transition. A machine that is
+    /// already in the state, for example from a save, keeps its current state.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the state machine.
+    /// * `states` - The states of the machine, starting with the initial state.
+    /// * `transitions` - The states each state can move to, by state.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _ready():
+    ///     store.create_fsm_slice("player", ["idle", "running", "jumping"], {
+    ///         "idle": ["running", "jumping"],
+    ///         "running": ["idle", "jumping"],
+    ///         "jumping": ["idle"],
+    ///     })
+    /// ```
+    #[export]
+    fn create_fsm_slice(
+        &self,
+        _owner: &Object,
+        name: GodotString,
+        states: VariantArray,
+        transitions: Dictionary,
+    ) {
+        let name = name.to_string();
+        let machine = match Machine::new(&states, &transitions) {
+            Ok(machine) => machine,
+            Err(message) => {
+                godot_error!("Unable to create the state machine {}: {}", name, message);
+                return;
+            }
+        };
+
+        let current = slice(&self.state.borrow(), fsm::SLICE);
+        let kept =
+            fsm::current_state(&current, &name).is_some_and(|state| machine.has_state(&state));
+        if !kept {
+            let next = current.duplicate();
+            next.insert(&name, fsm::machine_slice(machine.initial_state(), None));
+
+            let state = with_slice(&self.state.borrow(), fsm::SLICE, next);
+            *self.state.borrow_mut() = state;
+        }
+
+        self.machines.borrow_mut().insert(name, machine);
+    }
+
+    /// Moves a state machine to another state by dispatching an
+    /// `fsm_transition` action.
+    ///
+    /// # Arguments
+    ///
+    /// * `machine` - The name of the state machine.
+    /// * `to` - The state to move to.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _on_jump_pressed():
+    ///     store.transition_fsm("player", "jumping")
+    /// ```
+    #[export]
+    fn transition_fsm(&self, _owner: &Object, machine: GodotString, to: GodotString) {
+        let action = fsm::transition(&machine.to_string(), &to.to_string());
+        self.dispatch_action(action.owned_to_variant());
+    }
+
+    /// Returns the current state of a state machine, or an empty String if
+    /// there is no state machine with the name.
+    ///
+    /// # Arguments
+    ///
+    /// * `machine` - The name of the state machine.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _physics_process(delta):
+    ///     if store.get_fsm_state("player") == "jumping":
+    ///         apply_gravity(delta)
+    /// ```
+    #[export]
+    fn get_fsm_state(&self, _owner: &Object, machine: GodotString) -> GodotString {
+        let slice = slice(&self.state.borrow(), fsm::SLICE);
+
+        fsm::current_state(&slice, &machine.to_string())
+            .unwrap_or_default()
+            .into()
+    }
+
+    /// Adds a guard that has to allow a state machine to move into a state.
+    /// The guard is passed the state of the store and the names of the state
+    /// being left and the state being entered, and the transition is rejected
+    /// unless it returns `true`.
+    ///
+    /// # Arguments
+    ///
+    /// * `machine` - The name of the state machine.
+    /// * `state` - The state the guard protects.
+    /// * `guard_fn_instance` - The instance on which the guard function exists.
+    /// * `guard_fn_name` - The name of the guard function.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _ready():
+    ///     store.add_fsm_guard("player", "jumping", self, "can_jump")
+    ///
+    /// func can_jump(state, from, to):
+    ///     return state.player.on_floor
+    /// ```
+    #[export]
+    fn add_fsm_guard(
+        &self,
+        _owner: &Object,
+        machine: GodotString,
+        state: GodotString,
+        guard_fn_instance: Ref<Object, Shared>,
+        guard_fn_name: GodotString,
+    ) {
+        self.update_machine(
+            &machine.to_string(),
+            &state.to_string(),
+            |machine, state| machine.add_guard(state, func_ref(guard_fn_instance, guard_fn_name)),
+        );
+    }
+
+    /// Adds an effect that is run after a state machine moves into a state,
+    /// once the state of the store has been updated. The effect is passed the
+    /// state of the store and the names of the state that was left and the
+    /// state that was entered. Effects aren't run while running a scenario.
+    ///
+    /// # Arguments
+    ///
+    /// * `machine` - The name of the state machine.
+    /// * `state` - The state that runs the effect when it's entered.
+    /// * `effect_fn_instance` - The instance on which the effect function
+    /// exists.
+    /// * `effect_fn_name` - The name of the effect function.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _ready():
+    ///     store.add_fsm_on_enter("player", "jumping", self, "play_jump_sound")
+    ///
+    /// func play_jump_sound(state, from, to):
+    ///     $JumpSound.play()
+    /// ```
+    #[export]
+    fn add_fsm_on_enter(
+        &self,
+        _owner: &Object,
+        machine: GodotString,
+        state: GodotString,
+        effect_fn_instance: Ref<Object, Shared>,
+        effect_fn_name: GodotString,
+    ) {
+        self.update_machine(
+            &machine.to_string(),
+            &state.to_string(),
+            |machine, state| {
+                machine.add_on_enter(state, func_ref(effect_fn_instance, effect_fn_name))
+            },
+        );
+    }
+
+    /// Changes a state machine, printing an error if the machine or the state
+    /// doesn't exist.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the state machine.
+    /// * `state` - The state of the machine the change is for.
+    /// * `update` - The change to make.
+    fn update_machine<F: FnOnce(&mut Machine, &str)>(&self, name: &str, state: &str, update: F) {
+        match self.machines.borrow_mut().get_mut(name) {
+            Some(machine) if machine.has_state(state) => update(machine, state),
+            Some(_) => godot_error!(
+                "The state machine {} doesn't have the state {}",
+                name,
+                state
+            ),
+            None => godot_error!("There is no state machine named {}", name),
+        }
+    }
+
+    /// Runs the effects queued while the state was being updated, passing each
+    /// one the new state first. Effects aren't run while running a scenario.
+    fn run_pending_effects(&self) {
+        let pending_effects = self.pending_effects.replace(vec![]);
+        if self.running_scenario.get() {
+            return;
+        }
+
+        for (effect, args) in pending_effects {
+            let mut all_args = vec![Variant::from_dictionary(&self.readable_state())];
+            all_args.extend(args);
+
+            effect.call_func(&all_args);
+        }
+    }
+
+    /// Updates the `cooldowns` slice for cooldown actions.
+    ///
+    /// # Arguments
+    ///
+    /// * `action` - The action that was dispatched.
+    /// * `new_state` - The state returned by the reducer.
+    fn reduce_cooldowns(&self, action: &Variant, new_state: Dictionary) -> Dictionary {
+        let state = self.readable_state();
+        let current = slice(&state, cooldown::SLICE);
+
+        match cooldown::reduce(&current, action) {
+            None if !state.contains(cooldown::SLICE) => new_state,
+            next => keep_slice(new_state, cooldown::SLICE, next, current),
+        }
+    }
+
+    /// Starts a cooldown by dispatching a `start_cooldown` action. Cooldowns
+    /// are kept in the `cooldowns` slice of the state by name, with how long
+    /// they last under `duration` and how many seconds are left under
+    /// `remaining`. While any cooldown is running the store dispatches a
+    /// `cooldown_tick` action every frame with the seconds that passed under
+    /// `delta`, so the store has to be attached to the tree with
+    /// `attach_to_tree`. Starting a cooldown that is already running starts it
+    /// over.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the cooldown.
+    /// * `duration` - How long the cooldown lasts, in seconds.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _on_dash_pressed():
+    ///     if store.is_cooldown_ready("dash"):
+    ///         dash()
+    ///         store.start_cooldown("dash", 2.0)
+    /// ```
+    #[export]
+    fn start_cooldown(&self, _owner: &Object, name: GodotString, duration: f64) {
+        if self.last_cooldown_tick.get().is_none() {
+            self.last_cooldown_tick.set(Some(self.now()));
+        }
+
+        let action = cooldown::start(&name.to_string(), duration);
+        self.dispatch_action(action.owned_to_variant());
+    }
+
+    /// Returns whether a cooldown has run out, which is also the case for a
+    /// cooldown that was never started.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the cooldown.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _process(delta):
+    ///     $DashIcon.modulate.a = 1.0 if store.is_cooldown_ready("dash") else 0.5
+    /// ```
+    #[export]
+    fn is_cooldown_ready(&self, _owner: &Object, name: GodotString) -> bool {
+        let slice = slice(&self.readable_state(), cooldown::SLICE);
+
+        cooldown::time_left(&slice, &name.to_string()) <= 0.0
+    }
+
+    /// Returns how many seconds are left on a cooldown, which is 0 for a
+    /// cooldown that has run out or was never started.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the cooldown.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _process(delta):
+    ///     $DashLabel.text = "%.1f" % store.get_cooldown_time_left("dash")
+    /// ```
+    #[export]
+    fn get_cooldown_time_left(&self, _owner: &Object, name: GodotString) -> f64 {
+        let slice = slice(&self.readable_state(), cooldown::SLICE);
+
+        cooldown::time_left(&slice, &name.to_string())
+    }
+
+    /// Updates the `preload` slice for preload actions and for the resources
+    /// the reducer asked for under `__preload`, which is taken out of the
+    /// state, and queues the resources that were asked for.
+    ///
+    /// # Arguments
+    ///
+    /// * `action` - The action that was dispatched.
+    /// * `new_state` - The state returned by the reducer.
+    fn reduce_preload(&self, action: &Variant, new_state: Dictionary) -> Dictionary {
+        let (new_state, mut paths) = preload::take_marked(new_state);
+        paths.extend(preload::requested(action));
+
+        // Resources aren't loaded while running a scenario, but the slice
+        // still shows that they were asked for.
+        if !self.running_scenario.get() {
+            let mut preloader = self.preloader.borrow_mut();
+            for path in &paths {
+                preloader.queue(path);
+            }
+        }
+
+        let current = slice(&self.state.borrow(), preload::SLICE);
+        match preload::reduce(&current, &paths, action) {
+            None if !self.state.borrow().contains(preload::SLICE) => new_state,
+            next => keep_slice(new_state, preload::SLICE, next, current),
+        }
+    }
+
+    /// Preloads resources in the background, a bit at a time every frame, so
+    /// a loading screen can show their progress and the scene that needs them
+    /// can be changed to right away once they're loaded. This dispatches a
+    /// `preload` action with the paths under `paths`, which is the same as a
+    /// reducer returning a state with the paths under `__preload`, for
+    /// example `{ "__preload": ["res://levels/3.tscn"] }`, which the store
+    /// takes out of the state.
+    ///
+    /// The progress is kept in the `preload` slice of the state, with an
+    /// entry for every resource under `resources` that has how much of it
+    /// was loaded under `progress`, whether it's done under `done`, and the
+    /// error it failed with under `error`, along with how much of every
+    /// resource was loaded under `progress` and whether they're all done
+    /// under `done`. `preload_progress`, `preload_finished`, and
+    /// `preload_failed` actions are dispatched as the resources are loaded,
+    /// which only happens while the store is attached to the tree with
+    /// `attach_to_tree`. Loaded resources are kept until they're released
+    /// with `release_preloaded`.
+    ///
+    /// # Arguments
+    ///
+    /// * `paths` - The paths of the resources.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _on_level_selected(level):
+    ///     store.preload_resources(["res://levels/%d.tscn" % level])
+    ///
+    /// func _on_state_changed(state):
+    ///     $LoadingBar.value = state.preload.progress * 100
+    /// ```
+    #[export]
+    fn preload_resources(&self, _owner: &Object, paths: VariantArray) {
+        self.dispatch_action(preload::request(paths).owned_to_variant());
+    }
+
+    /// Returns a resource that was preloaded with `preload_resources`, or
+    /// `null` if it isn't loaded yet.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the resource.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _on_state_changed(state):
+    ///     if state.preload.done:
+    ///         get_tree().change_scene_to(store.get_preloaded("res://levels/3.tscn"))
+    /// ```
+    #[export]
+    fn get_preloaded(&self, _owner: &Object, path: GodotString) -> Variant {
+        match self.preloader.borrow().get(&path.to_string()) {
+            Some(resource) => resource.to_variant(),
+            None => Variant::new(),
+        }
+    }
+
+    /// Lets go of a resource that was preloaded once it isn't needed anymore
+    /// and removes it from the `preload` slice by dispatching a
+    /// `preload_released` action. Returns whether the resource was loaded.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the resource.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _on_level_started():
+    ///     store.release_preloaded("res://levels/3.tscn")
+    /// ```
+    #[export]
+    fn release_preloaded(&self, _owner: &Object, path: GodotString) -> bool {
+        let path = path.to_string();
+        let released = self.preloader.borrow_mut().release(&path);
+        if released {
+            self.dispatch_action(preload::released(&path).owned_to_variant());
+        }
+
+        released
+    }
+
+    /// Sets how long resources are preloaded for every frame, which is 8
+    /// milliseconds by default. A higher budget loads them faster while
+    /// taking more time away from the frame.
+    ///
+    /// # Arguments
+    ///
+    /// * `budget_msec` - The time, in milliseconds, which has to be at least 1.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _on_loading_screen_shown():
+    ///     store.set_preload_budget(30)
+    /// ```
+    #[export]
+    fn set_preload_budget(&self, _owner: &Object, budget_msec: i64) {
+        if budget_msec < 1 {
+            godot_error!(
+                "The preload budget has to be at least 1 millisecond, got {}",
+                budget_msec
+            );
+            return;
+        }
+
+        self.preloader.borrow_mut().set_budget(budget_msec);
+    }
+
+    /// Loads some of the resources being preloaded and dispatches what
+    /// happened to them.
+    fn poll_preloads(&self) {
+        if self.preloader.borrow().is_idle() {
+            return;
+        }
+
+        let events = self.preloader.borrow_mut().poll();
+        for event in events {
+            self.dispatch_action(event.to_action().owned_to_variant());
+        }
+    }
+
+    /// Counts down the running cooldowns by the time that passed since they
+    /// were last counted down.
+    fn tick_cooldowns(&self) {
+        let running = cooldown::any_running(&slice(&self.readable_state(), cooldown::SLICE));
+        if !running {
+            self.last_cooldown_tick.set(None);
+            return;
+        }
+
+        let now = self.now();
+        let delta = now - self.last_cooldown_tick.get().unwrap_or(now);
+        self.last_cooldown_tick.set(Some(now));
+
+        // The time that passes while the cooldowns are frozen is skipped.
+        if delta > 0.0 && !self.is_slice_frozen(cooldown::SLICE) {
+            self.dispatch_action(cooldown::tick(delta).owned_to_variant());
+        }
+    }
+
+    /// Updates the `feature_flags` slice for feature flag actions.
+    ///
+    /// # Arguments
+    ///
+    /// * `action` - The action that was dispatched.
+    /// * `new_state` - The state returned by the reducer.
+    fn reduce_feature_flags(&self, action: &Variant, new_state: Dictionary) -> Dictionary {
+        let current = slice(&self.state.borrow(), feature_flag::SLICE);
+
+        match feature_flag::reduce(&current, action) {
+            None if !self.state.borrow().contains(feature_flag::SLICE) => new_state,
+            next => keep_slice(new_state, feature_flag::SLICE, next, current),
+        }
+    }
+
+    /// Sets the default value of feature flags by dispatching a `set_flags`
+    /// action. Flags are kept in the `feature_flags` slice of the state, with
+    /// the defaults under `defaults` and the overrides loaded with
+    /// `load_flag_overrides` under `overrides`, which win over the defaults.
+    /// The flags are merged into the ones that were already set.
+    ///
+    /// # Arguments
+    ///
+    /// * `flags` - The flags and whether they are enabled.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _ready():
+    ///     store.set_flags({ "new_shop": false, "double_xp": true })
+    /// ```
+    #[export]
+    fn set_flags(&self, _owner: &Object, flags: Dictionary) {
+        self.dispatch_action(feature_flag::set_flags(&flags).owned_to_variant());
+    }
+
+    /// Loads overrides for feature flags from a JSON file or from a URL, for
+    /// example to turn features on for an A/B test or a staged rollout without
+    /// shipping a new build. Overrides are applied by dispatching an
+    /// `override_flags` action once they are loaded. Loading from a URL needs
+    /// the store to be attached to a tree with `attach_to_tree` and happens in
+    /// the background.
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - The path of the file or the `http://` or `https://` URL.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _ready():
+    ///     store.attach_to_tree(get_tree())
+    ///     store.load_flag_overrides("https://example.com/flags.json")
+    /// ```
+    #[export]
+    fn load_flag_overrides(&self, owner: TRef<Object>, source: GodotString) {
+        let source = source.to_string();
+        if source.starts_with("http://") || source.starts_with("https://") {
+            self.request_flag_overrides(owner, &source);
+            return;
+        }
+
+        match file::read_json(&source) {
+            Ok(flags) => self.override_flags(&source, &flags),
+            Err(message) => godot_error!("Unable to load the flag overrides: {}", message),
+        }
+    }
+
+    /// Requests the overrides for feature flags from a URL with an HTTPRequest
+    /// added to the root of the tree the store is attached to.
+    ///
+    /// # Arguments
+    ///
+    /// * `owner` - The store.
+    /// * `url` - The URL.
+    fn request_flag_overrides(&self, owner: TRef<Object>, url: &str) {
+        let tree = match self.tree.borrow().as_ref() {
+            Some(tree) => tree.clone(),
+            None => {
+                godot_error!(
+                    "The store has to be attached to a tree with attach_to_tree to load flag overrides from {}",
+                    url
+                );
+                return;
+            }
+        };
+        let root = match unsafe { tree.assume_safe() }.root() {
+            Some(root) => unsafe { root.assume_safe() },
+            None => return,
+        };
+
+        let request = HTTPRequest::new().into_shared();
+        root.add_child(request.clone(), false);
+
+        let binds = VariantArray::new();
+        binds.push(request.clone());
+        binds.push(url);
+
+        let request = unsafe { request.assume_safe() };
+        let connected = request.connect(
+            "request_completed",
+            owner,
+            "on_flag_overrides_loaded",
+            binds.into_shared(),
+            0,
+        );
+        let requested = connected.and_then(|_| {
+            request.request(url, StringArray::new(), true, HTTPClient::METHOD_GET, "")
+        });
+
+        match requested {
+            Ok(()) => self
+                .flag_requests
+                .borrow_mut()
+                .push(request.get_instance_id()),
+            Err(error) => {
+                godot_error!(
+                    "Unable to request the flag overrides from {}: {:?}",
+                    url,
+                    error
+                );
+                request.queue_free();
+            }
+        }
+    }
+
+    /// Applies the overrides for feature flags once the request for them made
+    /// by `load_flag_overrides` is done.
+    #[export]
+    fn on_flag_overrides_loaded(
+        &self,
+        _owner: &Object,
+        result: i64,
+        response_code: i64,
+        _headers: StringArray,
+        body: ByteArray,
+        request: Ref<Node, Shared>,
+        url: GodotString,
+    ) {
+        let request = unsafe { request.assume_safe() };
+        let request_id = request.get_instance_id();
+        self.flag_requests
+            .borrow_mut()
+            .retain(|id| *id != request_id);
+        request.queue_free();
+
+        if result != HTTPRequest::RESULT_SUCCESS || response_code != 200 {
+            godot_error!(
+                "Unable to load the flag overrides from {}: the request failed with result {} and response code {}",
+                url,
+                result,
+                response_code
+            );
+            return;
+        }
+
+        let text = String::from_utf8_lossy(&body.read()).to_string();
+        match file::parse_json(&text) {
+            Ok(flags) => self.override_flags(&url.to_string(), &flags),
+            Err(message) => godot_error!(
+                "Unable to parse the flag overrides from {} as JSON: {}",
+                url,
+                message
+            ),
+        }
+    }
+
+    /// Dispatches an `override_flags` action with flags that were loaded.
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - Where the flags were loaded from.
+    /// * `flags` - The flags.
+    fn override_flags(&self, source: &str, flags: &Variant) {
+        match flags.try_to_dictionary() {
+            Some(flags) => {
+                self.dispatch_action(feature_flag::override_flags(&flags).owned_to_variant());
+            }
+            None => godot_error!("The flag overrides in {} aren't a Dictionary", source),
+        }
+    }
+
+    /// Returns whether a feature flag is enabled, which is its override if it
+    /// has one and otherwise its default. Flags that were never set are
+    /// disabled.
+    ///
+    /// # Arguments
+    ///
+    /// * `flag` - The name of the flag.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _ready():
+    ///     $NewShopButton.visible = store.is_enabled("new_shop")
+    /// ```
+    #[export]
+    fn is_enabled(&self, _owner: &Object, flag: GodotString) -> bool {
+        let slice = slice(&self.readable_state(), feature_flag::SLICE);
+
+        feature_flag::is_enabled(&slice, &flag.to_string())
+    }
+
+    /// Gates an action type behind a feature flag, so actions of the type are
+    /// dropped before they reach the middleware and the reducer while the flag
+    /// is disabled.
+    ///
+    /// # Arguments
+    ///
+    /// * `action_type` - The type of the actions to gate.
+    /// * `flag` - The name of the flag.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _ready():
+    ///     store.gate_action(Action.OPEN_NEW_SHOP, "new_shop")
+    /// ```
+    #[export]
+    fn gate_action(&self, _owner: &Object, action_type: Variant, flag: GodotString) {
+        self.flag_gates
+            .borrow_mut()
+            .push((action_type, flag.to_string()));
+    }
+
+    /// Collapses actions of a type that are dispatched again within a window
+    /// of time into the first one, like the pickup events of overlapping
+    /// `Area2D`s, so the duplicates are dropped before they reach the
+    /// middleware and the reducer. Two actions are the same if the values at
+    /// the payload keys are equal, or if the whole actions are equal when no
+    /// payload keys are given. The window starts when an action is let
+    /// through, on the store's clock, and the same action is let through
+    /// again once it has passed. Calling this again for the type replaces the
+    /// window and the payload keys, and a window of 0 stops deduplicating it.
+    /// Actions dispatched by dry runs, like `preview`, `run_scenario`,
+    /// `fuzz`, and `replay_from`, are never dropped and don't start a window.
+    ///
+    /// # Arguments
+    ///
+    /// * `action_type` - The type of the actions to deduplicate.
+    /// * `window_ms` - How long after an action the same action is dropped,
+    /// in milliseconds.
+    /// * `payload_keys` - Optional paths of the values in the action that
+    /// decide whether two actions are the same, for example `item_id`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _ready():
+    ///     store.set_dedupe(Action.PICK_UP, 100, ["item_id"])
+    ///
+    /// func _on_area_body_entered(_body):
+    ///     store.dispatch({"type": Action.PICK_UP, "item_id": item_id})
+    /// ```
+    #[export]
+    fn set_dedupe(
+        &self,
+        _owner: &Object,
+        action_type: Variant,
+        window_ms: f64,
+        #[opt] payload_keys: VariantArray,
+    ) {
+        self.dedupe
+            .borrow_mut()
+            .set(action_type, window_ms, &payload_keys);
+    }
+
+    /// Returns whether an action is the same as one that was dispatched
+    /// within the dedupe window of its type, in which case it's dropped.
+    ///
+    /// # Arguments
+    ///
+    /// * `action` - The action that was dispatched.
+    fn is_duplicate(&self, action: &Variant) -> bool {
+        let now = self.now();
+        self.dedupe
+            .borrow_mut()
+            .is_duplicate(action, now, self.running_scenario.get())
+    }
+
+    /// Sets what happens when a stage of a dispatch fails, like a reducer that
+    /// doesn't return a Dictionary, a patch that can't be applied, a state
+    /// that doesn't match the schema, a subscriber that can't be called, or an
+    /// effect of `on_action` that can't be run. The error is always printed,
+    /// and then:
+    ///
+    /// - `revert` - The state is left the way it was before the action and the
+    /// store carries on.
+    /// - `halt` - The state is left the way it was, the `fatal_error` signal
+    /// is emitted with the stage, the error, and the action, and every action
+    /// dispatched after it is dropped until `resume_dispatching` is called.
+    /// - `handler` - The state is left the way it was and the handler is
+    /// called with the stage, the error, and the action.
+    ///
+    /// Subscribers and effects run after the state was replaced, so their
+    /// failures can't revert it. An empty policy goes back to only printing
+    /// the error, which keeps whatever the failing stage produced.
+    ///
+    /// # Arguments
+    ///
+    /// * `policy` - The policy, which is `revert`, `halt`, `handler`, or empty.
+    /// * `handler` - Optional FuncRef called for the `handler` policy.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _ready():
+    ///     if OS.is_debug_build():
+    ///         store.set_error_policy("halt")
+    ///         store.connect("fatal_error", self, "_on_fatal_error")
+    ///     else:
+    ///         store.set_error_policy("handler", funcref(self, "_report_error"))
+    ///
+    /// func _report_error(stage, message, action):
+    ///     Analytics.send("store_error", { "stage": stage, "message": message })
+    /// ```
+    #[export]
+    fn set_error_policy(&self, _owner: &Object, policy: GodotString, #[opt] handler: Variant) {
+        match ErrorPolicy::from_name(&policy.to_string(), &handler) {
+            Ok(policy) => *self.error_policy.borrow_mut() = policy,
+            Err(message) => godot_error!("{}", message),
+        }
+    }
+
+    /// Lets actions be dispatched again after the `halt` error policy halted
+    /// dispatching. The actions dispatched while it was halted aren't
+    /// dispatched.
+    #[export]
+    fn resume_dispatching(&self, _owner: &Object) {
+        self.dispatch_halted.set(false);
+    }
+
+    /// Returns whether dispatching was halted by the `halt` error policy.
+    #[export]
+    fn is_dispatching_halted(&self, _owner: &Object) -> bool {
+        self.dispatch_halted.get()
+    }
+
+    /// Handles a stage of a dispatch failing according to the policy set with
+    /// `set_error_policy`, and returns whether the state should be left the
+    /// way it was before the action.
+    ///
+    /// # Arguments
+    ///
+    /// * `stage` - The stage that failed.
+    /// * `action` - The action being dispatched, or `null` if there's none.
+    /// * `message` - Why the stage failed.
+    fn pipeline_failed(&self, stage: &str, action: &Variant, message: &str) -> bool {
+        let policy = self.error_policy.borrow().clone();
+        let policy = match policy {
+            Some(policy) => policy,
+            None => return false,
+        };
+
+        let args = [
+            Variant::from_str(stage),
+            Variant::from_str(message),
+            action.clone(),
+        ];
+        match policy {
+            ErrorPolicy::Revert => {}
+            ErrorPolicy::Halt => {
+                self.dispatch_halted.set(true);
+                if !self.owner_was_freed() {
+                    unsafe { self.owner.assume_safe() }.emit_signal("fatal_error", &args);
+                }
+            }
+            ErrorPolicy::Handler(handler) => {
+                let handler = unsafe { handler.assume_safe() };
+                if handler.is_valid() {
+                    handler.call_func(&args);
+                } else {
+                    godot_error!(
+                        "Unable to call the error handler: The instance doesn't have the function"
+                    );
+                }
+            }
+        }
+
+        true
+    }
+
+    /// Registers a cheat, like god mode or unlocking every level, which
+    /// dispatches an action when it's used with `use_cheat` or the `cheat`
+    /// console command. Each cheat needs a permission level, and it can only
+    /// be used while the level set with `set_cheat_permission` is at least
+    /// that high. Actions of the same type as a cheat's action are also
+    /// rejected when they're dispatched in any other way while the level is
+    /// too low, so release builds can't use cheats even by dispatching their
+    /// actions. Every time a cheat is used, it's counted in the `cheats` slice
+    /// of the state under `used`, by name, so achievements or leaderboards can
+    /// be turned off. Registering a cheat with the name of one that was
+    /// registered before replaces it.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the cheat.
+    /// * `action` - The action the cheat dispatches.
+    /// * `permission_level` - The permission level needed to use the cheat.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _ready():
+    ///     store.register_cheat("god_mode", { "type": "set_invincible", "value": true }, 1)
+    ///     store.register_cheat("unlock_all", { "type": "unlock_all_levels" }, 2)
+    ///     if OS.has_feature("qa"):
+    ///         store.set_cheat_permission(2)
+    /// ```
+    #[export]
+    fn register_cheat(
+        &self,
+        _owner: &Object,
+        name: GodotString,
+        action: Variant,
+        permission_level: i64,
+    ) {
+        self.cheats
+            .borrow_mut()
+            .register(&name.to_string(), action, permission_level);
+    }
+
+    /// Sets the permission level that decides which cheats can be used. A
+    /// cheat can be used while the level is at least as high as the level it
+    /// needs. It starts out at 0.
+    ///
+    /// # Arguments
+    ///
+    /// * `level` - The permission level.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _on_qa_login_succeeded():
+    ///     store.set_cheat_permission(2)
+    /// ```
+    #[export]
+    fn set_cheat_permission(&self, _owner: &Object, level: i64) {
+        self.cheats.borrow_mut().set_permission(level);
+    }
+
+    /// Uses a cheat registered with `register_cheat`, dispatching its action.
+    /// Returns whether the cheat could be used, which it can't be if there's
+    /// no cheat with the name or the permission level is too low.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the cheat.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _on_god_mode_pressed():
+    ///     store.use_cheat("god_mode")
+    /// ```
+    #[export]
+    fn use_cheat(&self, _owner: &Object, name: GodotString) -> bool {
+        let action = self.cheats.borrow().action(&name.to_string());
+        match action {
+            Ok(action) => {
+                self.dispatch_action(action);
+                true
+            }
+            Err(message) => {
+                godot_error!("Unable to use the cheat: {}", message);
+                false
+            }
+        }
+    }
+
+    /// Returns the cheats that can be used with the permission level, with
+    /// the level each of them needs by name, for example to fill a cheat
+    /// menu.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _on_cheat_menu_opened():
+    ///     for cheat in store.get_cheats():
+    ///         $CheatList.add_item(cheat)
+    /// ```
+    #[export]
+    fn get_cheats(&self, _owner: &Object) -> Dictionary {
+        let cheats = Dictionary::new();
+        for (name, level) in self.cheats.borrow().available() {
+            cheats.insert(name, level);
+        }
+
+        cheats.into_shared()
+    }
+
+    /// Returns the name of the cheat that forbids an action from being
+    /// dispatched since it needs a higher permission level, or `None` if the
+    /// action can be dispatched.
+    ///
+    /// # Arguments
+    ///
+    /// * `action` - The action that was dispatched.
+    fn forbidden_cheat(&self, action: &Variant) -> Option<String> {
+        self.cheats
+            .borrow()
+            .forbidding(action)
+            .map(|cheat| cheat.to_string())
+    }
+
+    /// Counts the cheats an action used in the `cheats` slice.
+    ///
+    /// # Arguments
+    ///
+    /// * `action` - The action that was dispatched.
+    /// * `new_state` - The state returned by the reducer.
+    fn reduce_cheats(&self, action: &Variant, new_state: Dictionary) -> Dictionary {
+        let cheats = self.cheats.borrow();
+        let used = cheats.used_by(action);
+
+        let current = slice(&self.state.borrow(), cheat::SLICE);
+        match cheat::reduce(&current, &used) {
+            None if !self.state.borrow().contains(cheat::SLICE) => new_state,
+            next => keep_slice(new_state, cheat::SLICE, next, current),
+        }
+    }
+
+    /// Seeds the random numbers drawn with `next_random` and
+    /// `next_random_int` by dispatching a `seed_random` action, which starts
+    /// every stream over. The seed is kept in the `random` slice of the state
+    /// under `seed`, along with how far along each stream is under
+    /// `streams`, so the numbers only depend on the state and the actions.
+    /// Replaying the actions, or dispatching the same ones on every peer in a
+    /// networked lockstep game, draws exactly the same numbers. The seed is 0
+    /// until this is called.
+    ///
+    /// # Arguments
+    ///
+    /// * `seed` - The seed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _on_match_started(match_seed):
+    ///     store.seed_random(match_seed)
+    /// ```
+    #[export]
+    fn seed_random(&self, _owner: &Object, seed: i64) {
+        self.dispatch_action(random::seed(seed).owned_to_variant());
+    }
+
+    /// Draws the next random number of a stream, from 0 up to but not
+    /// including 1, by dispatching a `random_drawn` action with the name of
+    /// the stream under `stream`. Since drawing a number is an action, it's
+    /// recorded like any other, so replays and peers that dispatch the same
+    /// actions draw the same numbers. Every stream has its own sequence, so
+    /// drawing from one, like `loot`, doesn't change the numbers drawn from
+    /// another, like `ai`.
+    ///
+    /// # Arguments
+    ///
+    /// * `stream` - The name of the stream.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func roll_critical_hit():
+    ///     return store.next_random("combat") < 0.1
+    /// ```
+    #[export]
+    fn next_random(&self, _owner: &Object, stream: GodotString) -> f64 {
+        let stream = stream.to_string();
+        self.dispatch_action(random::drawn(&stream).owned_to_variant());
+
+        random::value(&slice(&self.readable_state(), random::SLICE), &stream)
+    }
+
+    /// Draws the next random whole number of a stream, from one number up to
+    /// and including another, the same way as `next_random`.
+    ///
+    /// # Arguments
+    ///
+    /// * `stream` - The name of the stream.
+    /// * `from` - The smallest number that can be drawn.
+    /// * `to` - The largest number that can be drawn.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _on_chest_opened():
+    ///     var gold = store.next_random_int("loot", 10, 50)
+    /// ```
+    #[export]
+    fn next_random_int(&self, owner: &Object, stream: GodotString, from: i64, to: i64) -> i64 {
+        if to < from {
+            godot_error!(
+                "Unable to draw a random number since {} is smaller than {}",
+                to,
+                from
+            );
+            return from;
+        }
+
+        let value = self.next_random(owner, stream);
+        let count = to as f64 - from as f64 + 1.0;
+
+        // The value is below 1, so this only reaches `to` and never goes
+        // past it.
+        from + (value * count).floor() as i64
+    }
+
+    /// Updates the `random` slice for `seed_random` and `random_drawn`
+    /// actions.
+    ///
+    /// # Arguments
+    ///
+    /// * `action` - The action that was dispatched.
+    /// * `new_state` - The state returned by the reducer.
+    fn reduce_random(&self, action: &Variant, new_state: Dictionary) -> Dictionary {
+        let current = slice(&self.state.borrow(), random::SLICE);
+
+        match random::reduce(&current, action) {
+            None if !self.state.borrow().contains(random::SLICE) => new_state,
+            next => keep_slice(new_state, random::SLICE, next, current),
+        }
+    }
+
+    /// Returns whether an action is gated behind a feature flag that is
+    /// disabled.
+    ///
+    /// # Arguments
+    ///
+    /// * `action` - The action that was dispatched.
+    fn is_gated(&self, action: &Variant) -> bool {
+        let action_type = action_type(action);
+        let slice = slice(&self.readable_state(), feature_flag::SLICE);
+
+        self.flag_gates
+            .borrow()
+            .iter()
+            .any(|(gated, flag)| *gated == action_type && !feature_flag::is_enabled(&slice, flag))
+    }
+
+    /// Dispatches a `TICK` action at a fixed rate no matter how fast the game
+    /// is rendering, so deterministic simulations can be written entirely as
+    /// reducers that handle `TICK`. The time that passes on the store's clock
+    /// is added up every frame of the tree the store is attached to, or every
+    /// simulated frame, and a tick is dispatched for every `fixed_delta` of it,
+    /// with the seconds it simulates under `delta` and its number, starting
+    /// from 1, under `tick`. At most 8 ticks are dispatched in a frame so a
+    /// long frame slows the simulation down instead of making it fall behind.
+    /// Ticks can be frozen while the tree is paused with
+    /// `set_action_pause_mode("TICK", "stop")`.
+    ///
+    /// # Arguments
+    ///
+    /// * `fixed_delta` - The seconds each tick simulates, or 0 to stop ticking.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _ready():
+    ///     store.attach_to_tree(get_tree())
+    ///     store.enable_tick(1.0 / 30.0)
+    ///
+    /// func reducer(state, action):
+    ///     match action.type:
+    ///         'TICK':
+    ///             return { "position": state.position + state.velocity * action.delta }
+    /// ```
+    #[export]
+    fn enable_tick(&self, _owner: &Object, fixed_delta: f64) {
+        if fixed_delta < 0.0 {
+            godot_error!("The fixed delta can't be negative, got {}", fixed_delta);
+            return;
+        }
+
+        *self.tick.borrow_mut() = if fixed_delta > 0.0 {
+            Some(Tick::new(fixed_delta))
+        } else {
+            None
+        };
+    }
+
+    /// Dispatches the `TICK` actions that are due by the current frame.
+    fn dispatch_due_ticks(&self) {
+        if self.tick.borrow().is_none() {
+            return;
+        }
+
+        let now = self.now();
+        let frozen = self.is_paused()
+            && self
+                .pause_modes
+                .borrow()
+                .stops_action(&Variant::from_str(tick::TICK));
+        let due = match self.tick.borrow_mut().as_mut() {
+            Some(tick) => tick.due(now, frozen),
+            None => return,
+        };
+
+        for action in due {
+            self.dispatch_action(action.owned_to_variant());
+        }
+    }
+
+    /// Updates the `input_recording` slice for input recording actions and
+    /// records `input` actions while recording.
+    ///
+    /// # Arguments
+    ///
+    /// * `action` - The action that was dispatched.
+    /// * `new_state` - The state returned by the reducer.
+    fn reduce_input_recording(&self, action: &Variant, new_state: Dictionary) -> Dictionary {
+        let current = slice(&self.state.borrow(), input_recording::SLICE);
+
+        match input_recording::reduce(&current, action) {
+            None if !self.state.borrow().contains(input_recording::SLICE) => new_state,
+            next => keep_slice(new_state, input_recording::SLICE, next, current),
+        }
+    }
+
+    /// Dispatches an `input` action for every mapped input action that an
+    /// input event presses or releases, with the name of the input action
+    /// under `action`, whether it was pressed under `pressed`, and the frame it
+    /// happened on under `frame`. This is meant to be called from
+    /// `_unhandled_input` so the game reacts to input through the store, which
+    /// is what lets recorded input be replayed.
+    ///
+    /// # Arguments
+    ///
+    /// * `event` - The input event.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _unhandled_input(event):
+    ///     store.dispatch_input(event)
+    ///
+    /// func reducer(state, action):
+    ///     if action.type == "input" and action.action == "jump" and action.pressed:
+    ///         return { "jumping": true }
+    ///     return state
+    /// ```
+    #[export]
+    fn dispatch_input(&self, _owner: &Object, event: Ref<InputEvent, Shared>) {
+        let event = unsafe { event.assume_safe() };
+        let (actions, frame) = match self.input_recording.borrow().as_ref() {
+            Some(recording) => (
+                recording.actions.clone(),
+                self.frame.get() - recording.started_at,
+            ),
+            None => (vec![], self.frame.get()),
+        };
+
+        for (name, pressed) in input_recording::mapped_actions(event, &actions) {
+            let action = input_recording::input(&name, pressed, frame, false);
+            self.dispatch_action(action.owned_to_variant());
+        }
+    }
+
+    /// Starts recording the `input` actions dispatched with `dispatch_input`
+    /// into the `input_recording` slice, which has whether input is being
+    /// recorded under `recording`, whether it's being replayed under
+    /// `replaying`, and the recorded events under `events`. The frame of each
+    /// event is counted from the start of the recording. Starting a new
+    /// recording throws away the previous one.
+    ///
+    /// # Arguments
+    ///
+    /// * `actions` - Optional names of the input actions to record. Every
+    /// mapped input action is recorded if this is left out.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _on_level_started():
+    ///     store.start_input_recording(["move_left", "move_right", "jump"])
+    /// ```
+    #[export]
+    fn start_input_recording(&self, _owner: &Object, #[opt] actions: VariantArray) {
+        *self.input_recording.borrow_mut() = Some(Recording {
+            actions: actions.iter().map(|action| action.to_string()).collect(),
+            started_at: self.frame.get(),
+        });
+
+        self.dispatch_action(input_recording::recording_started().owned_to_variant());
+    }
+
+    /// Stops recording input and returns the recorded events, which can be
+    /// passed to `replay_input` later.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Optional file to save the recorded events to.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _on_level_finished():
+    ///     store.stop_input_recording("user://ghosts/level_1.json")
+    /// ```
+    #[export]
+    fn stop_input_recording(&self, _owner: &Object, #[opt] path: GodotString) -> VariantArray {
+        if self.input_recording.replace(None).is_none() {
+            godot_error!("Unable to stop recording input since it isn't being recorded");
+            return VariantArray::new_shared();
+        }
+
+        self.dispatch_action(input_recording::recording_stopped().owned_to_variant());
+
+        let events = input_recording::events(&slice(&self.state.borrow(), input_recording::SLICE));
+        if !path.is_empty() {
+            let text = snapshot::to_stable_text(&Variant::from_array(&events));
+            if let Err(message) = file::write_text(&path.to_string(), &text) {
+                godot_error!("Unable to save the recorded input: {}", message);
+            }
+        }
+
+        events
+    }
+
+    /// Replays recorded input by dispatching each recorded `input` action again
+    /// on the frame it was recorded on, counted from the start of the replay.
+    /// Replayed actions have `replayed` set to `true` so the reducer can tell
+    /// them apart from live input, for example to drive a ghost. The
+    /// `input_replay_finished` signal is emitted once every event has been
+    /// dispatched.
+    ///
+    /// # Arguments
+    ///
+    /// * `events` - The recorded events, or the path of a file they were saved
+    /// to.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _on_attract_mode_started():
+    ///     store.replay_input("res://demos/level_1.json")
+    ///     store.connect("input_replay_finished", self, "_on_attract_mode_started", [], CONNECT_ONESHOT)
+    /// ```
+    #[export]
+    fn replay_input(&self, _owner: &Object, events: Variant) {
+        let events = match events.get_type() {
+            VariantType::VariantArray => Ok(events.to_array()),
+            VariantType::GodotString => {
+                let path = events.to_string();
+                file::read_json(&path).and_then(|events| {
+                    events
+                        .try_to_array()
+                        .ok_or_else(|| format!("The recorded input in {} isn't an Array", path))
+                })
+            }
+            _ => Err(format!(
+                "Input can only be replayed from an Array of events or the path of a file, got {}",
+                events.to_string()
+            )),
+        };
+
+        let replay = match events.and_then(|events| Replay::new(&events, self.frame.get())) {
+            Ok(replay) => replay,
+            Err(message) => {
+                godot_error!("{}", message);
+                return;
+            }
+        };
+
+        *self.input_replay.borrow_mut() = Some(replay);
+        self.dispatch_action(input_recording::replay_started().owned_to_variant());
+    }
+
+    /// Stops replaying input without dispatching the rest of the replay. The
+    /// `input_replay_finished` signal isn't emitted for a stopped replay.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _unhandled_input(event):
+    ///     if event.is_pressed():
+    ///         store.stop_input_replay()
+    /// ```
+    #[export]
+    fn stop_input_replay(&self, _owner: &Object) {
+        if self.input_replay.borrow().is_some() {
+            self.end_input_replay();
+        }
+    }
+
+    /// Drops the input replay and dispatches `input_replay_finished`.
+    fn end_input_replay(&self) {
+        *self.input_replay.borrow_mut() = None;
+        self.dispatch_action(input_recording::replay_finished().owned_to_variant());
+    }
+
+    /// Adds a signal to the store that is emitted with the payload of an action
+    /// whenever an action of its type passes the reducer, so systems that are
+    /// already driven by signals can react to what happens in the store
+    /// without subscribing to the whole state. The signal is emitted once the
+    /// state has been updated, so its handlers see the new state, and it
+    /// isn't emitted while running a scenario. The payload is the action
+    /// Dictionary, or an empty Dictionary for an action that is just its
+    /// type. An action type can emit more than one signal.
+    ///
+    /// # Arguments
+    ///
+    /// * `action_type` - The type of the actions that emit the signal.
+    /// * `signal_name` - The name of the signal.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _ready():
+    ///     store.emit_signal_for_action(Action.ENEMY_KILLED, "enemy_killed")
+    ///     store.connect("enemy_killed", $Announcer, "_on_enemy_killed")
+    /// ```
+    #[export]
+    fn emit_signal_for_action(
+        &self,
+        owner: &Object,
+        action_type: Variant,
+        signal_name: GodotString,
+    ) {
+        if signal_name.is_empty() {
+            godot_error!("Unable to emit a signal for the action: the signal needs a name");
+            return;
+        }
+
+        if !owner.has_signal(signal_name.clone()) {
+            let argument = Dictionary::new();
+            argument.insert("name", "payload");
+            argument.insert("type", VariantType::Dictionary as i64);
+            let arguments = VariantArray::new();
+            arguments.push(argument.into_shared());
+            owner.add_user_signal(signal_name.clone(), arguments.into_shared());
+        }
+
+        let mut action_signals = self.action_signals.borrow_mut();
+        if !action_signals
+            .iter()
+            .any(|(signal_type, signal)| *signal_type == action_type && *signal == signal_name)
+        {
+            action_signals.push((action_type, signal_name));
+        }
+    }
+
+    /// Queues the signals added with `emit_signal_for_action` for the type of
+    /// an action that passed the reducer.
+    ///
+    /// # Arguments
+    ///
+    /// * `action` - The action that was dispatched.
+    fn queue_action_signals(&self, action: &Variant) {
+        if self.action_signals.borrow().is_empty() {
+            return;
+        }
+
+        let action_type = action_type(action);
+        let payload = action
+            .try_to_dictionary()
+            .unwrap_or_else(Dictionary::new_shared);
+        let signals = self
+            .action_signals
+            .borrow()
+            .iter()
+            .filter(|(signal_type, _)| *signal_type == action_type)
+            .map(|(_, signal)| signal.clone())
+            .collect::<Vec<_>>();
+        for signal in signals {
+            self.emit_after_update(signal, vec![Variant::from_dictionary(&payload)]);
+        }
+    }
+
+    /// Returns whether anything is connected to the `state_changed` signal, so
+    /// the state is only compared with the one before it when the signal is
+    /// going to be emitted.
+    fn state_changed_is_connected(&self) -> bool {
+        if self.running_scenario.get() || self.owner_was_freed() {
+            return false;
+        }
+
+        !unsafe { self.owner.assume_safe() }
+            .get_signal_connection_list("state_changed")
+            .is_empty()
+    }
+
+    /// Queues a signal to be emitted once the state has been updated, so the
+    /// signal's handlers see the new state.
+    ///
+    /// # Arguments
+    ///
+    /// * `signal` - The name of the signal.
+    /// * `args` - The arguments of the signal.
+    fn emit_after_update(&self, signal: impl Into<GodotString>, args: Vec<Variant>) {
+        self.pending_signals
+            .borrow_mut()
+            .push((signal.into(), args));
+    }
+
+    /// Emits the signals queued while the state was being updated. Signals
+    /// aren't emitted while running a scenario.
+    fn emit_pending_signals(&self) {
+        let pending_signals = self.pending_signals.replace(vec![]);
+        if self.running_scenario.get() {
+            return;
+        }
+
+        for (signal, args) in pending_signals {
+            if self.owner_was_freed() {
+                return;
+            }
+            unsafe { self.owner.assume_safe() }.emit_signal(signal, &args);
+        }
+    }
+
+    /// Returns whether the store's owner was freed, which can happen when a
+    /// subscriber, signal handler, or effect frees the store in the middle of
+    /// a dispatch. The store is torn down if it was so that the dispatches
+    /// still queued or timed are cancelled instead of calling into the freed
+    /// owner.
+    fn owner_was_freed(&self) -> bool {
+        if unsafe { Object::try_from_instance_id(self.owner_id) }.is_some() {
+            return false;
+        }
+
+        self.tear_down();
+        true
+    }
+
+    /// Runs the subscriptions for the store.
+    fn dispatch_subscriptions(&self) {
+        if self.running_scenario.get() {
+            return;
+        }
+        if self.batch_depth.get() > 0 {
+            self.batch_changed.set(true);
+            return;
+        }
+
+        let state = self.readable_state();
+        let args = &[Variant::from_dictionary(&state)];
+
+        // Subscribers whose instance was freed are unsubscribed.
+        self.drop_freed_subscriptions();
+
+        // The subscriptions are copied so that a subscription can subscribe,
+        // unsubscribe, or dispatch without invalidating the list being iterated
+        // over. Each one is checked again before it's run since an earlier one
+        // could unsubscribe it, free its instance, or free the store itself.
+        let subscriptions = self.subscriptions.borrow().clone();
+        for (id, subscriber) in &subscriptions {
+            if self.owner_was_freed() {
+                return;
+            }
+            if !self.is_subscribed(*id) || !subscriber.is_alive() {
+                continue;
+            }
+            if let Err(message) = self.timed_call(subscriber, || subscriber.call(args)) {
+                self.report_subscriber_error(subscriber, &message);
+            }
+        }
+
+        let selector_subscriptions = self.selector_subscriptions.borrow().clone();
+        for (id, subscription) in &selector_subscriptions {
+            if self.owner_was_freed() {
+                return;
+            }
+            if !self.is_subscribed(*id) || !subscription.is_alive() {
+                continue;
+            }
+
+            let selected = self.timed_call(subscription.selector(), || subscription.select(&state));
+            let value = match selected {
+                Ok(Some(value)) => value,
+                Ok(None) => continue,
+                Err(message) => {
+                    self.report_subscriber_error(subscription.selector(), &message);
+                    continue;
+                }
+            };
+
+            // The subscription may have been removed by its own selector.
+            let update = self
+                .selector_subscriptions
+                .borrow_mut()
+                .iter_mut()
+                .find(|(subscribed, _)| subscribed == id)
+                .map(|(_, subscription)| subscription.update(value));
+            if let Some((callback, args)) = update {
+                if let Err(message) = self.timed_call(&callback, || callback.call(&args)) {
+                    self.report_subscriber_error(&callback, &message);
+                }
+            }
+        }
+
+        let path_subscriptions = self.path_subscriptions.borrow().clone();
+        for (id, subscription) in &path_subscriptions {
+            if self.owner_was_freed() {
+                return;
+            }
+            if !self.is_subscribed(*id) || !subscription.is_alive() {
+                continue;
+            }
+
+            let value = match subscription.select(&state) {
+                Some(value) => value,
+                None => continue,
+            };
+
+            // The subscription may have been removed by a callback before it.
+            let update = self
+                .path_subscriptions
+                .borrow_mut()
+                .iter_mut()
+                .find(|(subscribed, _)| subscribed == id)
+                .map(|(_, subscription)| subscription.update(value));
+            if let Some((callback, args)) = update {
+                if let Err(message) = self.timed_call(&callback, || callback.call(&args)) {
+                    self.report_subscriber_error(&callback, &message);
+                }
+            }
+        }
+    }
+
+    /// Runs a call to a subscriber and adds how long it took to the times of
+    /// the subscriber, warning if it went over the subscriber budget.
+    ///
+    /// # Arguments
+    ///
+    /// * `subscriber` - The subscriber being called.
+    /// * `call` - The call.
+    fn timed_call<T>(&self, subscriber: &Subscriber, call: impl FnOnce() -> T) -> T {
+        let started = clock::real_now();
+        let result = call();
+        let usec = ((clock::real_now() - started) * 1_000_000.0) as i64;
+
+        let over = self
+            .call_budget
+            .borrow_mut()
+            .record(subscriber.identity(), usec);
+        if let Some(budget) = over {
+            godot_warn!(
+                "The subscriber {} took {:.3} ms, which is over the budget of {:.3} ms",
+                subscriber.identity(),
+                usec as f64 / 1000.0,
+                budget as f64 / 1000.0
+            );
+        }
+
+        result
+    }
+
+    /// Warns that a subscriber couldn't be called and emits the
+    /// `subscriber_error` signal, so one bad subscriber doesn't stop the ones
+    /// after it from being called.
+    ///
+    /// # Arguments
+    ///
+    /// * `subscriber` - The subscriber that couldn't be called.
+    /// * `message` - Why it couldn't be called.
+    fn report_subscriber_error(&self, subscriber: &Subscriber, message: &str) {
+        godot_warn!(
+            "Unable to call the subscriber {}: {}",
+            subscriber.identity(),
+            message
+        );
+        self.pipeline_failed("subscriber", &Variant::new(), message);
+        if self.owner_was_freed() {
+            return;
+        }
+
+        let owner = unsafe { self.owner.assume_safe() };
+        owner.emit_signal(
+            "subscriber_error",
+            &[
+                Variant::from_str(subscriber.identity()),
+                Variant::from_str(message),
+            ],
+        );
+    }
+
+    /// Subscribes to changes to the state. When a change to the state is made, the
+    /// callback function is run and passed the current state as an argument.
+    ///
+    /// The instance is only referenced weakly, so once it's freed the
+    /// subscriber is unsubscribed. Use `subscribe_strong` to keep it alive. If
+    /// a subscriber can't be called, for example because its instance doesn't
+    /// have the callback function, a warning is printed and the
+    /// `subscriber_error` signal is emitted with the subscriber and the error,
+    /// and the subscribers after it are still called. Returns the id of the
+    /// subscription, which can be passed to `unsubscribe`.
+    ///
+    /// Nodes can also connect to the `state_changed` signal instead, which is
+    /// emitted with the new state and the action whenever an action changes
+    /// the state.
+    ///
+    /// # Arguments
+    ///
+    /// * `callback_fn_instance` - The instance that contains the callback function.
+    /// * `param callback_fn_name` - The name of the callback function.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// const state = {
+    ///     "counter": 0,
+    /// }
+    ///
+    /// enum Action {
+    ///     INCREMENT,
+    ///     DECREMENT,
+    /// }
+    ///
+    /// func reducer(state, action):
+    ///     match action:
+    ///         Action.INCREMENT:
+    ///             return {
+    ///                 "counter": state.counter + 1,
+    ///             }
+    ///         Action.DECREMENT:
+    ///             return {
+    ///                 "counter": state.counter - 1,
+    ///             }
+    ///
+    /// func _ready():
+    ///     var store = Store.new()
+    ///     store.set_state_and_reducer(initial_state, self, 'reducer')
+    ///     store.subscribe(self, 'print_counter')
+    ///
+    /// func print_counter(state):
+    ///     print(state.counter)
+    /// ```
+    #[export]
+    fn subscribe(
+        &self,
+        _owner: &Object,
+        subscriber_fn_instance: Ref<Object, Shared>,
+        subscriber_fn_name: GodotString,
+    ) -> i64 {
+        let id = self.next_subscription_id();
+        self.subscriptions.borrow_mut().push((
+            id,
+            Subscriber::new(subscriber_fn_instance, subscriber_fn_name),
+        ));
+
+        id
+    }
+
+    /// Subscribes to changes to the state like `subscribe`, but keeps the
+    /// instance alive for as long as the subscription lasts if it's a
+    /// Reference, for example a helper object that nothing else holds on to.
+    /// Nodes are freed manually so they can't be kept alive. Returns the id of
+    /// the subscription, which can be passed to `unsubscribe`.
+    ///
+    /// # Arguments
+    ///
+    /// * `callback_fn_instance` - The instance that contains the callback function.
+    /// * `callback_fn_name` - The name of the callback function.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _ready():
+    ///     store.subscribe_strong(AutosaveWriter.new(), 'write')
+    /// ```
+    #[export]
+    fn subscribe_strong(
+        &self,
+        _owner: &Object,
+        subscriber_fn_instance: Ref<Object, Shared>,
+        subscriber_fn_name: GodotString,
+    ) -> i64 {
+        let id = self.next_subscription_id();
+        self.subscriptions.borrow_mut().push((
+            id,
+            Subscriber::strong(subscriber_fn_instance, subscriber_fn_name),
+        ));
+
+        id
+    }
+
+    /// Subscribes to a value selected from the state. When a change to the state
+    /// is made, the selector function is passed the state and the callback
+    /// function is run only if the value it returns isn't equal to the last one,
+    /// and is passed the new and the old value.
+    ///
+    /// The equality used to compare them can be:
+    ///
+    /// * `reference` - Dictionaries and Arrays are only equal if they are the
+    ///   same one, which is the cheapest and the default.
+    /// * `shallow` - Dictionaries and Arrays are equal if their values are equal
+    ///   by reference.
+    /// * `deep` - Dictionaries and Arrays are equal if their values are equal,
+    ///   however deeply they are nested.
+    /// * A FuncRef that is passed the old and the new value and returns whether
+    ///   they are equal.
+    ///
+    /// Returns the id of the subscription, which can be passed to
+    /// `unsubscribe`, or 0 if the equality isn't valid.
+    ///
+    /// # Arguments
+    ///
+    /// * `selector_fn_instance` - The instance that contains the selector function.
+    /// * `selector_fn_name` - The name of the selector function.
+    /// * `callback_fn_instance` - The instance that contains the callback function.
+    /// * `callback_fn_name` - The name of the callback function.
+    /// * `equality` - Optional name of the equality or a FuncRef to compare with.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _ready():
+    ///     store.subscribe_selector(self, 'select_inventory', self, 'on_inventory_changed', 'shallow')
+    ///
+    /// func select_inventory(state):
+    ///     return state.inventory
+    ///
+    /// func on_inventory_changed(inventory, old_inventory):
+    ///     print(inventory)
+    /// ```
+    #[export]
+    fn subscribe_selector(
+        &self,
+        _owner: &Object,
+        selector_fn_instance: Ref<Object, Shared>,
+        selector_fn_name: GodotString,
+        callback_fn_instance: Ref<Object, Shared>,
+        callback_fn_name: GodotString,
+        #[opt] equality: Variant,
+    ) -> i64 {
+        let equality = match Equality::from_variant(&equality) {
+            Ok(equality) => equality,
+            Err(message) => {
+                godot_error!("Unable to subscribe to the selector: {}", message);
+                return 0;
+            }
+        };
+
+        let subscription = SelectorSubscription::new(
+            Subscriber::new(selector_fn_instance, selector_fn_name),
+            Subscriber::new(callback_fn_instance, callback_fn_name),
+            equality,
+            &self.readable_state(),
+        );
+        let id = self.next_subscription_id();
+        self.selector_subscriptions
+            .borrow_mut()
+            .push((id, subscription));
+
+        id
+    }
+
+    /// Subscribes to the value at a path of the state, like `player/hp`. When
+    /// a change to the state is made, the callback function is run only if
+    /// the value at the path changed, and is passed the new and the old
+    /// value, which is `null` if the path wasn't in the state. Values are
+    /// compared however deeply they are nested, so a Dictionary that was
+    /// copied without changing anything in it doesn't count as a change.
+    /// Returns the id of the subscription, which can be passed to
+    /// `unsubscribe`.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the value.
+    /// * `callback_fn_instance` - The instance that contains the callback function.
+    /// * `callback_fn_name` - The name of the callback function.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _ready():
+    ///     store.subscribe_to_path("player/hp", self, "on_hp_changed")
+    ///
+    /// func on_hp_changed(hp, old_hp):
+    ///     $HpBar.value = hp
+    /// ```
+    #[export]
+    fn subscribe_to_path(
+        &self,
+        _owner: &Object,
+        path: GodotString,
+        callback_fn_instance: Ref<Object, Shared>,
+        callback_fn_name: GodotString,
+    ) -> i64 {
+        let subscription = PathSubscription::new(
+            path.to_string(),
+            Subscriber::new(callback_fn_instance, callback_fn_name),
+            &self.readable_state(),
+        );
+        let id = self.next_subscription_id();
+        self.path_subscriptions
+            .borrow_mut()
+            .push((id, subscription));
+
+        id
+    }
+
+    /// Removes a subscription added with `subscribe`, `subscribe_strong`,
+    /// `subscribe_selector`, or `subscribe_to_path`. A subscription removed
+    /// while the subscriptions are running isn't run anymore. Returns whether
+    /// there was a subscription with the id.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The id of the subscription.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func open():
+    ///     subscription = store.subscribe(self, 'on_state_changed')
+    ///
+    /// func close():
+    ///     store.unsubscribe(subscription)
+    /// ```
+    #[export]
+    fn unsubscribe(&self, _owner: &Object, id: i64) -> bool {
+        let subscribed = self.is_subscribed(id);
+
+        self.subscriptions
+            .borrow_mut()
+            .retain(|(subscribed, _)| *subscribed != id);
+        self.selector_subscriptions
+            .borrow_mut()
+            .retain(|(subscribed, _)| *subscribed != id);
+        self.path_subscriptions
+            .borrow_mut()
+            .retain(|(subscribed, _)| *subscribed != id);
+
+        subscribed
+    }
+
+    /// Removes every subscription whose function, or selector function, is on
+    /// an instance. Returns the number of subscriptions removed.
+    ///
+    /// # Arguments
+    ///
+    /// * `instance` - The instance to unsubscribe.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func close():
+    ///     store.unsubscribe_all(self)
+    /// ```
+    #[export]
+    fn unsubscribe_all(&self, _owner: &Object, instance: Ref<Object, Shared>) -> i64 {
+        let instance_id = unsafe { instance.assume_safe() }.get_instance_id();
+
+        let mut subscriptions = self.subscriptions.borrow_mut();
+        let mut selector_subscriptions = self.selector_subscriptions.borrow_mut();
+        let mut path_subscriptions = self.path_subscriptions.borrow_mut();
+        let count = subscriptions.len() + selector_subscriptions.len() + path_subscriptions.len();
+
+        subscriptions.retain(|(_, subscriber)| !subscriber.belongs_to(instance_id));
+        selector_subscriptions.retain(|(_, subscription)| !subscription.belongs_to(instance_id));
+        path_subscriptions.retain(|(_, subscription)| !subscription.belongs_to(instance_id));
+
+        (count - subscriptions.len() - selector_subscriptions.len() - path_subscriptions.len())
+            as i64
+    }
+
+    /// Returns whether there is a subscription with an id.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The id of the subscription.
+    fn is_subscribed(&self, id: i64) -> bool {
+        self.subscriptions
+            .borrow()
+            .iter()
+            .any(|(subscribed, _)| *subscribed == id)
+            || self
+                .selector_subscriptions
+                .borrow()
+                .iter()
+                .any(|(subscribed, _)| *subscribed == id)
+            || self
+                .path_subscriptions
+                .borrow()
+                .iter()
+                .any(|(subscribed, _)| *subscribed == id)
+    }
+
+    /// Returns the id for a new subscription.
+    fn next_subscription_id(&self) -> i64 {
+        let id = self.next_subscription_id.get();
+        self.next_subscription_id.set(id + 1);
+
+        id
+    }
+
+    /// Adds a middleware function that can intercept a dispatch and modify the action
+    /// to be run before it reaches the reducer. Returns the id of the middleware,
+    /// which can be passed to `remove_middleware`.
+    ///
+    /// # Arguments
+    ///
+    /// * `middleware_fn_instance` - The instance that contains the middleware function.
+    /// * `middleware_fn_name` - The name of the middleware function.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func reverse_middleware(state, action):
+    ///     match action {
+    ///         Action.INCREMENT:
+    ///             return Action.DECREMENT
+    ///         Action.DECREMENT:
+    ///             return Action.INCREMENT
+    ///
+    /// func _ready():
+    ///     var store = Store.new(state, self, 'reducer')
+    ///     store.add_middleware(self, 'reverse_middleware')
+    ///
+    ///     # This will actually run the `DECREMENT` action because of our middleware.
+    ///     store.dispatch(Action.INCREMENT)
+    /// ```
+    #[export]
+    fn add_middleware(
+        &self,
+        owner: &Object,
+        middleware_fn_instance: Ref<Object, Shared>,
+        middleware_fn_name: GodotString,
+    ) -> i64 {
+        self.push_middleware(
+            owner,
+            middleware_fn_instance,
+            middleware_fn_name,
+            Convention::Plain,
+        )
+    }
+
+    /// Adds a middleware function like `add_middleware` that is also passed the
+    /// context of the dispatch, a Dictionary that starts out with just the
+    /// `correlation_id` of the dispatch and is shared by all of the middleware
+    /// that handle it. Earlier
+    /// middleware can write to it and later middleware can read from it, for
+    /// example to pass along who sent the action or a correlation id, without
+    /// adding them to the action itself. Returns the id of the middleware, which
+    /// can be passed to `remove_middleware`.
+    ///
+    /// # Arguments
+    ///
+    /// * `middleware_fn_instance` - The instance that contains the middleware function.
+    /// * `middleware_fn_name` - The name of the middleware function, which is
+    /// passed the state, the action, and the context.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func auth_middleware(state, action, context):
+    ///     context.user = state.session.user
+    ///     return action
+    ///
+    /// func audit_middleware(state, action, context):
+    ///     print("%s dispatched %s" % [context.user, action])
+    ///     return action
+    ///
+    /// func _ready():
+    ///     store.add_context_middleware(self, 'auth_middleware')
+    ///     store.add_context_middleware(self, 'audit_middleware')
+    /// ```
+    #[export]
+    fn add_context_middleware(
+        &self,
+        owner: &Object,
+        middleware_fn_instance: Ref<Object, Shared>,
+        middleware_fn_name: GodotString,
+    ) -> i64 {
+        self.push_middleware(
+            owner,
+            middleware_fn_instance,
+            middleware_fn_name,
+            Convention::Context,
+        )
+    }
+
+    /// Adds a middleware function like `add_middleware` that is also passed the
+    /// store, so it can dispatch other actions, like a thunk that dispatches
+    /// the start of a request, waits for it, and then dispatches its result.
+    /// The actions it dispatches go through all of the middleware like any
+    /// other action. Returning `null` stops the action it was passed, and so
+    /// does yielding, so a function that waits on a signal or a timer should
+    /// return `null` or dispatch what it needs once it's done. Returns the id
+    /// of the middleware, which can be passed to `remove_middleware`.
+    ///
+    /// # Arguments
+    ///
+    /// * `middleware_fn_instance` - The instance that contains the middleware function.
+    /// * `middleware_fn_name` - The name of the middleware function, which is
+    /// passed the state, the action, and the store.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func fetch_middleware(state, action, store):
+    ///     if not action is Dictionary or action.type != Action.FETCH_SCORES:
+    ///         return action
+    ///
+    ///     store.dispatch({ "type": Action.SCORES_LOADING })
+    ///     $HTTPRequest.request(SCORES_URL)
+    ///     var response = yield($HTTPRequest, "request_completed")
+    ///     store.dispatch({ "type": Action.SCORES_LOADED, "body": response[3] })
+    ///
+    /// func _ready():
+    ///     store.add_thunk_middleware(self, 'fetch_middleware')
+    /// ```
+    #[export]
+    fn add_thunk_middleware(
+        &self,
+        owner: &Object,
+        middleware_fn_instance: Ref<Object, Shared>,
+        middleware_fn_name: GodotString,
+    ) -> i64 {
+        self.push_middleware(
+            owner,
+            middleware_fn_instance,
+            middleware_fn_name,
+            Convention::Store,
+        )
+    }
+
+    /// Adds a middleware function that is passed a `next` function instead of
+    /// returning the action to pass on, like middleware in Redux. Calling
+    /// `next.call_func(action)` runs the rest of the middleware and the
+    /// reducer and returns what they returned, so the middleware can do work
+    /// after they ran, like logging the new state. Whatever the middleware
+    /// returns is passed back to the middleware before it, or returned by
+    /// `dispatch`, so it can also stop the action and return a result of its
+    /// own without calling `next`. `next` can only be called once, but it can
+    /// be called after the middleware yielded. Returns the id of the
+    /// middleware, which can be passed to `remove_middleware`.
+    ///
+    /// # Arguments
+    ///
+    /// * `middleware_fn_instance` - The instance that contains the middleware function.
+    /// * `middleware_fn_name` - The name of the middleware function, which is
+    /// passed the state, the action, and the `next` function.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func timing_middleware(state, action, next):
+    ///     var started = OS.get_ticks_usec()
+    ///     var result = next.call_func(action)
+    ///     print("%s took %d usec" % [action, OS.get_ticks_usec() - started])
+    ///     return result
+    ///
+    /// func _ready():
+    ///     store.add_next_middleware(self, 'timing_middleware')
+    /// ```
+    #[export]
+    fn add_next_middleware(
+        &self,
+        owner: &Object,
+        middleware_fn_instance: Ref<Object, Shared>,
+        middleware_fn_name: GodotString,
+    ) -> i64 {
+        self.push_middleware(
+            owner,
+            middleware_fn_instance,
+            middleware_fn_name,
+            Convention::Next,
+        )
+    }
+
+    /// Adds a middleware function, emits `middleware_added`, and returns the id
+    /// of the middleware.
+    ///
+    /// # Arguments
+    ///
+    /// * `owner` - The store, which emits the signal.
+    /// * `instance` - The instance on which the middleware function exists.
+    /// * `function` - The name of the middleware function.
+    /// * `convention` - What the function is passed along with the state and
+    /// the action.
+    fn push_middleware(
+        &self,
+        owner: &Object,
+        instance: Ref<Object, Shared>,
+        function: GodotString,
+        convention: Convention,
+    ) -> i64 {
+        let identity = subscriber::identity(&unsafe { instance.assume_safe() }, &function);
+        let id = self.next_middleware_id.get();
+        self.next_middleware_id.set(id + 1);
+        self.middleware.borrow_mut().push(
+            id,
+            Middleware {
+                function: func_ref(instance, function),
+                convention,
+                side_effects: false,
+                identity: identity.clone(),
+            },
+        );
+
+        owner.emit_signal("middleware_added", &[Variant::from_str(identity)]);
+
+        id
+    }
+
+    /// Removes a middleware function added with `add_middleware`,
+    /// `add_context_middleware`, `add_thunk_middleware`, or
+    /// `add_next_middleware`, like a debug logger that is only needed for a
+    /// while. Removing middleware while an action is being dispatched is safe,
+    /// and the action skips the middleware if it hasn't reached it yet.
+    /// Returns whether there was middleware with the id.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The id of the middleware.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func enable_logging(enabled):
+    ///     if enabled:
+    ///         logger = store.add_middleware(self, 'log_middleware')
+    ///     else:
+    ///         store.remove_middleware(logger)
+    /// ```
+    #[export]
+    fn remove_middleware(&self, _owner: &Object, id: i64) -> bool {
+        self.middleware.borrow_mut().remove(id)
+    }
+
+    /// Marks middleware as having side effects, like making HTTP requests,
+    /// sending analytics, or playing sounds, so it's skipped when actions
+    /// aren't really happening: while running a scenario with `run_scenario`,
+    /// previewing an action with `preview`, and dispatching the input of an
+    /// input replay. Middleware without side effects, like middleware that
+    /// changes actions, still runs so actions are changed the same way. The
+    /// time travel history and the event log are replayed straight through
+    /// the reducer since they keep the actions that reached it, and actions
+    /// dispatched while they're replayed skip middleware with side effects
+    /// too. Returns whether there was middleware with the id.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The id of the middleware.
+    /// * `side_effects` - Whether the middleware has side effects.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _ready():
+    ///     var analytics = store.add_middleware(self, 'analytics_middleware')
+    ///     store.set_middleware_side_effects(analytics, true)
+    /// ```
+    #[export]
+    fn set_middleware_side_effects(&self, _owner: &Object, id: i64, side_effects: bool) -> bool {
+        match self.middleware.borrow_mut().get_mut(id) {
+            Some(middleware) => {
+                middleware.side_effects = side_effects;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Removes every middleware function, leaving the subscriptions as they
+    /// are. An action that is being dispatched goes straight to the reducer
+    /// from the middleware it's in.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _on_cheats_disabled():
+    ///     store.clear_middleware()
+    /// ```
+    #[export]
+    fn clear_middleware(&self, _owner: &Object) {
+        self.middleware.borrow_mut().clear();
+    }
+
+    /// Resets everything the store was set up with apart from the state and
+    /// the reducers, so the store can keep being used with the state it has.
+    ///
+    /// * Removed: the subscriptions, the middleware along with `persist` and
+    /// the logging, and everything registered or loaded, like the codecs,
+    /// migrations, schemas, dedupe rules, cheats, action aliases, effects,
+    /// pipelines, forms, queries, selectors, view models, projections,
+    /// dialogues, quests, state machines, and content packs.
+    /// * Cancelled: the deferred and frozen dispatches, the pending effects,
+    /// the background saves, the input recording and replay, the requests
+    /// for flag overrides, and the actions queued by other threads. The
+    /// DevTools connection is closed and the store is detached from the tree.
+    /// * Set back to their defaults: the error policy, the max dispatch depth,
+    /// the pause modes, the clock, the storage, the save info, the backup
+    /// count, the save encryption, the history memory limit, the subscriber
+    /// budget, and the stats sample rate. Telemetry, session analytics,
+    /// stats, the inventory, the router, and the tick are turned off.
+    /// * Emptied: time travel, undo, event sourcing, the access audit, and the
+    /// test mode stay on if they were, but forget what they recorded, so
+    /// nothing can go back to a state from before the store was cleared.
+    /// * Kept: the state, the reducers and slice reducers, the initial state,
+    /// the protected paths, and the frame count.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _exit_tree():
+    ///     store.clear()
+    /// ```
+    #[export]
+    fn clear(&self, _owner: &Object) {
+        self.tear_down();
+    }
+
+    /// Drops everything the store holds on to that references other objects
+    /// so nothing is left calling into instances that have been freed,
+    /// cancels the requests for flag overrides that haven't completed yet,
+    /// and resets the rest of the setup as described in `clear`.
+    fn tear_down(&self) {
+        self.subscriptions.borrow_mut().clear();
+        self.selector_subscriptions.borrow_mut().clear();
+        self.path_subscriptions.borrow_mut().clear();
+        self.middleware.borrow_mut().clear();
+        *self.persisted.borrow_mut() = None;
+        *self.logger.borrow_mut() = None;
+        self.codecs.borrow_mut().clear();
+        self.migrations.borrow_mut().clear();
+        if let Some(devtools) = self.devtools.replace(None) {
+            devtools.close();
+        }
+        self.recent_actions.borrow_mut().clear();
+        self.content_filters.borrow_mut().clear();
+        self.action_schemas.borrow_mut().clear();
+        *self.state_schema.borrow_mut() = None;
+        self.check_dispatched_state.set(false);
+        self.flag_gates.borrow_mut().clear();
+        self.pipelines.borrow_mut().clear();
+        self.pending_pipeline_runs.borrow_mut().clear();
+        self.dedupe.borrow_mut().clear();
+        *self.error_policy.borrow_mut() = None;
+        self.dispatch_halted.set(false);
+        self.max_dispatch_depth.set(DEFAULT_MAX_DISPATCH_DEPTH);
+        for request_id in self.flag_requests.replace(vec![]) {
+            if let Some(request) = unsafe { HTTPRequest::try_from_instance_id(request_id) } {
+                request.cancel_request();
+                request.queue_free();
+            }
+        }
+        self.action_aliases.borrow_mut().clear();
+        *self.audio.borrow_mut() = None;
+        self.effect_bindings.borrow_mut().clear();
+        self.pending_bound_effects.borrow_mut().clear();
+        *self.clock.borrow_mut() = Clock::default();
+        self.deferred_dispatches.borrow_mut().clear();
+        self.deferred_budget.set(None);
+        if let Some(dispatcher) = self.threadsafe_dispatcher.replace(None) {
+            // The actions still queued by other threads are dropped.
+            let dispatcher = unsafe { dispatcher.assume_safe() };
+            dispatcher.map(|dispatcher, _| dispatcher.take()).ok();
+        }
+        // The time travel history starts over from the current state, so
+        // nothing can jump back to a state from before the store was cleared.
+        if self.history.borrow().is_some() {
+            *self.history.borrow_mut() = Some(History::new(&self.readable_state()));
+        }
+        if let Some(undo_stack) = self.undo_stack.borrow_mut().as_mut() {
+            undo_stack.clear();
+        }
+        if self.event_log.borrow().is_some() {
+            *self.event_log.borrow_mut() = Some(EventLog::new(&self.readable_state()));
+        }
+        if let Some(audit) = self.access_audit.borrow_mut().as_mut() {
+            *audit = AccessAudit::default();
+        }
+        if let Some(log) = self.dispatch_log.borrow_mut().as_mut() {
+            *log = DispatchLog::default();
+        }
+        self.history_memory_limit.set(None);
+        *self.save_info.borrow_mut() = SaveInfo::default();
+        self.background_saves.borrow_mut().clear();
+        self.backup_count.set(0);
+        *self.encryption.borrow_mut() = None;
+        *self.storage.borrow_mut() = Storage::default();
+        self.frozen_dispatches.borrow_mut().clear();
+        *self.pause_modes.borrow_mut() = PauseModes::default();
+        *self.tree.borrow_mut() = None;
+        *self.router.borrow_mut() = None;
+        self.forms.borrow_mut().clear();
+        self.bound_fields.borrow_mut().clear();
+        self.queries.borrow_mut().clear();
+        *self.stats.borrow_mut() = None;
+        self.pending_signals.borrow_mut().clear();
+        self.action_signals.borrow_mut().clear();
+        *self.inventory.borrow_mut() = None;
+        *self.quests.borrow_mut() = None;
+        self.dialogues.borrow_mut().clear();
+        self.machines.borrow_mut().clear();
+        self.pending_effects.borrow_mut().clear();
+        self.readonly_slices.borrow_mut().clear();
+        *self.content_packs.borrow_mut() = ContentPacks::default();
+        self.view_models.borrow_mut().clear();
+        self.memoized_selectors.borrow_mut().clear();
+        self.dirty_slices.borrow_mut().clear();
+        *self.previous_state.borrow_mut() = Dictionary::new_shared();
+        self.batch_depth.set(0);
+        self.batch_changed.set(false);
+        self.macros.borrow_mut().clear();
+        *self.input_recording.borrow_mut() = None;
+        *self.input_replay.borrow_mut() = None;
+        self.last_cooldown_tick.set(None);
+        *self.tick.borrow_mut() = None;
+        *self.preloader.borrow_mut() = Preloader::default();
+        self.cheats.borrow_mut().clear();
+        *self.telemetry.borrow_mut() = None;
+        *self.session_analytics.borrow_mut() = None;
+        *self.call_budget.borrow_mut() = CallBudget::default();
+        self.stats_sample_rate.set(1);
+        self.projections.borrow_mut().clear();
+    }
+}
+
+/// Creates a reference to a function on an instance.
+///
+/// # Arguments
+///
+/// * `instance` - The instance that contains the function.
+/// * `function` - The name of the function.
+pub(crate) fn func_ref(
+    instance: Ref<Object, Shared>,
+    function: GodotString,
+) -> Ref<FuncRef, ThreadLocal> {
+    let func_ref = FuncRef::new();
+    func_ref.set_instance(instance);
+    func_ref.set_function(function);
+
+    func_ref.into_thread_local()
+}
+
+/// Returns the SceneTree the game is running in, or `None` if the main loop
+/// isn't a SceneTree.
+fn main_scene_tree() -> Option<Ref<SceneTree, Shared>> {
+    Engine::godot_singleton()
+        .get_main_loop()
+        .and_then(|main_loop| main_loop.cast::<SceneTree>())
+}
+
+/// Returns whether a value returned by a function is the state of the
+/// function after it yielded, instead of what it returned.
+///
+/// # Arguments
+///
+/// * `value` - The value the function returned.
+fn is_yielded(value: &Variant) -> bool {
+    value
+        .try_to_object::<Object>()
+        .is_some_and(|object| unsafe { object.assume_safe() }.is_class("GDScriptFunctionState"))
+}
+
+/// Returns the slice of the state under a key, or an empty Dictionary if the
+/// state doesn't have the slice.
+///
+/// # Arguments
+///
+/// * `state` - The state to get the slice from.
+/// * `key` - The key of the slice.
+fn slice(state: &Dictionary, key: &str) -> Dictionary {
+    path::get(state, key)
+        .and_then(|slice| slice.try_to_dictionary())
+        .unwrap_or_else(Dictionary::new_shared)
+}
+
+/// Returns a copy of the state with the slice under a key replaced.
+///
+/// # Arguments
+///
+/// * `state` - The state to copy.
+/// * `key` - The key of the slice.
+/// * `slice` - The new slice.
+fn with_slice<S: OwnedToVariant>(state: &Dictionary, key: &str, slice: S) -> Dictionary {
+    let state = state.duplicate();
+    state.insert(key, slice);
+
+    state.into_shared()
+}
+
+/// Returns an action with a payload merged into it. An action that is just its
+/// type is turned into a Dictionary if there is a payload, and the payload
+/// never replaces the type or a value the action already has.
+///
+/// # Arguments
+///
+/// * `action` - The action.
+/// * `payload` - The data to add to the action.
+fn with_payload(action: &Variant, payload: &Dictionary) -> Variant {
+    if payload.is_empty() {
+        return action.clone();
+    }
+
+    let merged = match action.try_to_dictionary() {
+        Some(action) => action.duplicate(),
+        None => {
+            let merged = Dictionary::new();
+            merged.insert(action::TYPE_KEY, action);
+            merged
+        }
+    };
+    for (key, value) in payload.iter() {
+        if !merged.contains(&key) {
+            merged.insert(key, value);
+        }
+    }
+
+    merged.owned_to_variant()
+}
+
+/// Returns the state with each slice replaced by what its reducer returns for
+/// an action. A slice that isn't in the state is passed to its reducer as
+/// `null`.
+///
+/// # Arguments
+///
+/// * `state` - The current state.
+/// * `action` - The action to reduce.
+/// * `reducers` - The reducer of each slice, along with the key of the slice.
+/// * `timings` - Where how long each reducer took is added, in microseconds,
+/// if the dispatch is being profiled.
+fn reduce_slices(
+    state: &Dictionary,
+    action: &Variant,
+    reducers: &[(String, Ref<FuncRef, Shared>)],
+    mut timings: Option<&mut Vec<(String, i64)>>,
+) -> Dictionary {
+    let new_state = state.duplicate();
+    for (key, reducer) in reducers {
+        let current = path::get(state, key).unwrap_or_else(Variant::new);
+        let started = timings.as_ref().map(|_| clock::real_now());
+        let next = unsafe { reducer.assume_safe() }.call_func(&[current, action.clone()]);
+        if let (Some(timings), Some(started)) = (timings.as_mut(), started) {
+            let usec = (clock::real_now() - started) * 1_000_000.0;
+            timings.push((key.clone(), usec as i64));
+        }
+        new_state.insert(key, next);
+    }
+
+    new_state.into_shared()
+}
+
+/// Returns the state with a built-in slice updated. If the slice didn't change
+/// but the reducer dropped it from the state, the current slice is put back.
+///
+/// # Arguments
+///
+/// * `new_state` - The state returned by the reducer.
+/// * `key` - The key of the slice.
+/// * `next` - The updated slice, or `None` if the slice didn't change.
+/// * `current` - The slice before the action.
+fn keep_slice<S: OwnedToVariant>(
+    new_state: Dictionary,
+    key: &str,
+    next: Option<S>,
+    current: Dictionary,
+) -> Dictionary {
+    match next {
+        Some(next) => with_slice(&new_state, key, next),
+        None if new_state.contains(key) => new_state,
+        None => with_slice(&new_state, key, current),
+    }
+}
 
-        self.middleware.push(middleware_fn_ref)
+impl Drop for GodotRedux {
+    fn drop(&mut self) {
+        self.tear_down();
     }
 }
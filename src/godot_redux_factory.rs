@@ -0,0 +1,169 @@
+use std::cell::RefCell;
+
+use gdnative::prelude::{
+    core_types::GodotString, methods, Dictionary, Instance, NativeClass, Object, Ref, Reference,
+    Shared, ToVariant, Unique,
+};
+
+use crate::godot_redux::GodotRedux;
+use crate::mock_godot_redux::MockGodotRedux;
+use crate::subscription_spy::SubscriptionSpy;
+
+/// Creates stores for tests and frees them again when the tests are done, so
+/// every test can start from a fresh store without having to remember to free
+/// the last one. Since the factory is reference counted, everything it
+/// created is torn down automatically when it is freed, for example when it
+/// is recreated in GUT's `before_each`.
+#[inherit(Reference)]
+#[derive(NativeClass)]
+pub struct GodotReduxFactory {
+    /// The stores and mock stores created by the factory.
+    stores: RefCell<Vec<Ref<Object, Shared>>>,
+    /// The subscription spies created by the factory, which are kept here so
+    /// they live as long as the stores they are subscribed to.
+    spies: RefCell<Vec<Instance<SubscriptionSpy, Shared>>>,
+}
+
+#[methods]
+impl GodotReduxFactory {
+    /// Initializes the struct with default values.
+    fn new(_owner: &Reference) -> Self {
+        GodotReduxFactory {
+            stores: RefCell::new(vec![]),
+            spies: RefCell::new(vec![]),
+        }
+    }
+
+    /// Creates a store with an initial state and reducer that is freed when
+    /// the factory is torn down.
+    ///
+    /// # Arguments
+    ///
+    /// * `initial_state` - The initial state of the application.
+    /// * `reducer_fn_instance` - The instance on which the reducer exists.
+    /// * `reducer_fn_name` - The name of the reducer function.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// var stores
+    ///
+    /// func before_each():
+    ///     stores = GodotReduxFactory.new()
+    ///
+    /// func test_increment():
+    ///     var store = stores.create_store({ "counter": 0 }, self, 'reducer')
+    ///     store.dispatch(Action.INCREMENT)
+    ///
+    ///     assert_eq(store.get_value("counter"), 1)
+    /// ```
+    #[export]
+    fn create_store(
+        &self,
+        _owner: &Reference,
+        initial_state: Dictionary,
+        reducer_fn_instance: Ref<Object, Shared>,
+        reducer_fn_name: GodotString,
+    ) -> Instance<GodotRedux, Shared> {
+        let store = Instance::<GodotRedux, Unique>::new().into_shared();
+
+        let args = &[
+            initial_state.to_variant(),
+            reducer_fn_instance.to_variant(),
+            reducer_fn_name.to_variant(),
+        ];
+        unsafe {
+            store
+                .base()
+                .assume_safe()
+                .call("set_state_and_reducer", args)
+        };
+
+        self.stores.borrow_mut().push(store.base().clone());
+        store
+    }
+
+    /// Creates a mock store with an initial state that is freed when the
+    /// factory is torn down.
+    ///
+    /// # Arguments
+    ///
+    /// * `initial_state` - The state of the mock store.
+    #[export]
+    fn create_mock(
+        &self,
+        _owner: &Reference,
+        initial_state: Dictionary,
+    ) -> Instance<MockGodotRedux, Shared> {
+        let store = Instance::<MockGodotRedux, Unique>::new().into_shared();
+
+        let args = &[initial_state.to_variant()];
+        unsafe { store.base().assume_safe().call("set_state", args) };
+
+        self.stores.borrow_mut().push(store.base().clone());
+        store
+    }
+
+    /// Creates a `SubscriptionSpy` and subscribes it to a store so tests can
+    /// check when and with what state the store runs its subscriptions.
+    ///
+    /// # Arguments
+    ///
+    /// * `store` - The store or mock store to subscribe the spy to.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func test_subscribers_are_notified():
+    ///     var store = stores.create_store({ "counter": 0 }, self, 'reducer')
+    ///     var spy = stores.spy_on(store)
+    ///     store.dispatch(Action.INCREMENT)
+    ///
+    ///     assert_eq(spy.get_call_count(), 1)
+    ///     assert_eq(spy.get_last_state().counter, 1)
+    /// ```
+    #[export]
+    fn spy_on(
+        &self,
+        _owner: &Reference,
+        store: Ref<Object, Shared>,
+    ) -> Instance<SubscriptionSpy, Shared> {
+        let spy = Instance::<SubscriptionSpy, Unique>::new().into_shared();
+
+        let args = &[spy.base().to_variant(), "record_call".to_variant()];
+        unsafe { store.assume_safe().call("subscribe", args) };
+
+        self.spies.borrow_mut().push(spy.clone());
+        spy
+    }
+
+    /// Frees every store and mock store created by the factory and lets go of
+    /// its spies. This is also done when the factory is freed so it only needs
+    /// to be called to tear everything down early, for example in GUT's
+    /// `after_each`.
+    #[export]
+    fn tear_down(&self, _owner: &Reference) {
+        self.free_all();
+    }
+
+    /// Frees every store and mock store created by the factory and lets go of
+    /// its spies.
+    fn free_all(&self) {
+        self.spies.borrow_mut().clear();
+
+        for store in self.stores.replace(vec![]) {
+            // A test might have freed the store itself already.
+            unsafe {
+                if store.is_instance_sane() {
+                    store.assume_unique().free();
+                }
+            }
+        }
+    }
+}
+
+impl Drop for GodotReduxFactory {
+    fn drop(&mut self) {
+        self.free_all();
+    }
+}
@@ -0,0 +1,192 @@
+use gdnative::prelude::{
+    core_types::GodotString, methods, Dictionary, Instance, NativeClass, Node, Object, Ref, Shared,
+    TRef, ToVariant, Unique, Variant,
+};
+
+use crate::godot_redux::GodotRedux;
+
+/// A Node that holds a store so it can be added as an autoload, like
+/// `Store`, and used from every script in the game. It attaches the store to
+/// the tree when it's ready, flushes the actions queued with
+/// `dispatch_deferred` every frame, and lets go of the subscriptions of nodes
+/// that were freed, so none of that has to be done by hand. The most common
+/// methods of the store are available on the node itself, and the store is
+/// returned by `get_store` for everything else.
+#[inherit(Node)]
+#[derive(NativeClass)]
+pub struct GodotReduxNode {
+    /// The store.
+    store: Instance<GodotRedux, Shared>,
+}
+
+#[methods]
+impl GodotReduxNode {
+    /// Initializes the struct with a new store.
+    fn new(_owner: &Node) -> Self {
+        GodotReduxNode {
+            store: Instance::<GodotRedux, Unique>::new().into_shared(),
+        }
+    }
+
+    /// Attaches the store to the tree the node was added to, so frames are
+    /// counted and the pause modes follow the tree.
+    #[export]
+    fn _ready(&self, owner: TRef<Node>) {
+        if let Some(tree) = owner.get_tree() {
+            self.call("attach_to_tree", &[tree.to_variant()]);
+        }
+    }
+
+    /// Dispatches the actions queued with `dispatch_deferred` and lets go of
+    /// the subscriptions of nodes that were freed during the frame.
+    ///
+    /// # Arguments
+    ///
+    /// * `delta` - The seconds since the last frame, which aren't used.
+    #[export]
+    fn _process(&self, _owner: &Node, _delta: f64) {
+        self.call("flush_deferred_dispatches", &[]);
+        self.call("prune_subscriptions", &[]);
+    }
+
+    /// Dispatches the actions that are still queued when the node leaves the
+    /// tree, for example when the game quits, so they aren't lost.
+    #[export]
+    fn _exit_tree(&self, _owner: &Node) {
+        self.call("flush_deferred_dispatches", &[]);
+    }
+
+    /// Returns the store, which has every method of the store.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _ready():
+    ///     Store.get_store().enable_undo(50)
+    /// ```
+    #[export]
+    fn get_store(&self, _owner: &Node) -> Instance<GodotRedux, Shared> {
+        self.store.clone()
+    }
+
+    /// Sets the state and the reducer of the store, like the store's
+    /// `set_state_and_reducer`.
+    ///
+    /// # Arguments
+    ///
+    /// * `initial_state` - The initial state of the application.
+    /// * `reducer_fn_instance` - The instance on which the reducer exists.
+    /// * `reducer_fn_name` - The name of the reducer function.
+    #[export]
+    fn set_state_and_reducer(
+        &self,
+        _owner: &Node,
+        initial_state: Dictionary,
+        reducer_fn_instance: Ref<Object, Shared>,
+        reducer_fn_name: GodotString,
+    ) {
+        self.call(
+            "set_state_and_reducer",
+            &[
+                initial_state.to_variant(),
+                reducer_fn_instance.to_variant(),
+                reducer_fn_name.to_variant(),
+            ],
+        );
+    }
+
+    /// Dispatches an action, like the store's `dispatch`.
+    ///
+    /// # Arguments
+    ///
+    /// * `action` - The action to dispatch.
+    #[export]
+    fn dispatch(&self, _owner: &Node, action: Variant) -> Variant {
+        self.call("dispatch", &[action])
+    }
+
+    /// Queues an action to be dispatched at the end of the frame, like the
+    /// store's `dispatch_deferred`.
+    ///
+    /// # Arguments
+    ///
+    /// * `action` - The action to dispatch.
+    /// * `lane` - Optional lane to queue the action in.
+    #[export]
+    fn dispatch_deferred(&self, _owner: &Node, action: Variant, #[opt] lane: GodotString) {
+        self.call("dispatch_deferred", &[action, lane.to_variant()]);
+    }
+
+    /// Returns the current state, like the store's `state`.
+    #[export]
+    fn state(&self, _owner: &Node) -> Dictionary {
+        self.call("state", &[]).to_dictionary()
+    }
+
+    /// Returns the value at a path in the state, like the store's
+    /// `get_value`.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path to the value.
+    #[export]
+    fn get_value(&self, _owner: &Node, path: GodotString) -> Variant {
+        self.call("get_value", &[path.to_variant()])
+    }
+
+    /// Subscribes to changes to the state, like the store's `subscribe`.
+    ///
+    /// # Arguments
+    ///
+    /// * `callback_fn_instance` - The instance that contains the callback
+    /// function.
+    /// * `callback_fn_name` - The name of the callback function.
+    #[export]
+    fn subscribe(
+        &self,
+        _owner: &Node,
+        callback_fn_instance: Ref<Object, Shared>,
+        callback_fn_name: GodotString,
+    ) -> i64 {
+        self.call(
+            "subscribe",
+            &[
+                callback_fn_instance.to_variant(),
+                callback_fn_name.to_variant(),
+            ],
+        )
+        .to_i64()
+    }
+
+    /// Stops a subscription, like the store's `unsubscribe`.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The id of the subscription.
+    #[export]
+    fn unsubscribe(&self, _owner: &Node, id: i64) -> bool {
+        self.call("unsubscribe", &[id.to_variant()]).to_bool()
+    }
+
+    /// Calls a method of the store.
+    ///
+    /// # Arguments
+    ///
+    /// * `method` - The name of the method.
+    /// * `args` - The arguments to pass to the method.
+    fn call(&self, method: &str, args: &[Variant]) -> Variant {
+        unsafe { self.store.base().assume_safe().call(method, args) }
+    }
+}
+
+impl Drop for GodotReduxNode {
+    fn drop(&mut self) {
+        // The store is an Object, so it has to be freed along with the node.
+        let store = self.store.base().clone();
+        unsafe {
+            if store.is_instance_sane() {
+                store.assume_unique().free();
+            }
+        }
+    }
+}
@@ -0,0 +1,381 @@
+use gdnative::prelude::{Dictionary, Unique, Variant, VariantArray};
+
+use crate::compression::Compressed;
+use crate::{diff, event_log};
+
+/// An action in the history along with how the state changed because of it.
+struct Entry {
+    /// The action.
+    action: Variant,
+    /// How the state after the action is different from the one before it.
+    delta: Compressed,
+    /// The number of bytes the action and the compressed change take up.
+    size: usize,
+}
+
+/// The actions that were dispatched while time travel is enabled and the
+/// state after each of them, so the state can be moved back to any of them
+/// while debugging. Only the first state is kept as a whole and every other
+/// one is kept as the keys that changed from the one before it, since most
+/// actions only change a small part of the state. Both are kept compressed
+/// and are only rebuilt when a state is read, so a long history stays small.
+pub struct History {
+    /// The state before the first action.
+    base: Compressed,
+    /// The actions, in the order they were dispatched.
+    timeline: Timeline<Entry>,
+}
+
+impl History {
+    /// Creates an empty history that starts from a state.
+    ///
+    /// # Arguments
+    ///
+    /// * `state` - The state before the first action.
+    pub fn new(state: &Dictionary) -> Self {
+        History {
+            base: Compressed::new(&Variant::from_dictionary(state)),
+            timeline: Timeline::default(),
+        }
+    }
+
+    /// Records an action and the state it led to. If the state was moved back
+    /// to an earlier action, the actions after that one are dropped first
+    /// since the state is moving on from there instead.
+    ///
+    /// # Arguments
+    ///
+    /// * `action` - The action.
+    /// * `old_state` - The state before the action.
+    /// * `new_state` - The state after the action.
+    pub fn record(&mut self, action: &Variant, old_state: &Dictionary, new_state: &Dictionary) {
+        let delta = diff::compress(&diff::delta(old_state, new_state));
+        let size = event_log::encoded_size(action) + delta.size();
+        self.timeline.record(Entry {
+            action: action.clone(),
+            delta,
+            size,
+        });
+    }
+
+    /// Returns every action in the history along with the state after it, in
+    /// the order they were dispatched, as Dictionaries with the action under
+    /// `action` and the state under `state`.
+    pub fn to_array(&self) -> VariantArray<Unique> {
+        let mut state = self.base_state();
+
+        self.timeline
+            .entries
+            .iter()
+            .map(|entry| {
+                state = diff::apply(&state, &diff::decompress(&entry.delta)).into_shared();
+
+                let item = Dictionary::new();
+                item.insert("action", &entry.action);
+                item.insert("state", &state);
+                item
+            })
+            .collect()
+    }
+
+    /// Moves the state back, or forward again, to the action at an index and
+    /// returns the state after it, so the actions after it are dropped once
+    /// another one is recorded. Returns an error if the index isn't in the
+    /// history.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - The index of the action.
+    pub fn jump_to(&mut self, index: i64) -> Result<Dictionary, String> {
+        let index = self.timeline.index(index).ok_or_else(|| {
+            format!(
+                "Unable to jump to {} since the history has {} actions",
+                index,
+                self.timeline.len()
+            )
+        })?;
+        self.timeline.jump(index);
+
+        Ok(self.state_after(Some(index)))
+    }
+
+    /// Removes the action at an index and every action after it, and returns
+    /// the state before the action along with the removed actions so they
+    /// can be dispatched again. Returns an error if the index isn't in the
+    /// history.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - The index of the first action to remove.
+    pub fn rewind_to(&mut self, index: i64) -> Result<(Dictionary, Vec<Variant>), String> {
+        let index = self.timeline.index(index).ok_or_else(|| {
+            format!(
+                "Unable to replay from {} since the history has {} actions",
+                index,
+                self.timeline.len()
+            )
+        })?;
+        let state = self.state_after(index.checked_sub(1));
+
+        let actions = self
+            .timeline
+            .rewind(index)
+            .into_iter()
+            .map(|entry| entry.action)
+            .collect();
+
+        Ok((state, actions))
+    }
+
+    /// Returns the state before the first action, rebuilt from its compressed
+    /// copy.
+    fn base_state(&self) -> Dictionary {
+        self.base.value().try_to_dictionary().unwrap_or_default()
+    }
+
+    /// Returns the state after the action at an index, or before the first
+    /// action if the index is `None`.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - The index of the action, which has to be in the history.
+    fn state_after(&self, index: Option<usize>) -> Dictionary {
+        self.timeline
+            .up_to(index)
+            .iter()
+            .fold(self.base_state(), |state, entry| {
+                diff::apply(&state, &diff::decompress(&entry.delta)).into_shared()
+            })
+    }
+
+    /// Returns the number of bytes the actions and changes in the history
+    /// take up, which is roughly how much memory they use.
+    pub fn size(&self) -> usize {
+        self.timeline.entries.iter().map(|entry| entry.size).sum()
+    }
+
+    /// Removes the oldest actions from the history until the rest of them
+    /// take up at most a number of bytes, moving the state the history starts
+    /// from forward past them, and returns how many were removed. The action
+    /// the state was moved back to is kept along with the ones after it.
+    ///
+    /// # Arguments
+    ///
+    /// * `limit` - The number of bytes the history can take up.
+    pub fn trim_to(&mut self, limit: usize) -> usize {
+        let removed = self.timeline.trim_to(limit, |entry| entry.size);
+        if !removed.is_empty() {
+            let base = removed.iter().fold(self.base_state(), |state, entry| {
+                diff::apply(&state, &diff::decompress(&entry.delta)).into_shared()
+            });
+            self.base = Compressed::new(&Variant::from_dictionary(&base));
+        }
+
+        removed.len()
+    }
+}
+
+/// Entries in the order they were recorded along with the one the state was
+/// moved back to, which is how the history keeps track of its actions apart
+/// from the states they led to.
+struct Timeline<T> {
+    /// The entries, in the order they were recorded.
+    entries: Vec<T>,
+    /// The index of the entry the state was moved back to with `jump`, or
+    /// `None` if it's after the last one.
+    cursor: Option<usize>,
+}
+
+impl<T> Default for Timeline<T> {
+    fn default() -> Self {
+        Timeline {
+            entries: vec![],
+            cursor: None,
+        }
+    }
+}
+
+impl<T> Timeline<T> {
+    /// Adds an entry after the others. If the state was moved back to an
+    /// earlier entry, the ones after it are dropped first.
+    ///
+    /// # Arguments
+    ///
+    /// * `entry` - The entry.
+    fn record(&mut self, entry: T) {
+        if let Some(cursor) = self.cursor.take() {
+            self.entries.truncate(cursor + 1);
+        }
+
+        self.entries.push(entry);
+    }
+
+    /// Returns the number of entries.
+    fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns an index of an entry from gdscript as an index of the entries,
+    /// or `None` if there's no entry at it.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - The index of the entry.
+    fn index(&self, index: i64) -> Option<usize> {
+        if index >= 0 && (index as usize) < self.entries.len() {
+            Some(index as usize)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the entries up to and including the one at an index, or none
+    /// of them if the index is `None`.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - The index of the last entry, which has to be in the
+    /// timeline.
+    fn up_to(&self, index: Option<usize>) -> &[T] {
+        &self.entries[..index.map_or(0, |index| index + 1)]
+    }
+
+    /// Moves the state back, or forward again, to the entry at an index.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - The index of the entry, which has to be in the timeline.
+    fn jump(&mut self, index: usize) {
+        self.cursor = Some(index).filter(|index| index + 1 < self.entries.len());
+    }
+
+    /// Removes the entry at an index and every entry after it, and returns
+    /// them.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - The index of the first entry to remove, which has to be in
+    /// the timeline.
+    fn rewind(&mut self, index: usize) -> Vec<T> {
+        self.cursor = None;
+
+        self.entries.drain(index..).collect()
+    }
+
+    /// Removes the oldest entries until the rest of them take up at most a
+    /// number of bytes, and returns the ones that were removed. The entry the
+    /// state was moved back to is kept along with the ones after it.
+    ///
+    /// # Arguments
+    ///
+    /// * `limit` - The number of bytes the entries can take up.
+    /// * `size` - Returns the number of bytes an entry takes up.
+    fn trim_to(&mut self, limit: usize, size: impl Fn(&T) -> usize) -> Vec<T> {
+        let mut total = self.entries.iter().map(&size).sum::<usize>();
+        let count = self
+            .entries
+            .iter()
+            .take_while(|entry| {
+                let over = total > limit;
+                total -= size(entry);
+                over
+            })
+            .count();
+        let count = self.cursor.map_or(count, |cursor| count.min(cursor));
+
+        self.cursor = self.cursor.map(|cursor| cursor - count);
+        self.entries.drain(..count).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Timeline;
+
+    /// Creates a timeline with changes to a number as its entries.
+    fn timeline(changes: &[i32]) -> Timeline<i32> {
+        let mut timeline = Timeline::default();
+        for change in changes {
+            timeline.record(*change);
+        }
+
+        timeline
+    }
+
+    /// Returns a number after the changes up to an index are made to it,
+    /// which is how the history rebuilds a state from its deltas.
+    fn rebuilt(base: i32, timeline: &Timeline<i32>, index: Option<usize>) -> i32 {
+        timeline
+            .up_to(index)
+            .iter()
+            .fold(base, |value, change| value + change)
+    }
+
+    #[test]
+    fn the_state_is_rebuilt_from_the_entries_up_to_an_index() {
+        let timeline = timeline(&[1, 2, 3]);
+
+        assert_eq!(rebuilt(10, &timeline, None), 10);
+        assert_eq!(rebuilt(10, &timeline, Some(0)), 11);
+        assert_eq!(rebuilt(10, &timeline, Some(2)), 16);
+    }
+
+    #[test]
+    fn only_indexes_of_entries_are_in_the_timeline() {
+        let timeline = timeline(&[1, 2]);
+
+        assert_eq!(timeline.index(-1), None);
+        assert_eq!(timeline.index(0), Some(0));
+        assert_eq!(timeline.index(1), Some(1));
+        assert_eq!(timeline.index(2), None);
+    }
+
+    #[test]
+    fn recording_after_a_jump_drops_the_entries_after_it() {
+        let mut timeline = timeline(&[1, 2, 3]);
+        timeline.jump(0);
+        timeline.record(5);
+
+        assert_eq!(timeline.entries, vec![1, 5]);
+        assert_eq!(timeline.cursor, None);
+    }
+
+    #[test]
+    fn jumping_to_the_last_entry_keeps_every_entry() {
+        let mut timeline = timeline(&[1, 2, 3]);
+        timeline.jump(0);
+        timeline.jump(2);
+        timeline.record(4);
+
+        assert_eq!(timeline.entries, vec![1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn rewind_removes_the_entry_and_the_ones_after_it() {
+        let mut timeline = timeline(&[1, 2, 3]);
+        timeline.jump(0);
+
+        assert_eq!(timeline.rewind(1), vec![2, 3]);
+        assert_eq!(timeline.entries, vec![1]);
+        assert_eq!(timeline.cursor, None);
+    }
+
+    #[test]
+    fn trim_to_removes_the_oldest_entries_until_they_fit() {
+        let mut timeline = timeline(&[10, 20, 5, 5]);
+
+        assert_eq!(timeline.trim_to(15, |size| *size as usize), vec![10, 20]);
+        assert_eq!(timeline.entries, vec![5, 5]);
+        assert!(timeline.trim_to(10, |size| *size as usize).is_empty());
+    }
+
+    #[test]
+    fn trim_to_keeps_the_entry_that_was_jumped_to() {
+        let mut timeline = timeline(&[10, 20, 5, 5]);
+        timeline.jump(1);
+
+        assert_eq!(timeline.trim_to(0, |size| *size as usize), vec![10]);
+        assert_eq!(timeline.entries, vec![20, 5, 5]);
+        assert_eq!(timeline.cursor, Some(0));
+    }
+}
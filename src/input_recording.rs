@@ -0,0 +1,302 @@
+use gdnative::api::{InputEvent, InputMap};
+use gdnative::prelude::{
+    Dictionary, OwnedToVariant, TRef, Unique, Variant, VariantArray, VariantType,
+};
+
+use crate::action;
+use crate::path;
+
+/// The key of the slice of the state that holds the recorded input.
+pub const SLICE: &str = "input_recording";
+
+/// The type of the action dispatched for every mapped input action that is
+/// pressed or released.
+const INPUT: &str = "input";
+
+/// The type of the action dispatched when a recording starts.
+const RECORDING_STARTED: &str = "input_recording_started";
+
+/// The type of the action dispatched when a recording stops.
+const RECORDING_STOPPED: &str = "input_recording_stopped";
+
+/// The type of the action dispatched when a replay starts.
+const REPLAY_STARTED: &str = "input_replay_started";
+
+/// The type of the action dispatched once every event of a replay has been
+/// dispatched.
+const REPLAY_FINISHED: &str = "input_replay_finished";
+
+/// The key of the name of the input action, both in an `input` action and in
+/// a recorded event.
+const ACTION_KEY: &str = "action";
+
+/// The key of whether the input action was pressed or released, both in an
+/// `input` action and in a recorded event.
+const PRESSED_KEY: &str = "pressed";
+
+/// The key of the frame the input happened on, both in an `input` action and
+/// in a recorded event.
+const FRAME_KEY: &str = "frame";
+
+/// The key of whether an `input` action comes from a replay.
+const REPLAYED_KEY: &str = "replayed";
+
+/// The key of whether input is being recorded in the slice.
+const RECORDING_KEY: &str = "recording";
+
+/// The key of whether input is being replayed in the slice.
+const REPLAYING_KEY: &str = "replaying";
+
+/// The key of the recorded events in the slice.
+const EVENTS_KEY: &str = "events";
+
+/// A recording started with `start_input_recording`.
+#[derive(Clone)]
+pub struct Recording {
+    /// The input actions that are recorded, or every mapped input action if
+    /// it's empty.
+    pub actions: Vec<String>,
+    /// The frame of the store the recording started on.
+    pub started_at: u64,
+}
+
+/// A replay started with `replay_input`.
+pub struct Replay {
+    /// The `input` actions to dispatch along with the frame they're
+    /// dispatched on relative to the start of the replay, in order.
+    events: Vec<(u64, Variant)>,
+    /// The frame of the store the replay started on.
+    started_at: u64,
+    /// The index of the next event to dispatch.
+    next: usize,
+}
+
+impl Replay {
+    /// Creates a replay of recorded events, returning an error describing the
+    /// first event that isn't valid.
+    ///
+    /// # Arguments
+    ///
+    /// * `events` - The recorded events, as returned by
+    /// `stop_input_recording`.
+    /// * `started_at` - The frame of the store the replay starts on.
+    pub fn new(events: &VariantArray, started_at: u64) -> Result<Self, String> {
+        let mut events = events
+            .iter()
+            .map(|event| parse_event(&event))
+            .collect::<Result<Vec<_>, _>>()?;
+        events.sort_by_key(|(frame, _)| *frame);
+
+        Ok(Replay {
+            events,
+            started_at,
+            next: 0,
+        })
+    }
+
+    /// Returns the `input` actions that are due by the frame and haven't been
+    /// dispatched yet. An event recorded on a frame is due once that frame has
+    /// passed, since input is handled before the frame that follows it.
+    ///
+    /// # Arguments
+    ///
+    /// * `frame` - The current frame of the store.
+    pub fn due(&mut self, frame: u64) -> Vec<Variant> {
+        let elapsed = frame.saturating_sub(self.started_at);
+
+        let due = self.events[self.next..]
+            .iter()
+            .take_while(|(event_frame, _)| *event_frame < elapsed)
+            .map(|(_, action)| action.clone())
+            .collect::<Vec<_>>();
+        self.next += due.len();
+
+        due
+    }
+
+    /// Returns whether every event of the replay has been dispatched.
+    pub fn is_finished(&self) -> bool {
+        self.next >= self.events.len()
+    }
+}
+
+/// Returns the input actions that an input event presses or releases, along
+/// with whether each one was pressed. Echoes of held keys are ignored.
+///
+/// # Arguments
+///
+/// * `event` - The input event.
+/// * `actions` - The input actions to check for, or every mapped input action
+/// if it's empty.
+pub fn mapped_actions(event: TRef<InputEvent>, actions: &[String]) -> Vec<(String, bool)> {
+    let actions = if actions.is_empty() {
+        InputMap::godot_singleton()
+            .get_actions()
+            .iter()
+            .map(|action| action.to_string())
+            .collect()
+    } else {
+        actions.to_vec()
+    };
+
+    actions
+        .into_iter()
+        .filter_map(|action| {
+            if event.is_action_pressed(&action, false) {
+                Some((action, true))
+            } else if event.is_action_released(&action) {
+                Some((action, false))
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/// Returns the action dispatched for an input action that was pressed or
+/// released.
+///
+/// # Arguments
+///
+/// * `name` - The name of the input action.
+/// * `pressed` - Whether the input action was pressed or released.
+/// * `frame` - The frame the input happened on.
+/// * `replayed` - Whether the input comes from a replay.
+pub fn input(name: &str, pressed: bool, frame: u64, replayed: bool) -> Dictionary<Unique> {
+    let action = action::named(INPUT);
+    action.insert(ACTION_KEY, name);
+    action.insert(PRESSED_KEY, pressed);
+    action.insert(FRAME_KEY, frame);
+    action.insert(REPLAYED_KEY, replayed);
+
+    action
+}
+
+/// Returns the action dispatched when a recording starts.
+pub fn recording_started() -> Dictionary<Unique> {
+    action::named(RECORDING_STARTED)
+}
+
+/// Returns the action dispatched when a recording stops.
+pub fn recording_stopped() -> Dictionary<Unique> {
+    action::named(RECORDING_STOPPED)
+}
+
+/// Returns the action dispatched when a replay starts.
+pub fn replay_started() -> Dictionary<Unique> {
+    action::named(REPLAY_STARTED)
+}
+
+/// Returns the action dispatched once every event of a replay has been
+/// dispatched.
+pub fn replay_finished() -> Dictionary<Unique> {
+    action::named(REPLAY_FINISHED)
+}
+
+/// Returns the slice after an action, or `None` if the action doesn't change
+/// it. `input` actions are only added to the slice while recording.
+///
+/// # Arguments
+///
+/// * `current` - The slice before the action.
+/// * `action` - The action that was dispatched.
+pub fn reduce(current: &Dictionary, action: &Variant) -> Option<Dictionary<Unique>> {
+    let recording = path::get(current, RECORDING_KEY)
+        .unwrap_or_default()
+        .to_bool();
+    let replaying = path::get(current, REPLAYING_KEY)
+        .unwrap_or_default()
+        .to_bool();
+
+    if action::is_type(action, RECORDING_STARTED) {
+        Some(slice(true, replaying, VariantArray::new()))
+    } else if action::is_type(action, RECORDING_STOPPED) {
+        Some(slice(false, replaying, events(current).duplicate()))
+    } else if action::is_type(action, REPLAY_STARTED) {
+        Some(slice(recording, true, events(current).duplicate()))
+    } else if action::is_type(action, REPLAY_FINISHED) {
+        Some(slice(recording, false, events(current).duplicate()))
+    } else if action::is_type(action, INPUT) && recording {
+        let event = Dictionary::new();
+        for key in &[FRAME_KEY, ACTION_KEY, PRESSED_KEY] {
+            event.insert(*key, action::field(action, key).unwrap_or_default());
+        }
+
+        let events = events(current).duplicate();
+        events.push(event);
+
+        Some(slice(recording, replaying, events))
+    } else {
+        None
+    }
+}
+
+/// Returns the recorded events in the slice.
+///
+/// # Arguments
+///
+/// * `slice` - The slice.
+pub fn events(slice: &Dictionary) -> VariantArray {
+    path::get(slice, EVENTS_KEY)
+        .and_then(|events| events.try_to_array())
+        .unwrap_or_else(VariantArray::new_shared)
+}
+
+/// Parses a recorded event into the frame it happened on and the `input`
+/// action to replay it with.
+///
+/// # Arguments
+///
+/// * `event` - The Dictionary of the event.
+fn parse_event(event: &Variant) -> Result<(u64, Variant), String> {
+    let definition = event.try_to_dictionary().ok_or_else(|| {
+        format!(
+            "A recorded input event has to be a Dictionary, got {}",
+            event.to_string()
+        )
+    })?;
+    let get = |key: &str| {
+        path::get(&definition, key).ok_or_else(|| {
+            format!(
+                "The recorded input event {} is missing its {}",
+                event.to_string(),
+                key
+            )
+        })
+    };
+
+    // Frames read back from JSON are floats, so they go through f64.
+    let frame = get(FRAME_KEY)?;
+    if !matches!(frame.get_type(), VariantType::I64 | VariantType::F64) || frame.to_f64() < 0.0 {
+        return Err(format!(
+            "The frame of the recorded input event {} has to be a positive number",
+            event.to_string()
+        ));
+    }
+    let frame = frame.to_f64() as u64;
+
+    let action = input(
+        &get(ACTION_KEY)?.to_string(),
+        get(PRESSED_KEY)?.to_bool(),
+        frame,
+        true,
+    );
+
+    Ok((frame, action.owned_to_variant()))
+}
+
+/// Creates the slice.
+///
+/// # Arguments
+///
+/// * `recording` - Whether input is being recorded.
+/// * `replaying` - Whether input is being replayed.
+/// * `events` - The recorded events.
+fn slice(recording: bool, replaying: bool, events: VariantArray<Unique>) -> Dictionary<Unique> {
+    let slice = Dictionary::new();
+    slice.insert(RECORDING_KEY, recording);
+    slice.insert(REPLAYING_KEY, replaying);
+    slice.insert(EVENTS_KEY, events);
+
+    slice
+}
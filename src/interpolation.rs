@@ -0,0 +1,83 @@
+use gdnative::prelude::{Dictionary, OwnedToVariant, Variant, VariantArray, VariantType};
+
+/// Returns a blend between the previous and the current value of a path, or
+/// `None` if the values can't be blended. Numbers, `Vector2`s, `Vector3`s,
+/// and `Color`s can be blended, and an `int` can be blended with a `float`,
+/// which always gives a `float`. Dictionaries are blended key by key and
+/// Arrays of the same length item by item, like the positions of every enemy,
+/// keeping the current value of whatever inside of them can't be blended.
+///
+/// # Arguments
+///
+/// * `previous` - The value before the last change.
+/// * `current` - The value now.
+/// * `weight` - How far to blend from the previous value to the current one,
+/// from 0 to 1, which is clamped.
+pub fn blend(previous: &Variant, current: &Variant, weight: f64) -> Option<Variant> {
+    let weight = weight.max(0.0).min(1.0);
+
+    match (previous.get_type(), current.get_type()) {
+        (VariantType::I64, VariantType::I64)
+        | (VariantType::I64, VariantType::F64)
+        | (VariantType::F64, VariantType::I64)
+        | (VariantType::F64, VariantType::F64) => {
+            let (from, to) = (previous.to_f64(), current.to_f64());
+
+            Some(Variant::from_f64(from + (to - from) * weight))
+        }
+        (VariantType::Vector2, VariantType::Vector2) => {
+            let (from, to) = (previous.to_vector2(), current.to_vector2());
+
+            Some(Variant::from_vector2(&(from + (to - from) * weight as f32)))
+        }
+        (VariantType::Vector3, VariantType::Vector3) => {
+            let (from, to) = (previous.to_vector3(), current.to_vector3());
+
+            Some(Variant::from_vector3(&(from + (to - from) * weight as f32)))
+        }
+        (VariantType::Color, VariantType::Color) => Some(Variant::from_color(
+            &previous.to_color().lerp(current.to_color(), weight as f32),
+        )),
+        (VariantType::Dictionary, VariantType::Dictionary) => {
+            let (previous, current) = (previous.to_dictionary(), current.to_dictionary());
+
+            let blended = Dictionary::new();
+            for (key, value) in current.iter() {
+                let value = if previous.contains(&key) {
+                    blend_or_current(&previous.get(&key), &value, weight)
+                } else {
+                    value
+                };
+                blended.insert(key, value);
+            }
+
+            Some(blended.owned_to_variant())
+        }
+        (VariantType::VariantArray, VariantType::VariantArray) => {
+            let (previous, current) = (previous.to_array(), current.to_array());
+            if previous.len() != current.len() {
+                return None;
+            }
+
+            let blended = VariantArray::new();
+            for (previous, current) in previous.iter().zip(current.iter()) {
+                blended.push(blend_or_current(&previous, &current, weight));
+            }
+
+            Some(blended.owned_to_variant())
+        }
+        _ => None,
+    }
+}
+
+/// Returns a blend between two values, or the current value if they can't be
+/// blended.
+///
+/// # Arguments
+///
+/// * `previous` - The value before the last change.
+/// * `current` - The value now.
+/// * `weight` - How far to blend from the previous value to the current one.
+fn blend_or_current(previous: &Variant, current: &Variant, weight: f64) -> Variant {
+    blend(previous, current, weight).unwrap_or_else(|| current.clone())
+}
@@ -0,0 +1,657 @@
+use std::collections::BTreeMap;
+
+use gdnative::prelude::{Dictionary, Unique, Variant, VariantArray};
+
+use crate::{action, path};
+
+/// The key of the slice of the state that holds the inventory.
+pub const SLICE: &str = "inventory";
+
+/// The type of the action that adds items to the inventory.
+const ADD_ITEM: &str = "add_item";
+
+/// The type of the action that removes items from the inventory.
+const REMOVE_ITEM: &str = "remove_item";
+
+/// The type of the action that moves a stack to another slot.
+const MOVE_ITEM: &str = "move_item";
+
+/// The type of the action that swaps the stacks in two slots.
+const SWAP_ITEMS: &str = "swap_items";
+
+/// The type of the action that splits part of a stack into another slot.
+const SPLIT_STACK: &str = "split_stack";
+
+/// The key of the slots in the slice.
+const SLOTS_KEY: &str = "slots";
+
+/// The key of the item, both in a stack and in an item action.
+const ITEM_KEY: &str = "item";
+
+/// The key of the number of items, both in a stack and in an item action.
+const COUNT_KEY: &str = "count";
+
+/// The key of the slot a stack is moved from, in a slot action.
+const FROM_KEY: &str = "from";
+
+/// The key of the slot a stack is moved to, in a slot action.
+const TO_KEY: &str = "to";
+
+/// The key of the slot of the stack to split, in a `split_stack` action.
+const SLOT_KEY: &str = "slot";
+
+/// The key of the most items that fit in one slot, in an item definition.
+const MAX_STACK_KEY: &str = "max_stack";
+
+/// The key of the tags of an item, in an item definition.
+const TAGS_KEY: &str = "tags";
+
+/// The definition of an item that can be put in the inventory.
+#[derive(Clone)]
+struct ItemDefinition {
+    /// The most items of this kind that fit in one slot.
+    max_stack: i64,
+    /// The tags of the item, used by `get_items_by_tag`.
+    tags: Vec<String>,
+}
+
+/// Some number of the same item in a slot.
+#[derive(Clone)]
+struct Stack {
+    /// The name of the item.
+    item: String,
+    /// The number of items in the stack.
+    count: i64,
+}
+
+/// The inventory managed by the store, enabled with `enable_inventory`.
+#[derive(Clone)]
+pub struct Inventory {
+    /// The number of slots.
+    capacity: usize,
+    /// The items that can be put in the inventory, by name.
+    items: BTreeMap<String, ItemDefinition>,
+}
+
+impl Inventory {
+    /// Creates an inventory, returning an error describing the first item
+    /// definition that isn't valid.
+    ///
+    /// # Arguments
+    ///
+    /// * `capacity` - The number of slots.
+    /// * `items` - The item definitions, by name. Each one is a Dictionary
+    /// with the most items that fit in one slot under `max_stack`, which is 1
+    /// by default, and the tags of the item under `tags`.
+    pub fn new(capacity: i64, items: &Dictionary) -> Result<Self, String> {
+        if capacity < 1 {
+            return Err(format!(
+                "The capacity of the inventory has to be at least 1, got {}",
+                capacity
+            ));
+        }
+
+        let items = items
+            .iter()
+            .map(|(name, definition)| {
+                let name = name.to_string();
+                parse_item(&name, &definition).map(|definition| (name, definition))
+            })
+            .collect::<Result<BTreeMap<_, _>, _>>()?;
+
+        Ok(Inventory {
+            capacity: capacity as usize,
+            items,
+        })
+    }
+
+    /// Returns the slice with every slot empty.
+    pub fn initial_slice(&self) -> Dictionary<Unique> {
+        slice(&vec![None; self.capacity])
+    }
+
+    /// Returns the slice after an action, or `None` if the action isn't an
+    /// inventory action. An error is returned for an action that can't be
+    /// carried out, like adding more items than there's room for, in which
+    /// case nothing in the inventory changes.
+    ///
+    /// # Arguments
+    ///
+    /// * `current` - The slice before the action.
+    /// * `action` - The action that was dispatched.
+    pub fn reduce(
+        &self,
+        current: &Dictionary,
+        action: &Variant,
+    ) -> Result<Option<Dictionary<Unique>>, String> {
+        let mut slots = self.slots(current);
+
+        if action::is_type(action, ADD_ITEM) {
+            let (item, count) = self.item_and_count(action)?;
+            self.add(&mut slots, &item, count)?;
+        } else if action::is_type(action, REMOVE_ITEM) {
+            let (item, count) = self.item_and_count(action)?;
+            remove(&mut slots, &item, count)?;
+        } else if action::is_type(action, MOVE_ITEM) {
+            let from = self.slot_index(action, FROM_KEY)?;
+            let to = self.slot_index(action, TO_KEY)?;
+            self.move_stack(&mut slots, from, to)?;
+        } else if action::is_type(action, SWAP_ITEMS) {
+            let from = self.slot_index(action, FROM_KEY)?;
+            let to = self.slot_index(action, TO_KEY)?;
+            slots.swap(from, to);
+        } else if action::is_type(action, SPLIT_STACK) {
+            self.split(&mut slots, action)?;
+        } else {
+            return Ok(None);
+        }
+
+        Ok(Some(slice(&slots)))
+    }
+
+    /// Adds items to the stacks of the item that aren't full and then to empty
+    /// slots.
+    ///
+    /// # Arguments
+    ///
+    /// * `slots` - The slots of the inventory.
+    /// * `item` - The name of the item.
+    /// * `count` - The number of items to add.
+    fn add(&self, slots: &mut [Option<Stack>], item: &str, count: i64) -> Result<(), String> {
+        let max_stack = self.max_stack(item);
+
+        let room = slots
+            .iter()
+            .map(|slot| match slot {
+                Some(stack) if stack.item == item => max_stack - stack.count,
+                Some(_) => 0,
+                None => max_stack,
+            })
+            .sum::<i64>();
+        if room < count {
+            return Err(format!(
+                "There isn't enough room in the inventory for {} {}, only {} fit",
+                count, item, room
+            ));
+        }
+
+        let mut left = count;
+        for stack in slots.iter_mut().flatten() {
+            if stack.item == item {
+                let added = left.min(max_stack - stack.count);
+                stack.count += added;
+                left -= added;
+            }
+        }
+        for slot in slots.iter_mut().filter(|slot| slot.is_none()) {
+            if left == 0 {
+                break;
+            }
+
+            let added = left.min(max_stack);
+            *slot = Some(Stack {
+                item: item.to_string(),
+                count: added,
+            });
+            left -= added;
+        }
+
+        Ok(())
+    }
+
+    /// Moves the stack in a slot to another slot. The stack is merged into the
+    /// other slot if it has the same item, leaving whatever doesn't fit behind.
+    ///
+    /// # Arguments
+    ///
+    /// * `slots` - The slots of the inventory.
+    /// * `from` - The slot to move the stack from.
+    /// * `to` - The slot to move the stack to.
+    fn move_stack(
+        &self,
+        slots: &mut [Option<Stack>],
+        from: usize,
+        to: usize,
+    ) -> Result<(), String> {
+        if from == to {
+            return Ok(());
+        }
+
+        let moved = slots[from]
+            .clone()
+            .ok_or_else(|| format!("There is no item in the slot {} to move", from))?;
+
+        match slots[to].clone() {
+            None => {
+                slots[to] = Some(moved);
+                slots[from] = None;
+            }
+            Some(target) if target.item == moved.item => {
+                let max_stack = self.max_stack(&moved.item);
+                let merged = moved.count.min(max_stack - target.count);
+                let left = moved.count - merged;
+
+                slots[to] = Some(Stack {
+                    count: target.count + merged,
+                    ..target
+                });
+                slots[from] = if left > 0 {
+                    Some(Stack {
+                        count: left,
+                        ..moved
+                    })
+                } else {
+                    None
+                };
+            }
+            Some(target) => {
+                return Err(format!(
+                    "Unable to move {} onto {} in the slot {}, use swap_items to swap them",
+                    moved.item, target.item, to
+                ))
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Splits part of a stack into another slot, which is the first empty slot
+    /// unless the action has one under `to`. Half of the stack is split off if
+    /// the action doesn't have a `count`.
+    ///
+    /// # Arguments
+    ///
+    /// * `slots` - The slots of the inventory.
+    /// * `action` - The `split_stack` action.
+    fn split(&self, slots: &mut [Option<Stack>], action: &Variant) -> Result<(), String> {
+        let from = self.slot_index(action, SLOT_KEY)?;
+        let stack = slots[from]
+            .clone()
+            .ok_or_else(|| format!("There is no item in the slot {} to split", from))?;
+
+        let count = match action::field(action, COUNT_KEY) {
+            Some(count) => count.to_i64(),
+            None => stack.count / 2,
+        };
+        if count < 1 || count >= stack.count {
+            return Err(format!(
+                "Unable to split {} off of a stack of {} {}",
+                count, stack.count, stack.item
+            ));
+        }
+
+        let to = match action::field(action, TO_KEY) {
+            Some(_) => self.slot_index(action, TO_KEY)?,
+            None => slots
+                .iter()
+                .position(|slot| slot.is_none())
+                .ok_or_else(|| "There is no empty slot to split the stack into".to_string())?,
+        };
+        if slots[to].is_some() {
+            return Err(format!("The slot {} has to be empty to split into", to));
+        }
+
+        slots[to] = Some(Stack {
+            item: stack.item.clone(),
+            count,
+        });
+        slots[from] = Some(Stack {
+            count: stack.count - count,
+            ..stack
+        });
+
+        Ok(())
+    }
+
+    /// Returns the most items of a kind that fit in one slot, which is 1 for
+    /// an item that isn't defined.
+    ///
+    /// # Arguments
+    ///
+    /// * `item` - The name of the item.
+    fn max_stack(&self, item: &str) -> i64 {
+        self.items
+            .get(item)
+            .map_or(1, |definition| definition.max_stack)
+    }
+
+    /// Returns the slots in the slice, with any missing slots empty.
+    ///
+    /// # Arguments
+    ///
+    /// * `slice` - The slice.
+    fn slots(&self, slice: &Dictionary) -> Vec<Option<Stack>> {
+        let mut slots = parse_slots(slice);
+        slots.resize(self.capacity.max(slots.len()), None);
+
+        slots
+    }
+
+    /// Returns the item and the number of items of an item action, where the
+    /// number is 1 if the action doesn't have a `count`.
+    ///
+    /// # Arguments
+    ///
+    /// * `action` - The item action.
+    fn item_and_count(&self, action: &Variant) -> Result<(String, i64), String> {
+        let item = action::field(action, ITEM_KEY)
+            .ok_or_else(|| format!("The action {} is missing its item", action.to_string()))?
+            .to_string();
+        if !self.items.contains_key(&item) {
+            return Err(format!("The item {} isn't defined in the inventory", item));
+        }
+
+        let count = action::field(action, COUNT_KEY).map_or(1, |count| count.to_i64());
+        if count < 1 {
+            return Err(format!(
+                "The count of {} has to be at least 1, got {}",
+                item, count
+            ));
+        }
+
+        Ok((item, count))
+    }
+
+    /// Returns the slot under a key of a slot action.
+    ///
+    /// # Arguments
+    ///
+    /// * `action` - The slot action.
+    /// * `key` - The key of the slot.
+    fn slot_index(&self, action: &Variant, key: &str) -> Result<usize, String> {
+        let slot = action::field(action, key)
+            .ok_or_else(|| format!("The action {} is missing its {}", action.to_string(), key))?
+            .to_i64();
+
+        if slot < 0 || slot as usize >= self.capacity {
+            return Err(format!(
+                "The slot {} is outside of the inventory, which has {} slots",
+                slot, self.capacity
+            ));
+        }
+
+        Ok(slot as usize)
+    }
+
+    /// Returns every stack of an item with the tag, as Dictionaries
+    /// with the slot under `slot`, the item under `item`, and the number of
+    /// items under `count`.
+    ///
+    /// # Arguments
+    ///
+    /// * `slice` - The slice.
+    /// * `tag` - The tag to look for.
+    pub fn items_by_tag(&self, slice: &Dictionary, tag: &str) -> VariantArray<Unique> {
+        let found = VariantArray::new();
+
+        for (index, slot) in parse_slots(slice).into_iter().enumerate() {
+            if let Some(stack) = slot {
+                let tagged = self
+                    .items
+                    .get(&stack.item)
+                    .is_some_and(|definition| definition.tags.iter().any(|t| t == tag));
+
+                if tagged {
+                    let entry = stack_dictionary(&stack);
+                    entry.insert(SLOT_KEY, index as i64);
+                    found.push(entry);
+                }
+            }
+        }
+
+        found
+    }
+}
+
+/// Returns the total number of an item across every slot.
+///
+/// # Arguments
+///
+/// * `slice` - The slice.
+/// * `item` - The name of the item.
+pub fn item_count(slice: &Dictionary, item: &str) -> i64 {
+    parse_slots(slice)
+        .iter()
+        .flatten()
+        .filter(|stack| stack.item == item)
+        .map(|stack| stack.count)
+        .sum()
+}
+
+/// Removes items from the last stacks of the item first.
+///
+/// # Arguments
+///
+/// * `slots` - The slots of the inventory.
+/// * `item` - The name of the item.
+/// * `count` - The number of items to remove.
+fn remove(slots: &mut [Option<Stack>], item: &str, count: i64) -> Result<(), String> {
+    let held = slots
+        .iter()
+        .flatten()
+        .filter(|stack| stack.item == item)
+        .map(|stack| stack.count)
+        .sum::<i64>();
+    if held < count {
+        return Err(format!(
+            "Unable to remove {} {} from the inventory, it only has {}",
+            count, item, held
+        ));
+    }
+
+    let mut left = count;
+    for slot in slots.iter_mut().rev() {
+        if left == 0 {
+            break;
+        }
+
+        if let Some(stack) = slot {
+            if stack.item == item {
+                let removed = left.min(stack.count);
+                stack.count -= removed;
+                left -= removed;
+
+                if stack.count == 0 {
+                    *slot = None;
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Parses an item definition.
+///
+/// # Arguments
+///
+/// * `name` - The name of the item.
+/// * `definition` - The Dictionary of the item.
+fn parse_item(name: &str, definition: &Variant) -> Result<ItemDefinition, String> {
+    let definition = definition.try_to_dictionary().ok_or_else(|| {
+        format!(
+            "The definition of the item {} has to be a Dictionary, got {}",
+            name,
+            definition.to_string()
+        )
+    })?;
+
+    let max_stack = path::get(&definition, MAX_STACK_KEY).map_or(1, |max| max.to_i64());
+    if max_stack < 1 {
+        return Err(format!(
+            "The max_stack of the item {} has to be at least 1, got {}",
+            name, max_stack
+        ));
+    }
+
+    let tags = path::get(&definition, TAGS_KEY)
+        .and_then(|tags| tags.try_to_array())
+        .map(|tags| tags.iter().map(|tag| tag.to_string()).collect())
+        .unwrap_or_default();
+
+    Ok(ItemDefinition { max_stack, tags })
+}
+
+/// Returns the slots in the slice, where an empty slot is `null` and a slot
+/// with items is a Dictionary with the item under `item` and the number of
+/// items under `count`.
+///
+/// # Arguments
+///
+/// * `slice` - The slice.
+fn parse_slots(slice: &Dictionary) -> Vec<Option<Stack>> {
+    let slots = path::get(slice, SLOTS_KEY)
+        .and_then(|slots| slots.try_to_array())
+        .unwrap_or_else(VariantArray::new_shared);
+
+    slots
+        .iter()
+        .map(|slot| {
+            let slot = slot.try_to_dictionary()?;
+            let item = path::get(&slot, ITEM_KEY)?.to_string();
+            let count = path::get(&slot, COUNT_KEY)?.to_i64();
+
+            Some(Stack { item, count }).filter(|stack| stack.count > 0)
+        })
+        .collect()
+}
+
+/// Creates the slice.
+///
+/// # Arguments
+///
+/// * `slots` - The slots of the inventory.
+fn slice(slots: &[Option<Stack>]) -> Dictionary<Unique> {
+    let array = VariantArray::new();
+    for slot in slots {
+        match slot {
+            Some(stack) => array.push(stack_dictionary(stack)),
+            None => array.push(Variant::new()),
+        }
+    }
+
+    let slice = Dictionary::new();
+    slice.insert(SLOTS_KEY, array);
+
+    slice
+}
+
+/// Returns the Dictionary of a stack.
+///
+/// # Arguments
+///
+/// * `stack` - The stack.
+fn stack_dictionary(stack: &Stack) -> Dictionary<Unique> {
+    let dictionary = Dictionary::new();
+    dictionary.insert(ITEM_KEY, &stack.item);
+    dictionary.insert(COUNT_KEY, stack.count);
+
+    dictionary
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{remove, Inventory, ItemDefinition, Stack};
+
+    /// Creates an inventory with 3 slots where potions stack up to 5 and
+    /// swords don't stack.
+    fn inventory() -> Inventory {
+        let items = vec![("potion", 5), ("sword", 1)]
+            .into_iter()
+            .map(|(name, max_stack)| {
+                let definition = ItemDefinition {
+                    max_stack,
+                    tags: vec![],
+                };
+                (name.to_string(), definition)
+            })
+            .collect();
+
+        Inventory { capacity: 3, items }
+    }
+
+    /// Creates slots from items and counts, where `None` is an empty slot.
+    fn slots(stacks: &[Option<(&str, i64)>]) -> Vec<Option<Stack>> {
+        stacks
+            .iter()
+            .map(|stack| {
+                stack.map(|(item, count)| Stack {
+                    item: item.to_string(),
+                    count,
+                })
+            })
+            .collect()
+    }
+
+    /// Returns the items and counts in slots, where `None` is an empty slot.
+    fn contents(slots: &[Option<Stack>]) -> Vec<Option<(&str, i64)>> {
+        slots
+            .iter()
+            .map(|slot| {
+                slot.as_ref()
+                    .map(|stack| (stack.item.as_str(), stack.count))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn add_fills_the_stacks_of_the_item_before_empty_slots() {
+        let mut slots = slots(&[None, Some(("potion", 3)), Some(("sword", 1))]);
+
+        inventory().add(&mut slots, "potion", 4).unwrap();
+
+        assert_eq!(
+            contents(&slots),
+            vec![Some(("potion", 2)), Some(("potion", 5)), Some(("sword", 1))]
+        );
+    }
+
+    #[test]
+    fn add_leaves_the_slots_alone_when_the_items_dont_fit() {
+        let mut slots = slots(&[None, Some(("potion", 3)), Some(("sword", 1))]);
+
+        assert!(inventory().add(&mut slots, "potion", 8).is_err());
+        assert_eq!(
+            contents(&slots),
+            vec![None, Some(("potion", 3)), Some(("sword", 1))]
+        );
+    }
+
+    #[test]
+    fn remove_takes_from_the_last_stacks_first() {
+        let mut slots = slots(&[Some(("potion", 5)), Some(("sword", 1)), Some(("potion", 2))]);
+
+        remove(&mut slots, "potion", 4).unwrap();
+
+        assert_eq!(
+            contents(&slots),
+            vec![Some(("potion", 3)), Some(("sword", 1)), None]
+        );
+        assert!(remove(&mut slots, "potion", 4).is_err());
+        assert_eq!(contents(&slots)[0], Some(("potion", 3)));
+    }
+
+    #[test]
+    fn move_stack_merges_into_the_same_item_and_leaves_the_rest() {
+        let mut slots = slots(&[Some(("potion", 4)), Some(("potion", 3)), None]);
+
+        inventory().move_stack(&mut slots, 0, 1).unwrap();
+
+        assert_eq!(
+            contents(&slots),
+            vec![Some(("potion", 2)), Some(("potion", 5)), None]
+        );
+    }
+
+    #[test]
+    fn move_stack_refuses_to_move_onto_another_item() {
+        let mut slots = slots(&[Some(("potion", 4)), Some(("sword", 1)), None]);
+
+        assert!(inventory().move_stack(&mut slots, 0, 1).is_err());
+        assert!(inventory().move_stack(&mut slots, 2, 0).is_err());
+        assert_eq!(
+            contents(&slots),
+            vec![Some(("potion", 4)), Some(("sword", 1)), None]
+        );
+    }
+}
@@ -1,8 +1,83 @@
+mod action;
+mod action_alias;
+mod audio;
+mod audit;
+mod background_save;
+mod call_budget;
+mod cheat;
+mod clock;
+mod codec;
+mod compression;
+mod console;
+mod content_filter;
+mod content_pack;
+mod cooldown;
+mod dedupe;
+mod devtools;
+mod dialogue;
+mod diff;
+mod dispatch_queue;
+mod effect_binding;
+mod effect_pipeline;
+mod equality;
+mod error_policy;
+mod event_log;
+mod feature_flag;
+mod file;
+mod form;
+mod fsm;
+mod fuzz;
 mod godot_redux;
+mod godot_redux_factory;
+mod godot_redux_node;
+mod history;
+mod input_recording;
+mod interpolation;
+mod inventory;
+mod logging;
+mod math_type;
+mod merge;
+mod middleware;
+mod migration;
+mod mock_godot_redux;
+mod patch;
+mod path;
+mod pause;
+mod persistence;
+mod preload;
+mod protection;
+mod query;
+mod quest;
+mod random;
+mod router;
+mod schema;
+mod scrub;
+mod selector;
+mod session_analytics;
+mod snapshot;
+mod stats;
+mod storage;
+mod store_registry;
+mod subscriber;
+mod subscription_spy;
+mod telemetry;
+mod testing;
+mod threadsafe_dispatch;
+mod tick;
+mod undo;
+mod view_model;
+mod window;
 use gdnative::prelude::*;
 
 fn init(handle: InitHandle) {
     handle.add_class::<godot_redux::GodotRedux>();
+    handle.add_class::<mock_godot_redux::MockGodotRedux>();
+    handle.add_class::<godot_redux_factory::GodotReduxFactory>();
+    handle.add_class::<godot_redux_node::GodotReduxNode>();
+    handle.add_class::<store_registry::StoreRegistry>();
+    handle.add_class::<subscription_spy::SubscriptionSpy>();
+    handle.add_class::<middleware::MiddlewareNext>();
+    handle.add_class::<threadsafe_dispatch::ThreadSafeDispatcher>();
 }
 
 godot_init!(init);
@@ -0,0 +1,152 @@
+use gdnative::prelude::{Dictionary, Variant, VariantArray};
+
+use crate::action::action_type;
+use crate::{path, snapshot};
+
+/// How much is logged for each action.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Verbosity {
+    /// Nothing is logged.
+    Off,
+    /// Only the type of the action is logged.
+    Types,
+    /// The whole action is logged.
+    Actions,
+    /// The whole action is logged along with the state before and after it.
+    States,
+}
+
+impl Verbosity {
+    /// Returns the verbosity with a name.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the verbosity.
+    pub fn from_name(name: &str) -> Result<Self, String> {
+        match name {
+            "off" => Ok(Verbosity::Off),
+            "types" => Ok(Verbosity::Types),
+            "actions" => Ok(Verbosity::Actions),
+            "" | "states" => Ok(Verbosity::States),
+            other => Err(format!(
+                "The verbosity has to be off, types, actions, or states, got {}",
+                other
+            )),
+        }
+    }
+}
+
+/// The logger enabled with `enable_logging`, which prints the actions that
+/// are dispatched to the output along with the states they lead to.
+pub struct Logger {
+    /// How much is logged for each action.
+    verbosity: Verbosity,
+    /// Whether actions and states are printed as indented JSON with sorted
+    /// keys instead of on a single line.
+    pretty: bool,
+    /// The paths of the state that are logged, or none to log the whole
+    /// state.
+    paths: Vec<String>,
+    /// The types of the actions that aren't logged, as strings.
+    ignored: Vec<String>,
+}
+
+impl Logger {
+    /// Creates a logger from the options passed to `enable_logging`.
+    ///
+    /// # Arguments
+    ///
+    /// * `verbosity` - How much is logged for each action.
+    /// * `options` - The options, with whether to pretty-print under
+    /// `pretty`, the paths of the state to log under `paths`, and the types
+    /// of the actions that aren't logged under `ignored_types`.
+    pub fn new(verbosity: Verbosity, options: &Dictionary) -> Self {
+        let strings = |key: &str| {
+            path::get(options, key)
+                .and_then(|value| value.try_to_array())
+                .unwrap_or_else(VariantArray::new_shared)
+                .iter()
+                .map(|value| value.to_string())
+                .collect()
+        };
+
+        Logger {
+            verbosity,
+            pretty: path::get(options, "pretty").is_some_and(|pretty| pretty.to_bool()),
+            paths: strings("paths"),
+            ignored: strings("ignored_types"),
+        }
+    }
+
+    /// Returns the lines to print for an action, which are none if the type
+    /// of the action is ignored.
+    ///
+    /// # Arguments
+    ///
+    /// * `action` - The action that was dispatched.
+    /// * `old_state` - The state before the action.
+    /// * `new_state` - The state after the action.
+    pub fn lines(
+        &self,
+        action: &Variant,
+        old_state: &Dictionary,
+        new_state: &Dictionary,
+    ) -> Vec<String> {
+        let action_type = action_type(action);
+        if self.verbosity == Verbosity::Off || self.ignored.contains(&action_type.to_string()) {
+            return vec![];
+        }
+
+        if self.verbosity == Verbosity::Types {
+            return vec![format!("[action] {}", action_type.to_string())];
+        }
+
+        let mut lines = vec![format!("[action] {}", self.text(action))];
+        if self.verbosity == Verbosity::States {
+            lines.push(format!(
+                "[prev state] {}",
+                self.text(&self.filtered(old_state))
+            ));
+            lines.push(format!(
+                "[next state] {}",
+                self.text(&self.filtered(new_state))
+            ));
+        }
+
+        lines
+    }
+
+    /// Returns the part of a state that is logged, which is the whole state
+    /// or the values at the logged paths, by path.
+    ///
+    /// # Arguments
+    ///
+    /// * `state` - The state.
+    fn filtered(&self, state: &Dictionary) -> Variant {
+        if self.paths.is_empty() {
+            return Variant::from_dictionary(state);
+        }
+
+        let filtered = Dictionary::new();
+        for logged in &self.paths {
+            if let Some(value) = path::get(state, logged) {
+                filtered.insert(logged.as_str(), value);
+            }
+        }
+
+        Variant::from_dictionary(&filtered.into_shared())
+    }
+
+    /// Returns the text a value is printed as.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The value.
+    fn text(&self, value: &Variant) -> String {
+        if self.pretty {
+            snapshot::to_stable_text(value)
+        } else {
+            value.to_string()
+        }
+    }
+}
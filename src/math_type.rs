@@ -0,0 +1,162 @@
+use gdnative::prelude::{
+    Color, Dictionary, OwnedToVariant, Unique, Variant, VariantArray, VariantType, Vector2, Vector3,
+};
+
+/// The key of the name of the type in a Dictionary that stands in for a
+/// `Vector2`, `Vector3`, or `Color` in JSON and MessagePack.
+pub const TYPE_KEY: &str = "__type";
+
+/// The engine math types that are kept as their components, along with the
+/// names of their components, which are the same as in GDScript.
+const TYPES: &[(&str, VariantType, &[&str])] = &[
+    ("Vector2", VariantType::Vector2, &["x", "y"]),
+    ("Vector3", VariantType::Vector3, &["x", "y", "z"]),
+    ("Color", VariantType::Color, &["r", "g", "b", "a"]),
+];
+
+/// Returns the names and values of the components of a `Vector2`, `Vector3`,
+/// or `Color`, or `None` if the value is none of them.
+///
+/// # Arguments
+///
+/// * `value` - The value.
+pub fn components(value: &Variant) -> Option<Vec<(&'static str, f32)>> {
+    let values = match value.get_type() {
+        VariantType::Vector2 => {
+            let vector = value.to_vector2();
+            vec![vector.x, vector.y]
+        }
+        VariantType::Vector3 => {
+            let vector = value.to_vector3();
+            vec![vector.x, vector.y, vector.z]
+        }
+        VariantType::Color => {
+            let color = value.to_color();
+            vec![color.r, color.g, color.b, color.a]
+        }
+        _ => return None,
+    };
+    let (_, _, names) = TYPES
+        .iter()
+        .find(|(_, kind, _)| *kind == value.get_type())?;
+
+    Some(names.iter().copied().zip(values).collect())
+}
+
+/// Returns a component of a `Vector2`, `Vector3`, or `Color` by its name, like
+/// `x` or `a`, or `None` if the value is none of them or has no component
+/// with the name.
+///
+/// # Arguments
+///
+/// * `value` - The value.
+/// * `name` - The name of the component.
+pub fn component(value: &Variant, name: &str) -> Option<Variant> {
+    components(value)?
+        .into_iter()
+        .find(|(component, _)| *component == name)
+        .map(|(_, component)| Variant::from_f64(component as f64))
+}
+
+/// Returns the Dictionary that stands in for a `Vector2`, `Vector3`, or
+/// `Color` in formats that have no representation for them, with the name of
+/// the type under `__type` and each component under its name, or `None` if
+/// the value is none of them.
+///
+/// # Arguments
+///
+/// * `value` - The value.
+pub fn to_tagged(value: &Variant) -> Option<Dictionary<Unique>> {
+    let components = components(value)?;
+    let (name, _, _) = TYPES
+        .iter()
+        .find(|(_, kind, _)| *kind == value.get_type())?;
+
+    let tagged = Dictionary::new();
+    tagged.insert(TYPE_KEY, *name);
+    for (component, value) in components {
+        tagged.insert(component, widen(value));
+    }
+
+    Some(tagged)
+}
+
+/// Returns the `Vector2`, `Vector3`, or `Color` a Dictionary made by
+/// `to_tagged` stands in for, or `None` if it isn't one.
+///
+/// # Arguments
+///
+/// * `tagged` - The Dictionary.
+pub fn from_tagged(tagged: &Dictionary) -> Option<Variant> {
+    if !tagged.contains(TYPE_KEY) {
+        return None;
+    }
+
+    let name = tagged.get(TYPE_KEY).try_to_string()?;
+    let (_, kind, names) = TYPES.iter().find(|(type_name, _, _)| *type_name == name)?;
+    if tagged.len() as usize != names.len() + 1 {
+        return None;
+    }
+
+    let values = names
+        .iter()
+        .map(|component| {
+            let value = tagged.get(*component);
+            match value.get_type() {
+                VariantType::I64 | VariantType::F64 => Some(value.to_f64() as f32),
+                _ => None,
+            }
+        })
+        .collect::<Option<Vec<_>>>()?;
+
+    Some(match kind {
+        VariantType::Vector2 => Variant::from_vector2(&Vector2::new(values[0], values[1])),
+        VariantType::Vector3 => {
+            Variant::from_vector3(&Vector3::new(values[0], values[1], values[2]))
+        }
+        _ => Variant::from_color(&Color::rgba(values[0], values[1], values[2], values[3])),
+    })
+}
+
+/// Returns a value with every Dictionary made by `to_tagged` in it, however
+/// deeply it's nested, turned back into the `Vector2`, `Vector3`, or `Color`
+/// it stands in for. Dictionaries and Arrays are copied.
+///
+/// # Arguments
+///
+/// * `value` - The value, like the state read back from JSON.
+pub fn revive(value: &Variant) -> Variant {
+    match value.get_type() {
+        VariantType::Dictionary => {
+            let dictionary = value.to_dictionary();
+            if let Some(revived) = from_tagged(&dictionary) {
+                return revived;
+            }
+
+            let copy = Dictionary::new();
+            for (key, value) in dictionary.iter() {
+                copy.insert(key, revive(&value));
+            }
+            copy.owned_to_variant()
+        }
+        VariantType::VariantArray => {
+            let copy = VariantArray::new();
+            for value in value.to_array().iter() {
+                copy.push(revive(&value));
+            }
+            copy.owned_to_variant()
+        }
+        _ => value.clone(),
+    }
+}
+
+/// Returns the shortest number that reads back as the same `f32`, so a
+/// component like 0.1 is written as 0.1 instead of the 0.10000000149011612
+/// it widens to.
+///
+/// # Arguments
+///
+/// * `value` - The component.
+fn widen(value: f32) -> f64 {
+    value.to_string().parse().unwrap_or(value as f64)
+}
@@ -0,0 +1,161 @@
+use std::collections::BTreeMap;
+
+use gdnative::prelude::{Dictionary, OwnedToVariant, Unique, Variant};
+
+/// How a slice of state that is loaded or imported is combined with the
+/// slice the store already has.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Strategy {
+    /// The loaded slice replaces the one the store has, and a slice that
+    /// isn't loaded is dropped.
+    Replace,
+    /// The keys of the loaded slice replace the same keys of the one the
+    /// store has, keeping the rest of them.
+    ShallowMerge,
+    /// Like `ShallowMerge`, but Dictionaries in both slices are merged the
+    /// same way however deeply they are nested.
+    DeepMerge,
+    /// Only keys that the store doesn't have yet are added, however deeply
+    /// they are nested, and everything the store has is kept.
+    KeepExisting,
+}
+
+impl Strategy {
+    /// Returns the strategy with a name, or `Replace` if the name is empty.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the strategy.
+    pub fn from_name(name: &str) -> Result<Self, String> {
+        match name {
+            "" | "replace" => Ok(Strategy::Replace),
+            "shallow_merge" => Ok(Strategy::ShallowMerge),
+            "deep_merge" => Ok(Strategy::DeepMerge),
+            "keep_existing" => Ok(Strategy::KeepExisting),
+            other => Err(format!(
+                "The merge strategy has to be replace, shallow_merge, deep_merge, or keep_existing, got {}",
+                other
+            )),
+        }
+    }
+}
+
+/// The strategies used to combine a loaded or imported state with the state
+/// the store has, for the whole state and for single slices.
+pub struct Merge {
+    /// The strategy of the slices that don't have one of their own.
+    default: Strategy,
+    /// The strategies of single slices, by key.
+    slices: BTreeMap<String, Strategy>,
+}
+
+impl Merge {
+    /// Creates the strategies for a load or import.
+    ///
+    /// # Arguments
+    ///
+    /// * `default` - The name of the strategy for the whole state.
+    /// * `slices` - The names of the strategies of single slices, by key.
+    pub fn new(default: &str, slices: &Dictionary) -> Result<Self, String> {
+        let slices = slices
+            .iter()
+            .map(|(slice, strategy)| {
+                Ok((
+                    slice.to_string(),
+                    Strategy::from_name(&strategy.to_string())?,
+                ))
+            })
+            .collect::<Result<BTreeMap<_, _>, String>>()?;
+
+        Ok(Merge {
+            default: Strategy::from_name(default)?,
+            slices,
+        })
+    }
+
+    /// Creates the strategies for a load that replaces the whole state.
+    pub fn replace() -> Self {
+        Merge {
+            default: Strategy::Replace,
+            slices: BTreeMap::new(),
+        }
+    }
+
+    /// Returns the state that combining a loaded or imported state with the
+    /// current one leads to.
+    ///
+    /// # Arguments
+    ///
+    /// * `current` - The state the store has.
+    /// * `incoming` - The state being loaded or imported.
+    pub fn apply(&self, current: &Dictionary, incoming: &Dictionary) -> Dictionary<Unique> {
+        let state = Dictionary::new();
+        for (slice, value) in current.iter() {
+            if incoming.contains(&slice) || self.strategy_of(&slice) != Strategy::Replace {
+                state.insert(slice, value);
+            }
+        }
+
+        for (slice, value) in incoming.iter() {
+            if !current.contains(&slice) {
+                state.insert(slice, value);
+                continue;
+            }
+
+            let merged = combine(&current.get(&slice), &value, self.strategy_of(&slice));
+            state.insert(slice, merged);
+        }
+
+        state
+    }
+
+    /// Returns the strategy of a slice.
+    ///
+    /// # Arguments
+    ///
+    /// * `slice` - The key of the slice.
+    fn strategy_of(&self, slice: &Variant) -> Strategy {
+        self.slices
+            .get(&slice.to_string())
+            .copied()
+            .unwrap_or(self.default)
+    }
+}
+
+/// Returns the value that combining a loaded or imported value with the
+/// current one leads to.
+///
+/// # Arguments
+///
+/// * `current` - The value the store has.
+/// * `incoming` - The value being loaded or imported.
+/// * `strategy` - How the values are combined.
+fn combine(current: &Variant, incoming: &Variant, strategy: Strategy) -> Variant {
+    if strategy == Strategy::Replace {
+        return incoming.clone();
+    }
+
+    let (current_dictionary, incoming_dictionary) =
+        match (current.try_to_dictionary(), incoming.try_to_dictionary()) {
+            (Some(current), Some(incoming)) => (current, incoming),
+            _ if strategy == Strategy::KeepExisting => return current.clone(),
+            _ => return incoming.clone(),
+        };
+
+    let merged = current_dictionary.duplicate();
+    for (key, value) in incoming_dictionary.iter() {
+        if !current_dictionary.contains(&key) {
+            merged.insert(key, value);
+            continue;
+        }
+
+        if strategy == Strategy::ShallowMerge {
+            merged.insert(key, value);
+        } else {
+            let combined = combine(&current_dictionary.get(&key), &value, strategy);
+            merged.insert(key, combined);
+        }
+    }
+
+    merged.owned_to_variant()
+}
@@ -0,0 +1,408 @@
+use std::cell::Cell;
+
+use gdnative::prelude::{
+    godot_error, methods, Dictionary, FuncRef, NativeClass, Object, Ref, Reference, Shared,
+    ThreadLocal, Variant,
+};
+
+/// What a middleware function is passed along with the state and the action.
+#[derive(Clone, Copy)]
+pub enum Convention {
+    /// Just the state and the action, for `add_middleware`.
+    Plain,
+    /// The context of the dispatch, for `add_context_middleware`.
+    Context,
+    /// The store, so the function can dispatch other actions right away or
+    /// later on, for `add_thunk_middleware`.
+    Store,
+    /// A `MiddlewareNext` that continues the chain, for
+    /// `add_next_middleware`.
+    Next,
+}
+
+/// A middleware function added to a store.
+pub struct Middleware {
+    /// The function.
+    pub function: Ref<FuncRef, ThreadLocal>,
+    /// What the function is passed along with the state and the action.
+    pub convention: Convention,
+    /// Whether the middleware has side effects, so it's skipped when actions
+    /// aren't really happening.
+    pub side_effects: bool,
+    /// The name of the function along with the instance it's on.
+    pub identity: String,
+}
+
+/// The middleware of a store in the order it was added, each with the id it
+/// was given. An action looks up the middleware after the one it's in by id
+/// every time it moves along instead of going over a copy of the list, so
+/// middleware that is removed or cleared in the middle of a dispatch, by a
+/// middleware function or a subscriber, is skipped from then on while the
+/// middleware the action is in finishes as usual.
+pub struct Chain<T> {
+    /// The ids and the middleware, in the order they were added.
+    entries: Vec<(i64, T)>,
+}
+
+impl<T> Default for Chain<T> {
+    fn default() -> Self {
+        Chain { entries: vec![] }
+    }
+}
+
+impl<T> Chain<T> {
+    /// Adds middleware to the end of the chain. Ids have to be larger than
+    /// the ids of the middleware added before.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The id of the middleware.
+    /// * `middleware` - The middleware.
+    pub fn push(&mut self, id: i64, middleware: T) {
+        self.entries.push((id, middleware));
+    }
+
+    /// Removes the middleware with an id and returns whether there was any.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The id of the middleware.
+    pub fn remove(&mut self, id: i64) -> bool {
+        let count = self.entries.len();
+        self.entries.retain(|(added, _)| *added != id);
+
+        self.entries.len() != count
+    }
+
+    /// Removes every middleware.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Returns the middleware with an id, if there is any.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The id of the middleware.
+    pub fn get_mut(&mut self, id: i64) -> Option<&mut T> {
+        self.entries
+            .iter_mut()
+            .find(|(added, _)| *added == id)
+            .map(|(_, middleware)| middleware)
+    }
+
+    /// Returns the first middleware after the one with an id, along with its
+    /// id, that isn't skipped.
+    ///
+    /// # Arguments
+    ///
+    /// * `after` - The id of the middleware the action is in, or 0 for the
+    /// first one.
+    /// * `skipped` - Returns whether a middleware is skipped.
+    pub fn next_after(&self, after: i64, skipped: impl Fn(&T) -> bool) -> Option<(i64, &T)> {
+        self.entries
+            .iter()
+            .find(|(id, middleware)| *id > after && !skipped(middleware))
+            .map(|(id, middleware)| (*id, middleware))
+    }
+
+    /// Returns the first middleware after the one with an id that runs, along
+    /// with its id. Middleware with side effects is skipped during dry runs,
+    /// when actions aren't really happening.
+    ///
+    /// # Arguments
+    ///
+    /// * `after` - The id of the middleware the action is in, or 0 for the
+    /// first one.
+    /// * `dry_run` - Whether the action is dispatched in a dry run.
+    /// * `side_effects` - Returns whether a middleware has side effects.
+    pub fn next_to_run(
+        &self,
+        after: i64,
+        dry_run: bool,
+        side_effects: impl Fn(&T) -> bool,
+    ) -> Option<(i64, &T)> {
+        self.next_after(after, |middleware| dry_run && side_effects(middleware))
+    }
+
+    /// Returns the ids and the middleware, in the order they were added.
+    pub fn iter(&self) -> impl Iterator<Item = (i64, &T)> {
+        self.entries
+            .iter()
+            .map(|(id, middleware)| (*id, middleware))
+    }
+
+    /// Returns the number of middleware.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns whether there's no middleware.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// The dispatch a `next` function was created in, so a `next` that is kept
+/// around and called from another dispatch can be told apart from one that
+/// continues its own.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Origin {
+    /// The correlation id of the dispatch.
+    pub correlation: Option<u64>,
+    /// How many dispatches were nested when it was created, or 0 if nothing
+    /// is being dispatched.
+    pub depth: usize,
+}
+
+impl Origin {
+    /// Returns whether a `next` function created in this dispatch is stale
+    /// when it's called while another dispatch is in progress. Calling it once
+    /// nothing is being dispatched isn't stale, since that's how a middleware
+    /// that yielded continues the chain.
+    ///
+    /// # Arguments
+    ///
+    /// * `current` - The dispatch in progress when the function is called.
+    pub fn is_stale(&self, current: Origin) -> bool {
+        current.depth != 0 && *self != current
+    }
+}
+
+/// The `next` function passed to middleware added with `add_next_middleware`.
+/// Calling it runs the rest of the middleware and the reducer with an action
+/// and returns what they returned. It has a `call_func` method like a FuncRef
+/// so it can be called the same way as one.
+#[inherit(Reference)]
+#[derive(NativeClass)]
+#[no_constructor]
+pub struct MiddlewareNext {
+    /// The store the chain belongs to.
+    store: Ref<Object, Shared>,
+    /// The id of the middleware the function was passed to, so the chain
+    /// continues with the middleware after it.
+    after: i64,
+    /// The context of the dispatch.
+    context: Dictionary,
+    /// The dispatch the function was created in.
+    origin: Origin,
+    /// Whether the function was called already.
+    called: Cell<bool>,
+}
+
+#[methods]
+impl MiddlewareNext {
+    /// Creates the function that continues the chain after a middleware.
+    ///
+    /// # Arguments
+    ///
+    /// * `store` - The store the chain belongs to.
+    /// * `after` - The id of the middleware the function is passed to.
+    /// * `context` - The context of the dispatch.
+    /// * `origin` - The dispatch the function is created in.
+    pub fn create(
+        store: Ref<Object, Shared>,
+        after: i64,
+        context: Dictionary,
+        origin: Origin,
+    ) -> Self {
+        MiddlewareNext {
+            store,
+            after,
+            context,
+            origin,
+            called: Cell::new(false),
+        }
+    }
+
+    /// Runs the rest of the middleware and the reducer with an action and
+    /// returns what the next middleware returned, or the action if it reached
+    /// the reducer. It can only be called once since calling it again would
+    /// run the reducer twice for the same dispatch, and the store refuses it
+    /// if it's called while another dispatch is in progress.
+    ///
+    /// # Arguments
+    ///
+    /// * `action` - The action to pass on.
+    #[export]
+    fn call_func(&self, _owner: &Reference, action: Variant) -> Variant {
+        if self.called.replace(true) {
+            godot_error!(
+                "next was already called for this middleware, so {} wasn't passed on",
+                action.to_string()
+            );
+            return Variant::new();
+        }
+
+        match unsafe { self.store.assume_safe_if_sane() } {
+            Some(store) => unsafe {
+                store.call(
+                    "_continue_middleware",
+                    &[
+                        Variant::from_i64(self.after),
+                        action,
+                        Variant::from_dictionary(&self.context),
+                        match self.origin.correlation {
+                            Some(correlation) => Variant::from_u64(correlation),
+                            None => Variant::new(),
+                        },
+                        Variant::from_u64(self.origin.depth as u64),
+                    ],
+                )
+            },
+            None => {
+                godot_error!("Unable to pass on the action since the store was freed");
+                Variant::new()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use super::{Chain, Origin};
+
+    /// Creates a chain with the middleware `a`, `b`, and `c`, with the ids 1,
+    /// 2, and 3.
+    fn chain() -> RefCell<Chain<&'static str>> {
+        let mut chain = Chain::default();
+        for (id, middleware) in ["a", "b", "c"].iter().enumerate() {
+            chain.push(id as i64 + 1, *middleware);
+        }
+
+        RefCell::new(chain)
+    }
+
+    /// Moves an action along the chain the way a dispatch does, calling a
+    /// function for each middleware it reaches without holding on to the
+    /// chain, and returns the middleware that were reached.
+    fn walk(
+        chain: &RefCell<Chain<&'static str>>,
+        mut reached: impl FnMut(&RefCell<Chain<&'static str>>, &str),
+    ) -> Vec<&'static str> {
+        let mut walked = vec![];
+        let mut after = 0;
+        loop {
+            let next = chain
+                .borrow()
+                .next_after(after, |_| false)
+                .map(|(id, middleware)| (id, *middleware));
+            let (id, middleware) = match next {
+                Some(next) => next,
+                None => return walked,
+            };
+            walked.push(middleware);
+            reached(chain, middleware);
+            after = id;
+        }
+    }
+
+    #[test]
+    fn middleware_that_removes_itself_finishes_and_is_skipped_next_time() {
+        let chain = chain();
+        let walked = walk(&chain, |chain, middleware| {
+            if middleware == "b" {
+                assert!(chain.borrow_mut().remove(2));
+            }
+        });
+
+        assert_eq!(walked, ["a", "b", "c"]);
+        assert_eq!(walk(&chain, |_, _| {}), ["a", "c"]);
+    }
+
+    #[test]
+    fn middleware_removed_before_the_action_reaches_it_is_skipped() {
+        let chain = chain();
+        let walked = walk(&chain, |chain, middleware| {
+            if middleware == "a" {
+                assert!(chain.borrow_mut().remove(2));
+            }
+        });
+
+        assert_eq!(walked, ["a", "c"]);
+        assert_eq!(walk(&chain, |_, _| {}), ["a", "c"]);
+    }
+
+    #[test]
+    fn clearing_from_a_middleware_sends_the_action_to_the_reducer() {
+        let chain = chain();
+        let walked = walk(&chain, |chain, middleware| {
+            if middleware == "b" {
+                chain.borrow_mut().clear();
+            }
+        });
+
+        assert_eq!(walked, ["a", "b"]);
+        assert!(walk(&chain, |_, _| {}).is_empty());
+    }
+
+    #[test]
+    fn removing_from_a_subscriber_changes_the_next_dispatch() {
+        let chain = chain();
+
+        // Subscribers are run once the action went through the whole chain.
+        assert_eq!(walk(&chain, |_, _| {}), ["a", "b", "c"]);
+        assert!(chain.borrow_mut().remove(1));
+        assert!(!chain.borrow_mut().remove(1));
+        assert_eq!(walk(&chain, |_, _| {}), ["b", "c"]);
+
+        chain.borrow_mut().clear();
+        assert!(walk(&chain, |_, _| {}).is_empty());
+    }
+
+    #[test]
+    fn skipped_middleware_is_passed_over() {
+        let chain = chain();
+        let next = chain
+            .borrow()
+            .next_after(0, |middleware| *middleware == "a")
+            .map(|(id, middleware)| (id, *middleware));
+
+        assert_eq!(next, Some((2, "b")));
+    }
+
+    #[test]
+    fn next_is_only_refused_while_another_dispatch_is_in_progress() {
+        let origin = Origin {
+            correlation: Some(4),
+            depth: 2,
+        };
+        let idle = Origin {
+            correlation: None,
+            depth: 0,
+        };
+
+        assert!(!origin.is_stale(origin));
+        assert!(!origin.is_stale(idle));
+        assert!(origin.is_stale(Origin { depth: 3, ..origin }));
+        assert!(origin.is_stale(Origin {
+            correlation: Some(5),
+            ..origin
+        }));
+    }
+
+    #[test]
+    fn middleware_with_side_effects_only_runs_outside_of_dry_runs() {
+        let mut chain = Chain::default();
+        chain.push(1, ("normalize", false));
+        chain.push(2, ("analytics", true));
+        chain.push(3, ("logger", true));
+
+        let run = |dry_run| {
+            let mut ran = vec![];
+            let mut after = 0;
+            while let Some((id, (name, _))) =
+                chain.next_to_run(after, dry_run, |(_, side_effects)| *side_effects)
+            {
+                ran.push(*name);
+                after = id;
+            }
+            ran
+        };
+
+        assert_eq!(run(false), ["normalize", "analytics", "logger"]);
+        assert_eq!(run(true), ["normalize"]);
+    }
+}
@@ -0,0 +1,91 @@
+use std::collections::BTreeMap;
+
+use gdnative::api::FuncRef;
+use gdnative::prelude::{Dictionary, Ref, ThreadLocal, Variant};
+
+/// The functions registered with `register_migration` that upgrade a state
+/// saved with an older version of its shape, by the version they upgrade
+/// from. Each one upgrades the state by one version, so a save that is a few
+/// versions behind is upgraded by running them one after another.
+#[derive(Default)]
+pub struct Migrations {
+    /// The migrations, by the version they upgrade from.
+    migrations: BTreeMap<i64, Ref<FuncRef, ThreadLocal>>,
+}
+
+impl Migrations {
+    /// Registers the migration that upgrades a state from a version to the
+    /// next one, replacing the one that was registered for the version
+    /// before.
+    ///
+    /// # Arguments
+    ///
+    /// * `from_version` - The version the migration upgrades from.
+    /// * `migration` - The function that is passed the state and returns the
+    /// upgraded state.
+    pub fn register(&mut self, from_version: i64, migration: Ref<FuncRef, ThreadLocal>) {
+        self.migrations.insert(from_version, migration);
+    }
+
+    /// Returns the migrations that upgrade a state from a version to another
+    /// one, in the order they have to run in, along with the version each of
+    /// them upgrades from. Returns an error if the state is newer than the
+    /// version it's upgraded to or a migration along the way is missing.
+    ///
+    /// # Arguments
+    ///
+    /// * `from_version` - The version of the state.
+    /// * `to_version` - The version the state is upgraded to.
+    pub fn chain(
+        &self,
+        from_version: i64,
+        to_version: i64,
+    ) -> Result<Vec<(i64, Ref<FuncRef, ThreadLocal>)>, String> {
+        if from_version > to_version {
+            return Err(format!(
+                "The state has version {}, which is newer than version {}",
+                from_version, to_version
+            ));
+        }
+
+        (from_version..to_version)
+            .map(|version| match self.migrations.get(&version) {
+                Some(migration) => Ok((version, migration.clone())),
+                None => Err(format!(
+                    "There's no migration from version {} of the state",
+                    version
+                )),
+            })
+            .collect()
+    }
+
+    /// Drops every migration.
+    pub fn clear(&mut self) {
+        self.migrations.clear();
+    }
+}
+
+/// Runs migrations returned by `Migrations::chain` on a state and returns the
+/// upgraded state, or an error if a migration doesn't return a Dictionary.
+///
+/// # Arguments
+///
+/// * `state` - The state to upgrade.
+/// * `chain` - The migrations, along with the version each of them upgrades
+/// from.
+pub fn run(
+    state: Dictionary,
+    chain: &[(i64, Ref<FuncRef, ThreadLocal>)],
+) -> Result<Dictionary, String> {
+    chain.iter().try_fold(state, |state, (version, migration)| {
+        migration
+            .call_func(&[Variant::from_dictionary(&state)])
+            .try_to_dictionary()
+            .ok_or_else(|| {
+                format!(
+                    "The migration from version {} of the state didn't return a Dictionary",
+                    version
+                )
+            })
+    })
+}
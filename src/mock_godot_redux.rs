@@ -0,0 +1,210 @@
+use std::cell::RefCell;
+
+use gdnative::api::FuncRef;
+use gdnative::prelude::{
+    core_types::GodotString, methods, Dictionary, NativeClass, Object, Ref, Shared, ThreadLocal,
+    Unique, Variant, VariantArray,
+};
+
+use crate::godot_redux::func_ref;
+use crate::path;
+
+/// A stand-in for the store meant to be used in unit tests. It has the same
+/// methods as the store but never runs a reducer. Instead, every dispatched
+/// action is recorded so tests can check what was dispatched and the state is
+/// set directly with `set_state`.
+#[inherit(Object)]
+#[derive(NativeClass)]
+pub struct MockGodotRedux {
+    /// The state, which only changes when it is set with `set_state`.
+    state: RefCell<Dictionary>,
+    /// The middleware functions that are run for each dispatched action.
+    middleware: RefCell<Vec<Ref<FuncRef, ThreadLocal>>>,
+    /// The callback functions to run when the state is changed.
+    subscriptions: RefCell<Vec<Ref<FuncRef, ThreadLocal>>>,
+    /// Every action that has been dispatched, in the order it was dispatched.
+    dispatched_actions: RefCell<Vec<Variant>>,
+}
+
+#[methods]
+impl MockGodotRedux {
+    /// Initializes the struct with default values.
+    fn new(_owner: &Object) -> Self {
+        MockGodotRedux {
+            state: RefCell::new(Dictionary::new_shared()),
+            middleware: RefCell::new(vec![]),
+            subscriptions: RefCell::new(vec![]),
+            dispatched_actions: RefCell::new(vec![]),
+        }
+    }
+
+    /// Sets the initial state. The reducer is accepted so the mock can be used
+    /// anywhere the store is but it is never run.
+    ///
+    /// # Arguments
+    ///
+    /// * `initial_state` - The initial state of the application.
+    /// * `_reducer_fn_instance` - The instance on which the reducer exists.
+    /// * `_reducer_fn_name` - The name of the reducer function.
+    #[export]
+    fn set_state_and_reducer(
+        &self,
+        _owner: &Object,
+        initial_state: Dictionary,
+        _reducer_fn_instance: Ref<Object, Shared>,
+        _reducer_fn_name: GodotString,
+    ) {
+        *self.state.borrow_mut() = initial_state;
+
+        self.middleware.borrow_mut().clear();
+        self.subscriptions.borrow_mut().clear();
+        self.dispatched_actions.borrow_mut().clear();
+    }
+
+    /// Replaces the state and runs the subscriptions with the new state, as if
+    /// an action had changed it.
+    ///
+    /// # Arguments
+    ///
+    /// * `state` - The new state.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func test_shows_hp():
+    ///     var store = MockStore.new()
+    ///     var label = HpLabel.new(store)
+    ///
+    ///     store.set_state({ "hp": 5 })
+    ///
+    ///     assert_eq(label.text, "5")
+    /// ```
+    #[export]
+    fn set_state(&self, _owner: &Object, state: Dictionary) {
+        *self.state.borrow_mut() = state;
+
+        self.dispatch_subscriptions();
+    }
+
+    /// Returns the current state.
+    #[export]
+    fn state(&self, _owner: &Object) -> Dictionary<Unique> {
+        self.state.borrow().duplicate()
+    }
+
+    /// Returns the value at a path in the state or `null` if the path doesn't
+    /// exist.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path to the value, for example `player/hp`.
+    #[export]
+    fn get_value(&self, _owner: &Object, path: GodotString) -> Variant {
+        path::get(&self.state.borrow(), &path.to_string()).unwrap_or_else(Variant::new)
+    }
+
+    /// Records the action and runs it through the middleware. The state is not
+    /// changed but the subscriptions are still run, like they would be after a
+    /// real dispatch.
+    ///
+    /// # Arguments
+    ///
+    /// * `action` - The action to dispatch.
+    #[export]
+    fn dispatch(&self, _owner: &Object, action: Variant) {
+        self.dispatched_actions.borrow_mut().push(action.clone());
+
+        let middleware = self.middleware.borrow().clone();
+        let mut next = action;
+        for middleware in &middleware {
+            let args = &[Variant::from_dictionary(&self.state.borrow()), next];
+            next = middleware.call_func(args);
+            if next.is_nil() {
+                return;
+            }
+        }
+
+        self.dispatch_subscriptions();
+    }
+
+    /// Runs the subscriptions for the store.
+    fn dispatch_subscriptions(&self) {
+        let args = &[Variant::from_dictionary(&self.state.borrow())];
+
+        let subscriptions = self.subscriptions.borrow().clone();
+        for subscription in &subscriptions {
+            subscription.call_func(args);
+        }
+    }
+
+    /// Returns every action that has been dispatched, in the order it was
+    /// dispatched.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func test_jump_button():
+    ///     var store = MockStore.new()
+    ///     var button = JumpButton.new(store)
+    ///
+    ///     button.emit_signal("pressed")
+    ///
+    ///     assert_eq(store.get_dispatched_actions(), [Action.JUMP])
+    /// ```
+    #[export]
+    fn get_dispatched_actions(&self, _owner: &Object) -> VariantArray<Unique> {
+        self.dispatched_actions.borrow().iter().collect()
+    }
+
+    /// Forgets every action that has been dispatched so far.
+    #[export]
+    fn clear_dispatched_actions(&self, _owner: &Object) {
+        self.dispatched_actions.borrow_mut().clear();
+    }
+
+    /// Subscribes to changes to the state, which happen when `set_state` is
+    /// called or an action is dispatched.
+    ///
+    /// # Arguments
+    ///
+    /// * `subscriber_fn_instance` - The instance that contains the callback function.
+    /// * `subscriber_fn_name` - The name of the callback function.
+    #[export]
+    fn subscribe(
+        &self,
+        _owner: &Object,
+        subscriber_fn_instance: Ref<Object, Shared>,
+        subscriber_fn_name: GodotString,
+    ) {
+        self.subscriptions
+            .borrow_mut()
+            .push(func_ref(subscriber_fn_instance, subscriber_fn_name));
+    }
+
+    /// Adds a middleware function that dispatched actions are run through.
+    ///
+    /// # Arguments
+    ///
+    /// * `middleware_fn_instance` - The instance that contains the middleware function.
+    /// * `middleware_fn_name` - The name of the middleware function.
+    #[export]
+    fn add_middleware(
+        &self,
+        _owner: &Object,
+        middleware_fn_instance: Ref<Object, Shared>,
+        middleware_fn_name: GodotString,
+    ) {
+        self.middleware
+            .borrow_mut()
+            .push(func_ref(middleware_fn_instance, middleware_fn_name));
+    }
+
+    /// Removes every subscription and middleware function and forgets the
+    /// dispatched actions.
+    #[export]
+    fn clear(&self, _owner: &Object) {
+        self.subscriptions.borrow_mut().clear();
+        self.middleware.borrow_mut().clear();
+        self.dispatched_actions.borrow_mut().clear();
+    }
+}
@@ -0,0 +1,275 @@
+use gdnative::prelude::{Dictionary, OwnedToVariant, Unique, Variant, VariantArray};
+
+use crate::{action, path};
+
+/// The type of the action that changes the state with a list of patch
+/// operations.
+pub const PATCH: &str = "__patch";
+
+/// The key of the operations in a `__patch` action.
+const OPS_KEY: &str = "ops";
+
+/// The key of the name of the operation in an operation.
+const OP_KEY: &str = "op";
+
+/// The key of the path the operation changes in an operation.
+const PATH_KEY: &str = "path";
+
+/// The key of the path the value is taken from in a `move` or `copy`
+/// operation.
+const FROM_KEY: &str = "from";
+
+/// The key of the value in an `add`, `replace`, or `test` operation.
+const VALUE_KEY: &str = "value";
+
+/// The key an `add` operation can use to add a value to the end of an Array.
+const END_OF_ARRAY: &str = "-";
+
+/// A change made to the value at the end of a path.
+enum Edit {
+    /// Adds a value, replacing the value under a Dictionary key or inserting
+    /// it into an Array.
+    Add(Variant),
+    /// Replaces a value that has to exist.
+    Replace(Variant),
+    /// Removes a value that has to exist.
+    Remove,
+}
+
+/// Returns a `__patch` action that sets the value at a path with a single
+/// `add` operation.
+///
+/// # Arguments
+///
+/// * `path` - The path to set.
+/// * `value` - The value to set it to.
+pub fn set(path: &str, value: &Variant) -> Dictionary<Unique> {
+    let op = Dictionary::new();
+    op.insert(OP_KEY, "add");
+    op.insert(PATH_KEY, path);
+    op.insert(VALUE_KEY, value);
+
+    let ops = VariantArray::new();
+    ops.push(op);
+
+    let action = action::named(PATCH);
+    action.insert(OPS_KEY, ops);
+
+    action
+}
+
+/// Returns the state after a `__patch` action, or `None` if the action isn't
+/// one. The operations follow JSON Patch, with paths written the same way as
+/// everywhere else in the store, like `player/hp`:
+///
+/// * `add` - Sets the value under `path`, or inserts it if the path points
+///   into an Array, where `-` adds it to the end.
+/// * `remove` - Removes the value under `path`.
+/// * `replace` - Replaces the value under `path`.
+/// * `move` - Moves the value under `from` to `path`.
+/// * `copy` - Copies the value under `from` to `path`.
+/// * `test` - Checks that the value under `path` is `value`.
+///
+/// Either every operation is applied or none are, so an error is returned
+/// describing the first operation that couldn't be applied, and the state is
+/// never changed in place.
+///
+/// # Arguments
+///
+/// * `state` - The state before the action.
+/// * `action` - The action that was dispatched.
+pub fn apply(state: &Dictionary, action: &Variant) -> Result<Option<Dictionary>, String> {
+    if !action::is_type(action, PATCH) {
+        return Ok(None);
+    }
+
+    let ops = action::field(action, OPS_KEY)
+        .and_then(|ops| ops.try_to_array())
+        .ok_or_else(|| format!("The {} under {} has to be an Array", OPS_KEY, PATCH))?;
+
+    let mut patched = Variant::from_dictionary(state);
+    for (index, op) in ops.iter().enumerate() {
+        patched = apply_op(&patched, &op)
+            .map_err(|message| format!("Operation {} failed: {}", index, message))?;
+    }
+
+    Ok(Some(patched.to_dictionary()))
+}
+
+/// Returns the state after a single operation.
+///
+/// # Arguments
+///
+/// * `state` - The state before the operation.
+/// * `op` - The operation.
+fn apply_op(state: &Variant, op: &Variant) -> Result<Variant, String> {
+    let name = action::field(op, OP_KEY)
+        .ok_or_else(|| format!("The operation {} doesn't have an op", op.to_string()))?
+        .to_string();
+    let path = path_of(op, PATH_KEY)?;
+
+    match name.as_str() {
+        "add" => edit(state, &path, Edit::Add(value_of(op)?)),
+        "remove" => edit(state, &path, Edit::Remove),
+        "replace" => edit(state, &path, Edit::Replace(value_of(op)?)),
+        "move" => {
+            let from = path_of(op, FROM_KEY)?;
+            if path.starts_with(&from) && path.len() > from.len() {
+                return Err(format!(
+                    "{} can't be moved into itself",
+                    from.join(&path::SEPARATOR.to_string())
+                ));
+            }
+
+            let value = value_at(state, &from)?;
+            let state = edit(state, &from, Edit::Remove)?;
+            edit(&state, &path, Edit::Add(value))
+        }
+        "copy" => {
+            let value = value_at(state, &path_of(op, FROM_KEY)?)?;
+            edit(state, &path, Edit::Add(value))
+        }
+        "test" => {
+            let expected = value_of(op)?;
+            let actual = value_at(state, &path)?;
+            if actual == expected {
+                Ok(state.clone())
+            } else {
+                Err(format!(
+                    "Expected {} to be {}, got {}",
+                    path.join(&path::SEPARATOR.to_string()),
+                    expected.to_string(),
+                    actual.to_string()
+                ))
+            }
+        }
+        _ => Err(format!("The op {} isn't supported", name)),
+    }
+}
+
+/// Returns the keys of a path in an operation, which can't point to the root
+/// of the state.
+///
+/// # Arguments
+///
+/// * `op` - The operation.
+/// * `key` - The key of the path in the operation.
+fn path_of(op: &Variant, key: &str) -> Result<Vec<String>, String> {
+    let path = action::field(op, key)
+        .ok_or_else(|| format!("The operation {} doesn't have a {}", op.to_string(), key))?
+        .to_string();
+
+    let keys = path::keys(&path).map(str::to_string).collect::<Vec<_>>();
+    if keys.is_empty() {
+        return Err("The root of the state can't be patched".to_string());
+    }
+
+    Ok(keys)
+}
+
+/// Returns the value of an operation.
+///
+/// # Arguments
+///
+/// * `op` - The operation.
+fn value_of(op: &Variant) -> Result<Variant, String> {
+    action::field(op, VALUE_KEY)
+        .ok_or_else(|| format!("The operation {} doesn't have a value", op.to_string()))
+}
+
+/// Returns the value at the end of a path, or an error if it doesn't exist.
+///
+/// # Arguments
+///
+/// * `state` - The state to get the value from.
+/// * `keys` - The keys of the path.
+fn value_at(state: &Variant, keys: &[String]) -> Result<Variant, String> {
+    let path = keys.join(&path::SEPARATOR.to_string());
+
+    path::get(&state.to_dictionary(), &path).ok_or_else(|| format!("{} doesn't exist", path))
+}
+
+/// Returns a copy of a Dictionary or Array with the value at the end of a path
+/// changed. Only the Dictionaries and Arrays along the path are copied, so the
+/// original is left untouched.
+///
+/// # Arguments
+///
+/// * `container` - The Dictionary or Array the path starts from.
+/// * `keys` - The keys of the path.
+/// * `change` - The change to make to the value at the end of the path.
+fn edit(container: &Variant, keys: &[String], change: Edit) -> Result<Variant, String> {
+    let (key, rest) = match keys.split_first() {
+        Some(split) => split,
+        None => return Err("The root of the state can't be patched".to_string()),
+    };
+
+    if let Some(dictionary) = container.try_to_dictionary() {
+        let copy = dictionary.duplicate();
+
+        if !rest.is_empty() {
+            if !dictionary.contains(key) {
+                return Err(format!("{} doesn't exist", key));
+            }
+            let child = edit(&dictionary.get(key), rest, change)
+                .map_err(|message| format!("{}/{}", key, message))?;
+            copy.insert(key, child);
+
+            return Ok(copy.owned_to_variant());
+        }
+
+        match change {
+            Edit::Add(value) => copy.insert(key, value),
+            Edit::Replace(_) | Edit::Remove if !dictionary.contains(key) => {
+                return Err(format!("{} doesn't exist", key));
+            }
+            Edit::Replace(value) => copy.insert(key, value),
+            Edit::Remove => copy.erase(key),
+        }
+
+        return Ok(copy.owned_to_variant());
+    }
+
+    if let Some(array) = container.try_to_array() {
+        let copy = array.duplicate();
+        let len = array.len();
+
+        // Only an `add` at the end of the path can use the index just past
+        // the end of the Array, which inserts the value at the end.
+        let last = if rest.is_empty() && matches!(change, Edit::Add(_)) {
+            len
+        } else {
+            len - 1
+        };
+        let index = if key == END_OF_ARRAY {
+            Some(len)
+        } else {
+            key.parse::<i32>().ok()
+        };
+        let index = index
+            .filter(|index| *index >= 0 && *index <= last)
+            .ok_or_else(|| format!("{} isn't an index of the Array", key))?;
+
+        if !rest.is_empty() {
+            let child = edit(&array.get(index), rest, change)
+                .map_err(|message| format!("{}/{}", key, message))?;
+            copy.set(index, child);
+
+            return Ok(copy.owned_to_variant());
+        }
+
+        match change {
+            Edit::Add(value) => copy.insert(index, value),
+            Edit::Replace(value) => copy.set(index, value),
+            Edit::Remove => copy.remove(index),
+        }
+
+        return Ok(copy.owned_to_variant());
+    }
+
+    Err(format!(
+        "{} can't be patched since it's under {}, which isn't a Dictionary or an Array",
+        key,
+        container.to_string()
+    ))
+}
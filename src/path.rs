@@ -0,0 +1,165 @@
+use gdnative::prelude::{Dictionary, Unique, Variant};
+
+use crate::math_type;
+
+/// The character used to separate the keys in a state path, for example
+/// `player/hp`.
+pub const SEPARATOR: char = '/';
+
+/// Returns the keys that make up a state path, skipping empty keys so that
+/// leading, trailing, and repeated separators are ignored.
+///
+/// # Arguments
+///
+/// * `path` - The path to split into keys.
+pub fn keys(path: &str) -> impl Iterator<Item = &str> {
+    path.split(SEPARATOR).filter(|key| !key.is_empty())
+}
+
+/// Joins a key onto the end of a path.
+///
+/// # Arguments
+///
+/// * `path` - The path to add the key to, which can be empty.
+/// * `key` - The key to add.
+pub fn join(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}{}{}", path, SEPARATOR, key)
+    }
+}
+
+/// Returns the value at the path in the state or `None` if any of the keys
+/// along the way don't exist. Arrays along the path can be indexed with
+/// numeric keys, for example `inventory/0/name`, and the components of a
+/// `Vector2`, `Vector3`, or `Color` can be read by their name, for example
+/// `player/position/x`.
+///
+/// # Arguments
+///
+/// * `state` - The state to get the value from.
+/// * `path` - The path to the value.
+pub fn get(state: &Dictionary, path: &str) -> Option<Variant> {
+    let mut value = Variant::from_dictionary(state);
+
+    for key in keys(path) {
+        value = child(&value, key)?;
+    }
+
+    Some(value)
+}
+
+/// Returns a copy of the state with the value at the path replaced, or `None`
+/// if the path doesn't exist or goes through something other than a
+/// Dictionary. Only the Dictionaries along the path are copied, so the state
+/// itself is left untouched.
+///
+/// # Arguments
+///
+/// * `state` - The state to copy.
+/// * `path` - The path to the value.
+/// * `value` - The value to put at the path.
+pub fn with_value(state: &Dictionary, path: &str, value: Variant) -> Option<Dictionary<Unique>> {
+    let keys = keys(path).collect::<Vec<_>>();
+
+    replace_in(state, &keys, value)
+}
+
+/// Returns a copy of a Dictionary with the value at the end of the keys
+/// replaced, copying every Dictionary along the way.
+///
+/// # Arguments
+///
+/// * `dictionary` - The Dictionary to copy.
+/// * `keys` - The keys of the path to the value.
+/// * `value` - The value to put at the end of the keys.
+fn replace_in(
+    dictionary: &Dictionary,
+    keys: &[&str],
+    value: Variant,
+) -> Option<Dictionary<Unique>> {
+    let (key, rest) = keys.split_first()?;
+    if !dictionary.contains(*key) {
+        return None;
+    }
+
+    let copy = dictionary.duplicate();
+    if rest.is_empty() {
+        copy.insert(*key, value);
+    } else {
+        let child = dictionary.get(*key).try_to_dictionary()?;
+        copy.insert(*key, replace_in(&child, rest, value)?);
+    }
+
+    Some(copy)
+}
+
+/// Returns the value stored under a single key of a Dictionary or Array
+/// value, or a component of a `Vector2`, `Vector3`, or `Color`.
+///
+/// # Arguments
+///
+/// * `value` - The Dictionary, Array, or math type to get the value from.
+/// * `key` - The key, index, or component of the value.
+fn child(value: &Variant, key: &str) -> Option<Variant> {
+    if let Some(dictionary) = value.try_to_dictionary() {
+        if dictionary.contains(key) {
+            return Some(dictionary.get(key));
+        }
+        return None;
+    }
+    if let Some(component) = math_type::component(value, key) {
+        return Some(component);
+    }
+
+    let array = value.try_to_array()?;
+    let index = key.parse::<i32>().ok()?;
+    if index >= 0 && index < array.len() {
+        Some(array.get(index))
+    } else {
+        None
+    }
+}
+
+/// Returns the paths to every value in the state that isn't itself a
+/// non-empty Dictionary.
+///
+/// # Arguments
+///
+/// * `state` - The state to list the paths of.
+pub fn leaves(state: &Dictionary) -> Vec<String> {
+    let mut paths = vec![];
+    collect_leaves("", state, &mut paths);
+
+    paths
+}
+
+/// Adds the paths to the leaves of the Dictionary to the list of paths.
+///
+/// # Arguments
+///
+/// * `prefix` - The path to the Dictionary.
+/// * `dictionary` - The Dictionary to list the leaves of.
+/// * `paths` - The list to add the paths to.
+fn collect_leaves(prefix: &str, dictionary: &Dictionary, paths: &mut Vec<String>) {
+    for (key, value) in dictionary {
+        let path = join(prefix, &key.to_string());
+
+        match value.try_to_dictionary() {
+            Some(child) if !child.is_empty() => collect_leaves(&path, &child, paths),
+            _ => paths.push(path),
+        }
+    }
+}
+
+/// Returns whether `path` is `ancestor` or is nested somewhere inside of it.
+///
+/// # Arguments
+///
+/// * `path` - The path to check.
+/// * `ancestor` - The path that might contain `path`.
+pub fn is_within(path: &str, ancestor: &str) -> bool {
+    path == ancestor
+        || (path.starts_with(ancestor) && path[ancestor.len()..].starts_with(SEPARATOR))
+}
@@ -0,0 +1,91 @@
+use std::collections::BTreeMap;
+
+use gdnative::prelude::Variant;
+
+use crate::action::action_type;
+
+/// Whether something the store does over time keeps going while the tree is
+/// paused, named after the pause modes of nodes.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Mode {
+    /// Frozen while the tree is paused, like gameplay actions.
+    Stop,
+    /// Keeps going while the tree is paused, like UI and menu actions.
+    Process,
+}
+
+impl Mode {
+    /// Returns the mode with a name, which is either `stop` or `process`.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the mode.
+    pub fn from_name(name: &str) -> Result<Self, String> {
+        match name {
+            "stop" => Ok(Mode::Stop),
+            "process" => Ok(Mode::Process),
+            _ => Err(format!(
+                "The pause mode has to be stop or process, got {}",
+                name
+            )),
+        }
+    }
+}
+
+/// The pause modes set with `set_action_pause_mode` and
+/// `set_slice_pause_mode`. Anything without a mode keeps going while the tree
+/// is paused, which is how the store behaved before pause modes existed.
+#[derive(Default)]
+pub struct PauseModes {
+    /// The modes of the deferred actions of each type.
+    actions: Vec<(Variant, Mode)>,
+    /// The modes of the timed updates of the slices the store manages, like
+    /// the countdown of the `cooldowns` slice, by slice.
+    slices: BTreeMap<String, Mode>,
+}
+
+impl PauseModes {
+    /// Sets the mode of the deferred actions of a type.
+    ///
+    /// # Arguments
+    ///
+    /// * `action` - The type of the actions.
+    /// * `mode` - The mode.
+    pub fn set_action_mode(&mut self, action: Variant, mode: Mode) {
+        self.actions.retain(|(a, _)| *a != action);
+        self.actions.push((action, mode));
+    }
+
+    /// Sets the mode of the timed updates of a slice.
+    ///
+    /// # Arguments
+    ///
+    /// * `slice` - The key of the slice.
+    /// * `mode` - The mode.
+    pub fn set_slice_mode(&mut self, slice: &str, mode: Mode) {
+        self.slices.insert(slice.to_string(), mode);
+    }
+
+    /// Returns whether a deferred action is frozen while the tree is paused.
+    ///
+    /// # Arguments
+    ///
+    /// * `action` - The action.
+    pub fn stops_action(&self, action: &Variant) -> bool {
+        let action_type = action_type(action);
+
+        self.actions
+            .iter()
+            .any(|(a, mode)| *a == action_type && *mode == Mode::Stop)
+    }
+
+    /// Returns whether the timed updates of a slice are frozen while the tree
+    /// is paused.
+    ///
+    /// # Arguments
+    ///
+    /// * `slice` - The key of the slice.
+    pub fn stops_slice(&self, slice: &str) -> bool {
+        self.slices.get(slice) == Some(&Mode::Stop)
+    }
+}
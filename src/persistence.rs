@@ -0,0 +1,238 @@
+use gdnative::api::{Marshalls, OS};
+use gdnative::prelude::{Dictionary, GodotString, OwnedToVariant, Unique, Variant};
+
+use crate::codec::Codec;
+use crate::file::{self, Encryption};
+use crate::path;
+
+/// The key of the version of the game in the metadata of a save.
+const GAME_VERSION_KEY: &str = "game_version";
+
+/// The key of the version of the state in the metadata of a save.
+const STATE_VERSION_KEY: &str = "state_version";
+
+/// The key of the time the save was made at, in seconds since the Unix epoch,
+/// in the metadata of a save.
+const TIMESTAMP_KEY: &str = "timestamp";
+
+/// The key of the playtime in the metadata of a save.
+const PLAYTIME_KEY: &str = "playtime";
+
+/// The key of the metadata passed to `save_to_file` in the metadata of a
+/// save.
+const CUSTOM_KEY: &str = "custom";
+
+/// The key of the version of the state in a file written with `write_plain`.
+const PLAIN_VERSION_KEY: &str = "version";
+
+/// The key of the state in a file written with `write_plain`.
+const PLAIN_STATE_KEY: &str = "state";
+
+/// What the store writes into the metadata of every save.
+#[derive(Default)]
+pub struct SaveInfo {
+    /// The version of the game, set with `set_game_version`.
+    pub game_version: String,
+    /// The version of the shape of the state, set with `set_state_version`.
+    pub state_version: i64,
+    /// The path of the playtime in the state, set with `set_playtime_path`.
+    pub playtime_path: Option<String>,
+}
+
+impl SaveInfo {
+    /// Returns the metadata of a save, which has the version of the game and
+    /// the state, the time the save was made at, the playtime, and the custom
+    /// metadata.
+    ///
+    /// # Arguments
+    ///
+    /// * `state` - The state being saved, which the playtime is read from.
+    /// * `custom` - The metadata passed to `save_to_file`.
+    pub fn metadata(&self, state: &Dictionary, custom: &Dictionary) -> Dictionary<Unique> {
+        let playtime = self
+            .playtime_path
+            .as_ref()
+            .and_then(|playtime_path| path::get(state, playtime_path))
+            .unwrap_or_else(Variant::new);
+
+        let metadata = Dictionary::new();
+        metadata.insert(GAME_VERSION_KEY, &self.game_version);
+        metadata.insert(STATE_VERSION_KEY, self.state_version);
+        metadata.insert(TIMESTAMP_KEY, OS::godot_singleton().get_unix_time());
+        metadata.insert(PLAYTIME_KEY, playtime);
+        metadata.insert(CUSTOM_KEY, custom);
+
+        metadata
+    }
+}
+
+/// Writes a save to a file, with its metadata before the state so the
+/// metadata can be read without reading the state, and a checksum of the
+/// state after it so a save that was only partly written or got corrupted
+/// can be told apart. The saves that were there before are kept as backups
+/// first, with the newest one moved to `.bak1`, the one that was there to
+/// `.bak2`, and so on, dropping the oldest one.
+///
+/// # Arguments
+///
+/// * `path` - The path to the file, for example `user://saves/1.save`.
+/// * `metadata` - The metadata of the save.
+/// * `state` - The state to save.
+/// * `backups` - The number of backups to keep.
+/// * `encryption` - What the save is encrypted with, if it is.
+pub fn write(
+    path: &str,
+    metadata: &Dictionary,
+    state: &Dictionary,
+    backups: usize,
+    encryption: Option<&Encryption>,
+) -> Result<(), String> {
+    rotate_backups(path, backups)?;
+
+    file::write_vars(
+        path,
+        &[
+            metadata.owned_to_variant(),
+            state.owned_to_variant(),
+            checksum(state).owned_to_variant(),
+        ],
+        encryption,
+    )
+}
+
+/// Returns the path of a backup of a save.
+///
+/// # Arguments
+///
+/// * `path` - The path to the save, for example `user://saves/1.save`.
+/// * `backup` - The number of the backup, where 1 is the newest.
+pub fn backup_path(path: &str, backup: usize) -> String {
+    format!("{}.bak{}", path, backup)
+}
+
+/// Moves every backup of a save one back, and the save itself to the newest
+/// backup, so the save can be written without losing it.
+///
+/// # Arguments
+///
+/// * `path` - The path to the save, for example `user://saves/1.save`.
+/// * `backups` - The number of backups to keep.
+fn rotate_backups(path: &str, backups: usize) -> Result<(), String> {
+    if backups == 0 {
+        return Ok(());
+    }
+
+    for backup in (1..backups).rev() {
+        let from = backup_path(path, backup);
+        if file::exists(&from) {
+            file::rename(&from, &backup_path(path, backup + 1))?;
+        }
+    }
+
+    if file::exists(path) {
+        file::rename(path, &backup_path(path, 1))?;
+    }
+
+    Ok(())
+}
+
+/// Returns the checksum of the state of a save.
+///
+/// # Arguments
+///
+/// * `state` - The state.
+fn checksum(state: &Dictionary) -> GodotString {
+    Marshalls::godot_singleton()
+        .variant_to_base64(state.owned_to_variant(), false)
+        .sha256_text()
+}
+
+/// Writes just the state to a file along with its version, without the
+/// metadata and checksum of a save written with `write`.
+///
+/// # Arguments
+///
+/// * `path` - The path to the file, for example `user://state.json`.
+/// * `state` - The state to write.
+/// * `version` - The version of the shape of the state.
+/// * `codec` - What the state is written as.
+/// * `encryption` - What the file is encrypted with, if it is.
+pub fn write_plain(
+    path: &str,
+    state: &Dictionary,
+    version: i64,
+    codec: &Codec,
+    encryption: Option<&Encryption>,
+) -> Result<(), String> {
+    let plain = Dictionary::new();
+    plain.insert(PLAIN_VERSION_KEY, version);
+    plain.insert(PLAIN_STATE_KEY, state);
+
+    let bytes = codec.encode(&plain.owned_to_variant())?;
+    file::write_bytes(path, bytes, encryption)
+}
+
+/// Reads the version and the state written with `write_plain`.
+///
+/// # Arguments
+///
+/// * `path` - The path to the file, for example `user://state.json`.
+/// * `codec` - What the state was written as.
+/// * `encryption` - What the file is encrypted with, if it is.
+pub fn read_plain(
+    path: &str,
+    codec: &Codec,
+    encryption: Option<&Encryption>,
+) -> Result<(i64, Dictionary), String> {
+    let plain = codec
+        .decode(file::read_bytes(path, encryption)?)?
+        .try_to_dictionary()
+        .ok_or_else(|| format!("{} doesn't have a state in it", path))?;
+
+    // JSON reads the version back as a float, which `to_i64` turns back into
+    // an integer.
+    match plain.get(PLAIN_STATE_KEY).try_to_dictionary() {
+        Some(state) => Ok((plain.get(PLAIN_VERSION_KEY).to_i64(), state)),
+        None => Err(format!("{} doesn't have a state in it", path)),
+    }
+}
+
+/// Reads just the metadata of a save written with `write`.
+///
+/// # Arguments
+///
+/// * `path` - The path to the file, for example `user://saves/1.save`.
+/// * `encryption` - What the save is encrypted with, if it is.
+pub fn read_metadata(path: &str, encryption: Option<&Encryption>) -> Result<Dictionary, String> {
+    let values = file::read_vars(path, 1, encryption)?;
+
+    values[0]
+        .try_to_dictionary()
+        .ok_or_else(|| format!("{} isn't a save", path))
+}
+
+/// Reads the version of the state in the metadata of a save written with
+/// `write`, and the state, returning an error if it doesn't match its
+/// checksum.
+///
+/// # Arguments
+///
+/// * `path` - The path to the file, for example `user://saves/1.save`.
+/// * `encryption` - What the save is encrypted with, if it is.
+pub fn read_state(
+    path: &str,
+    encryption: Option<&Encryption>,
+) -> Result<(i64, Dictionary), String> {
+    let values = file::read_vars(path, 3, encryption)?;
+
+    let (metadata, state) = match (values[0].try_to_dictionary(), values[1].try_to_dictionary()) {
+        (Some(metadata), Some(state)) => (metadata, state),
+        _ => return Err(format!("{} isn't a save", path)),
+    };
+
+    if values[2].try_to_godot_string() != Some(checksum(&state)) {
+        return Err(format!("{} doesn't match its checksum", path));
+    }
+
+    Ok((metadata.get(STATE_VERSION_KEY).to_i64(), state))
+}
@@ -0,0 +1,395 @@
+use std::collections::{BTreeMap, VecDeque};
+
+use gdnative::api::{Resource, ResourceInteractiveLoader, ResourceLoader, OS};
+use gdnative::prelude::{
+    Dictionary, GodotError, OwnedToVariant, Ref, Shared, Unique, Variant, VariantArray,
+};
+
+use crate::{action, path};
+
+/// The key of the slice of the state that holds the progress of the
+/// resources being preloaded.
+pub const SLICE: &str = "preload";
+
+/// The key a reducer can add to the state it returns with an Array of the
+/// paths of resources to preload, which the store removes from the state.
+pub const MARKER: &str = "__preload";
+
+/// The type of the action that asks for resources to be preloaded.
+const PRELOAD: &str = "preload";
+
+/// The type of the action dispatched as a resource is being loaded.
+const PRELOAD_PROGRESS: &str = "preload_progress";
+
+/// The type of the action dispatched when a resource was loaded.
+const PRELOAD_FINISHED: &str = "preload_finished";
+
+/// The type of the action dispatched when a resource couldn't be loaded.
+const PRELOAD_FAILED: &str = "preload_failed";
+
+/// The type of the action dispatched when a preloaded resource is let go of.
+const PRELOAD_RELEASED: &str = "preload_released";
+
+/// The key of the paths in a `preload` action.
+const PATHS_KEY: &str = "paths";
+
+/// The key of the path in the other preload actions.
+const PATH_KEY: &str = "path";
+
+/// The key of the resources in the slice, by path.
+const RESOURCES_KEY: &str = "resources";
+
+/// The key of how much of a resource was loaded, from 0 to 1, both in the
+/// entry of a resource and in a `preload_progress` action. In the slice it's
+/// how much of every resource was loaded.
+const PROGRESS_KEY: &str = "progress";
+
+/// The key of whether a resource was loaded in the entry of a resource. In
+/// the slice it's whether every resource is done loading.
+const DONE_KEY: &str = "done";
+
+/// The key of the error in the entry of a resource and in a `preload_failed`
+/// action.
+const ERROR_KEY: &str = "error";
+
+/// How long the resources are loaded for every frame by default, in
+/// milliseconds.
+const DEFAULT_BUDGET_MSEC: i64 = 8;
+
+/// Something that happened while preloading resources, which the store
+/// dispatches as an action.
+pub enum Event {
+    /// A resource is part of the way loaded.
+    Progress(String, f64),
+    /// A resource was loaded.
+    Finished(String),
+    /// A resource couldn't be loaded.
+    Failed(String, String),
+}
+
+impl Event {
+    /// Returns the action for the event.
+    pub fn to_action(&self) -> Dictionary<Unique> {
+        match self {
+            Event::Progress(path, progress) => {
+                let action = path_action(PRELOAD_PROGRESS, path);
+                action.insert(PROGRESS_KEY, *progress);
+                action
+            }
+            Event::Finished(path) => path_action(PRELOAD_FINISHED, path),
+            Event::Failed(path, error) => {
+                let action = path_action(PRELOAD_FAILED, path);
+                action.insert(ERROR_KEY, error.as_str());
+                action
+            }
+        }
+    }
+}
+
+/// The resources being preloaded a bit at a time every frame, with
+/// ResourceInteractiveLoader, and the ones that were loaded, which are kept
+/// until they're released so the scene that needs them can be changed to
+/// right away.
+pub struct Preloader {
+    /// The resources waiting to be loaded, in the order they were asked for.
+    queue: VecDeque<String>,
+    /// The resource being loaded and its loader.
+    current: Option<(String, Ref<ResourceInteractiveLoader, Shared>)>,
+    /// The resources that were loaded, by path.
+    loaded: BTreeMap<String, Ref<Resource, Shared>>,
+    /// How long the resources are loaded for every frame, in milliseconds.
+    budget_msec: i64,
+}
+
+impl Default for Preloader {
+    fn default() -> Self {
+        Preloader {
+            queue: VecDeque::new(),
+            current: None,
+            loaded: BTreeMap::new(),
+            budget_msec: DEFAULT_BUDGET_MSEC,
+        }
+    }
+}
+
+impl Preloader {
+    /// Sets how long the resources are loaded for every frame.
+    ///
+    /// # Arguments
+    ///
+    /// * `budget_msec` - The time, in milliseconds.
+    pub fn set_budget(&mut self, budget_msec: i64) {
+        self.budget_msec = budget_msec;
+    }
+
+    /// Adds a resource to the end of the queue, unless it's already loaded or
+    /// waiting to be.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the resource.
+    pub fn queue(&mut self, path: &str) {
+        let loading = self
+            .current
+            .as_ref()
+            .is_some_and(|(current, _)| current == path);
+        if !loading && !self.loaded.contains_key(path) && !self.queue.iter().any(|p| p == path) {
+            self.queue.push_back(path.to_string());
+        }
+    }
+
+    /// Returns whether there's nothing left to load.
+    pub fn is_idle(&self) -> bool {
+        self.current.is_none() && self.queue.is_empty()
+    }
+
+    /// Loads the resources in the queue, one after the other, until the time
+    /// budget for the frame runs out, and returns what happened to them.
+    pub fn poll(&mut self) -> Vec<Event> {
+        let os = OS::godot_singleton();
+        let deadline = os.get_ticks_msec() + self.budget_msec;
+
+        let mut events = vec![];
+        while os.get_ticks_msec() < deadline {
+            let (path, loader) = match self.current.take() {
+                Some(current) => current,
+                None => match self.queue.pop_front() {
+                    Some(path) => {
+                        let loader =
+                            ResourceLoader::godot_singleton().load_interactive(path.as_str(), "");
+                        match loader {
+                            Some(loader) => (path, loader),
+                            None => {
+                                let error = format!("Unable to open {}", path);
+                                events.push(Event::Failed(path, error));
+                                continue;
+                            }
+                        }
+                    }
+                    None => break,
+                },
+            };
+
+            let loading = unsafe { loader.assume_safe() };
+            match loading.poll() {
+                Ok(()) => self.current = Some((path, loader)),
+                Err(GodotError::FileEof) => match loading.get_resource() {
+                    Some(resource) => {
+                        self.loaded.insert(path.clone(), resource);
+                        events.push(Event::Finished(path));
+                    }
+                    None => {
+                        let error = format!("{} didn't load a resource", path);
+                        events.push(Event::Failed(path, error));
+                    }
+                },
+                Err(error) => {
+                    let error = format!("Unable to load {}: {:?}", path, error);
+                    events.push(Event::Failed(path, error));
+                }
+            }
+        }
+
+        if let Some((path, loader)) = &self.current {
+            let loader = unsafe { loader.assume_safe() };
+            let stages = loader.get_stage_count().max(1);
+            let progress = loader.get_stage() as f64 / stages as f64;
+            events.push(Event::Progress(path.clone(), progress));
+        }
+
+        events
+    }
+
+    /// Returns a resource that was loaded, or `None` if it wasn't.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the resource.
+    pub fn get(&self, path: &str) -> Option<Ref<Resource, Shared>> {
+        self.loaded.get(path).cloned()
+    }
+
+    /// Lets go of a resource that was loaded and returns whether it was.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the resource.
+    pub fn release(&mut self, path: &str) -> bool {
+        self.loaded.remove(path).is_some()
+    }
+}
+
+/// Returns the action that asks for resources to be preloaded.
+///
+/// # Arguments
+///
+/// * `paths` - The paths of the resources.
+pub fn request(paths: VariantArray) -> Dictionary<Unique> {
+    let action = action::named(PRELOAD);
+    action.insert(PATHS_KEY, paths);
+
+    action
+}
+
+/// Returns the action dispatched when a preloaded resource is let go of.
+///
+/// # Arguments
+///
+/// * `path` - The path of the resource.
+pub fn released(path: &str) -> Dictionary<Unique> {
+    path_action(PRELOAD_RELEASED, path)
+}
+
+/// Returns the state returned by a reducer without the `__preload` key, along
+/// with the paths of the resources that were under it.
+///
+/// # Arguments
+///
+/// * `state` - The state returned by the reducer.
+pub fn take_marked(state: Dictionary) -> (Dictionary, Vec<String>) {
+    if !state.contains(MARKER) {
+        return (state, vec![]);
+    }
+
+    let marked = state.get(MARKER);
+    let paths = match marked.try_to_array() {
+        Some(paths) => paths.iter().map(|path| path.to_string()).collect(),
+        None => vec![marked.to_string()],
+    };
+
+    let state = state.duplicate();
+    state.erase(MARKER);
+
+    (state.into_shared(), paths)
+}
+
+/// Returns the paths of the resources a `preload` action asks for, which is
+/// empty for any other action.
+///
+/// # Arguments
+///
+/// * `action` - The action that was dispatched.
+pub fn requested(action: &Variant) -> Vec<String> {
+    if !action::is_type(action, PRELOAD) {
+        return vec![];
+    }
+
+    action::field(action, PATHS_KEY)
+        .and_then(|paths| paths.try_to_array())
+        .map(|paths| paths.iter().map(|path| path.to_string()).collect())
+        .unwrap_or_default()
+}
+
+/// Returns the slice after some resources were asked for and an action, or
+/// `None` if nothing was asked for and the action isn't a preload action.
+/// The slice has an entry for every resource under `resources`, by path,
+/// with how much of it was loaded under `progress`, whether it's loaded under
+/// `done`, and the error it failed with under `error`, as well as how much
+/// of every resource was loaded under `progress` and whether they're all
+/// done under `done`.
+///
+/// # Arguments
+///
+/// * `current` - The slice before the action.
+/// * `paths` - The paths of the resources that were asked for.
+/// * `action` - The action that was dispatched.
+pub fn reduce(
+    current: &Dictionary,
+    paths: &[String],
+    action: &Variant,
+) -> Option<Dictionary<Unique>> {
+    let resources = path::get(current, RESOURCES_KEY)
+        .and_then(|resources| resources.try_to_dictionary())
+        .unwrap_or_default()
+        .duplicate();
+
+    // A resource that failed before is tried again when it's asked for.
+    let mut changed = false;
+    for path in paths {
+        let failed = path::get(&resources, path)
+            .and_then(|entry| entry.try_to_dictionary())
+            .is_some_and(|entry| !entry.get(ERROR_KEY).is_nil());
+        if !resources.contains(path.as_str()) || failed {
+            resources.insert(path.as_str(), entry(0.0, false, Variant::new()));
+        }
+        changed = true;
+    }
+
+    let path = action::field(action, PATH_KEY).map(|path| path.to_string());
+    match path {
+        Some(path) if action::is_type(action, PRELOAD_PROGRESS) => {
+            let progress = action::field(action, PROGRESS_KEY).unwrap_or_default();
+            resources.insert(path, entry(progress.to_f64(), false, Variant::new()));
+            changed = true;
+        }
+        Some(path) if action::is_type(action, PRELOAD_FINISHED) => {
+            resources.insert(path, entry(1.0, true, Variant::new()));
+            changed = true;
+        }
+        Some(path) if action::is_type(action, PRELOAD_FAILED) => {
+            let error = action::field(action, ERROR_KEY).unwrap_or_default();
+            resources.insert(path, entry(1.0, true, error));
+            changed = true;
+        }
+        Some(path) if action::is_type(action, PRELOAD_RELEASED) => {
+            resources.erase(path);
+            changed = true;
+        }
+        _ => {}
+    }
+
+    if !changed {
+        return None;
+    }
+
+    let entries = resources
+        .iter()
+        .filter_map(|(_, entry)| entry.try_to_dictionary())
+        .collect::<Vec<_>>();
+    let progress = if entries.is_empty() {
+        1.0
+    } else {
+        entries
+            .iter()
+            .map(|entry| entry.get(PROGRESS_KEY).to_f64())
+            .sum::<f64>()
+            / entries.len() as f64
+    };
+    let done = entries.iter().all(|entry| entry.get(DONE_KEY).to_bool());
+
+    let next = Dictionary::new();
+    next.insert(RESOURCES_KEY, resources);
+    next.insert(PROGRESS_KEY, progress);
+    next.insert(DONE_KEY, done);
+
+    Some(next)
+}
+
+/// Returns the action with a type and the path of a resource.
+///
+/// # Arguments
+///
+/// * `name` - The name of the type.
+/// * `path` - The path of the resource.
+fn path_action(name: &str, path: &str) -> Dictionary<Unique> {
+    let action = action::named(name);
+    action.insert(PATH_KEY, path);
+
+    action
+}
+
+/// Creates the entry of a resource in the slice.
+///
+/// # Arguments
+///
+/// * `progress` - How much of the resource was loaded, from 0 to 1.
+/// * `done` - Whether the resource is done loading.
+/// * `error` - The error it failed with, or `null`.
+fn entry(progress: f64, done: bool, error: Variant) -> Variant {
+    let entry = Dictionary::new();
+    entry.insert(PROGRESS_KEY, progress);
+    entry.insert(DONE_KEY, done);
+    entry.insert(ERROR_KEY, error);
+
+    entry.owned_to_variant()
+}
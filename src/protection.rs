@@ -0,0 +1,225 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::Hasher;
+
+use gdnative::api::{Marshalls, RandomNumberGenerator};
+use gdnative::prelude::{ByteArray, Dictionary, NewRef, Unique, Variant};
+
+use crate::{action, path};
+
+/// The type of the action dispatched when a protected value was changed
+/// without going through the store.
+pub const TAMPER_DETECTED: &str = "TAMPER_DETECTED";
+
+/// The key of the protected path in a `TAMPER_DETECTED` action.
+const PATH_KEY: &str = "path";
+
+/// The number of bytes in the key protected values are obfuscated with.
+const KEY_LENGTH: usize = 32;
+
+/// The number of bytes of the checksum at the start of a sealed value.
+const CHECKSUM_LENGTH: usize = 8;
+
+/// The key protected values are obfuscated with when they're saved to a file,
+/// which stays the same between runs so they can be loaded again.
+const FILE_KEY: &[u8] = b"godot-redux/protected-values/v1";
+
+/// The paths of the state that are protected with `protect_path`. The values
+/// at these paths are kept sealed in the state: obfuscated with a key that is
+/// made up when the store starts, along with a checksum so changes made to
+/// them without going through the store can be detected.
+pub struct Protection {
+    /// The key values are obfuscated with.
+    key: Vec<u8>,
+    /// The protected paths.
+    paths: Vec<String>,
+}
+
+impl Default for Protection {
+    /// Creates the protection with a random key and no protected paths.
+    fn default() -> Self {
+        let rng = RandomNumberGenerator::new();
+        rng.randomize();
+
+        Protection {
+            key: (0..KEY_LENGTH).map(|_| rng.randi() as u8).collect(),
+            paths: vec![],
+        }
+    }
+}
+
+impl Protection {
+    /// Protects a path.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path to protect.
+    pub fn add_path(&mut self, path: &str) {
+        if !self.paths.iter().any(|protected| protected == path) {
+            self.paths.push(path.to_string());
+        }
+    }
+
+    /// Returns the protection for saving the state to a file, which protects
+    /// the same paths but with a key that stays the same between runs, since
+    /// the key of the store is made up again every time it starts.
+    pub fn for_files(&self) -> Protection {
+        Protection {
+            key: FILE_KEY.to_vec(),
+            paths: self.paths.clone(),
+        }
+    }
+
+    /// Returns a copy of the state with the value at every protected path
+    /// sealed. Values that are already sealed are left as they are, and only
+    /// the Dictionaries along the protected paths are copied.
+    ///
+    /// # Arguments
+    ///
+    /// * `state` - The state with plain values.
+    pub fn seal_state(&self, state: &Dictionary) -> Dictionary {
+        let mut sealed = state.new_ref();
+        for protected in &self.paths {
+            let value = match path::get(&sealed, protected) {
+                Some(value) if self.unseal(&value).is_none() => value,
+                _ => continue,
+            };
+
+            if let Some(next) = path::with_value(&sealed, protected, self.seal(&value)) {
+                sealed = next.into_shared();
+            }
+        }
+
+        sealed
+    }
+
+    /// Returns a copy of the state with the value at every protected path
+    /// unsealed, along with the protected paths whose value couldn't be
+    /// unsealed since it was changed without going through the store. Those
+    /// values are `null` in the copy.
+    ///
+    /// # Arguments
+    ///
+    /// * `state` - The state with sealed values.
+    pub fn unseal_state(&self, state: &Dictionary) -> (Dictionary, Vec<String>) {
+        let mut unsealed = state.new_ref();
+        let mut tampered = vec![];
+
+        for protected in &self.paths {
+            let value = match path::get(&unsealed, protected) {
+                Some(value) => value,
+                None => continue,
+            };
+
+            let plain = self.unseal(&value).unwrap_or_else(|| {
+                tampered.push(protected.clone());
+                Variant::new()
+            });
+            if let Some(next) = path::with_value(&unsealed, protected, plain) {
+                unsealed = next.into_shared();
+            }
+        }
+
+        (unsealed, tampered)
+    }
+
+    /// Returns the state with the values at the protected paths that were
+    /// changed without going through the store replaced with a sealed `null`,
+    /// so they read as `null` from then on, along with those paths.
+    ///
+    /// # Arguments
+    ///
+    /// * `state` - The state with sealed values.
+    pub fn drop_tampered(&self, state: &Dictionary) -> (Dictionary, Vec<String>) {
+        let (_, tampered) = self.unseal_state(state);
+
+        let mut state = state.new_ref();
+        for path in &tampered {
+            if let Some(next) = path::with_value(&state, path, self.seal(&Variant::new())) {
+                state = next.into_shared();
+            }
+        }
+
+        (state, tampered)
+    }
+
+    /// Returns a value sealed so it can be kept in the state.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The plain value.
+    fn seal(&self, value: &Variant) -> Variant {
+        let payload = Marshalls::godot_singleton()
+            .variant_to_base64(value.clone(), false)
+            .to_string()
+            .into_bytes();
+
+        let mut bytes = self.checksum(&payload).to_le_bytes().to_vec();
+        bytes.extend(payload);
+        self.obfuscate(&mut bytes);
+
+        Variant::from_byte_array(&ByteArray::from_vec(bytes))
+    }
+
+    /// Returns the plain value of a sealed value, or `None` if the value
+    /// isn't sealed or was changed after it was sealed.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The sealed value.
+    fn unseal(&self, value: &Variant) -> Option<Variant> {
+        let mut bytes = value.try_to_byte_array()?.read().to_vec();
+        if bytes.len() < CHECKSUM_LENGTH {
+            return None;
+        }
+        self.obfuscate(&mut bytes);
+
+        let payload = bytes.split_off(CHECKSUM_LENGTH);
+        let mut checksum = [0; CHECKSUM_LENGTH];
+        checksum.copy_from_slice(&bytes);
+        if u64::from_le_bytes(checksum) != self.checksum(&payload) {
+            return None;
+        }
+
+        let payload = String::from_utf8(payload).ok()?;
+        Some(Marshalls::godot_singleton().base64_to_variant(payload, false))
+    }
+
+    /// Obfuscates bytes with the key, or turns obfuscated bytes back into the
+    /// original ones.
+    ///
+    /// # Arguments
+    ///
+    /// * `bytes` - The bytes, which are changed in place.
+    fn obfuscate(&self, bytes: &mut [u8]) {
+        for (byte, key) in bytes.iter_mut().zip(self.key.iter().cycle()) {
+            *byte ^= key;
+        }
+    }
+
+    /// Returns the checksum of a payload, which depends on the key so it can't
+    /// be worked out without it.
+    ///
+    /// # Arguments
+    ///
+    /// * `payload` - The payload.
+    fn checksum(&self, payload: &[u8]) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        hasher.write(&self.key);
+        hasher.write(payload);
+
+        hasher.finish()
+    }
+}
+
+/// Returns the action dispatched when the value at a protected path was
+/// changed without going through the store.
+///
+/// # Arguments
+///
+/// * `path` - The protected path.
+pub fn tamper_detected(path: &str) -> Dictionary<Unique> {
+    let action = action::named(TAMPER_DETECTED);
+    action.insert(PATH_KEY, path);
+
+    action
+}
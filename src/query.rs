@@ -0,0 +1,272 @@
+use gdnative::api::FuncRef;
+use gdnative::prelude::{Dictionary, Ref, ThreadLocal, Unique, Variant, VariantArray};
+
+use crate::action::{self, TYPE_KEY};
+use crate::path;
+
+/// The key of the slice of the state that holds the cached result of every
+/// query, by name.
+pub const SLICE: &str = "queries";
+
+/// The type of the action dispatched when a query starts fetching.
+const STARTED: &str = "query_started";
+
+/// The type of the action dispatched when a query has fetched its data.
+const SUCCEEDED: &str = "query_succeeded";
+
+/// The type of the action dispatched when a query failed to fetch its data.
+const FAILED: &str = "query_failed";
+
+/// The type of the action dispatched when queries are invalidated.
+const INVALIDATED: &str = "query_invalidated";
+
+/// The key of the name of the query in a query action.
+const QUERY_KEY: &str = "query";
+
+/// The key of the names of the queries in a `query_invalidated` action.
+const QUERIES_KEY: &str = "queries";
+
+/// The key of the fetched data, both in a cache entry and in a
+/// `query_succeeded` action.
+const DATA_KEY: &str = "data";
+
+/// The key of the error, both in a cache entry and in a `query_failed` action.
+const ERROR_KEY: &str = "error";
+
+/// The key of whether the query is fetching in a cache entry.
+const LOADING_KEY: &str = "loading";
+
+/// The key of whether the cached data was invalidated in a cache entry.
+const STALE_KEY: &str = "stale";
+
+/// The key of the time the data was fetched at, both in a cache entry and in a
+/// `query_succeeded` action.
+const FETCHED_AT_KEY: &str = "fetched_at";
+
+/// A query registered with `register_query`.
+#[derive(Clone)]
+pub struct Query {
+    /// The function that fetches the data.
+    pub fetch: Ref<FuncRef, ThreadLocal>,
+    /// How long the fetched data is used for before it's fetched again, in
+    /// seconds.
+    pub ttl: f64,
+    /// The tags that invalidate the query.
+    pub tags: Vec<String>,
+    /// The arguments the query was last fetched with.
+    pub last_args: Variant,
+    /// The number of the last fetch that was started, used to ignore the
+    /// results of older fetches.
+    pub request: u64,
+}
+
+impl Query {
+    /// Returns whether the query has any of the tags.
+    ///
+    /// # Arguments
+    ///
+    /// * `tags` - The tags to check for.
+    pub fn has_any_tag(&self, tags: &[String]) -> bool {
+        self.tags.iter().any(|tag| tags.contains(tag))
+    }
+}
+
+/// Returns the cache entry of a query that hasn't been fetched yet.
+pub fn empty_entry() -> Dictionary<Unique> {
+    let entry = Dictionary::new();
+    entry.insert(DATA_KEY, Variant::new());
+    entry.insert(ERROR_KEY, Variant::new());
+    entry.insert(LOADING_KEY, false);
+    entry.insert(STALE_KEY, false);
+    entry.insert(FETCHED_AT_KEY, Variant::new());
+
+    entry
+}
+
+/// Returns the action dispatched when a query starts fetching.
+///
+/// # Arguments
+///
+/// * `name` - The name of the query.
+pub fn started(name: &str) -> Dictionary<Unique> {
+    query_action(STARTED, name)
+}
+
+/// Returns the action dispatched when a query has fetched its data.
+///
+/// # Arguments
+///
+/// * `name` - The name of the query.
+/// * `data` - The fetched data.
+/// * `now` - The current time of the store's clock.
+pub fn succeeded(name: &str, data: Variant, now: f64) -> Dictionary<Unique> {
+    let action = query_action(SUCCEEDED, name);
+    action.insert(DATA_KEY, data);
+    action.insert(FETCHED_AT_KEY, now);
+
+    action
+}
+
+/// Returns the action dispatched when a query failed to fetch its data.
+///
+/// # Arguments
+///
+/// * `name` - The name of the query.
+/// * `error` - The error the query failed with.
+pub fn failed(name: &str, error: Variant) -> Dictionary<Unique> {
+    let action = query_action(FAILED, name);
+    action.insert(ERROR_KEY, error);
+
+    action
+}
+
+/// Returns the action dispatched when queries are invalidated.
+///
+/// # Arguments
+///
+/// * `names` - The names of the invalidated queries.
+pub fn invalidated(names: &[String]) -> Dictionary<Unique> {
+    let action = Dictionary::new();
+    action.insert(TYPE_KEY, INVALIDATED);
+    action.insert(QUERIES_KEY, names.iter().collect::<VariantArray<Unique>>());
+
+    action
+}
+
+/// Returns the `queries` slice after an action, or `None` if the action isn't
+/// a query action.
+///
+/// # Arguments
+///
+/// * `current` - The `queries` slice before the action.
+/// * `action` - The action that was dispatched.
+pub fn reduce(current: &Dictionary, action: &Variant) -> Option<Dictionary<Unique>> {
+    if action::is_type(action, INVALIDATED) {
+        let names = action::field(action, QUERIES_KEY)?.try_to_array()?;
+        let next = current.duplicate();
+
+        for name in names.iter() {
+            if let Some(entry) = entry(current, &name.to_string()) {
+                let entry = entry.duplicate();
+                entry.insert(STALE_KEY, true);
+                next.insert(name, entry);
+            }
+        }
+
+        return Some(next);
+    }
+
+    let is_query_action = [STARTED, SUCCEEDED, FAILED]
+        .iter()
+        .any(|name| action::is_type(action, name));
+    if !is_query_action {
+        return None;
+    }
+
+    let name = action::field(action, QUERY_KEY)?.to_string();
+    let entry = match entry(current, &name) {
+        Some(entry) => entry.duplicate(),
+        None => empty_entry(),
+    };
+
+    if action::is_type(action, STARTED) {
+        entry.insert(LOADING_KEY, true);
+    } else if action::is_type(action, SUCCEEDED) {
+        entry.insert(
+            DATA_KEY,
+            action::field(action, DATA_KEY).unwrap_or_default(),
+        );
+        entry.insert(ERROR_KEY, Variant::new());
+        entry.insert(LOADING_KEY, false);
+        entry.insert(STALE_KEY, false);
+        entry.insert(
+            FETCHED_AT_KEY,
+            action::field(action, FETCHED_AT_KEY).unwrap_or_default(),
+        );
+    } else {
+        entry.insert(
+            ERROR_KEY,
+            action::field(action, ERROR_KEY).unwrap_or_default(),
+        );
+        entry.insert(LOADING_KEY, false);
+    }
+
+    let next = current.duplicate();
+    next.insert(name, entry);
+
+    Some(next)
+}
+
+/// Returns the cache entry of a query in the `queries` slice, or `None` if
+/// there isn't one.
+///
+/// # Arguments
+///
+/// * `queries` - The `queries` slice.
+/// * `name` - The name of the query.
+pub fn entry(queries: &Dictionary, name: &str) -> Option<Dictionary> {
+    if queries.contains(name) {
+        queries.get(name).try_to_dictionary()
+    } else {
+        None
+    }
+}
+
+/// Returns the data in a cache entry.
+///
+/// # Arguments
+///
+/// * `entry` - The cache entry.
+pub fn data(entry: &Dictionary) -> Variant {
+    path::get(entry, DATA_KEY).unwrap_or_default()
+}
+
+/// Returns whether a query is fetching.
+///
+/// # Arguments
+///
+/// * `entry` - The cache entry of the query.
+pub fn is_loading(entry: &Dictionary) -> bool {
+    path::get(entry, LOADING_KEY).unwrap_or_default().to_bool()
+}
+
+/// Returns whether the data in a cache entry can still be used, which is when
+/// it was fetched less than `ttl` seconds ago and hasn't been invalidated.
+///
+/// # Arguments
+///
+/// * `entry` - The cache entry.
+/// * `ttl` - How long fetched data can be used for, in seconds.
+/// * `now` - The current time of the store's clock.
+pub fn is_fresh(entry: &Dictionary, ttl: f64, now: f64) -> bool {
+    let fetched_at = path::get(entry, FETCHED_AT_KEY).unwrap_or_default();
+
+    !fetched_at.is_nil()
+        && !path::get(entry, STALE_KEY).unwrap_or_default().to_bool()
+        && now - fetched_at.to_f64() < ttl
+}
+
+/// Returns whether a query has fetched data at least once.
+///
+/// # Arguments
+///
+/// * `entry` - The cache entry of the query.
+pub fn was_fetched(entry: &Dictionary) -> bool {
+    !path::get(entry, FETCHED_AT_KEY)
+        .unwrap_or_default()
+        .is_nil()
+}
+
+/// Returns an action for a single query.
+///
+/// # Arguments
+///
+/// * `action_type` - The type of the action.
+/// * `name` - The name of the query.
+fn query_action(action_type: &str, name: &str) -> Dictionary<Unique> {
+    let action = Dictionary::new();
+    action.insert(TYPE_KEY, action_type);
+    action.insert(QUERY_KEY, name);
+
+    action
+}
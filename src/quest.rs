@@ -0,0 +1,479 @@
+use std::collections::BTreeMap;
+
+use gdnative::prelude::{Dictionary, Unique, Variant, VariantType};
+
+use crate::{action, path};
+
+/// The key of the slice of the state that holds the progress of every quest,
+/// by name.
+pub const SLICE: &str = "quests";
+
+/// The type of the action that starts a quest.
+const START_QUEST: &str = "start_quest";
+
+/// The type of the action that adds progress to an objective of a quest.
+const ADVANCE_QUEST: &str = "advance_quest";
+
+/// The type of the action that completes a quest.
+const COMPLETE_QUEST: &str = "complete_quest";
+
+/// The type of the action that fails a quest.
+const FAIL_QUEST: &str = "fail_quest";
+
+/// The key of the name of the quest in a quest action.
+const QUEST_KEY: &str = "quest";
+
+/// The key of the name of the objective in an `advance_quest` action.
+const OBJECTIVE_KEY: &str = "objective";
+
+/// The key of the progress to add in an `advance_quest` action.
+const AMOUNT_KEY: &str = "amount";
+
+/// The key of the status of a quest in its progress.
+const STATUS_KEY: &str = "status";
+
+/// The key of the objectives, both in the progress of a quest and in its
+/// definition.
+const OBJECTIVES_KEY: &str = "objectives";
+
+/// The key of the quests that have to be completed first, in a quest
+/// definition.
+const PREREQUISITES_KEY: &str = "prerequisites";
+
+/// The status of a quest that hasn't been started.
+const INACTIVE: &str = "inactive";
+
+/// The status of a quest that has been started.
+const ACTIVE: &str = "active";
+
+/// The status of a quest that has been completed.
+const COMPLETED: &str = "completed";
+
+/// The status of a quest that has been failed.
+const FAILED: &str = "failed";
+
+/// Something that was completed by a quest action, which the store emits a
+/// signal for.
+pub enum Completion {
+    /// An objective reached its target, with the name of the quest and of the
+    /// objective.
+    Objective(String, String),
+    /// A quest was completed, with the name of the quest.
+    Quest(String),
+}
+
+/// The slice after a quest action along with what the action completed.
+pub type Outcome = (Dictionary<Unique>, Vec<Completion>);
+
+/// The definition of a quest.
+#[derive(Clone)]
+struct QuestDefinition {
+    /// The quests that have to be completed before this one can be started.
+    prerequisites: Vec<String>,
+    /// The progress each objective needs to be completed, by objective name.
+    objectives: BTreeMap<String, f64>,
+}
+
+/// The quests loaded with `load_quests`.
+#[derive(Clone)]
+pub struct Quests {
+    /// The definition of every quest, by name.
+    definitions: BTreeMap<String, QuestDefinition>,
+}
+
+impl Quests {
+    /// Creates the quests from their definitions, returning an error
+    /// describing the first definition that isn't valid.
+    ///
+    /// # Arguments
+    ///
+    /// * `definitions` - The quest definitions, by name. Each one is a
+    /// Dictionary with the names of the quests that have to be completed first
+    /// under `prerequisites` and the progress each objective needs under
+    /// `objectives`.
+    pub fn new(definitions: &Dictionary) -> Result<Self, String> {
+        let definitions = definitions
+            .iter()
+            .map(|(name, definition)| {
+                let name = name.to_string();
+                parse_quest(&name, &definition).map(|definition| (name, definition))
+            })
+            .collect::<Result<BTreeMap<_, _>, _>>()?;
+
+        Self::from_definitions(definitions)
+    }
+
+    /// Creates the quests from parsed definitions, returning an error if a
+    /// quest has a prerequisite that isn't defined.
+    ///
+    /// # Arguments
+    ///
+    /// * `definitions` - The quest definitions, by name.
+    fn from_definitions(definitions: BTreeMap<String, QuestDefinition>) -> Result<Self, String> {
+        for (name, definition) in &definitions {
+            if let Some(missing) = definition
+                .prerequisites
+                .iter()
+                .find(|prerequisite| !definitions.contains_key(*prerequisite))
+            {
+                return Err(format!(
+                    "The prerequisite {} of the quest {} isn't defined",
+                    missing, name
+                ));
+            }
+        }
+
+        Ok(Quests { definitions })
+    }
+
+    /// Returns the slice with the progress of every quest, keeping the
+    /// progress already in the current slice, for example from a save, and
+    /// adding quests that aren't in it yet as inactive.
+    ///
+    /// # Arguments
+    ///
+    /// * `current` - The slice that is in the state.
+    pub fn initial_slice(&self, current: &Dictionary) -> Dictionary<Unique> {
+        let slice = current.duplicate();
+        for (name, definition) in &self.definitions {
+            if !slice.contains(name) {
+                slice.insert(name, progress(INACTIVE, &definition.objectives, None));
+            }
+        }
+
+        slice
+    }
+
+    /// Returns whether a quest can be started, which is when it hasn't been
+    /// started yet and every one of its prerequisites has been completed.
+    ///
+    /// # Arguments
+    ///
+    /// * `slice` - The slice.
+    /// * `quest` - The name of the quest.
+    pub fn can_start(&self, slice: &Dictionary, quest: &str) -> bool {
+        self.check_can_start(quest, |name| status_of(slice, name))
+            .is_ok()
+    }
+
+    /// Returns the slice after an action along with what the action
+    /// completed, or `None` if the action isn't a quest action. An error is
+    /// returned for an action that can't be carried out, like starting a quest
+    /// whose prerequisites haven't been completed.
+    ///
+    /// # Arguments
+    ///
+    /// * `current` - The slice before the action.
+    /// * `action` - The action that was dispatched.
+    pub fn reduce(
+        &self,
+        current: &Dictionary,
+        action: &Variant,
+    ) -> Result<Option<Outcome>, String> {
+        let is_quest_action = [START_QUEST, ADVANCE_QUEST, COMPLETE_QUEST, FAIL_QUEST]
+            .iter()
+            .any(|name| action::is_type(action, name));
+        if !is_quest_action {
+            return Ok(None);
+        }
+
+        let quest = action::field(action, QUEST_KEY)
+            .ok_or_else(|| format!("The action {} is missing its quest", action.to_string()))?
+            .to_string();
+        let definition = self
+            .definitions
+            .get(&quest)
+            .ok_or_else(|| format!("The quest {} isn't defined", quest))?;
+        let objectives = objectives(current, &quest);
+        let mut completions = vec![];
+
+        let next = if action::is_type(action, START_QUEST) {
+            self.check_can_start(&quest, |name| status_of(current, name))?;
+            progress(ACTIVE, &definition.objectives, None)
+        } else {
+            let status = status_of(current, &quest);
+            if status != ACTIVE {
+                return Err(format!(
+                    "The quest {} has to be active to change it, but it's {}",
+                    quest, status
+                ));
+            }
+
+            if action::is_type(action, ADVANCE_QUEST) {
+                let objective = action::field(action, OBJECTIVE_KEY)
+                    .ok_or_else(|| {
+                        format!("The action {} is missing its objective", action.to_string())
+                    })?
+                    .to_string();
+                let target = *definition.objectives.get(&objective).ok_or_else(|| {
+                    format!(
+                        "The quest {} doesn't have the objective {}",
+                        quest, objective
+                    )
+                })?;
+                let amount = action::field(action, AMOUNT_KEY).map_or(1.0, |a| a.to_f64());
+
+                let before = path::get(&objectives, &objective)
+                    .unwrap_or_default()
+                    .to_f64();
+                let (after, reached) = advance(before, amount, target);
+
+                // Whole numbers are kept as integers so the progress reads the
+                // same as the target in gdscript.
+                let objectives = objectives.duplicate();
+                if after.fract() == 0.0 {
+                    objectives.insert(&objective, after as i64);
+                } else {
+                    objectives.insert(&objective, after);
+                }
+                let objectives = objectives.into_shared();
+
+                if reached {
+                    completions.push(Completion::Objective(quest.clone(), objective));
+                }
+
+                let all_done = definition.objectives.iter().all(|(name, target)| {
+                    path::get(&objectives, name).unwrap_or_default().to_f64() >= *target
+                });
+                if all_done {
+                    completions.push(Completion::Quest(quest.clone()));
+                    progress(COMPLETED, &definition.objectives, Some(&objectives))
+                } else {
+                    progress(ACTIVE, &definition.objectives, Some(&objectives))
+                }
+            } else if action::is_type(action, COMPLETE_QUEST) {
+                completions.push(Completion::Quest(quest.clone()));
+                progress(COMPLETED, &definition.objectives, Some(&objectives))
+            } else {
+                progress(FAILED, &definition.objectives, Some(&objectives))
+            }
+        };
+
+        let slice = current.duplicate();
+        slice.insert(&quest, next);
+
+        Ok(Some((slice, completions)))
+    }
+
+    /// Returns an error describing why a quest can't be started, if it can't.
+    ///
+    /// # Arguments
+    ///
+    /// * `quest` - The name of the quest.
+    /// * `status` - Returns the status of a quest by name.
+    fn check_can_start(&self, quest: &str, status: impl Fn(&str) -> String) -> Result<(), String> {
+        let definition = self
+            .definitions
+            .get(quest)
+            .ok_or_else(|| format!("The quest {} isn't defined", quest))?;
+
+        let current = status(quest);
+        if current != INACTIVE {
+            return Err(format!(
+                "The quest {} can't be started since it's already {}",
+                quest, current
+            ));
+        }
+
+        let missing = definition
+            .prerequisites
+            .iter()
+            .filter(|prerequisite| status(prerequisite) != COMPLETED)
+            .cloned()
+            .collect::<Vec<_>>();
+        if !missing.is_empty() {
+            return Err(format!(
+                "The quest {} can't be started until {} is completed",
+                quest,
+                missing.join(", ")
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns the progress of an objective after adding to it, which stops at
+/// its target, along with whether this is what made it reach the target.
+///
+/// # Arguments
+///
+/// * `before` - The progress before adding to it.
+/// * `amount` - The progress to add.
+/// * `target` - The progress the objective needs.
+fn advance(before: f64, amount: f64, target: f64) -> (f64, bool) {
+    let after = (before + amount).min(target);
+
+    (after, before < target && after >= target)
+}
+
+/// Returns the status of a quest in the slice, which is `inactive` if the
+/// quest isn't in it.
+///
+/// # Arguments
+///
+/// * `slice` - The slice.
+/// * `quest` - The name of the quest.
+fn status_of(slice: &Dictionary, quest: &str) -> String {
+    path::get(slice, &path::join(quest, STATUS_KEY))
+        .map(|status| status.to_string())
+        .unwrap_or_else(|| INACTIVE.to_string())
+}
+
+/// Returns the progress of every objective of a quest in the slice, or an
+/// empty Dictionary if the quest isn't in it.
+///
+/// # Arguments
+///
+/// * `slice` - The slice.
+/// * `quest` - The name of the quest.
+fn objectives(slice: &Dictionary, quest: &str) -> Dictionary {
+    path::get(slice, &path::join(quest, OBJECTIVES_KEY))
+        .and_then(|objectives| objectives.try_to_dictionary())
+        .unwrap_or_else(Dictionary::new_shared)
+}
+
+/// Returns the progress of a quest, with its status under `status` and the
+/// progress of each objective under `objectives`.
+///
+/// # Arguments
+///
+/// * `status` - The status of the quest.
+/// * `targets` - The progress each objective needs, by objective name.
+/// * `objectives` - The progress of each objective, or `None` to start every
+/// objective at 0.
+fn progress(
+    status: &str,
+    targets: &BTreeMap<String, f64>,
+    objectives: Option<&Dictionary>,
+) -> Dictionary<Unique> {
+    let all_objectives = Dictionary::new();
+    for name in targets.keys() {
+        let value = objectives
+            .and_then(|objectives| path::get(objectives, name))
+            .unwrap_or_else(|| Variant::from_i64(0));
+        all_objectives.insert(name, value);
+    }
+
+    let progress = Dictionary::new();
+    progress.insert(STATUS_KEY, status);
+    progress.insert(OBJECTIVES_KEY, all_objectives);
+
+    progress
+}
+
+/// Parses a quest definition.
+///
+/// # Arguments
+///
+/// * `name` - The name of the quest.
+/// * `definition` - The Dictionary of the quest.
+fn parse_quest(name: &str, definition: &Variant) -> Result<QuestDefinition, String> {
+    let definition = definition.try_to_dictionary().ok_or_else(|| {
+        format!(
+            "The definition of the quest {} has to be a Dictionary, got {}",
+            name,
+            definition.to_string()
+        )
+    })?;
+
+    let prerequisites = path::get(&definition, PREREQUISITES_KEY)
+        .and_then(|prerequisites| prerequisites.try_to_array())
+        .map(|prerequisites| prerequisites.iter().map(|p| p.to_string()).collect())
+        .unwrap_or_default();
+
+    let mut objectives = BTreeMap::new();
+    if let Some(definitions) =
+        path::get(&definition, OBJECTIVES_KEY).and_then(|objectives| objectives.try_to_dictionary())
+    {
+        for (objective, target) in definitions.iter() {
+            if !matches!(target.get_type(), VariantType::I64 | VariantType::F64) {
+                return Err(format!(
+                    "The target of the objective {} of the quest {} has to be a number, got {}",
+                    objective.to_string(),
+                    name,
+                    target.to_string()
+                ));
+            }
+
+            objectives.insert(objective.to_string(), target.to_f64());
+        }
+    }
+
+    Ok(QuestDefinition {
+        prerequisites,
+        objectives,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::{advance, QuestDefinition, Quests, ACTIVE, COMPLETED, INACTIVE};
+
+    /// Creates the quests from their prerequisites, by name.
+    fn quests(prerequisites: &[(&str, &[&str])]) -> Result<Quests, String> {
+        let definitions = prerequisites
+            .iter()
+            .map(|(name, prerequisites)| {
+                let definition = QuestDefinition {
+                    prerequisites: prerequisites.iter().map(|p| p.to_string()).collect(),
+                    objectives: BTreeMap::new(),
+                };
+                (name.to_string(), definition)
+            })
+            .collect();
+
+        Quests::from_definitions(definitions)
+    }
+
+    /// Returns a function that looks the status of a quest up, where quests
+    /// that aren't listed are inactive.
+    fn statuses<'a>(statuses: &'a [(&str, &str)]) -> impl Fn(&str) -> String + 'a {
+        move |quest| {
+            statuses
+                .iter()
+                .find(|(name, _)| *name == quest)
+                .map_or(INACTIVE, |(_, status)| status)
+                .to_string()
+        }
+    }
+
+    #[test]
+    fn prerequisites_have_to_be_defined() {
+        assert!(quests(&[("rescue", &["find_map"]), ("find_map", &[])]).is_ok());
+        assert!(quests(&[("rescue", &["find_map"])]).is_err());
+    }
+
+    #[test]
+    fn a_quest_can_start_once_its_prerequisites_are_completed() {
+        let quests = quests(&[("find_map", &[]), ("rescue", &["find_map"])]).unwrap();
+
+        assert!(quests.check_can_start("rescue", statuses(&[])).is_err());
+        assert!(quests
+            .check_can_start("rescue", statuses(&[("find_map", ACTIVE)]))
+            .is_err());
+        assert!(quests
+            .check_can_start("rescue", statuses(&[("find_map", COMPLETED)]))
+            .is_ok());
+    }
+
+    #[test]
+    fn a_quest_can_only_start_once() {
+        let quests = quests(&[("find_map", &[])]).unwrap();
+
+        assert!(quests.check_can_start("find_map", statuses(&[])).is_ok());
+        assert!(quests
+            .check_can_start("find_map", statuses(&[("find_map", ACTIVE)]))
+            .is_err());
+        assert!(quests.check_can_start("missing", statuses(&[])).is_err());
+    }
+
+    #[test]
+    fn advance_stops_at_the_target_and_reaches_it_once() {
+        assert_eq!(advance(1.0, 1.0, 3.0), (2.0, false));
+        assert_eq!(advance(2.0, 5.0, 3.0), (3.0, true));
+        assert_eq!(advance(3.0, 1.0, 3.0), (3.0, false));
+    }
+}
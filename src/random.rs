@@ -0,0 +1,155 @@
+use gdnative::prelude::{Dictionary, Unique, Variant};
+
+use crate::{action, path};
+
+/// The key of the slice of the state that holds the seed and the position of
+/// every random stream.
+pub const SLICE: &str = "random";
+
+/// The type of the action that seeds the random streams.
+const SEED_RANDOM: &str = "seed_random";
+
+/// The type of the action dispatched every time a random number is drawn
+/// from a stream.
+const RANDOM_DRAWN: &str = "random_drawn";
+
+/// The key of the seed, both in the slice and in a `seed_random` action.
+const SEED_KEY: &str = "seed";
+
+/// The key of the position of every stream, by name, in the slice.
+const STREAMS_KEY: &str = "streams";
+
+/// The key of the name of the stream in a `random_drawn` action.
+const STREAM_KEY: &str = "stream";
+
+/// How far the position of a stream moves every time a number is drawn from
+/// it, which is the golden ratio as a 64 bit fraction, like in SplitMix64.
+const GAMMA: u64 = 0x9E37_79B9_7F4A_7C15;
+
+/// Returns the action that seeds the random streams, starting every stream
+/// over.
+///
+/// # Arguments
+///
+/// * `seed` - The seed.
+pub fn seed(seed: i64) -> Dictionary<Unique> {
+    let action = action::named(SEED_RANDOM);
+    action.insert(SEED_KEY, seed);
+
+    action
+}
+
+/// Returns the action that moves a stream to the next number.
+///
+/// # Arguments
+///
+/// * `stream` - The name of the stream.
+pub fn drawn(stream: &str) -> Dictionary<Unique> {
+    let action = action::named(RANDOM_DRAWN);
+    action.insert(STREAM_KEY, stream);
+
+    action
+}
+
+/// Returns the slice after an action, or `None` if the action isn't a random
+/// action.
+///
+/// # Arguments
+///
+/// * `current` - The slice before the action.
+/// * `action` - The action that was dispatched.
+pub fn reduce(current: &Dictionary, action: &Variant) -> Option<Dictionary<Unique>> {
+    if action::is_type(action, SEED_RANDOM) {
+        let seed = action::field(action, SEED_KEY)?.to_i64();
+
+        let next = Dictionary::new();
+        next.insert(SEED_KEY, seed);
+        next.insert(STREAMS_KEY, Dictionary::new());
+
+        return Some(next);
+    }
+
+    if action::is_type(action, RANDOM_DRAWN) {
+        let stream = action::field(action, STREAM_KEY)?.to_string();
+
+        let streams = path::get(current, STREAMS_KEY)
+            .and_then(|streams| streams.try_to_dictionary())
+            .unwrap_or_default()
+            .duplicate();
+        let position = position(current, &stream).wrapping_add(GAMMA);
+        streams.insert(stream, position as i64);
+
+        let next = current.duplicate();
+        next.insert(SEED_KEY, seed_of(current));
+        next.insert(STREAMS_KEY, streams);
+
+        return Some(next);
+    }
+
+    None
+}
+
+/// Returns the number a stream is at, from 0 up to but not including 1,
+/// which changes every time a `random_drawn` action is dispatched for it.
+///
+/// # Arguments
+///
+/// * `slice` - The slice.
+/// * `stream` - The name of the stream.
+pub fn value(slice: &Dictionary, stream: &str) -> f64 {
+    // The top 53 bits fill the fraction of a float exactly.
+    (mix(position(slice, stream)) >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// Returns the seed in the slice, which is 0 until `seed_random` is
+/// dispatched.
+///
+/// # Arguments
+///
+/// * `slice` - The slice.
+fn seed_of(slice: &Dictionary) -> i64 {
+    path::get(slice, SEED_KEY).map_or(0, |seed| seed.to_i64())
+}
+
+/// Returns the position of a stream. A stream nothing was drawn from yet
+/// starts from the seed mixed with its name, so every stream has its own
+/// sequence and drawing from one doesn't change the others.
+///
+/// # Arguments
+///
+/// * `slice` - The slice.
+/// * `stream` - The name of the stream.
+fn position(slice: &Dictionary, stream: &str) -> u64 {
+    let drawn = path::get(slice, STREAMS_KEY)
+        .and_then(|streams| streams.try_to_dictionary())
+        .filter(|streams| streams.contains(stream))
+        .map(|streams| streams.get(stream).to_i64() as u64);
+
+    drawn.unwrap_or_else(|| mix(seed_of(slice) as u64 ^ hash(stream)))
+}
+
+/// Scrambles the bits of a number, which is the output function of
+/// SplitMix64.
+///
+/// # Arguments
+///
+/// * `value` - The number.
+fn mix(value: u64) -> u64 {
+    let value = (value ^ (value >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    let value = (value ^ (value >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+
+    value ^ (value >> 31)
+}
+
+/// Returns the FNV-1a hash of the name of a stream, which is the same on
+/// every platform and in every build, unlike the hasher of the standard
+/// library.
+///
+/// # Arguments
+///
+/// * `stream` - The name of the stream.
+fn hash(stream: &str) -> u64 {
+    stream.bytes().fold(0xCBF2_9CE4_8422_2325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0100_0000_01B3)
+    })
+}
@@ -0,0 +1,146 @@
+use gdnative::api::SceneTree;
+use gdnative::prelude::{Dictionary, Ref, Shared, Unique, Variant, VariantArray};
+
+use crate::action;
+
+/// The key of the slice of the state that the router keeps up to date.
+pub const SLICE: &str = "router";
+
+/// The type of the action that changes to another scene.
+const NAVIGATE: &str = "navigate";
+
+/// The type of the action that goes back to the previous scene.
+const NAVIGATE_BACK: &str = "navigate_back";
+
+/// The key of the path of the scene, both in the slice and in a `navigate`
+/// action.
+const SCENE_KEY: &str = "scene";
+
+/// The key of the scenes that were navigated away from, oldest first.
+const HISTORY_KEY: &str = "history";
+
+/// Changes scenes in response to navigation actions.
+pub struct Router {
+    /// The tree whose scene is changed.
+    tree: Ref<SceneTree, Shared>,
+}
+
+impl Router {
+    /// Creates a router that changes the scene of the tree.
+    ///
+    /// # Arguments
+    ///
+    /// * `tree` - The tree whose scene is changed.
+    pub fn new(tree: Ref<SceneTree, Shared>) -> Self {
+        Router { tree }
+    }
+
+    /// Returns the slice for the scene the tree is currently showing and an
+    /// empty history.
+    pub fn initial_slice(&self) -> Dictionary<Unique> {
+        let tree = unsafe { self.tree.assume_safe() };
+        let scene = match tree.current_scene() {
+            Some(scene) => unsafe { scene.assume_safe() }.filename().to_string(),
+            None => String::new(),
+        };
+
+        slice(&scene, &VariantArray::new().into_shared())
+    }
+
+    /// Changes the scene of the tree to the scene at the path.
+    ///
+    /// # Arguments
+    ///
+    /// * `scene` - The path of the scene to change to.
+    pub fn change_scene(&self, scene: &str) -> Result<(), String> {
+        unsafe { self.tree.assume_safe() }
+            .change_scene(scene)
+            .map_err(|error| format!("Couldn't change to the scene {}: {:?}", scene, error))
+    }
+}
+
+/// Returns the router slice after an action, or `None` if the action isn't a
+/// navigation action. An error is returned for a navigation action that can't
+/// be carried out, like going back when there's no scene to go back to.
+///
+/// # Arguments
+///
+/// * `current` - The router slice before the action.
+/// * `action` - The action that was dispatched.
+pub fn reduce(
+    current: &Dictionary,
+    action: &Variant,
+) -> Result<Option<Dictionary<Unique>>, String> {
+    let scene = string_value(current, SCENE_KEY);
+    let history = if current.contains(HISTORY_KEY) {
+        current
+            .get(HISTORY_KEY)
+            .try_to_array()
+            .map(|history| history.duplicate())
+            .unwrap_or_default()
+    } else {
+        VariantArray::new()
+    };
+
+    if action::is_type(action, NAVIGATE) {
+        let next = match action::field(action, SCENE_KEY) {
+            Some(next) if !next.to_string().is_empty() => next.to_string(),
+            _ => return Err("A navigate action needs the path of a scene under scene".to_string()),
+        };
+
+        if !scene.is_empty() {
+            history.push(scene);
+        }
+
+        return Ok(Some(slice(&next, &history.into_shared())));
+    }
+
+    if action::is_type(action, NAVIGATE_BACK) {
+        if history.is_empty() {
+            return Err("There is no scene to navigate back to".to_string());
+        }
+
+        let previous = history.pop().to_string();
+        return Ok(Some(slice(&previous, &history.into_shared())));
+    }
+
+    Ok(None)
+}
+
+/// Returns the path of the scene in a router slice.
+///
+/// # Arguments
+///
+/// * `slice` - The router slice.
+pub fn scene(slice: &Dictionary) -> String {
+    string_value(slice, SCENE_KEY)
+}
+
+/// Creates a router slice.
+///
+/// # Arguments
+///
+/// * `scene` - The path of the current scene.
+/// * `history` - The scenes that were navigated away from, oldest first.
+fn slice(scene: &str, history: &VariantArray) -> Dictionary<Unique> {
+    let slice = Dictionary::new();
+    slice.insert(SCENE_KEY, scene);
+    slice.insert(HISTORY_KEY, history);
+
+    slice
+}
+
+/// Returns the String under a key of a Dictionary, or an empty String if it
+/// doesn't have the key.
+///
+/// # Arguments
+///
+/// * `dictionary` - The Dictionary to get the String from.
+/// * `key` - The key of the String.
+fn string_value(dictionary: &Dictionary, key: &str) -> String {
+    if dictionary.contains(key) {
+        dictionary.get(key).to_string()
+    } else {
+        String::new()
+    }
+}
@@ -0,0 +1,285 @@
+use gdnative::prelude::{Dictionary, Variant, VariantArray, VariantType};
+
+use crate::{math_type, path};
+
+/// The key of the type in a field definition.
+const TYPE_KEY: &str = "type";
+
+/// The key of whether a field can be left out, in a field definition.
+const OPTIONAL_KEY: &str = "optional";
+
+/// The key of the smallest value of a number field, or of each component of
+/// a `Vector2`, `Vector3`, or `Color` field, in a field definition.
+const MIN_KEY: &str = "min";
+
+/// The key of the largest value of a number field, or of each component of
+/// a `Vector2`, `Vector3`, or `Color` field, in a field definition.
+const MAX_KEY: &str = "max";
+
+/// The key of the values a field can have, in a field definition.
+const VALUES_KEY: &str = "values";
+
+/// The types a field can have, along with the names they can be given by,
+/// which are the same as in GDScript.
+const TYPES: &[(&str, VariantType)] = &[
+    ("bool", VariantType::Bool),
+    ("int", VariantType::I64),
+    ("float", VariantType::F64),
+    ("String", VariantType::GodotString),
+    ("Vector2", VariantType::Vector2),
+    ("Rect2", VariantType::Rect2),
+    ("Vector3", VariantType::Vector3),
+    ("Color", VariantType::Color),
+    ("NodePath", VariantType::NodePath),
+    ("Object", VariantType::Object),
+    ("Dictionary", VariantType::Dictionary),
+    ("Array", VariantType::VariantArray),
+];
+
+/// A field of a schema.
+struct Field {
+    /// The name of the field.
+    name: String,
+    /// The type the field has to have, if it has to have one.
+    kind: Option<VariantType>,
+    /// Whether the field can be left out.
+    optional: bool,
+    /// The smallest value of a number field, or of each component of a math
+    /// type field.
+    min: Option<f64>,
+    /// The largest value of a number field, or of each component of a math
+    /// type field.
+    max: Option<f64>,
+    /// The values the field can have, if it can only have some.
+    values: Option<VariantArray>,
+}
+
+/// A schema that checks the fields of a Dictionary, like the payload of an
+/// action or the state.
+pub struct Schema {
+    /// The fields of the schema, in the order they were defined.
+    fields: Vec<Field>,
+}
+
+impl Schema {
+    /// Creates a schema from its field definitions. Each field is either
+    /// defined by just its type, which is the name of a type like `int` or a
+    /// `TYPE_*` constant, or by a Dictionary with its type under `type`,
+    /// whether it can be left out under `optional`, the smallest and largest
+    /// values of a number, or of each component of a `Vector2`, `Vector3`, or
+    /// `Color`, under `min` and `max`, and the values it can have under
+    /// `values`. Fields have to be there unless they're optional, and
+    /// an `int` can be given where a `float` is expected. The name of a field
+    /// can be a path to check a nested value, like `player/hp`.
+    ///
+    /// # Arguments
+    ///
+    /// * `definitions` - The field definitions, by field name.
+    pub fn new(definitions: &Dictionary) -> Result<Self, String> {
+        let fields = definitions
+            .iter()
+            .map(|(name, definition)| {
+                let name = name.to_string();
+
+                let definition = match definition.try_to_dictionary() {
+                    Some(definition) => definition,
+                    None => {
+                        return Ok(Field {
+                            kind: Some(type_of(&name, &definition)?),
+                            name,
+                            optional: false,
+                            min: None,
+                            max: None,
+                            values: None,
+                        })
+                    }
+                };
+
+                let kind = if definition.contains(TYPE_KEY) {
+                    Some(type_of(&name, &definition.get(TYPE_KEY))?)
+                } else {
+                    None
+                };
+                let number = |key: &str| {
+                    if definition.contains(key) {
+                        Some(definition.get(key).to_f64())
+                    } else {
+                        None
+                    }
+                };
+
+                Ok(Field {
+                    kind,
+                    optional: definition.contains(OPTIONAL_KEY)
+                        && definition.get(OPTIONAL_KEY).to_bool(),
+                    min: number(MIN_KEY),
+                    max: number(MAX_KEY),
+                    values: if definition.contains(VALUES_KEY) {
+                        definition.get(VALUES_KEY).try_to_array()
+                    } else {
+                        None
+                    },
+                    name,
+                })
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+
+        Ok(Schema { fields })
+    }
+
+    /// Checks a Dictionary against the schema, returning an error naming the
+    /// first field that doesn't match it.
+    ///
+    /// # Arguments
+    ///
+    /// * `dictionary` - The Dictionary to check.
+    pub fn validate(&self, dictionary: &Dictionary) -> Result<(), String> {
+        match self.fields.iter().find_map(|field| field.check(dictionary)) {
+            Some(error) => Err(error),
+            None => Ok(()),
+        }
+    }
+
+    /// Checks a Dictionary against the schema, returning an error for every
+    /// field that is missing or doesn't match it, in the order the fields
+    /// were defined.
+    ///
+    /// # Arguments
+    ///
+    /// * `dictionary` - The Dictionary to check.
+    pub fn errors(&self, dictionary: &Dictionary) -> Vec<String> {
+        self.fields
+            .iter()
+            .filter_map(|field| field.check(dictionary))
+            .collect()
+    }
+}
+
+impl Field {
+    /// Checks the field in a Dictionary, returning an error naming the field
+    /// if it's missing or doesn't match its definition.
+    ///
+    /// # Arguments
+    ///
+    /// * `dictionary` - The Dictionary to check.
+    fn check(&self, dictionary: &Dictionary) -> Option<String> {
+        match path::get(dictionary, &self.name) {
+            Some(value) => self.validate(&value).err(),
+            None if self.optional => None,
+            None => Some(format!("The field {} is missing", self.name)),
+        }
+    }
+
+    /// Checks the value of the field, returning an error naming the field if
+    /// the value doesn't match its definition.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The value of the field.
+    fn validate(&self, value: &Variant) -> Result<(), String> {
+        let actual = value.get_type();
+        if let Some(kind) = self.kind {
+            let matches =
+                actual == kind || (kind == VariantType::F64 && actual == VariantType::I64);
+            if !matches {
+                return Err(format!(
+                    "The field {} has to be a {}, got {} which is a {}",
+                    self.name,
+                    type_name(kind),
+                    value.to_string(),
+                    type_name(actual)
+                ));
+            }
+        }
+
+        // The bounds of a math type apply to each of its components, like
+        // keeping every channel of a `Color` between 0 and 1.
+        let numbers = match math_type::components(value) {
+            Some(components) => components
+                .into_iter()
+                .map(|(_, component)| component as f64)
+                .collect(),
+            None if actual == VariantType::I64 || actual == VariantType::F64 => {
+                vec![value.to_f64()]
+            }
+            None => vec![],
+        };
+        if let Some(min) = self
+            .min
+            .filter(|min| numbers.iter().any(|number| number < min))
+        {
+            return Err(format!(
+                "The field {} has to be at least {}, got {}",
+                self.name,
+                min,
+                value.to_string()
+            ));
+        }
+        if let Some(max) = self
+            .max
+            .filter(|max| numbers.iter().any(|number| number > max))
+        {
+            return Err(format!(
+                "The field {} has to be at most {}, got {}",
+                self.name,
+                max,
+                value.to_string()
+            ));
+        }
+
+        match &self.values {
+            Some(values) if !values.contains(value) => Err(format!(
+                "The field {} has to be one of {}, got {}",
+                self.name,
+                Variant::from_array(values).to_string(),
+                value.to_string()
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Returns the type described by a value in a field definition, which is
+/// either the name of the type or a `TYPE_*` constant.
+///
+/// # Arguments
+///
+/// * `field` - The name of the field, for the error.
+/// * `kind` - The value describing the type.
+fn type_of(field: &str, kind: &Variant) -> Result<VariantType, String> {
+    let found = match kind.get_type() {
+        VariantType::GodotString => {
+            let name = kind.to_string();
+            TYPES
+                .iter()
+                .find(|(type_name, _)| type_name.eq_ignore_ascii_case(&name))
+        }
+        VariantType::I64 => TYPES
+            .iter()
+            .find(|(_, variant_type)| *variant_type as i64 == kind.to_i64()),
+        _ => None,
+    };
+
+    found.map(|(_, variant_type)| *variant_type).ok_or_else(|| {
+        format!(
+            "The type of the field {} has to be the name of a type or a TYPE_* constant, got {}",
+            field,
+            kind.to_string()
+        )
+    })
+}
+
+/// Returns the name of a type for errors.
+///
+/// # Arguments
+///
+/// * `kind` - The type.
+fn type_name(kind: VariantType) -> &'static str {
+    match kind {
+        VariantType::Nil => "null",
+        _ => TYPES
+            .iter()
+            .find(|(_, variant_type)| *variant_type == kind)
+            .map_or("value of another type", |(name, _)| name),
+    }
+}
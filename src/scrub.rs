@@ -0,0 +1,169 @@
+use gdnative::prelude::{Dictionary, OwnedToVariant, Variant, VariantArray};
+
+use crate::path;
+
+/// The key in a rule path that matches any key of a Dictionary or any index
+/// of an Array, for example `players/*/email`.
+const WILDCARD: &str = "*";
+
+/// What a masked value is replaced with.
+const MASK: &str = "***";
+
+/// What is done to the values at the path of a rule.
+#[derive(Clone, Copy, PartialEq)]
+enum Treatment {
+    /// The value is left out.
+    Remove,
+    /// The value is replaced with `***`.
+    Mask,
+}
+
+/// A path of the state and what is done to the values at it.
+struct Rule {
+    /// The keys of the path, where `*` matches any key.
+    keys: Vec<String>,
+    /// What is done to the values at the path.
+    treatment: Treatment,
+}
+
+/// Rules for scrubbing sensitive values, like tokens or emails, out of a copy
+/// of the state before it's shared.
+pub struct Scrubber {
+    /// The rules, in the order they were given.
+    rules: Vec<Rule>,
+}
+
+impl Scrubber {
+    /// Creates a scrubber from its rules, which are the paths to scrub along
+    /// with whether to `remove` or `mask` the values at them. A key of `*` in
+    /// a path matches any key of a Dictionary or index of an Array.
+    ///
+    /// # Arguments
+    ///
+    /// * `rules` - Whether to `remove` or `mask` the values, by path.
+    pub fn new(rules: &Dictionary) -> Result<Self, String> {
+        let rules = rules
+            .iter()
+            .map(|(rule_path, treatment)| {
+                let rule_path = rule_path.to_string();
+                let treatment = match treatment.to_string().as_str() {
+                    "remove" => Treatment::Remove,
+                    "mask" => Treatment::Mask,
+                    other => {
+                        return Err(format!(
+                            "The rule for {} has to be remove or mask, got {}",
+                            rule_path, other
+                        ))
+                    }
+                };
+
+                let keys = path::keys(&rule_path).map(String::from).collect::<Vec<_>>();
+                if keys.is_empty() {
+                    return Err("The path of a rule can't be empty".to_string());
+                }
+
+                Ok(Rule { keys, treatment })
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+
+        Ok(Scrubber { rules })
+    }
+
+    /// Returns a copy of the state with the values matched by the rules
+    /// removed or masked. Only the Dictionaries and Arrays along the paths of
+    /// the rules are copied, so the state itself is left untouched.
+    ///
+    /// # Arguments
+    ///
+    /// * `state` - The state to scrub.
+    pub fn scrub(&self, state: &Dictionary) -> Dictionary {
+        let rules = self.rules.iter().collect::<Vec<_>>();
+
+        scrub_dictionary(state, &rules, 0)
+    }
+}
+
+/// Returns a copy of a Dictionary with the values matched by the rules
+/// removed or masked.
+///
+/// # Arguments
+///
+/// * `dictionary` - The Dictionary to scrub.
+/// * `rules` - The rules whose keys matched the path to the Dictionary.
+/// * `depth` - How many keys of the rules the path to the Dictionary matched.
+fn scrub_dictionary(dictionary: &Dictionary, rules: &[&Rule], depth: usize) -> Dictionary {
+    let copy = Dictionary::new();
+    for (key, value) in dictionary.iter() {
+        if let Some(value) = scrub_child(&key.to_string(), &value, rules, depth) {
+            copy.insert(key, value);
+        }
+    }
+
+    copy.into_shared()
+}
+
+/// Returns a copy of an Array with the values matched by the rules removed or
+/// masked.
+///
+/// # Arguments
+///
+/// * `array` - The Array to scrub.
+/// * `rules` - The rules whose keys matched the path to the Array.
+/// * `depth` - How many keys of the rules the path to the Array matched.
+fn scrub_array(array: &VariantArray, rules: &[&Rule], depth: usize) -> VariantArray {
+    let copy = VariantArray::new();
+    for (index, value) in array.iter().enumerate() {
+        if let Some(value) = scrub_child(&index.to_string(), &value, rules, depth) {
+            copy.push(value);
+        }
+    }
+
+    copy.into_shared()
+}
+
+/// Returns a value of a Dictionary or Array with the rules applied to it, or
+/// `None` if it's removed.
+///
+/// # Arguments
+///
+/// * `key` - The key or index of the value.
+/// * `value` - The value.
+/// * `rules` - The rules whose keys matched the path to the value's parent.
+/// * `depth` - How many keys of the rules the path to the value's parent
+/// matched.
+fn scrub_child(key: &str, value: &Variant, rules: &[&Rule], depth: usize) -> Option<Variant> {
+    let matching = rules
+        .iter()
+        .copied()
+        .filter(|rule| rule.keys[depth] == key || rule.keys[depth] == WILDCARD)
+        .collect::<Vec<_>>();
+
+    // A value that is removed by one rule and masked by another is removed.
+    let treatments = matching
+        .iter()
+        .filter(|rule| rule.keys.len() == depth + 1)
+        .map(|rule| rule.treatment)
+        .collect::<Vec<_>>();
+    if treatments.contains(&Treatment::Remove) {
+        return None;
+    }
+    if treatments.contains(&Treatment::Mask) {
+        return Some(Variant::from_str(MASK));
+    }
+
+    let deeper = matching
+        .into_iter()
+        .filter(|rule| rule.keys.len() > depth + 1)
+        .collect::<Vec<_>>();
+    if deeper.is_empty() {
+        return Some(value.clone());
+    }
+
+    if let Some(dictionary) = value.try_to_dictionary() {
+        Some(scrub_dictionary(&dictionary, &deeper, depth + 1).owned_to_variant())
+    } else if let Some(array) = value.try_to_array() {
+        Some(scrub_array(&array, &deeper, depth + 1).owned_to_variant())
+    } else {
+        Some(value.clone())
+    }
+}
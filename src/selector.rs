@@ -0,0 +1,260 @@
+use gdnative::prelude::{Dictionary, Variant};
+
+use crate::equality::Equality;
+use crate::path;
+use crate::subscriber::Subscriber;
+
+/// A subscription added with `subscribe_selector`, which runs its callback
+/// only when the value picked out of the state by its selector changes.
+#[derive(Clone)]
+pub struct SelectorSubscription {
+    /// The function that picks the value out of the state.
+    selector: Subscriber,
+    /// The function run when the value changes.
+    callback: Subscriber,
+    /// How the old and the new value are compared.
+    equality: Equality,
+    /// The value the selector returned last.
+    last: Variant,
+}
+
+impl SelectorSubscription {
+    /// Creates a subscription that starts from the value the selector returns
+    /// for the current state.
+    ///
+    /// # Arguments
+    ///
+    /// * `selector` - The function that picks the value out of the state.
+    /// * `callback` - The function run when the value changes.
+    /// * `equality` - How the old and the new value are compared.
+    /// * `state` - The current state.
+    pub fn new(
+        selector: Subscriber,
+        callback: Subscriber,
+        equality: Equality,
+        state: &Dictionary,
+    ) -> Self {
+        let last = selector
+            .call(&[Variant::from_dictionary(state)])
+            .unwrap_or_default();
+
+        SelectorSubscription {
+            selector,
+            callback,
+            equality,
+            last,
+        }
+    }
+
+    /// Returns the selector.
+    pub fn selector(&self) -> &Subscriber {
+        &self.selector
+    }
+
+    /// Returns whether either the selector or the callback is on an instance.
+    ///
+    /// # Arguments
+    ///
+    /// * `instance_id` - The instance id of the instance.
+    pub fn belongs_to(&self, instance_id: i64) -> bool {
+        self.selector.belongs_to(instance_id) || self.callback.belongs_to(instance_id)
+    }
+
+    /// Returns whether the instances of both the selector and the callback
+    /// haven't been freed.
+    pub fn is_alive(&self) -> bool {
+        self.selector.is_alive() && self.callback.is_alive()
+    }
+
+    /// Returns the value the selector returns for the state if it isn't equal
+    /// to the last one, or `None` if it is, or an error if the selector
+    /// couldn't be called.
+    ///
+    /// # Arguments
+    ///
+    /// * `state` - The new state.
+    pub fn select(&self, state: &Dictionary) -> Result<Option<Variant>, String> {
+        let value = self.selector.call(&[Variant::from_dictionary(state)])?;
+        if self.equality.equals(&self.last, &value) {
+            return Ok(None);
+        }
+
+        Ok(Some(value))
+    }
+
+    /// Remembers the new value and returns the callback along with what it's
+    /// passed: the new and the old value.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The new value.
+    pub fn update(&mut self, value: Variant) -> (Subscriber, [Variant; 2]) {
+        let old = std::mem::replace(&mut self.last, value.clone());
+
+        (self.callback.clone(), [value, old])
+    }
+}
+
+/// A selector registered with `register_selector`, which remembers what it
+/// returned last so it's only run again once its inputs change.
+pub struct MemoizedSelector {
+    /// The function that computes the value from the state.
+    selector: Subscriber,
+    /// The paths of the state the selector depends on, or none if it depends
+    /// on the whole state.
+    inputs: Vec<String>,
+    /// How the inputs are compared to the ones the selector was last run with.
+    equality: Equality,
+    /// The inputs the selector was last run with and the value it returned,
+    /// if it was run yet.
+    last: Option<(Vec<Variant>, Variant)>,
+}
+
+impl MemoizedSelector {
+    /// Creates a selector that hasn't been run yet.
+    ///
+    /// # Arguments
+    ///
+    /// * `selector` - The function that computes the value from the state.
+    /// * `inputs` - The paths of the state the selector depends on, or none
+    /// if it depends on the whole state.
+    /// * `equality` - How the inputs are compared.
+    pub fn new(selector: Subscriber, inputs: Vec<String>, equality: Equality) -> Self {
+        MemoizedSelector {
+            selector,
+            inputs,
+            equality,
+            last: None,
+        }
+    }
+
+    /// Returns the selector.
+    pub fn selector(&self) -> &Subscriber {
+        &self.selector
+    }
+
+    /// Returns the inputs of the selector for a state, which are the values
+    /// at its input paths, or the state itself if it doesn't have any.
+    ///
+    /// # Arguments
+    ///
+    /// * `state` - The state.
+    pub fn inputs(&self, state: &Dictionary) -> Vec<Variant> {
+        if self.inputs.is_empty() {
+            return vec![Variant::from_dictionary(state)];
+        }
+
+        self.inputs
+            .iter()
+            .map(|input| path::get(state, input).unwrap_or_else(Variant::new))
+            .collect()
+    }
+
+    /// Returns the value the selector returned last if it was run with inputs
+    /// equal to these, or `None` if it has to be run again.
+    ///
+    /// # Arguments
+    ///
+    /// * `inputs` - The inputs for the current state.
+    pub fn cached(&self, inputs: &[Variant]) -> Option<Variant> {
+        let (last_inputs, value) = self.last.as_ref()?;
+        let unchanged = last_inputs.len() == inputs.len()
+            && last_inputs
+                .iter()
+                .zip(inputs)
+                .all(|(last, input)| self.equality.equals(last, input));
+
+        if unchanged {
+            Some(value.clone())
+        } else {
+            None
+        }
+    }
+
+    /// Remembers the value the selector returned for its inputs.
+    ///
+    /// # Arguments
+    ///
+    /// * `inputs` - The inputs the selector was run with.
+    /// * `value` - The value it returned.
+    pub fn remember(&mut self, inputs: Vec<Variant>, value: Variant) {
+        self.last = Some((inputs, value));
+    }
+}
+
+/// A subscription added with `subscribe_to_path`, which runs its callback
+/// only when the value at a path of the state changes.
+#[derive(Clone)]
+pub struct PathSubscription {
+    /// The path of the value, for example `player/hp`.
+    path: String,
+    /// The function run when the value changes.
+    callback: Subscriber,
+    /// The value at the path after the last change, or `null` if the path
+    /// wasn't in the state.
+    last: Variant,
+}
+
+impl PathSubscription {
+    /// Creates a subscription that starts from the value at the path in the
+    /// current state.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the value.
+    /// * `callback` - The function run when the value changes.
+    /// * `state` - The current state.
+    pub fn new(path: String, callback: Subscriber, state: &Dictionary) -> Self {
+        let last = path::get(state, &path).unwrap_or_default();
+
+        PathSubscription {
+            path,
+            callback,
+            last,
+        }
+    }
+
+    /// Returns whether the callback is on an instance.
+    ///
+    /// # Arguments
+    ///
+    /// * `instance_id` - The instance id of the instance.
+    pub fn belongs_to(&self, instance_id: i64) -> bool {
+        self.callback.belongs_to(instance_id)
+    }
+
+    /// Returns whether the instance of the callback hasn't been freed.
+    pub fn is_alive(&self) -> bool {
+        self.callback.is_alive()
+    }
+
+    /// Returns the value at the path in the state if it changed since the
+    /// last change, or `None` if it didn't. A value that is the same
+    /// Dictionary or Array is skipped without comparing what's inside of it.
+    ///
+    /// # Arguments
+    ///
+    /// * `state` - The new state.
+    pub fn select(&self, state: &Dictionary) -> Option<Variant> {
+        let value = path::get(state, &self.path).unwrap_or_default();
+        if Equality::Reference.equals(&self.last, &value)
+            || Equality::Deep.equals(&self.last, &value)
+        {
+            return None;
+        }
+
+        Some(value)
+    }
+
+    /// Remembers the new value and returns the callback along with what it's
+    /// passed: the new and the old value.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The new value.
+    pub fn update(&mut self, value: Variant) -> (Subscriber, [Variant; 2]) {
+        let old = std::mem::replace(&mut self.last, value.clone());
+
+        (self.callback.clone(), [value, old])
+    }
+}
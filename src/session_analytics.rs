@@ -0,0 +1,218 @@
+use std::collections::BTreeMap;
+
+use gdnative::prelude::{Dictionary, Unique, Variant, VariantType};
+
+use crate::action::{self, action_type};
+
+/// The most different values of a text or bool field that are counted for
+/// an action type, so a field like a player name doesn't grow the analytics
+/// without end. Values after these are counted under `other`.
+const MAX_VALUES: usize = 20;
+
+/// What was recorded about a field of the actions of a type.
+#[derive(Default)]
+struct FieldStats {
+    /// The number of actions the field was a number in.
+    numbers: u64,
+    /// The sum of the numbers.
+    sum: f64,
+    /// The smallest number.
+    min: f64,
+    /// The largest number.
+    max: f64,
+    /// The number of times each text or bool value was seen, in the order
+    /// the values were first seen.
+    values: Vec<(Variant, u64)>,
+    /// The number of text or bool values that weren't counted since there
+    /// were already `MAX_VALUES` different ones.
+    other: u64,
+}
+
+impl FieldStats {
+    /// Records a value of the field.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The value.
+    fn record(&mut self, value: &Variant) {
+        match value.get_type() {
+            VariantType::I64 | VariantType::F64 => {
+                let number = value.to_f64();
+                if self.numbers == 0 {
+                    self.min = number;
+                    self.max = number;
+                } else {
+                    self.min = self.min.min(number);
+                    self.max = self.max.max(number);
+                }
+                self.numbers += 1;
+                self.sum += number;
+            }
+            VariantType::Bool | VariantType::GodotString => {
+                match self.values.iter_mut().find(|(seen, _)| seen == value) {
+                    Some((_, count)) => *count += 1,
+                    None if self.values.len() < MAX_VALUES => self.values.push((value.clone(), 1)),
+                    None => self.other += 1,
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Returns the stats of the field, with the number of times it was a
+    /// number under `count` and the sum, smallest, largest, and average
+    /// number under `sum`, `min`, `max`, and `average`, and the number of
+    /// times each text or bool value was seen under `values`, by value.
+    fn to_dictionary(&self) -> Dictionary<Unique> {
+        let stats = Dictionary::new();
+        if self.numbers > 0 {
+            stats.insert("count", self.numbers as i64);
+            stats.insert("sum", self.sum);
+            stats.insert("min", self.min);
+            stats.insert("max", self.max);
+            stats.insert("average", self.sum / self.numbers as f64);
+        }
+        if !self.values.is_empty() {
+            let values = Dictionary::new();
+            for (value, count) in &self.values {
+                values.insert(value, *count as i64);
+            }
+            if self.other > 0 {
+                values.insert("other", self.other as i64);
+            }
+            stats.insert("values", values.into_shared());
+        }
+
+        stats
+    }
+}
+
+/// What was recorded about the actions of a type.
+struct ActionStats {
+    /// The type of the actions.
+    action_type: Variant,
+    /// The number of actions.
+    count: u64,
+    /// The time the first action was dispatched at, in seconds.
+    first_at: f64,
+    /// The time the last action was dispatched at, in seconds.
+    last_at: f64,
+    /// What was recorded about the fields of the actions, by key.
+    fields: BTreeMap<String, FieldStats>,
+}
+
+/// How often each type of action was dispatched during a play session and
+/// what was in them, recorded once it's enabled with
+/// `set_session_analytics`, so designers can see how players actually play
+/// without an analytics service.
+pub struct SessionAnalytics {
+    /// The time the session started at, in seconds.
+    started_at: f64,
+    /// What was recorded about each action type, in the order the types were
+    /// first dispatched.
+    actions: Vec<ActionStats>,
+}
+
+impl SessionAnalytics {
+    /// Starts a session.
+    ///
+    /// # Arguments
+    ///
+    /// * `now` - The current time, in seconds.
+    pub fn new(now: f64) -> Self {
+        SessionAnalytics {
+            started_at: now,
+            actions: vec![],
+        }
+    }
+
+    /// Records an action that passed the reducer, counting it and adding the
+    /// numbers, texts, and bools in it to the stats of its fields.
+    ///
+    /// # Arguments
+    ///
+    /// * `action` - The action.
+    /// * `now` - The current time, in seconds.
+    pub fn record(&mut self, action: &Variant, now: f64) {
+        let action_type = action_type(action);
+        let index = match self
+            .actions
+            .iter()
+            .position(|stats| stats.action_type == action_type)
+        {
+            Some(index) => index,
+            None => {
+                self.actions.push(ActionStats {
+                    action_type,
+                    count: 0,
+                    first_at: now,
+                    last_at: now,
+                    fields: BTreeMap::new(),
+                });
+                self.actions.len() - 1
+            }
+        };
+
+        let stats = &mut self.actions[index];
+        stats.count += 1;
+        stats.last_at = now;
+
+        if let Some(action) = action.try_to_dictionary() {
+            for (key, value) in action.iter() {
+                let key = key.to_string();
+                if key != action::TYPE_KEY {
+                    stats.fields.entry(key).or_default().record(&value);
+                }
+            }
+        }
+    }
+
+    /// Returns what was recorded during the session, with how long it has
+    /// lasted in seconds under `duration`, the number of actions under
+    /// `action_count`, and the stats of each action type under `actions`, by
+    /// type. Each type has the number of actions under `count`, how many of
+    /// them were dispatched a minute on average under `per_minute`, the times
+    /// since the start of the session the first and last one were dispatched
+    /// at under `first_at` and `last_at`, and the stats of each field under
+    /// `payload`, by key.
+    ///
+    /// # Arguments
+    ///
+    /// * `now` - The current time, in seconds.
+    pub fn to_dictionary(&self, now: f64) -> Dictionary<Unique> {
+        let duration = now - self.started_at;
+
+        let actions = Dictionary::new();
+        for stats in &self.actions {
+            let payload = Dictionary::new();
+            for (key, field) in &stats.fields {
+                payload.insert(key.as_str(), field.to_dictionary().into_shared());
+            }
+
+            let entry = Dictionary::new();
+            entry.insert("count", stats.count as i64);
+            entry.insert(
+                "per_minute",
+                if duration > 0.0 {
+                    stats.count as f64 / duration * 60.0
+                } else {
+                    0.0
+                },
+            );
+            entry.insert("first_at", stats.first_at - self.started_at);
+            entry.insert("last_at", stats.last_at - self.started_at);
+            entry.insert("payload", payload.into_shared());
+            actions.insert(&stats.action_type, entry.into_shared());
+        }
+
+        let analytics = Dictionary::new();
+        analytics.insert("duration", duration);
+        analytics.insert(
+            "action_count",
+            self.actions.iter().map(|stats| stats.count).sum::<u64>() as i64,
+        );
+        analytics.insert("actions", actions.into_shared());
+
+        analytics
+    }
+}
@@ -0,0 +1,265 @@
+use gdnative::prelude::{OwnedToVariant, Variant, VariantType};
+
+use crate::math_type;
+
+/// The number of spaces each nested level of a snapshot is indented by.
+const INDENT: usize = 2;
+
+/// The number of unchanged lines shown around each change in a diff.
+const DIFF_CONTEXT: usize = 2;
+
+/// The largest number of line comparisons a diff is allowed to make before
+/// it gives up and only reports the first line that is different.
+const MAX_DIFF_COMPARISONS: usize = 4_000_000;
+
+/// Serializes a value to pretty printed JSON with the keys of every
+/// Dictionary sorted, so the same state always produces exactly the same text
+/// no matter what order its keys were inserted in. `Vector2`s, `Vector3`s,
+/// and `Color`s are written as a Dictionary with their type under `__type`
+/// and their components, like `{"__type": "Vector2", "x": 1.0, "y": 2.0}`,
+/// which `file::parse_json` turns back into them. Other values that JSON has
+/// no representation for, like a `NodePath`, are written as a string made up
+/// of their type and value.
+///
+/// # Arguments
+///
+/// * `value` - The value to serialize.
+pub fn to_stable_text(value: &Variant) -> String {
+    let mut text = String::new();
+    write_value(value, 0, &mut text);
+    text.push('\n');
+
+    text
+}
+
+/// Writes a value to the text.
+///
+/// # Arguments
+///
+/// * `value` - The value to write.
+/// * `depth` - How deeply nested the value is.
+/// * `text` - The text to write the value to.
+fn write_value(value: &Variant, depth: usize, text: &mut String) {
+    match value.get_type() {
+        VariantType::Nil => text.push_str("null"),
+        VariantType::Bool => text.push_str(if value.to_bool() { "true" } else { "false" }),
+        VariantType::I64 => text.push_str(&value.to_i64().to_string()),
+        VariantType::F64 => text.push_str(&format!("{:?}", value.to_f64())),
+        VariantType::GodotString => write_string(&value.to_string(), text),
+        VariantType::Dictionary => {
+            let dictionary = value.to_dictionary();
+            let mut entries = dictionary
+                .iter()
+                .map(|(key, value)| (key.to_string(), value))
+                .collect::<Vec<_>>();
+            entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+            write_nested('{', '}', entries.len(), depth, text, |index, text| {
+                let (key, value) = &entries[index];
+                write_string(key, text);
+                text.push_str(": ");
+                write_value(value, depth + 1, text);
+            });
+        }
+        VariantType::VariantArray => {
+            let array = value.to_array();
+
+            write_nested(
+                '[',
+                ']',
+                array.len() as usize,
+                depth,
+                text,
+                |index, text| {
+                    write_value(&array.get(index as i32), depth + 1, text);
+                },
+            );
+        }
+        other => match math_type::to_tagged(value) {
+            Some(tagged) => write_value(&tagged.owned_to_variant(), depth, text),
+            None => write_string(&format!("{:?}{}", other, value.to_string()), text),
+        },
+    }
+}
+
+/// Writes a Dictionary or Array with each of its items on its own line.
+///
+/// # Arguments
+///
+/// * `open` - The character that opens the collection.
+/// * `close` - The character that closes the collection.
+/// * `len` - The number of items in the collection.
+/// * `depth` - How deeply nested the collection is.
+/// * `text` - The text to write the collection to.
+/// * `write_item` - Writes the item at an index to the text.
+fn write_nested<F>(
+    open: char,
+    close: char,
+    len: usize,
+    depth: usize,
+    text: &mut String,
+    write_item: F,
+) where
+    F: Fn(usize, &mut String),
+{
+    text.push(open);
+
+    if len > 0 {
+        for index in 0..len {
+            text.push('\n');
+            text.push_str(&" ".repeat((depth + 1) * INDENT));
+            write_item(index, text);
+            if index + 1 < len {
+                text.push(',');
+            }
+        }
+
+        text.push('\n');
+        text.push_str(&" ".repeat(depth * INDENT));
+    }
+
+    text.push(close);
+}
+
+/// Writes a string as a quoted and escaped JSON string.
+///
+/// # Arguments
+///
+/// * `string` - The string to write.
+/// * `text` - The text to write the string to.
+fn write_string(string: &str, text: &mut String) {
+    text.push('"');
+
+    for c in string.chars() {
+        match c {
+            '"' => text.push_str("\\\""),
+            '\\' => text.push_str("\\\\"),
+            '\n' => text.push_str("\\n"),
+            '\r' => text.push_str("\\r"),
+            '\t' => text.push_str("\\t"),
+            c if (c as u32) < 0x20 => text.push_str(&format!("\\u{:04x}", c as u32)),
+            c => text.push(c),
+        }
+    }
+
+    text.push('"');
+}
+
+/// A single line of a diff.
+enum DiffLine<'a> {
+    /// A line that is the same in both texts.
+    Same(&'a str),
+    /// A line that is only in the expected text.
+    Removed(&'a str),
+    /// A line that is only in the actual text.
+    Added(&'a str),
+}
+
+/// Returns a readable line by line diff between two texts, where lines only
+/// in the expected text start with `-` and lines only in the actual text start
+/// with `+`. Only the changed lines and a few lines around them are included.
+///
+/// # Arguments
+///
+/// * `expected` - The text that was expected.
+/// * `actual` - The text that was produced.
+pub fn line_diff(expected: &str, actual: &str) -> String {
+    let expected = expected.lines().collect::<Vec<_>>();
+    let actual = actual.lines().collect::<Vec<_>>();
+
+    if expected.len().saturating_mul(actual.len()) > MAX_DIFF_COMPARISONS {
+        return first_difference(&expected, &actual);
+    }
+
+    let lines = diff_lines(&expected, &actual);
+    let is_change = |line: &DiffLine| !matches!(line, DiffLine::Same(_));
+
+    let mut diff = vec![];
+    let mut last_shown = None;
+    for (index, line) in lines.iter().enumerate() {
+        let start = index.saturating_sub(DIFF_CONTEXT);
+        let end = (index + DIFF_CONTEXT + 1).min(lines.len());
+        if !lines[start..end].iter().any(is_change) {
+            continue;
+        }
+
+        if let Some(last) = last_shown {
+            if index > last + 1 {
+                diff.push("...".to_string());
+            }
+        }
+        last_shown = Some(index);
+
+        diff.push(match line {
+            DiffLine::Same(line) => format!("  {}", line),
+            DiffLine::Removed(line) => format!("- {}", line),
+            DiffLine::Added(line) => format!("+ {}", line),
+        });
+    }
+
+    diff.join("\n")
+}
+
+/// Returns the lines of both texts marked with whether they are the same,
+/// removed, or added, using the longest common subsequence of lines.
+///
+/// # Arguments
+///
+/// * `expected` - The lines of the text that was expected.
+/// * `actual` - The lines of the text that was produced.
+fn diff_lines<'a>(expected: &[&'a str], actual: &[&'a str]) -> Vec<DiffLine<'a>> {
+    // `common[i][j]` is the length of the longest common subsequence of
+    // `expected[i..]` and `actual[j..]`.
+    let mut common = vec![vec![0usize; actual.len() + 1]; expected.len() + 1];
+    for i in (0..expected.len()).rev() {
+        for j in (0..actual.len()).rev() {
+            common[i][j] = if expected[i] == actual[j] {
+                common[i + 1][j + 1] + 1
+            } else {
+                common[i + 1][j].max(common[i][j + 1])
+            };
+        }
+    }
+
+    let mut lines = vec![];
+    let (mut i, mut j) = (0, 0);
+    while i < expected.len() && j < actual.len() {
+        if expected[i] == actual[j] {
+            lines.push(DiffLine::Same(expected[i]));
+            i += 1;
+            j += 1;
+        } else if common[i + 1][j] >= common[i][j + 1] {
+            lines.push(DiffLine::Removed(expected[i]));
+            i += 1;
+        } else {
+            lines.push(DiffLine::Added(actual[j]));
+            j += 1;
+        }
+    }
+    lines.extend(expected[i..].iter().map(|&line| DiffLine::Removed(line)));
+    lines.extend(actual[j..].iter().map(|&line| DiffLine::Added(line)));
+
+    lines
+}
+
+/// Describes the first line that is different between two texts, used when
+/// the texts are too large to diff.
+///
+/// # Arguments
+///
+/// * `expected` - The lines of the text that was expected.
+/// * `actual` - The lines of the text that was produced.
+fn first_difference(expected: &[&str], actual: &[&str]) -> String {
+    let index = expected
+        .iter()
+        .zip(actual)
+        .position(|(a, b)| a != b)
+        .unwrap_or_else(|| expected.len().min(actual.len()));
+
+    format!(
+        "First difference at line {}:\n- {}\n+ {}",
+        index + 1,
+        expected.get(index).unwrap_or(&""),
+        actual.get(index).unwrap_or(&"")
+    )
+}
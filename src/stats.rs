@@ -0,0 +1,322 @@
+use gdnative::prelude::{Dictionary, Unique, Variant, VariantArray, VariantType};
+
+use crate::action::{self, action_type};
+use crate::path;
+
+/// The key of the slice of the state that holds the statistics and unlocked
+/// achievements.
+pub const SLICE: &str = "stats";
+
+/// The key of the value of every statistic in the slice.
+const VALUES_KEY: &str = "values";
+
+/// The key of the names of the unlocked achievements in the slice.
+const UNLOCKED_KEY: &str = "unlocked";
+
+/// How a rule changes its statistic.
+#[derive(Clone, Copy)]
+enum Op {
+    /// Adds the amount of the rule, which is 1 by default.
+    Increment,
+    /// Subtracts the amount of the rule, which is 1 by default.
+    Decrement,
+    /// Adds the value under the field of the action.
+    Add,
+    /// Sets the statistic to the value under the field of the action.
+    Set,
+    /// Keeps the largest of the statistic and the value under the field of the
+    /// action.
+    Max,
+    /// Keeps the smallest of the statistic and the value under the field of
+    /// the action.
+    Min,
+}
+
+/// A rule that changes a statistic when an action of its type is dispatched.
+#[derive(Clone)]
+struct Rule {
+    /// The type of the action that triggers the rule.
+    action: Variant,
+    /// The name of the statistic.
+    stat: String,
+    /// How the statistic is changed.
+    op: Op,
+    /// The amount used by `increment` and `decrement`.
+    amount: Variant,
+    /// The field of the action whose value is used by the other operations.
+    field: String,
+}
+
+/// An achievement that is unlocked once a statistic reaches a threshold.
+#[derive(Clone)]
+struct Achievement {
+    /// The name of the achievement.
+    name: String,
+    /// The name of the statistic.
+    stat: String,
+    /// The value the statistic has to reach.
+    threshold: f64,
+}
+
+/// The statistics tracked by the store, configured with `enable_stats`.
+#[derive(Clone)]
+pub struct Stats {
+    /// The rules that change the statistics.
+    rules: Vec<Rule>,
+    /// The achievements that can be unlocked.
+    achievements: Vec<Achievement>,
+    /// The file the statistics are saved to, if they are saved.
+    pub save_path: Option<String>,
+}
+
+impl Stats {
+    /// Creates the statistics from their rules and achievements, returning an
+    /// error describing the first rule or achievement that isn't valid.
+    ///
+    /// # Arguments
+    ///
+    /// * `rules` - Dictionaries with the type of the action under `action`, the
+    /// name of the statistic under `stat`, and the operation under `op`.
+    /// * `achievements` - Dictionaries with the name of the achievement under
+    /// `name`, the name of the statistic under `stat`, and the value it has to
+    /// reach under `threshold`.
+    /// * `save_path` - The file the statistics are saved to, if they are saved.
+    pub fn new(
+        rules: &VariantArray,
+        achievements: &VariantArray,
+        save_path: Option<String>,
+    ) -> Result<Self, String> {
+        let rules = rules
+            .iter()
+            .map(|rule| parse_rule(&rule))
+            .collect::<Result<Vec<_>, _>>()?;
+        let achievements = achievements
+            .iter()
+            .map(|achievement| parse_achievement(&achievement))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Stats {
+            rules,
+            achievements,
+            save_path,
+        })
+    }
+
+    /// Returns the slice with nothing unlocked and every statistic at 0,
+    /// except for the ones kept by `max` and `min` which start out as `null`
+    /// until the first value is seen.
+    pub fn initial_slice(&self) -> Dictionary<Unique> {
+        let values = Dictionary::new();
+        for rule in &self.rules {
+            match rule.op {
+                Op::Max | Op::Min => values.insert(&rule.stat, Variant::new()),
+                _ if !values.contains(&rule.stat) => values.insert(&rule.stat, 0),
+                _ => {}
+            }
+        }
+
+        slice(values, VariantArray::new())
+    }
+
+    /// Returns the slice after an action along with the names of the
+    /// achievements it unlocked, or `None` if no rule matches the action.
+    ///
+    /// # Arguments
+    ///
+    /// * `current` - The slice before the action.
+    /// * `action` - The action that was dispatched.
+    pub fn reduce(
+        &self,
+        current: &Dictionary,
+        action: &Variant,
+    ) -> Option<(Dictionary<Unique>, Vec<String>)> {
+        let action_type = action_type(action);
+        let rules = self
+            .rules
+            .iter()
+            .filter(|rule| rule.action == action_type)
+            .collect::<Vec<_>>();
+        if rules.is_empty() {
+            return None;
+        }
+
+        let values = dictionary_or_empty(current, VALUES_KEY).duplicate();
+        for rule in rules {
+            let value = if values.contains(&rule.stat) {
+                values.get(&rule.stat)
+            } else {
+                Variant::from_i64(0)
+            };
+            values.insert(&rule.stat, apply(rule, &value, action));
+        }
+
+        let unlocked = array_or_empty(current, UNLOCKED_KEY).duplicate();
+        let mut newly_unlocked = vec![];
+        for achievement in &self.achievements {
+            if unlocked.contains(&achievement.name) || !values.contains(&achievement.stat) {
+                continue;
+            }
+
+            let value = values.get(&achievement.stat);
+            if is_number(&value) && value.to_f64() >= achievement.threshold {
+                unlocked.push(&achievement.name);
+                newly_unlocked.push(achievement.name.clone());
+            }
+        }
+
+        Some((slice(values, unlocked), newly_unlocked))
+    }
+}
+
+/// Returns the value of a statistic after a rule is applied to it.
+///
+/// # Arguments
+///
+/// * `rule` - The rule to apply.
+/// * `value` - The value of the statistic before the rule is applied.
+/// * `action` - The action that triggered the rule.
+fn apply(rule: &Rule, value: &Variant, action: &Variant) -> Variant {
+    let field = action::field(action, &rule.field).unwrap_or_default();
+
+    match rule.op {
+        Op::Increment => add(value, &rule.amount, 1),
+        Op::Decrement => add(value, &rule.amount, -1),
+        Op::Add => add(value, &field, 1),
+        Op::Set => field,
+        Op::Max | Op::Min if !is_number(&field) => value.clone(),
+        Op::Max | Op::Min if !is_number(value) => field,
+        Op::Max if field.to_f64() > value.to_f64() => field,
+        Op::Min if field.to_f64() < value.to_f64() => field,
+        Op::Max | Op::Min => value.clone(),
+    }
+}
+
+/// Returns the sum of two numbers, or `a` minus `b` when `sign` is -1. The
+/// result is an integer when both numbers are integers.
+///
+/// # Arguments
+///
+/// * `a` - The first number.
+/// * `b` - The second number.
+/// * `sign` - 1 to add the numbers or -1 to subtract them.
+fn add(a: &Variant, b: &Variant, sign: i64) -> Variant {
+    if a.get_type() == VariantType::I64 && b.get_type() == VariantType::I64 {
+        Variant::from_i64(a.to_i64() + sign * b.to_i64())
+    } else {
+        Variant::from_f64(a.to_f64() + sign as f64 * b.to_f64())
+    }
+}
+
+/// Returns whether a value is an integer or a float.
+///
+/// # Arguments
+///
+/// * `value` - The value to check.
+fn is_number(value: &Variant) -> bool {
+    matches!(value.get_type(), VariantType::I64 | VariantType::F64)
+}
+
+/// Parses a rule from its Dictionary.
+///
+/// # Arguments
+///
+/// * `rule` - The Dictionary of the rule.
+fn parse_rule(rule: &Variant) -> Result<Rule, String> {
+    let definition = rule.try_to_dictionary().ok_or_else(|| {
+        format!(
+            "A stats rule has to be a Dictionary, got {}",
+            rule.to_string()
+        )
+    })?;
+    let get = |key: &str| path::get(&definition, key);
+
+    let op = match get("op").map(|op| op.to_string()).as_deref() {
+        Some("increment") | None => Op::Increment,
+        Some("decrement") => Op::Decrement,
+        Some("add") => Op::Add,
+        Some("set") => Op::Set,
+        Some("max") => Op::Max,
+        Some("min") => Op::Min,
+        Some(other) => return Err(format!("Unknown stats rule op {}", other)),
+    };
+
+    Ok(Rule {
+        action: get("action")
+            .ok_or_else(|| format!("The stats rule {} is missing its action", rule.to_string()))?,
+        stat: get("stat")
+            .ok_or_else(|| format!("The stats rule {} is missing its stat", rule.to_string()))?
+            .to_string(),
+        op,
+        amount: get("amount").unwrap_or_else(|| Variant::from_i64(1)),
+        field: get("field")
+            .map(|field| field.to_string())
+            .unwrap_or_else(|| "value".to_string()),
+    })
+}
+
+/// Parses an achievement from its Dictionary.
+///
+/// # Arguments
+///
+/// * `achievement` - The Dictionary of the achievement.
+fn parse_achievement(achievement: &Variant) -> Result<Achievement, String> {
+    let definition = achievement.try_to_dictionary().ok_or_else(|| {
+        format!(
+            "An achievement has to be a Dictionary, got {}",
+            achievement.to_string()
+        )
+    })?;
+    let get = |key: &str| {
+        path::get(&definition, key).ok_or_else(|| {
+            format!(
+                "The achievement {} is missing its {}",
+                achievement.to_string(),
+                key
+            )
+        })
+    };
+
+    Ok(Achievement {
+        name: get("name")?.to_string(),
+        stat: get("stat")?.to_string(),
+        threshold: get("threshold")?.to_f64(),
+    })
+}
+
+/// Creates the slice.
+///
+/// # Arguments
+///
+/// * `values` - The value of every statistic.
+/// * `unlocked` - The names of the unlocked achievements.
+fn slice(values: Dictionary<Unique>, unlocked: VariantArray<Unique>) -> Dictionary<Unique> {
+    let slice = Dictionary::new();
+    slice.insert(VALUES_KEY, values);
+    slice.insert(UNLOCKED_KEY, unlocked);
+
+    slice
+}
+
+/// Returns the Dictionary under a key of the slice, or an empty one.
+///
+/// # Arguments
+///
+/// * `slice` - The slice.
+/// * `key` - The key of the Dictionary.
+fn dictionary_or_empty(slice: &Dictionary, key: &str) -> Dictionary {
+    path::get(slice, key)
+        .and_then(|value| value.try_to_dictionary())
+        .unwrap_or_else(Dictionary::new_shared)
+}
+
+/// Returns the Array under a key of the slice, or an empty one.
+///
+/// # Arguments
+///
+/// * `slice` - The slice.
+/// * `key` - The key of the Array.
+fn array_or_empty(slice: &Dictionary, key: &str) -> VariantArray {
+    path::get(slice, key)
+        .and_then(|value| value.try_to_array())
+        .unwrap_or_else(VariantArray::new_shared)
+}
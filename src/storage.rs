@@ -0,0 +1,146 @@
+use gdnative::api::{Directory, JavaScript, OS};
+use gdnative::prelude::godot_warn;
+
+/// The folder that saves given as a relative path go in on desktop, mobile,
+/// and web platforms.
+const USER_ROOT: &str = "user://";
+
+/// The JavaScript that writes the files in `user://` to IndexedDB on HTML5
+/// exports, which otherwise only happens when the engine gets to it.
+const SYNC_SCRIPT: &str = "FS.syncfs(false, function (err) { if (err) console.error(err); });";
+
+/// The kinds of platforms the game can run on, which keep saves in different
+/// places.
+#[derive(PartialEq)]
+enum Platform {
+    /// Windows, macOS, Linux, and servers.
+    Desktop,
+    /// Android and iOS.
+    Mobile,
+    /// HTML5 exports, where `user://` is kept in IndexedDB.
+    Web,
+    /// Any other platform, like a console, which has its own place for saves.
+    Other(String),
+}
+
+impl Platform {
+    /// Returns the platform the game is running on.
+    fn current() -> Self {
+        let name = OS::godot_singleton().get_name().to_string();
+
+        match name.as_str() {
+            "Windows" | "OSX" | "X11" | "Server" => Platform::Desktop,
+            "Android" | "iOS" => Platform::Mobile,
+            "HTML5" => Platform::Web,
+            _ => Platform::Other(name),
+        }
+    }
+}
+
+/// Where the store keeps its saves and what it does after writing them on the
+/// platform the game is running on.
+#[derive(Default)]
+pub struct Storage {
+    /// The folder relative paths are kept in, set with `set_storage_root`,
+    /// instead of the one for the platform.
+    root: Option<String>,
+    /// Whether it was already shown that saves don't last on this platform.
+    warned: bool,
+}
+
+impl Storage {
+    /// Sets the folder relative paths are kept in, or goes back to the one
+    /// for the platform if it's `None`.
+    ///
+    /// # Arguments
+    ///
+    /// * `root` - The folder, for example `user://saves`.
+    pub fn set_root(&mut self, root: Option<String>) {
+        self.root = root;
+    }
+
+    /// Returns the path a save is kept at. Paths like `user://saves/1.save`
+    /// are kept as they are, while relative ones like `saves/1.save` go in
+    /// the root set with `set_root`, or in `user://` on desktop, mobile, and
+    /// web platforms. Other platforms, like consoles, need a root since they
+    /// have their own place for saves.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the save.
+    pub fn path(&self, path: &str) -> Result<String, String> {
+        if path.contains("://") || path.starts_with('/') {
+            return Ok(path.to_string());
+        }
+
+        match (&self.root, Platform::current()) {
+            (Some(root), _) => Ok(join(root, path)),
+            (None, Platform::Other(name)) => Err(format!(
+                "Saves on {} need a folder set with set_storage_root",
+                name
+            )),
+            (None, _) => Ok(join(USER_ROOT, path)),
+        }
+    }
+
+    /// Creates the folder a save goes in if it doesn't exist yet.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the save returned by `path`.
+    pub fn prepare(&self, path: &str) -> Result<(), String> {
+        let folder = match path.rfind('/') {
+            Some(end) => &path[..end],
+            None => return Ok(()),
+        };
+
+        // A save right inside of `user://` or `/` doesn't need a folder.
+        let directory = Directory::new();
+        if folder.is_empty() || folder.ends_with(":/") || directory.dir_exists(folder) {
+            return Ok(());
+        }
+
+        directory
+            .make_dir_recursive(folder)
+            .map_err(|err| format!("Unable to create the folder {}: {}", folder, err))
+    }
+
+    /// Returns whether saves can be written on a worker thread, which isn't
+    /// the case on HTML5 exports without thread support.
+    pub fn has_threads(&self) -> bool {
+        OS::godot_singleton().can_use_threads()
+    }
+
+    /// Makes sure the saves that were written are kept. On HTML5 exports this
+    /// writes `user://` to IndexedDB right away, so a save isn't lost if the
+    /// page is closed before the engine gets to it, and warns once if the
+    /// browser doesn't keep it, like in a private window.
+    pub fn flush(&mut self) {
+        if Platform::current() != Platform::Web {
+            return;
+        }
+
+        if !OS::godot_singleton().is_userfs_persistent() && !self.warned {
+            self.warned = true;
+            godot_warn!(
+                "This browser doesn't keep user://, so saves are lost when the page is closed"
+            );
+        }
+
+        JavaScript::godot_singleton().eval(SYNC_SCRIPT, true);
+    }
+}
+
+/// Joins a relative path onto a folder.
+///
+/// # Arguments
+///
+/// * `root` - The folder, for example `user://` or `user://saves`.
+/// * `path` - The relative path, for example `saves/1.save`.
+fn join(root: &str, path: &str) -> String {
+    if root.ends_with('/') {
+        format!("{}{}", root, path)
+    } else {
+        format!("{}/{}", root, path)
+    }
+}
@@ -0,0 +1,170 @@
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+
+use gdnative::prelude::{
+    core_types::GodotString, godot_error, methods, Instance, NativeClass, Node, OwnedToVariant,
+    Shared, StringArray, Unique, Variant,
+};
+
+use crate::godot_redux::GodotRedux;
+
+/// Keeps stores by name, like one for the UI, one for gameplay, and one for
+/// the settings, so scripts anywhere can get a store by its name instead of
+/// having references passed around. The registry is a Node so it can be
+/// added as an autoload, and the stores it created are freed along with it.
+#[inherit(Node)]
+#[derive(NativeClass)]
+pub struct StoreRegistry {
+    /// The stores, by name.
+    stores: RefCell<BTreeMap<String, Instance<GodotRedux, Shared>>>,
+}
+
+#[methods]
+impl StoreRegistry {
+    /// Initializes the struct with default values.
+    fn new(_owner: &Node) -> Self {
+        StoreRegistry {
+            stores: RefCell::new(BTreeMap::new()),
+        }
+    }
+
+    /// Creates a store with a name and returns it, or returns the store that
+    /// already has the name. The store still needs its state and reducer set
+    /// with `set_state_and_reducer`, and it's freed when it's removed with
+    /// `remove_store` or when the registry is freed.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the store.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _ready():
+    ///     var settings = Stores.create_store("settings")
+    ///     settings.set_state_and_reducer({ "volume": 1.0 }, self, "settings_reducer")
+    /// ```
+    #[export]
+    fn create_store(&self, _owner: &Node, name: GodotString) -> Instance<GodotRedux, Shared> {
+        let name = name.to_string();
+        if let Some(store) = self.store(&name) {
+            return store;
+        }
+
+        let store = Instance::<GodotRedux, Unique>::new().into_shared();
+        self.stores.borrow_mut().insert(name, store.clone());
+
+        store
+    }
+
+    /// Returns the store with a name, or `null` if there's no store with the
+    /// name.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the store.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _on_volume_changed(volume):
+    ///     Stores.get_store("settings").dispatch({ "type": "set_volume", "volume": volume })
+    /// ```
+    #[export]
+    fn get_store(&self, _owner: &Node, name: GodotString) -> Variant {
+        match self.store(&name.to_string()) {
+            Some(store) => store.owned_to_variant(),
+            None => {
+                godot_error!("There is no store named {}", name);
+                Variant::new()
+            }
+        }
+    }
+
+    /// Returns whether there's a store with a name.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the store.
+    #[export]
+    fn has_store(&self, _owner: &Node, name: GodotString) -> bool {
+        self.store(&name.to_string()).is_some()
+    }
+
+    /// Returns the names of the stores, in alphabetical order.
+    #[export]
+    fn get_store_names(&self, _owner: &Node) -> StringArray {
+        self.stores
+            .borrow_mut()
+            .retain(|_, store| unsafe { store.base().is_instance_sane() });
+
+        let names = StringArray::new();
+        for name in self.stores.borrow().keys() {
+            names.push(GodotString::from_str(name));
+        }
+
+        names
+    }
+
+    /// Removes the store with a name and frees it, and returns whether there
+    /// was a store with the name.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the store.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _on_level_unloaded():
+    ///     Stores.remove_store("level")
+    /// ```
+    #[export]
+    fn remove_store(&self, _owner: &Node, name: GodotString) -> bool {
+        let store = self.stores.borrow_mut().remove(&name.to_string());
+        match store {
+            Some(store) => {
+                free(store);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Returns the store with a name, forgetting it if a script freed it.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the store.
+    fn store(&self, name: &str) -> Option<Instance<GodotRedux, Shared>> {
+        let store = self.stores.borrow().get(name).cloned()?;
+        if unsafe { store.base().is_instance_sane() } {
+            return Some(store);
+        }
+
+        self.stores.borrow_mut().remove(name);
+        None
+    }
+}
+
+impl Drop for StoreRegistry {
+    fn drop(&mut self) {
+        for (_, store) in std::mem::take(self.stores.get_mut()) {
+            free(store);
+        }
+    }
+}
+
+/// Frees a store, unless a script freed it already.
+///
+/// # Arguments
+///
+/// * `store` - The store.
+fn free(store: Instance<GodotRedux, Shared>) {
+    let store = store.base().clone();
+    unsafe {
+        if store.is_instance_sane() {
+            store.assume_unique().free();
+        }
+    }
+}
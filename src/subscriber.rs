@@ -0,0 +1,114 @@
+use gdnative::api::{FuncRef, Reference};
+use gdnative::prelude::{GodotObject, GodotString, Object, Ref, Shared, ThreadLocal, Variant};
+
+use crate::godot_redux::func_ref;
+
+/// A function the store calls back into, like a subscription, along with a
+/// description of it so a call that fails can be reported without stopping
+/// the other calls. The instance with the function is only referenced weakly
+/// unless the subscriber is strong, so a subscriber whose instance was freed
+/// counts as unsubscribed.
+#[derive(Clone)]
+pub struct Subscriber {
+    /// The function.
+    callback: Ref<FuncRef, ThreadLocal>,
+    /// The class and id of the instance and the name of the function, like
+    /// `Node#1234.on_state_changed`.
+    identity: String,
+    /// The id of the instance, which is used to check whether it's alive.
+    instance_id: i64,
+    /// The instance, if it's a Reference that the subscriber keeps alive.
+    keep_alive: Option<Ref<Reference, Shared>>,
+}
+
+impl Subscriber {
+    /// Creates a subscriber that doesn't keep its instance alive.
+    ///
+    /// # Arguments
+    ///
+    /// * `instance` - The instance that contains the function.
+    /// * `function` - The name of the function.
+    pub fn new(instance: Ref<Object, Shared>, function: GodotString) -> Self {
+        let (identity, instance_id) = {
+            let instance = unsafe { instance.assume_safe() };
+
+            (identity(&instance, &function), instance.get_instance_id())
+        };
+
+        Subscriber {
+            callback: func_ref(instance, function),
+            identity,
+            instance_id,
+            keep_alive: None,
+        }
+    }
+
+    /// Creates a subscriber that keeps its instance alive if it's a
+    /// Reference. Nodes and other objects that are freed manually can't be
+    /// kept alive.
+    ///
+    /// # Arguments
+    ///
+    /// * `instance` - The instance that contains the function.
+    /// * `function` - The name of the function.
+    pub fn strong(instance: Ref<Object, Shared>, function: GodotString) -> Self {
+        let keep_alive = unsafe { instance.assume_safe() }
+            .cast::<Reference>()
+            .map(|reference| reference.claim());
+
+        Subscriber {
+            keep_alive,
+            ..Subscriber::new(instance, function)
+        }
+    }
+
+    /// Returns whether the instance with the function hasn't been freed.
+    pub fn is_alive(&self) -> bool {
+        self.keep_alive.is_some()
+            || unsafe { Object::try_from_instance_id(self.instance_id) }.is_some()
+    }
+
+    /// Returns whether the function is on an instance.
+    ///
+    /// # Arguments
+    ///
+    /// * `instance_id` - The instance id of the instance.
+    pub fn belongs_to(&self, instance_id: i64) -> bool {
+        self.instance_id == instance_id
+    }
+
+    /// Returns the description of the subscriber.
+    pub fn identity(&self) -> &str {
+        &self.identity
+    }
+
+    /// Calls the function, returning an error instead if its instance doesn't
+    /// have the function.
+    ///
+    /// # Arguments
+    ///
+    /// * `args` - The arguments to pass to the function.
+    pub fn call(&self, args: &[Variant]) -> Result<Variant, String> {
+        if !self.callback.is_valid() {
+            return Err("The instance doesn't have the function".to_string());
+        }
+
+        Ok(self.callback.call_func(args))
+    }
+}
+
+/// Returns how a function on an instance is shown in warnings and signals,
+/// like `Node2D#1234.on_state_changed`.
+///
+/// # Arguments
+///
+/// * `instance` - The instance that contains the function.
+/// * `function` - The name of the function.
+pub fn identity(instance: &Object, function: &GodotString) -> String {
+    format!(
+        "{}#{}.{}",
+        instance.get_class(),
+        instance.get_instance_id(),
+        function
+    )
+}
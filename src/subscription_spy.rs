@@ -0,0 +1,66 @@
+use std::cell::RefCell;
+
+use gdnative::prelude::{methods, NativeClass, Reference, Unique, Variant, VariantArray};
+
+/// A subscriber meant to be used in tests that records every time it is run
+/// and the state it was run with, so tests can check that a store notifies
+/// its subscribers without writing a callback for it. Spies are usually
+/// created with `GodotReduxFactory.spy_on` which also subscribes them.
+#[inherit(Reference)]
+#[derive(NativeClass)]
+pub struct SubscriptionSpy {
+    /// The state the spy was run with each time it was run.
+    states: RefCell<Vec<Variant>>,
+}
+
+#[methods]
+impl SubscriptionSpy {
+    /// Initializes the struct with default values.
+    fn new(_owner: &Reference) -> Self {
+        SubscriptionSpy {
+            states: RefCell::new(vec![]),
+        }
+    }
+
+    /// The subscriber function that records the call. This is the function
+    /// passed to `subscribe`.
+    ///
+    /// # Arguments
+    ///
+    /// * `state` - The state the subscriber was run with.
+    #[export]
+    fn record_call(&self, _owner: &Reference, state: Variant) {
+        self.states.borrow_mut().push(state);
+    }
+
+    /// Returns the number of times the spy was run.
+    #[export]
+    fn get_call_count(&self, _owner: &Reference) -> i64 {
+        self.states.borrow().len() as i64
+    }
+
+    /// Returns whether the spy was run at least once.
+    #[export]
+    fn was_called(&self, _owner: &Reference) -> bool {
+        !self.states.borrow().is_empty()
+    }
+
+    /// Returns the state the spy was run with each time it was run, in order.
+    #[export]
+    fn get_calls(&self, _owner: &Reference) -> VariantArray<Unique> {
+        self.states.borrow().iter().collect()
+    }
+
+    /// Returns the state the spy was last run with or `null` if it was never
+    /// run.
+    #[export]
+    fn get_last_state(&self, _owner: &Reference) -> Variant {
+        self.states.borrow().last().cloned().unwrap_or_default()
+    }
+
+    /// Forgets every recorded call.
+    #[export]
+    fn reset(&self, _owner: &Reference) {
+        self.states.borrow_mut().clear();
+    }
+}
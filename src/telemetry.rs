@@ -0,0 +1,255 @@
+use std::collections::BTreeMap;
+
+use gdnative::prelude::{Dictionary, Unique};
+
+/// How many seconds the dispatches are counted over before they're reported
+/// with the `stats_tick` signal.
+const WINDOW: f64 = 1.0;
+
+/// The dispatches counted since they were last reported with `stats_tick`.
+struct Window {
+    /// The time the dispatches started being counted at, in seconds.
+    start: f64,
+    /// The number of actions that were dispatched, including the ones
+    /// dispatched by other dispatches.
+    dispatches: u64,
+    /// The number of outermost dispatches that were timed.
+    timed: u64,
+    /// How long the outermost dispatches took altogether, in microseconds.
+    total_usec: i64,
+}
+
+impl Window {
+    /// Starts counting dispatches.
+    ///
+    /// # Arguments
+    ///
+    /// * `now` - The current time, in seconds.
+    fn new(now: f64) -> Self {
+        Window {
+            start: now,
+            dispatches: 0,
+            timed: 0,
+            total_usec: 0,
+        }
+    }
+}
+
+/// What was measured for a slice of the state during the dispatches that
+/// were profiled.
+#[derive(Default)]
+struct SliceStats {
+    /// The number of times the reducer of the slice was run.
+    reductions: u64,
+    /// How long the reducer of the slice took altogether, in microseconds.
+    reducer_usec: i64,
+    /// The number of profiled dispatches that changed the slice.
+    changes: u64,
+}
+
+/// The dispatches counted since telemetry was enabled or `reset_stats` was
+/// called.
+#[derive(Default)]
+struct Totals {
+    /// The number of actions that were dispatched, including the ones
+    /// dispatched by other dispatches.
+    dispatches: u64,
+    /// The number of outermost dispatches that were profiled.
+    profiled: u64,
+    /// How long the profiled dispatches took altogether, in microseconds.
+    total_usec: i64,
+    /// How long the slowest profiled dispatch took, in microseconds.
+    max_usec: i64,
+    /// What was measured for each slice, by key.
+    slices: BTreeMap<String, SliceStats>,
+}
+
+/// The dispatches counted for live dashboards and systems that adapt to how
+/// busy the store is. Every dispatch is counted, but only one in every
+/// few outermost dispatches is profiled, timing the dispatch and the
+/// reducers of the slices, so the overhead can be kept down.
+pub struct Telemetry {
+    /// The dispatches counted since they were last reported.
+    window: Window,
+    /// The dispatches counted since the stats were last reset.
+    totals: Totals,
+    /// One in how many outermost dispatches is profiled.
+    sample_rate: u64,
+    /// The number of outermost dispatches since the last one that was
+    /// profiled.
+    skipped: u64,
+    /// Whether the dispatch in progress is being profiled.
+    profiling: bool,
+}
+
+impl Telemetry {
+    /// Starts counting dispatches.
+    ///
+    /// # Arguments
+    ///
+    /// * `now` - The current time, in seconds.
+    /// * `sample_rate` - One in how many outermost dispatches is profiled.
+    pub fn new(now: f64, sample_rate: u64) -> Self {
+        Telemetry {
+            window: Window::new(now),
+            totals: Totals::default(),
+            sample_rate: sample_rate.max(1),
+            skipped: 0,
+            profiling: false,
+        }
+    }
+
+    /// Sets one in how many outermost dispatches is profiled.
+    ///
+    /// # Arguments
+    ///
+    /// * `sample_rate` - The rate, where 1 profiles every dispatch.
+    pub fn set_sample_rate(&mut self, sample_rate: u64) {
+        self.sample_rate = sample_rate.max(1);
+        self.skipped = 0;
+    }
+
+    /// Counts an action that was dispatched.
+    pub fn count_dispatch(&mut self) {
+        self.window.dispatches += 1;
+        self.totals.dispatches += 1;
+    }
+
+    /// Starts an outermost dispatch and returns whether it's profiled, which
+    /// is the case for the first one and then one in every sample rate.
+    pub fn start_dispatch(&mut self) -> bool {
+        self.profiling = self.skipped == 0;
+        self.skipped = (self.skipped + 1) % self.sample_rate;
+
+        self.profiling
+    }
+
+    /// Returns whether the dispatch in progress is being profiled.
+    pub fn is_profiling(&self) -> bool {
+        self.profiling
+    }
+
+    /// Adds how long a profiled outermost dispatch took, along with
+    /// everything it dispatched, and finishes profiling it.
+    ///
+    /// # Arguments
+    ///
+    /// * `usec` - How long the dispatch took, in microseconds.
+    pub fn time_dispatch(&mut self, usec: i64) {
+        self.window.timed += 1;
+        self.window.total_usec += usec;
+
+        self.totals.profiled += 1;
+        self.totals.total_usec += usec;
+        self.totals.max_usec = self.totals.max_usec.max(usec);
+
+        self.profiling = false;
+    }
+
+    /// Adds how long the reducer of a slice took during a profiled dispatch.
+    ///
+    /// # Arguments
+    ///
+    /// * `slice` - The key of the slice.
+    /// * `usec` - How long the reducer took, in microseconds.
+    pub fn time_slice(&mut self, slice: &str, usec: i64) {
+        let stats = self.totals.slices.entry(slice.to_string()).or_default();
+        stats.reductions += 1;
+        stats.reducer_usec += usec;
+    }
+
+    /// Counts the slices a profiled dispatch changed.
+    ///
+    /// # Arguments
+    ///
+    /// * `slices` - The keys of the slices.
+    pub fn count_changes(&mut self, slices: Vec<String>) {
+        for slice in slices {
+            self.totals.slices.entry(slice).or_default().changes += 1;
+        }
+    }
+
+    /// Returns the number of dispatches and how long a profiled outermost
+    /// dispatch took on average, in milliseconds, once a second has passed
+    /// since they were last reported, and starts counting them again.
+    /// Returns `None` if a second hasn't passed yet.
+    ///
+    /// # Arguments
+    ///
+    /// * `now` - The current time, in seconds.
+    pub fn report(&mut self, now: f64) -> Option<(u64, f64)> {
+        if now - self.window.start < WINDOW {
+            return None;
+        }
+
+        let report = (
+            self.window.dispatches,
+            average_msec(self.window.total_usec, self.window.timed),
+        );
+        self.window = Window::new(now);
+
+        Some(report)
+    }
+
+    /// Forgets every dispatch that was counted and starts counting again,
+    /// keeping the sample rate.
+    ///
+    /// # Arguments
+    ///
+    /// * `now` - The current time, in seconds.
+    pub fn reset(&mut self, now: f64) {
+        *self = Telemetry::new(now, self.sample_rate);
+    }
+
+    /// Returns the dispatches counted since the stats were last reset, with
+    /// the number of dispatches under `dispatches`, the number that were
+    /// profiled under `profiled`, the sample rate under `sample_rate`, how
+    /// long a profiled dispatch took on average and at most in milliseconds
+    /// under `avg_dispatch_ms` and `max_dispatch_ms`, and a breakdown of each
+    /// slice under `slices`, by key. Each slice has the number of times its
+    /// reducer was run under `reductions`, how long its reducer took
+    /// altogether and on average in milliseconds under `reducer_ms` and
+    /// `avg_reducer_ms`, and the number of profiled dispatches that changed
+    /// it under `changes`.
+    pub fn to_dictionary(&self) -> Dictionary<Unique> {
+        let slices = Dictionary::new();
+        for (key, stats) in &self.totals.slices {
+            let slice = Dictionary::new();
+            slice.insert("reductions", stats.reductions as i64);
+            slice.insert("reducer_ms", stats.reducer_usec as f64 / 1000.0);
+            slice.insert(
+                "avg_reducer_ms",
+                average_msec(stats.reducer_usec, stats.reductions),
+            );
+            slice.insert("changes", stats.changes as i64);
+            slices.insert(key.as_str(), slice.into_shared());
+        }
+
+        let stats = Dictionary::new();
+        stats.insert("dispatches", self.totals.dispatches as i64);
+        stats.insert("profiled", self.totals.profiled as i64);
+        stats.insert("sample_rate", self.sample_rate as i64);
+        stats.insert(
+            "avg_dispatch_ms",
+            average_msec(self.totals.total_usec, self.totals.profiled),
+        );
+        stats.insert("max_dispatch_ms", self.totals.max_usec as f64 / 1000.0);
+        stats.insert("slices", slices.into_shared());
+
+        stats
+    }
+}
+
+/// Returns how long something took on average, in milliseconds.
+///
+/// # Arguments
+///
+/// * `total_usec` - How long it took altogether, in microseconds.
+/// * `count` - The number of times it happened.
+fn average_msec(total_usec: i64, count: u64) -> f64 {
+    if count == 0 {
+        0.0
+    } else {
+        total_usec as f64 / count as f64 / 1000.0
+    }
+}
@@ -0,0 +1,166 @@
+use gdnative::api::FuncRef;
+use gdnative::prelude::{Dictionary, Unique, Variant, VariantArray};
+
+use crate::action::action_type;
+use crate::{diff, path, snapshot};
+
+/// Keeps a record of every action dispatched while the test mode is enabled
+/// and which of those actions have been matched by an expectation.
+#[derive(Default)]
+pub struct DispatchLog {
+    /// The dispatched actions and whether each one has been matched.
+    entries: Vec<(Variant, bool)>,
+}
+
+impl DispatchLog {
+    /// Records a dispatched action.
+    ///
+    /// # Arguments
+    ///
+    /// * `action` - The action that was dispatched.
+    pub fn record(&mut self, action: &Variant) {
+        self.entries.push((action.clone(), false));
+    }
+
+    /// Returns every dispatched action, in the order they were dispatched.
+    pub fn actions(&self) -> VariantArray<Unique> {
+        self.entries.iter().map(|(action, _)| action).collect()
+    }
+
+    /// Returns the dispatched actions that haven't been matched yet along with
+    /// their position in the log.
+    pub fn unmatched(&self) -> Vec<(usize, Variant)> {
+        self.entries
+            .iter()
+            .enumerate()
+            .filter(|(_, (_, matched))| !matched)
+            .map(|(index, (action, _))| (index, action.clone()))
+            .collect()
+    }
+
+    /// Marks the action at the position in the log as matched.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - The position of the action in the log.
+    pub fn mark_matched(&mut self, index: usize) {
+        if let Some((_, matched)) = self.entries.get_mut(index) {
+            *matched = true;
+        }
+    }
+}
+
+/// Returns whether an action has the expected type and a payload accepted by
+/// the matcher. The matcher can be:
+///
+/// * `null` - Any payload is accepted.
+/// * A Dictionary - The action has to be a Dictionary containing each of the
+///   matcher's keys with the same value.
+/// * A FuncRef - The FuncRef is called with the action and the payload is
+///   accepted if it returns `true`.
+///
+/// # Arguments
+///
+/// * `action` - The action to check.
+/// * `expected_type` - The type the action should have.
+/// * `payload_matcher` - The matcher the payload of the action should pass.
+pub fn matches(action: &Variant, expected_type: &Variant, payload_matcher: &Variant) -> bool {
+    if action_type(action) != *expected_type {
+        return false;
+    }
+
+    if payload_matcher.is_nil() {
+        return true;
+    }
+
+    if let Some(matcher) = payload_matcher.try_to_object::<FuncRef>() {
+        let matcher = unsafe { matcher.assume_safe() };
+        return matcher.call_func(&[action.clone()]).to_bool();
+    }
+
+    match (
+        payload_matcher.try_to_dictionary(),
+        action.try_to_dictionary(),
+    ) {
+        (Some(expected), Some(action)) => expected
+            .iter()
+            .all(|(key, value)| action.contains(&key) && action.get(&key) == value),
+        (Some(expected), None) => expected.is_empty(),
+        _ => false,
+    }
+}
+
+/// Returns a description of every value that is different between the
+/// expected state and the actual state, skipping any value at or inside one
+/// of the ignored paths.
+///
+/// # Arguments
+///
+/// * `expected` - The state that was expected.
+/// * `actual` - The actual state.
+/// * `ignore_paths` - The paths that don't have to match.
+pub fn state_mismatches(
+    expected: &Dictionary,
+    actual: &Dictionary,
+    ignore_paths: &[String],
+) -> Vec<String> {
+    diff::changed_paths(expected, actual)
+        .into_iter()
+        .filter(|changed| {
+            !ignore_paths
+                .iter()
+                .any(|ignored| path::is_within(changed, ignored))
+        })
+        .map(|changed| {
+            describe_mismatch(
+                &changed,
+                path::get(expected, &changed).as_ref(),
+                path::get(actual, &changed).as_ref(),
+            )
+        })
+        .collect()
+}
+
+/// Returns a description of a value in the state that isn't what it was
+/// expected to be, made up of its path and both values.
+///
+/// # Arguments
+///
+/// * `path` - The path to the value.
+/// * `expected` - The value that was expected, or `None` if it shouldn't exist.
+/// * `actual` - The actual value, or `None` if it doesn't exist.
+pub fn describe_mismatch(
+    path: &str,
+    expected: Option<&Variant>,
+    actual: Option<&Variant>,
+) -> String {
+    match (expected, actual) {
+        (Some(expected), Some(actual)) => format!(
+            "{}: expected {} but was {}",
+            path,
+            inline_text(expected),
+            inline_text(actual)
+        ),
+        (Some(expected), None) => format!(
+            "{}: expected {} but it doesn't exist",
+            path,
+            inline_text(expected)
+        ),
+        (None, Some(actual)) => format!(
+            "{}: expected it not to exist but was {}",
+            path,
+            inline_text(actual)
+        ),
+        (None, None) => format!("{}: doesn't exist", path),
+    }
+}
+
+/// Returns a value as text for a failure message, with Strings in quotes so
+/// they can be told apart from numbers.
+///
+/// # Arguments
+///
+/// * `value` - The value to write.
+fn inline_text(value: &Variant) -> String {
+    snapshot::to_stable_text(value).trim_end().to_string()
+}
@@ -0,0 +1,227 @@
+use std::sync::{Mutex, PoisonError};
+
+use gdnative::nativescript::user_data::ArcData;
+use gdnative::prelude::{methods, NativeClass, Object, Reference, Variant};
+
+use crate::background_save;
+
+/// Lets threads other than the main thread dispatch actions, like a Thread
+/// that finds a path and dispatches the result. The store can only be called
+/// from the thread it was created on, so the thread is handed this instead,
+/// which the store returns from `get_threadsafe_dispatcher`. The actions
+/// passed to `dispatch_threadsafe` are queued behind a lock and handed to the
+/// store on the main thread, which dispatches them with the actions queued
+/// with `dispatch_deferred`, in the order they were queued.
+#[inherit(Reference)]
+#[user_data(ArcData<ThreadSafeDispatcher>)]
+#[derive(NativeClass)]
+#[no_constructor]
+pub struct ThreadSafeDispatcher {
+    /// The instance id of the store the actions are dispatched to.
+    store_id: i64,
+    /// The actions that were queued and not yet handed to the store.
+    actions: Queue<Variant>,
+}
+
+#[methods]
+impl ThreadSafeDispatcher {
+    /// Creates the dispatcher of a store.
+    ///
+    /// # Arguments
+    ///
+    /// * `store_id` - The instance id of the store.
+    pub fn create(store_id: i64) -> Self {
+        ThreadSafeDispatcher {
+            store_id,
+            actions: Queue::default(),
+        }
+    }
+
+    /// Queues an action to be dispatched on the main thread, which can be
+    /// called from any thread. The action is copied along with the
+    /// Dictionaries and Arrays in it, so the thread can keep using its own.
+    ///
+    /// # Arguments
+    ///
+    /// * `action` - The action to dispatch.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// func _find_path(dispatcher):
+    ///     var path = $Navigation.get_simple_path(from, to)
+    ///     dispatcher.dispatch_threadsafe({ "type": Action.PATH_FOUND, "path": path })
+    /// ```
+    #[export]
+    fn dispatch_threadsafe(&self, owner: &Reference, action: Variant) {
+        let action = background_save::copy_value(&action);
+
+        // Deferred calls are queued behind a lock of the engine's own and run
+        // on the main thread, which is where the store has to be called from.
+        if self.actions.push(action) {
+            unsafe { owner.call_deferred("flush", &[]) };
+        }
+    }
+
+    /// Returns the number of actions that were queued and not yet handed to
+    /// the store.
+    #[export]
+    fn get_pending_count(&self, _owner: &Reference) -> i64 {
+        self.actions.count() as i64
+    }
+
+    /// Hands the queued actions to the store, which is called on the main
+    /// thread once an action is queued.
+    #[export]
+    fn flush(&self, _owner: &Reference) {
+        match unsafe { Object::try_from_instance_id(self.store_id) } {
+            Some(store) => unsafe {
+                store.call("flush_threadsafe_dispatches", &[]);
+            },
+            // Nothing can dispatch the actions once the store was freed.
+            None => {
+                self.take();
+            }
+        }
+    }
+
+    /// Returns the queued actions, in the order they were queued, and empties
+    /// the queue.
+    pub fn take(&self) -> Vec<Variant> {
+        self.actions.take()
+    }
+}
+
+/// Values queued by any number of threads behind a lock and taken out all at
+/// once, in the order they were queued.
+pub struct Queue<T> {
+    /// The values, in the order they were queued.
+    values: Mutex<Vec<T>>,
+}
+
+impl<T> Default for Queue<T> {
+    fn default() -> Self {
+        Queue {
+            values: Mutex::new(vec![]),
+        }
+    }
+}
+
+impl<T> Queue<T> {
+    /// Queues a value and returns whether the queue was empty, in which case
+    /// nothing is going to take the values out yet. A thread that panicked
+    /// while holding the lock can't leave the values half-changed, so the
+    /// queue keeps working after it.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The value.
+    pub fn push(&self, value: T) -> bool {
+        let mut values = self.values.lock().unwrap_or_else(PoisonError::into_inner);
+        values.push(value);
+
+        values.len() == 1
+    }
+
+    /// Returns the number of values in the queue.
+    pub fn count(&self) -> usize {
+        self.values
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .len()
+    }
+
+    /// Returns the values in the queue, in the order they were queued, and
+    /// empties it.
+    pub fn take(&self) -> Vec<T> {
+        std::mem::take(&mut *self.values.lock().unwrap_or_else(PoisonError::into_inner))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::thread;
+
+    use super::Queue;
+
+    /// The number of threads queueing values at the same time.
+    const PRODUCERS: usize = 8;
+
+    /// The number of values each thread queues.
+    const VALUES_PER_PRODUCER: usize = 2_000;
+
+    /// Checks that the values taken out of a queue have every value queued by
+    /// every producer exactly once, in the order each producer queued them.
+    fn assert_each_value_once_in_order(taken: &[(usize, usize)]) {
+        assert_eq!(taken.len(), PRODUCERS * VALUES_PER_PRODUCER);
+
+        let mut next = [0; PRODUCERS];
+        for (producer, value) in taken {
+            assert_eq!(
+                *value, next[*producer],
+                "producer {} was taken out of order",
+                producer
+            );
+            next[*producer] += 1;
+        }
+        assert!(next.iter().all(|count| *count == VALUES_PER_PRODUCER));
+    }
+
+    /// Starts the producers, which each queue their values numbered from 0.
+    fn start_producers(queue: &Arc<Queue<(usize, usize)>>) -> Vec<thread::JoinHandle<()>> {
+        (0..PRODUCERS)
+            .map(|producer| {
+                let queue = Arc::clone(queue);
+                thread::spawn(move || {
+                    for value in 0..VALUES_PER_PRODUCER {
+                        queue.push((producer, value));
+                    }
+                })
+            })
+            .collect()
+    }
+
+    #[test]
+    fn take_returns_every_value_of_concurrent_producers_once() {
+        let queue = Arc::new(Queue::default());
+        for producer in start_producers(&queue) {
+            producer.join().unwrap();
+        }
+
+        assert_eq!(queue.count(), PRODUCERS * VALUES_PER_PRODUCER);
+        assert_each_value_once_in_order(&queue.take());
+        assert_eq!(queue.count(), 0);
+        assert!(queue.take().is_empty());
+    }
+
+    #[test]
+    fn take_while_producers_are_queueing_loses_nothing() {
+        let queue = Arc::new(Queue::default());
+        let producers = start_producers(&queue);
+
+        // The values are taken out while the producers are still queueing,
+        // like the main thread flushing between frames.
+        let mut taken = vec![];
+        while taken.len() < PRODUCERS * VALUES_PER_PRODUCER {
+            taken.extend(queue.take());
+            thread::yield_now();
+        }
+        for producer in producers {
+            producer.join().unwrap();
+        }
+
+        assert_each_value_once_in_order(&taken);
+        assert!(queue.take().is_empty());
+    }
+
+    #[test]
+    fn push_reports_when_a_flush_has_to_be_scheduled() {
+        let queue = Queue::default();
+
+        assert!(queue.push(1));
+        assert!(!queue.push(2));
+        assert_eq!(queue.take(), vec![1, 2]);
+        assert!(queue.push(3));
+    }
+}
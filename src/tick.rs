@@ -0,0 +1,94 @@
+use gdnative::prelude::{Dictionary, Unique};
+
+use crate::action;
+
+/// The type of the action dispatched at the fixed rate set with `enable_tick`.
+pub const TICK: &str = "TICK";
+
+/// The key of the seconds each tick simulates in a `TICK` action.
+const DELTA_KEY: &str = "delta";
+
+/// The key of the number of the tick in a `TICK` action, starting from 1.
+const TICK_KEY: &str = "tick";
+
+/// The most ticks dispatched in a single frame, so that a long frame doesn't
+/// make the simulation dispatch more and more ticks to catch up.
+const MAX_TICKS_PER_FRAME: u32 = 8;
+
+/// Dispatches `TICK` actions at a fixed rate no matter how fast frames are
+/// rendered, by adding up the time that passes between frames and using it up
+/// one fixed step at a time.
+pub struct Tick {
+    /// The seconds each tick simulates.
+    fixed_delta: f64,
+    /// The time that has passed and hasn't been used up by a tick yet.
+    accumulator: f64,
+    /// The time of the last frame, or `None` before the first frame.
+    last_time: Option<f64>,
+    /// The number of ticks dispatched so far.
+    count: u64,
+}
+
+impl Tick {
+    /// Creates a tick that hasn't seen a frame yet.
+    ///
+    /// # Arguments
+    ///
+    /// * `fixed_delta` - The seconds each tick simulates.
+    pub fn new(fixed_delta: f64) -> Self {
+        Tick {
+            fixed_delta,
+            accumulator: 0.0,
+            last_time: None,
+            count: 0,
+        }
+    }
+
+    /// Returns the `TICK` actions that are due by a frame. The time that
+    /// passes while the tick is frozen is skipped.
+    ///
+    /// # Arguments
+    ///
+    /// * `now` - The time of the frame.
+    /// * `frozen` - Whether the ticks are frozen, for example while the tree
+    /// is paused.
+    pub fn due(&mut self, now: f64, frozen: bool) -> Vec<Dictionary<Unique>> {
+        let elapsed = now - self.last_time.unwrap_or(now);
+        self.last_time = Some(now);
+        if frozen || elapsed <= 0.0 {
+            return vec![];
+        }
+
+        self.accumulator += elapsed;
+
+        let mut due = vec![];
+        while self.accumulator >= self.fixed_delta {
+            self.accumulator -= self.fixed_delta;
+            self.count += 1;
+            due.push(tick(self.fixed_delta, self.count));
+
+            // Whatever is left after the most ticks a frame can have is
+            // dropped so the simulation slows down instead of falling behind.
+            if due.len() as u32 == MAX_TICKS_PER_FRAME {
+                self.accumulator = 0.0;
+                break;
+            }
+        }
+
+        due
+    }
+}
+
+/// Returns a `TICK` action.
+///
+/// # Arguments
+///
+/// * `delta` - The seconds the tick simulates.
+/// * `count` - The number of the tick.
+fn tick(delta: f64, count: u64) -> Dictionary<Unique> {
+    let action = action::named(TICK);
+    action.insert(DELTA_KEY, delta);
+    action.insert(TICK_KEY, count);
+
+    action
+}
@@ -0,0 +1,297 @@
+use std::collections::VecDeque;
+
+use gdnative::prelude::{Dictionary, Variant, VariantArray};
+
+use crate::compression::Compressed;
+use crate::{diff, event_log};
+
+/// The type of the action an `undo` is recorded as in the time travel history.
+pub const UNDO: &str = "__undo";
+
+/// The type of the action a `redo` is recorded as in the time travel history.
+pub const REDO: &str = "__redo";
+
+/// A change to the state that can be undone and redone.
+struct Step {
+    /// The action that made the change.
+    action: Variant,
+    /// What has to change to go back to the state before the action.
+    undo: Compressed,
+    /// What has to change to go forward to the state after the action again.
+    redo: Compressed,
+    /// The number of bytes the action and the compressed changes take up.
+    size: usize,
+}
+
+/// The changes to the state that can be undone and redone, like the moves in
+/// a puzzle or the edits in a level editor. Each step is kept as the keys
+/// the action changed, so undoing it only puts those keys back and leaves
+/// the changes made by excluded actions, like moving the camera, as they are.
+/// The changes are kept compressed and only rebuilt when a step is undone or
+/// redone.
+pub struct UndoStack {
+    /// The most steps that are kept.
+    max_depth: usize,
+    /// The types of the actions that aren't kept, as strings.
+    excluded: Vec<String>,
+    /// The steps that can be undone and redone.
+    steps: Steps<Step>,
+}
+
+impl UndoStack {
+    /// Creates an empty stack.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_depth` - The most steps that are kept.
+    /// * `excluded_types` - The types of the actions that aren't kept.
+    pub fn new(max_depth: usize, excluded_types: &VariantArray) -> Self {
+        UndoStack {
+            max_depth,
+            excluded: excluded_types
+                .iter()
+                .map(|action_type| action_type.to_string())
+                .collect(),
+            steps: Steps::default(),
+        }
+    }
+
+    /// Returns whether actions of a type are kept.
+    ///
+    /// # Arguments
+    ///
+    /// * `action_type` - The type of the action.
+    pub fn keeps(&self, action_type: &Variant) -> bool {
+        !self.excluded.contains(&action_type.to_string())
+    }
+
+    /// Records the change made by an action, unless it didn't change anything,
+    /// and forgets the steps that were undone since they can't be redone on
+    /// top of a new change. The oldest step is dropped once there are more
+    /// than the max depth.
+    ///
+    /// # Arguments
+    ///
+    /// * `action` - The action.
+    /// * `old_state` - The state before the action.
+    /// * `new_state` - The state after the action.
+    pub fn record(&mut self, action: &Variant, old_state: &Dictionary, new_state: &Dictionary) {
+        let redo = diff::delta(old_state, new_state);
+        if redo.is_empty() {
+            return;
+        }
+        let undo = diff::compress(&diff::delta(new_state, old_state));
+        let redo = diff::compress(&redo);
+        let size = event_log::encoded_size(action) + undo.size() + redo.size();
+
+        self.steps.push(
+            Step {
+                action: action.clone(),
+                undo,
+                redo,
+                size,
+            },
+            self.max_depth,
+        );
+    }
+
+    /// Undoes the latest step and returns the state before it along with the
+    /// action that made it, or `None` if there's nothing to undo.
+    ///
+    /// # Arguments
+    ///
+    /// * `state` - The current state.
+    pub fn undo(&mut self, state: &Dictionary) -> Option<(Dictionary, Variant)> {
+        let step = self.steps.undo()?;
+
+        Some((
+            diff::apply(state, &diff::decompress(&step.undo)).into_shared(),
+            step.action.clone(),
+        ))
+    }
+
+    /// Redoes the latest step that was undone and returns the state after it
+    /// along with the action that made it, or `None` if there's nothing to
+    /// redo.
+    ///
+    /// # Arguments
+    ///
+    /// * `state` - The current state.
+    pub fn redo(&mut self, state: &Dictionary) -> Option<(Dictionary, Variant)> {
+        let step = self.steps.redo()?;
+
+        Some((
+            diff::apply(state, &diff::decompress(&step.redo)).into_shared(),
+            step.action.clone(),
+        ))
+    }
+
+    /// Returns the number of steps that can be undone.
+    pub fn undo_count(&self) -> usize {
+        self.steps.undo.len()
+    }
+
+    /// Returns the number of steps that can be redone.
+    pub fn redo_count(&self) -> usize {
+        self.steps.redo.len()
+    }
+
+    /// Forgets every step, so there's nothing to undo or redo until the next
+    /// change is recorded.
+    pub fn clear(&mut self) {
+        self.steps.clear();
+    }
+
+    /// Returns the number of bytes the steps take up, which is roughly how
+    /// much memory they use.
+    pub fn size(&self) -> usize {
+        self.steps
+            .undo
+            .iter()
+            .chain(&self.steps.redo)
+            .map(|step| step.size)
+            .sum()
+    }
+
+    /// Drops the oldest steps that can be undone until the steps take up at
+    /// most a number of bytes, and returns how many were dropped. The steps
+    /// that can be redone are kept.
+    ///
+    /// # Arguments
+    ///
+    /// * `limit` - The number of bytes the steps can take up.
+    pub fn trim_to(&mut self, limit: usize) -> usize {
+        let mut size = self.size();
+        let mut count = 0;
+        while size > limit {
+            match self.steps.undo.pop_front() {
+                Some(step) => size -= step.size,
+                None => break,
+            }
+            count += 1;
+        }
+
+        count
+    }
+}
+
+/// The steps that can be undone, with the latest one last, and the steps
+/// that were undone and can be redone, with the latest one that was undone
+/// last.
+struct Steps<T> {
+    /// The steps that can be undone.
+    undo: VecDeque<T>,
+    /// The steps that can be redone.
+    redo: Vec<T>,
+}
+
+impl<T> Default for Steps<T> {
+    fn default() -> Self {
+        Steps {
+            undo: VecDeque::new(),
+            redo: vec![],
+        }
+    }
+}
+
+impl<T> Steps<T> {
+    /// Adds a step that can be undone and forgets the ones that can be redone,
+    /// dropping the oldest step once there are more than the max depth.
+    ///
+    /// # Arguments
+    ///
+    /// * `step` - The step.
+    /// * `max_depth` - The most steps that are kept.
+    fn push(&mut self, step: T, max_depth: usize) {
+        self.redo.clear();
+        self.undo.push_back(step);
+        if self.undo.len() > max_depth {
+            self.undo.pop_front();
+        }
+    }
+
+    /// Moves the latest step that can be undone over to the ones that can be
+    /// redone and returns it, or `None` if there's nothing to undo.
+    fn undo(&mut self) -> Option<&T> {
+        let step = self.undo.pop_back()?;
+        self.redo.push(step);
+
+        self.redo.last()
+    }
+
+    /// Moves the latest step that was undone back to the ones that can be
+    /// undone and returns it, or `None` if there's nothing to redo.
+    fn redo(&mut self) -> Option<&T> {
+        let step = self.redo.pop()?;
+        self.undo.push_back(step);
+
+        self.undo.back()
+    }
+
+    /// Forgets every step.
+    fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Steps;
+
+    /// Creates steps with 1, 2, and 3 recorded and 3 undone.
+    fn steps() -> Steps<i32> {
+        let mut steps = Steps::default();
+        for step in 1..=3 {
+            steps.push(step, 10);
+        }
+        steps.undo();
+
+        steps
+    }
+
+    #[test]
+    fn undo_and_redo_move_the_latest_step() {
+        let mut steps = steps();
+
+        assert_eq!(steps.undo(), Some(&2));
+        assert_eq!(steps.redo(), Some(&2));
+        assert_eq!(steps.redo(), Some(&3));
+        assert_eq!(steps.redo(), None);
+    }
+
+    #[test]
+    fn push_forgets_the_steps_that_were_undone() {
+        let mut steps = steps();
+        steps.push(4, 10);
+
+        assert_eq!(steps.redo(), None);
+        assert_eq!(steps.undo(), Some(&4));
+        assert_eq!(steps.undo(), Some(&2));
+    }
+
+    #[test]
+    fn push_drops_the_oldest_step_past_the_max_depth() {
+        let mut steps = steps();
+        steps.push(4, 2);
+
+        assert_eq!(steps.undo(), Some(&4));
+        assert_eq!(steps.undo(), Some(&2));
+        assert_eq!(steps.undo(), None);
+    }
+
+    #[test]
+    fn undo_after_clear_changes_nothing() {
+        let mut steps = steps();
+        steps.clear();
+
+        assert_eq!(steps.undo(), None);
+        assert_eq!(steps.redo(), None);
+        assert!(steps.undo.is_empty());
+        assert!(steps.redo.is_empty());
+
+        steps.push(4, 10);
+        assert_eq!(steps.undo(), Some(&4));
+        assert_eq!(steps.undo(), None);
+    }
+}
@@ -0,0 +1,288 @@
+use gdnative::prelude::{Dictionary, Unique, Variant, VariantType};
+
+use crate::equality::Equality;
+use crate::path;
+
+/// The key of the source path in a field definition.
+const PATH_KEY: &str = "path";
+
+/// The key of the format in a field definition.
+const FORMAT_KEY: &str = "format";
+
+/// The number of decimals `%f` shows when it isn't given a precision, which
+/// is the same as in GDScript.
+const DEFAULT_PRECISION: usize = 6;
+
+/// A placeholder in a format, like `%05.1f`.
+struct Placeholder {
+    /// How the value is shown, which is `s`, `d`, or `f`.
+    conversion: char,
+    /// Whether numbers are padded with zeros instead of spaces.
+    zero_pad: bool,
+    /// The minimum number of characters to show.
+    width: usize,
+    /// The number of decimals `%f` shows.
+    precision: Option<usize>,
+}
+
+/// A piece of a format.
+enum Piece {
+    /// Text that is shown as it is.
+    Text(String),
+    /// A placeholder that is replaced by the value.
+    Value(Placeholder),
+}
+
+/// A field of a view model.
+struct Field {
+    /// The key of the field in the view model.
+    key: String,
+    /// The path of the state the field is taken from.
+    path: String,
+    /// The format the value is shown with, if it's turned into a String.
+    format: Option<Vec<Piece>>,
+}
+
+/// A view model registered with `register_view_model`, which is a flat
+/// Dictionary made from values in the state that UI code can bind to.
+pub struct ViewModel {
+    /// The fields of the view model.
+    fields: Vec<Field>,
+    /// The values of the source paths the fields were last computed from, if
+    /// they were computed yet.
+    sources: Option<Vec<Variant>>,
+    /// The value of each field.
+    values: Vec<Variant>,
+}
+
+impl ViewModel {
+    /// Creates a view model from a mapping of field keys to their source. The
+    /// source is either the path of the value in the state or a Dictionary
+    /// with the path under `path` and a format, like `%d gold`, under
+    /// `format`. Formats use the same placeholders as GDScript's `%`
+    /// operator: `%s`, `%d`, and `%f`, with an optional width, zero padding,
+    /// and precision, and `%%` for a percent sign.
+    ///
+    /// # Arguments
+    ///
+    /// * `mapping` - The source of each field, by key.
+    pub fn new(mapping: &Dictionary) -> Result<Self, String> {
+        let fields = mapping
+            .iter()
+            .map(|(key, source)| {
+                let key = key.to_string();
+
+                match source.get_type() {
+                    VariantType::GodotString => Ok(Field {
+                        key,
+                        path: source.to_string(),
+                        format: None,
+                    }),
+                    VariantType::Dictionary => {
+                        let source = source.to_dictionary();
+                        if !source.contains(PATH_KEY) {
+                            return Err(format!("The field {} doesn't have a {}", key, PATH_KEY));
+                        }
+
+                        let format = if source.contains(FORMAT_KEY) {
+                            Some(parse_format(&source.get(FORMAT_KEY).to_string())?)
+                        } else {
+                            None
+                        };
+
+                        Ok(Field {
+                            key,
+                            path: source.get(PATH_KEY).to_string(),
+                            format,
+                        })
+                    }
+                    _ => Err(format!(
+                        "The field {} has to be a path or a Dictionary, got {}",
+                        key,
+                        source.to_string()
+                    )),
+                }
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+
+        Ok(ViewModel {
+            values: vec![Variant::new(); fields.len()],
+            fields,
+            sources: None,
+        })
+    }
+
+    /// Returns the source paths of the fields.
+    pub fn paths(&self) -> impl Iterator<Item = &str> {
+        self.fields.iter().map(|field| field.path.as_str())
+    }
+
+    /// Returns the view model for a state. Only the fields whose source path
+    /// changed since the last time are computed again.
+    ///
+    /// # Arguments
+    ///
+    /// * `state` - The current state.
+    pub fn compute(&mut self, state: &Dictionary) -> Dictionary<Unique> {
+        let sources = self
+            .fields
+            .iter()
+            .map(|field| path::get(state, &field.path).unwrap_or_else(Variant::new))
+            .collect::<Vec<_>>();
+
+        for (index, field) in self.fields.iter().enumerate() {
+            let unchanged = self
+                .sources
+                .as_ref()
+                .is_some_and(|last| Equality::Reference.equals(&last[index], &sources[index]));
+            if unchanged {
+                continue;
+            }
+
+            self.values[index] = match &field.format {
+                Some(format) if !sources[index].is_nil() => {
+                    Variant::from_str(render(format, &sources[index]))
+                }
+                _ => sources[index].clone(),
+            };
+        }
+        self.sources = Some(sources);
+
+        let view_model = Dictionary::new();
+        for (field, value) in self.fields.iter().zip(self.values.iter()) {
+            view_model.insert(&field.key, value);
+        }
+
+        view_model
+    }
+}
+
+/// Parses a format into its pieces.
+///
+/// # Arguments
+///
+/// * `format` - The format, like `%d gold`.
+fn parse_format(format: &str) -> Result<Vec<Piece>, String> {
+    let mut pieces = vec![];
+    let mut text = String::new();
+    let mut chars = format.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            text.push(c);
+            continue;
+        }
+        if chars.peek() == Some(&'%') {
+            chars.next();
+            text.push('%');
+            continue;
+        }
+
+        let zero_pad = chars.peek() == Some(&'0');
+        if zero_pad {
+            chars.next();
+        }
+        let width = take_number(&mut chars).unwrap_or(0);
+        let precision = if chars.peek() == Some(&'.') {
+            chars.next();
+            Some(take_number(&mut chars).unwrap_or(0))
+        } else {
+            None
+        };
+
+        let conversion = match chars.next() {
+            Some(conversion @ 's') | Some(conversion @ 'd') | Some(conversion @ 'f') => conversion,
+            Some(other) => {
+                return Err(format!(
+                    "The format {} has an unknown placeholder %{}",
+                    format, other
+                ))
+            }
+            None => {
+                return Err(format!(
+                    "The format {} ends in the middle of a placeholder",
+                    format
+                ))
+            }
+        };
+
+        if !text.is_empty() {
+            pieces.push(Piece::Text(std::mem::take(&mut text)));
+        }
+        pieces.push(Piece::Value(Placeholder {
+            conversion,
+            zero_pad,
+            width,
+            precision,
+        }));
+    }
+
+    if !text.is_empty() {
+        pieces.push(Piece::Text(text));
+    }
+
+    Ok(pieces)
+}
+
+/// Takes the digits at the start of a format and returns the number they make
+/// up, or `None` if it doesn't start with a digit.
+///
+/// # Arguments
+///
+/// * `chars` - The rest of the format.
+fn take_number(chars: &mut std::iter::Peekable<std::str::Chars>) -> Option<usize> {
+    let mut number = None;
+    while let Some(digit) = chars.peek().and_then(|c| c.to_digit(10)) {
+        chars.next();
+        number = Some(number.unwrap_or(0) * 10 + digit as usize);
+    }
+
+    number
+}
+
+/// Returns a value shown with a format.
+///
+/// # Arguments
+///
+/// * `format` - The pieces of the format.
+/// * `value` - The value.
+fn render(format: &[Piece], value: &Variant) -> String {
+    format
+        .iter()
+        .map(|piece| match piece {
+            Piece::Text(text) => text.clone(),
+            Piece::Value(placeholder) => {
+                let text = match placeholder.conversion {
+                    'd' => value.to_i64().to_string(),
+                    'f' => format!(
+                        "{:.*}",
+                        placeholder.precision.unwrap_or(DEFAULT_PRECISION),
+                        value.to_f64()
+                    ),
+                    _ => value.to_string(),
+                };
+
+                pad(&text, placeholder)
+            }
+        })
+        .collect()
+}
+
+/// Returns the text of a value padded to the width of its placeholder. Only
+/// numbers are padded with zeros, which go after the sign.
+///
+/// # Arguments
+///
+/// * `text` - The text of the value.
+/// * `placeholder` - The placeholder the value replaces.
+fn pad(text: &str, placeholder: &Placeholder) -> String {
+    let padding = placeholder.width.saturating_sub(text.chars().count());
+    if !placeholder.zero_pad || placeholder.conversion == 's' {
+        return format!("{}{}", " ".repeat(padding), text);
+    }
+
+    match text.strip_prefix('-') {
+        Some(digits) => format!("-{}{}", "0".repeat(padding), digits),
+        None => format!("{}{}", "0".repeat(padding), text),
+    }
+}
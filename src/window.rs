@@ -0,0 +1,129 @@
+use std::cmp::Ordering;
+
+use gdnative::prelude::{Unique, Variant, VariantArray, VariantType};
+
+use crate::path;
+
+/// What the items of an array are sorted by, taken from each item once so
+/// the items themselves don't have to be copied to be sorted.
+enum SortKey {
+    /// A `bool`, which sorts before numbers.
+    Bool(bool),
+    /// An `int` or a `float`.
+    Number(f64),
+    /// A String, or any other value as its text.
+    Text(String),
+    /// The item doesn't have the sort key, so it's put last.
+    Missing,
+}
+
+impl SortKey {
+    /// Returns the sort key of an item.
+    ///
+    /// # Arguments
+    ///
+    /// * `item` - The item.
+    /// * `sort_key` - The path to the value in the item to sort by, or an
+    /// empty string to sort by the item itself.
+    fn of(item: &Variant, sort_key: &str) -> Self {
+        let value = if sort_key.is_empty() {
+            Some(item.clone())
+        } else {
+            item.try_to_dictionary()
+                .and_then(|item| path::get(&item, sort_key))
+        };
+
+        match value {
+            None => SortKey::Missing,
+            Some(value) => match value.get_type() {
+                VariantType::Nil => SortKey::Missing,
+                VariantType::Bool => SortKey::Bool(value.to_bool()),
+                VariantType::I64 | VariantType::F64 => SortKey::Number(value.to_f64()),
+                _ => SortKey::Text(value.to_string()),
+            },
+        }
+    }
+
+    /// Returns the rank of the kind of key, so keys of different kinds are
+    /// still sorted the same way every time.
+    fn rank(&self) -> u8 {
+        match self {
+            SortKey::Bool(_) => 0,
+            SortKey::Number(_) => 1,
+            SortKey::Text(_) => 2,
+            SortKey::Missing => 3,
+        }
+    }
+
+    /// Compares two keys, putting missing keys last whether the order is
+    /// descending or not.
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - The other key.
+    /// * `descending` - Whether the largest keys come first.
+    fn compare(&self, other: &SortKey, descending: bool) -> Ordering {
+        let ordering = match (self, other) {
+            (SortKey::Bool(a), SortKey::Bool(b)) => a.cmp(b),
+            (SortKey::Number(a), SortKey::Number(b)) => a.partial_cmp(b).unwrap_or(Ordering::Equal),
+            (SortKey::Text(a), SortKey::Text(b)) => a.cmp(b),
+            (SortKey::Missing, _) | (_, SortKey::Missing) => return self.rank().cmp(&other.rank()),
+            _ => self.rank().cmp(&other.rank()),
+        };
+
+        if descending {
+            ordering.reverse()
+        } else {
+            ordering
+        }
+    }
+}
+
+/// Returns a page of the items of an array sorted by a value in each of
+/// them, like a page of a leaderboard. Only the sort keys are sorted, and
+/// only as far as the page needs, so the items outside of the page aren't
+/// copied. Items with the same key keep the order they have in the array.
+///
+/// # Arguments
+///
+/// * `array` - The array.
+/// * `start` - The index of the first item of the page in the sorted items.
+/// * `count` - The most items in the page.
+/// * `sort_key` - The path to the value in each item to sort by, for example
+/// `score`, or an empty string to sort by the items themselves.
+/// * `descending` - Whether the items with the largest values come first.
+pub fn select(
+    array: &VariantArray,
+    start: usize,
+    count: usize,
+    sort_key: &str,
+    descending: bool,
+) -> VariantArray<Unique> {
+    let page = VariantArray::new();
+    let len = array.len() as usize;
+    if start >= len || count == 0 {
+        return page;
+    }
+    let end = (start + count).min(len);
+
+    let mut keys: Vec<(SortKey, usize)> = (0..len)
+        .map(|index| (SortKey::of(&array.get(index as i32), sort_key), index))
+        .collect();
+    let compare = |a: &(SortKey, usize), b: &(SortKey, usize)| {
+        a.0.compare(&b.0, descending).then(a.1.cmp(&b.1))
+    };
+
+    // The keys after the page only have to be moved past it, so only the
+    // ones up to the end of the page are sorted.
+    if end < len {
+        keys.select_nth_unstable_by(end, compare);
+        keys.truncate(end);
+    }
+    keys.sort_unstable_by(compare);
+
+    for (_, index) in &keys[start..end] {
+        page.push(array.get(*index as i32));
+    }
+
+    page
+}